@@ -0,0 +1,21 @@
+//! Blocks as gossiped (compact) and as served by sync (full), then the checks a node
+//! runs on them before any state is involved.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ockham::crypto::hash_data;
+use ockham::types::{Block, CompactBlock};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(compact) = serde_json::from_slice::<CompactBlock>(data) {
+        let _ = compact.header.verify_signature();
+    }
+    if let Ok(block) = serde_json::from_slice::<Block>(data) {
+        let _ = hash_data(&block);
+        let _ = block.verify_signature();
+        let _ = block.body_hash();
+        let _ = block.transaction_proof(0);
+        let _ = CompactBlock::new(&block);
+        let _ = block.split();
+    }
+});
@@ -0,0 +1,14 @@
+//! Quorum certificates, as carried by blocks and finality proofs.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ockham::crypto::generate_keypair_from_id;
+use ockham::types::{DEFAULT_CHAIN_ID, QuorumCertificate};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(qc) = serde_json::from_slice::<QuorumCertificate>(data) {
+        let committee: Vec<_> = (0..4).map(|i| generate_keypair_from_id(i).0).collect();
+        let _ = qc.signers.resolve(&committee);
+        let _ = qc.verify_signature(&committee, DEFAULT_CHAIN_ID);
+    }
+});
@@ -0,0 +1,9 @@
+//! Messages of the sync topic (block, proof, witness and transaction requests).
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ockham::types::SyncMessage;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<SyncMessage>(data);
+});
@@ -0,0 +1,14 @@
+//! Transactions as gossiped or submitted over RPC, through the pool's stateless checks.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ockham::crypto::verify;
+use ockham::types::{Transaction, validate_tx_stateless};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tx) = serde_json::from_slice::<Transaction>(data) {
+        let _ = verify(&tx.public_key, &tx.sighash().0, &tx.signature);
+        let _ = tx.sender();
+        let _ = validate_tx_stateless(&tx, None);
+    }
+});
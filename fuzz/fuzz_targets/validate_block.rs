@@ -0,0 +1,44 @@
+//! Arbitrary blocks through a node's validation (`validate_and_store_block`), both as
+//! proposals and as sync responses. The block is re-signed by a committee member so
+//! inputs get past the signature check and reach the structural and execution checks.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ockham::consensus::SimplexState;
+use ockham::crypto::generate_keypair_from_id;
+use ockham::state::StateManager;
+use ockham::storage::MemStorage;
+use ockham::tx_pool::TxPool;
+use ockham::types::{Block, DEFAULT_BLOCK_GAS_LIMIT};
+use ockham::vm::Executor;
+use std::sync::{Arc, Mutex};
+
+fn make_node() -> SimplexState {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let committee = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let executor = Executor::new(state_manager, DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        DEFAULT_BLOCK_GAS_LIMIT,
+    )
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut block) = serde_json::from_slice::<Block>(data) else {
+        return;
+    };
+    let (pk, sk) = generate_keypair_from_id(block.view % 4);
+    block.author = pk;
+    block.sign(&sk);
+
+    let _ = make_node().on_proposal(block.clone());
+    let _ = make_node().on_block_response(block);
+});
@@ -0,0 +1,17 @@
+//! Votes and vote aggregates as received on the votes topic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ockham::crypto::generate_keypair_from_id;
+use ockham::types::{AggregateVote, DEFAULT_CHAIN_ID, Vote};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(vote) = serde_json::from_slice::<Vote>(data) {
+        let _ = vote.verify_signature(DEFAULT_CHAIN_ID);
+    }
+    if let Ok(aggregate) = serde_json::from_slice::<AggregateVote>(data) {
+        let committee: Vec<_> = (0..4).map(|i| generate_keypair_from_id(i).0).collect();
+        let _ = aggregate.verify(&committee, DEFAULT_CHAIN_ID);
+        let _ = aggregate.to_qc();
+    }
+});
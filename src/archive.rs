@@ -0,0 +1,287 @@
+//! Backups of the finalized chain (`ockham export` / `ockham import`).
+//!
+//! A `ChainArchive` holds every finalized block from genesis to the chain head, with the
+//! certificates that notarized it and, where stored, finalized it. Importing replays the
+//! archive into a fresh data directory with full validation: lineage, signatures and
+//! certificates against the committee of the time, and re-execution of every block
+//! against its state and receipts roots.
+
+use crate::crypto::{Hash, hash_data};
+use crate::light::{FinalityError, FinalityProof, verify_certificate, verify_finality};
+use crate::storage::{ChainHead, Storage, StorageError};
+use crate::types::{Block, QuorumCertificate, View, VoteType};
+use crate::vm::Executor;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+/// Format version written by this build.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+    #[error("No consensus state found")]
+    MissingState,
+    #[error("Unsupported archive version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Block {0:?} is missing from storage")]
+    MissingBlock(Hash),
+    #[error("No notarization certificate for the block at view {0}")]
+    MissingCertificate(View),
+    #[error("The archive does not end with a finalized block")]
+    NotFinal,
+    #[error("Archive starts from genesis {0:?}, the data directory has {1:?}")]
+    GenesisMismatch(Hash, Hash),
+    #[error("Data directory is not fresh (chain head at view {0})")]
+    NotFresh(View),
+    #[error("Block at view {0} does not extend the previous block")]
+    BrokenChain(View),
+    #[error("Invalid proposer signature on the block at view {0}")]
+    InvalidSignature(View),
+    #[error("Invalid certificate for the block at view {0}: {1}")]
+    Certificate(View, FinalityError),
+    #[error("Block at view {0} failed execution: {1}")]
+    Execution(View, String),
+    #[error("Block at view {0} does not reproduce its state root")]
+    InvalidStateRoot(View),
+    #[error("Block at view {0} does not reproduce its receipts root")]
+    InvalidReceiptsRoot(View),
+}
+
+/// A finalized block with its certificates.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedBlock {
+    pub block: Block,
+    /// Aggregated Notarize votes for the block.
+    pub notarization: QuorumCertificate,
+    /// Aggregated Finalize votes, if the block was finalized directly rather than
+    /// through a descendant.
+    pub finalization: Option<QuorumCertificate>,
+}
+
+/// The finalized chain above genesis, oldest block first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainArchive {
+    pub version: u32,
+    pub genesis_hash: Hash,
+    pub blocks: Vec<ArchivedBlock>,
+}
+
+/// Outcome of `ChainArchive::import`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportReport {
+    pub blocks: usize,
+    pub head: ChainHead,
+}
+
+impl ChainArchive {
+    /// Collect the finalized chain of `storage`, up to the committed chain head (or the
+    /// last block with a finalization certificate at or below `to`).
+    pub fn export(storage: &dyn Storage, to: Option<View>) -> Result<Self, ArchiveError> {
+        let head = storage
+            .get_chain_head()?
+            .ok_or(ArchiveError::MissingState)?;
+        let to = to.unwrap_or(View::MAX);
+
+        // Walk back from the head; the blocks above `to` are only needed for their
+        // justify certificates.
+        let mut chain = Vec::new();
+        let mut hash = head.block_hash;
+        let genesis_hash = loop {
+            let block = storage
+                .get_block(&hash)?
+                .ok_or(ArchiveError::MissingBlock(hash))?;
+            if block.view == 0 {
+                break hash;
+            }
+            let parent = block.parent_hash;
+            chain.push((hash, block));
+            hash = parent;
+        };
+        chain.reverse();
+
+        let mut blocks = Vec::with_capacity(chain.len());
+        for (i, (hash, block)) in chain.iter().enumerate() {
+            if block.view > to {
+                break;
+            }
+            // A block's notarization is also the justify certificate of its child
+            let notarization = storage
+                .get_qc(block.view)?
+                .filter(|qc| qc.block_hash == *hash)
+                .or_else(|| {
+                    chain
+                        .get(i + 1)
+                        .map(|(_, child)| child.justify.clone())
+                        .filter(|qc| qc.block_hash == *hash)
+                })
+                .ok_or(ArchiveError::MissingCertificate(block.view))?;
+            let finalization = storage
+                .get_finality_qc(block.view)?
+                .filter(|qc| qc.block_hash == *hash);
+            blocks.push(ArchivedBlock {
+                block: block.clone(),
+                notarization,
+                finalization,
+            });
+        }
+
+        // Only a finalization certificate proves the blocks below it final
+        while blocks
+            .last()
+            .is_some_and(|archived| archived.finalization.is_none())
+        {
+            blocks.pop();
+        }
+        Ok(Self {
+            version: ARCHIVE_VERSION,
+            genesis_hash,
+            blocks,
+        })
+    }
+
+    /// Validate and commit every block of the archive into `storage`, executing them with
+    /// `executor` (whose state must be on `storage`). `storage` must hold only the
+    /// genesis state (as written by `SimplexState::new`); a failed import leaves it
+    /// partially written and it should be discarded.
+    pub fn import(
+        &self,
+        storage: &dyn Storage,
+        executor: &Executor,
+    ) -> Result<ImportReport, ArchiveError> {
+        if self.version != ARCHIVE_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(self.version));
+        }
+        let mut head = storage
+            .get_chain_head()?
+            .ok_or(ArchiveError::MissingState)?;
+        if head.view != 0 {
+            return Err(ArchiveError::NotFresh(head.view));
+        }
+        if head.block_hash != self.genesis_hash {
+            return Err(ArchiveError::GenesisMismatch(
+                self.genesis_hash,
+                head.block_hash,
+            ));
+        }
+        if self
+            .blocks
+            .last()
+            .is_some_and(|archived| archived.finalization.is_none())
+        {
+            return Err(ArchiveError::NotFinal);
+        }
+
+        for archived in &self.blocks {
+            let block = &archived.block;
+            let view = block.view;
+            if block.parent_hash != head.block_hash || view <= head.view {
+                return Err(ArchiveError::BrokenChain(view));
+            }
+            if !block.verify_signature() {
+                return Err(ArchiveError::InvalidSignature(view));
+            }
+
+            // Certificates are checked against the committee the block was produced by
+            let committee = storage
+                .get_consensus_state()?
+                .ok_or(ArchiveError::MissingState)?
+                .committee;
+            let block_hash = match &archived.finalization {
+                Some(finalization) => verify_finality(
+                    block,
+                    &FinalityProof {
+                        notarization: archived.notarization.clone(),
+                        finalization: finalization.clone(),
+                    },
+                    &committee,
+                    executor.chain_id,
+                ),
+                None => {
+                    check_notarization(block, &archived.notarization, &committee, executor.chain_id)
+                }
+            }
+            .map_err(|e| ArchiveError::Certificate(view, e))?;
+
+            let mut executed = block.clone();
+            executed.gas_used = 0;
+            executor
+                .execute_block(&mut executed)
+                .map_err(|e| ArchiveError::Execution(view, e.to_string()))?;
+            if executed.state_root != block.state_root {
+                return Err(ArchiveError::InvalidStateRoot(view));
+            }
+            if executed.receipts_root != block.receipts_root
+                || executed.logs_bloom != block.logs_bloom
+            {
+                return Err(ArchiveError::InvalidReceiptsRoot(view));
+            }
+
+            storage.save_block(block)?;
+            storage.save_qc(&archived.notarization)?;
+            if let Some(finalization) = &archived.finalization {
+                storage.save_finality_qc(finalization)?;
+            }
+            head = ChainHead {
+                view,
+                block_hash,
+                state_root: block.state_root,
+            };
+        }
+
+        if !self.blocks.is_empty() {
+            storage.save_chain_head(&head)?;
+            let mut state = storage
+                .get_consensus_state()?
+                .ok_or(ArchiveError::MissingState)?;
+            state.view = state.view.max(head.view + 1);
+            state.finalized_height = head.view;
+            state.preferred_block = head.block_hash;
+            state.preferred_view = head.view;
+            state.last_voted_view = state.last_voted_view.max(head.view);
+            storage.save_consensus_state(&state)?;
+        }
+        Ok(ImportReport {
+            blocks: self.blocks.len(),
+            head,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+        Ok(bincode::deserialize(&std::fs::read(path)?)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ArchiveError> {
+        std::fs::write(path, bincode::serialize(self)?)?;
+        Ok(())
+    }
+}
+
+/// Check a block that was finalized through a descendant: produced by `committee` and
+/// notarized by a quorum of it. Returns the block hash.
+fn check_notarization(
+    block: &Block,
+    notarization: &QuorumCertificate,
+    committee: &[crate::crypto::PublicKey],
+    chain_id: u64,
+) -> Result<Hash, FinalityError> {
+    if block.committee_hash != hash_data(&committee) {
+        return Err(FinalityError::CommitteeMismatch);
+    }
+    let block_hash = hash_data(block);
+    verify_certificate(
+        VoteType::Notarize,
+        notarization,
+        block.view,
+        &block_hash,
+        committee,
+        chain_id,
+    )?;
+    Ok(block_hash)
+}
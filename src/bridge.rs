@@ -0,0 +1,195 @@
+//! Outbound feed for bridges: finalized blocks packaged for a light-client contract on
+//! another chain (e.g. Ethereum).
+//!
+//! A `BridgeUpdate` carries the header fields a contract needs, the serialized block
+//! (`preimage`, whose SHA-256 is the block hash) and the finalization certificate as a
+//! bitmap over the committee with the aggregate signature. BLS points are in the EIP-2537
+//! encoding (each base field element padded to 64 bytes), so the contract can check the
+//! certificate with the BLS12-381 precompiles: signatures are G1 points signing the
+//! Finalize vote message (`types::vote_message`) under `crypto::DST`, public keys are G2
+//! points. The contract is initialized with a `BridgeCommittee` and relies on finalization
+//! certificates alone (a Finalize quorum implies the block is final).
+
+use crate::crypto::{Hash, PublicKey, Signature, hash_data};
+use crate::light::{FinalityError, FinalizedBlock, verify_certificate};
+use crate::types::{Bytes, U256, VoteType};
+use serde::{Deserialize, Serialize};
+
+/// Most updates returned by one `bridge_getUpdates` call.
+pub const MAX_BRIDGE_UPDATES: u64 = 64;
+
+/// Size of a G1 point (signature) in the EIP-2537 encoding.
+pub const G1_POINT_SIZE: usize = 128;
+
+/// Size of a G2 point (public key) in the EIP-2537 encoding.
+pub const G2_POINT_SIZE: usize = 256;
+
+/// Header fields of a finalized block, as read by the bridge contract.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BridgeHeader {
+    pub view: u64,
+    pub height: u64,
+    pub timestamp: u64,
+    pub block_hash: Hash,
+    pub parent_hash: Hash,
+    pub state_root: Hash,
+    pub transactions_root: Hash,
+    pub receipts_root: Hash,
+    pub committee_hash: Hash,
+}
+
+/// A finalized block with its finalization certificate, for relayers.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BridgeUpdate {
+    pub header: BridgeHeader,
+    /// Serialized block: `sha256(preimage) == header.block_hash`.
+    pub preimage: Bytes,
+    /// Bit `i` is set if committee member `i` signed the finalization (committees of up
+    /// to 256 members).
+    pub signers: U256,
+    /// Aggregate finalization signature (G1, `G1_POINT_SIZE` bytes).
+    pub signature: Bytes,
+}
+
+impl BridgeUpdate {
+    /// Package `finalized` after checking its finalization certificate against
+    /// `committee`, the committee that produced it, on `chain_id`.
+    pub fn new(
+        finalized: &FinalizedBlock,
+        committee: &[PublicKey],
+        chain_id: u64,
+    ) -> Result<Self, FinalityError> {
+        let block = &finalized.header;
+        if block.is_dummy {
+            return Err(FinalityError::DummyBlock);
+        }
+        if block.committee_hash != hash_data(&committee) {
+            return Err(FinalityError::CommitteeMismatch);
+        }
+        let block_hash = hash_data(block);
+        let finalization = &finalized.proof.finalization;
+        verify_certificate(
+            VoteType::Finalize,
+            finalization,
+            block.view,
+            &block_hash,
+            committee,
+            chain_id,
+        )?;
+
+        let mut signers = U256::ZERO;
+        for index in finalization.signers.indices() {
+            // Checked above: every bit is a committee member
+            signers.set_bit(index, true);
+        }
+        Ok(Self {
+            header: BridgeHeader {
+                view: block.view,
+                height: block.height,
+                timestamp: block.timestamp,
+                block_hash,
+                parent_hash: block.parent_hash,
+                state_root: block.state_root,
+                transactions_root: block.transactions_root,
+                receipts_root: block.receipts_root,
+                committee_hash: block.committee_hash,
+            },
+            preimage: serde_json::to_vec(block).unwrap_or_default().into(),
+            signers,
+            signature: encode_g1(&finalization.signature).into(),
+        })
+    }
+
+    /// ABI encoding of `(uint64 view, uint64 height, uint64 timestamp, bytes32 blockHash,
+    /// bytes32 parentHash, bytes32 stateRoot, bytes32 transactionsRoot, bytes32
+    /// receiptsRoot, bytes32 committeeHash, uint256 signers, bytes signature, bytes
+    /// preimage)`, the arguments of the contract's update function.
+    pub fn abi_encode(&self) -> Bytes {
+        let header = &self.header;
+        let mut head = Vec::new();
+        for value in [header.view, header.height, header.timestamp] {
+            head.extend_from_slice(&U256::from(value).to_be_bytes::<32>());
+        }
+        for hash in [
+            &header.block_hash,
+            &header.parent_hash,
+            &header.state_root,
+            &header.transactions_root,
+            &header.receipts_root,
+            &header.committee_hash,
+        ] {
+            head.extend_from_slice(&hash.0);
+        }
+        head.extend_from_slice(&self.signers.to_be_bytes::<32>());
+
+        // Offsets of the two dynamic arguments follow, then their contents
+        let head_size = head.len() + 2 * 32;
+        let signature = abi_bytes(&self.signature);
+        head.extend_from_slice(&U256::from(head_size).to_be_bytes::<32>());
+        head.extend_from_slice(&U256::from(head_size + signature.len()).to_be_bytes::<32>());
+        head.extend_from_slice(&signature);
+        head.extend_from_slice(&abi_bytes(&self.preimage));
+        head.into()
+    }
+}
+
+/// Committee the bridge contract checks certificates against.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BridgeCommittee {
+    /// `hash_data` of the committee, as committed by block headers.
+    pub committee_hash: Hash,
+    /// Public keys in committee order (G2, `G2_POINT_SIZE` bytes each).
+    pub public_keys: Vec<Bytes>,
+    /// Chain id of the signed vote messages.
+    pub chain_id: u64,
+}
+
+impl BridgeCommittee {
+    pub fn new(committee: &[PublicKey], chain_id: u64) -> Self {
+        Self {
+            committee_hash: hash_data(&committee),
+            public_keys: committee
+                .iter()
+                .map(|pk| Bytes::from(encode_g2(pk)))
+                .collect(),
+            chain_id,
+        }
+    }
+}
+
+/// EIP-2537 encoding of a signature: the affine coordinates x and y, each padded to 64
+/// bytes. The point at infinity is all zeros.
+pub fn encode_g1(signature: &Signature) -> Vec<u8> {
+    let point = signature.0.serialize();
+    let mut out = vec![0u8; G1_POINT_SIZE];
+    // Uncompressed serialization flags infinity in the top bits of the first byte
+    if point[0] & 0x40 == 0 {
+        out[16..64].copy_from_slice(&point[..48]);
+        out[80..].copy_from_slice(&point[48..]);
+    }
+    out
+}
+
+/// EIP-2537 encoding of a public key: x and y in Fp2, each as c0 then c1 padded to 64
+/// bytes (blst serializes c1 first).
+pub fn encode_g2(public_key: &PublicKey) -> Vec<u8> {
+    let point = public_key.0.serialize();
+    let mut out = vec![0u8; G2_POINT_SIZE];
+    if point[0] & 0x40 == 0 {
+        for (i, coordinate) in point.chunks(96).enumerate() {
+            let (c1, c0) = coordinate.split_at(48);
+            let at = i * 128;
+            out[at + 16..at + 64].copy_from_slice(c0);
+            out[at + 80..at + 128].copy_from_slice(c1);
+        }
+    }
+    out
+}
+
+// ABI tail of a `bytes` argument: its length, then the data padded to whole words
+fn abi_bytes(data: &[u8]) -> Vec<u8> {
+    let mut out = U256::from(data.len()).to_be_bytes::<32>().to_vec();
+    out.extend_from_slice(data);
+    out.resize(out.len() + (32 - data.len() % 32) % 32, 0);
+    out
+}
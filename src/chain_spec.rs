@@ -0,0 +1,252 @@
+use crate::crypto::{Hash, PublicKey};
+use crate::state::StateManager;
+use crate::storage::{AccountInfo, ChainHead, ConsensusState, Storage, StorageError, ValidatorSet};
+use crate::system_contracts::staking;
+use crate::types::{Address, Bytes, ChainParams, Hardfork, U256, View, keccak256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ChainSpecError {
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("State error: {0}")]
+    State(String),
+    #[error("No consensus state found")]
+    MissingState,
+    #[error("View {0} is not the finalized view ({1}); historical state is not retained")]
+    NotFinalized(View, View),
+    #[error("Invalid validator key: {0}")]
+    InvalidKey(String),
+    #[error("Invalid chain parameters: {0}")]
+    InvalidParams(&'static str),
+    #[error("The spec has no validators")]
+    NoValidators,
+    #[error("Storage already holds a chain")]
+    AlreadyInitialized,
+}
+
+/// A validator entry of the chain spec. Keys are hex encoded (96-byte BLS public keys).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GenesisValidator {
+    pub public_key: String,
+    pub stake: U256,
+}
+
+impl GenesisValidator {
+    pub fn public_key(&self) -> Result<PublicKey, ChainSpecError> {
+        let bytes = hex::decode(self.public_key.trim_start_matches("0x"))
+            .map_err(|e| ChainSpecError::InvalidKey(e.to_string()))?;
+        blst::min_sig::PublicKey::from_bytes(&bytes)
+            .map(PublicKey)
+            .map_err(|e| ChainSpecError::InvalidKey(format!("{:?}", e)))
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GenesisAccount {
+    pub nonce: u64,
+    pub balance: U256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// Chain specification capturing the full state (accounts, code, storage, validator set).
+/// Maps are ordered so that the same state always exports to the same JSON.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    /// Human-readable network name (informational).
+    #[serde(default)]
+    pub name: String,
+    /// View the state was captured at (0 for a fresh genesis).
+    pub view: View,
+    /// EVM hardfork transactions execute with.
+    #[serde(default)]
+    pub hardfork: Hardfork,
+    pub validators: Vec<GenesisValidator>,
+    #[serde(default)]
+    pub params: ChainParams,
+    pub accounts: BTreeMap<Address, GenesisAccount>,
+}
+
+impl ChainSpec {
+    /// Capture the committed state of `storage` at the finalized view `at`
+    /// (defaults to the node's finalized view).
+    pub fn export(
+        storage: &dyn Storage,
+        chain_id: u64,
+        at: Option<View>,
+    ) -> Result<Self, ChainSpecError> {
+        let state = storage
+            .get_consensus_state()?
+            .ok_or(ChainSpecError::MissingState)?;
+
+        // Only the committed (finalized) state is kept on disk
+        let view = at.unwrap_or(state.finalized_height);
+        if view != state.finalized_height {
+            return Err(ChainSpecError::NotFinalized(view, state.finalized_height));
+        }
+
+        let mut validators = Vec::with_capacity(state.committee.len());
+        for pk in &state.committee {
+            let slot = staking::stake_slot(staking::validator_address(pk));
+            validators.push(GenesisValidator {
+                public_key: hex::encode(pk.0.to_bytes()),
+                stake: storage.get_storage(&staking::STAKING_ADDRESS, &slot)?,
+            });
+        }
+
+        let empty_code_hash = Hash(keccak256([]).into());
+        let mut accounts = BTreeMap::new();
+        for (address, info) in storage.get_all_accounts()? {
+            let code = if info.code_hash != Hash::default() && info.code_hash != empty_code_hash {
+                storage.get_code(&info.code_hash)?
+            } else {
+                None
+            }
+            .filter(|c| !c.is_empty());
+
+            let slots = storage
+                .get_all_storage(&address)?
+                .into_iter()
+                .filter(|(_, v)| *v != U256::ZERO)
+                .collect();
+
+            accounts.insert(
+                address,
+                GenesisAccount {
+                    nonce: info.nonce,
+                    balance: info.balance,
+                    code,
+                    storage: slots,
+                },
+            );
+        }
+
+        Ok(Self {
+            chain_id,
+            name: String::new(),
+            view,
+            hardfork: Hardfork::default(),
+            validators,
+            params: state.params,
+            accounts,
+        })
+    }
+
+    /// Write the accounts of the spec into `state` (updating the state tree).
+    pub fn apply(&self, state: &StateManager) -> Result<(), ChainSpecError> {
+        for (address, account) in &self.accounts {
+            let code_hash = match &account.code {
+                Some(code) => Hash(keccak256(code).into()),
+                None => Hash(keccak256([]).into()),
+            };
+            if let Some(code) = &account.code {
+                state
+                    .commit_code(code_hash, code)
+                    .map_err(|e| ChainSpecError::State(e.to_string()))?;
+            }
+            let info = AccountInfo {
+                nonce: account.nonce,
+                balance: account.balance,
+                code_hash,
+            };
+            state
+                .commit_account(*address, info)
+                .map_err(|e| ChainSpecError::State(e.to_string()))?;
+            for (index, value) in &account.storage {
+                state
+                    .commit_storage(*address, *index, *value)
+                    .map_err(|e| ChainSpecError::State(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start a new chain in the empty `storage` from the spec: its accounts, and its
+    /// validators as the genesis committee, with their stakes deposited in the staking
+    /// contract (whose balance backs them, unless the spec allocates it). `SimplexState::new`
+    /// then loads this genesis. Returns the genesis state root.
+    pub fn init_genesis(&self, storage: Arc<dyn Storage>) -> Result<Hash, ChainSpecError> {
+        if storage.get_consensus_state()?.is_some() {
+            return Err(ChainSpecError::AlreadyInitialized);
+        }
+        if self.validators.is_empty() {
+            return Err(ChainSpecError::NoValidators);
+        }
+        self.params
+            .validate()
+            .map_err(ChainSpecError::InvalidParams)?;
+        let committee = self
+            .validators
+            .iter()
+            .map(GenesisValidator::public_key)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let state = StateManager::new(storage.clone(), None);
+        self.apply(&state)?;
+        if !self.accounts.contains_key(&staking::STAKING_ADDRESS) {
+            let deposits = self
+                .validators
+                .iter()
+                .fold(U256::ZERO, |total, v| total.saturating_add(v.stake));
+            let info = AccountInfo {
+                nonce: 0,
+                balance: deposits,
+                code_hash: Hash(keccak256([]).into()),
+            };
+            state
+                .commit_account(staking::STAKING_ADDRESS, info)
+                .map_err(|e| ChainSpecError::State(e.to_string()))?;
+        }
+        for (pk, validator) in committee.iter().zip(&self.validators) {
+            let slot = staking::stake_slot(staking::validator_address(pk));
+            state
+                .commit_storage(staking::STAKING_ADDRESS, slot, validator.stake)
+                .map_err(|e| ChainSpecError::State(e.to_string()))?;
+        }
+        let state_root = state.root();
+
+        let genesis = crate::consensus::genesis_block();
+        let genesis_hash = crate::crypto::hash_data(&genesis);
+        storage.save_block(&genesis)?;
+        storage.save_qc(&genesis.justify)?;
+        let consensus_state = ConsensusState::genesis(genesis_hash, committee, self.params.clone());
+        storage.save_consensus_state(&consensus_state)?;
+        let validators = ValidatorSet::from_state(0, 0, &consensus_state, storage.as_ref())?;
+        storage.save_validator_set(&validators)?;
+        storage.save_chain_head(&ChainHead {
+            view: 0,
+            block_hash: genesis_hash,
+            state_root,
+        })?;
+        Ok(state_root)
+    }
+
+    pub fn to_json(&self) -> Result<String, ChainSpecError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ChainSpecError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ChainSpecError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ChainSpecError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
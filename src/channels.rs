@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Default capacity of the channels between subsystems.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 100;
+
+/// Capacities of the channels between the node's subsystems (the network event queues
+/// are sized by `network::EventQueueConfig`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChannelConfig {
+    /// Broadcasts and peer management requests to the network task.
+    pub network_commands: usize,
+    /// Transactions submitted over RPC, waiting to be gossiped.
+    pub rpc_transactions: usize,
+    /// Proof requests of a light node's RPC.
+    pub light_requests: usize,
+}
+
+impl ChannelConfig {
+    /// Every channel with the same capacity.
+    pub fn uniform(capacity: usize) -> Self {
+        Self {
+            network_commands: capacity,
+            rpc_transactions: capacity,
+            light_requests: capacity,
+        }
+    }
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self::uniform(DEFAULT_CHANNEL_CAPACITY)
+    }
+}
+
+/// Send outcomes of one channel.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub sent: u64,
+    /// Sends that found the channel full and waited for the receiver.
+    pub waited: u64,
+    /// Messages dropped because the channel was full (lossy channels only).
+    pub dropped: u64,
+    /// Messages lost because the receiver was gone.
+    pub closed: u64,
+}
+
+/// Send outcomes by channel name, shared by the senders and whoever reports them.
+#[derive(Clone, Default)]
+pub struct ChannelMetrics(Arc<Mutex<HashMap<&'static str, ChannelStats>>>);
+
+impl ChannelMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, channel: &'static str, f: impl FnOnce(&mut ChannelStats)) {
+        f(self.0.lock().unwrap().entry(channel).or_default());
+    }
+
+    pub fn stats(&self, channel: &str) -> ChannelStats {
+        self.0
+            .lock()
+            .unwrap()
+            .get(channel)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Counters by channel name.
+    pub fn snapshot(&self) -> BTreeMap<&'static str, ChannelStats> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(channel, stats)| (*channel, stats.clone()))
+            .collect()
+    }
+
+    /// Send `value`, waiting for room when the channel is full: for messages that must
+    /// not be lost (votes, blocks). Returns false if the receiver is gone.
+    pub async fn send<T>(&self, channel: &'static str, sender: &mpsc::Sender<T>, value: T) -> bool {
+        let value = match sender.try_send(value) {
+            Ok(()) => {
+                self.update(channel, |s| s.sent += 1);
+                return true;
+            }
+            Err(mpsc::error::TrySendError::Full(value)) => value,
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.on_closed(channel);
+                return false;
+            }
+        };
+        tracing::debug!("Channel {} is full, waiting for the receiver", channel);
+        self.update(channel, |s| s.waited += 1);
+        if sender.send(value).await.is_err() {
+            self.on_closed(channel);
+            return false;
+        }
+        self.update(channel, |s| s.sent += 1);
+        true
+    }
+
+    /// Send `value` unless the channel is full: for messages that are recovered some
+    /// other way (e.g. transactions, which are re-announced). Returns false if dropped.
+    pub fn try_send<T>(&self, channel: &'static str, sender: &mpsc::Sender<T>, value: T) -> bool {
+        match sender.try_send(value) {
+            Ok(()) => {
+                self.update(channel, |s| s.sent += 1);
+                true
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                tracing::debug!("Channel {} is full, dropping a message", channel);
+                self.update(channel, |s| s.dropped += 1);
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.on_closed(channel);
+                false
+            }
+        }
+    }
+
+    fn on_closed(&self, channel: &'static str) {
+        tracing::warn!("Channel {} is closed, message lost", channel);
+        self.update(channel, |s| s.closed += 1);
+    }
+}
@@ -1,8 +1,28 @@
-use crate::crypto::{PrivateKey, sign};
-use crate::types::{Address, Block, Transaction, U256};
+use crate::bridge::BridgeUpdate;
+use crate::crypto::{Hash, PrivateKey, PublicKey, sign};
+use crate::light::TransactionProof;
+use crate::rpc::{CallRequest, EpochOrView, FeeHistory, LogFilter, MatchedLog, TransactionReceipt};
+use crate::storage::ValidatorSet;
+use crate::system_contracts::{ENCRYPTED_MEMPOOL_ADDRESS, encrypted_mempool};
+use crate::types::{
+    Address, Block, Bytes, CommitteeTransition, EncryptedTransaction, Transaction, U256, keccak256,
+};
 use jsonrpsee::core::client::ClientT;
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
 use jsonrpsee::rpc_params;
+use std::time::Duration;
+
+/// Headroom added to `estimate_gas` for the submitted gas limit (1/GAS_MARGIN, i.e. 20%).
+const GAS_MARGIN: u64 = 5;
+
+/// Priority fee per gas when recent blocks give no hint (0.001 Gwei).
+const DEFAULT_PRIORITY_FEE: u64 = 1_000_000;
+
+/// Recent blocks whose median tips `suggest_priority_fee` looks at.
+const PRIORITY_FEE_BLOCKS: u64 = 20;
+
+/// Interval between `get_transaction_receipt` polls in `wait_for_receipt`.
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 pub struct OckhamClient {
     client: HttpClient,
@@ -16,7 +36,7 @@ impl OckhamClient {
 
     pub async fn get_block_by_hash(
         &self,
-        hash: crate::crypto::Hash,
+        hash: Hash,
     ) -> Result<Option<Block>, Box<dyn std::error::Error>> {
         let params = rpc_params![hash];
         let block: Option<Block> = self.client.request("get_block_by_hash", params).await?;
@@ -31,45 +51,79 @@ impl OckhamClient {
         Ok(block)
     }
 
+    /// Latest block signed by the node; fails if the signature does not verify.
+    pub async fn get_latest_block_signed(
+        &self,
+    ) -> Result<crate::rpc::SignedResponse<Option<Block>>, Box<dyn std::error::Error>> {
+        let response: crate::rpc::SignedResponse<Option<Block>> = self
+            .client
+            .request("get_latest_block_signed", rpc_params![])
+            .await?;
+        if !response.verify() {
+            return Err("Invalid response signature".into());
+        }
+        Ok(response)
+    }
+
+    pub async fn get_committee_transition(
+        &self,
+        epoch: u64,
+    ) -> Result<Option<CommitteeTransition>, Box<dyn std::error::Error>> {
+        let params = rpc_params![epoch];
+        let transition: Option<CommitteeTransition> = self
+            .client
+            .request("get_committee_transition", params)
+            .await?;
+        Ok(transition)
+    }
+
+    /// Validator sets at `at` (the live ones if None).
+    pub async fn get_validator_set(
+        &self,
+        at: Option<EpochOrView>,
+    ) -> Result<Option<ValidatorSet>, Box<dyn std::error::Error>> {
+        let set: Option<ValidatorSet> = self
+            .client
+            .request("get_validator_set", rpc_params![at])
+            .await?;
+        Ok(set)
+    }
+
     pub async fn get_balance(&self, address: Address) -> Result<U256, Box<dyn std::error::Error>> {
         let params = rpc_params![address];
         let balance: U256 = self.client.request("get_balance", params).await?;
         Ok(balance)
     }
 
+    /// Nonce of `address` at `block` ("latest" or "pending").
     pub async fn get_transaction_count(
         &self,
         address: Address,
+        block: &str,
     ) -> Result<u64, Box<dyn std::error::Error>> {
-        let params = rpc_params![address];
+        let params = rpc_params![address, block];
         let nonce: u64 = self.client.request("get_transaction_count", params).await?;
         Ok(nonce)
     }
 
+    /// Sign and submit a transaction; without a `nonce`, the sender's pending nonce is used.
     pub async fn send_transaction(
         &self,
-        nonce: u64,
+        nonce: Option<u64>,
         to: Option<Address>,
         value: U256,
-        data: crate::types::Bytes,
+        data: Bytes,
         key: &PrivateKey,
-    ) -> Result<crate::crypto::Hash, Box<dyn std::error::Error>> {
+    ) -> Result<Hash, Box<dyn std::error::Error>> {
         // 1. Get Chain ID (for now hardcoded or fetched)
         let chain_id: u64 = self.client.request("chain_id", rpc_params![]).await?;
 
-        // 2. Get Nonce (using balance/account info? No, need get_transaction_count equivalent.
-        // For MVP, we don't have get_transaction_count.
-        // But we have get_balance.
-        // Wait, we need the nonce. `get_balance` implementation in `rpc.rs` uses `storage.get_account`.
-        // We should add `get_nonce` to RPC or just guess.
-        // Let's check `rpc.rs` again.
-
-        // rpc.rs has `get_balance`. It fetches account.
-        // I should probably add `get_nonce` to RPC to be correct, but I cannot modify rpc.rs in this step trivially without replanning?
-        // Actually, I can check if I can add valid nonce.
-        // The user just said "execute bin in test folder".
-        // If I use a random key, nonce is 0.
-        // So I can just generate a new random key for every tx in the test.
+        // 2. Get Nonce (next after the sender's transactions already in the pool)
+        let sender = Self::address_of(key);
+        let nonce = match nonce {
+            Some(nonce) => nonce,
+            None => self.get_transaction_count(sender, "pending").await?,
+        };
 
         // 3. Get Gas Price (Base Fee)
         let base_fee: U256 = self
@@ -77,20 +131,32 @@ impl OckhamClient {
             .request("suggest_base_fee", rpc_params![])
             .await?;
 
-        // Priority Fee
-        let priority_fee = U256::from(1_000_000); // 0.001 Gwei
+        // Priority Fee (from recent blocks)
+        let priority_fee = self.suggest_priority_fee().await?;
         // Use 2x Base Fee buffer to ensure inclusion even if base fee spikes
         let max_fee = base_fee
             .saturating_mul(U256::from(2))
             .saturating_add(priority_fee);
 
+        // Gas Limit: estimate on the current state, with some headroom
+        let estimate = self
+            .estimate_gas(CallRequest {
+                from: Some(sender),
+                to,
+                value: Some(value),
+                data: Some(data.clone()),
+                ..Default::default()
+            })
+            .await?;
+        let gas_limit = estimate + estimate / GAS_MARGIN;
+
         // 4. Construct Transaction
         let mut tx = Transaction {
             chain_id,
             nonce,
             max_priority_fee_per_gas: priority_fee,
             max_fee_per_gas: max_fee,
-            gas_limit: 100000, // Standard transfer + data
+            gas_limit,
             to,
             value,
             data,
@@ -105,10 +171,197 @@ impl OckhamClient {
         tx.signature = signature;
 
         // 6. Send
-        let hash: crate::crypto::Hash = self
+        let hash: Hash = self
             .client
             .request("send_transaction", rpc_params![tx])
             .await?;
         Ok(hash)
     }
+
+    /// Encrypt the signed `tx` to `committee` and submit it to the encrypted mempool, in a
+    /// transaction signed by `key` (which pays for the submission). If `key` also signed
+    /// `tx`, `tx` must take the nonce following the submission's.
+    pub async fn send_encrypted_transaction(
+        &self,
+        tx: &Transaction,
+        committee: &[PublicKey],
+        key: &PrivateKey,
+    ) -> Result<Hash, Box<dyn std::error::Error>> {
+        let envelope = EncryptedTransaction::encrypt(tx, committee).ok_or("Empty committee")?;
+        let data = encrypted_mempool::submit_data(&envelope);
+        self.send_transaction(None, Some(ENCRYPTED_MEMPOOL_ADDRESS), U256::ZERO, data, key)
+            .await
+    }
+
+    /// Deploy `bytecode` with the ABI-encoded constructor `args`. Returns the transaction
+    /// hash and the address the contract will have once it is included.
+    pub async fn deploy_contract(
+        &self,
+        bytecode: Bytes,
+        args: Bytes,
+        key: &PrivateKey,
+    ) -> Result<(Hash, Address), Box<dyn std::error::Error>> {
+        let sender = Self::address_of(key);
+        let nonce = self.get_transaction_count(sender, "pending").await?;
+        let data = Bytes::from([bytecode.as_ref(), args.as_ref()].concat());
+        let hash = self
+            .send_transaction(Some(nonce), None, U256::ZERO, data, key)
+            .await?;
+        Ok((hash, sender.create(nonce)))
+    }
+
+    /// Deploy like `deploy_contract` and wait up to `timeout` for the deployment to be
+    /// executed. Returns the address of the contract from its receipt; an error if the
+    /// deployment failed.
+    pub async fn deploy_contract_and_wait(
+        &self,
+        bytecode: Bytes,
+        args: Bytes,
+        key: &PrivateKey,
+        timeout: Duration,
+    ) -> Result<Address, Box<dyn std::error::Error>> {
+        let (hash, _) = self.deploy_contract(bytecode, args, key).await?;
+        let receipt = self.wait_for_receipt(hash, timeout).await?;
+        receipt.contract_address.ok_or_else(|| {
+            format!(
+                "Deployment {:?} failed: {}",
+                hash,
+                crate::vm::decode_revert_reason(&receipt.receipt.revert_output)
+                    .unwrap_or_else(|| "reverted".into())
+            )
+            .into()
+        })
+    }
+
+    /// Read-only call of the function with `selector` (see `system_contracts::selector`)
+    /// and ABI-encoded `args` on `address`. Returns the raw return data.
+    pub async fn call_contract(
+        &self,
+        address: Address,
+        selector: [u8; 4],
+        args: Bytes,
+    ) -> Result<Bytes, Box<dyn std::error::Error>> {
+        let request = CallRequest {
+            to: Some(address),
+            data: Some(Bytes::from([&selector[..], args.as_ref()].concat())),
+            ..Default::default()
+        };
+        let output: Bytes = self.client.request("call", rpc_params![request]).await?;
+        Ok(output)
+    }
+
+    pub async fn estimate_gas(
+        &self,
+        request: CallRequest,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let gas: u64 = self
+            .client
+            .request("estimate_gas", rpc_params![request])
+            .await?;
+        Ok(gas)
+    }
+
+    /// See `fee_history` on the node.
+    pub async fn fee_history(
+        &self,
+        block_count: u64,
+        newest_block: Option<String>,
+        percentiles: Vec<f64>,
+    ) -> Result<FeeHistory, Box<dyn std::error::Error>> {
+        let history: FeeHistory = self
+            .client
+            .request(
+                "fee_history",
+                rpc_params![block_count, newest_block, percentiles],
+            )
+            .await?;
+        Ok(history)
+    }
+
+    /// Median of the median tips paid in recent non-empty blocks, or `DEFAULT_PRIORITY_FEE`
+    /// if there are none.
+    pub async fn suggest_priority_fee(&self) -> Result<U256, Box<dyn std::error::Error>> {
+        let history = self
+            .fee_history(PRIORITY_FEE_BLOCKS, None, vec![50.0])
+            .await?;
+        let mut tips: Vec<U256> = history
+            .reward
+            .iter()
+            .zip(&history.gas_used_ratio)
+            .filter(|(_, ratio)| **ratio > 0.0)
+            .filter_map(|(reward, _)| reward.first().copied())
+            .collect();
+        if tips.is_empty() {
+            return Ok(U256::from(DEFAULT_PRIORITY_FEE));
+        }
+        tips.sort();
+        Ok(tips[tips.len() / 2])
+    }
+
+    pub async fn get_transaction_receipt(
+        &self,
+        hash: Hash,
+    ) -> Result<Option<TransactionReceipt>, Box<dyn std::error::Error>> {
+        let receipt: Option<TransactionReceipt> = self
+            .client
+            .request("get_transaction_receipt", rpc_params![hash])
+            .await?;
+        Ok(receipt)
+    }
+
+    pub async fn get_logs(
+        &self,
+        filter: LogFilter,
+    ) -> Result<Vec<MatchedLog>, Box<dyn std::error::Error>> {
+        let logs: Vec<MatchedLog> = self.client.request("get_logs", rpc_params![filter]).await?;
+        Ok(logs)
+    }
+
+    /// Finalized inclusion proof of a transaction; check it with `TransactionProof::verify`.
+    pub async fn get_transaction_proof(
+        &self,
+        hash: Hash,
+    ) -> Result<Option<TransactionProof>, Box<dyn std::error::Error>> {
+        let proof: Option<TransactionProof> = self
+            .client
+            .request("get_transaction_proof", rpc_params![hash])
+            .await?;
+        Ok(proof)
+    }
+
+    /// Bridge updates from `from_view` on (see `bridge_getUpdates`).
+    pub async fn bridge_updates(
+        &self,
+        from_view: u64,
+        limit: u64,
+    ) -> Result<Vec<BridgeUpdate>, Box<dyn std::error::Error>> {
+        let updates: Vec<BridgeUpdate> = self
+            .client
+            .request("bridge_getUpdates", rpc_params![from_view, limit])
+            .await?;
+        Ok(updates)
+    }
+
+    /// Poll for the receipt of `hash` until it is executed or `timeout` elapses.
+    pub async fn wait_for_receipt(
+        &self,
+        hash: Hash,
+        timeout: Duration,
+    ) -> Result<TransactionReceipt, Box<dyn std::error::Error>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(receipt) = self.get_transaction_receipt(hash).await? {
+                return Ok(receipt);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(format!("Timed out waiting for the receipt of {:?}", hash).into());
+            }
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Account address of `key` (the sender of the transactions it signs).
+    pub fn address_of(key: &PrivateKey) -> Address {
+        Address::from_slice(&keccak256(key.public_key().0.to_bytes())[12..])
+    }
 }
@@ -0,0 +1,434 @@
+use crate::chain_spec::{ChainSpec, GenesisAccount};
+use crate::crypto::{
+    Hash, PublicKey, aggregate, generate_keypair_from_id, hash_data, sign, verify,
+};
+use crate::state::StateManager;
+use crate::storage::MemStorage;
+use crate::types::{
+    Address, Block, DEFAULT_BLOCK_GAS_LIMIT, DEFAULT_CHAIN_ID, INITIAL_BASE_FEE, QuorumCertificate,
+    SignerBitmap, Transaction, U256, Vote, VoteType, vote_message,
+};
+use crate::vm::Executor;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// Bumped whenever the vector format (not the expected values) changes.
+pub const SUITE_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum ConformanceError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Unsupported suite version {0} (expected {SUITE_VERSION})")]
+    UnsupportedVersion(u32),
+}
+
+/// A machine-readable set of protocol test vectors.
+///
+/// Every vector holds its inputs and the outputs this implementation expects, so an
+/// alternative implementation only needs a JSON parser to check itself against the
+/// crate (and the crate checks itself against previously exported suites).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConformanceSuite {
+    pub version: u32,
+    pub chain_id: u64,
+    pub vectors: Vec<TestVector>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    #[serde(flatten)]
+    pub case: VectorCase,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VectorCase {
+    /// Block hash (sha256 of the JSON encoding), proposer sighash and signature check.
+    Block {
+        block: Block,
+        expected_hash: Hash,
+        expected_sighash: Hash,
+        signature_valid: bool,
+    },
+    /// Vote hash and signature check (votes sign `types::vote_message` on the suite's chain).
+    Vote {
+        vote: Vote,
+        expected_hash: Hash,
+        signature_valid: bool,
+    },
+    /// Aggregate signature check of a QC, its signers resolved against `committee` (view 0
+    /// is the genesis QC and always valid).
+    QuorumCertificate {
+        qc: QuorumCertificate,
+        committee: Vec<PublicKey>,
+        valid: bool,
+    },
+    /// Transaction sighash, sender derivation and signature check.
+    Transaction {
+        tx: Transaction,
+        expected_sighash: Hash,
+        expected_sender: Address,
+        signature_valid: bool,
+    },
+    /// Executing `block` on top of `genesis` yields the expected roots and gas.
+    StateTransition {
+        genesis: ChainSpec,
+        block: Block,
+        expected_state_root: Hash,
+        expected_receipts_root: Hash,
+        expected_gas_used: u64,
+    },
+}
+
+/// Result of running a suite.
+#[derive(Clone, Debug, Default)]
+pub struct ConformanceReport {
+    pub passed: usize,
+    /// (vector name, reason)
+    pub failures: Vec<(String, String)>,
+}
+
+impl ConformanceReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl ConformanceSuite {
+    /// Build the reference suite, computing the expected values with this implementation.
+    /// Keys are derived from fixed IDs, so the output is deterministic.
+    pub fn generate() -> Self {
+        let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+        let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+        let mut vectors = Vec::new();
+
+        // Transactions
+        let (user_pk, user_sk) = generate_keypair_from_id(100);
+        let mut tx = Transaction {
+            chain_id: DEFAULT_CHAIN_ID,
+            nonce: 0,
+            max_priority_fee_per_gas: U256::from(1_000_000u64),
+            max_fee_per_gas: U256::from(100_000_000u64),
+            gas_limit: 21000,
+            to: Some(Address::from_slice(&[0x42; 20])),
+            value: U256::from(1000u64),
+            data: vec![].into(),
+            access_list: vec![],
+            public_key: user_pk,
+            signature: Default::default(),
+        };
+        tx.signature = sign(&user_sk, &tx.sighash().0);
+        vectors.push(Self::transaction_vector("transaction/transfer", tx.clone()));
+
+        let mut forged_tx = tx.clone();
+        forged_tx.value = U256::from(2000u64);
+        vectors.push(Self::transaction_vector(
+            "transaction/modified_after_signing",
+            forged_tx,
+        ));
+
+        // Blocks
+        let mut block = Block::new(
+            keys[1].0.clone(),
+            1,
+            Hash::default(),
+            QuorumCertificate::default(),
+            Hash::default(),
+            Hash::default(),
+            vec![tx],
+            U256::from(INITIAL_BASE_FEE),
+            0,
+            vec![],
+            hash_data(&committee),
+        );
+        let pre_execution = block.clone();
+        block.sign(&keys[1].1);
+        vectors.push(Self::block_vector("block/signed_proposal", block.clone()));
+
+        let mut wrong_signer = block.clone();
+        wrong_signer.sign(&keys[2].1);
+        vectors.push(Self::block_vector("block/wrong_signer", wrong_signer));
+
+        vectors.push(Self::block_vector(
+            "block/dummy",
+            Block::new_dummy(
+                keys[2].0.clone(),
+                2,
+                hash_data(&block),
+                QuorumCertificate::default(),
+            ),
+        ));
+
+        // Votes
+        let block_hash = hash_data(&block);
+        let votes: Vec<Vote> = keys
+            .iter()
+            .take(3)
+            .map(|(pk, sk)| Vote {
+                view: 1,
+                block_hash,
+                vote_type: VoteType::Notarize,
+                author: pk.clone(),
+                signature: sign(
+                    sk,
+                    &vote_message(VoteType::Notarize, 1, &block_hash, DEFAULT_CHAIN_ID),
+                ),
+            })
+            .collect();
+        vectors.push(Self::vote_vector("vote/notarize", votes[0].clone()));
+
+        let mut foreign_vote = votes[0].clone();
+        foreign_vote.author = keys[3].0.clone();
+        vectors.push(Self::vote_vector("vote/wrong_author", foreign_vote));
+
+        let mut retyped_vote = votes[0].clone();
+        retyped_vote.vote_type = VoteType::Finalize;
+        vectors.push(Self::vote_vector("vote/wrong_type", retyped_vote));
+
+        // Quorum Certificates
+        let qc = QuorumCertificate {
+            view: 1,
+            block_hash,
+            signature: aggregate(
+                &votes
+                    .iter()
+                    .map(|v| v.signature.clone())
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap_or_default(),
+            epoch: 0,
+            signers: SignerBitmap::from_signers(&committee, votes.iter().map(|v| &v.author))
+                .unwrap_or_default(),
+        };
+        vectors.push(Self::qc_vector("qc/notarization", qc.clone(), &committee));
+        vectors.push(Self::qc_vector(
+            "qc/genesis",
+            QuorumCertificate::default(),
+            &committee,
+        ));
+
+        let mut wrong_block = qc.clone();
+        wrong_block.block_hash = Hash([0xab; 32]);
+        vectors.push(Self::qc_vector("qc/wrong_block", wrong_block, &committee));
+
+        let mut missing_signer = qc;
+        missing_signer.signers.truncate(2);
+        vectors.push(Self::qc_vector(
+            "qc/missing_signer",
+            missing_signer,
+            &committee,
+        ));
+
+        // State Transitions
+        let mut genesis = ChainSpec {
+            chain_id: DEFAULT_CHAIN_ID,
+            ..Default::default()
+        };
+        genesis.accounts.insert(
+            pre_execution.payload[0].sender(),
+            GenesisAccount {
+                balance: U256::from(10u64).pow(U256::from(18)),
+                ..Default::default()
+            },
+        );
+        if let Ok(executed) = Self::execute(&genesis, &pre_execution) {
+            vectors.push(TestVector {
+                name: "state/transfer".to_string(),
+                case: VectorCase::StateTransition {
+                    genesis,
+                    block: pre_execution,
+                    expected_state_root: executed.state_root,
+                    expected_receipts_root: executed.receipts_root,
+                    expected_gas_used: executed.gas_used,
+                },
+            });
+        }
+
+        Self {
+            version: SUITE_VERSION,
+            chain_id: DEFAULT_CHAIN_ID,
+            vectors,
+        }
+    }
+
+    /// Check every vector against this implementation.
+    pub fn run(&self) -> ConformanceReport {
+        let mut report = ConformanceReport::default();
+        for vector in &self.vectors {
+            match Self::check(&vector.case, self.chain_id) {
+                Ok(()) => report.passed += 1,
+                Err(reason) => report.failures.push((vector.name.clone(), reason)),
+            }
+        }
+        report
+    }
+
+    fn check(case: &VectorCase, chain_id: u64) -> Result<(), String> {
+        match case {
+            VectorCase::Block {
+                block,
+                expected_hash,
+                expected_sighash,
+                signature_valid,
+            } => {
+                expect("hash", expected_hash, &hash_data(block))?;
+                expect("sighash", expected_sighash, &block.sighash())?;
+                expect(
+                    "signature_valid",
+                    signature_valid,
+                    &block.verify_signature(),
+                )
+            }
+            VectorCase::Vote {
+                vote,
+                expected_hash,
+                signature_valid,
+            } => {
+                expect("hash", expected_hash, &hash_data(vote))?;
+                expect(
+                    "signature_valid",
+                    signature_valid,
+                    &vote.verify_signature(chain_id),
+                )
+            }
+            VectorCase::QuorumCertificate {
+                qc,
+                committee,
+                valid,
+            } => expect("valid", valid, &qc.verify_signature(committee, chain_id)),
+            VectorCase::Transaction {
+                tx,
+                expected_sighash,
+                expected_sender,
+                signature_valid,
+            } => {
+                expect("sighash", expected_sighash, &tx.sighash())?;
+                expect("sender", expected_sender, &tx.sender())?;
+                expect(
+                    "signature_valid",
+                    signature_valid,
+                    &verify(&tx.public_key, &tx.sighash().0, &tx.signature),
+                )
+            }
+            VectorCase::StateTransition {
+                genesis,
+                block,
+                expected_state_root,
+                expected_receipts_root,
+                expected_gas_used,
+            } => {
+                let executed = Self::execute(genesis, block)?;
+                expect("state_root", expected_state_root, &executed.state_root)?;
+                expect(
+                    "receipts_root",
+                    expected_receipts_root,
+                    &executed.receipts_root,
+                )?;
+                expect("gas_used", expected_gas_used, &executed.gas_used)
+            }
+        }
+    }
+
+    /// Execute `block` on a fresh in-memory state built from `genesis`.
+    fn execute(genesis: &ChainSpec, block: &Block) -> Result<Block, String> {
+        let storage = Arc::new(MemStorage::new());
+        let state = StateManager::new(storage, None);
+        genesis.apply(&state).map_err(|e| e.to_string())?;
+        let executor = Executor::new(Arc::new(Mutex::new(state)), DEFAULT_BLOCK_GAS_LIMIT)
+            .with_chain_id(genesis.chain_id)
+            .with_hardfork(genesis.hardfork);
+        let mut executed = block.clone();
+        executor
+            .execute_block(&mut executed)
+            .map_err(|e| format!("execution failed: {}", e))?;
+        Ok(executed)
+    }
+
+    fn block_vector(name: &str, block: Block) -> TestVector {
+        TestVector {
+            name: name.to_string(),
+            case: VectorCase::Block {
+                expected_hash: hash_data(&block),
+                expected_sighash: block.sighash(),
+                signature_valid: block.verify_signature(),
+                block,
+            },
+        }
+    }
+
+    fn vote_vector(name: &str, vote: Vote) -> TestVector {
+        TestVector {
+            name: name.to_string(),
+            case: VectorCase::Vote {
+                expected_hash: hash_data(&vote),
+                signature_valid: vote.verify_signature(DEFAULT_CHAIN_ID),
+                vote,
+            },
+        }
+    }
+
+    fn qc_vector(name: &str, qc: QuorumCertificate, committee: &[PublicKey]) -> TestVector {
+        TestVector {
+            name: name.to_string(),
+            case: VectorCase::QuorumCertificate {
+                valid: qc.verify_signature(committee, DEFAULT_CHAIN_ID),
+                qc,
+                committee: committee.to_vec(),
+            },
+        }
+    }
+
+    fn transaction_vector(name: &str, tx: Transaction) -> TestVector {
+        TestVector {
+            name: name.to_string(),
+            case: VectorCase::Transaction {
+                expected_sighash: tx.sighash(),
+                expected_sender: tx.sender(),
+                signature_valid: verify(&tx.public_key, &tx.sighash().0, &tx.signature),
+                tx,
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, ConformanceError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ConformanceError> {
+        let suite: Self = serde_json::from_str(json)?;
+        if suite.version != SUITE_VERSION {
+            return Err(ConformanceError::UnsupportedVersion(suite.version));
+        }
+        Ok(suite)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConformanceError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ConformanceError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+fn expect<T: PartialEq + std::fmt::Debug>(
+    field: &str,
+    expected: &T,
+    actual: &T,
+) -> Result<(), String> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected {:?}, got {:?}",
+            field, expected, actual
+        ))
+    }
+}
@@ -1,18 +1,181 @@
-use crate::crypto::{
-    Hash, PrivateKey, PublicKey, aggregate, hash_data, sign, verify, verify_aggregate,
-};
+use crate::crypto::{Hash, PrivateKey, PublicKey, Signature, aggregate, hash_data, sign};
 
+use crate::engine::ExecutionEngine;
+use crate::events::{ConsensusEvent, EventStream, EvidenceKind};
 use crate::evidence_pool::EvidencePool;
-use crate::storage::{ConsensusState, StateOverlay, Storage};
+use crate::memory::{MemoryBudget, MemoryHandle, block_size, seen_entry_size, vote_size};
+use crate::payload::{NonceOrderedBuilder, Payload, PayloadBuilder, PayloadRequest};
+use crate::seen_cache::SeenCache;
+use crate::state::StateWitness;
+use crate::storage::{ChainHead, ConsensusHead, ConsensusState, Storage, ValidatorSet, VoteRecord};
+use crate::system_contracts::{encrypted_mempool, staking};
 use crate::tx_pool::TxPool;
 use crate::types::{
-    Block, EquivocationEvidence, INITIAL_BASE_FEE, QuorumCertificate, U256, View, Vote, VoteType,
+    Address, AggregateVote, Block, BlockBody, ChainParams, CommitteeTransition, CompactBlock,
+    DecryptionKey, DecryptionShare, EncryptedTransaction, EquivocationEvidence, INITIAL_BASE_FEE,
+    ProposalEquivocationEvidence, ProposalMetadata, QuorumCertificate, SyncMessage, Transaction,
+    U256, View, Vote, VoteType, calculate_transactions_root, vote_message, wire_size,
 };
-use crate::vm::Executor;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use crate::validation::{BlockValidated, ValidationJob, check_execution};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+/// Stake credited to each member of the genesis committee.
+pub const GENESIS_STAKE: u64 = 5000;
+
+/// Message hashes remembered for duplicate / replay detection.
+pub const SEEN_VOTES_CAPACITY: usize = 65_536;
+pub const SEEN_BLOCKS_CAPACITY: usize = 4_096;
+
+/// Compact blocks waiting for transactions (or a body) fetched from the network.
+pub const MAX_PENDING_BLOCKS: usize = 64;
+
+/// How far (in seconds) a proposal's timestamp may run ahead of our clock.
+pub const MAX_TIMESTAMP_DRIFT: u64 = 15;
+
+/// Encrypted transactions a proposal reveals at most.
+pub const MAX_REVEALED_PER_BLOCK: usize = 64;
+
+/// Bytes of `ChainParams::max_block_size` a proposal keeps for its header: the justify
+/// QC, metadata, and the roots, bloom and signature set after execution.
+const PROPOSAL_HEADER_RESERVE: u64 = 16 * 1024;
+
+/// The genesis block (view 0) every chain builds on.
+pub fn genesis_block() -> Block {
+    Block::new(
+        crate::crypto::generate_keypair_from_id(0).0,
+        0,
+        Hash::default(),
+        QuorumCertificate::default(),
+        Hash::default(), // state_root
+        Hash::default(), // receipts_root
+        vec![],
+        U256::from(INITIAL_BASE_FEE), // Genesis Base Fee
+        0,
+        vec![],          // Evidence
+        Hash::default(), // Committee Hash
+    )
+}
+
+/// Current Unix time in seconds (the default consensus clock).
+pub fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The `items`, in order, whose encoding fits in what is left of `budget`, which they use up.
+fn take_fitting<T: serde::Serialize>(items: Vec<T>, budget: &mut u64) -> Vec<T> {
+    items
+        .into_iter()
+        .filter(|item| {
+            let size = wire_size(item) as u64 + 1; // With its separator in the list
+            if size > *budget {
+                return false;
+            }
+            *budget -= size;
+            true
+        })
+        .collect()
+}
+
+/// A block buffered until its parent arrives.
+#[derive(Clone, Debug)]
+pub struct Orphan {
+    pub block: Block,
+    /// Peer that sent it (None if unknown), for per-peer quotas.
+    pub peer: Option<String>,
+    /// View we were in when it arrived; it expires `expiry_views` later.
+    pub received_view: View,
+}
+
+/// A gossiped compact block whose transactions are being fetched.
+#[derive(Clone, Debug)]
+pub struct PendingBlock {
+    pub compact: CompactBlock,
+    /// Peer that relayed it.
+    pub peer_id: String,
+    /// Transactions found so far, by hash.
+    pub received: HashMap<Hash, Transaction>,
+}
+
+/// Outcome of the checks made before executing a block.
+enum Precheck {
+    Stored,
+    Orphan(Vec<ConsensusAction>),
+    Execute,
+}
+
+/// A proposal built by the leader, waiting to be executed on its parent state. Executing
+/// it touches no consensus state, so it can run on another thread.
+pub struct ProposalJob {
+    pub block: Block,
+    engine: Arc<dyn ExecutionEngine>,
+    parent_root: Hash,
+    /// End of the proposal's time budget (`ProposerConfig::time_budget`).
+    deadline: Option<Instant>,
+}
+
+impl ProposalJob {
+    pub fn view(&self) -> View {
+        self.block.view
+    }
+
+    pub fn execute(mut self) -> ProposalReady {
+        let view = self.block.view;
+        let block =
+            match self
+                .engine
+                .execute_proposal(&mut self.block, self.parent_root, self.deadline)
+            {
+                Ok(_) => Some(self.block),
+                Err(e) => {
+                    tracing::error!("Proposal execution failed (View {}): {:?}", view, e);
+                    None
+                }
+            };
+        ProposalReady { view, block }
+    }
+}
+
+/// An executed proposal (None if execution failed), for `SimplexState::on_proposal_ready`.
+#[derive(Debug)]
+pub struct ProposalReady {
+    pub view: View,
+    pub block: Option<Block>,
+}
+
+/// Limits on the orphan buffer, so that blocks with unknown parents cannot exhaust memory.
+#[derive(Clone, Debug)]
+pub struct OrphanConfig {
+    pub max_orphans: usize,
+    pub max_per_peer: usize,
+    pub expiry_views: View,
+}
+
+impl Default for OrphanConfig {
+    fn default() -> Self {
+        Self {
+            max_orphans: 256,
+            max_per_peer: 32,
+            expiry_views: 50,
+        }
+    }
+}
+
+/// Orphan buffer size and what has been dropped from it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrphanMetrics {
+    pub count: usize,
+    pub bytes: usize,
+    pub expired: u64,
+    pub evicted: u64,  // Pushed out by the count limit or the memory budget
+    pub rejected: u64, // Refused on arrival (peer over quota, or buffer full)
+}
+
 #[derive(Error, Debug)]
 pub enum ConsensusError {
     #[error("Invalid view for operation")]
@@ -31,6 +194,31 @@ pub enum ConsensusError {
     InvalidReceiptsRoot,
     #[error("Invalid Signature")]
     InvalidSignature,
+    #[error("Missing block {0:?}")]
+    MissingBlock(Hash),
+    #[error("Block gas limit exceeded: {gas} > {limit}")]
+    GasLimitExceeded { gas: u64, limit: u64 },
+    #[error("Block too large: {size} > {limit} bytes")]
+    BlockTooLarge { size: usize, limit: u64 },
+    #[error("Vote author is not in the committee")]
+    NotInCommittee,
+    #[error("Proposer is not the leader of the view")]
+    NotLeader,
+    #[error("Storage error: {0}")]
+    StorageError(String),
+}
+
+/// What `SimplexState::recover` did on startup.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// Committed chain head after recovery (None before the first commit).
+    pub head: Option<ChainHead>,
+    /// Finalized views whose commit was interrupted and has been replayed.
+    pub replayed: Vec<View>,
+    /// Unfinalized stored blocks that re-executed to their state root.
+    pub revalidated: Vec<View>,
+    /// Unfinalized stored blocks dropped from the preferred chain (and the blocks above them).
+    pub discarded: Vec<View>,
 }
 
 /// Abstract actions emitted by the consensus state machine.
@@ -38,14 +226,43 @@ pub enum ConsensusError {
 #[derive(Debug, Clone)]
 pub enum ConsensusAction {
     BroadcastVote(Vote),
+    BroadcastAggregate(AggregateVote), // Grown aggregate of the votes for a target
     BroadcastEvidence(EquivocationEvidence),
+    BroadcastProposalEvidence(ProposalEquivocationEvidence),
     BroadcastBlock(Block),
+    BroadcastDecryptionShares(Vec<DecryptionShare>), // Ours, for the ciphertexts of a notarized block
     // Sync Actions
     BroadcastRequest(Hash),
     SendBlock(Block, String), // Respond to a specific peer (String is PeerId)
+    RequestTransactions(Hash, Vec<Hash>), // Transactions of a compact block missing from our pool
+    RequestBody(Hash),        // Whole body, when the transactions do not match the header
                               // In a real implementation, we'd have Timer start/stop actions here
 }
 
+/// Local settings applied to our own proposals (not checked by other validators).
+#[derive(Clone, Debug, Default)]
+pub struct ProposerConfig {
+    /// Receives the priority fees of our blocks (Address::ZERO burns them).
+    pub fee_recipient: Address,
+    /// Senders whose transactions are placed first in our proposals (within the gas limit).
+    pub operator_accounts: HashSet<Address>,
+    /// Wall-clock time to build and execute a proposal, so it goes out before the view
+    /// times out: selection stops halfway through it, and transactions still executing
+    /// when it runs out are left for later blocks. None: no limit.
+    pub time_budget: Option<Duration>,
+    /// Gas limit our proposals move the header gas limit towards, a step of at most
+    /// `1 / GAS_LIMIT_BOUND_DIVISOR` per block. None: `ChainParams::block_gas_limit`.
+    pub gas_limit_target: Option<u64>,
+}
+
+/// Best aggregate known for a vote target, and its number of signers when last relayed
+/// (by us, or by whoever sent us an aggregate that large).
+#[derive(Clone, Debug)]
+pub struct RelayedAggregate {
+    pub aggregate: AggregateVote,
+    pub relayed: usize,
+}
+
 pub struct SimplexState {
     pub my_id: PublicKey,
     pub my_key: PrivateKey,
@@ -55,7 +272,10 @@ pub struct SimplexState {
     pub preferred_block: Hash,
     pub preferred_view: View,
     pub last_voted_view: View,
+    /// Gas limit of a new chain; afterwards `ChainParams::block_gas_limit` applies.
     pub block_gas_limit: u64,
+    /// Chain our votes are signed for (see `vote_message`), and those we accept.
+    pub chain_id: u64,
 
     // Storage (Abstracted)
     pub storage: std::sync::Arc<dyn Storage>,
@@ -65,17 +285,66 @@ pub struct SimplexState {
     pub votes_received: HashMap<View, HashMap<PublicKey, Vote>>,
     // Track Finalize votes separately for easier counting
     pub finalize_votes_received: HashMap<View, HashMap<PublicKey, Vote>>,
+    // Best known aggregate per (view, block hash, vote type), see `on_aggregate_vote`
+    pub aggregates: HashMap<(View, Hash, VoteType), RelayedAggregate>,
+
+    // Committee Hand-over
+    // Epoch = number of committee changes since genesis
+    pub epoch: u64,
+    // Map: Commitment -> (Unsigned Transition, Outgoing Committee)
+    pub pending_transitions: HashMap<Hash, (CommitteeTransition, Vec<PublicKey>)>,
+    pub handover_votes_received: HashMap<Hash, HashMap<PublicKey, Vote>>,
+
+    // Encrypted Mempool
+    // Map: Ciphertext Id -> (View of the notarized block including it, Envelope)
+    pub ciphertexts: HashMap<Hash, (View, EncryptedTransaction)>,
+    // Map: Ciphertext Id -> (View first seen, Shares by member)
+    pub decryption_shares: HashMap<Hash, (View, HashMap<PublicKey, Signature>)>,
 
     // Sync: Orphan Buffer
     // Map: ParentHash -> List of Orphan Blocks waiting for that parent
-    pub orphans: HashMap<Hash, Vec<Block>>,
+    pub orphans: HashMap<Hash, Vec<Orphan>>,
+    pub orphan_config: OrphanConfig,
+    orphan_metrics: OrphanMetrics,
 
     // Slashing
     pub evidence_pool: EvidencePool,
+    // First proposal seen per view (to detect double proposals)
+    pub seen_proposals: HashMap<View, Block>,
+
+    // Deduplication: hashes of votes and proposals already processed
+    pub seen_votes: SeenCache,
+    pub seen_blocks: SeenCache,
+
+    // Compact blocks waiting for missing transactions: Block Hash -> Pending Block
+    pub pending_blocks: HashMap<Hash, PendingBlock>,
 
     // Execution & P2P
     pub tx_pool: Arc<TxPool>,
-    pub executor: Executor,
+    pub engine: Arc<dyn ExecutionEngine>,
+    pub proposer: ProposerConfig,
+    pub payload_builder: Arc<dyn PayloadBuilder>,
+    // Unix time (seconds) stamped on our proposals and bounding the timestamps we accept
+    pub clock: fn() -> u64,
+    // Validate blocks from their state witness when one is available
+    pub stateless: bool,
+    // Execute our proposals off the event loop (see `take_proposal_job`)
+    pub pipelined: bool,
+    proposal_job: Option<ProposalJob>,
+    // Our proposal waiting for its slot (`ChainParams::min_block_interval`): the unix time
+    // it may be made at, and what `start_proposal` needs to make it
+    deferred_proposal: Option<(u64, View, QuorumCertificate, Hash)>,
+    // Execute incoming blocks on validation workers (see `take_validation_jobs`)
+    pub async_validation: bool,
+    validation_jobs: Vec<ValidationJob>,
+    validating: HashSet<Hash>,
+    // Monitoring: protocol steps for external observers (see `with_events`)
+    events: Option<EventStream>,
+
+    // Memory Budget (None = unbounded)
+    orphan_memory: Option<MemoryHandle>,
+    vote_memory: Option<MemoryHandle>,
+    seen_memory: Option<MemoryHandle>,
 }
 
 impl SimplexState {
@@ -85,12 +354,19 @@ impl SimplexState {
         committee: Vec<PublicKey>,
         storage: std::sync::Arc<dyn Storage>,
         tx_pool: Arc<TxPool>,
-        executor: Executor,
+        engine: impl ExecutionEngine + 'static,
         block_gas_limit: u64,
     ) -> Self {
+        let epoch = storage
+            .get_latest_committee_transition()
+            .ok()
+            .flatten()
+            .map(|t| t.epoch)
+            .unwrap_or(0);
+
         // Attempt to load existing state
         if let Ok(Some(saved_state)) = storage.get_consensus_state() {
-            log::info!(
+            tracing::info!(
                 "Loaded persistent state: View {}, Finalized {}, Preferred View {}, Last Voted View {}",
                 saved_state.view,
                 saved_state.finalized_height,
@@ -98,9 +374,24 @@ impl SimplexState {
                 saved_state.last_voted_view
             );
             if saved_state.committee != committee {
-                log::warn!("Loaded committee differs from argument. Using persisted committee.");
+                tracing::warn!(
+                    "Loaded committee differs from argument. Using persisted committee."
+                );
             }
             let effective_committee = saved_state.committee.clone();
+            // Databases from before epoch snapshots: snapshot the current epoch now
+            if matches!(storage.get_validator_set(epoch), Ok(None)) {
+                let saved = ValidatorSet::from_state(
+                    epoch,
+                    saved_state.finalized_height,
+                    &saved_state,
+                    storage.as_ref(),
+                )
+                .and_then(|set| storage.save_validator_set(&set));
+                if let Err(e) = saved {
+                    tracing::error!("Failed to save validator set: {:?}", e);
+                }
+            }
 
             return Self {
                 my_id,
@@ -114,29 +405,44 @@ impl SimplexState {
                 storage,
                 votes_received: HashMap::new(),
                 finalize_votes_received: HashMap::new(),
+                aggregates: HashMap::new(),
+                epoch,
+                pending_transitions: HashMap::new(),
+                handover_votes_received: HashMap::new(),
+                ciphertexts: HashMap::new(),
+                decryption_shares: HashMap::new(),
                 orphans: HashMap::new(),
+                orphan_config: OrphanConfig::default(),
+                orphan_metrics: OrphanMetrics::default(),
                 evidence_pool: EvidencePool::new(),
+                seen_proposals: HashMap::new(),
+                seen_votes: SeenCache::new(SEEN_VOTES_CAPACITY),
+                seen_blocks: SeenCache::new(SEEN_BLOCKS_CAPACITY),
+                pending_blocks: HashMap::new(),
                 tx_pool,
-                executor,
+                engine: Arc::new(engine),
                 block_gas_limit: crate::types::DEFAULT_BLOCK_GAS_LIMIT,
+                chain_id: crate::types::DEFAULT_CHAIN_ID,
+                proposer: ProposerConfig::default(),
+                payload_builder: Arc::new(NonceOrderedBuilder),
+                clock: unix_time,
+                stateless: false,
+                pipelined: false,
+                proposal_job: None,
+                deferred_proposal: None,
+                async_validation: false,
+                validation_jobs: Vec::new(),
+                validating: HashSet::new(),
+                events: None,
+                orphan_memory: None,
+                vote_memory: None,
+                seen_memory: None,
             };
         }
 
         // Initialize Genesis
         let genesis_qc = QuorumCertificate::default();
-        let genesis_block = Block::new(
-            crate::crypto::generate_keypair_from_id(0).0,
-            0,
-            Hash::default(),
-            genesis_qc.clone(),
-            Hash::default(), // state_root
-            Hash::default(), // receipts_root
-            vec![],
-            U256::from(INITIAL_BASE_FEE), // Genesis Base Fee
-            0,
-            vec![],          // Evidence
-            Hash::default(), // Committee Hash
-        );
+        let genesis_block = genesis_block();
         let genesis_hash = hash_data(&genesis_block);
 
         // Save Genesis
@@ -146,27 +452,27 @@ impl SimplexState {
         // but let's save genesis as the "default" block.
         storage.save_qc(&genesis_qc).unwrap();
 
-        let mut initial_stakes = HashMap::new();
-        for pk in &committee {
-            let pk_bytes = pk.0.to_bytes();
-            let hash = crate::types::keccak256(pk_bytes);
-            let address = crate::types::Address::from_slice(&hash[12..]);
-            initial_stakes.insert(address, crate::types::U256::from(5000u64));
-        }
-
-        let initial_state = ConsensusState {
-            view: 1,
-            finalized_height: 0,
-            preferred_block: genesis_hash,
-            preferred_view: 0,
-            last_voted_view: 0,
-            committee: committee.clone(),
-            pending_validators: vec![],
-            exiting_validators: vec![],
-            stakes: initial_stakes,
-            inactivity_scores: HashMap::new(),
-        };
+        let initial_state = ConsensusState::genesis(
+            genesis_hash,
+            committee.clone(),
+            ChainParams {
+                block_gas_limit,
+                ..ChainParams::default()
+            },
+        );
         storage.save_consensus_state(&initial_state).unwrap();
+        storage
+            .save_chain_head(&ChainHead {
+                view: 0,
+                block_hash: genesis_hash,
+                state_root: Hash::default(),
+            })
+            .unwrap();
+
+        // Genesis stakes live in the staking contract storage
+        staking::init_genesis(storage.as_ref(), &committee, U256::from(GENESIS_STAKE)).unwrap();
+        let validators = ValidatorSet::from_state(0, 0, &initial_state, storage.as_ref()).unwrap();
+        storage.save_validator_set(&validators).unwrap();
 
         // Allocating funds to Node 0 (Genesis Account)
         let (pk0, _) = crate::crypto::generate_keypair_from_id(0);
@@ -179,7 +485,6 @@ impl SimplexState {
             nonce: 0,
             balance: crate::types::U256::MAX,
             code_hash: crate::crypto::Hash(crate::types::keccak256([]).into()),
-            code: None,
         };
         storage.save_account(&address, &account).unwrap();
 
@@ -195,20 +500,123 @@ impl SimplexState {
             storage,
             votes_received: HashMap::new(),
             finalize_votes_received: HashMap::new(),
+            aggregates: HashMap::new(),
+            epoch,
+            pending_transitions: HashMap::new(),
+            handover_votes_received: HashMap::new(),
+            ciphertexts: HashMap::new(),
+            decryption_shares: HashMap::new(),
             orphans: HashMap::new(),
+            orphan_config: OrphanConfig::default(),
+            orphan_metrics: OrphanMetrics::default(),
             evidence_pool: EvidencePool::new(),
+            seen_proposals: HashMap::new(),
+            seen_votes: SeenCache::new(SEEN_VOTES_CAPACITY),
+            seen_blocks: SeenCache::new(SEEN_BLOCKS_CAPACITY),
+            pending_blocks: HashMap::new(),
             tx_pool,
-            executor,
+            engine: Arc::new(engine),
             block_gas_limit,
+            chain_id: crate::types::DEFAULT_CHAIN_ID,
+            proposer: ProposerConfig::default(),
+            payload_builder: Arc::new(NonceOrderedBuilder),
+            clock: unix_time,
+            stateless: false,
+            pipelined: false,
+            proposal_job: None,
+            deferred_proposal: None,
+            async_validation: false,
+            validation_jobs: Vec::new(),
+            validating: HashSet::new(),
+            events: None,
+            orphan_memory: None,
+            vote_memory: None,
+            seen_memory: None,
         }
     }
 
+    /// Sign and accept votes for `chain_id` (the chain spec's) instead of the default.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Set the fee recipient and operator allowlist used for our proposals.
+    pub fn with_proposer_config(mut self, proposer: ProposerConfig) -> Self {
+        self.proposer = proposer;
+        self
+    }
+
+    /// Select and order the transactions of our proposals with `builder`
+    /// (`NonceOrderedBuilder` by default).
+    pub fn with_payload_builder(mut self, builder: Arc<dyn PayloadBuilder>) -> Self {
+        self.payload_builder = builder;
+        self
+    }
+
+    /// Use `clock` instead of the system time (e.g. a fixed one for reproducible runs).
+    pub fn with_clock(mut self, clock: fn() -> u64) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Validate blocks by re-executing them on their state witness (see `on_witness`)
+    /// instead of our own state, when the witness has arrived. Stepping stone toward
+    /// validators that do not keep the state.
+    pub fn with_stateless_validation(mut self) -> Self {
+        self.stateless = true;
+        self
+    }
+
+    /// Execute our proposals in a background task instead of the event handler, so votes
+    /// keep being processed meanwhile: the block is built when we become leader, handed
+    /// out by `take_proposal_job`, and its `ProposalReady` result fed to `on_proposal_ready`.
+    pub fn with_proposal_pipeline(mut self) -> Self {
+        self.pipelined = true;
+        self
+    }
+
+    /// Execute incoming blocks on a `ValidationPool` instead of the event handler: blocks
+    /// passing the cheap checks are handed out by `take_validation_jobs`, and votes are cast
+    /// when their `BlockValidated` result is fed to `on_block_validated`.
+    pub fn with_async_validation(mut self) -> Self {
+        self.async_validation = true;
+        self
+    }
+
+    /// Emit a `ConsensusEvent` on `events` at each view change, proposal, QC, finalization,
+    /// timeout and detected equivocation.
+    pub fn with_events(mut self, events: EventStream) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Set the orphan buffer limits.
+    pub fn with_orphan_config(mut self, config: OrphanConfig) -> Self {
+        self.orphan_config = config;
+        self
+    }
+
+    /// Account the orphan buffer, vote maps and seen-message caches against `budget`.
+    /// When over their share, the lowest-view orphans, hand-over votes for transitions not
+    /// reached yet, the oldest views' votes and the least recently seen hashes are evicted.
+    pub fn with_memory_budget(mut self, budget: &MemoryBudget) -> Self {
+        self.orphan_memory = Some(budget.register("orphans"));
+        self.vote_memory = Some(budget.register("votes"));
+        self.seen_memory = Some(budget.register("seen_messages"));
+        self.enforce_orphan_budget();
+        self.enforce_vote_budget();
+        self.enforce_seen_budget();
+        self
+    }
+
     /// Triggered on start or view change to check if we should propose.
+    #[tracing::instrument(name = "view", skip_all, fields(view = self.current_view))]
     pub fn try_propose(&mut self) -> Result<Vec<ConsensusAction>, ConsensusError> {
         if self.is_leader(self.current_view) {
             let prev_view = self.current_view - 1;
             if let Ok(Some(qc)) = self.storage.get_qc(prev_view) {
-                log::info!(
+                tracing::info!(
                     "I am the leader for View {}! Proposing block...",
                     self.current_view
                 );
@@ -221,77 +629,149 @@ impl SimplexState {
                 } else {
                     qc.block_hash
                 };
-                let mut block = self.create_proposal(self.current_view, qc.clone(), parent_hash)?;
+                return self.start_proposal(self.current_view, qc, parent_hash);
+            }
+        }
+        Ok(vec![])
+    }
+
+    /// Build the proposal for `view` and execute it, inline or (when pipelined) as a
+    /// `ProposalJob` picked up with `take_proposal_job`. Before the parent's time plus
+    /// the minimum block interval the proposal is deferred until `on_slot`.
+    fn start_proposal(
+        &mut self,
+        view: View,
+        qc: QuorumCertificate,
+        parent_hash: Hash,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let interval = self.chain_params().min_block_interval;
+        if interval > 0
+            && let Ok(Some(parent)) = self.storage.get_block(&parent_hash)
+        {
+            let slot = parent.timestamp.saturating_add(interval);
+            if (self.clock)() < slot {
+                tracing::info!("Waiting for the slot of View {} (at {})", view, slot);
+                self.deferred_proposal = Some((slot, view, qc, parent_hash));
+                return Ok(vec![]);
+            }
+        }
 
-                // Executor: Execute block to update state_root/receipts_root and validate transactions
-                // USE EPHEMERAL OVERLAY for execution (do not commit to DB)
-                let overlay = Arc::new(StateOverlay::new(self.storage.clone()));
+        let started = Instant::now();
+        let budget = self.proposer.time_budget;
+        let block = self.create_proposal(view, qc, parent_hash, budget.map(|b| started + b / 2))?;
 
-                // Fork state from Parent Root
-                let parent_root = if parent_hash == Hash::default() {
-                    Hash::default()
-                } else {
-                    self.storage
-                        .get_block(&parent_hash)
-                        .ok()
-                        .flatten()
-                        .map(|b| b.state_root)
-                        .unwrap_or_default()
-                };
+        // Executor: Execute block to update state_root/receipts_root and validate transactions
+        // Fork state from Parent Root
+        let parent_root = if parent_hash == Hash::default() {
+            Hash::default()
+        } else {
+            self.storage
+                .get_block(&parent_hash)
+                .ok()
+                .flatten()
+                .map(|b| b.state_root)
+                .unwrap_or_default()
+        };
 
-                let state_manager = Arc::new(Mutex::new(
-                    self.executor
-                        .state
-                        .lock()
-                        .unwrap()
-                        .fork(parent_root, overlay),
-                ));
+        let job = ProposalJob {
+            block,
+            engine: self.engine.clone(),
+            parent_root,
+            deadline: budget.map(|b| started + b),
+        };
+        if self.pipelined {
+            self.proposal_job = Some(job);
+            return Ok(vec![]);
+        }
+        self.on_proposal_ready(job.execute())
+    }
 
-                let executor = Executor::new(state_manager, self.block_gas_limit);
+    /// Unix time (seconds) at which our deferred proposal may be made, if one is waiting.
+    pub fn next_slot(&self) -> Option<u64> {
+        self.deferred_proposal.as_ref().map(|(slot, ..)| *slot)
+    }
+
+    /// Make the deferred proposal once its slot has started. It is dropped if we left its
+    /// view in the meantime.
+    pub fn on_slot(&mut self) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let Some((slot, view, qc, parent_hash)) = self.deferred_proposal.take() else {
+            return Ok(vec![]);
+        };
+        if view != self.current_view {
+            tracing::warn!("Dropping deferred proposal for View {}", view);
+            return Ok(vec![]);
+        }
+        if (self.clock)() < slot {
+            self.deferred_proposal = Some((slot, view, qc, parent_hash));
+            return Ok(vec![]);
+        }
+        self.start_proposal(view, qc, parent_hash)
+    }
 
-                executor
-                    .execute_block(&mut block)
-                    .map_err(|_e| ConsensusError::InvalidParent)?; // Map error appropriately
+    /// The proposal waiting to be executed off the event loop, if any.
+    pub fn take_proposal_job(&mut self) -> Option<ProposalJob> {
+        self.proposal_job.take()
+    }
 
-                log::info!(
-                    "Proposal Executed (View {}): Root {:?}, Gas {}",
-                    block.view,
-                    block.state_root,
-                    block.gas_used
-                );
+    /// Sign, store and broadcast an executed proposal, and vote for it. Proposals for a
+    /// view we already left are dropped.
+    pub fn on_proposal_ready(
+        &mut self,
+        ready: ProposalReady,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        if ready.view < self.current_view {
+            tracing::warn!("Dropping stale proposal for View {}", ready.view);
+            return Ok(vec![]);
+        }
+        let Some(mut block) = ready.block else {
+            tracing::error!("Failed to execute proposal View {}", ready.view);
+            return Err(ConsensusError::InvalidParent);
+        };
+        tracing::info!(
+            "Proposal Executed (View {}): Root {:?}, Gas {}",
+            block.view,
+            block.state_root,
+            block.gas_used
+        );
+
+        block.sign(&self.my_key);
 
-                // Clean up transactions from pool immediately
-                self.tx_pool.remove_transactions(&block.payload);
+        // Clean up transactions from pool immediately (kept aside until finalized)
+        let block_hash = hash_data(&block);
+        self.tx_pool.include_block(block_hash, &block);
 
-                // SAVE the block immediately (Leader trusts own execution)
-                // Note: StateOverlay ensures only block data is saved, not state changes.
-                // Wait, we are calling self.storage.save_block directly here, so it IS saved.
-                // This is correct. We want Block Data in DB, just not Account State.
-                self.storage.save_block(&block).unwrap();
+        // SAVE the block immediately (Leader trusts own execution)
+        // Note: StateOverlay ensures only block data is saved, not state changes.
+        // This is correct. We want Block Data in DB, just not Account State.
+        self.storage
+            .save_block(&block)
+            .map_err(|e| ConsensusError::StorageError(e.to_string()))?;
 
-                // Remove included evidence from pool
-                let evidence_in_block = block.evidence.clone();
-                self.evidence_pool.remove_evidence(&evidence_in_block);
+        // Remove included evidence from pool
+        self.evidence_pool.remove_evidence(&block.evidence);
+        self.evidence_pool
+            .remove_proposal_evidence(&block.proposal_evidence);
 
-                let mut actions = vec![ConsensusAction::BroadcastBlock(block.clone())];
+        let mut actions = vec![ConsensusAction::BroadcastBlock(block.clone())];
 
-                // Generate Vote (Leader votes for own proposal)
-                let block_hash = hash_data(&block);
-                let vote = self.create_vote(block.view, block_hash, VoteType::Notarize);
-                actions.push(ConsensusAction::BroadcastVote(vote));
+        // Update last_voted_view to prevent double voting via on_proposal reflection
+        self.last_voted_view = block.view;
+        self.persist_state();
 
-                // Check Finalize (if QC justifies previous view)
-                let qc_view = block.justify.view;
-                if qc_view > 0 {
-                    let finalize_vote =
-                        self.create_vote(qc_view, block.justify.block_hash, VoteType::Finalize);
-                    actions.push(ConsensusAction::BroadcastVote(finalize_vote));
-                }
+        // Generate Vote (Leader votes for own proposal)
+        actions.extend(self.journaled_vote(block.view, block_hash, VoteType::Notarize));
 
-                return Ok(actions);
-            }
+        // Check Finalize (if QC justifies previous view)
+        let qc_view = block.justify.view;
+        if qc_view > 0 {
+            actions.extend(self.journaled_vote(
+                qc_view,
+                block.justify.block_hash,
+                VoteType::Finalize,
+            ));
         }
-        Ok(vec![])
+
+        Ok(actions)
     }
 
     // Helper to cleanup tx pool after proposing
@@ -305,44 +785,81 @@ impl SimplexState {
     fn validate_and_store_block(
         &mut self,
         block: Block,
+        peer: Option<&str>,
     ) -> Result<(bool, Vec<ConsensusAction>), ConsensusError> {
-        let block_hash = hash_data(&block);
+        match self.precheck_block(&block, peer)? {
+            Precheck::Stored => return Ok((true, vec![])),
+            Precheck::Orphan(actions) => return Ok((false, actions)),
+            Precheck::Execute => {}
+        }
+
+        // 1.5 Execute Block (Validation)
+        let validated = self.validation_job(&block, peer, false)?.run();
+        self.store_validated_block(&block, validated.result?)?;
+        Ok((true, vec![]))
+    }
+
+    /// The checks of `validate_and_store_block` that come before execution.
+    fn precheck_block(
+        &mut self,
+        block: &Block,
+        peer: Option<&str>,
+    ) -> Result<Precheck, ConsensusError> {
+        // Dummy blocks are never sent, and would skip the checks below
+        if block.is_dummy {
+            tracing::warn!("Received dummy block for View {}", block.view);
+            return Err(ConsensusError::InvalidBlock);
+        }
+        let block_hash = hash_data(block);
         if self
             .storage
             .get_block(&block_hash)
             .unwrap_or(None)
             .is_some()
         {
-            return Ok((true, vec![]));
+            return Ok(Precheck::Stored);
+        }
+        // 0. Proposer Signature
+        if !block.verify_signature() {
+            tracing::warn!(
+                "Invalid Proposer Signature for View {} from {:?}",
+                block.view,
+                block.author
+            );
+            return Err(ConsensusError::InvalidSignature);
+        }
+        if !self.is_leader_of(&block.author, block.view) {
+            tracing::warn!(
+                "Proposal for View {} from non-leader {:?}",
+                block.view,
+                block.author
+            );
+            return Err(ConsensusError::NotLeader);
         }
         // 1. Check Parent (Simplex Lineage)
         if block.parent_hash != Hash::default()
             && self
                 .storage
                 .get_block(&block.parent_hash)
-                .unwrap()
+                .unwrap_or(None)
                 .is_none()
         {
             // Orphan Logic: Buffer and Request Parent
-            println!(
-                "DEBUG: Orphan Detected. Parent not found: {:?}",
-                block.parent_hash
-            );
-            self.orphans
-                .entry(block.parent_hash)
-                .or_default()
-                .push(block.clone());
+            tracing::debug!("Orphan Detected. Parent not found: {:?}", block.parent_hash);
+            if !self.buffer_orphan(block.clone(), peer) {
+                return Ok(Precheck::Orphan(vec![]));
+            }
 
-            return Ok((
-                false,
-                vec![ConsensusAction::BroadcastRequest(block.parent_hash)],
-            ));
+            return Ok(Precheck::Orphan(vec![ConsensusAction::BroadcastRequest(
+                block.parent_hash,
+            )]));
         }
 
-        // 1.1 Committee Hash Check
-        let expected_committee_hash = hash_data(&self.committee);
+        // 1.1 Committee Hash Check: against the committee of the block's epoch, so blocks
+        // of earlier (or later) epochs validate during catch-up
+        let expected_committee_hash = hash_data(&self.block_committee(block.view));
         if block.committee_hash != expected_committee_hash {
-            log::warn!(
+            tracing::warn!(
                 "Invalid Committee Hash: Expected {:?}, Got {:?}",
                 expected_committee_hash,
                 block.committee_hash
@@ -350,71 +867,185 @@ impl SimplexState {
             return Err(ConsensusError::InvalidBlock); // Or specific error
         }
 
+        // 1.1.0 Transactions Root Check: the header commits to the payload it carries
+        if block.transactions_root != calculate_transactions_root(&block.payload) {
+            tracing::warn!("Invalid Transactions Root for View {}", block.view);
+            return Err(ConsensusError::InvalidBlock);
+        }
+
+        // 1.1.0.1 Size Check: the block and its transactions must be small enough to gossip
+        // (revealed transactions are bounded by their ciphertexts, included earlier)
+        let params = self.chain_params();
+        let size = wire_size(block);
+        if size as u64 > params.max_block_size {
+            tracing::warn!(
+                "Block too large for View {}: {} > {} bytes",
+                block.view,
+                size,
+                params.max_block_size
+            );
+            return Err(ConsensusError::BlockTooLarge {
+                size,
+                limit: params.max_block_size,
+            });
+        }
+        if block
+            .payload
+            .iter()
+            .skip(block.metadata.decryption_keys.len())
+            .any(|tx| wire_size(tx) as u64 > params.max_tx_size)
+        {
+            tracing::warn!("Transaction too large in View {}", block.view);
+            return Err(ConsensusError::InvalidBlock);
+        }
+
+        // 1.1.1 Proposal Metadata Check
+        let leading = block.metadata.operator_txs as usize + block.metadata.decryption_keys.len();
+        if leading > block.payload.len()
+            || block.metadata.decryption_keys.len() > MAX_REVEALED_PER_BLOCK
+        {
+            tracing::warn!(
+                "Invalid Proposal Metadata: {} operator txs and {} revealed txs in a payload of {}",
+                block.metadata.operator_txs,
+                block.metadata.decryption_keys.len(),
+                block.payload.len()
+            );
+            return Err(ConsensusError::InvalidBlock);
+        }
+
+        // 1.1.1.0 Gas Check: the payload (bar the revealed transactions, checked as they
+        // execute) and the claimed gas used must fit in the block's gas limit, within the
+        // chain's cap
+        let limit = crate::fees::execution_gas_limit(&params, &block);
+        let declared_gas = block.payload[block.metadata.decryption_keys.len()..]
+            .iter()
+            .fold(0u64, |gas, tx| gas.saturating_add(tx.gas_limit));
+        let gas = declared_gas.max(block.gas_used);
+        if gas > limit {
+            tracing::warn!(
+                "Block gas exceeds the limit for View {}: {} > {}",
+                block.view,
+                gas,
+                limit
+            );
+            return Err(ConsensusError::GasLimitExceeded { gas, limit });
+        }
+
+        // 1.1.1.1 Height, Timestamp and Gas Limit Check: one above the parent, at least the
+        // minimum block interval after the parent's time and not too far ahead of our clock,
+        // with a gas limit at most 1/GAS_LIMIT_BOUND_DIVISOR away from the parent's
+        if let Some(parent) = self.storage.get_block(&block.parent_hash).unwrap_or(None) {
+            if block.height != parent.height + 1 {
+                tracing::warn!(
+                    "Invalid Height: {} on a parent at height {}",
+                    block.height,
+                    parent.height
+                );
+                return Err(ConsensusError::InvalidBlock);
+            }
+            let min_timestamp = parent
+                .timestamp
+                .saturating_add(self.chain_params().min_block_interval);
+            let max_timestamp = (self.clock)().saturating_add(MAX_TIMESTAMP_DRIFT);
+            if block.timestamp < min_timestamp || block.timestamp > max_timestamp {
+                tracing::warn!(
+                    "Invalid Timestamp: {} (min {}, max {})",
+                    block.timestamp,
+                    min_timestamp,
+                    max_timestamp
+                );
+                return Err(ConsensusError::InvalidBlock);
+            }
+            let bounds =
+                crate::fees::gas_limit_bounds(crate::fees::header_gas_limit(&params, &parent));
+            if !bounds.contains(&block.gas_limit) {
+                tracing::warn!(
+                    "Invalid Gas Limit: {} (allowed {:?})",
+                    block.gas_limit,
+                    bounds
+                );
+                return Err(ConsensusError::InvalidBlock);
+            }
+        }
+
+        // 1.1.2 Evidence Expiry Check (ancient evidence must not be replayed)
+        if let Some(expired) = block
+            .evidence
+            .iter()
+            .find(|e| params.is_evidence_expired(e, block.view))
+        {
+            tracing::warn!(
+                "Expired Evidence: offence at View {} included in View {}",
+                expired.vote_a.view,
+                block.view
+            );
+            return Err(ConsensusError::InvalidBlock);
+        }
+        if let Some(expired) = block
+            .proposal_evidence
+            .iter()
+            .find(|e| params.is_proposal_evidence_expired(e, block.view))
+        {
+            tracing::warn!(
+                "Expired Proposal Evidence: offence at View {} included in View {}",
+                expired.view(),
+                block.view
+            );
+            return Err(ConsensusError::InvalidBlock);
+        }
+
         // 1.2 Fork/Lineage Check
         // 1.2 Fork/Lineage Check
         // Disabled because SMT Root in blocks (ephemeral) differs from Local SMT Root (persistent) in current implementation.
         // if let Ok(Some(parent)) = self.storage.get_block(&block.parent_hash) {
-        //     let current_root = self.executor.state.lock().unwrap().root();
+        //     let current_root = self.engine.state_root();
         //     if parent.state_root != current_root {
-        //         println!("DEBUG: Fork Detected! Parent Root {:?} != Local Root {:?}", parent.state_root, current_root);
+        //         tracing::debug!("Fork Detected! Parent Root {:?} != Local Root {:?}", parent.state_root, current_root);
         //         // Let's drop it to silence the error.
         //         return Ok((false, vec![]));
         //     }
         // }
 
-        // 1.5 Execute Block (Validation)
-        // We must re-execute to verify state_root and receipts_root matches.
-        let overlay = Arc::new(StateOverlay::new(self.storage.clone()));
+        Ok(Precheck::Execute)
+    }
 
-        // Fork state from Parent Root
-        let parent_root = if block.parent_hash == Hash::default() {
-            Hash::default()
-        } else {
-            self.storage
-                .get_block(&block.parent_hash)
-                .ok()
-                .flatten()
-                .map(|b| b.state_root)
-                .unwrap_or_default()
+    /// Prepare the re-execution of `block`: on the block's witness when validating
+    /// statelessly, otherwise on our own state (recording the witness for others).
+    fn validation_job(
+        &self,
+        block: &Block,
+        peer: Option<&str>,
+        sync: bool,
+    ) -> Result<ValidationJob, ConsensusError> {
+        let block_hash = hash_data(block);
+        let parent_root = self.parent_state_root(block);
+        let witness = match self.storage.get_witness(&block_hash).ok().flatten() {
+            Some(witness) if self.stateless => {
+                if !witness.verify(&parent_root) {
+                    tracing::warn!("Invalid State Witness for View {}", block.view);
+                    return Err(ConsensusError::InvalidBlock);
+                }
+                Some(witness)
+            }
+            _ => None,
         };
+        Ok(ValidationJob {
+            block: block.clone(),
+            peer: peer.map(str::to_string),
+            sync,
+            engine: self.engine.clone(),
+            parent_root,
+            witness,
+        })
+    }
 
-        let state_manager = Arc::new(Mutex::new(
-            self.executor
-                .state
-                .lock()
-                .unwrap()
-                .fork(parent_root, overlay),
-        ));
-
-        let executor = Executor::new(state_manager, self.block_gas_limit);
-
-        let mut executed_block = block.clone();
-        // Clear gas used/roots to verify execution recreation
-        executed_block.gas_used = 0;
-        // executed_block.state_root = Hash::default(); // Keep original to compare? No, executor overwrites it.
-
-        executor.execute_block(&mut executed_block).map_err(|e| {
-            log::error!("Block Execution Failed: {:?}", e);
-            ConsensusError::InvalidBlock
-        })?;
-
-        if block.state_root != executed_block.state_root {
-            log::error!(
-                "Invalid State Root: expected {:?}, got {:?}",
-                block.state_root,
-                executed_block.state_root
-            );
-            return Err(ConsensusError::InvalidStateRoot);
-        }
-
-        if executed_block.receipts_root != block.receipts_root {
-            log::error!(
-                "Invalid Receipts Root: expected {:?}, got {:?}",
-                block.receipts_root,
-                executed_block.receipts_root
-            );
-            return Err(ConsensusError::InvalidReceiptsRoot);
-        }
+    /// Store a block whose execution checked out (the rest of `validate_and_store_block`).
+    fn store_validated_block(
+        &mut self,
+        block: &Block,
+        witness: Option<StateWitness>,
+    ) -> Result<(), ConsensusError> {
+        let block_hash = hash_data(block);
 
         // 2. Verify QC
         self.verify_qc(&block.justify)?;
@@ -423,64 +1054,370 @@ impl SimplexState {
         self.update_preferred_chain(&block.justify);
 
         // 4. Update state (store block)
-        self.storage.save_block(&block).unwrap();
+        self.storage
+            .save_block(block)
+            .map_err(|e| ConsensusError::StorageError(e.to_string()))?;
+        if let Some(witness) = witness
+            && let Err(e) = self.storage.save_witness(&block_hash, &witness)
+        {
+            tracing::error!("Failed to save state witness: {:?}", e);
+        }
 
         // 5. Clean up TxPool
-        // Remove transactions included in this valid block from our pool
-        self.tx_pool.remove_transactions(&block.payload);
+        // Remove transactions included in this valid block from our pool, until it is
+        // either finalized or abandoned
+        self.tx_pool.include_block(block_hash, block);
 
         // Remove included evidence from pool (if any)
         self.evidence_pool.remove_evidence(&block.evidence);
+        self.evidence_pool
+            .remove_proposal_evidence(&block.proposal_evidence);
 
-        Ok((true, vec![]))
+        Ok(())
     }
 
-    /// Handle a new proposal.
-    pub fn on_proposal(&mut self, block: Block) -> Result<Vec<ConsensusAction>, ConsensusError> {
-        // 1. View Check (Strict for proposals)
-        if block.view < self.current_view {
-            // For live proposals, late blocks are irrelevant
-            return Err(ConsensusError::InvalidView);
-        }
+    /// Re-execute `block` on top of its parent's state (in an overlay) and check that it
+    /// reproduces the block's state and receipts roots. Returns the pre-state it read.
+    fn verify_execution(&self, block: &Block) -> Result<StateWitness, ConsensusError> {
+        let witness = check_execution(
+            block,
+            self.engine.as_ref(),
+            self.parent_state_root(block),
+            None,
+        )?;
+        Ok(witness.unwrap_or_default())
+    }
 
-        // 2. Common Validation & Storage
-        let (stored, mut actions) = self.validate_and_store_block(block.clone())?;
-        if !stored {
-            return Ok(actions); // It was an orphan, request sent
+    fn parent_state_root(&self, block: &Block) -> Hash {
+        if block.parent_hash == Hash::default() {
+            Hash::default()
+        } else {
+            self.storage
+                .get_block(&block.parent_hash)
+                .ok()
+                .flatten()
+                .map(|b| b.state_root)
+                .unwrap_or_default()
         }
+    }
 
-        // 3. Update view if needed (fast forward)
-        if block.view >= self.current_view {
-            self.current_view = block.view;
-            self.persist_state();
+    /// Run the cheap checks on `block` and queue its execution (`take_validation_jobs`).
+    fn queue_validation(
+        &mut self,
+        block: Block,
+        peer: Option<&str>,
+        sync: bool,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let block_hash = hash_data(&block);
+        match self.precheck_block(&block, peer)? {
+            Precheck::Stored if sync => return self.finish_block_response(block, vec![]),
+            Precheck::Stored => return self.finish_proposal(block, vec![]),
+            Precheck::Orphan(actions) => {
+                if !sync {
+                    // A redelivery may retry once the parent is here
+                    self.seen_blocks.remove(&block_hash);
+                }
+                return Ok(actions);
+            }
+            Precheck::Execute => {}
         }
-
-        // 4. Generate Vote (Strict Check)
-        if block.view <= self.last_voted_view {
-            // We already voted for this view (or a higher one). Do not vote again.
-            // Parallel Chain Prevention: Honest nodes MUST NOT equivocate.
-            log::warn!(
-                "Double Voting Attempt Rejected: View {}, Last Voted {}",
-                block.view,
-                self.last_voted_view
-            );
-            return Ok(actions);
+        if self.validating.contains(&block_hash) {
+            return Ok(vec![]);
         }
+        let job = self.validation_job(&block, peer, sync)?;
+        self.validating.insert(block_hash);
+        self.validation_jobs.push(job);
+        Ok(vec![])
+    }
 
-        // UPDATE AND PERSIST STATE BEFORE VOTING
-        self.last_voted_view = block.view;
-        self.persist_state(); // Critical: Persist the fact that we voted.
+    /// Blocks waiting to be executed by the validation workers.
+    pub fn take_validation_jobs(&mut self) -> Vec<ValidationJob> {
+        std::mem::take(&mut self.validation_jobs)
+    }
 
-        let block_hash = hash_data(&block);
-        let vote = self.create_vote(block.view, block_hash, VoteType::Notarize);
-        actions.push(ConsensusAction::BroadcastVote(vote));
+    /// Blocks handed to the validation workers whose results have not come back yet.
+    pub fn pending_validations(&self) -> usize {
+        self.validating.len()
+    }
+
+    /// Write the view, vote and preferred chain to storage. They are written as they
+    /// change; this is the final write on shutdown.
+    pub fn flush(&self) {
+        self.persist_state();
+    }
+
+    /// Store a block executed by a validation worker, then vote for it (proposals) or
+    /// catch up on it (synced blocks).
+    pub fn on_block_validated(
+        &mut self,
+        validated: BlockValidated,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let block_hash = hash_data(&validated.block);
+        self.validating.remove(&block_hash);
+        let witness = validated.result?;
+        self.store_validated_block(&validated.block, witness)?;
+        if validated.sync {
+            return self.finish_block_response(validated.block, vec![]);
+        }
+        let mut actions = self.finish_proposal(validated.block, vec![])?;
+        // Children may have arrived while it was being executed
+        actions.extend(self.process_orphans_of(block_hash));
+        Ok(actions)
+    }
+
+    /// Handle a new proposal.
+    pub fn on_proposal(&mut self, block: Block) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        self.process_proposal(block, None)
+    }
+
+    /// Handle a gossiped compact block: rebuild it from the transaction pool, or request
+    /// the transactions missing from it.
+    pub fn on_compact_block(
+        &mut self,
+        compact: CompactBlock,
+        peer_id: String,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let block_hash = compact.block_hash;
+        if compact.header.view < self.current_view
+            || self.seen_blocks.contains(&block_hash)
+            || self.pending_blocks.contains_key(&block_hash)
+        {
+            return Ok(vec![]);
+        }
+        let mut received = HashMap::new();
+        let mut missing = vec![];
+        for id in &compact.tx_ids {
+            match self.tx_pool.get_transaction(id) {
+                Some(tx) => {
+                    received.insert(*id, tx);
+                }
+                None => missing.push(*id),
+            }
+        }
+        let pending = PendingBlock {
+            compact,
+            peer_id,
+            received,
+        };
+        if missing.is_empty() {
+            return self.complete_pending(pending);
+        }
+
+        let current_view = self.current_view;
+        self.pending_blocks
+            .retain(|_, pending| pending.compact.header.view >= current_view);
+        if self.pending_blocks.len() >= MAX_PENDING_BLOCKS {
+            return Ok(vec![]);
+        }
+        self.pending_blocks.insert(block_hash, pending);
+        Ok(vec![ConsensusAction::RequestTransactions(
+            block_hash, missing,
+        )])
+    }
+
+    /// Add transactions fetched for a pending compact block, and process it once complete.
+    pub fn on_block_transactions(
+        &mut self,
+        block_hash: Hash,
+        txs: Vec<Transaction>,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let Some(mut pending) = self.pending_blocks.remove(&block_hash) else {
+            return Ok(vec![]);
+        };
+        for tx in txs {
+            let id = hash_data(&tx);
+            if pending.compact.tx_ids.contains(&id) {
+                pending.received.insert(id, tx);
+            }
+        }
+        if pending
+            .compact
+            .tx_ids
+            .iter()
+            .any(|id| !pending.received.contains_key(id))
+        {
+            self.pending_blocks.insert(block_hash, pending);
+            return Ok(vec![]);
+        }
+        self.complete_pending(pending)
+    }
+
+    /// Serve the transactions of `block_hash` (from storage, or our pool for a fresh
+    /// proposal) that a peer is missing.
+    pub fn on_transactions_request(&self, block_hash: Hash, ids: &[Hash]) -> Vec<Transaction> {
+        if let Ok(Some(block)) = self.storage.get_block(&block_hash) {
+            return block
+                .payload
+                .into_iter()
+                .filter(|tx| ids.contains(&hash_data(tx)))
+                .collect();
+        }
+        ids.iter()
+            .filter_map(|id| self.tx_pool.get_transaction(id))
+            .collect()
+    }
+
+    /// Complete a pending compact block with a body fetched from the network.
+    pub fn on_block_body(
+        &mut self,
+        block_hash: Hash,
+        body: BlockBody,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let Some(pending) = self.pending_blocks.remove(&block_hash) else {
+            return Ok(vec![]);
+        };
+        match pending.compact.with_body(body) {
+            Some(block) => self.on_proposal_from(block, pending.peer_id),
+            None => {
+                // Not the announced body; wait for another response
+                self.pending_blocks.insert(block_hash, pending);
+                Ok(vec![])
+            }
+        }
+    }
+
+    fn complete_pending(
+        &mut self,
+        pending: PendingBlock,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let received = &pending.received;
+        match pending.compact.reconstruct(|id| received.get(id).cloned()) {
+            Some(block) => self.on_proposal_from(block, pending.peer_id),
+            None => {
+                // The transaction list does not match the header: fetch the whole body
+                let block_hash = pending.compact.block_hash;
+                self.pending_blocks.insert(block_hash, pending);
+                Ok(vec![ConsensusAction::RequestBody(block_hash)])
+            }
+        }
+    }
+
+    /// Handle a new proposal relayed by `peer_id` (charged against its orphan quota).
+    pub fn on_proposal_from(
+        &mut self,
+        block: Block,
+        peer_id: String,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        self.process_proposal(block, Some(&peer_id))
+    }
+
+    #[tracing::instrument(
+        name = "view",
+        skip_all,
+        fields(view = self.current_view, block_view = block.view)
+    )]
+    fn process_proposal(
+        &mut self,
+        block: Block,
+        peer: Option<&str>,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        // Dummy blocks are never proposed: one claiming to be would skip the checks below
+        if block.is_dummy {
+            return Err(ConsensusError::InvalidBlock);
+        }
+
+        // 0. Duplicate Check (gossip redelivery, our own proposal looping back)
+        let block_id = hash_data(&block);
+        if !self.seen_blocks.insert(block_id) {
+            return Ok(vec![]);
+        }
+        self.enforce_seen_budget();
+
+        // 1. View Check (Strict for proposals)
+        if block.view < self.current_view {
+            // For live proposals, late blocks are irrelevant
+            return Err(ConsensusError::InvalidView);
+        }
+
+        // 1.1 Proposer Signature (checked first so forged blocks cannot frame the leader)
+        if !block.verify_signature() {
+            return Err(ConsensusError::InvalidSignature);
+        }
+        // Only the leader of the view (under its committee) proposes
+        if !self.is_leader_of(&block.author, block.view) {
+            return Err(ConsensusError::NotLeader);
+        }
+        self.emit(ConsensusEvent::ProposalReceived {
+            view: block.view,
+            height: block.height,
+            block_hash: block_id,
+            author: block.author.clone(),
+        });
+
+        // 1.2 Double Proposal Check: a conflicting block is evidence, never a candidate
+        if let Some(evidence) = self.check_double_proposal(&block) {
+            tracing::warn!(
+                "Double Proposal Detected from {:?} in View {}",
+                block.author,
+                block.view
+            );
+            if self.evidence_pool.add_proposal_evidence(evidence.clone()) {
+                self.emit(ConsensusEvent::EvidenceDetected {
+                    view: block.view,
+                    offender: block.author.clone(),
+                    kind: EvidenceKind::DoubleProposal,
+                });
+                return Ok(vec![ConsensusAction::BroadcastProposalEvidence(evidence)]);
+            }
+            return Ok(vec![]);
+        }
+
+        // 2. Common Validation & Storage (execution goes to a worker when asynchronous)
+        if self.async_validation {
+            return self.queue_validation(block, peer, false);
+        }
+        let (stored, actions) = self.validate_and_store_block(block.clone(), peer)?;
+        if !stored {
+            // It was an orphan, request sent. A redelivery may retry once the parent is here.
+            self.seen_blocks.remove(&block_id);
+            return Ok(actions);
+        }
+        self.finish_proposal(block, actions)
+    }
+
+    /// Vote for a stored proposal (the steps of `process_proposal` after validation).
+    fn finish_proposal(
+        &mut self,
+        block: Block,
+        mut actions: Vec<ConsensusAction>,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        // Validated after we left its view: too late to vote
+        if block.view < self.current_view {
+            return Ok(actions);
+        }
+
+        // 3. Update view if needed (fast forward)
+        if block.view >= self.current_view {
+            self.enter_view(block.view);
+        }
+
+        // 4. Generate Vote (Strict Check)
+        if block.view <= self.last_voted_view {
+            // We already voted for this view (or a higher one). Do not vote again.
+            // Parallel Chain Prevention: Honest nodes MUST NOT equivocate.
+            tracing::warn!(
+                "Double Voting Attempt Rejected: View {}, Last Voted {}",
+                block.view,
+                self.last_voted_view
+            );
+            return Ok(actions);
+        }
+
+        // UPDATE AND PERSIST STATE BEFORE VOTING
+        self.last_voted_view = block.view;
+        self.persist_state(); // Critical: Persist the fact that we voted.
+
+        let block_hash = hash_data(&block);
+        actions.extend(self.journaled_vote(block.view, block_hash, VoteType::Notarize));
 
         // 5. Check if we should broadcast Finalize
         let qc_view = block.justify.view;
         if qc_view > 0 {
-            let finalize_vote =
-                self.create_vote(qc_view, block.justify.block_hash, VoteType::Finalize);
-            actions.push(ConsensusAction::BroadcastVote(finalize_vote));
+            actions.extend(self.journaled_vote(
+                qc_view,
+                block.justify.block_hash,
+                VoteType::Finalize,
+            ));
         }
 
         Ok(actions)
@@ -488,13 +1425,42 @@ impl SimplexState {
 
     /// Handle an incoming vote.
     /// If we have enough votes (2f+1), form a QC.
+    #[tracing::instrument(
+        name = "view",
+        skip_all,
+        fields(view = self.current_view, vote_view = vote.view)
+    )]
     pub fn on_vote(&mut self, vote: Vote) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        // Duplicate Check (gossip redelivery, our own votes looping back)
+        if !self.seen_votes.insert(hash_data(&vote)) {
+            return Ok(vec![]);
+        }
+        let result = self.process_vote(vote);
+        self.enforce_vote_budget();
+        self.enforce_seen_budget();
+        result
+    }
+
+    fn process_vote(&mut self, vote: Vote) -> Result<Vec<ConsensusAction>, ConsensusError> {
         // Verify signature
-        if !verify(&vote.author, &vote.block_hash.0, &vote.signature) {
-            log::warn!("Invalid signature from author {:?}", vote.author);
+        if !vote.verify_signature(self.chain_id) {
+            tracing::warn!("Invalid signature from author {:?}", vote.author);
             return Err(ConsensusError::InvalidSignature);
         }
 
+        if vote.vote_type == VoteType::Handover {
+            return self.on_handover_vote(vote);
+        }
+        // Views below the finalized height are settled: their votes are replays or stragglers
+        if vote.view < self.finalized_height {
+            return Ok(vec![]);
+        }
+        // Only the committee of the vote's view counts towards its quorums (and evidence).
+        // Ours stands in for views whose epoch snapshot we have not stored yet.
+        if !self.block_committee(vote.view).contains(&vote.author) {
+            tracing::warn!("Vote from non-committee member {:?}", vote.author);
+            return Err(ConsensusError::NotInCommittee);
+        }
         if vote.vote_type == VoteType::Finalize {
             return self.on_finalize_vote(vote);
         }
@@ -508,7 +1474,7 @@ impl SimplexState {
             && existing_vote.block_hash != Hash::default()
             && vote.block_hash != Hash::default()
         {
-            log::warn!(
+            tracing::warn!(
                 "Equivocation Detected from {:?} in View {}",
                 vote.author,
                 vote.view
@@ -519,6 +1485,11 @@ impl SimplexState {
             };
             // Add to pool and broadcast
             if self.evidence_pool.add_evidence(evidence.clone()) {
+                self.emit(ConsensusEvent::EvidenceDetected {
+                    view: vote.view,
+                    offender: vote.author.clone(),
+                    kind: EvidenceKind::DoubleVote,
+                });
                 return Ok(vec![ConsensusAction::BroadcastEvidence(evidence)]);
             } else {
                 return Ok(vec![]);
@@ -527,149 +1498,272 @@ impl SimplexState {
 
         view_votes.insert(vote.author.clone(), vote.clone());
 
-        let threshold = (self.committee.len() * 2) / 3 + 1;
+        let relay = self.add_to_aggregate(&vote);
+        let mut actions = self.try_notarize(vote.view, vote.block_hash);
+        actions.extend(relay);
+        Ok(actions)
+    }
+
+    /// Aggregates of other nodes' votes (gossiped instead of the votes themselves): merge
+    /// into ours, then act on a quorum like for individual votes.
+    pub fn on_aggregate_vote(
+        &mut self,
+        aggregate: AggregateVote,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        if aggregate.vote_type == VoteType::Handover || aggregate.view < self.finalized_height {
+            return Ok(vec![]);
+        }
+        // Bits of another epoch's committee: the individual votes still get through
+        let (epoch, committee) = self.vote_committee(aggregate.view);
+        if aggregate.epoch != epoch {
+            return Ok(vec![]);
+        }
+        if !aggregate.verify(&committee, self.chain_id) {
+            tracing::warn!("Invalid aggregate signature for View {}", aggregate.view);
+            return Err(ConsensusError::InvalidSignature);
+        }
+
+        let key = (aggregate.view, aggregate.block_hash, aggregate.vote_type);
+        self.merge_aggregate(aggregate);
+        let (view, block_hash, vote_type) = key;
+        let mut actions = match vote_type {
+            VoteType::Notarize => self.try_notarize(view, block_hash),
+            _ => {
+                if self.aggregate_size(&key) >= self.quorum_at(view) {
+                    self.finalize(view, block_hash)?
+                } else {
+                    vec![]
+                }
+            }
+        };
+        actions.extend(self.relay_aggregate(&key));
+        self.enforce_vote_budget();
+        Ok(actions)
+    }
+
+    fn aggregate_size(&self, key: &(View, Hash, VoteType)) -> usize {
+        self.aggregates
+            .get(key)
+            .map_or(0, |a| a.aggregate.signers.len())
+    }
 
-        let mut count_for_block = 0;
-        let mut signatures = Vec::new();
-        let mut signers = Vec::new();
+    /// Fold a committee member's vote into the aggregate for its target.
+    fn add_to_aggregate(&mut self, vote: &Vote) -> Option<ConsensusAction> {
+        let (epoch, committee) = self.vote_committee(vote.view);
+        let index = committee.iter().position(|m| *m == vote.author)?;
+        let single = AggregateVote::from_vote(vote, epoch, index);
+        let key = (vote.view, vote.block_hash, vote.vote_type);
+        match self.aggregates.get_mut(&key) {
+            Some(entry) => {
+                if !entry.aggregate.signers.contains(index) {
+                    entry.aggregate.merge(&single);
+                }
+            }
+            None => {
+                self.aggregates.insert(
+                    key,
+                    RelayedAggregate {
+                        aggregate: single,
+                        relayed: 0,
+                    },
+                );
+            }
+        }
+        self.relay_aggregate(&key)
+    }
 
-        // Simple aggregation: check how many votes for this specific block_hash
-        for v in view_votes.values() {
-            if v.block_hash == vote.block_hash {
-                count_for_block += 1;
-                signatures.push(v.signature.clone());
-                signers.push(v.author.clone());
+    /// Merge a verified aggregate into ours: both if their signers are disjoint, else the
+    /// larger of ours and theirs topped up with the individual votes it lacks.
+    fn merge_aggregate(&mut self, received: AggregateVote) {
+        let key = (received.view, received.block_hash, received.vote_type);
+        let votes = match received.vote_type {
+            VoteType::Notarize => self.votes_received.get(&received.view),
+            _ => self.finalize_votes_received.get(&received.view),
+        };
+        let mut candidate = received.clone();
+        let (epoch, committee) = self.vote_committee(received.view);
+        for (index, member) in committee.iter().enumerate() {
+            if let Some(vote) = votes.and_then(|votes| votes.get(member))
+                && vote.block_hash == received.block_hash
+                && !candidate.signers.contains(index)
+            {
+                candidate.merge(&AggregateVote::from_vote(vote, epoch, index));
             }
         }
 
-        if count_for_block >= threshold {
+        let received_size = received.signers.len();
+        let entry = self.aggregates.entry(key).or_insert(RelayedAggregate {
+            aggregate: candidate.clone(),
+            relayed: 0,
+        });
+        if !entry.aggregate.merge(&received)
+            && candidate.signers.len() > entry.aggregate.signers.len()
+        {
+            entry.aggregate = candidate;
+        }
+        // Whoever sent it already gossiped an aggregate this large
+        entry.relayed = entry.relayed.max(received_size);
+    }
+
+    /// Gossip our aggregate for `key` each time it doubles in size since it was last
+    /// relayed, and once when it reaches a quorum: O(log n) messages per node and target
+    /// instead of relaying all n votes.
+    fn relay_aggregate(&mut self, key: &(View, Hash, VoteType)) -> Option<ConsensusAction> {
+        let threshold = self.quorum_at(key.0);
+        let entry = self.aggregates.get_mut(key)?;
+        let size = entry.aggregate.signers.len();
+        let doubled = size >= 2 * entry.relayed.max(1);
+        let quorum = size >= threshold && entry.relayed < threshold;
+        if size < 2 || size <= entry.relayed || !(doubled || quorum) {
+            return None;
+        }
+        entry.relayed = size;
+        Some(ConsensusAction::BroadcastAggregate(entry.aggregate.clone()))
+    }
+
+    /// Form the QC for `block_hash` at `view` once the notarize aggregate has a quorum.
+    fn try_notarize(&mut self, view: View, block_hash: Hash) -> Vec<ConsensusAction> {
+        let threshold = self.quorum_at(view);
+        let Some(entry) = self.aggregates.get(&(view, block_hash, VoteType::Notarize)) else {
+            return vec![];
+        };
+
+        if entry.aggregate.signers.len() >= threshold {
             // QC Formed!
-            // In a real system we'd handle failure better, but here we expect strictly valid signatures
-            let aggregated_signature =
-                aggregate(&signatures).expect("Failed to aggregate signatures");
-
-            let qc = QuorumCertificate {
-                view: vote.view,
-                block_hash: vote.block_hash,
-                signature: aggregated_signature,
-                signers,
-            };
+            let qc = entry.aggregate.to_qc();
 
             // Check if we haven't already processed this QC to avoid dupes?
-            if self.storage.get_qc(vote.view).unwrap().is_none() {
-                log::info!("QC Formed for View {}", vote.view);
-                self.storage.save_qc(&qc).unwrap();
+            let stored = match self.storage.get_qc(view) {
+                Ok(stored) => stored,
+                Err(e) => {
+                    tracing::error!("Failed to read QC for View {}: {:?}", view, e);
+                    return vec![];
+                }
+            };
+            if stored.is_none() {
+                tracing::info!("QC Formed for View {}", view);
+                if let Err(e) = self.storage.save_qc(&qc) {
+                    tracing::error!("Failed to save QC for View {}: {:?}", view, e);
+                    return vec![];
+                }
                 self.update_preferred_chain(&qc);
+                self.emit(ConsensusEvent::QcFormed {
+                    view,
+                    block_hash,
+                    signers: qc.signers.len(),
+                });
 
-                let next_view = vote.view + 1;
+                let next_view = view + 1;
 
                 // Broadcast Finalize for this View (since it is now notarized!)
-                let finalize_vote =
-                    self.create_vote(vote.view, vote.block_hash, VoteType::Finalize);
-                let mut actions = vec![ConsensusAction::BroadcastVote(finalize_vote)];
+                let mut actions: Vec<_> = self
+                    .journaled_vote(view, block_hash, VoteType::Finalize)
+                    .into_iter()
+                    .collect();
+                actions.extend(self.release_decryption_shares(view, block_hash));
                 if next_view > self.current_view {
-                    self.current_view = next_view;
-                    self.persist_state();
+                    self.enter_view(next_view);
                 }
 
                 // If we are the leader for the NEXT view (qc.view + 1), PROPOSE!
                 if self.is_leader(next_view) {
-                    log::info!(
+                    tracing::info!(
                         "I am the leader for View {}! Proposing block (Chain)...",
                         next_view
                     );
                     // FIX: If QC (from vote) is for a dummy block, extend preferred_block
-                    let parent_hash = if vote.block_hash == Hash::default() {
+                    let parent_hash = if block_hash == Hash::default() {
                         self.preferred_block
                     } else {
-                        vote.block_hash
+                        block_hash
                     };
 
-                    if let Ok(mut block) = self.create_proposal(next_view, qc, parent_hash) {
-                        // Full Proposal Lifecycle (Ephemeral Execution)
-                        let overlay = Arc::new(StateOverlay::new(self.storage.clone()));
-                        let parent_root = if parent_hash == Hash::default() {
-                            Hash::default()
-                        } else {
-                            self.storage
-                                .get_block(&parent_hash)
-                                .ok()
-                                .flatten()
-                                .map(|b| b.state_root)
-                                .unwrap_or(Hash::default())
-                        };
-
-                        let state_manager = Arc::new(Mutex::new(
-                            self.executor
-                                .state
-                                .lock()
-                                .unwrap()
-                                .fork(parent_root, overlay),
-                        ));
-                        let executor = Executor::new(state_manager, self.block_gas_limit);
-
-                        if executor.execute_block(&mut block).is_ok() {
-                            log::info!(
-                                "Proposal Executed (Chain). View: {}, Root: {:?}, Gas: {}",
-                                block.view,
-                                block.state_root,
-                                block.gas_used
-                            );
-
-                            self.tx_pool.remove_transactions(&block.payload);
-                            self.storage.save_block(&block).unwrap();
-
-                            actions.push(ConsensusAction::BroadcastBlock(block.clone()));
-
-                            // Update last_voted_view to prevent double voting via on_proposal reflection
-                            self.last_voted_view = block.view;
-                            self.persist_state();
-
-                            // Vote for own block
-                            let block_hash = hash_data(&block);
-                            let vote = self.create_vote(block.view, block_hash, VoteType::Notarize);
-                            actions.push(ConsensusAction::BroadcastVote(vote));
-
-                            // Finalize Vote if justified
-                            let qc_view = block.justify.view;
-                            if qc_view > 0 {
-                                let finalize_vote = self.create_vote(
-                                    qc_view,
-                                    block.justify.block_hash,
-                                    VoteType::Finalize,
-                                );
-                                actions.push(ConsensusAction::BroadcastVote(finalize_vote));
-                            }
-                        } else {
-                            log::error!("Failed to execute chained proposal View {}", next_view);
-                        }
+                    match self.start_proposal(next_view, qc, parent_hash) {
+                        Ok(proposal_actions) => actions.extend(proposal_actions),
+                        Err(e) => tracing::error!(
+                            "Failed to build chained proposal View {}: {:?}",
+                            next_view,
+                            e
+                        ),
                     }
                 }
-                return Ok(actions);
+                return actions;
             }
-            return Ok(vec![]);
         }
-
-        Ok(vec![])
+        vec![]
     }
 
     /// Handle timeout (dummy block generation).
+    #[tracing::instrument(name = "view", skip_all, fields(view = self.current_view, timeout_view = view))]
     pub fn on_timeout(&mut self, view: View) -> Result<Vec<ConsensusAction>, ConsensusError> {
         if view < self.current_view {
             // For now, ignore old timeouts
             return Ok(vec![]);
         }
+        self.expire_orphans();
+        self.emit(ConsensusEvent::TimeoutTriggered { view });
 
         // Simplex timeout -> Vote for dummy
         let dummy_hash = Hash([0u8; 32]);
-        let vote = self.create_vote(view, dummy_hash, VoteType::Notarize);
+        Ok(self
+            .journaled_vote(view, dummy_hash, VoteType::Notarize)
+            .into_iter()
+            .collect())
+    }
+
+    /// Our vote, recorded in the vote journal before it is handed out for broadcast. None
+    /// if we journaled a vote of the same type for another block in this view (e.g. before
+    /// a crash), or the journal cannot be written: better no vote than an equivocation.
+    /// Votes for the dummy block (timeouts) do not conflict with block votes.
+    fn journaled_vote(
+        &mut self,
+        view: View,
+        block_hash: Hash,
+        vote_type: VoteType,
+    ) -> Option<ConsensusAction> {
+        let records = match self.storage.get_vote_records(view) {
+            Ok(records) => records,
+            Err(e) => {
+                tracing::error!("Failed to read the vote journal: {:?}", e);
+                return None;
+            }
+        };
+        let conflict = records.iter().find(|r| {
+            r.view == view
+                && r.vote_type == vote_type
+                && r.block_hash != block_hash
+                && r.block_hash != Hash::default()
+                && block_hash != Hash::default()
+        });
+        if let Some(record) = conflict {
+            tracing::warn!(
+                "Vote Refused: View {} already voted for {:?} (journal)",
+                view,
+                record.block_hash
+            );
+            return None;
+        }
 
-        Ok(vec![ConsensusAction::BroadcastVote(vote)])
+        let record = VoteRecord {
+            view,
+            block_hash,
+            vote_type,
+            timestamp: (self.clock)(),
+        };
+        if let Err(e) = self.storage.append_vote_record(&record) {
+            tracing::error!("Failed to journal vote for View {}: {:?}", view, e);
+            return None;
+        }
+        Some(ConsensusAction::BroadcastVote(
+            self.create_vote(view, block_hash, vote_type),
+        ))
     }
 
     fn create_vote(&self, view: View, block_hash: Hash, vote_type: VoteType) -> Vote {
-        // Sign the block hash
-        let signature = sign(&self.my_key, &block_hash.0);
+        let signature = sign(
+            &self.my_key,
+            &vote_message(vote_type, view, &block_hash, self.chain_id),
+        );
         Vote {
             view,
             block_hash,
@@ -680,40 +1774,84 @@ impl SimplexState {
     }
 
     fn is_leader(&self, view: View) -> bool {
-        let idx = (view as usize) % self.committee.len();
-        self.committee[idx] == self.my_id
+        self.is_leader_of(&self.my_id, view)
+    }
+
+    /// Round-robin leader of `view` over the committee certifying it.
+    fn is_leader_of(&self, author: &PublicKey, view: View) -> bool {
+        let committee = self.block_committee(view);
+        !committee.is_empty() && committee[(view as usize) % committee.len()] == *author
     }
 
     fn create_proposal(
-        &self,
+        &mut self,
         view: View,
         qc: QuorumCertificate,
         parent: Hash,
+        deadline: Option<Instant>,
     ) -> Result<Block, ConsensusError> {
         // Calculate Next Base Fee based on Parent
         // We need to fetch the parent block to know its gas_used and base_fee.
         // We know 'parent' hash.
-        let base_fee = if let Ok(Some(parent_block)) = self.storage.get_block(&parent) {
-            self.calculate_next_base_fee(&parent_block)
-        } else {
+        let Ok(Some(parent_block)) = self.storage.get_block(&parent) else {
             // FIX: If we can't find the parent, we can't safely propose because:
             // 1. We don't know the base fee.
             // 2. We haven't executed the parent, so our DB state is likely stale.
             // 3. We might re-include transactions that were already in the parent.
             // (Unless it's Genesis, but Genesis handling should ensure it's saved).
-            log::warn!(
+            tracing::warn!(
                 "Parent block {:?} not found. Dropping proposal opportunity.",
                 parent
             );
             // We should ideally request sync here too.
             return Err(ConsensusError::InvalidParent);
         };
-
-        // Filter transactions by base_fee
-        // Note: get_transactions_for_block should now assume sorted by priority fee and filter by base_fee
-        let payload = self
-            .tx_pool
-            .get_transactions_for_block(self.block_gas_limit, base_fee);
+        let params = self.chain_params();
+        let base_fee = crate::fees::next_base_fee(&params, &parent_block);
+        let header_gas_limit = crate::fees::next_gas_limit(
+            crate::fees::header_gas_limit(&params, &parent_block),
+            self.proposer
+                .gas_limit_target
+                .unwrap_or(params.block_gas_limit),
+        );
+        let gas_limit = header_gas_limit.min(params.block_gas_limit);
+
+        // Revealed encrypted transactions lead the payload, in the order they were included,
+        // then the evidence, then the pool's transactions, in what is left of the block size
+        let mut size_budget = params
+            .max_block_size
+            .saturating_sub(PROPOSAL_HEADER_RESERVE);
+        let (decryption_keys, mut payload): (Vec<DecryptionKey>, Vec<Transaction>) =
+            take_fitting(self.revealable(view, &params, gas_limit), &mut size_budget)
+                .into_iter()
+                .unzip();
+        self.evidence_pool
+            .prune_expired(view, params.evidence_max_age);
+        let evidence = take_fitting(self.evidence_pool.get_all(), &mut size_budget);
+        let proposal_evidence =
+            take_fitting(self.evidence_pool.get_all_proposals(), &mut size_budget);
+        let revealed_gas: u64 = payload.iter().map(|tx| tx.gas_limit).sum();
+        let revealed_nonces: HashSet<(Address, u64)> =
+            payload.iter().map(|tx| (tx.sender(), tx.nonce)).collect();
+        let mut candidates = self.tx_pool.candidates(base_fee);
+        candidates.retain(|tx| !revealed_nonces.contains(&(tx.sender(), tx.nonce)));
+
+        // Filter transactions by base_fee, then leave selection and order to the builder
+        // Operator transactions (allowlisted senders) come next
+        let Payload {
+            transactions,
+            operator_txs,
+        } = self.payload_builder.build(
+            candidates,
+            &PayloadRequest {
+                gas_limit: gas_limit - revealed_gas,
+                base_fee,
+                operators: &self.proposer.operator_accounts,
+                max_size: size_budget,
+                deadline,
+            },
+        );
+        payload.extend(transactions);
 
         // Note: We don't know gas_used yet, only at execution.
         // But Block::new requires it?
@@ -721,7 +1859,7 @@ impl SimplexState {
         // In this architecture, we execute IMMEDIATELY after creation in try_propose.
         // So we can initialize with 0, and executor updates it.
 
-        let block = Block::new(
+        let mut block = Block::new(
             self.my_id.clone(),
             view,
             parent, // Parent of new block is the block certified by QC
@@ -730,101 +1868,545 @@ impl SimplexState {
             Hash::default(), // receipts_root
             payload,
             base_fee,
-            0,                            // gas_used initialized to 0, updated by executor
-            self.evidence_pool.get_all(), // Include all pending evidence
-            hash_data(&self.committee),   // Committee Hash
+            0,        // gas_used initialized to 0, updated by executor
+            evidence, // Pending evidence (what fits)
+            hash_data(&self.block_committee(view)), // Committee Hash
+        );
+        block.height = parent_block.height + 1;
+        block.gas_limit = header_gas_limit;
+        block.timestamp = (self.clock)().max(
+            parent_block
+                .timestamp
+                .saturating_add(params.min_block_interval),
         );
+        block.proposal_evidence = proposal_evidence;
+        block.metadata = ProposalMetadata {
+            fee_recipient: self.proposer.fee_recipient,
+            operator_txs: operator_txs as u32,
+            decryption_keys,
+        };
         Ok(block)
     }
 
-    /// EIP-1559 Base Fee Calculation
-    fn calculate_next_base_fee(&self, parent: &Block) -> U256 {
-        let elasticity_multiplier = 2;
-        let base_fee_max_change_denominator = 8;
-        let target_gas = self.block_gas_limit / elasticity_multiplier;
+    /// Ciphertexts a proposal for `view` can reveal, with their keys and transactions: those
+    /// within their window whose shares we hold from every member, oldest first, up to
+    /// `MAX_REVEALED_PER_BLOCK` and `gas_limit`.
+    fn revealable(
+        &self,
+        view: View,
+        params: &ChainParams,
+        gas_limit: u64,
+    ) -> Vec<(DecryptionKey, Transaction)> {
+        if !params.encrypted_mempool {
+            return vec![];
+        }
+        let mut ready: Vec<(&Hash, &(View, EncryptedTransaction))> = self
+            .ciphertexts
+            .iter()
+            .filter(|(_, (included, _))| {
+                *included < view && view <= included.saturating_add(params.decryption_window)
+            })
+            .collect();
+        ready.sort_by_key(|(id, (included, _))| (*included, id.0));
+
+        let mut revealed = vec![];
+        let mut gas = 0u64;
+        for (id, (_, envelope)) in ready {
+            if revealed.len() >= MAX_REVEALED_PER_BLOCK {
+                break;
+            }
+            let Some((_, shares)) = self.decryption_shares.get(id) else {
+                continue;
+            };
+            let Some(signatures) = self
+                .committee
+                .iter()
+                .map(|member| shares.get(member).cloned())
+                .collect::<Option<Vec<Signature>>>()
+            else {
+                continue;
+            };
+            let Some(key) = aggregate(&signatures) else {
+                continue;
+            };
+            let key = DecryptionKey {
+                envelope: envelope.clone(),
+                key,
+            };
+            // Checked as the executor will (a failure there would invalidate the block)
+            let Ok(tx) = encrypted_mempool::open(&self.committee, &key) else {
+                continue;
+            };
+            if gas + tx.gas_limit > gas_limit {
+                continue;
+            }
+            gas += tx.gas_limit;
+            revealed.push((key, tx));
+        }
+        revealed
+    }
+
+    /// Sign and record our decryption shares of the ciphertexts submitted in the block
+    /// notarized at `view`, and forget the ciphertexts it reveals. Shares are only
+    /// released from notarization on, when the order of the ciphertexts is fixed.
+    fn release_decryption_shares(&mut self, view: View, block_hash: Hash) -> Vec<ConsensusAction> {
+        if block_hash == Hash::default() || !self.chain_params().encrypted_mempool {
+            return vec![];
+        }
+        let Ok(Some(block)) = self.storage.get_block(&block_hash) else {
+            return vec![];
+        };
+        for key in &block.metadata.decryption_keys {
+            let id = key.envelope.id();
+            self.ciphertexts.remove(&id);
+            self.decryption_shares.remove(&id);
+        }
+        if !self.committee.contains(&self.my_id) {
+            return vec![];
+        }
+
+        let mut shares = vec![];
+        for envelope in block
+            .payload
+            .iter()
+            .filter_map(encrypted_mempool::submitted_envelope)
+        {
+            let id = envelope.id();
+            if self.ciphertexts.contains_key(&id) {
+                continue;
+            }
+            self.ciphertexts.insert(id, (view, envelope));
+            let share = sign(&self.my_key, &id.0);
+            self.decryption_shares
+                .entry(id)
+                .or_insert_with(|| (view, HashMap::new()))
+                .1
+                .insert(self.my_id.clone(), share.clone());
+            shares.push(DecryptionShare {
+                id,
+                author: self.my_id.clone(),
+                share,
+            });
+        }
+        if shares.is_empty() {
+            return vec![];
+        }
+        tracing::info!(
+            "Releasing {} decryption shares for View {}",
+            shares.len(),
+            view
+        );
+        vec![ConsensusAction::BroadcastDecryptionShares(shares)]
+    }
 
-        let parent_gas_used = parent.gas_used;
-        let parent_base_fee = parent.base_fee_per_gas;
+    /// Process decryption shares gossiped by committee members (shares of non-members are
+    /// ignored).
+    pub fn on_decryption_shares(
+        &mut self,
+        shares: Vec<DecryptionShare>,
+    ) -> Result<(), ConsensusError> {
+        let view = self.current_view;
+        for share in shares {
+            if !self.committee.contains(&share.author) {
+                continue;
+            }
+            if !share.verify() {
+                return Err(ConsensusError::InvalidSignature);
+            }
+            self.decryption_shares
+                .entry(share.id)
+                .or_insert_with(|| (view, HashMap::new()))
+                .1
+                .insert(share.author, share.share);
+        }
+        Ok(())
+    }
 
-        if parent_gas_used == target_gas {
-            parent_base_fee
-        } else if parent_gas_used > target_gas {
-            let gas_used_delta = parent_gas_used - target_gas;
-            let base_fee_increase = parent_base_fee * U256::from(gas_used_delta)
-                / U256::from(target_gas)
-                / U256::from(base_fee_max_change_denominator);
-            parent_base_fee + base_fee_increase
-        } else {
-            let gas_used_delta = target_gas - parent_gas_used;
-            let base_fee_decrease = parent_base_fee * U256::from(gas_used_delta)
-                / U256::from(target_gas)
-                / U256::from(base_fee_max_change_denominator);
-            parent_base_fee.saturating_sub(base_fee_decrease)
+    /// Remember the first proposal of each view; returns evidence if `block` conflicts with it.
+    fn check_double_proposal(&mut self, block: &Block) -> Option<ProposalEquivocationEvidence> {
+        let max_age = self.chain_params().evidence_max_age;
+        let min_view = self.current_view.saturating_sub(max_age);
+        self.seen_proposals.retain(|view, _| *view >= min_view);
+
+        match self.seen_proposals.get(&block.view) {
+            Some(first) if first.author == block.author && hash_data(first) != hash_data(block) => {
+                Some(ProposalEquivocationEvidence {
+                    block_a: first.clone(),
+                    block_b: block.clone(),
+                })
+            }
+            Some(_) => None,
+            None => {
+                self.seen_proposals.insert(block.view, block.clone());
+                None
+            }
         }
     }
 
+    /// Live chain parameters (changed through governance).
+    fn chain_params(&self) -> ChainParams {
+        self.storage
+            .get_consensus_state()
+            .ok()
+            .flatten()
+            .map(|s| s.params)
+            .unwrap_or_else(|| ChainParams {
+                block_gas_limit: self.block_gas_limit,
+                ..ChainParams::default()
+            })
+    }
+
     // try_finalize removed in favor of on_finalize_vote
     fn on_finalize_vote(&mut self, vote: Vote) -> Result<Vec<ConsensusAction>, ConsensusError> {
         let view_votes = self.finalize_votes_received.entry(vote.view).or_default();
         view_votes.insert(vote.author.clone(), vote.clone());
+        // The quorum has to be for this block: votes split across blocks finalize none
+        let votes = view_votes
+            .values()
+            .filter(|v| v.block_hash == vote.block_hash)
+            .count();
+
+        let relay = self.add_to_aggregate(&vote);
+        let mut actions = if votes >= self.quorum_at(vote.view) {
+            self.finalize(vote.view, vote.block_hash)?
+        } else {
+            vec![]
+        };
+        actions.extend(relay);
+        Ok(actions)
+    }
 
-        let threshold = (self.committee.len() * 2) / 3 + 1;
-        if view_votes.len() >= threshold {
-            // Explicit Simplex Finalization!
-            if vote.view > self.finalized_height {
-                self.finalized_height = vote.view;
-                log::info!("EXPLICITLY FINALIZED VIEW: {}", vote.view);
-                self.persist_state();
-
-                // Check for Dummy Block (Timeout)
-                if vote.block_hash == Hash::default() {
-                    log::info!(
-                        "Finalized Dummy Block (Timeout) for View {}. Skipping state commit.",
-                        vote.view
-                    );
-                    return Ok(vec![]);
-                }
+    /// Explicit Simplex Finalization of `view` (2f+1 Finalize votes), committing `block_hash`.
+    fn finalize(
+        &mut self,
+        view: View,
+        block_hash: Hash,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        if view <= self.finalized_height {
+            return Ok(vec![]);
+        }
+        self.finalized_height = view;
+        tracing::info!("EXPLICITLY FINALIZED VIEW: {}", view);
+        self.save_finality_qc(view, block_hash);
+        self.persist_state();
+        self.prune_finalized();
+        self.emit(ConsensusEvent::Finalized { view, block_hash });
+
+        // Check for Dummy Block (Timeout)
+        if block_hash == Hash::default() {
+            tracing::info!(
+                "Finalized Dummy Block (Timeout) for View {}. Skipping state commit.",
+                view
+            );
+            self.settle_included_transactions(view, block_hash);
+            return Ok(vec![]);
+        }
 
-                // COMMIT STATE (Re-execute against persistent storage)
-                match self.storage.get_block(&vote.block_hash) {
-                    Ok(Some(mut block)) => {
-                        log::info!("Committing Finalized Block View {}", block.view);
-                        // Use self.executor which points to REAL storage
-                        if let Err(e) = self.executor.execute_block(&mut block) {
-                            log::error!("CRITICAL: Failed to commit finalized block: {:?}", e);
-                        } else {
-                            log::info!("State Committed for View {}", block.view);
-
-                            // RELOAD COMMITTEE from System Contract (Storage)
-                            let db = self.executor.state.lock().unwrap();
-                            if let Ok(Some(state)) = db.get_consensus_state() {
-                                // Update local view of committee
-                                self.committee = state.committee;
-                                log::info!("Updated Validator Set. Size: {}", self.committee.len());
-                            }
+        // COMMIT STATE (Re-execute against persistent storage)
+        match self.storage.get_block(&block_hash) {
+            Ok(Some(mut block)) => {
+                tracing::info!("Committing Finalized Block View {}", block.view);
+                if let Err(e) = self.engine.commit(&mut block) {
+                    tracing::error!("CRITICAL: Failed to commit finalized block: {:?}", e);
+                } else {
+                    tracing::info!("State Committed for View {}", block.view);
+                    self.save_chain_head(block.view, block_hash);
+                    self.settle_included_transactions(view, block_hash);
+
+                    // RELOAD COMMITTEE from System Contract (Storage)
+                    let new_committee = self
+                        .storage
+                        .get_consensus_state()
+                        .ok()
+                        .flatten()
+                        .map(|s| s.committee);
+                    if let Some(new_committee) = new_committee {
+                        // Update local view of committee
+                        let old_committee = std::mem::replace(&mut self.committee, new_committee);
+                        tracing::info!("Updated Validator Set. Size: {}", self.committee.len());
+
+                        if old_committee != self.committee {
+                            return Ok(self.start_committee_transition(
+                                view,
+                                block_hash,
+                                old_committee,
+                            ));
                         }
                     }
-                    Ok(None) => {
-                        log::warn!(
-                            "Finalized block not found in storage: {:?}",
-                            vote.block_hash
-                        );
-                        // We might need to request it?
-                    }
-                    Err(e) => {
-                        log::error!("Storage error fetching finalized block: {:?}", e);
+                }
+            }
+            Ok(None) => {
+                tracing::warn!("Finalized block not found in storage: {:?}", block_hash);
+                // We might need to request it?
+            }
+            Err(e) => {
+                tracing::error!("Storage error fetching finalized block: {:?}", e);
+            }
+        }
+        Ok(vec![])
+    }
+
+    /// `view` was finalized with `block_hash` (the default hash for a dummy block): the
+    /// blocks we took transactions from that can no longer be finalized give them back to
+    /// the pool. A finalized block settles every view up to its own (only its chain
+    /// survives); a dummy block only settles its view.
+    fn settle_included_transactions(&self, view: View, block_hash: Hash) {
+        let dummy = block_hash == Hash::default();
+        let settled: Vec<(Hash, View)> = self
+            .tx_pool
+            .included_blocks()
+            .into_iter()
+            .filter(|(_, v)| *v == view || (!dummy && *v < view))
+            .collect();
+        let Some(lowest) = settled.iter().map(|(_, v)| *v).min() else {
+            return;
+        };
+
+        // The finalized chain, down to the lowest settled view
+        let mut chain = HashSet::new();
+        let mut hash = block_hash;
+        while hash != Hash::default()
+            && let Ok(Some(header)) = self.storage.get_header(&hash)
+            && header.view >= lowest
+        {
+            chain.insert(hash);
+            hash = header.parent_hash;
+        }
+
+        let (finalized, abandoned): (Vec<Hash>, Vec<Hash>) = settled
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .partition(|hash| chain.contains(hash));
+        let reinjected = self.tx_pool.settle_blocks(&finalized, &abandoned);
+        if !abandoned.is_empty() {
+            tracing::info!(
+                "{} abandoned blocks up to View {}: {} transactions back in the pool",
+                abandoned.len(),
+                view,
+                reinjected
+            );
+        }
+    }
+
+    /// Keep the Finalize aggregate for `block_hash` as its finalization certificate, so
+    /// light clients can be served a finality proof for the block. The aggregate is topped
+    /// up with the individual votes it lacks (or rebuilt from them if it was evicted).
+    fn save_finality_qc(&self, view: View, block_hash: Hash) {
+        if block_hash == Hash::default() {
+            return;
+        }
+        let mut certificate = self
+            .aggregates
+            .get(&(view, block_hash, VoteType::Finalize))
+            .map(|entry| entry.aggregate.clone());
+        let (epoch, committee) = self.vote_committee(view);
+        let votes = self.finalize_votes_received.get(&view);
+        for (index, member) in committee.iter().enumerate() {
+            if let Some(vote) = votes.and_then(|votes| votes.get(member))
+                && vote.block_hash == block_hash
+            {
+                let single = AggregateVote::from_vote(vote, epoch, index);
+                match &mut certificate {
+                    Some(certificate) if !certificate.signers.contains(index) => {
+                        certificate.merge(&single);
                     }
+                    Some(_) => {}
+                    None => certificate = Some(single),
                 }
             }
         }
+
+        let threshold = (committee.len() * 2) / 3 + 1;
+        let Some(certificate) = certificate.filter(|c| c.signers.len() >= threshold) else {
+            tracing::error!("No finality certificate for View {}", view);
+            return;
+        };
+        if let Err(e) = self.storage.save_finality_qc(&certificate.to_qc()) {
+            tracing::error!("Failed to save finality certificate: {:?}", e);
+        }
+    }
+
+    /// Snapshot the validator sets at the start of the current epoch (for `get_validator_set`).
+    fn save_validator_set(&self, view: View) {
+        let Ok(Some(state)) = self.storage.get_consensus_state() else {
+            return;
+        };
+        let saved = ValidatorSet::from_state(self.epoch, view, &state, self.storage.as_ref())
+            .and_then(|set| self.storage.save_validator_set(&set));
+        if let Err(e) = saved {
+            tracing::error!("Failed to save validator set: {:?}", e);
+        }
+    }
+
+    /// Open a hand-over for the epoch started by the committee change at `view`.
+    /// Members of the outgoing committee sign the transition commitment.
+    fn start_committee_transition(
+        &mut self,
+        view: View,
+        block_hash: Hash,
+        old_committee: Vec<PublicKey>,
+    ) -> Vec<ConsensusAction> {
+        self.epoch += 1;
+        self.save_validator_set(view);
+        let transition = CommitteeTransition::new(
+            self.epoch,
+            view,
+            block_hash,
+            &old_committee,
+            self.committee.clone(),
+        );
+        let commitment = transition.commitment();
+        tracing::info!(
+            "Committee Transition to Epoch {} at View {} (Commitment {:?})",
+            self.epoch,
+            view,
+            commitment
+        );
+
+        let mut actions = vec![];
+        if old_committee.contains(&self.my_id) {
+            let vote = self.create_vote(view, commitment, VoteType::Handover);
+            self.handover_votes_received
+                .entry(commitment)
+                .or_default()
+                .insert(self.my_id.clone(), vote.clone());
+            actions.push(ConsensusAction::BroadcastVote(vote));
+        }
+
+        self.pending_transitions
+            .insert(commitment, (transition, old_committee));
+        self.try_certify_transition(commitment);
+        actions
+    }
+
+    fn on_handover_vote(&mut self, vote: Vote) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        // Only the committee handing over at the vote's view signs, once per epoch
+        let (epoch, outgoing) = self.vote_committee(vote.view);
+        if !outgoing.contains(&vote.author) {
+            tracing::warn!("Hand-over vote from non-committee member {:?}", vote.author);
+            return Err(ConsensusError::NotInCommittee);
+        }
+        if matches!(
+            self.storage.get_committee_transition(epoch + 1),
+            Ok(Some(_))
+        ) {
+            return Ok(vec![]);
+        }
+
+        // Votes may arrive before we finalize the block ourselves, so buffer by commitment
+        self.handover_votes_received
+            .entry(vote.block_hash)
+            .or_default()
+            .insert(vote.author.clone(), vote.clone());
+        self.try_certify_transition(vote.block_hash);
         Ok(vec![])
     }
 
+    /// Aggregate the hand-over once 2f+1 of the outgoing committee signed it, and persist it.
+    fn try_certify_transition(&mut self, commitment: Hash) {
+        let (Some((transition, old_committee)), Some(votes)) = (
+            self.pending_transitions.get(&commitment),
+            self.handover_votes_received.get(&commitment),
+        ) else {
+            return;
+        };
+
+        let threshold = (old_committee.len() * 2) / 3 + 1;
+        let (signers, signatures): (Vec<PublicKey>, Vec<_>) = votes
+            .values()
+            .filter(|v| old_committee.contains(&v.author))
+            .map(|v| (v.author.clone(), v.signature.clone()))
+            .unzip();
+        if signers.len() < threshold {
+            return;
+        }
+
+        let Some(signature) = aggregate(&signatures) else {
+            tracing::error!(
+                "Failed to aggregate hand-over signatures for {:?}",
+                commitment
+            );
+            return;
+        };
+        let mut transition = transition.clone();
+        transition.signature = signature;
+        transition.signers = signers;
+
+        if let Err(e) = self.storage.save_committee_transition(&transition) {
+            tracing::error!("Failed to persist committee transition: {:?}", e);
+            return;
+        }
+        tracing::info!(
+            "Committee Transition Certified: Epoch {}, Signers {}",
+            transition.epoch,
+            transition.signers.len()
+        );
+
+        self.pending_transitions.remove(&commitment);
+        // Drop buffered hand-over votes that can no longer complete
+        self.handover_votes_received
+            .retain(|_, votes| votes.values().any(|v| v.view > transition.view));
+    }
+
+    /// The committee of `epoch`: ours, or a stored snapshot.
+    fn committee_of_epoch(&self, epoch: u64) -> Option<Vec<PublicKey>> {
+        if epoch == self.epoch {
+            return Some(self.committee.clone());
+        }
+        let set = self.storage.get_validator_set(epoch).ok().flatten()?;
+        Some(set.committee.into_iter().map(|v| v.public_key).collect())
+    }
+
+    /// The committee that voted in `view`: the one of the last epoch started before it
+    /// (the block changing the committee is still certified by the outgoing one).
+    pub fn committee_at(&self, view: View) -> Option<Vec<PublicKey>> {
+        self.epoch_committee_at(view)
+            .map(|(_, committee)| committee)
+    }
+
+    fn epoch_committee_at(&self, view: View) -> Option<(u64, Vec<PublicKey>)> {
+        let set = self
+            .storage
+            .get_validator_set_at(view.saturating_sub(1))
+            .ok()
+            .flatten()?;
+        let committee = set.committee.into_iter().map(|v| v.public_key).collect();
+        Some((set.epoch, committee))
+    }
+
+    /// The committee a block of `view` commits to (`Block::committee_hash`): the one
+    /// certifying it, from the stored snapshots (ours if there is none).
+    fn block_committee(&self, view: View) -> Vec<PublicKey> {
+        self.committee_at(view)
+            .unwrap_or_else(|| self.committee.clone())
+    }
+
+    /// Epoch and committee whose votes certify `view`, as `block_committee`: aggregate
+    /// signer bits index this committee.
+    fn vote_committee(&self, view: View) -> (u64, Vec<PublicKey>) {
+        self.epoch_committee_at(view)
+            .unwrap_or_else(|| (self.epoch, self.committee.clone()))
+    }
+
+    /// Votes (2f+1) needed for a quorum in `view`.
+    fn quorum_at(&self, view: View) -> usize {
+        (self.block_committee(view).len() * 2) / 3 + 1
+    }
+
     fn verify_qc(&self, qc: &QuorumCertificate) -> Result<(), ConsensusError> {
         if qc.view == 0 {
             return Ok(());
         }
-        if !verify_aggregate(&qc.signers, &qc.block_hash.0, &qc.signature) {
+        // Signers index the committee of the QC's epoch. Certificates of historical blocks
+        // (e.g. during sync) may predate the epoch field, or their epoch snapshot may be
+        // missing: those resolve the committee from the QC's view instead.
+        let verified = self
+            .committee_of_epoch(qc.epoch)
+            .is_some_and(|committee| qc.verify_signature(&committee, self.chain_id))
+            || self
+                .committee_at(qc.view)
+                .is_some_and(|committee| qc.verify_signature(&committee, self.chain_id));
+        if !verified {
             return Err(ConsensusError::InvalidQC);
         }
         Ok(())
@@ -839,44 +2421,209 @@ impl SimplexState {
         }
     }
 
-    fn persist_state(&self) {
-        // Read-Modify-Write to preserve pending/exiting/stakes which we don't track in memory
-        let mut state = self
+    /// Record `block_hash` as the committed chain head, at the engine's committed root.
+    fn save_chain_head(&self, view: View, block_hash: Hash) {
+        let head = ChainHead {
+            view,
+            block_hash,
+            state_root: self.engine.state_root(),
+        };
+        if let Err(e) = self.storage.save_chain_head(&head) {
+            tracing::error!("Failed to persist chain head: {:?}", e);
+        }
+    }
+
+    /// Crash recovery, run once after `new` and before rejoining consensus.
+    ///
+    /// Catches up with the votes journaled after the consensus state was last saved, restarts
+    /// the execution engine from the committed chain head, commits the finalized blocks
+    /// whose commit was interrupted, then re-executes the stored blocks above the finalized
+    /// one and cuts the preferred chain back to the last block that still validates.
+    pub fn recover(&mut self) -> Result<RecoveryReport, ConsensusError> {
+        let mut report = RecoveryReport::default();
+
+        // 0. Votes sent before the crash (`journaled_vote` checks the journal again per vote)
+        let records = self
             .storage
-            .get_consensus_state()
-            .unwrap()
-            .unwrap_or_else(|| {
-                let mut stakes = HashMap::new();
-                for pk in &self.committee {
-                    let pk_bytes = pk.0.to_bytes();
-                    let hash = crate::types::keccak256(pk_bytes);
-                    let address = crate::types::Address::from_slice(&hash[12..]);
-                    stakes.insert(address, crate::types::U256::from(5000u64));
-                }
-                ConsensusState {
-                    view: self.current_view,
-                    finalized_height: self.finalized_height,
-                    preferred_block: self.preferred_block,
-                    preferred_view: self.preferred_view,
-                    last_voted_view: self.last_voted_view,
-                    committee: self.committee.clone(),
-                    pending_validators: vec![],
-                    exiting_validators: vec![],
-                    stakes,
-                    inactivity_scores: HashMap::new(),
-                }
+            .get_vote_records(self.last_voted_view + 1)
+            .unwrap_or_else(|e| {
+                tracing::warn!("Recovery: failed to read the vote journal: {:?}", e);
+                vec![]
             });
+        let journaled = records
+            .iter()
+            .filter(|r| r.vote_type == VoteType::Notarize && r.block_hash != Hash::default())
+            .map(|r| r.view)
+            .max();
+        if let Some(view) = journaled {
+            tracing::info!("Recovery: vote journal has a vote for view {}", view);
+            self.last_voted_view = view;
+            self.persist_state();
+        }
+
+        let mut head = self.storage.get_chain_head().ok().flatten();
+
+        // 1. Committed State
+        if let Some(head) = &head {
+            self.engine.fork_choice_updated(head);
+        }
+
+        // 2. Replay Finalized Blocks above the Head
+        let head_view = head.as_ref().map_or(0, |h| h.view);
+        let finalized = (head_view + 1..=self.finalized_height)
+            .rev()
+            .filter_map(|view| self.storage.get_qc(view).ok().flatten())
+            .find(|qc| qc.block_hash != Hash::default());
+        if let Some(qc) = finalized {
+            if head.is_none() {
+                // Committed before chain heads were recorded: trust the finalized block's root
+                tracing::warn!(
+                    "No chain head stored. Adopting finalized block at view {}",
+                    qc.view
+                );
+                let block = self.get_stored_block(&qc.block_hash)?;
+                let adopted = ChainHead {
+                    view: block.view,
+                    block_hash: qc.block_hash,
+                    state_root: block.state_root,
+                };
+                self.engine.fork_choice_updated(&adopted);
+                self.storage.save_chain_head(&adopted).ok();
+                head = Some(adopted);
+            } else {
+                let head_hash = head.as_ref().map(|h| h.block_hash);
+                for (hash, mut block) in self.chain_between(qc.block_hash, head_hash, head_view)? {
+                    if block.is_dummy {
+                        continue;
+                    }
+                    tracing::info!(
+                        "Recovery: committing finalized block at view {}",
+                        block.view
+                    );
+                    self.engine.commit(&mut block).map_err(|e| {
+                        tracing::error!("Recovery: failed to commit view {}: {:?}", block.view, e);
+                        ConsensusError::InvalidBlock
+                    })?;
+                    self.save_chain_head(block.view, hash);
+                    report.replayed.push(block.view);
+                }
+                head = self.storage.get_chain_head().ok().flatten();
+            }
+        }
+
+        // 3. Re-validate Unfinalized Blocks (preferred chain above the head)
+        let head_view = head.as_ref().map_or(0, |h| h.view);
+        let head_hash = head.as_ref().map(|h| h.block_hash);
+        let mut last_valid: Option<(Hash, View)> = None;
+        let mut invalid = false;
+        for (hash, block) in self.chain_between(self.preferred_block, head_hash, head_view)? {
+            if invalid {
+                report.discarded.push(block.view);
+                continue;
+            }
+            if !block.is_dummy
+                && let Err(e) = self.verify_execution(&block)
+            {
+                tracing::warn!(
+                    "Recovery: stored block at view {} is invalid: {:?}",
+                    block.view,
+                    e
+                );
+                invalid = true;
+                report.discarded.push(block.view);
+                continue;
+            }
+            report.revalidated.push(block.view);
+            last_valid = Some((hash, block.view));
+        }
+        if invalid {
+            let (hash, view) = last_valid
+                .or(head.as_ref().map(|h| (h.block_hash, h.view)))
+                .unwrap_or((self.preferred_block, self.preferred_view));
+            self.preferred_block = hash;
+            self.preferred_view = view;
+            self.persist_state();
+        }
+
+        report.head = head;
+        tracing::info!("Recovery complete: {:?}", report);
+        Ok(report)
+    }
+
+    fn get_stored_block(&self, hash: &Hash) -> Result<Block, ConsensusError> {
+        self.storage
+            .get_block(hash)
+            .ok()
+            .flatten()
+            .ok_or(ConsensusError::MissingBlock(*hash))
+    }
+
+    /// The stored chain ending at `tip`, back to (excluding) `base` or the first block at or
+    /// below `base_view`, in ascending order.
+    fn chain_between(
+        &self,
+        tip: Hash,
+        base: Option<Hash>,
+        base_view: View,
+    ) -> Result<Vec<(Hash, Block)>, ConsensusError> {
+        let mut chain = Vec::new();
+        let mut hash = tip;
+        while Some(hash) != base && hash != Hash::default() {
+            let block = self.get_stored_block(&hash)?;
+            if block.view <= base_view {
+                break;
+            }
+            let parent = block.parent_hash;
+            chain.push((hash, block));
+            hash = parent;
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// Move to `view` (the current one or a later one) and persist it.
+    fn enter_view(&mut self, view: View) {
+        if view > self.current_view {
+            self.emit(ConsensusEvent::ViewStarted { view });
+        }
+        self.current_view = view;
+        self.persist_state();
+    }
 
-        // Update fields we manage
-        state.view = self.current_view;
-        state.finalized_height = self.finalized_height;
-        state.preferred_block = self.preferred_block;
-        state.preferred_view = self.preferred_view;
-        state.last_voted_view = self.last_voted_view;
-        state.committee = self.committee.clone();
+    fn emit(&self, event: ConsensusEvent) {
+        if let Some(events) = &self.events {
+            events.emit(event);
+        }
+    }
 
-        if let Err(e) = self.storage.save_consensus_state(&state) {
-            log::error!("Failed to persist state: {:?}", e);
+    fn persist_state(&self) {
+        let head = ConsensusHead {
+            view: self.current_view,
+            finalized_height: self.finalized_height,
+            preferred_block: self.preferred_block,
+            preferred_view: self.preferred_view,
+            last_voted_view: self.last_voted_view,
+        };
+        // Only the head pointers are ours: the committee, scores and queues are written by
+        // execution (the committee is reloaded from storage on commit)
+        let result = match self.storage.get_consensus_head() {
+            Ok(Some(_)) => self.storage.save_consensus_head(&head),
+            Ok(None) => {
+                let mut state = ConsensusState {
+                    committee: self.committee.clone(),
+                    params: ChainParams {
+                        block_gas_limit: self.block_gas_limit,
+                        ..ChainParams::default()
+                    },
+                    ..Default::default()
+                };
+                state.set_head(&head);
+                self.storage.save_consensus_state(&state)
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to persist state: {:?}", e);
         }
     }
 
@@ -887,48 +2634,410 @@ impl SimplexState {
         peer_id: String,
     ) -> Result<Vec<ConsensusAction>, ConsensusError> {
         if let Ok(Some(block)) = self.storage.get_block(&block_hash) {
-            log::info!("Serving Block Request for {:?}", block_hash);
+            tracing::info!("Serving Block Request for {:?}", block_hash);
             return Ok(vec![ConsensusAction::SendBlock(block, peer_id)]);
         }
         Ok(vec![])
     }
 
+    /// Our `SyncMessage::Status`: the views, the preferred block and the highest stored
+    /// certificate.
+    pub fn status(&self) -> SyncMessage {
+        let high_qc = (self.preferred_view..self.current_view.max(self.preferred_view + 1))
+            .rev()
+            .find_map(|view| self.storage.get_qc(view).ok().flatten())
+            .unwrap_or_else(|| genesis_block().justify);
+        SyncMessage::Status {
+            current_view: self.current_view,
+            finalized_height: self.finalized_height,
+            head_hash: self.preferred_block,
+            high_qc,
+        }
+    }
+
+    /// Catch up with a peer's `SyncMessage::Status`. The claimed views only make us fetch
+    /// the peer's head (its ancestors follow as orphans); the pacemaker jumps only on a
+    /// quorum certificate of the current committee.
+    pub fn on_status(
+        &mut self,
+        current_view: View,
+        finalized_height: View,
+        head_hash: Hash,
+        high_qc: QuorumCertificate,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        let mut actions = vec![];
+        let missing = |hash: &Hash| {
+            *hash != Hash::default() && matches!(self.storage.get_block(hash), Ok(None))
+        };
+        if (current_view > self.current_view || finalized_height > self.finalized_height)
+            && missing(&head_hash)
+        {
+            tracing::info!(
+                "Peer is at View {} (Finalized {}). Requesting its head {:?}",
+                current_view,
+                finalized_height,
+                head_hash
+            );
+            actions.push(ConsensusAction::BroadcastRequest(head_hash));
+        }
+
+        if high_qc.view < self.current_view || high_qc.view == 0 {
+            return Ok(actions);
+        }
+        let (epoch, committee) = self.vote_committee(high_qc.view);
+        if high_qc.epoch != epoch
+            || high_qc.signers.len() < (committee.len() * 2) / 3 + 1
+            || !high_qc.verify_signature(&committee, self.chain_id)
+        {
+            tracing::warn!("Ignoring invalid certificate in peer status");
+            return Ok(actions);
+        }
+        tracing::info!(
+            "Fast-forwarding from View {} to View {} (certified by peer status)",
+            self.current_view,
+            high_qc.view + 1
+        );
+        self.enter_view(high_qc.view + 1);
+        let requested = matches!(actions.first(), Some(ConsensusAction::BroadcastRequest(hash)) if *hash == high_qc.block_hash);
+        if missing(&high_qc.block_hash) && !requested {
+            actions.push(ConsensusAction::BroadcastRequest(high_qc.block_hash));
+        }
+        Ok(actions)
+    }
+
+    /// Store the state witness of `block_hash` received from a peer (checked when used).
+    pub fn on_witness(&self, block_hash: Hash, witness: StateWitness) {
+        if let Err(e) = self.storage.save_witness(&block_hash, &witness) {
+            tracing::error!("Failed to save state witness: {:?}", e);
+        }
+    }
+
     /// Handle a Block Response (Synced Block).
     pub fn on_block_response(
         &mut self,
         block: Block,
     ) -> Result<Vec<ConsensusAction>, ConsensusError> {
-        log::info!("Received Synced Block View {}", block.view);
+        self.process_block_response(block, None)
+    }
+
+    /// Handle a Block Response sent by `peer_id` (charged against its orphan quota).
+    pub fn on_block_response_from(
+        &mut self,
+        block: Block,
+        peer_id: String,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        self.process_block_response(block, Some(&peer_id))
+    }
+
+    #[tracing::instrument(
+        name = "view",
+        skip_all,
+        fields(view = self.current_view, block_view = block.view)
+    )]
+    fn process_block_response(
+        &mut self,
+        block: Block,
+        peer: Option<&str>,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
+        tracing::info!("Received Synced Block View {}", block.view);
 
         // Use shared validation logic (allows old blocks!)
-        let (stored, mut actions) = self.validate_and_store_block(block.clone())?;
+        if self.async_validation {
+            return self.queue_validation(block, peer, true);
+        }
+        let (stored, actions) = self.validate_and_store_block(block.clone(), peer)?;
 
         if !stored {
             // It was an orphan, request sent via actions
             return Ok(actions);
         }
+        self.finish_block_response(block, actions)
+    }
 
+    /// Catch up on a stored synced block (the steps of `process_block_response` after
+    /// validation).
+    fn finish_block_response(
+        &mut self,
+        block: Block,
+        mut actions: Vec<ConsensusAction>,
+    ) -> Result<Vec<ConsensusAction>, ConsensusError> {
         // Fast-forward view if we synced a newer block
         if block.view >= self.current_view {
-            self.current_view = block.view;
-            self.persist_state();
+            self.enter_view(block.view);
         }
 
         // Check if this block fills any gaps (is a parent for orphans)
-        let block_hash = hash_data(&block);
+        actions.extend(self.process_orphans_of(hash_data(&block)));
+        Ok(actions)
+    }
+
+    /// Re-process the orphans waiting for `block_hash`, now that it is stored.
+    fn process_orphans_of(&mut self, block_hash: Hash) -> Vec<ConsensusAction> {
+        let mut actions = vec![];
         if let Some(orphans) = self.orphans.remove(&block_hash) {
-            log::info!(
+            self.enforce_orphan_budget();
+            tracing::info!(
                 "Processed Orphan Parent. Re-processing {} orphans...",
                 orphans.len()
             );
             for orphan in orphans {
                 // Recursively process orphans
-                if let Ok(orphan_actions) = self.on_block_response(orphan) {
+                if let Ok(orphan_actions) =
+                    self.process_block_response(orphan.block, orphan.peer.as_deref())
+                {
                     actions.extend(orphan_actions);
                 }
             }
         }
 
-        Ok(actions)
+        actions
+    }
+
+    /// Drop votes and orphans for views below the finalized height; they can no longer
+    /// affect consensus.
+    fn prune_finalized(&mut self) {
+        let height = self.finalized_height;
+        self.votes_received.retain(|view, _| *view >= height);
+        self.finalize_votes_received
+            .retain(|view, _| *view >= height);
+        self.aggregates.retain(|(view, _, _), _| *view >= height);
+        let window = self.chain_params().decryption_window;
+        self.ciphertexts
+            .retain(|_, (view, _)| view.saturating_add(window) >= height);
+        self.decryption_shares
+            .retain(|_, (view, _)| view.saturating_add(window) >= height);
+        for orphans in self.orphans.values_mut() {
+            orphans.retain(|o| o.block.view >= height);
+        }
+        self.orphans.retain(|_, orphans| !orphans.is_empty());
+        self.expire_orphans();
+        self.enforce_vote_budget();
+        if let Err(e) = self.storage.prune_vote_records(height) {
+            tracing::warn!("Failed to prune the vote journal: {:?}", e);
+        }
+    }
+
+    /// Orphan buffer size and drop counters.
+    pub fn orphan_metrics(&self) -> OrphanMetrics {
+        let orphans = self.orphans.values().flatten();
+        OrphanMetrics {
+            count: orphans.clone().count(),
+            bytes: orphans.map(|o| block_size(&o.block)).sum(),
+            ..self.orphan_metrics.clone()
+        }
+    }
+
+    /// Buffer `block` until its parent arrives. Returns false if it was refused: its peer
+    /// is over quota, or the buffer is full of orphans closer to connecting (lower views).
+    fn buffer_orphan(&mut self, block: Block, peer: Option<&str>) -> bool {
+        self.expire_orphans();
+        let parent = block.parent_hash;
+        let block_hash = hash_data(&block);
+        if self
+            .orphans
+            .get(&parent)
+            .is_some_and(|os| os.iter().any(|o| hash_data(&o.block) == block_hash))
+        {
+            return true; // Already buffered (redelivery)
+        }
+
+        if let Some(peer) = peer {
+            let from_peer = self
+                .orphans
+                .values()
+                .flatten()
+                .filter(|o| o.peer.as_deref() == Some(peer))
+                .count();
+            if from_peer >= self.orphan_config.max_per_peer {
+                tracing::warn!("Orphan quota exceeded by peer {}", peer);
+                self.orphan_metrics.rejected += 1;
+                return false;
+            }
+        }
+
+        let count: usize = self.orphans.values().map(|os| os.len()).sum();
+        if count >= self.orphan_config.max_orphans {
+            let highest = self
+                .orphans
+                .iter()
+                .flat_map(|(p, os)| {
+                    os.iter()
+                        .enumerate()
+                        .map(move |(i, o)| (*p, i, o.block.view))
+                })
+                .max_by_key(|(_, _, view)| *view);
+            match highest {
+                Some((p, i, view)) if view > block.view => {
+                    self.remove_orphan(&p, i);
+                    self.orphan_metrics.evicted += 1;
+                }
+                _ => {
+                    tracing::warn!("Orphan buffer full: dropping block for view {}", block.view);
+                    self.orphan_metrics.rejected += 1;
+                    return false;
+                }
+            }
+        }
+
+        self.orphans.entry(parent).or_default().push(Orphan {
+            block,
+            peer: peer.map(str::to_string),
+            received_view: self.current_view,
+        });
+        self.enforce_orphan_budget();
+        true
+    }
+
+    fn remove_orphan(&mut self, parent: &Hash, index: usize) {
+        if let Some(orphans) = self.orphans.get_mut(parent) {
+            orphans.remove(index);
+            if orphans.is_empty() {
+                self.orphans.remove(parent);
+            }
+        }
+    }
+
+    /// Drop orphans that have waited more than `expiry_views` views for their parent.
+    fn expire_orphans(&mut self) {
+        let expiry = self.orphan_config.expiry_views;
+        let current_view = self.current_view;
+        let mut expired = 0;
+        for orphans in self.orphans.values_mut() {
+            let before = orphans.len();
+            orphans.retain(|o| o.received_view.saturating_add(expiry) >= current_view);
+            expired += before - orphans.len();
+        }
+        if expired > 0 {
+            self.orphans.retain(|_, orphans| !orphans.is_empty());
+            self.orphan_metrics.expired += expired as u64;
+            tracing::info!("Expired {} orphan blocks", expired);
+        }
+        self.enforce_orphan_budget();
+    }
+
+    /// Report the orphan buffer usage and drop the highest-view orphans if over budget
+    /// (they are the furthest from connecting and will be re-requested).
+    fn enforce_orphan_budget(&mut self) {
+        let Some(memory) = &self.orphan_memory else {
+            return;
+        };
+        let usage: usize = self
+            .orphans
+            .values()
+            .flatten()
+            .map(|o| block_size(&o.block))
+            .sum();
+        memory.set_usage(usage);
+
+        let excess = memory.excess();
+        if excess == 0 {
+            return;
+        }
+        let mut orphans: Vec<(Hash, Orphan)> = self
+            .orphans
+            .drain()
+            .flat_map(|(parent, orphans)| orphans.into_iter().map(move |o| (parent, o)))
+            .collect();
+        orphans.sort_by_key(|(_, o)| o.block.view);
+
+        let mut freed = 0;
+        while freed < excess
+            && let Some((_, orphan)) = orphans.pop()
+        {
+            freed += block_size(&orphan.block);
+            self.orphan_metrics.evicted += 1;
+        }
+        for (parent, orphan) in orphans {
+            self.orphans.entry(parent).or_default().push(orphan);
+        }
+        memory.set_usage(usage.saturating_sub(freed));
+        tracing::warn!("Orphan buffer over memory budget: evicted {} bytes", freed);
+    }
+
+    /// Report the vote maps usage and drop the oldest views if over budget, after the
+    /// hand-over votes buffered for transitions we have not reached (they can be re-sent).
+    /// Votes for the current view and later are kept so that liveness is unaffected.
+    fn enforce_vote_budget(&mut self) {
+        let Some(memory) = &self.vote_memory else {
+            return;
+        };
+        let count = |m: &HashMap<View, HashMap<PublicKey, Vote>>| {
+            m.values().map(|v| v.len()).sum::<usize>()
+        };
+        let handover: usize = self.handover_votes_received.values().map(|v| v.len()).sum();
+        // An aggregate weighs about a vote (one signature, a few bitmap bytes)
+        let usage = (count(&self.votes_received)
+            + count(&self.finalize_votes_received)
+            + self.aggregates.len()
+            + handover)
+            * vote_size();
+        memory.set_usage(usage);
+
+        let excess = memory.excess();
+        if excess == 0 {
+            return;
+        }
+        let mut views: Vec<View> = self
+            .votes_received
+            .keys()
+            .chain(self.finalize_votes_received.keys())
+            .chain(self.aggregates.keys().map(|(view, _, _)| view))
+            .copied()
+            .filter(|v| *v < self.current_view)
+            .collect();
+        views.sort_unstable();
+        views.dedup();
+
+        // Hand-over votes for commitments we have not reached yet go first
+        let mut freed = 0;
+        let pending = &self.pending_transitions;
+        self.handover_votes_received.retain(|commitment, votes| {
+            if freed >= excess || pending.contains_key(commitment) {
+                return true;
+            }
+            freed += votes.len() * vote_size();
+            false
+        });
+        for view in views {
+            if freed >= excess {
+                break;
+            }
+            let notarize = self.votes_received.remove(&view).map_or(0, |v| v.len());
+            let finalize = self
+                .finalize_votes_received
+                .remove(&view)
+                .map_or(0, |v| v.len());
+            let aggregates = self.aggregates.len();
+            self.aggregates.retain(|(v, _, _), _| *v != view);
+            freed += (notarize + finalize + aggregates - self.aggregates.len()) * vote_size();
+        }
+        memory.set_usage(usage.saturating_sub(freed));
+        tracing::warn!("Vote maps over memory budget: evicted {} bytes", freed);
+    }
+
+    /// Report the seen-message caches usage and forget the least recently seen hashes
+    /// if over budget (votes first: a forgotten hash only costs a redundant re-check).
+    fn enforce_seen_budget(&mut self) {
+        let Some(memory) = &self.seen_memory else {
+            return;
+        };
+        let usage = (self.seen_votes.len() + self.seen_blocks.len()) * seen_entry_size();
+        memory.set_usage(usage);
+
+        let excess = memory.excess();
+        if excess == 0 {
+            return;
+        }
+        let mut entries = excess.div_ceil(seen_entry_size());
+        let from_votes = entries.min(self.seen_votes.len());
+        self.seen_votes.evict_oldest(from_votes);
+        entries -= from_votes;
+        self.seen_blocks.evict_oldest(entries);
+        let remaining = (self.seen_votes.len() + self.seen_blocks.len()) * seen_entry_size();
+        memory.set_usage(remaining);
+        tracing::warn!(
+            "Seen-message caches over memory budget: evicted {} bytes",
+            usage - remaining
+        );
     }
 }
@@ -1,5 +1,6 @@
 use blst::min_sig::{
-    AggregateSignature, PublicKey as BlstPublicKey, SecretKey, Signature as BlstSignature,
+    AggregatePublicKey, AggregateSignature, PublicKey as BlstPublicKey, SecretKey,
+    Signature as BlstSignature,
 };
 use rand::RngCore;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -167,7 +168,7 @@ impl Default for Signature {
 
 /// Signs a message (bytes) using the private key.
 /// Domain separation tag (DST) is important for security.
-const DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_";
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_";
 
 pub fn sign(priv_key: &PrivateKey, message: &[u8]) -> Signature {
     Signature(priv_key.0.sign(message, DST, &[]))
@@ -251,6 +252,61 @@ pub fn verify_aggregate(pub_keys: &[PublicKey], message: &[u8], signature: &Sign
     err == blst::BLST_ERROR::BLST_SUCCESS
 }
 
+// -----------------------------------------------------------------------------
+// Encryption to the committee (see `types::EncryptedTransaction`)
+//
+// Boneh-Franklin style, with the tag T as identity: a ciphertext carries an ephemeral
+// key R = r·g2 and is keyed by e(r·H(T), PK), PK being the sum of the committee's keys.
+// A member's decryption share is its BLS signature on T; the aggregate of all shares,
+// S = sk·H(T), yields the same key as e(S, R). Shares verify like signatures.
+// -----------------------------------------------------------------------------
+
+/// Sum of `keys`, the key that ciphertexts for the whole committee are encrypted to.
+pub fn aggregate_public_keys(keys: &[PublicKey]) -> Option<PublicKey> {
+    let refs: Vec<&BlstPublicKey> = keys.iter().map(|pk| &pk.0).collect();
+    let aggregate = AggregatePublicKey::aggregate(&refs, true).ok()?;
+    Some(PublicKey(aggregate.to_public_key()))
+}
+
+/// Symmetric key derived from the pairing e(`point`, `key`) (G1 x G2).
+pub fn pairing_key(point: &Signature, key: &PublicKey) -> Option<Hash> {
+    let mut p = blst::blst_p1_affine::default();
+    let mut q = blst::blst_p2_affine::default();
+    // SAFETY: the buffers are the uncompressed encodings blst expects (96 and 192 bytes)
+    let decoded = unsafe {
+        blst::blst_p1_deserialize(&mut p, point.0.serialize().as_ptr())
+            == blst::BLST_ERROR::BLST_SUCCESS
+            && blst::blst_p2_deserialize(&mut q, key.0.serialize().as_ptr())
+                == blst::BLST_ERROR::BLST_SUCCESS
+    };
+    if !decoded {
+        return None;
+    }
+    let gt = blst::blst_fp12::miller_loop(&q, &p).final_exp();
+    let mut bytes = [0u8; 48 * 12];
+    // SAFETY: `bytes` holds the 12 big-endian field elements of an Fp12
+    unsafe { blst::blst_bendian_from_fp12(bytes.as_mut_ptr(), &gt) };
+    Some(Hash(Sha256::digest(bytes).into()))
+}
+
+/// XOR `data` with the SHA-256 counter-mode keystream of `key` (encrypts and decrypts).
+pub fn apply_keystream(key: &Hash, data: &[u8]) -> Vec<u8> {
+    data.chunks(32)
+        .enumerate()
+        .flat_map(|(counter, chunk)| {
+            let mut hasher = Sha256::new();
+            hasher.update(key.0);
+            hasher.update((counter as u64).to_be_bytes());
+            let block: [u8; 32] = hasher.finalize().into();
+            chunk
+                .iter()
+                .zip(block)
+                .map(|(byte, k)| byte ^ k)
+                .collect::<Vec<u8>>()
+        })
+        .collect()
+}
+
 /// Generate a KeyPair from a u64 ID (deterministic).
 /// Useful for static committees where keys are derived from IDs.
 pub fn generate_keypair_from_id(id: u64) -> (PublicKey, PrivateKey) {
@@ -0,0 +1,178 @@
+//! Local devnet: a whole committee of validators in one process (`ockham devnet`).
+//!
+//! The validators run on a `SimNetwork` driven by the wall clock: one simulated tick per
+//! tick of real time, every message delivered on the next tick, nothing dropped. Each
+//! validator serves the RPC on its own port (`rpc_port + i`), and transactions submitted
+//! to any of them reach every pool, as gossip would. Validator keys come from
+//! `generate_keypair_from_id`, so node 0's funded genesis account is known up front.
+
+use crate::rpc::{OckhamRpcImpl, OckhamRpcServer};
+use crate::testing::{SimConfig, SimNetwork};
+use crate::types::{DEFAULT_BLOCK_GAS_LIMIT, Transaction};
+use jsonrpsee::server::{Server, ServerHandle};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Validators of `ockham devnet` without `--validators`.
+pub const DEFAULT_DEVNET_VALIDATORS: usize = 4;
+
+/// RPC port of the first validator; the others follow it.
+pub const DEFAULT_DEVNET_RPC_PORT: u16 = 8545;
+
+/// Transactions submitted through the RPC waiting to be gossiped.
+const DEVNET_TX_CHANNEL: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct DevnetConfig {
+    pub validators: usize,
+    /// Validator `i` serves the RPC on `rpc_port + i` (0: a free port for each).
+    pub rpc_port: u16,
+    pub rpc_addr: IpAddr,
+    /// Network tick: messages take one tick to arrive.
+    pub tick: Duration,
+    pub view_timeout: Duration,
+}
+
+impl Default for DevnetConfig {
+    fn default() -> Self {
+        Self {
+            validators: DEFAULT_DEVNET_VALIDATORS,
+            rpc_port: DEFAULT_DEVNET_RPC_PORT,
+            rpc_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            tick: Duration::from_millis(100),
+            view_timeout: Duration::from_secs(3),
+        }
+    }
+}
+
+pub struct Devnet {
+    network: SimNetwork,
+    tick: Duration,
+    rpc_addrs: Vec<SocketAddr>,
+    rpc_handles: Vec<ServerHandle>,
+    tx_receiver: mpsc::Receiver<Transaction>,
+}
+
+impl Devnet {
+    /// Start the validators and their RPC servers. Consensus only makes progress while
+    /// `run` or `run_until` drives the network.
+    pub async fn start(config: DevnetConfig) -> std::io::Result<Self> {
+        let tick_ms = config.tick.as_millis().max(1) as u64;
+        let network = SimNetwork::new(SimConfig {
+            nodes: config.validators,
+            min_latency_ms: tick_ms,
+            max_latency_ms: tick_ms,
+            view_timeout_ms: config.view_timeout.as_millis() as u64,
+            tick_ms,
+            wall_clock: true,
+            ..Default::default()
+        });
+
+        let (tx_sender, tx_receiver) = mpsc::channel(DEVNET_TX_CHANNEL);
+        let mut rpc_addrs = Vec::with_capacity(config.validators);
+        let mut rpc_handles = Vec::with_capacity(config.validators);
+        for i in 0..config.validators {
+            let port = match config.rpc_port {
+                0 => 0,
+                base => base + i as u16,
+            };
+            let server = Server::builder()
+                .build(SocketAddr::new(config.rpc_addr, port))
+                .await?;
+            rpc_addrs.push(server.local_addr()?);
+            let rpc = OckhamRpcImpl::new(
+                network.storage(i),
+                network.tx_pool(i).expect("devnet validators start alive"),
+                network.executor(i).expect("devnet validators start alive"),
+                DEFAULT_BLOCK_GAS_LIMIT,
+                tx_sender.clone(),
+            );
+            rpc_handles.push(server.start(rpc.into_rpc()));
+        }
+
+        Ok(Self {
+            network,
+            tick: Duration::from_millis(tick_ms),
+            rpc_addrs,
+            rpc_handles,
+            tx_receiver,
+        })
+    }
+
+    /// RPC address of each validator.
+    pub fn rpc_addrs(&self) -> &[SocketAddr] {
+        &self.rpc_addrs
+    }
+
+    pub fn network(&self) -> &SimNetwork {
+        &self.network
+    }
+
+    /// Drive the network until `shutdown` completes.
+    pub async fn run(&mut self, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        let mut ticker = tokio::time::interval(self.tick);
+        let mut last_finalized = self.network.last_finalized();
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                Some(tx) = self.tx_receiver.recv() => self.gossip(tx),
+                _ = ticker.tick() => {
+                    self.network.step();
+                    if self.network.last_finalized() > last_finalized {
+                        last_finalized = self.network.last_finalized();
+                        tracing::info!(
+                            "Devnet finalized View {} (current View {})",
+                            last_finalized,
+                            self.network.max_view()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drive the network until `done` holds (returns true) or `timeout` has passed
+    /// (returns false).
+    pub async fn run_until(
+        &mut self,
+        timeout: Duration,
+        mut done: impl FnMut(&SimNetwork) -> bool,
+    ) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut ticker = tokio::time::interval(self.tick);
+        while !done(&self.network) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::select! {
+                Some(tx) = self.tx_receiver.recv() => self.gossip(tx),
+                _ = ticker.tick() => self.network.step(),
+            }
+        }
+        true
+    }
+
+    /// Stop the RPC servers.
+    pub async fn stop(self) {
+        for handle in &self.rpc_handles {
+            let _ = handle.stop();
+        }
+        for handle in self.rpc_handles {
+            handle.stopped().await;
+        }
+    }
+
+    /// Hand a transaction submitted through one validator's RPC to every pool.
+    fn gossip(&mut self, tx: Transaction) {
+        for i in 0..self.network.len() {
+            if let Some(pool) = self.network.tx_pool(i)
+                && let Err(e) = pool.add_transaction(tx.clone())
+            {
+                tracing::debug!("Devnet validator {} did not take a transaction: {:?}", i, e);
+            }
+        }
+    }
+}
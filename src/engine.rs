@@ -0,0 +1,338 @@
+//! Interface between the consensus driver and the execution layer.
+//!
+//! `SimplexState` only talks to an `ExecutionEngine`: it asks it to execute proposals and
+//! incoming blocks on top of their parent state without persisting anything, to commit
+//! finalized blocks, and to move its committed state when the chain head changes (crash
+//! recovery). `Executor` (revm and the WASM engine over a `StateManager`) is the built-in
+//! engine; other backends, such as an external execution client, plug in here.
+//!
+//! A block is executed when it is validated and again when it is finalized, possibly more
+//! than once if it arrives both as a proposal and through sync. `Executor` keeps the
+//! outcome of each validation in an `ExecutionCache`, answers repeated validations from it
+//! and, when the committed state has not moved since, commits the cached writes instead of
+//! executing the block a second time.
+
+use crate::crypto::{Hash, hash_data};
+use crate::lru::Lru;
+use crate::state::{StateDiff, StateManager, StateWitness};
+use crate::storage::{BLOCK_HASH_HISTORY, ChainHead, StateOverlay, Storage, StorageError};
+use crate::types::{Block, Bloom, Receipt};
+use crate::vm::{ExecutionError, Executor};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Validated blocks whose execution outcome `Executor` keeps when no size is configured.
+pub const DEFAULT_EXECUTION_CACHE_ENTRIES: usize = 64;
+
+pub trait ExecutionEngine: Send + Sync {
+    /// Execute `block` on the state at `parent_root`, filling in its state root, receipts
+    /// root and gas used. Nothing is persisted. With a `witness` (already checked against
+    /// `parent_root`), execute on it alone; otherwise on our own state, returning the
+    /// witness of what was read.
+    fn execute_and_validate(
+        &self,
+        block: &mut Block,
+        parent_root: Hash,
+        witness: Option<&StateWitness>,
+    ) -> Result<Option<StateWitness>, ExecutionError>;
+
+    /// Execute our own proposal like `execute_and_validate`, cutting the payload
+    /// transactions that have not executed by `deadline`. Engines that cannot stop early
+    /// execute the whole payload.
+    fn execute_proposal(
+        &self,
+        block: &mut Block,
+        parent_root: Hash,
+        _deadline: Option<Instant>,
+    ) -> Result<(), ExecutionError> {
+        self.execute_and_validate(block, parent_root, None)
+            .map(|_| ())
+    }
+
+    /// Execute a finalized `block` on the committed state and persist the result. Returns
+    /// the new committed state root.
+    fn commit(&self, block: &mut Block) -> Result<Hash, ExecutionError>;
+
+    /// The chain head moved to `head` outside of `commit` (recovery, an adopted head):
+    /// continue from its state.
+    fn fork_choice_updated(&self, head: &ChainHead);
+
+    /// Root of the committed state.
+    fn state_root(&self) -> Hash;
+}
+
+/// What executing a block produced.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionOutcome {
+    pub state_diff: StateDiff,
+    pub receipts: Vec<Receipt>,
+    pub gas_used: u64,
+}
+
+/// Execution cache size and hit counts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionCacheMetrics {
+    pub entries: usize,
+    /// Validations answered without executing.
+    pub validation_hits: u64,
+    /// Commits that wrote a cached outcome instead of executing.
+    pub commit_hits: u64,
+    /// Commits that had to execute (never validated, evicted, or the state moved since).
+    pub commit_misses: u64,
+    pub evicted: u64,
+}
+
+// A block executed on our own state, and everything needed to commit it without
+// executing it again
+struct CachedExecution {
+    outcome: ExecutionOutcome,
+    parent_root: Hash,
+    state_root: Hash,
+    receipts_root: Hash,
+    logs_bloom: Bloom,
+    // Committed chain head when the block was executed: the writes below are only valid
+    // on top of the committed state of that moment
+    base: Option<Hash>,
+    writes: Arc<StateOverlay>,
+}
+
+impl CachedExecution {
+    // Fill in what execution computes, as executing `block` would
+    fn apply_to(&self, block: &mut Block) {
+        block.state_root = self.state_root;
+        block.receipts_root = self.receipts_root;
+        block.logs_bloom = self.logs_bloom;
+        block.gas_used = self.outcome.gas_used;
+    }
+}
+
+/// LRU cache of execution outcomes keyed by block hash, see the module docs.
+pub struct ExecutionCache {
+    inner: Mutex<ExecutionCacheInner>,
+}
+
+struct ExecutionCacheInner {
+    capacity: usize,
+    entries: Lru<Hash, Arc<CachedExecution>>,
+    metrics: ExecutionCacheMetrics,
+}
+
+impl ExecutionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(ExecutionCacheInner {
+                capacity,
+                entries: Lru::new(),
+                metrics: ExecutionCacheMetrics::default(),
+            }),
+        }
+    }
+
+    pub fn metrics(&self) -> ExecutionCacheMetrics {
+        let inner = self.inner.lock().unwrap();
+        ExecutionCacheMetrics {
+            entries: inner.entries.len(),
+            ..inner.metrics.clone()
+        }
+    }
+
+    /// Outcome of the execution of `block_hash`, if it is cached.
+    pub fn outcome(&self, block_hash: &Hash) -> Option<ExecutionOutcome> {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .peek(block_hash)
+            .map(|cached| cached.outcome.clone())
+    }
+
+    fn get(&self, block_hash: &Hash) -> Option<Arc<CachedExecution>> {
+        self.inner.lock().unwrap().entries.get(block_hash).cloned()
+    }
+
+    fn insert(&self, block_hash: Hash, cached: CachedExecution) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return;
+        }
+        inner.entries.insert(block_hash, Arc::new(cached));
+        while inner.entries.len() > inner.capacity && inner.entries.pop_oldest().is_some() {
+            inner.metrics.evicted += 1;
+        }
+    }
+
+    fn remove(&self, block_hash: &Hash) -> Option<Arc<CachedExecution>> {
+        self.inner.lock().unwrap().entries.remove(block_hash)
+    }
+
+    fn record(&self, f: impl FnOnce(&mut ExecutionCacheMetrics)) {
+        f(&mut self.inner.lock().unwrap().metrics);
+    }
+}
+
+impl ExecutionEngine for Executor {
+    fn execute_and_validate(
+        &self,
+        block: &mut Block,
+        parent_root: Hash,
+        witness: Option<&StateWitness>,
+    ) -> Result<Option<StateWitness>, ExecutionError> {
+        // Seen before (e.g. as a proposal and again through sync): its witness, if any,
+        // was kept the first time
+        if witness.is_none()
+            && let Some(cached) = self
+                .execution_cache
+                .get(&hash_data(&*block))
+                .filter(|cached| cached.parent_root == parent_root)
+        {
+            cached.apply_to(block);
+            self.execution_cache.record(|m| m.validation_hits += 1);
+            return Ok(None);
+        }
+
+        let (state, recording, base) = {
+            let committed = self.state.lock().unwrap();
+            match witness {
+                Some(witness) => {
+                    let witness_storage = Arc::new(witness.to_storage());
+                    if let Ok(Some(consensus_state)) = committed.get_consensus_state() {
+                        let _ = witness_storage.save_consensus_state(&consensus_state);
+                    }
+                    copy_ancestor_hashes(&*committed.backing_storage(), &*witness_storage, block)
+                        .map_err(|e| ExecutionError::State(e.to_string()))?;
+                    // Same overlay semantics as execution on our own state
+                    let overlay = Arc::new(StateOverlay::new(witness_storage));
+                    (StateManager::new(overlay, Some(parent_root)), None, None)
+                }
+                None => {
+                    // Read through the backing storage of our state (and its cache, if any)
+                    let storage = committed.backing_storage();
+                    let base = storage
+                        .get_chain_head()
+                        .map_err(|e| ExecutionError::State(e.to_string()))?
+                        .map(|head| head.block_hash);
+                    let overlay = Arc::new(StateOverlay::recording(storage));
+                    let state = committed.fork(parent_root, overlay.clone());
+                    state.record_diff();
+                    (state, Some(overlay), base)
+                }
+            }
+        };
+
+        let state = Arc::new(Mutex::new(state));
+        self.with_state(state.clone()).execute_block(block)?;
+        let Some(overlay) = recording else {
+            return Ok(None);
+        };
+
+        let block_hash = hash_data(&*block);
+        let receipts = overlay
+            .get_receipts(&block_hash)
+            .map_err(|e| ExecutionError::State(e.to_string()))?
+            .unwrap_or_default();
+        let state_diff = state.lock().unwrap().take_diff().unwrap_or_default();
+        self.execution_cache.insert(
+            block_hash,
+            CachedExecution {
+                outcome: ExecutionOutcome {
+                    state_diff,
+                    receipts,
+                    gas_used: block.gas_used,
+                },
+                parent_root,
+                state_root: block.state_root,
+                receipts_root: block.receipts_root,
+                logs_bloom: block.logs_bloom,
+                base,
+                writes: overlay.clone(),
+            },
+        );
+        Ok(Some(overlay.witness()))
+    }
+
+    fn execute_proposal(
+        &self,
+        block: &mut Block,
+        parent_root: Hash,
+        deadline: Option<Instant>,
+    ) -> Result<(), ExecutionError> {
+        let state = {
+            let committed = self.state.lock().unwrap();
+            let overlay = Arc::new(StateOverlay::new(committed.backing_storage()));
+            committed.fork(parent_root, overlay)
+        };
+        self.with_state(Arc::new(Mutex::new(state)))
+            .execute_block_until(block, deadline)
+    }
+
+    fn commit(&self, block: &mut Block) -> Result<Hash, ExecutionError> {
+        let block_hash = hash_data(&*block);
+        if let Some(cached) = self.execution_cache.remove(&block_hash) {
+            let mut state = self.state.lock().unwrap();
+            let storage = state.backing_storage();
+            let head = storage
+                .get_chain_head()
+                .map_err(|e| ExecutionError::State(e.to_string()))?
+                .map(|head| head.block_hash);
+            // Executed on exactly the state we are about to commit on
+            if cached.parent_root == state.root() && cached.base == head {
+                cached
+                    .writes
+                    .flush()
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
+                state.reset(cached.state_root);
+                cached.apply_to(block);
+                if let Err(e) = storage.save_state_diff(&block_hash, &cached.outcome.state_diff) {
+                    tracing::error!("Failed to save state diff: {:?}", e);
+                }
+                self.execution_cache.record(|m| m.commit_hits += 1);
+                return Ok(state.root());
+            }
+        }
+        self.execution_cache.record(|m| m.commit_misses += 1);
+
+        self.state.lock().unwrap().record_diff();
+        let executed = self.execute_block(block);
+        let state = self.state.lock().unwrap();
+        let diff = state.take_diff();
+        executed?;
+        // Kept for `get_state_diff`
+        if let Some(diff) = diff
+            && let Err(e) = state
+                .backing_storage()
+                .save_state_diff(&crate::crypto::hash_data(block), &diff)
+        {
+            tracing::error!("Failed to save state diff: {:?}", e);
+        }
+        Ok(state.root())
+    }
+
+    fn fork_choice_updated(&self, head: &ChainHead) {
+        self.state.lock().unwrap().reset(head.state_root);
+    }
+
+    fn state_root(&self) -> Hash {
+        self.state.lock().unwrap().root()
+    }
+}
+
+/// Index the hashes of `block`'s ancestors (those `BLOCKHASH` can read) from our own chain
+/// into the witness store `target`, rather than trusting the hashes a witness carries.
+fn copy_ancestor_hashes(
+    chain: &dyn Storage,
+    target: &dyn Storage,
+    block: &Block,
+) -> Result<(), StorageError> {
+    let mut current_hash = block.parent_hash;
+    for _ in 0..BLOCK_HASH_HISTORY {
+        let Some(ancestor) = chain.get_header(&current_hash)? else {
+            break;
+        };
+        target.save_block_hash(ancestor.height, &current_hash)?;
+        if ancestor.height == 0 {
+            break;
+        }
+        current_hash = ancestor.parent_hash;
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Structured consensus events for external monitoring.
+//!
+//! `SimplexState` emits a `ConsensusEvent` at each step of the protocol (see
+//! `SimplexState::with_events`) on an `EventStream`, a broadcast channel: the RPC
+//! serves it to WebSocket subscribers (`subscribe_consensus_events`), and `write_jsonl`
+//! appends it to a file, one JSON record per line. Dashboards can then follow views,
+//! certificates and finality without parsing the logs.
+//!
+//! Emitting never blocks consensus: without receivers events are dropped, and receivers
+//! that fall more than the channel capacity behind skip the oldest events.
+
+use crate::crypto::{Hash, PublicKey};
+use crate::types::View;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+
+/// Events buffered for each receiver before the slowest start skipping.
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    /// Two Notarize votes for different blocks in one view.
+    DoubleVote,
+    /// Two proposals for one view by its leader.
+    DoubleProposal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConsensusEvent {
+    /// We moved to `view` (a QC for the previous view, a proposal, or a peer's certificate).
+    ViewStarted { view: View },
+    /// A new signed proposal (or dummy block) passed the signature check.
+    ProposalReceived {
+        view: View,
+        height: u64,
+        block_hash: Hash,
+        author: PublicKey,
+    },
+    /// A quorum of Notarize votes for `block_hash` (the zero hash: the dummy block).
+    QcFormed {
+        view: View,
+        block_hash: Hash,
+        signers: usize,
+    },
+    /// A quorum of Finalize votes committed `block_hash`.
+    Finalized { view: View, block_hash: Hash },
+    /// The view timer fired: we vote for the dummy block of `view`.
+    TimeoutTriggered { view: View },
+    /// `offender` equivocated in `view`; the evidence went to our pool.
+    EvidenceDetected {
+        view: View,
+        offender: PublicKey,
+        kind: EvidenceKind,
+    },
+}
+
+/// An event with the time it was emitted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// Unix time in milliseconds.
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub event: ConsensusEvent,
+}
+
+/// Sending side of the consensus events, cloned into whatever emits or serves them.
+#[derive(Clone, Debug)]
+pub struct EventStream {
+    sender: broadcast::Sender<EventRecord>,
+}
+
+impl EventStream {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Timestamp `event` and hand it to the current receivers, if any.
+    pub fn emit(&self, event: ConsensusEvent) {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let _ = self.sender.send(EventRecord {
+            timestamp_ms,
+            event,
+        });
+    }
+
+    /// Receive the events emitted from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<EventRecord> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventStream {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+/// Append the events of `receiver` to the file at `path` as JSON lines, until every
+/// `EventStream` is dropped. Events missed by falling behind are logged and skipped.
+pub async fn write_jsonl(
+    mut receiver: broadcast::Receiver<EventRecord>,
+    path: impl AsRef<Path>,
+) -> std::io::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    loop {
+        match receiver.recv().await {
+            Ok(record) => {
+                let mut line = serde_json::to_vec(&record)?;
+                line.push(b'\n');
+                file.write_all(&line).await?;
+                file.flush().await?;
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Event file fell behind: skipped {} events", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
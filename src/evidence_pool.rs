@@ -1,4 +1,4 @@
-use crate::types::EquivocationEvidence;
+use crate::types::{EquivocationEvidence, ProposalEquivocationEvidence, View};
 use std::collections::HashMap;
 
 /// simple pool to manage collected evidence.
@@ -6,17 +6,26 @@ use std::collections::HashMap;
 pub struct EvidencePool {
     // Map: Author -> List of Evidence (could be multiple views)
     evidences: HashMap<crate::crypto::PublicKey, Vec<EquivocationEvidence>>,
+    // Double proposals (by leader), same layout
+    proposals: HashMap<crate::crypto::PublicKey, Vec<ProposalEquivocationEvidence>>,
+    // Evidence for views below this has expired (advanced by `prune_expired`)
+    min_view: View,
 }
 
 impl EvidencePool {
     pub fn new() -> Self {
         Self {
             evidences: HashMap::new(),
+            proposals: HashMap::new(),
+            min_view: 0,
         }
     }
 
     /// Add evidence if valid and not already present.
     pub fn add_evidence(&mut self, evidence: EquivocationEvidence) -> bool {
+        if evidence.vote_a.view < self.min_view {
+            return false; // Expired, would be rejected in a block
+        }
         let author = evidence.vote_a.author.clone();
 
         let existing = self.evidences.entry(author).or_default();
@@ -58,8 +67,52 @@ impl EvidencePool {
         }
     }
 
+    /// Add double-proposal evidence if valid and not already present.
+    pub fn add_proposal_evidence(&mut self, evidence: ProposalEquivocationEvidence) -> bool {
+        if evidence.view() < self.min_view || !evidence.is_valid() {
+            return false;
+        }
+        let existing = self
+            .proposals
+            .entry(evidence.offender().clone())
+            .or_default();
+        // One piece of evidence per offence (the same view may be reported in either order)
+        if existing.iter().any(|e| e.view() == evidence.view()) {
+            return false;
+        }
+        existing.push(evidence);
+        true
+    }
+
+    pub fn get_all_proposals(&self) -> Vec<ProposalEquivocationEvidence> {
+        self.proposals.values().flatten().cloned().collect()
+    }
+
+    pub fn remove_proposal_evidence(&mut self, evidence: &[ProposalEquivocationEvidence]) {
+        for e in evidence {
+            if let Some(list) = self.proposals.get_mut(e.offender()) {
+                list.retain(|x| x.view() != e.view());
+            }
+        }
+    }
+
+    /// Drop evidence older than `max_age` views and reject such evidence from now on.
+    pub fn prune_expired(&mut self, current_view: View, max_age: View) {
+        let min_view = current_view.saturating_sub(max_age);
+        self.min_view = self.min_view.max(min_view);
+        for list in self.evidences.values_mut() {
+            list.retain(|e| e.vote_a.view >= min_view);
+        }
+        self.evidences.retain(|_, list| !list.is_empty());
+        for list in self.proposals.values_mut() {
+            list.retain(|e| e.view() >= min_view);
+        }
+        self.proposals.retain(|_, list| !list.is_empty());
+    }
+
     pub fn len(&self) -> usize {
-        self.evidences.values().map(|v| v.len()).sum()
+        self.evidences.values().map(|v| v.len()).sum::<usize>()
+            + self.proposals.values().map(|v| v.len()).sum::<usize>()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -0,0 +1,400 @@
+//! Export of finalized chain data (blocks, transactions, receipts) to CSV or Parquet files
+//! for offline analytics.
+//!
+//! Every batch covers a range of finalized views and produces one file per table, named
+//! `<table>_<from>_<to>.<ext>` (views zero-padded so that files sort in chain order).
+//! Batch mode exports a fixed range; follow mode polls the finalized height and exports
+//! each newly finalized range as it appears.
+
+use crate::crypto::{Hash, hash_data};
+use crate::storage::{Storage, StorageError};
+use crate::types::{Address, Block, View};
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[error("Unknown export format: {0}")]
+    UnknownFormat(String),
+    #[error("No consensus state found")]
+    MissingState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "parquet" => Ok(ExportFormat::Parquet),
+            _ => Err(ExportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tables
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    U64,
+    Text, // Hashes, addresses (0x-hex) and U256 amounts (decimal)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    U64(u64),
+    Text(String),
+}
+
+pub const BLOCK_COLUMNS: &[(&str, ColumnType)] = &[
+    ("view", ColumnType::U64),
+    ("hash", ColumnType::Text),
+    ("parent_hash", ColumnType::Text),
+    ("author", ColumnType::Text),
+    ("state_root", ColumnType::Text),
+    ("receipts_root", ColumnType::Text),
+    ("base_fee_per_gas", ColumnType::Text),
+    ("gas_used", ColumnType::U64),
+    ("tx_count", ColumnType::U64),
+    ("evidence_count", ColumnType::U64),
+    ("fee_recipient", ColumnType::Text),
+];
+
+pub const TRANSACTION_COLUMNS: &[(&str, ColumnType)] = &[
+    ("block_view", ColumnType::U64),
+    ("block_hash", ColumnType::Text),
+    ("tx_index", ColumnType::U64),
+    ("hash", ColumnType::Text),
+    ("from", ColumnType::Text),
+    ("to", ColumnType::Text), // Empty for contract creation
+    ("nonce", ColumnType::U64),
+    ("value", ColumnType::Text),
+    ("gas_limit", ColumnType::U64),
+    ("max_fee_per_gas", ColumnType::Text),
+    ("max_priority_fee_per_gas", ColumnType::Text),
+    ("input_size", ColumnType::U64),
+];
+
+pub const RECEIPT_COLUMNS: &[(&str, ColumnType)] = &[
+    ("block_view", ColumnType::U64),
+    ("block_hash", ColumnType::Text),
+    ("tx_index", ColumnType::U64),
+    ("tx_hash", ColumnType::Text),
+    ("status", ColumnType::U64),
+    ("gas_used", ColumnType::U64),
+    ("cumulative_gas_used", ColumnType::U64),
+    ("log_count", ColumnType::U64),
+];
+
+/// Rows of one table, in the order of its column definitions.
+#[derive(Clone, Debug)]
+pub struct Table {
+    pub name: &'static str,
+    pub columns: &'static [(&'static str, ColumnType)],
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    fn new(name: &'static str, columns: &'static [(&'static str, ColumnType)]) -> Self {
+        Self {
+            name,
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    fn write_csv(&self, path: &Path) -> Result<(), ExportError> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let header: Vec<&str> = self.columns.iter().map(|(name, _)| *name).collect();
+        writeln!(out, "{}", header.join(","))?;
+        for row in &self.rows {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|v| match v {
+                    Value::U64(n) => n.to_string(),
+                    Value::Text(s) => csv_escape(s),
+                })
+                .collect();
+            writeln!(out, "{}", fields.join(","))?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    fn write_parquet(&self, path: &Path) -> Result<(), ExportError> {
+        let schema = Arc::new(Schema::new(
+            self.columns
+                .iter()
+                .map(|(name, ty)| {
+                    let data_type = match ty {
+                        ColumnType::U64 => DataType::UInt64,
+                        ColumnType::Text => DataType::Utf8,
+                    };
+                    Field::new(*name, data_type, false)
+                })
+                .collect::<Vec<_>>(),
+        ));
+
+        let arrays: Vec<ArrayRef> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, (_, ty))| -> ArrayRef {
+                match ty {
+                    ColumnType::U64 => Arc::new(UInt64Array::from_iter_values(
+                        self.rows.iter().map(|row| match &row[i] {
+                            Value::U64(n) => *n,
+                            Value::Text(_) => 0,
+                        }),
+                    )),
+                    ColumnType::Text => Arc::new(StringArray::from_iter_values(
+                        self.rows.iter().map(|row| match &row[i] {
+                            Value::Text(s) => s.clone(),
+                            Value::U64(n) => n.to_string(),
+                        }),
+                    )),
+                }
+            })
+            .collect();
+
+        let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+        let mut writer = ArrowWriter::try_new(File::create(path)?, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn hex_hash(hash: &Hash) -> String {
+    format!("0x{}", hex::encode(hash.0))
+}
+
+fn hex_address(address: &Address) -> String {
+    format!("0x{}", hex::encode(address.as_slice()))
+}
+
+/// Rows extracted from a range of finalized views.
+#[derive(Clone, Debug)]
+pub struct ExportBatch {
+    pub from: View,
+    pub to: View,
+    pub blocks: Table,
+    pub transactions: Table,
+    pub receipts: Table,
+}
+
+impl ExportBatch {
+    /// Collect the finalized blocks in `from..=to` (dummy views are skipped).
+    pub fn collect(storage: &dyn Storage, from: View, to: View) -> Result<Self, ExportError> {
+        let mut batch = Self {
+            from,
+            to,
+            blocks: Table::new("blocks", BLOCK_COLUMNS),
+            transactions: Table::new("transactions", TRANSACTION_COLUMNS),
+            receipts: Table::new("receipts", RECEIPT_COLUMNS),
+        };
+
+        for view in from..=to {
+            let Some(qc) = storage.get_qc(view)? else {
+                continue;
+            };
+            if qc.block_hash == Hash::default() {
+                continue; // Timeout (dummy block)
+            }
+            let Some(block) = storage.get_block(&qc.block_hash)? else {
+                continue;
+            };
+            if block.is_dummy {
+                continue;
+            }
+            let receipts = storage.get_receipts(&qc.block_hash)?.unwrap_or_default();
+            batch.push_block(&qc.block_hash, &block, &receipts);
+        }
+        Ok(batch)
+    }
+
+    fn push_block(&mut self, hash: &Hash, block: &Block, receipts: &[crate::types::Receipt]) {
+        self.blocks.rows.push(vec![
+            Value::U64(block.view),
+            Value::Text(hex_hash(hash)),
+            Value::Text(hex_hash(&block.parent_hash)),
+            Value::Text(format!("0x{}", hex::encode(block.author.0.to_bytes()))),
+            Value::Text(hex_hash(&block.state_root)),
+            Value::Text(hex_hash(&block.receipts_root)),
+            Value::Text(block.base_fee_per_gas.to_string()),
+            Value::U64(block.gas_used),
+            Value::U64(block.payload.len() as u64),
+            Value::U64(block.evidence.len() as u64),
+            Value::Text(hex_address(&block.metadata.fee_recipient)),
+        ]);
+
+        let mut previous_cumulative = 0;
+        for (index, tx) in block.payload.iter().enumerate() {
+            let tx_hash = hex_hash(&hash_data(tx));
+            self.transactions.rows.push(vec![
+                Value::U64(block.view),
+                Value::Text(hex_hash(hash)),
+                Value::U64(index as u64),
+                Value::Text(tx_hash.clone()),
+                Value::Text(hex_address(&tx.sender())),
+                Value::Text(tx.to.as_ref().map(hex_address).unwrap_or_default()),
+                Value::U64(tx.nonce),
+                Value::Text(tx.value.to_string()),
+                Value::U64(tx.gas_limit),
+                Value::Text(tx.max_fee_per_gas.to_string()),
+                Value::Text(tx.max_priority_fee_per_gas.to_string()),
+                Value::U64(tx.data.len() as u64),
+            ]);
+
+            if let Some(receipt) = receipts.get(index) {
+                self.receipts.rows.push(vec![
+                    Value::U64(block.view),
+                    Value::Text(hex_hash(hash)),
+                    Value::U64(index as u64),
+                    Value::Text(tx_hash),
+                    Value::U64(receipt.status as u64),
+                    Value::U64(receipt.cumulative_gas_used - previous_cumulative),
+                    Value::U64(receipt.cumulative_gas_used),
+                    Value::U64(receipt.logs.len() as u64),
+                ]);
+                previous_cumulative = receipt.cumulative_gas_used;
+            }
+        }
+    }
+
+    /// Write one file per table into `dir`. Returns the written paths.
+    pub fn write(&self, dir: &Path, format: ExportFormat) -> Result<Vec<PathBuf>, ExportError> {
+        std::fs::create_dir_all(dir)?;
+        let mut paths = Vec::with_capacity(3);
+        for table in [&self.blocks, &self.transactions, &self.receipts] {
+            let path = dir.join(format!(
+                "{}_{:010}_{:010}.{}",
+                table.name,
+                self.from,
+                self.to,
+                format.extension()
+            ));
+            match format {
+                ExportFormat::Csv => table.write_csv(&path)?,
+                ExportFormat::Parquet => table.write_parquet(&path)?,
+            }
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Exporter
+// -----------------------------------------------------------------------------
+
+/// Streams finalized chain data into `out_dir`, remembering how far it got.
+pub struct ChainExporter {
+    storage: Arc<dyn Storage>,
+    out_dir: PathBuf,
+    format: ExportFormat,
+    next_view: View,
+}
+
+impl ChainExporter {
+    pub fn new(
+        storage: Arc<dyn Storage>,
+        out_dir: impl Into<PathBuf>,
+        format: ExportFormat,
+    ) -> Self {
+        Self {
+            storage,
+            out_dir: out_dir.into(),
+            format,
+            next_view: 0,
+        }
+    }
+
+    /// Start exporting at `view` instead of genesis (e.g. to resume a previous export).
+    pub fn with_start_view(mut self, view: View) -> Self {
+        self.next_view = view;
+        self
+    }
+
+    /// First view not exported yet.
+    pub fn next_view(&self) -> View {
+        self.next_view
+    }
+
+    /// Export finalized views from `next_view` up to `to` (capped at the finalized height).
+    /// Returns None if there was nothing new to export.
+    pub fn export_until(&mut self, to: View) -> Result<Option<ExportBatch>, ExportError> {
+        let finalized = self
+            .storage
+            .get_consensus_state()?
+            .ok_or(ExportError::MissingState)?
+            .finalized_height;
+        let to = to.min(finalized);
+        if to < self.next_view {
+            return Ok(None);
+        }
+
+        let batch = ExportBatch::collect(self.storage.as_ref(), self.next_view, to)?;
+        batch.write(&self.out_dir, self.format)?;
+        tracing::info!(
+            "Exported views {}..={}: {} blocks, {} txs, {} receipts",
+            batch.from,
+            batch.to,
+            batch.blocks.rows.len(),
+            batch.transactions.rows.len(),
+            batch.receipts.rows.len()
+        );
+        self.next_view = to + 1;
+        Ok(Some(batch))
+    }
+
+    /// Follow mode: export newly finalized views every `interval`. Runs until an error occurs.
+    pub async fn follow(mut self, interval: Duration) -> Result<(), ExportError> {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            self.export_until(View::MAX)?;
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::crypto::PublicKey;
+use crate::state::{StateError, StateManager};
+use crate::storage::AccountInfo;
+use crate::types::{Address, U256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FaucetError {
+    #[error("Faucet already funded this address; retry in {0}s")]
+    RateLimited(u64),
+    #[error("Faucet only credits on a chain whose only validator is this node")]
+    NotSoleValidator,
+    #[error("State error: {0}")]
+    State(#[from] StateError),
+}
+
+/// Amount and rate limit of the public faucet.
+#[derive(Clone, Debug)]
+pub struct FaucetConfig {
+    /// Credited per `faucet_request`.
+    pub drip: U256,
+    /// An address can request again after this many seconds.
+    pub cooldown_secs: u64,
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            drip: U256::from(10u64).pow(U256::from(18u64)),
+            cooldown_secs: 60,
+        }
+    }
+}
+
+/// Devnet faucet: credits balances directly in the node's state, outside of any block.
+/// Credits are not replicated to peers, so it refuses to credit unless `validator` (this
+/// node) is the whole committee: with any other validator, the states would diverge.
+#[derive(Clone)]
+pub struct Faucet {
+    state: Arc<Mutex<StateManager>>,
+    validator: PublicKey,
+    config: FaucetConfig,
+    // Unix time (seconds) of each address's last drip
+    last_drip: Arc<Mutex<HashMap<Address, u64>>>,
+}
+
+impl Faucet {
+    pub fn new(
+        state: Arc<Mutex<StateManager>>,
+        validator: PublicKey,
+        config: FaucetConfig,
+    ) -> Self {
+        Self {
+            state,
+            validator,
+            config,
+            last_drip: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Add `amount` to the balance of `address` (creating the account). Returns the new
+    /// balance.
+    pub fn fund(&self, address: Address, amount: U256) -> Result<U256, FaucetError> {
+        let state = self.state.lock().unwrap();
+        let storage = state.backing_storage();
+        let committee = storage
+            .get_consensus_state()
+            .map_err(|e| StateError::Smt(e.to_string()))?
+            .map(|s| s.committee)
+            .unwrap_or_default();
+        if committee != [self.validator.clone()] {
+            return Err(FaucetError::NotSoleValidator);
+        }
+
+        let mut info = storage
+            .get_account(&address)
+            .map_err(|e| StateError::Smt(e.to_string()))?
+            .unwrap_or_else(|| AccountInfo {
+                nonce: 0,
+                balance: U256::ZERO,
+                code_hash: crate::crypto::Hash(crate::types::keccak256([]).into()),
+            });
+        info.balance = info.balance.saturating_add(amount);
+        let balance = info.balance;
+        state.commit_account(address, info)?;
+        Ok(balance)
+    }
+
+    /// Credit the configured drip to `address`, at most once per cooldown (`now` is the
+    /// unix time in seconds).
+    pub fn drip(&self, address: Address, now: u64) -> Result<U256, FaucetError> {
+        let mut last_drip = self.last_drip.lock().unwrap();
+        if let Some(&last) = last_drip.get(&address) {
+            let ready_at = last.saturating_add(self.config.cooldown_secs);
+            if now < ready_at {
+                return Err(FaucetError::RateLimited(ready_at - now));
+            }
+        }
+        let balance = self.fund(address, self.config.drip)?;
+        last_drip.insert(address, now);
+        Ok(balance)
+    }
+}
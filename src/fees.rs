@@ -0,0 +1,145 @@
+//! EIP-1559 fee market: the base fee of a block, from its parent's header.
+//!
+//! Proposers (`SimplexState`), validators and the RPC (`suggest_base_fee`, `fee_history`)
+//! all go through `next_base_fee`, so they agree on the fee. The gas target is derived
+//! from the gas limit the parent carries in its header, not from a node's configuration.
+//!
+//! The header gas limit moves by at most `1 / GAS_LIMIT_BOUND_DIVISOR` of the parent's
+//! per block, towards each proposer's target (`ProposerConfig::gas_limit_target`).
+//! `ChainParams::block_gas_limit` caps the gas a block can actually use.
+
+use crate::types::{Block, ChainParams, U256};
+use std::ops::RangeInclusive;
+
+/// A block's gas limit differs from its parent's by at most the parent's over this.
+pub const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+
+/// Lowest gas limit a header can carry.
+pub const MIN_GAS_LIMIT: u64 = 5_000;
+
+/// Base fee after a block that paid `parent_base_fee` and used `parent_gas_used` of
+/// `gas_limit`: unchanged at the target (`gas_limit / elasticity_multiplier`), otherwise
+/// moved towards it by at most `1 / max_change_denominator`.
+pub fn calculate_next_base_fee(
+    parent_base_fee: U256,
+    parent_gas_used: u64,
+    gas_limit: u64,
+    elasticity_multiplier: u64,
+    max_change_denominator: u64,
+) -> U256 {
+    let target_gas = (gas_limit / elasticity_multiplier.max(1)).max(1);
+    let denominator = U256::from(max_change_denominator.max(1));
+
+    if parent_gas_used == target_gas {
+        parent_base_fee
+    } else if parent_gas_used > target_gas {
+        let gas_used_delta = parent_gas_used - target_gas;
+        let base_fee_increase =
+            parent_base_fee * U256::from(gas_used_delta) / U256::from(target_gas) / denominator;
+        parent_base_fee + base_fee_increase
+    } else {
+        let gas_used_delta = target_gas - parent_gas_used;
+        let base_fee_decrease =
+            parent_base_fee * U256::from(gas_used_delta) / U256::from(target_gas) / denominator;
+        parent_base_fee.saturating_sub(base_fee_decrease)
+    }
+}
+
+/// Gas limit in the header of `block`. Headers without one (zero: dummy blocks, and
+/// blocks from before it was recorded) count as `params.block_gas_limit`.
+pub fn header_gas_limit(params: &ChainParams, block: &Block) -> u64 {
+    match block.gas_limit {
+        0 => params.block_gas_limit,
+        gas_limit => gas_limit,
+    }
+}
+
+/// Gas the transactions of `block` can use: its header gas limit, within the cap set by
+/// `params`.
+pub fn execution_gas_limit(params: &ChainParams, block: &Block) -> u64 {
+    header_gas_limit(params, block).min(params.block_gas_limit)
+}
+
+/// Gas limits a child of a block with `parent_gas_limit` may carry.
+pub fn gas_limit_bounds(parent_gas_limit: u64) -> RangeInclusive<u64> {
+    let delta = parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR;
+    let lowest = parent_gas_limit.saturating_sub(delta).max(MIN_GAS_LIMIT);
+    let highest = parent_gas_limit.saturating_add(delta).max(MIN_GAS_LIMIT);
+    lowest..=highest
+}
+
+/// Gas limit of a proposal on top of a block with `parent_gas_limit`: as close to
+/// `target` as `gas_limit_bounds` allows.
+pub fn next_gas_limit(parent_gas_limit: u64, target: u64) -> u64 {
+    let bounds = gas_limit_bounds(parent_gas_limit);
+    target.clamp(*bounds.start(), *bounds.end())
+}
+
+/// Base fee of the block following `parent` under `params`.
+pub fn next_base_fee(params: &ChainParams, parent: &Block) -> U256 {
+    calculate_next_base_fee(
+        parent.base_fee_per_gas,
+        parent.gas_used,
+        header_gas_limit(params, parent),
+        params.elasticity_multiplier,
+        params.base_fee_max_change_denominator,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_fee_follows_parent_gas_limit() {
+        let base_fee = U256::from(800u64);
+        // At, above and below the target of a 30M block
+        assert_eq!(
+            calculate_next_base_fee(base_fee, 15_000_000, 30_000_000, 2, 8),
+            base_fee
+        );
+        assert_eq!(
+            calculate_next_base_fee(base_fee, 30_000_000, 30_000_000, 2, 8),
+            U256::from(900u64)
+        );
+        assert_eq!(
+            calculate_next_base_fee(base_fee, 0, 30_000_000, 2, 8),
+            U256::from(700u64)
+        );
+
+        // The same gas is on target for a parent whose header carries a 30M limit, whatever
+        // the local parameters say
+        let params = ChainParams {
+            block_gas_limit: 10_000_000,
+            ..Default::default()
+        };
+        let mut parent = Block::new_dummy(
+            crate::crypto::generate_keypair_from_id(0).0,
+            1,
+            crate::crypto::Hash::default(),
+            Default::default(),
+        );
+        parent.base_fee_per_gas = base_fee;
+        parent.gas_used = 15_000_000;
+        parent.gas_limit = 30_000_000;
+        assert_eq!(next_base_fee(&params, &parent), base_fee);
+        parent.gas_limit = 0;
+        assert!(next_base_fee(&params, &parent) > base_fee);
+    }
+
+    #[test]
+    fn test_gas_limit_moves_within_bounds() {
+        let parent = 30_000_000;
+        let delta = parent / GAS_LIMIT_BOUND_DIVISOR;
+        assert_eq!(gas_limit_bounds(parent), parent - delta..=parent + delta);
+
+        // Proposers step towards their target, and stop on it
+        assert_eq!(next_gas_limit(parent, 60_000_000), parent + delta);
+        assert_eq!(next_gas_limit(parent, 0), parent - delta);
+        assert_eq!(next_gas_limit(parent, parent + 1), parent + 1);
+
+        // Never below the minimum
+        assert_eq!(*gas_limit_bounds(MIN_GAS_LIMIT).start(), MIN_GAS_LIMIT);
+        assert_eq!(next_gas_limit(0, 0), MIN_GAS_LIMIT);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::storage::Storage;
+use crate::types::View;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Thresholds for the health (liveness) and readiness verdicts.
+#[derive(Clone, Debug)]
+pub struct HealthConfig {
+    /// The node counts as syncing while it is more than this many views behind the
+    /// highest view it has seen on the network.
+    pub max_sync_lag: View,
+    /// A node that has not finalized anything for this long is stuck (liveness fails).
+    pub max_finality_age_secs: u64,
+    /// Peers needed before the node reports ready.
+    pub min_peers: usize,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_sync_lag: 5,
+            max_finality_age_secs: 300,
+            min_peers: 1,
+        }
+    }
+}
+
+/// Result of `node_health` (and the body of the HTTP probes).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HealthReport {
+    pub current_view: View,
+    pub highest_seen_view: View,
+    pub syncing: bool,
+    pub peer_count: usize,
+    pub finalized_view: View,
+    /// Unix time (seconds) of the last finalization observed by this process.
+    pub last_finalized_at: Option<u64>,
+    /// Error reading the database, if any.
+    pub db_error: Option<String>,
+    /// Liveness: the database works and finality advanced recently.
+    pub healthy: bool,
+    /// Readiness: healthy, caught up and connected.
+    pub ready: bool,
+}
+
+#[derive(Default)]
+struct Progress {
+    current_view: View,
+    highest_seen_view: View,
+    peer_count: usize,
+    finalized_view: View,
+    last_finalized_at: Option<u64>,
+}
+
+/// Progress counters shared by the event loop, the network task and the RPC server.
+#[derive(Clone)]
+pub struct HealthMonitor {
+    pub config: HealthConfig,
+    started_at: u64,
+    progress: Arc<Mutex<Progress>>,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl HealthMonitor {
+    pub fn new(config: HealthConfig) -> Self {
+        Self::starting_at(config, unix_now())
+    }
+
+    /// A monitor whose grace period for the first finalization starts at `now`.
+    pub fn starting_at(config: HealthConfig, now: u64) -> Self {
+        Self {
+            config,
+            started_at: now,
+            progress: Arc::new(Mutex::new(Progress::default())),
+        }
+    }
+
+    /// Record the local consensus position after handling an event.
+    pub fn observe_consensus(&self, current_view: View, finalized_view: View, now: u64) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.current_view = current_view;
+        progress.highest_seen_view = progress.highest_seen_view.max(current_view);
+        if finalized_view > progress.finalized_view {
+            progress.finalized_view = finalized_view;
+            progress.last_finalized_at = Some(now);
+        }
+    }
+
+    /// Record a view seen in a message from the network.
+    pub fn observe_network_view(&self, view: View) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.highest_seen_view = progress.highest_seen_view.max(view);
+    }
+
+    pub fn set_peer_count(&self, count: usize) {
+        self.progress.lock().unwrap().peer_count = count;
+    }
+
+    pub fn report(&self, storage: &dyn Storage, now: u64) -> HealthReport {
+        let db_error = storage.get_consensus_state().err().map(|e| e.to_string());
+        let progress = self.progress.lock().unwrap();
+
+        let behind = progress
+            .highest_seen_view
+            .saturating_sub(progress.current_view);
+        let syncing = behind > self.config.max_sync_lag;
+        let last_progress = progress.last_finalized_at.unwrap_or(self.started_at);
+        let stuck = now.saturating_sub(last_progress) > self.config.max_finality_age_secs;
+        let healthy = db_error.is_none() && !stuck;
+        let ready = healthy && !syncing && progress.peer_count >= self.config.min_peers;
+
+        HealthReport {
+            current_view: progress.current_view,
+            highest_seen_view: progress.highest_seen_view,
+            syncing,
+            peer_count: progress.peer_count,
+            finalized_view: progress.finalized_view,
+            last_finalized_at: progress.last_finalized_at,
+            db_error,
+            healthy,
+            ready,
+        }
+    }
+}
+
+/// Serve `GET /healthz` (liveness) and `GET /readyz` (readiness) as plain HTTP: 200 when
+/// the check passes, 503 otherwise, with the `HealthReport` as a JSON body.
+pub async fn serve_http(
+    listener: TcpListener,
+    monitor: HealthMonitor,
+    storage: Arc<dyn Storage>,
+) -> std::io::Result<()> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let monitor = monitor.clone();
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = match stream.read(&mut buf).await {
+                Ok(n) => n,
+                Err(_) => return,
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("");
+            let report = monitor.report(storage.as_ref(), unix_now());
+            let passed = match path {
+                "/healthz" => report.healthy,
+                "/readyz" => report.ready,
+                _ => {
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                        .await;
+                    return;
+                }
+            };
+            let body = serde_json::to_string(&report).unwrap_or_default();
+            let status = if passed {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
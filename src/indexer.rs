@@ -0,0 +1,447 @@
+//! Explorer index of the finalized chain (built with `--features indexer`).
+//!
+//! An `Indexer` follows the finalized height and writes denormalized records into its own
+//! redb database: blocks by height, transactions by sender and recipient, logs by topic,
+//! and the balances of the accounts each block touched. `IndexerRpc` serves them to
+//! explorers alongside the node RPC.
+//!
+//! Balances are read when a block is indexed (the node keeps no historical state), so the
+//! history is exact only while the indexer keeps up with finalization.
+
+use crate::crypto::{Hash, PublicKey, hash_data};
+use crate::storage::{Storage, StorageError};
+use crate::types::{Address, Block, Log, Receipt, U256, View};
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use redb::{Database, ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+const TABLE_BLOCKS: TableDefinition<u64, Vec<u8>> = TableDefinition::new("index_blocks"); // Key: View
+const TABLE_TXS: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("index_txs"); // Key: Address + View + Index
+const TABLE_LOGS: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("index_logs"); // Key: Topic + View + Tx Index + Log Index
+const TABLE_BALANCES: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("index_balances"); // Key: Address + View
+const TABLE_META: TableDefinition<&str, u64> = TableDefinition::new("index_meta");
+
+/// Meta key of the first view not indexed yet.
+const NEXT_VIEW_KEY: &str = "next_view";
+
+/// Most records returned by one query.
+pub const MAX_QUERY_RESULTS: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("No consensus state found")]
+    MissingState,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedBlock {
+    pub view: View,
+    pub hash: Hash,
+    pub parent_hash: Hash,
+    pub author: PublicKey,
+    pub fee_recipient: Address,
+    pub transactions: usize,
+    pub gas_used: u64,
+    pub base_fee_per_gas: U256,
+}
+
+/// A transaction with its outcome, stored under both its sender and its recipient.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedTransaction {
+    pub hash: Hash,
+    pub view: View,
+    pub block_hash: Hash,
+    pub index: u32,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub nonce: u64,
+    pub value: U256,
+    pub status: u8,
+    pub gas_used: u64,
+    /// Address of the contract created by a successful deployment.
+    pub contract_address: Option<Address>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedLog {
+    pub view: View,
+    pub transaction_hash: Hash,
+    pub transaction_index: u32,
+    pub log_index: u32,
+    pub log: Log,
+}
+
+/// Balance of an account after the block at `view`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub view: View,
+    pub balance: U256,
+}
+
+pub struct Indexer {
+    db: Database,
+    storage: Arc<dyn Storage>,
+}
+
+impl Indexer {
+    /// Open (or create) the index database at `path`, indexing the chain of `storage`.
+    pub fn new<P: AsRef<Path>>(path: P, storage: Arc<dyn Storage>) -> Result<Self, StorageError> {
+        let db = Database::create(path)?;
+        let write_txn = db.begin_write()?;
+        {
+            let _ = write_txn.open_table(TABLE_BLOCKS)?;
+            let _ = write_txn.open_table(TABLE_TXS)?;
+            let _ = write_txn.open_table(TABLE_LOGS)?;
+            let _ = write_txn.open_table(TABLE_BALANCES)?;
+            let _ = write_txn.open_table(TABLE_META)?;
+        }
+        write_txn.commit()?;
+        Ok(Self { db, storage })
+    }
+
+    /// First view not indexed yet.
+    pub fn next_view(&self) -> Result<View, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_META)?;
+        Ok(table.get(NEXT_VIEW_KEY)?.map(|v| v.value()).unwrap_or(0))
+    }
+
+    /// Index finalized views from `next_view` up to `to` (capped at the finalized height).
+    /// Returns the number of blocks indexed.
+    pub fn index_until(&self, to: View) -> Result<usize, IndexerError> {
+        let finalized = self
+            .storage
+            .get_consensus_state()?
+            .ok_or(IndexerError::MissingState)?
+            .finalized_height;
+        let from = self.next_view()?;
+        let to = to.min(finalized);
+        if to < from {
+            return Ok(0);
+        }
+
+        let mut blocks = Vec::new();
+        for view in from..=to {
+            let Some(qc) = self.storage.get_qc(view)? else {
+                continue;
+            };
+            if qc.block_hash == Hash::default() {
+                continue; // Timeout (dummy block)
+            }
+            let Some(block) = self.storage.get_block(&qc.block_hash)? else {
+                continue;
+            };
+            if block.is_dummy {
+                continue;
+            }
+            let receipts = self
+                .storage
+                .get_receipts(&qc.block_hash)?
+                .unwrap_or_default();
+            blocks.push((qc.block_hash, block, receipts));
+        }
+
+        self.write(&blocks, to + 1)?;
+        if !blocks.is_empty() {
+            tracing::info!("Indexed views {}..={}: {} blocks", from, to, blocks.len());
+        }
+        Ok(blocks.len())
+    }
+
+    fn write(
+        &self,
+        blocks: &[(Hash, Block, Vec<Receipt>)],
+        next_view: View,
+    ) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut block_table = write_txn.open_table(TABLE_BLOCKS)?;
+            let mut tx_table = write_txn.open_table(TABLE_TXS)?;
+            let mut log_table = write_txn.open_table(TABLE_LOGS)?;
+            let mut balance_table = write_txn.open_table(TABLE_BALANCES)?;
+
+            for (hash, block, receipts) in blocks {
+                let indexed = IndexedBlock {
+                    view: block.view,
+                    hash: *hash,
+                    parent_hash: block.parent_hash,
+                    author: block.author.clone(),
+                    fee_recipient: block.metadata.fee_recipient,
+                    transactions: block.payload.len(),
+                    gas_used: block.gas_used,
+                    base_fee_per_gas: block.base_fee_per_gas,
+                };
+                block_table.insert(block.view, bincode::serialize(&indexed)?)?;
+
+                let mut touched = vec![block.metadata.fee_recipient];
+                let mut previous_cumulative = 0;
+                for (index, tx) in block.payload.iter().enumerate() {
+                    let receipt = receipts.get(index);
+                    let status = receipt.map(|r| r.status).unwrap_or_default();
+                    let cumulative = receipt
+                        .map(|r| r.cumulative_gas_used)
+                        .unwrap_or(previous_cumulative);
+                    let record = IndexedTransaction {
+                        hash: hash_data(tx),
+                        view: block.view,
+                        block_hash: *hash,
+                        index: index as u32,
+                        from: tx.sender(),
+                        to: tx.to,
+                        nonce: tx.nonce,
+                        value: tx.value,
+                        status,
+                        gas_used: cumulative - previous_cumulative,
+                        contract_address: receipt.and_then(|r| r.contract_address),
+                    };
+                    previous_cumulative = cumulative;
+
+                    let value = bincode::serialize(&record)?;
+                    let mut parties = vec![record.from];
+                    parties.extend(record.to.or(record.contract_address));
+                    parties.dedup();
+                    for address in &parties {
+                        let key = tx_key(address, block.view, index as u32);
+                        tx_table.insert(key.as_slice(), value.clone())?;
+                    }
+                    touched.extend(parties);
+
+                    for (log_index, log) in receipt.iter().flat_map(|r| r.logs.iter()).enumerate() {
+                        let indexed_log = IndexedLog {
+                            view: block.view,
+                            transaction_hash: record.hash,
+                            transaction_index: index as u32,
+                            log_index: log_index as u32,
+                            log: log.clone(),
+                        };
+                        let value = bincode::serialize(&indexed_log)?;
+                        for topic in &log.topics {
+                            let key = log_key(topic, block.view, index as u32, log_index as u32);
+                            log_table.insert(key.as_slice(), value.clone())?;
+                        }
+                    }
+                }
+
+                touched.sort();
+                touched.dedup();
+                for address in touched {
+                    let balance = self
+                        .storage
+                        .get_account(&address)?
+                        .map(|a| a.balance)
+                        .unwrap_or_default();
+                    let key = balance_key(&address, block.view);
+                    balance_table.insert(key.as_slice(), bincode::serialize(&balance)?)?;
+                }
+            }
+
+            let mut meta = write_txn.open_table(TABLE_META)?;
+            meta.insert(NEXT_VIEW_KEY, next_view)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Follow mode: index newly finalized views every `interval`. Runs until an error occurs.
+    pub async fn follow(self: Arc<Self>, interval: Duration) -> Result<(), IndexerError> {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            self.index_until(View::MAX)?;
+        }
+    }
+
+    /// Indexed blocks in `from..=to` (at most `MAX_QUERY_RESULTS`).
+    pub fn block_range(&self, from: View, to: View) -> Result<Vec<IndexedBlock>, StorageError> {
+        if from > to {
+            return Ok(Vec::new());
+        }
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_BLOCKS)?;
+        let mut blocks = Vec::new();
+        for entry in table.range(from..=to)?.take(MAX_QUERY_RESULTS) {
+            let (_, val) = entry?;
+            blocks.push(bincode::deserialize(&val.value())?);
+        }
+        Ok(blocks)
+    }
+
+    /// Transactions sent or received by `address`, newest first.
+    pub fn transactions_by_address(
+        &self,
+        address: &Address,
+        limit: usize,
+    ) -> Result<Vec<IndexedTransaction>, StorageError> {
+        self.scan_newest(TABLE_TXS, address.as_slice(), limit)
+    }
+
+    /// Logs with `topic` among their topics, newest first.
+    pub fn logs_by_topic(
+        &self,
+        topic: &Hash,
+        limit: usize,
+    ) -> Result<Vec<IndexedLog>, StorageError> {
+        self.scan_newest(TABLE_LOGS, &topic.0, limit)
+    }
+
+    /// Balances of `address` after the blocks that touched it, newest first.
+    pub fn balance_history(
+        &self,
+        address: &Address,
+        limit: usize,
+    ) -> Result<Vec<BalanceChange>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_BALANCES)?;
+        let end = prefix_end(address.as_slice());
+        let mut changes = Vec::new();
+        // Keys end with the big-endian view, so the last entries are the newest
+        for entry in table
+            .range::<&[u8]>(address.as_slice()..end.as_slice())?
+            .rev()
+            .take(limit.min(MAX_QUERY_RESULTS))
+        {
+            let (key, val) = entry?;
+            let view = u64::from_be_bytes(key.value()[20..28].try_into().unwrap());
+            changes.push(BalanceChange {
+                view,
+                balance: bincode::deserialize(&val.value())?,
+            });
+        }
+        Ok(changes)
+    }
+
+    fn scan_newest<T: serde::de::DeserializeOwned>(
+        &self,
+        table: TableDefinition<&'static [u8], Vec<u8>>,
+        prefix: &[u8],
+        limit: usize,
+    ) -> Result<Vec<T>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(table)?;
+        let end = prefix_end(prefix);
+        let mut records = Vec::new();
+        for entry in table
+            .range::<&[u8]>(prefix..end.as_slice())?
+            .rev()
+            .take(limit.min(MAX_QUERY_RESULTS))
+        {
+            let (_, val) = entry?;
+            records.push(bincode::deserialize(&val.value())?);
+        }
+        Ok(records)
+    }
+}
+
+/// Upper bound of the keys starting with `prefix` (keys extend it by at most 16 bytes).
+fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+    let mut end = prefix.to_vec();
+    end.extend_from_slice(&[0xff; 16]);
+    end
+}
+
+fn tx_key(address: &Address, view: View, index: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(32);
+    key.extend_from_slice(address.as_slice());
+    key.extend_from_slice(&view.to_be_bytes());
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn log_key(topic: &Hash, view: View, tx_index: u32, log_index: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(48);
+    key.extend_from_slice(&topic.0);
+    key.extend_from_slice(&view.to_be_bytes());
+    key.extend_from_slice(&tx_index.to_be_bytes());
+    key.extend_from_slice(&log_index.to_be_bytes());
+    key
+}
+
+fn balance_key(address: &Address, view: View) -> Vec<u8> {
+    let mut key = Vec::with_capacity(28);
+    key.extend_from_slice(address.as_slice());
+    key.extend_from_slice(&view.to_be_bytes());
+    key
+}
+
+// -----------------------------------------------------------------------------
+// RPC
+// -----------------------------------------------------------------------------
+
+#[rpc(server)]
+pub trait IndexerRpc {
+    /// Transactions sent or received by `address`, newest first.
+    #[method(name = "get_transactions_by_address")]
+    fn get_transactions_by_address(
+        &self,
+        address: Address,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<IndexedTransaction>>;
+
+    /// Indexed (finalized, non-dummy) blocks with views in `from..=to`.
+    #[method(name = "get_block_range")]
+    fn get_block_range(&self, from: View, to: View) -> RpcResult<Vec<IndexedBlock>>;
+
+    #[method(name = "get_logs_by_topic")]
+    fn get_logs_by_topic(&self, topic: Hash, limit: Option<usize>) -> RpcResult<Vec<IndexedLog>>;
+
+    #[method(name = "get_balance_history")]
+    fn get_balance_history(
+        &self,
+        address: Address,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<BalanceChange>>;
+}
+
+/// `IndexerRpc` over an `Indexer`; merge `into_rpc()` into the node's RPC module.
+pub struct IndexerRpcImpl {
+    indexer: Arc<Indexer>,
+}
+
+impl IndexerRpcImpl {
+    pub fn new(indexer: Arc<Indexer>) -> Self {
+        Self { indexer }
+    }
+}
+
+fn index_error(e: StorageError) -> jsonrpsee::types::ErrorObjectOwned {
+    jsonrpsee::types::ErrorObject::owned(-32000, format!("Index error: {:?}", e), None::<()>)
+}
+
+impl IndexerRpcServer for IndexerRpcImpl {
+    fn get_transactions_by_address(
+        &self,
+        address: Address,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<IndexedTransaction>> {
+        self.indexer
+            .transactions_by_address(&address, limit.unwrap_or(MAX_QUERY_RESULTS))
+            .map_err(index_error)
+    }
+
+    fn get_block_range(&self, from: View, to: View) -> RpcResult<Vec<IndexedBlock>> {
+        self.indexer.block_range(from, to).map_err(index_error)
+    }
+
+    fn get_logs_by_topic(&self, topic: Hash, limit: Option<usize>) -> RpcResult<Vec<IndexedLog>> {
+        self.indexer
+            .logs_by_topic(&topic, limit.unwrap_or(MAX_QUERY_RESULTS))
+            .map_err(index_error)
+    }
+
+    fn get_balance_history(
+        &self,
+        address: Address,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<BalanceChange>> {
+        self.indexer
+            .balance_history(&address, limit.unwrap_or(MAX_QUERY_RESULTS))
+            .map_err(index_error)
+    }
+}
@@ -1,11 +1,38 @@
+pub mod archive;
+pub mod bridge;
+pub mod chain_spec;
+pub mod channels;
 pub mod client;
+pub mod conformance;
 pub mod consensus;
 pub mod crypto;
+pub mod devnet;
+pub mod engine;
+pub mod events;
 pub mod evidence_pool;
+pub mod export;
+pub mod faucet;
+pub mod fees;
+pub mod health;
+#[cfg(feature = "indexer")]
+pub mod indexer;
+pub mod light;
+pub mod lru;
+pub mod memory;
+pub mod migrations;
 pub mod network;
+pub mod payload;
+pub mod precompiles;
+pub mod rate_limit;
 pub mod rpc;
+pub mod seen_cache;
+pub mod shutdown;
 pub mod state;
 pub mod storage;
+pub mod system_contracts;
+pub mod testing;
+pub mod tx_policy;
 pub mod tx_pool;
 pub mod types;
+pub mod validation;
 pub mod vm;
@@ -0,0 +1,417 @@
+//! Finality verification for light clients and bridges.
+//!
+//! The verification functions are pure: given a block, its certificates and the committee
+//! of its epoch (obtained through `CommitteeTransition::verify_chain`), finality is checked
+//! with hashing and BLS aggregate verification only, without storage or execution.
+//! `LightClient` builds on them to follow the chain from gossip (`--light` mode).
+
+use crate::crypto::{Hash, PublicKey, Signature, aggregate, hash_data, verify_aggregate};
+use crate::state::{StateError, StateManager, StateProof, account_leaf, verify_proof};
+use crate::storage::{AccountInfo, ChainHead, ConsensusState, Storage, StorageError};
+use crate::types::{
+    Address, AggregateVote, Block, DEFAULT_CHAIN_ID, MerkleProof, QuorumCertificate, SignerBitmap,
+    SyncMessage, Transaction, View, Vote, VoteType, vote_message,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+/// An account proof requested through a light node, answered once a verified proof arrives.
+pub type ProofRequest = (Address, oneshot::Sender<AccountProof>);
+
+/// Certificates proving that a block was notarized and then finalized.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct FinalityProof {
+    /// Aggregated Notarize votes for the block.
+    pub notarization: QuorumCertificate,
+    /// Aggregated Finalize votes for the block.
+    pub finalization: QuorumCertificate,
+}
+
+/// Result of `get_finality_proof`. The header is the full block: its hash commits to
+/// every field, so a verifier needs all of them to recompute it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FinalizedBlock {
+    pub header: Block,
+    pub proof: FinalityProof,
+}
+
+/// Result of `get_transaction_proof`: a transaction, its Merkle branch to the
+/// `transactions_root` of its block, and the finality proof of that block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionProof {
+    pub transaction: Transaction,
+    pub branch: MerkleProof,
+    pub block: FinalizedBlock,
+}
+
+impl TransactionProof {
+    /// Check that the transaction was finalized under `committee`: the block is final
+    /// (`verify_finality`) and its transactions root commits to the transaction at
+    /// `branch.index`. Returns the block hash.
+    pub fn verify(&self, committee: &[PublicKey], chain_id: u64) -> Result<Hash, FinalityError> {
+        let block_hash =
+            verify_finality(&self.block.header, &self.block.proof, committee, chain_id)?;
+        if !self.branch.verify(
+            &self.block.header.transactions_root,
+            &self.transaction.encode(),
+        ) {
+            return Err(FinalityError::NotIncluded);
+        }
+        Ok(block_hash)
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FinalityError {
+    #[error("Dummy blocks carry no finality proof")]
+    DummyBlock,
+    #[error("Block was produced by a different committee")]
+    CommitteeMismatch,
+    #[error("{0} certificate is for another block")]
+    BlockMismatch(&'static str),
+    #[error("{0} certificate is for view {1}, block is at view {2}")]
+    ViewMismatch(&'static str, u64, u64),
+    #[error("{0} certificate has a signer outside the committee")]
+    UnknownSigner(&'static str),
+    #[error("{0} certificate has {1} signers, {2} required")]
+    InsufficientSigners(&'static str, usize, usize),
+    #[error("{0} certificate signature is invalid")]
+    InvalidSignature(&'static str),
+    #[error("Transaction is not committed by the block's transactions root")]
+    NotIncluded,
+}
+
+/// Check that `header` is final under `committee`: both certificates are for the block,
+/// and each is signed by a quorum (2f+1) of distinct committee members (with votes of
+/// `chain_id`). Returns the block hash.
+pub fn verify_finality(
+    header: &Block,
+    proof: &FinalityProof,
+    committee: &[PublicKey],
+    chain_id: u64,
+) -> Result<Hash, FinalityError> {
+    if header.is_dummy {
+        return Err(FinalityError::DummyBlock);
+    }
+    if header.committee_hash != hash_data(&committee) {
+        return Err(FinalityError::CommitteeMismatch);
+    }
+    let block_hash = hash_data(header);
+    verify_certificate(
+        VoteType::Notarize,
+        &proof.notarization,
+        header.view,
+        &block_hash,
+        committee,
+        chain_id,
+    )?;
+    verify_certificate(
+        VoteType::Finalize,
+        &proof.finalization,
+        header.view,
+        &block_hash,
+        committee,
+        chain_id,
+    )?;
+    Ok(block_hash)
+}
+
+/// Check that `qc` aggregates a quorum of `vote_type` votes for `block_hash` in `view`.
+pub(crate) fn verify_certificate(
+    vote_type: VoteType,
+    qc: &QuorumCertificate,
+    view: u64,
+    block_hash: &Hash,
+    committee: &[PublicKey],
+    chain_id: u64,
+) -> Result<(), FinalityError> {
+    let kind = match vote_type {
+        VoteType::Notarize => "Notarization",
+        VoteType::Finalize => "Finalization",
+        VoteType::Handover => "Handover",
+    };
+    if qc.block_hash != *block_hash {
+        return Err(FinalityError::BlockMismatch(kind));
+    }
+    if qc.view != view {
+        return Err(FinalityError::ViewMismatch(kind, qc.view, view));
+    }
+    let Some(signers) = qc.signers.resolve(committee) else {
+        return Err(FinalityError::UnknownSigner(kind));
+    };
+    let threshold = (committee.len() * 2) / 3 + 1;
+    if signers.len() < threshold {
+        return Err(FinalityError::InsufficientSigners(
+            kind,
+            signers.len(),
+            threshold,
+        ));
+    }
+    let message = vote_message(vote_type, view, block_hash, chain_id);
+    if !verify_aggregate(&signers, &message, &qc.signature) {
+        return Err(FinalityError::InvalidSignature(kind));
+    }
+    Ok(())
+}
+
+/// An account (or its absence) with a Merkle proof against the state root of `block_hash`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountProof {
+    pub block_hash: Hash,
+    pub state_root: Hash,
+    pub address: Address,
+    pub account: Option<AccountInfo>,
+    /// Root of the account's storage trie, folded into its leaf.
+    pub storage_root: Hash,
+    /// Compiled sparse Merkle tree proof of the account leaf.
+    pub proof: Vec<u8>,
+}
+
+impl AccountProof {
+    /// Prove `address` in `state`, which must be the committed state of `head`.
+    pub fn from_state(
+        head: &ChainHead,
+        state: &StateManager,
+        address: Address,
+    ) -> Result<Self, StateError> {
+        if state.root() != head.state_root {
+            return Err(StateError::Smt(
+                "state is not at the chain head".to_string(),
+            ));
+        }
+        let proof = state.get_proof(address, &[])?;
+        Ok(Self {
+            block_hash: head.block_hash,
+            state_root: head.state_root,
+            address,
+            account: proof.account,
+            storage_root: proof.storage_root,
+            proof: proof.account_proof,
+        })
+    }
+
+    /// Check the proof against `state_root` (which must come from a verified header).
+    pub fn verify(&self, state_root: &Hash) -> bool {
+        let account_hash = match &self.account {
+            Some(info) => hash_data(info),
+            None => Hash::default(),
+        };
+        verify_proof(
+            state_root,
+            &StateProof {
+                state_root: self.state_root,
+                address: self.address,
+                account_hash: account_leaf(&account_hash, &self.storage_root),
+                account: self.account.clone(),
+                storage_root: self.storage_root,
+                account_proof: self.proof.clone(),
+                storage_proofs: vec![],
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+struct ViewVotes {
+    notarize: HashMap<Hash, HashMap<PublicKey, Signature>>,
+    finalize: HashMap<Hash, HashMap<PublicKey, Signature>>,
+}
+
+/// Follows finality from gossiped blocks and votes without executing anything.
+/// Verified headers and certificates go to `storage` (blocks, QCs, finality QCs and the
+/// chain head), so the storage-backed header and finality RPCs work unchanged.
+/// The committee is fixed: hand-overs are not gossiped, so a light node must be restarted
+/// with the new committee after an epoch change.
+pub struct LightClient {
+    pub committee: Vec<PublicKey>,
+    /// Epoch of `committee` (that of the latest stored hand-over, else genesis).
+    pub epoch: u64,
+    pub storage: Arc<dyn Storage>,
+    pub head: Option<ChainHead>,
+    /// Chain whose votes are accepted (see `vote_message`).
+    pub chain_id: u64,
+    votes: HashMap<View, ViewVotes>,
+}
+
+impl LightClient {
+    pub fn new(committee: Vec<PublicKey>, storage: Arc<dyn Storage>) -> Self {
+        let head = storage.get_chain_head().ok().flatten();
+        let epoch = storage
+            .get_latest_committee_transition()
+            .ok()
+            .flatten()
+            .map_or(0, |t| t.epoch);
+        Self {
+            committee,
+            epoch,
+            storage,
+            head,
+            chain_id: DEFAULT_CHAIN_ID,
+            votes: HashMap::new(),
+        }
+    }
+
+    /// Follow `chain_id` (the chain spec's) instead of the default.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    fn threshold(&self) -> usize {
+        (self.committee.len() * 2) / 3 + 1
+    }
+
+    fn head_view(&self) -> View {
+        self.head.as_ref().map(|h| h.view).unwrap_or(0)
+    }
+
+    /// Store a signed header from the committee, then check whether it is now final.
+    pub fn on_block(&mut self, block: Block) -> Result<Vec<SyncMessage>, StorageError> {
+        if block.is_dummy
+            || block.view <= self.head_view()
+            || block.committee_hash != hash_data(&self.committee)
+            || !self.committee.contains(&block.author)
+            || !block.verify_signature()
+        {
+            return Ok(vec![]);
+        }
+        let block_hash = hash_data(&block);
+        self.storage.save_block(&block)?;
+        self.try_finalize(block.view, block_hash)?;
+        Ok(vec![])
+    }
+
+    /// Collect a vote; once a quorum certifies a block, store the certificate. Returns a
+    /// request for the block if it is certified but was never received.
+    pub fn on_vote(&mut self, vote: Vote) -> Result<Vec<SyncMessage>, StorageError> {
+        if vote.block_hash == Hash::default()
+            || vote.view <= self.head_view()
+            || !self.committee.contains(&vote.author)
+            || !vote.verify_signature(self.chain_id)
+        {
+            return Ok(vec![]);
+        }
+        let threshold = self.threshold();
+        let view_votes = self.votes.entry(vote.view).or_default();
+        let votes = match vote.vote_type {
+            VoteType::Notarize => view_votes.notarize.entry(vote.block_hash).or_default(),
+            VoteType::Finalize => view_votes.finalize.entry(vote.block_hash).or_default(),
+            VoteType::Handover => return Ok(vec![]),
+        };
+        votes.insert(vote.author.clone(), vote.signature.clone());
+        if votes.len() < threshold {
+            return Ok(vec![]);
+        }
+
+        let mut signers = SignerBitmap::default();
+        let mut signatures = Vec::new();
+        for (index, member) in self.committee.iter().enumerate() {
+            if let Some(signature) = votes.get(member) {
+                signers.insert(index);
+                signatures.push(signature.clone());
+            }
+        }
+        let Some(signature) = aggregate(&signatures) else {
+            return Ok(vec![]);
+        };
+        let qc = QuorumCertificate {
+            view: vote.view,
+            block_hash: vote.block_hash,
+            signature,
+            epoch: self.epoch,
+            signers,
+        };
+        self.on_certificate(qc, vote.vote_type)
+    }
+
+    /// Vote aggregates gossiped by full nodes: a quorum-sized one is a certificate.
+    pub fn on_aggregate_vote(
+        &mut self,
+        aggregate: AggregateVote,
+    ) -> Result<Vec<SyncMessage>, StorageError> {
+        if aggregate.block_hash == Hash::default()
+            || aggregate.view <= self.head_view()
+            || aggregate.vote_type == VoteType::Handover
+            || aggregate.epoch != self.epoch
+            || aggregate.signers.len() < self.threshold()
+            || !aggregate.verify(&self.committee, self.chain_id)
+        {
+            return Ok(vec![]);
+        }
+        let vote_type = aggregate.vote_type;
+        self.on_certificate(aggregate.to_qc(), vote_type)
+    }
+
+    fn on_certificate(
+        &mut self,
+        qc: QuorumCertificate,
+        vote_type: VoteType,
+    ) -> Result<Vec<SyncMessage>, StorageError> {
+        match vote_type {
+            VoteType::Notarize => self.storage.save_qc(&qc)?,
+            _ => self.storage.save_finality_qc(&qc)?,
+        }
+
+        if self.storage.get_block(&qc.block_hash)?.is_none() {
+            return Ok(vec![SyncMessage::RequestBlock(qc.block_hash)]);
+        }
+        self.try_finalize(qc.view, qc.block_hash)?;
+        Ok(vec![])
+    }
+
+    /// Advance the head to `block_hash` if the header and both certificates verify.
+    fn try_finalize(&mut self, view: View, block_hash: Hash) -> Result<(), StorageError> {
+        if view <= self.head_view() {
+            return Ok(());
+        }
+        let (Some(header), Some(notarization), Some(finalization)) = (
+            self.storage.get_block(&block_hash)?,
+            self.storage.get_qc(view)?,
+            self.storage.get_finality_qc(view)?,
+        ) else {
+            return Ok(());
+        };
+        let proof = FinalityProof {
+            notarization,
+            finalization,
+        };
+        if verify_finality(&header, &proof, &self.committee, self.chain_id) != Ok(block_hash) {
+            return Ok(());
+        }
+
+        let head = ChainHead {
+            view,
+            block_hash,
+            state_root: header.state_root,
+        };
+        self.storage.save_chain_head(&head)?;
+        self.storage.save_consensus_state(&ConsensusState {
+            view,
+            finalized_height: view,
+            preferred_block: block_hash,
+            preferred_view: view,
+            committee: self.committee.clone(),
+            ..Default::default()
+        })?;
+        tracing::info!("Light client finalized view {}", view);
+        self.head = Some(head);
+        self.votes.retain(|v, _| *v > view);
+        Ok(())
+    }
+
+    /// Accept `proof` if it is against the state root of a header we verified as final.
+    pub fn verify_account_proof(&self, proof: &AccountProof) -> Result<bool, StorageError> {
+        let Some(header) = self.storage.get_block(&proof.block_hash)? else {
+            return Ok(false);
+        };
+        let finalized = self
+            .storage
+            .get_finality_qc(header.view)?
+            .is_some_and(|qc| qc.block_hash == proof.block_hash)
+            && header.view <= self.head_view();
+        Ok(finalized && proof.verify(&header.state_root))
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Map that remembers the order in which its entries were last used, for LRU caches.
+///
+/// Every use gets an increasing sequence number and is queued; `pop_oldest` walks the
+/// queue from the front, skipping uses superseded by a later one. Capacity and eviction
+/// policy (entry counts, byte budgets) are left to the cache wrapping it.
+pub struct Lru<K, V> {
+    next_seq: u64,
+    // Key -> value and the sequence number of its latest use
+    entries: HashMap<K, (V, u64)>,
+    // Uses, oldest first (entries superseded by a later use are skipped)
+    order: VecDeque<(K, u64)>,
+}
+
+impl<K: Eq + Hash + Clone, V> Lru<K, V> {
+    pub fn new() -> Self {
+        Self {
+            next_seq: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// The value of `key`, which becomes the most recently used entry.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let seq = self.next_seq;
+        self.entries.get_mut(key)?.1 = seq;
+        self.touch(key.clone(), seq);
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    /// The value of `key`, without counting as a use.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    /// Insert or replace the entry of `key` as the most recently used one; returns the
+    /// value it replaces.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let seq = self.next_seq;
+        let old = self.entries.insert(key.clone(), (value, seq));
+        self.touch(key, seq);
+        old.map(|(value, _)| value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.entries.remove(key).map(|(value, _)| value)
+    }
+
+    /// Remove and return the least recently used entry.
+    pub fn pop_oldest(&mut self) -> Option<(K, V)> {
+        while let Some((key, seq)) = self.order.pop_front() {
+            if self.entries.get(&key).is_some_and(|(_, last)| *last == seq) {
+                return self.entries.remove(&key).map(|(value, _)| (key, value));
+            }
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Record a use of `key` with sequence number `seq`
+    fn touch(&mut self, key: K, seq: u64) {
+        self.next_seq = seq + 1;
+        self.order.push_back((key, seq));
+        // Keep stale uses from piling up when the same keys are used repeatedly
+        if self.order.len() > 2 * self.entries.len().max(1) {
+            let entries = &self.entries;
+            self.order
+                .retain(|(k, s)| entries.get(k).is_some_and(|(_, last)| last == s));
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V> Default for Lru<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,29 +1,81 @@
-use jsonrpsee::server::Server;
-use ockham::consensus::{ConsensusAction, SimplexState};
+use jsonrpsee::server::middleware::rpc::RpcServiceBuilder;
+use jsonrpsee::server::{BatchRequestConfig, Server};
+use ockham::archive::ChainArchive;
+use ockham::channels::ChannelConfig;
+use ockham::conformance::ConformanceSuite;
+use ockham::consensus::{ConsensusAction, ProposalReady, ProposerConfig, SimplexState};
 use ockham::crypto::PublicKey;
-use ockham::network::{Network, NetworkEvent};
-use ockham::rpc::{OckhamRpcImpl, OckhamRpcServer};
-use ockham::state::StateManager;
-use ockham::tx_pool::TxPool;
+use ockham::devnet::{Devnet, DevnetConfig};
+use ockham::engine::{DEFAULT_EXECUTION_CACHE_ENTRIES, ExecutionCache};
+use ockham::events::EventStream;
+use ockham::export::{ChainExporter, ExportFormat};
+use ockham::health::{HealthConfig, HealthMonitor, unix_now};
+use ockham::light::{AccountProof, LightClient};
+use ockham::memory::MemoryBudget;
+use ockham::network::{
+    Network, NetworkConfig, NetworkEvent, load_or_create_node_key, node_multiaddr,
+};
+use ockham::payload::{NonceOrderedBuilder, PayloadBuilder, builder_by_name};
+use ockham::rate_limit::{ClientKeyLayer, Quota, RateLimitConfig, RateLimiter, RpcRateLimit};
+use ockham::rpc::{
+    DEFAULT_RPC_MAX_CONNECTIONS, MAX_RPC_BATCH_SIZE, MAX_RPC_REQUEST_SIZE, MAX_RPC_RESPONSE_SIZE,
+    OckhamRpcImpl, OckhamRpcServer, RpcAccessConfig, RpcCallMetrics, RpcMetrics, RpcTracing,
+};
+use ockham::shutdown::{DRAIN_TIMEOUT, ShutdownSummary};
+use ockham::state::{DEFAULT_STATE_CACHE_ENTRIES, StateCache, StateManager};
+use ockham::storage::JournalSync;
+use ockham::tx_pool::{
+    MAX_ANNOUNCED_HASHES, MAX_POOL_RESPONSE_BYTES, REBROADCAST_INTERVAL, TxPool,
+};
+use ockham::types::Address;
+use ockham::validation::{BlockValidated, DEFAULT_VALIDATION_WORKERS, ValidationPool};
 use ockham::vm::Executor;
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Duration;
 use tokio::time;
+use tracing_subscriber::EnvFilter;
+
+/// A view without progress for this long times out.
+const VIEW_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
-
     // 1. Parse Node ID from args (0, 1, 2, 3) and Gas Limit
     let args: Vec<String> = env::args().collect();
+    init_tracing(&args)?;
+
+    // Subcommands
+    if args.get(1).map(String::as_str) == Some("export-genesis") {
+        return export_genesis(&args);
+    }
+    if args.get(1).map(String::as_str) == Some("export-chain") {
+        return export_chain(&args);
+    }
+    if args.get(1).map(String::as_str) == Some("conformance") {
+        return conformance(&args);
+    }
+    if args.get(1).map(String::as_str) == Some("export") {
+        return export(&args);
+    }
+    if args.get(1).map(String::as_str) == Some("import") {
+        return import(&args);
+    }
+    if args.get(1).map(String::as_str) == Some("devnet") {
+        return devnet(&args).await;
+    }
+    if args.get(1).map(String::as_str) == Some("init") {
+        return init(&args);
+    }
+
     let id_arg = args
         .get(1)
-        .expect("Usage: cargo run -- <node_id> [--gas-limit <value>]")
+        .expect("Usage: cargo run -- <node_id> [--gas-limit <value>] [--gas-limit-target <value>] [--chain-spec <file>] [--fee-recipient <address>] [--operator <address>]... [--payload-builder nonce-ordered|priority-fee] [--memory-limit <MB>] [--vote-journal-sync always|deferred] [--export-dir <dir> [--export-format csv|parquet]] [--index-db <path>] [--sign-rpc] [--admin-rpc] [--dev] [--rpc-max-connections <n>] [--rpc-addr <ip>] [--rpc-cors <origin,...>] [--rpc-allowed-hosts <host,...>] [--rpc-tx-limit <per_sec>:<burst>] [--rpc-read-limit <per_sec>:<burst>] [--rpc-trust-proxy] [--bootnodes <multiaddr,...>] [--target-peers <n>] [--p2p-port <port>] [--nat <external-ip>] [--node-key <file>] [--channel-capacity <n>] [--log-format text|json] [--health-port <port>] [--events-file <file>] [--light] | export-genesis [--db <path>] [--at <view>] [--chain-id <id>] [--out <file>] | export-chain [--db <path>] [--out <dir>] [--format csv|parquet] [--from <view>] [--to <view>] | conformance [--out <file>] [--check <file>] | export --out <file> [--db <path>] [--to <view>] | import --in <file> [--db <path>] [--gas-limit <value>] | devnet [--validators <n>] [--rpc-port <port>] [--rpc-addr <ip>] | init <node_id> [--chain-spec <file>] [--gas-limit <value>] [--node-key <file>] [--p2p-port <port>] [--nat <external-ip>]")
         .parse::<u64>()?;
 
-    // Parse Optional --gas-limit
+    // Parse Optional --gas-limit (of a new chain; afterwards the live ChainParams apply)
     let mut block_gas_limit = ockham::types::DEFAULT_BLOCK_GAS_LIMIT;
     if let Some(val) = args
         .iter()
@@ -31,116 +83,537 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .and_then(|pos| args.get(pos + 1))
     {
         block_gas_limit = val.parse::<u64>()?;
-        log::info!("Configured Block Gas Limit: {}", block_gas_limit);
+        tracing::info!("Configured Block Gas Limit: {}", block_gas_limit);
+    }
+
+    // Parse Optional --chain-spec (the network whose transactions this node accepts, and
+    // the genesis of a fresh database)
+    let mut chain_id = ockham::types::DEFAULT_CHAIN_ID;
+    let mut hardfork = ockham::types::Hardfork::default();
+    let mut chain_spec = None;
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--chain-spec")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        let spec = ockham::chain_spec::ChainSpec::load(val)?;
+        chain_id = spec.chain_id;
+        hardfork = spec.hardfork;
+        tracing::info!(
+            "Configured Network: {} (chain id {}, {:?})",
+            spec.name,
+            chain_id,
+            hardfork
+        );
+        chain_spec = Some(spec);
+    }
+
+    // Parse Optional Proposer Settings (--fee-recipient, repeated --operator, --gas-limit-target)
+    let mut proposer = ProposerConfig {
+        // Leaves the rest of the view for the proposal to spread and be voted on
+        time_budget: Some(VIEW_TIMEOUT / 3),
+        ..Default::default()
+    };
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--fee-recipient")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        proposer.fee_recipient = val.parse::<Address>()?;
+        tracing::info!("Configured Fee Recipient: {:?}", proposer.fee_recipient);
+    }
+    for (pos, _) in args.iter().enumerate().filter(|(_, r)| *r == "--operator") {
+        if let Some(val) = args.get(pos + 1) {
+            proposer.operator_accounts.insert(val.parse::<Address>()?);
+        }
+    }
+    if !proposer.operator_accounts.is_empty() {
+        tracing::info!(
+            "Configured {} Operator Account(s)",
+            proposer.operator_accounts.len()
+        );
+    }
+    // --gas-limit-target: the header gas limit our proposals vote for (a step per block)
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--gas-limit-target")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        let target = val.parse::<u64>()?;
+        if target < ockham::fees::MIN_GAS_LIMIT {
+            return Err(format!(
+                "Gas limit target below the minimum of {}",
+                ockham::fees::MIN_GAS_LIMIT
+            )
+            .into());
+        }
+        proposer.gas_limit_target = Some(target);
+        tracing::info!("Configured Gas Limit Target: {}", target);
+    }
+
+    // Parse Optional --payload-builder (transaction selection of our proposals)
+    let mut payload_builder: Arc<dyn PayloadBuilder> = Arc::new(NonceOrderedBuilder);
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--payload-builder")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        payload_builder =
+            builder_by_name(val).ok_or_else(|| format!("Unknown payload builder: {}", val))?;
+    }
+    tracing::info!("Configured Payload Builder: {}", payload_builder.name());
+
+    // Parse Optional --memory-limit (MB, shared by the pool, orphan buffer and vote maps)
+    let mut memory_budget = MemoryBudget::unlimited();
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--memory-limit")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        let limit_mb = val.parse::<usize>()?;
+        memory_budget = MemoryBudget::new(limit_mb * 1024 * 1024);
+        tracing::info!("Configured Memory Limit: {} MB", limit_mb);
+    }
+
+    // Parse Optional --validation-workers (threads re-executing incoming blocks)
+    let mut validation_workers = DEFAULT_VALIDATION_WORKERS;
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--validation-workers")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        validation_workers = val.parse::<usize>()?;
+        tracing::info!("Configured Validation Workers: {}", validation_workers);
+    }
+
+    // Parse Optional --state-cache (entries of hot accounts, slots and code kept in memory)
+    let mut state_cache_entries = DEFAULT_STATE_CACHE_ENTRIES;
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--state-cache")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        state_cache_entries = val.parse::<usize>()?;
+        tracing::info!("Configured State Cache: {} entries", state_cache_entries);
     }
 
-    // 2. Initialize Consensus
+    // Parse Optional --execution-cache (validated blocks whose execution is kept for commit)
+    let mut execution_cache_entries = DEFAULT_EXECUTION_CACHE_ENTRIES;
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--execution-cache")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        execution_cache_entries = val.parse::<usize>()?;
+        tracing::info!(
+            "Configured Execution Cache: {} blocks",
+            execution_cache_entries
+        );
+    }
+
+    // Parse Optional --vote-journal-sync (fsync of our own votes before they are sent)
+    let mut journal_sync = JournalSync::Always;
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--vote-journal-sync")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        journal_sync = match val.as_str() {
+            "always" => JournalSync::Always,
+            "deferred" => JournalSync::Deferred,
+            _ => return Err(format!("Unknown vote journal sync: {}", val).into()),
+        };
+        tracing::info!("Configured Vote Journal Sync: {:?}", journal_sync);
+    }
+
+    // Parse Optional --bootnodes (comma-separated multiaddrs) and --target-peers
+    let mut network_config = NetworkConfig::default();
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--bootnodes")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        for addr in val.split(',').filter(|a| !a.is_empty()) {
+            network_config.bootnodes.push(addr.parse()?);
+        }
+    }
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--target-peers")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        network_config.target_peers = val.parse::<usize>()?;
+    }
+
+    // Parse Optional --p2p-port, --nat and --node-key (see `init`)
+    let p2p_port = p2p_port(&args, id_arg)?;
+    if let Some(ip) = nat_addr(&args)? {
+        network_config.listen_addr = std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED);
+        network_config.external_addr = Some(ip);
+        tracing::info!("Advertising external address {}", ip);
+    }
+    let node_key = load_or_create_node_key(node_key_path(&args, id_arg))?;
+    tracing::info!("Peer ID: {}", node_key.public().to_peer_id());
+    network_config.node_key = Some(node_key);
+
+    // Parse Optional --channel-capacity (of the channels between subsystems)
+    let mut channels = ChannelConfig::default();
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--channel-capacity")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        channels = ChannelConfig::uniform(val.parse::<usize>()?);
+        tracing::info!("Configured Channel Capacity: {}", val);
+    }
+    network_config.command_queue = channels.network_commands;
+    network_config.chain_id = chain_id;
+
+    // Light mode: follow headers and certificates only
+    if args.iter().any(|r| r == "--light") {
+        return run_light(id_arg, network_config, channels, &args).await;
+    }
+
+    // 2. Initialize Consensus (the spec's validators form the genesis committee, if any)
     let (my_id, my_key) = ockham::crypto::generate_keypair_from_id(id_arg);
-    let committee: Vec<PublicKey> = (0..5)
-        .map(|i| ockham::crypto::generate_keypair_from_id(i).0)
-        .collect();
+    let committee = genesis_committee(chain_spec.as_ref())?;
 
     let db_path = format!("./db/node_{}", id_arg);
-    let storage: Arc<dyn ockham::storage::Storage> =
-        Arc::new(ockham::storage::RedbStorage::new(db_path).expect("Failed to create DB"));
+    let storage: Arc<dyn ockham::storage::Storage> = Arc::new(
+        ockham::storage::RedbStorage::new(db_path)
+            .unwrap_or_else(|e| panic!("Failed to open DB: {}", e))
+            .with_journal_sync(journal_sync),
+    );
+
+    // Genesis allocations and validator stakes of the chain spec, on a fresh database
+    if let Some(spec) = chain_spec
+        .as_ref()
+        .filter(|spec| !spec.validators.is_empty())
+        && storage.get_consensus_state()?.is_none()
+    {
+        let state_root = spec.init_genesis(storage.clone())?;
+        tracing::info!(
+            "Initialized Genesis: {} validators, {} accounts, state root {:?}",
+            spec.validators.len(),
+            spec.accounts.len(),
+            state_root
+        );
+    }
 
     // 2.1 Initialize Execution Layer
-    let tx_pool = Arc::new(TxPool::new(storage.clone()));
+    let tx_pool = Arc::new(
+        TxPool::new(storage.clone())
+            .with_chain_id(chain_id)
+            .with_memory_budget(&memory_budget),
+    );
+    // Local transactions journaled by the last shutdown
+    match tx_pool.load_journal() {
+        Ok(0) => {}
+        Ok(n) => tracing::info!("Restored {} local transactions from the pool journal", n),
+        Err(e) => tracing::warn!("Failed to load the pool journal: {}", e),
+    }
 
     // Channel for broadcasting transactions from RPC to Network
-    let (bg_tx_sender, mut bg_tx_receiver) = tokio::sync::mpsc::channel(100);
+    let (bg_tx_sender, mut bg_tx_receiver) = tokio::sync::mpsc::channel(channels.rpc_transactions);
 
     // We already have `storage: Arc<dyn Storage>`.
     // We need to create StateManager.
-    // Try to load initial root from persistent consensus state
+    // Start from the committed chain head (SimplexState::recover finishes the job)
     let initial_root = storage
-        .get_consensus_state()
+        .get_chain_head()
         .ok()
         .flatten()
-        .and_then(|cs| storage.get_block(&cs.preferred_block).ok().flatten())
-        .map(|b| b.state_root);
+        .map(|head| head.state_root);
 
-    log::info!("Starting StateManager with Root: {:?}", initial_root);
+    tracing::info!("Starting StateManager with Root: {:?}", initial_root);
 
-    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), initial_root)));
-    let executor = Executor::new(state_manager.clone(), block_gas_limit);
+    let state_cache =
+        Arc::new(StateCache::new(state_cache_entries).with_memory_budget(&memory_budget));
+    let state_manager = Arc::new(Mutex::new(
+        StateManager::new(storage.clone(), initial_root).with_cache(state_cache.clone()),
+    ));
+    let execution_cache = Arc::new(ExecutionCache::new(execution_cache_entries));
+    let executor = Executor::new(state_manager.clone(), block_gas_limit)
+        .with_chain_id(chain_id)
+        .with_hardfork(hardfork)
+        .with_execution_cache(execution_cache.clone());
 
+    // Consensus events, served to RPC subscribers and optionally appended to a file
+    let events = EventStream::default();
     let mut state = SimplexState::new(
-        my_id,
-        my_key,
+        my_id.clone(),
+        my_key.clone(),
         committee,
         storage.clone(),
         tx_pool.clone(),
         executor.clone(),
         block_gas_limit,
+    )
+    .with_chain_id(chain_id)
+    .with_proposer_config(proposer)
+    .with_payload_builder(payload_builder)
+    .with_memory_budget(&memory_budget)
+    .with_proposal_pipeline()
+    .with_async_validation()
+    .with_events(events.clone());
+
+    // Crash Recovery: replay interrupted commits and re-check unfinalized blocks
+    let recovery = state.recover()?;
+    tracing::info!(
+        "Recovered chain head {:?} (replayed {:?}, discarded {:?})",
+        recovery.head.as_ref().map(|h| h.view),
+        recovery.replayed,
+        recovery.discarded
     );
 
+    // 3. Initialize Network (before the RPC server, which may manage its peers)
+    let health = HealthMonitor::new(HealthConfig::default());
+    // Node 0 Listen on 9000, others random (0), unless --p2p-port says otherwise
+    network_config.port = p2p_port;
+    // Bootnode logic: If not node 0 and no --bootnodes given, dial node 0
+    if id_arg != 0 && network_config.bootnodes.is_empty() {
+        network_config
+            .bootnodes
+            .push("/ip4/127.0.0.1/tcp/9000".parse()?);
+    }
+    tracing::info!("Bootnodes: {:?}", network_config.bootnodes);
+    network_config.peer_store = Some(storage.clone());
+    network_config.health = Some(health.clone());
+    // Gossip carries the largest transactions and blocks the chain accepts
+    if let Some(state) = storage.get_consensus_state()? {
+        network_config.peer_score.topic_limits = network_config
+            .peer_score
+            .topic_limits
+            .covering(&state.params);
+    }
+    let mut network = Network::with_config(network_config).await?;
+
     // Start RPC Server
     let rpc_port = 8545 + id_arg as u16; // 8545, 8546, ...
-    let addr = format!("127.0.0.1:{}", rpc_port);
-    let server = Server::builder().build(addr).await?;
-    let rpc_impl = OckhamRpcImpl::new(
+    let rpc_access = rpc_access_config(&args)?;
+    let rate_limiter = RateLimiter::new(rate_limit_config(&args)?);
+    let addr = std::net::SocketAddr::new(rpc_access.listen_addr, rpc_port);
+    let mut rpc_max_connections = DEFAULT_RPC_MAX_CONNECTIONS;
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--rpc-max-connections")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        rpc_max_connections = val.parse::<u32>()?;
+        tracing::info!("RPC Max Connections: {}", rpc_max_connections);
+    }
+    let rpc_metrics = RpcMetrics::new();
+    let metrics_layer = rpc_metrics.clone();
+    let server = Server::builder()
+        .max_request_body_size(MAX_RPC_REQUEST_SIZE)
+        .max_response_body_size(MAX_RPC_RESPONSE_SIZE)
+        .max_connections(rpc_max_connections)
+        .set_batch_request_config(BatchRequestConfig::Limit(MAX_RPC_BATCH_SIZE))
+        .set_http_middleware(
+            tower::ServiceBuilder::new()
+                .option_layer(rpc_access.host_filter()?)
+                .option_layer(rpc_access.cors_layer()?)
+                .layer(ClientKeyLayer::new(rate_limiter.config().trust_proxy)),
+        )
+        .set_rpc_middleware(
+            RpcServiceBuilder::new()
+                .layer_fn(RpcTracing)
+                .layer_fn(move |service| RpcCallMetrics::new(service, metrics_layer.clone()))
+                .layer_fn(move |service| RpcRateLimit::new(service, rate_limiter.clone())),
+        )
+        .build(addr)
+        .await?;
+    let mut rpc_impl = OckhamRpcImpl::new(
         storage.clone(),
         tx_pool.clone(),
         executor.clone(),
         block_gas_limit,
         bg_tx_sender,
     );
-    let handle = server.start(rpc_impl.into_rpc());
-    log::info!("RPC Server started on port {}", rpc_port);
+    rpc_impl = rpc_impl
+        .with_health(health.clone())
+        .with_events(events.clone());
+    if args.iter().any(|r| r == "--admin-rpc") {
+        rpc_impl = rpc_impl.with_admin(network.handle());
+        tracing::info!("Admin RPC enabled");
+    }
+    if args.iter().any(|r| r == "--dev") {
+        let faucet = ockham::faucet::Faucet::new(executor.state.clone(), my_id, Default::default());
+        rpc_impl = rpc_impl.with_faucet(faucet);
+        tracing::warn!("Dev RPC enabled: faucet credits bypass consensus (sole validator only)");
+    }
+    if args.iter().any(|r| r == "--sign-rpc") {
+        rpc_impl = rpc_impl.with_signing_key(my_key);
+        tracing::info!("RPC Response Signing enabled");
+    }
+    #[allow(unused_mut)]
+    let mut rpc_module = rpc_impl.into_rpc();
+
+    // Optional Explorer Index (follow mode), queried through extra RPC methods
+    #[cfg(feature = "indexer")]
+    if let Some(path) = args
+        .iter()
+        .position(|r| r == "--index-db")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        let indexer = Arc::new(ockham::indexer::Indexer::new(path, storage.clone())?);
+        let indexer_rpc = ockham::indexer::IndexerRpcImpl::new(indexer.clone());
+        rpc_module.merge(ockham::indexer::IndexerRpcServer::into_rpc(indexer_rpc))?;
+        tracing::info!("Indexing finalized chain into {}", path);
+        tokio::spawn(async move {
+            if let Err(e) = indexer.follow(Duration::from_secs(5)).await {
+                tracing::error!("Indexer stopped: {}", e);
+            }
+        });
+    }
+    let handle = server.start(rpc_module);
+    tracing::info!("RPC Server started on port {}", rpc_port);
 
-    log::info!("Starting Node {}", id_arg);
+    // Optional HTTP probes (/healthz, /readyz) for orchestrators
+    if let Some(val) = args
+        .iter()
+        .position(|r| r == "--health-port")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        let port = val.parse::<u16>()?;
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+        tracing::info!("Health endpoints on port {}", port);
+        let (health, storage) = (health.clone(), storage.clone());
+        tokio::spawn(async move {
+            if let Err(e) = ockham::health::serve_http(listener, health, storage).await {
+                tracing::error!("Health server stopped: {}", e);
+            }
+        });
+    }
 
-    // 3. Initialize Network
-    // Node 0 Listen on 9000, others random (0)
-    let port = if id_arg == 0 { 9000 } else { 0 };
-    let mut network = Network::new(port).await?;
+    // Optional Analytics Export (follow mode; the DB is locked by the node while it runs)
+    if let Some(dir) = args
+        .iter()
+        .position(|r| r == "--export-dir")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        let format = match args
+            .iter()
+            .position(|r| r == "--export-format")
+            .and_then(|pos| args.get(pos + 1))
+        {
+            Some(f) => f.parse::<ExportFormat>()?,
+            None => ExportFormat::Csv,
+        };
+        let exporter = ChainExporter::new(storage.clone(), dir, format);
+        tracing::info!("Exporting finalized chain data to {} ({:?})", dir, format);
+        tokio::spawn(async move {
+            if let Err(e) = exporter.follow(Duration::from_secs(5)).await {
+                tracing::error!("Chain export stopped: {}", e);
+            }
+        });
+    }
 
-    // Bootnode logic: If not node 0, dial node 0
-    if id_arg != 0 {
-        log::info!("Dialing bootnode...");
-        network.dial("/ip4/127.0.0.1/tcp/9000").await;
+    // Optional Consensus Event Log (JSON lines)
+    if let Some(path) = args
+        .iter()
+        .position(|r| r == "--events-file")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        let (receiver, path) = (events.subscribe(), path.clone());
+        tracing::info!("Writing consensus events to {}", path);
+        tokio::spawn(async move {
+            if let Err(e) = ockham::events::write_jsonl(receiver, &path).await {
+                tracing::error!("Event log stopped: {}", e);
+            }
+        });
     }
 
+    tracing::info!("Starting Node {}", id_arg);
+
     // 4. Initialize Consensus State
 
     // 5. Timer for Views (Simple timeout for prototype)
-    let mut view_timer = time::interval(Duration::from_secs(30));
+    let mut view_timer = time::interval(VIEW_TIMEOUT);
+
+    // Timer for re-gossiping local transactions and announcing the pool to peers
+    let mut mempool_timer = time::interval(REBROADCAST_INTERVAL);
 
     // State for startup synchronization
     let mut connected_peers = 0;
     let mut consensus_started = false;
+    // Proposals executing in the background (drained on shutdown)
+    let mut proposals_in_flight = 0usize;
+
+    // Our proposals are executed in the background and come back as ProposalReady
+    let (proposal_sender, mut proposal_receiver) = tokio::sync::mpsc::channel::<ProposalReady>(1);
+    // Incoming blocks are executed by the validation workers and come back as BlockValidated
+    let (validated_sender, mut validated_receiver) =
+        tokio::sync::mpsc::unbounded_channel::<BlockValidated>();
+    let validation_pool = ValidationPool::new(validation_workers, validated_sender);
 
     // 6. Main Event Loop
     loop {
+        health.observe_consensus(state.current_view, state.finalized_height, unix_now());
+        if let Some(job) = state.take_proposal_job() {
+            tracing::info!(
+                "Building proposal for View {} in the background",
+                job.view()
+            );
+            let sender = proposal_sender.clone();
+            proposals_in_flight += 1;
+            tokio::task::spawn_blocking(move || {
+                if sender.blocking_send(job.execute()).is_err() {
+                    tracing::warn!("Proposal channel closed, dropping an executed proposal");
+                }
+            });
+        }
+        for job in state.take_validation_jobs() {
+            tracing::debug!("Queueing validation of View {}", job.view());
+            validation_pool.submit(job);
+        }
+        // Our proposal may be waiting for its slot (ChainParams::min_block_interval)
+        let slot_wait = state
+            .next_slot()
+            .map(|slot| Duration::from_secs(slot.saturating_sub((state.clock)())));
         tokio::select! {
             // D. Broadcast Transactions from RPC
             Some(tx) = bg_tx_receiver.recv() => {
-                log::info!("Broadcasting Transaction from RPC via Gossip");
+                tracing::info!("Broadcasting Transaction from RPC via Gossip");
                 network.broadcast_transaction(tx).await;
             }
             // A. Network Events
             Some(event) = network.next_event() => {
                 let actions = match event {
                     NetworkEvent::VoteReceived(vote) => {
-                        log::info!("Received Vote View {} from {:?}", vote.view, vote.author);
+                        tracing::info!("Received Vote View {} from {:?}", vote.view, vote.author);
+                        health.observe_network_view(vote.view);
                         let old_view = state.current_view;
                         let res = state.on_vote(vote);
                         if state.current_view > old_view {
-                            log::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                            tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                            view_timer.reset();
+                        }
+                        res
+                    }
+                    NetworkEvent::AggregateVoteReceived(aggregate) => {
+                        tracing::debug!("Received Aggregate View {} ({} signers)", aggregate.view, aggregate.signers.len());
+                        health.observe_network_view(aggregate.view);
+                        let old_view = state.current_view;
+                        let res = state.on_aggregate_vote(aggregate);
+                        if state.current_view > old_view {
+                            tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
                             view_timer.reset();
                         }
                         res
                     }
-                    NetworkEvent::BlockReceived(block) => {
-                        log::info!("Received Block: {:?}", block);
-                        state.on_proposal(block)
+                    NetworkEvent::BlockReceived(compact, peer_id) => {
+                        tracing::info!("Received Block: {:?}", compact.header);
+                        health.observe_network_view(compact.header.view);
+                        state.on_compact_block(compact, peer_id)
                     }
                     NetworkEvent::PeerConnected(pid) => {
-                        log::info!("Peer Connected: {}", pid);
+                        tracing::info!("Peer Connected: {}", pid);
+                        // Tell the new peer where we are (and learn where it is from its status)
+                        network.broadcast_sync(state.status()).await;
                         connected_peers += 1;
                         if connected_peers >= 1 && !consensus_started {
-                            log::info!("Enough peers connected ({}). Starting Consensus!", connected_peers);
+                            tracing::info!("Enough peers connected ({}). Starting Consensus!", connected_peers);
                             consensus_started = true;
                             // Reset timer to align with start
                             view_timer.reset();
@@ -152,9 +625,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                  while let Some(action) = queue.pop() {
                                      match action {
                                          ConsensusAction::BroadcastVote(vote) => { network.broadcast_vote(vote).await; }
+                                         ConsensusAction::BroadcastAggregate(aggregate) => { network.broadcast_aggregate_vote(aggregate).await; }
                                          ConsensusAction::BroadcastEvidence(evidence) => { network.broadcast_evidence(evidence).await; }
+                                         ConsensusAction::BroadcastProposalEvidence(evidence) => { network.broadcast_proposal_evidence(evidence).await; }
+                                         ConsensusAction::BroadcastDecryptionShares(shares) => { network.broadcast_sync(ockham::types::SyncMessage::DecryptionShares(shares)).await; }
                                          ConsensusAction::BroadcastBlock(block) => {
-                                             log::info!("Broadcasting Block: {:?}", block);
+                                             tracing::info!("Broadcasting Block: {:?}", block);
                                              network.broadcast_block(block.clone()).await;
                                              // Loopback removed
                                          }
@@ -164,6 +640,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                          ConsensusAction::SendBlock(block, _) => {
                                              network.broadcast_sync(ockham::types::SyncMessage::ResponseBlock(Box::new(block))).await;
                                          }
+                                         ConsensusAction::RequestTransactions(hash, ids) => {
+                                             network.broadcast_sync(ockham::types::SyncMessage::RequestTransactions(hash, ids)).await;
+                                         }
+                                         ConsensusAction::RequestBody(hash) => {
+                                             network.broadcast_sync(ockham::types::SyncMessage::RequestBody(hash)).await;
+                                         }
                                      }
                                  }
                              }
@@ -173,28 +655,114 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     NetworkEvent::SyncMessageReceived(msg, peer_id) => {
                         match msg {
                             ockham::types::SyncMessage::RequestBlock(hash) => {
-                                log::info!("Received Block Request for {:?}", hash);
+                                tracing::info!("Received Block Request for {:?}", hash);
                                 state.on_block_request(hash, peer_id)
                             }
                             ockham::types::SyncMessage::ResponseBlock(block) => {
-                                log::info!("Received Block Response (Sync) View {}", block.view);
-                                state.on_block_response(*block)
+                                tracing::info!("Received Block Response (Sync) View {}", block.view);
+                                state.on_block_response_from(*block, peer_id)
+                            }
+                            ockham::types::SyncMessage::RequestAccountProof(address) => {
+                                let head = storage.get_chain_head().ok().flatten();
+                                let proof = head.and_then(|head| {
+                                    AccountProof::from_state(&head, &state_manager.lock().unwrap(), address).ok()
+                                });
+                                if let Some(proof) = proof {
+                                    network.broadcast_sync(ockham::types::SyncMessage::ResponseAccountProof(Box::new(proof))).await;
+                                }
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::ResponseAccountProof(_) => Ok(vec![]),
+                            ockham::types::SyncMessage::RequestWitness(hash) => {
+                                if let Ok(Some(witness)) = storage.get_witness(&hash) {
+                                    network.broadcast_sync(ockham::types::SyncMessage::ResponseWitness(hash, Box::new(witness))).await;
+                                }
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::ResponseWitness(hash, witness) => {
+                                state.on_witness(hash, *witness);
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::RequestTransactions(hash, ids) => {
+                                let txs = state.on_transactions_request(hash, &ids);
+                                if !txs.is_empty() {
+                                    network.broadcast_sync(ockham::types::SyncMessage::ResponseTransactions(hash, txs)).await;
+                                }
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::ResponseTransactions(hash, txs) => {
+                                state.on_block_transactions(hash, txs)
+                            }
+                            ockham::types::SyncMessage::RequestBody(hash) => {
+                                if let Ok(Some(block)) = storage.get_block(&hash) {
+                                    let (_, body) = block.split();
+                                    network.broadcast_sync(ockham::types::SyncMessage::ResponseBody(hash, Box::new(body))).await;
+                                }
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::ResponseBody(hash, body) => {
+                                state.on_block_body(hash, *body)
+                            }
+                            ockham::types::SyncMessage::PoolHashes(hashes) => {
+                                let missing = tx_pool.missing(&hashes);
+                                if !missing.is_empty() {
+                                    tracing::debug!("Requesting {} Pool Transactions", missing.len());
+                                    network.broadcast_sync(ockham::types::SyncMessage::RequestPoolTransactions(missing)).await;
+                                }
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::RequestPoolTransactions(hashes) => {
+                                let txs = tx_pool.get_transactions(&hashes, MAX_POOL_RESPONSE_BYTES);
+                                if !txs.is_empty() {
+                                    network.broadcast_sync(ockham::types::SyncMessage::ResponsePoolTransactions(txs)).await;
+                                }
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::ResponsePoolTransactions(txs) => {
+                                let added = txs.into_iter().filter(|tx| tx_pool.add_transaction(tx.clone()).is_ok()).count();
+                                if added > 0 {
+                                    tracing::info!("Added {} transactions from peer pools. Pool size: {}", added, tx_pool.len());
+                                }
+                                Ok(vec![])
+                            }
+                            ockham::types::SyncMessage::DecryptionShares(shares) => {
+                                state.on_decryption_shares(shares).map(|_| vec![])
+                            }
+                            ockham::types::SyncMessage::Status { current_view, finalized_height, head_hash, high_qc } => {
+                                // A peer behind us (e.g. just rejoined) catches up from ours
+                                if current_view < state.current_view {
+                                    network.broadcast_sync(state.status()).await;
+                                }
+                                let old_view = state.current_view;
+                                let res = state.on_status(current_view, finalized_height, head_hash, high_qc);
+                                if state.current_view > old_view {
+                                    tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                                    view_timer.reset();
+                                }
+                                res
                             }
                         }
                     }
                     NetworkEvent::EvidenceReceived(evidence) => {
-                        log::info!("Received Equivocation Evidence");
+                        tracing::info!("Received Equivocation Evidence");
                         if state.evidence_pool.add_evidence(evidence) {
-                            log::warn!("New Evidence Added to Pool");
+                            tracing::warn!("New Evidence Added to Pool");
+                        }
+                        Ok(vec![])
+                    }
+                    NetworkEvent::ProposalEvidenceReceived(evidence) => {
+                        tracing::info!("Received Double Proposal Evidence");
+                        if state.evidence_pool.add_proposal_evidence(evidence) {
+                            tracing::warn!("New Proposal Evidence Added to Pool");
                         }
                         Ok(vec![])
                     }
                     NetworkEvent::TransactionReceived(tx) => {
-                        log::info!("Received Transaction from {:?}", tx.public_key);
+                        tracing::info!("Received Transaction from {:?}", tx.public_key);
                         if let Err(e) = tx_pool.add_transaction(tx) {
-                             log::warn!("Failed to add transaction: {:?}", e);
+                             tracing::warn!("Failed to add transaction: {:?}", e);
                         } else {
-                             log::info!("Added transaction to pool. Pool size: {}", tx_pool.len());
+                             tracing::info!("Added transaction to pool. Pool size: {}", tx_pool.len());
                         }
                         Ok(vec![])
                     }
@@ -206,24 +774,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                              while let Some(action) = action_queue.pop() {
                                  match action {
                                      ConsensusAction::BroadcastVote(vote) => {
-                                         log::info!("Broadcasting Vote for View {}", vote.view);
+                                         tracing::info!("Broadcasting Vote for View {}", vote.view);
                                          network.broadcast_vote(vote.clone()).await;
 
                                          // Loopback: Apply own vote locally
                                          let old_view = state.current_view;
                                          if let Ok(new_actions) = state.on_vote(vote) {
                                              if state.current_view > old_view {
-                                                 log::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                                                 tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
                                                  view_timer.reset();
                                              }
                                              action_queue.extend(new_actions);
                                          }
                                      }
+                                     ConsensusAction::BroadcastAggregate(aggregate) => {
+                                         network.broadcast_aggregate_vote(aggregate).await;
+                                     }
                                      ConsensusAction::BroadcastEvidence(evidence) => {
                                          network.broadcast_evidence(evidence).await;
                                      }
+                                     ConsensusAction::BroadcastProposalEvidence(evidence) => {
+                                         network.broadcast_proposal_evidence(evidence).await;
+                                     }
+                                     ConsensusAction::BroadcastDecryptionShares(shares) => {
+                                         network.broadcast_sync(ockham::types::SyncMessage::DecryptionShares(shares)).await;
+                                     }
                                      ConsensusAction::BroadcastBlock(block) => {
-                                         log::info!("Broadcasting Block: {:?}", block);
+                                         tracing::info!("Broadcasting Block: {:?}", block);
                                          network.broadcast_block(block.clone()).await;
                                          // Loopback removed
                                      }
@@ -234,11 +811,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                          // For MVP, broadcast response to gossip
                                          network.broadcast_sync(ockham::types::SyncMessage::ResponseBlock(Box::new(block))).await;
                                      }
+                                     ConsensusAction::RequestTransactions(hash, ids) => {
+                                         network.broadcast_sync(ockham::types::SyncMessage::RequestTransactions(hash, ids)).await;
+                                     }
+                                     ConsensusAction::RequestBody(hash) => {
+                                         network.broadcast_sync(ockham::types::SyncMessage::RequestBody(hash)).await;
+                                     }
                                  }
                              }
                         }
                     },
-                    Err(e) => log::error!("Consensus Error: {:?}", e),
+                    Err(e) => tracing::error!("Consensus Error: {:?}", e),
+                }
+            }
+
+            // E. Mempool Anti-Entropy: re-gossip pending local txs, announce the pool
+            _ = mempool_timer.tick() => {
+                let local = tx_pool.local_transactions();
+                if !local.is_empty() {
+                    tracing::info!("Rebroadcasting {} pending local transactions", local.len());
+                }
+                for tx in local {
+                    network.broadcast_transaction(tx).await;
+                }
+                let hashes = tx_pool.hashes(MAX_ANNOUNCED_HASHES);
+                if !hashes.is_empty() {
+                    network.broadcast_sync(ockham::types::SyncMessage::PoolHashes(hashes)).await;
                 }
             }
 
@@ -248,28 +846,151 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     continue;
                 }
 
+                tracing::debug!("Orphan buffer: {:?}", state.orphan_metrics());
+                let cache_metrics = state_cache.metrics();
+                tracing::debug!("State cache: {:?} (hit rate {:.2})", cache_metrics, cache_metrics.hit_rate());
+                tracing::debug!("Execution cache: {:?}", execution_cache.metrics());
+                tracing::debug!("RPC calls: {:?}", rpc_metrics.snapshot());
+                tracing::debug!("Channels: {:?}", network.channel_metrics().snapshot());
+
                 // View Timeout processing
                 match state.on_timeout(state.current_view) {
                      Ok(mut action_queue) => {
                          while let Some(action) = action_queue.pop() {
                              match action {
                                  ConsensusAction::BroadcastVote(vote) => {
-                                     log::info!("Broadcasting Vote for View {}", vote.view);
+                                     tracing::info!("Broadcasting Vote for View {}", vote.view);
+                                     network.broadcast_vote(vote.clone()).await;
+                                     let old_view = state.current_view;
+                                     if let Ok(new_actions) = state.on_vote(vote) {
+                                         if state.current_view > old_view {
+                                             tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                                             view_timer.reset();
+                                         }
+                                         action_queue.extend(new_actions);
+                                     }
+                                 }
+                                 ConsensusAction::BroadcastAggregate(aggregate) => {
+                                     network.broadcast_aggregate_vote(aggregate).await;
+                                 }
+                                 ConsensusAction::BroadcastEvidence(evidence) => {
+                                     network.broadcast_evidence(evidence).await;
+                                 }
+                                 ConsensusAction::BroadcastProposalEvidence(evidence) => {
+                                     network.broadcast_proposal_evidence(evidence).await;
+                                 }
+                                 ConsensusAction::BroadcastDecryptionShares(shares) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::DecryptionShares(shares)).await;
+                                 }
+                                 ConsensusAction::BroadcastBlock(block) => {
+                                     tracing::info!("Broadcasting Block: {:?}", block);
+                                     network.broadcast_block(block).await;
+                                 }
+                                 ConsensusAction::BroadcastRequest(hash) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestBlock(hash)).await;
+                                 }
+                                 ConsensusAction::SendBlock(block, _) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::ResponseBlock(Box::new(block))).await;
+                                 }
+                                 ConsensusAction::RequestTransactions(hash, ids) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestTransactions(hash, ids)).await;
+                                 }
+                                 ConsensusAction::RequestBody(hash) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestBody(hash)).await;
+                                 }
+                             }
+                         }
+                     },
+                     Err(e) => tracing::error!("Timeout Error: {:?}", e),
+                }
+            }
+
+            // E. Proposal executed in the background
+            Some(ready) = proposal_receiver.recv() => {
+                proposals_in_flight = proposals_in_flight.saturating_sub(1);
+                match state.on_proposal_ready(ready) {
+                     Ok(mut action_queue) => {
+                         while let Some(action) = action_queue.pop() {
+                             match action {
+                                 ConsensusAction::BroadcastVote(vote) => {
+                                     tracing::info!("Broadcasting Vote for View {}", vote.view);
+                                     network.broadcast_vote(vote.clone()).await;
+                                     let old_view = state.current_view;
+                                     if let Ok(new_actions) = state.on_vote(vote) {
+                                         if state.current_view > old_view {
+                                             tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                                             view_timer.reset();
+                                         }
+                                         action_queue.extend(new_actions);
+                                     }
+                                 }
+                                 ConsensusAction::BroadcastAggregate(aggregate) => {
+                                     network.broadcast_aggregate_vote(aggregate).await;
+                                 }
+                                 ConsensusAction::BroadcastEvidence(evidence) => {
+                                     network.broadcast_evidence(evidence).await;
+                                 }
+                                 ConsensusAction::BroadcastProposalEvidence(evidence) => {
+                                     network.broadcast_proposal_evidence(evidence).await;
+                                 }
+                                 ConsensusAction::BroadcastDecryptionShares(shares) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::DecryptionShares(shares)).await;
+                                 }
+                                 ConsensusAction::BroadcastBlock(block) => {
+                                     tracing::info!("Broadcasting Block: {:?}", block);
+                                     network.broadcast_block(block).await;
+                                 }
+                                 ConsensusAction::BroadcastRequest(hash) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestBlock(hash)).await;
+                                 }
+                                 ConsensusAction::SendBlock(block, _) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::ResponseBlock(Box::new(block))).await;
+                                 }
+                                 ConsensusAction::RequestTransactions(hash, ids) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestTransactions(hash, ids)).await;
+                                 }
+                                 ConsensusAction::RequestBody(hash) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestBody(hash)).await;
+                                 }
+                             }
+                         }
+                     },
+                     Err(e) => tracing::error!("Proposal Error: {:?}", e),
+                }
+            }
+
+            // G. Slot of our deferred proposal (minimum block interval) has started
+            _ = time::sleep(slot_wait.unwrap_or_default()), if slot_wait.is_some() => {
+                match state.on_slot() {
+                     Ok(mut action_queue) => {
+                         while let Some(action) = action_queue.pop() {
+                             match action {
+                                 ConsensusAction::BroadcastVote(vote) => {
+                                     tracing::info!("Broadcasting Vote for View {}", vote.view);
                                      network.broadcast_vote(vote.clone()).await;
                                      let old_view = state.current_view;
                                      if let Ok(new_actions) = state.on_vote(vote) {
                                          if state.current_view > old_view {
-                                             log::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                                             tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
                                              view_timer.reset();
                                          }
                                          action_queue.extend(new_actions);
                                      }
                                  }
+                                 ConsensusAction::BroadcastAggregate(aggregate) => {
+                                     network.broadcast_aggregate_vote(aggregate).await;
+                                 }
                                  ConsensusAction::BroadcastEvidence(evidence) => {
                                      network.broadcast_evidence(evidence).await;
                                  }
+                                 ConsensusAction::BroadcastProposalEvidence(evidence) => {
+                                     network.broadcast_proposal_evidence(evidence).await;
+                                 }
+                                 ConsensusAction::BroadcastDecryptionShares(shares) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::DecryptionShares(shares)).await;
+                                 }
                                  ConsensusAction::BroadcastBlock(block) => {
-                                     log::info!("Broadcasting Block: {:?}", block);
+                                     tracing::info!("Broadcasting Block: {:?}", block);
                                      network.broadcast_block(block).await;
                                  }
                                  ConsensusAction::BroadcastRequest(hash) => {
@@ -278,27 +999,659 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                  ConsensusAction::SendBlock(block, _) => {
                                      network.broadcast_sync(ockham::types::SyncMessage::ResponseBlock(Box::new(block))).await;
                                  }
+                                 ConsensusAction::RequestTransactions(hash, ids) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestTransactions(hash, ids)).await;
+                                 }
+                                 ConsensusAction::RequestBody(hash) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestBody(hash)).await;
+                                 }
                              }
                          }
                      },
-                     Err(e) => log::error!("Timeout Error: {:?}", e),
+                     Err(e) => tracing::error!("Slot Proposal Error: {:?}", e),
+                }
+            }
+
+            // F. Block executed by a validation worker
+            Some(validated) = validated_receiver.recv() => {
+                match state.on_block_validated(validated) {
+                     Ok(mut action_queue) => {
+                         while let Some(action) = action_queue.pop() {
+                             match action {
+                                 ConsensusAction::BroadcastVote(vote) => {
+                                     tracing::info!("Broadcasting Vote for View {}", vote.view);
+                                     network.broadcast_vote(vote.clone()).await;
+                                     let old_view = state.current_view;
+                                     if let Ok(new_actions) = state.on_vote(vote) {
+                                         if state.current_view > old_view {
+                                             tracing::info!("View Advanced to {}. Resetting Timer.", state.current_view);
+                                             view_timer.reset();
+                                         }
+                                         action_queue.extend(new_actions);
+                                     }
+                                 }
+                                 ConsensusAction::BroadcastAggregate(aggregate) => {
+                                     network.broadcast_aggregate_vote(aggregate).await;
+                                 }
+                                 ConsensusAction::BroadcastEvidence(evidence) => {
+                                     network.broadcast_evidence(evidence).await;
+                                 }
+                                 ConsensusAction::BroadcastProposalEvidence(evidence) => {
+                                     network.broadcast_proposal_evidence(evidence).await;
+                                 }
+                                 ConsensusAction::BroadcastDecryptionShares(shares) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::DecryptionShares(shares)).await;
+                                 }
+                                 ConsensusAction::BroadcastBlock(block) => {
+                                     tracing::info!("Broadcasting Block: {:?}", block);
+                                     network.broadcast_block(block).await;
+                                 }
+                                 ConsensusAction::BroadcastRequest(hash) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestBlock(hash)).await;
+                                 }
+                                 ConsensusAction::SendBlock(block, _) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::ResponseBlock(Box::new(block))).await;
+                                 }
+                                 ConsensusAction::RequestTransactions(hash, ids) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestTransactions(hash, ids)).await;
+                                 }
+                                 ConsensusAction::RequestBody(hash) => {
+                                     network.broadcast_sync(ockham::types::SyncMessage::RequestBody(hash)).await;
+                                 }
+                             }
+                         }
+                     },
+                     Err(e) => tracing::error!("Validation Error: {:?}", e),
                 }
             }
 
             // C. Shutdown Signal
             _ = tokio::signal::ctrl_c() => {
-                log::info!("Shutdown signal received. Stopping RPC server...");
+                tracing::info!("Shutdown signal received. Stopping RPC server...");
                 let _ = handle.stop();
                 handle.stopped().await;
-                log::info!("RPC server stopped.");
-                log::info!("Shutting down Node {}...", id_arg);
+                tracing::info!("RPC server stopped.");
+                tracing::info!("Shutting down Node {}...", id_arg);
+                break;
+            }
+        }
+    }
+
+    // 7. Graceful Shutdown: let in-flight executions finish so their blocks are stored
+    // (the resulting votes are not sent; we are leaving), then persist and disconnect
+    let mut summary = ShutdownSummary::default();
+    let deadline = time::Instant::now() + DRAIN_TIMEOUT;
+    while proposals_in_flight > 0 || state.pending_validations() > 0 {
+        tokio::select! {
+            Some(ready) = proposal_receiver.recv() => {
+                proposals_in_flight = proposals_in_flight.saturating_sub(1);
+                match state.on_proposal_ready(ready) {
+                    Ok(_) => summary.drained_proposals += 1,
+                    Err(e) => summary.errors.push(format!("proposal: {:?}", e)),
+                }
+            }
+            Some(validated) = validated_receiver.recv() => {
+                match state.on_block_validated(validated) {
+                    Ok(_) => summary.drained_blocks += 1,
+                    Err(e) => summary.errors.push(format!("validation: {:?}", e)),
+                }
+            }
+            _ = time::sleep_until(deadline) => {
+                summary.abandoned = proposals_in_flight + state.pending_validations();
                 break;
             }
         }
     }
+    match tx_pool.save_journal() {
+        Ok(n) => summary.journaled_txs = n,
+        Err(e) => summary.errors.push(format!("pool journal: {}", e)),
+    }
+    state.flush();
+    summary.view = state.current_view;
+    summary.finalized_height = state.finalized_height;
+    summary.disconnected_peers = network.shutdown().await;
 
     // Explicitly drop state/storage to ensure DB closes cleanly (though RAII does this)
     drop(state);
-    log::info!("Node {} shutdown complete.", id_arg);
+    if !summary.is_clean() {
+        tracing::warn!("Node {} shut down with problems: {}", id_arg, summary);
+        return Err(format!("unclean shutdown: {}", summary).into());
+    }
+    tracing::info!("Node {} shutdown complete: {}", id_arg, summary);
+    Ok(())
+}
+
+/// `--light`: verify headers and certificates from gossip without executing blocks, serve
+/// the header and finality RPCs, and fetch account proofs from full nodes on demand.
+async fn run_light(
+    id_arg: u64,
+    mut network_config: NetworkConfig,
+    channels: ChannelConfig,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (my_id, my_key) = ockham::crypto::generate_keypair_from_id(id_arg);
+    let committee: Vec<PublicKey> = (0..5)
+        .map(|i| ockham::crypto::generate_keypair_from_id(i).0)
+        .collect();
+
+    let db_path = format!("./db/light_{}", id_arg);
+    let storage: Arc<dyn ockham::storage::Storage> = Arc::new(
+        ockham::storage::RedbStorage::new(db_path)
+            .unwrap_or_else(|e| panic!("Failed to open DB: {}", e)),
+    );
+    let mut light =
+        LightClient::new(committee, storage.clone()).with_chain_id(network_config.chain_id);
+    tracing::info!(
+        "Light client {:?} starting at view {}",
+        my_id,
+        light.head.as_ref().map(|h| h.view).unwrap_or(0)
+    );
+
+    if network_config.bootnodes.is_empty() {
+        network_config
+            .bootnodes
+            .push("/ip4/127.0.0.1/tcp/9000".parse()?);
+    }
+    network_config.peer_store = Some(storage.clone());
+    let mut network = Network::with_config(network_config).await?;
+
+    // The RPC server shares the storage; there is no pool or state to execute against
+    let (tx_sender, mut tx_receiver) = tokio::sync::mpsc::channel(channels.rpc_transactions);
+    let (proof_sender, mut proof_requests) = tokio::sync::mpsc::channel(channels.light_requests);
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let mut rpc_impl = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(TxPool::new(storage.clone())),
+        Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    )
+    .with_light_client(proof_sender);
+    if args.iter().any(|r| r == "--sign-rpc") {
+        rpc_impl = rpc_impl.with_signing_key(my_key);
+    }
+    let rpc_port = 8545 + id_arg as u16;
+    let rpc_access = rpc_access_config(args)?;
+    let rate_limiter = RateLimiter::new(rate_limit_config(args)?);
+    let server = Server::builder()
+        .max_request_body_size(MAX_RPC_REQUEST_SIZE)
+        .max_response_body_size(MAX_RPC_RESPONSE_SIZE)
+        .max_connections(DEFAULT_RPC_MAX_CONNECTIONS)
+        .set_batch_request_config(BatchRequestConfig::Limit(MAX_RPC_BATCH_SIZE))
+        .set_http_middleware(
+            tower::ServiceBuilder::new()
+                .option_layer(rpc_access.host_filter()?)
+                .option_layer(rpc_access.cors_layer()?)
+                .layer(ClientKeyLayer::new(rate_limiter.config().trust_proxy)),
+        )
+        .set_rpc_middleware(
+            RpcServiceBuilder::new()
+                .layer_fn(RpcTracing)
+                .layer_fn(move |service| RpcRateLimit::new(service, rate_limiter.clone())),
+        )
+        .build(std::net::SocketAddr::new(rpc_access.listen_addr, rpc_port))
+        .await?;
+    let handle = server.start(rpc_impl.into_rpc());
+    tracing::info!("Light RPC Server started on port {}", rpc_port);
+
+    let mut pending: HashMap<Address, Vec<tokio::sync::oneshot::Sender<AccountProof>>> =
+        HashMap::new();
+    loop {
+        tokio::select! {
+            Some(tx) = tx_receiver.recv() => {
+                // Light nodes relay transactions to full nodes
+                network.broadcast_transaction(tx).await;
+            }
+            Some((address, reply)) = proof_requests.recv() => {
+                pending.entry(address).or_default().push(reply);
+                network.broadcast_sync(ockham::types::SyncMessage::RequestAccountProof(address)).await;
+            }
+            Some(event) = network.next_event() => {
+                let requests = match event {
+                    // Certified blocks are fetched in full once their votes arrive (on_vote)
+                    NetworkEvent::BlockReceived(_, _) => Ok(vec![]),
+                    NetworkEvent::VoteReceived(vote) => light.on_vote(vote),
+                    NetworkEvent::AggregateVoteReceived(aggregate) => light.on_aggregate_vote(aggregate),
+                    NetworkEvent::SyncMessageReceived(msg, _) => match msg {
+                        ockham::types::SyncMessage::ResponseBlock(block) => light.on_block(*block),
+                        ockham::types::SyncMessage::ResponseAccountProof(proof) => {
+                            if pending.contains_key(&proof.address) && light.verify_account_proof(&proof)? {
+                                for reply in pending.remove(&proof.address).unwrap_or_default() {
+                                    let _ = reply.send((*proof).clone());
+                                }
+                            }
+                            Ok(vec![])
+                        }
+                        _ => Ok(vec![]),
+                    },
+                    _ => Ok(vec![]),
+                };
+                for request in requests? {
+                    network.broadcast_sync(request).await;
+                }
+                pending.retain(|_, replies| {
+                    replies.retain(|reply| !reply.is_closed());
+                    !replies.is_empty()
+                });
+            }
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("Shutdown signal received. Stopping RPC server...");
+                let _ = handle.stop();
+                handle.stopped().await;
+                break;
+            }
+        }
+    }
+    network.shutdown().await;
+    tracing::info!("Light client {} shutdown complete.", id_arg);
+    Ok(())
+}
+
+/// `--rpc-addr <ip>`, `--rpc-cors <origin,...>` and `--rpc-allowed-hosts <host,...>`.
+fn rpc_access_config(args: &[String]) -> Result<RpcAccessConfig, Box<dyn std::error::Error>> {
+    let value = |flag: &str| {
+        args.iter()
+            .position(|r| r == flag)
+            .and_then(|pos| args.get(pos + 1))
+    };
+    let list = |flag: &str| {
+        value(flag)
+            .map(|val| {
+                val.split(',')
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut config = RpcAccessConfig {
+        cors_origins: list("--rpc-cors"),
+        allowed_hosts: list("--rpc-allowed-hosts"),
+        ..Default::default()
+    };
+    if let Some(val) = value("--rpc-addr") {
+        config.listen_addr = val.parse()?;
+    }
+    tracing::info!("RPC Access: {:?}", config);
+    Ok(config)
+}
+
+/// `--rpc-tx-limit <per_sec>:<burst>`, `--rpc-read-limit <per_sec>:<burst>` and
+/// `--rpc-trust-proxy`.
+fn rate_limit_config(args: &[String]) -> Result<RateLimitConfig, Box<dyn std::error::Error>> {
+    let value = |flag: &str| {
+        args.iter()
+            .position(|r| r == flag)
+            .and_then(|pos| args.get(pos + 1))
+    };
+    let mut config = RateLimitConfig {
+        trust_proxy: args.iter().any(|r| r == "--rpc-trust-proxy"),
+        ..Default::default()
+    };
+    if let Some(val) = value("--rpc-tx-limit") {
+        config.transactions = val.parse::<Quota>()?;
+    }
+    if let Some(val) = value("--rpc-read-limit") {
+        config.heavy_reads = val.parse::<Quota>()?;
+    }
+    tracing::info!("RPC Rate Limits: {:?}", config);
+    Ok(config)
+}
+
+/// Log to stderr, filtered by `RUST_LOG`; `--log-format json` emits one JSON object per
+/// event (with its span fields) for log aggregation.
+fn init_tracing(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let format = args
+        .iter()
+        .position(|r| r == "--log-format")
+        .and_then(|pos| args.get(pos + 1))
+        .map(String::as_str)
+        .unwrap_or("text");
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr);
+    match format {
+        "text" => builder.init(),
+        "json" => builder.json().init(),
+        other => return Err(format!("Unknown log format: {}", other).into()),
+    }
+    Ok(())
+}
+
+/// `ockham export-genesis [--db <path>] [--at <view>] [--chain-id <id>] [--out <file>]`
+/// Dump the finalized state of a node database as a chain-spec JSON.
+fn export_genesis(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+    };
+
+    let db_path = flag("--db").unwrap_or_else(|| "./db/node_0".to_string());
+    let at = flag("--at").map(|v| v.parse::<u64>()).transpose()?;
+    let chain_id = flag("--chain-id")
+        .map(|v| v.parse::<u64>())
+        .transpose()?
+        .unwrap_or(ockham::types::DEFAULT_CHAIN_ID);
+
+    let storage = ockham::storage::RedbStorage::new(&db_path)?;
+    let spec = ockham::chain_spec::ChainSpec::export(&storage, chain_id, at)?;
+
+    match flag("--out") {
+        Some(out) => {
+            spec.save(&out)?;
+            tracing::info!(
+                "Exported {} accounts at view {} to {}",
+                spec.accounts.len(),
+                spec.view,
+                out
+            );
+        }
+        None => println!("{}", spec.to_json()?),
+    }
+    Ok(())
+}
+
+fn export_chain(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+    };
+
+    let db_path = flag("--db").unwrap_or_else(|| "./db/node_0".to_string());
+    let out_dir = flag("--out").unwrap_or_else(|| "./export".to_string());
+    let format = match flag("--format") {
+        Some(f) => f.parse::<ExportFormat>()?,
+        None => ExportFormat::Csv,
+    };
+    let from = flag("--from")
+        .map(|v| v.parse::<u64>())
+        .transpose()?
+        .unwrap_or(0);
+    let to = flag("--to")
+        .map(|v| v.parse::<u64>())
+        .transpose()?
+        .unwrap_or(u64::MAX);
+
+    let storage: Arc<dyn ockham::storage::Storage> =
+        Arc::new(ockham::storage::RedbStorage::new(&db_path)?);
+    let mut exporter = ChainExporter::new(storage, &out_dir, format).with_start_view(from);
+    match exporter.export_until(to)? {
+        Some(batch) => tracing::info!(
+            "Exported views {}..={} ({} blocks) to {}",
+            batch.from,
+            batch.to,
+            batch.blocks.rows.len(),
+            out_dir
+        ),
+        None => tracing::info!("Nothing to export from view {}", from),
+    }
+    Ok(())
+}
+
+fn conformance(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+    };
+
+    // Check an existing suite, or the freshly generated reference suite
+    let suite = match flag("--check") {
+        Some(path) => ConformanceSuite::load(path)?,
+        None => ConformanceSuite::generate(),
+    };
+
+    if let Some(out) = flag("--out") {
+        suite.save(&out)?;
+        tracing::info!("Wrote {} vectors to {}", suite.vectors.len(), out);
+    }
+
+    let report = suite.run();
+    for (name, reason) in &report.failures {
+        println!("FAIL {}: {}", name, reason);
+    }
+    println!("{} passed, {} failed", report.passed, report.failures.len());
+    if !report.is_success() {
+        return Err("conformance suite failed".into());
+    }
+    Ok(())
+}
+
+/// `ockham export --out <file> [--db <path>] [--to <view>]`
+/// Write the finalized chain (blocks and their certificates) of a node database to a file.
+fn export(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+    };
+
+    let db_path = flag("--db").unwrap_or_else(|| "./db/node_0".to_string());
+    let out = flag("--out").ok_or("export: --out <file> is required")?;
+    let to = flag("--to").map(|v| v.parse::<u64>()).transpose()?;
+
+    let storage = ockham::storage::RedbStorage::new(&db_path)?;
+    let archive = ChainArchive::export(&storage, to)?;
+    archive.save(&out)?;
+    tracing::info!(
+        "Exported {} finalized blocks (up to view {}) to {}",
+        archive.blocks.len(),
+        archive.blocks.last().map(|b| b.block.view).unwrap_or(0),
+        out
+    );
+    Ok(())
+}
+
+/// `ockham import --in <file> [--db <path>] [--gas-limit <value>]`
+/// Re-validate an exported chain block by block and commit it into a fresh node database.
+fn import(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+    };
+
+    let file = flag("--in").ok_or("import: --in <file> is required")?;
+    let db_path = flag("--db").unwrap_or_else(|| "./db/node_0".to_string());
+    let block_gas_limit = flag("--gas-limit")
+        .map(|v| v.parse::<u64>())
+        .transpose()?
+        .unwrap_or(ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+
+    let archive = ChainArchive::load(&file)?;
+    let storage: Arc<dyn ockham::storage::Storage> =
+        Arc::new(ockham::storage::RedbStorage::new(&db_path)?);
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let executor = Executor::new(state_manager, block_gas_limit);
+
+    // Writes the genesis state of a fresh database, as a node started on it would
+    let committee: Vec<PublicKey> = (0..5)
+        .map(|i| ockham::crypto::generate_keypair_from_id(i).0)
+        .collect();
+    let (my_id, my_key) = ockham::crypto::generate_keypair_from_id(0);
+    SimplexState::new(
+        my_id,
+        my_key,
+        committee,
+        storage.clone(),
+        Arc::new(TxPool::new(storage.clone())),
+        executor.clone(),
+        block_gas_limit,
+    );
+
+    let report = archive.import(storage.as_ref(), &executor)?;
+    tracing::info!(
+        "Imported {} blocks into {}, chain head at view {}",
+        report.blocks,
+        db_path,
+        report.head.view
+    );
+    Ok(())
+}
+
+/// `ockham devnet [--validators <n>] [--rpc-port <port>] [--rpc-addr <ip>]`
+/// Run a whole committee in this process, validator `i` serving the RPC on port + i.
+async fn devnet(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+    };
+
+    let mut config = DevnetConfig::default();
+    if let Some(val) = flag("--validators") {
+        config.validators = val.parse::<usize>()?;
+    }
+    if let Some(val) = flag("--rpc-port") {
+        config.rpc_port = val.parse::<u16>()?;
+    }
+    if let Some(val) = flag("--rpc-addr") {
+        config.rpc_addr = val.parse()?;
+    }
+    if config.validators == 0 {
+        return Err("devnet: --validators must be at least 1".into());
+    }
+    if usize::from(config.rpc_port) + config.validators > usize::from(u16::MAX) + 1 {
+        return Err("devnet: not enough RPC ports above --rpc-port".into());
+    }
+
+    let mut devnet = Devnet::start(config).await?;
+    for (i, addr) in devnet.rpc_addrs().iter().enumerate() {
+        tracing::info!("Devnet validator {} RPC on {}", i, addr);
+    }
+    let (pk0, _) = ockham::crypto::generate_keypair_from_id(0);
+    let funded = Address::from_slice(&ockham::types::keccak256(pk0.0.to_bytes())[12..]);
+    tracing::info!(
+        "Funded genesis account {:?} (key of validator 0, generate_keypair_from_id(0))",
+        funded
+    );
+
+    devnet
+        .run(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await;
+    tracing::info!("Shutdown signal received. Stopping devnet...");
+    devnet.stop().await;
+    Ok(())
+}
+
+/// The spec's validators if it has any, otherwise the five dev validators.
+fn genesis_committee(
+    chain_spec: Option<&ockham::chain_spec::ChainSpec>,
+) -> Result<Vec<PublicKey>, Box<dyn std::error::Error>> {
+    Ok(match chain_spec {
+        Some(spec) if !spec.validators.is_empty() => spec
+            .validators
+            .iter()
+            .map(|v| v.public_key())
+            .collect::<Result<_, _>>()?,
+        _ => (0..5)
+            .map(|i| ockham::crypto::generate_keypair_from_id(i).0)
+            .collect(),
+    })
+}
+
+/// `--p2p-port <port>`; node 0 listens on 9000 by default, the others on a free port.
+fn p2p_port(args: &[String], id: u64) -> Result<u16, Box<dyn std::error::Error>> {
+    match args
+        .iter()
+        .position(|r| r == "--p2p-port")
+        .and_then(|pos| args.get(pos + 1))
+    {
+        Some(val) => Ok(val.parse::<u16>()?),
+        None => Ok(if id == 0 { 9000 } else { 0 }),
+    }
+}
+
+/// `--nat <external-ip>`: the address peers reach us on (the node then listens on all
+/// interfaces), e.g. the host's when running in a container.
+fn nat_addr(args: &[String]) -> Result<Option<std::net::IpAddr>, Box<dyn std::error::Error>> {
+    args.iter()
+        .position(|r| r == "--nat")
+        .and_then(|pos| args.get(pos + 1))
+        .map(|val| val.parse().map_err(Into::into))
+        .transpose()
+}
+
+/// `--node-key <file>`, by default next to the node's database.
+fn node_key_path(args: &[String], id: u64) -> String {
+    args.iter()
+        .position(|r| r == "--node-key")
+        .and_then(|pos| args.get(pos + 1))
+        .cloned()
+        .unwrap_or_else(|| format!("./db/node_{}.key", id))
+}
+
+/// `ockham init <node_id> [--chain-spec <file>] [--gas-limit <value>] [--node-key <file>]
+/// [--p2p-port <port>] [--nat <external-ip>]`
+/// Write the genesis into the node's database and create its node key, then print its
+/// peer id and multiaddr (as `key=value` lines) for orchestration scripts to pass to the
+/// other nodes as `--bootnodes`.
+fn init(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+    };
+
+    let id = args
+        .get(2)
+        .ok_or("init: <node_id> is required")?
+        .parse::<u64>()?;
+    let block_gas_limit = flag("--gas-limit")
+        .map(|v| v.parse::<u64>())
+        .transpose()?
+        .unwrap_or(ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let chain_spec = flag("--chain-spec")
+        .map(ockham::chain_spec::ChainSpec::load)
+        .transpose()?;
+    let ip = nat_addr(args)?.unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+    let port = p2p_port(args, id)?;
+    if port == 0 {
+        return Err("init: --p2p-port is required for nodes other than 0".into());
+    }
+
+    // Genesis, as the node writes it when started on a fresh database
+    let db_path = format!("./db/node_{}", id);
+    if let Some(dir) = std::path::Path::new(&db_path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let storage: Arc<dyn ockham::storage::Storage> =
+        Arc::new(ockham::storage::RedbStorage::new(&db_path)?);
+    if let Some(spec) = chain_spec
+        .as_ref()
+        .filter(|spec| !spec.validators.is_empty())
+        && storage.get_consensus_state()?.is_none()
+    {
+        spec.init_genesis(storage.clone())?;
+    }
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let (my_id, my_key) = ockham::crypto::generate_keypair_from_id(id);
+    SimplexState::new(
+        my_id,
+        my_key,
+        genesis_committee(chain_spec.as_ref())?,
+        storage.clone(),
+        Arc::new(TxPool::new(storage.clone())),
+        Executor::new(state_manager, block_gas_limit),
+        block_gas_limit,
+    );
+    tracing::info!("Wrote genesis into {}", db_path);
+
+    // Node key and the address the other nodes dial
+    let node_key = load_or_create_node_key(node_key_path(args, id))?;
+    let peer_id = node_key.public().to_peer_id();
+    println!("peer_id={}", peer_id);
+    println!("multiaddr={}", node_multiaddr(ip, port, peer_id));
     Ok(())
 }
@@ -0,0 +1,163 @@
+use crate::crypto::PublicKey;
+use crate::types::{AccessListItem, Block, EquivocationEvidence, Transaction, Vote};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Central memory budget shared by the node's caches (tx pool, orphan buffer, vote maps, ...).
+///
+/// Each cache registers a `MemoryHandle` and reports its approximate usage in bytes.
+/// While the total stays under the limit every cache may grow freely; once it is exceeded,
+/// each cache is allowed its proportional share of the limit and must evict down to it.
+/// Other caches (state cache, seen-message caches) hook in the same way via `register`.
+#[derive(Clone)]
+pub struct MemoryBudget {
+    inner: Arc<BudgetInner>,
+}
+
+struct BudgetInner {
+    limit: usize,
+    next_id: AtomicU64,
+    usages: Mutex<HashMap<u64, (&'static str, usize)>>,
+}
+
+impl MemoryBudget {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            inner: Arc::new(BudgetInner {
+                limit,
+                next_id: AtomicU64::new(0),
+                usages: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Budget that never asks for eviction.
+    pub fn unlimited() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.inner.limit
+    }
+
+    /// Register a cache under `name`. Its usage is removed when the handle is dropped.
+    pub fn register(&self, name: &'static str) -> MemoryHandle {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        self.inner.usages.lock().unwrap().insert(id, (name, 0));
+        MemoryHandle {
+            budget: self.inner.clone(),
+            id,
+        }
+    }
+
+    /// Total reported usage in bytes.
+    pub fn usage(&self) -> usize {
+        self.inner.total()
+    }
+
+    /// Reported usage per cache name (for metrics / RPC).
+    pub fn usage_by_name(&self) -> Vec<(&'static str, usize)> {
+        let mut usages: Vec<_> = self
+            .inner
+            .usages
+            .lock()
+            .unwrap()
+            .values()
+            .copied()
+            .collect();
+        usages.sort();
+        usages
+    }
+}
+
+impl BudgetInner {
+    fn total(&self) -> usize {
+        self.usages
+            .lock()
+            .unwrap()
+            .values()
+            .fold(0usize, |acc, (_, u)| acc.saturating_add(*u))
+    }
+}
+
+/// A cache's registration with the `MemoryBudget`.
+pub struct MemoryHandle {
+    budget: Arc<BudgetInner>,
+    id: u64,
+}
+
+impl MemoryHandle {
+    /// Report the current usage of this cache in bytes.
+    pub fn set_usage(&self, bytes: usize) {
+        if let Some(entry) = self.budget.usages.lock().unwrap().get_mut(&self.id) {
+            entry.1 = bytes;
+        }
+    }
+
+    pub fn usage(&self) -> usize {
+        self.budget
+            .usages
+            .lock()
+            .unwrap()
+            .get(&self.id)
+            .map(|(_, u)| *u)
+            .unwrap_or(0)
+    }
+
+    /// Bytes this cache may hold: unbounded while the budget is respected,
+    /// otherwise its proportional share of the limit.
+    pub fn allowance(&self) -> usize {
+        let usages = self.budget.usages.lock().unwrap();
+        let total = usages
+            .values()
+            .fold(0usize, |acc, (_, u)| acc.saturating_add(*u));
+        if total <= self.budget.limit {
+            return usize::MAX;
+        }
+        let own = usages.get(&self.id).map(|(_, u)| *u).unwrap_or(0);
+        ((own as u128 * self.budget.limit as u128) / total as u128) as usize
+    }
+
+    /// Bytes the cache should evict now to get back to its allowance.
+    /// Compute once and evict down to it: the allowance moves as the cache shrinks.
+    pub fn excess(&self) -> usize {
+        self.usage().saturating_sub(self.allowance())
+    }
+}
+
+impl Drop for MemoryHandle {
+    fn drop(&mut self) {
+        self.budget.usages.lock().unwrap().remove(&self.id);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Size Estimates (inline size plus the main heap allocations)
+// -----------------------------------------------------------------------------
+
+pub fn transaction_size(tx: &Transaction) -> usize {
+    std::mem::size_of::<Transaction>()
+        + tx.data.len()
+        + tx.access_list
+            .iter()
+            .map(|item| std::mem::size_of::<AccessListItem>() + item.storage_keys.len() * 32)
+            .sum::<usize>()
+}
+
+pub fn block_size(block: &Block) -> usize {
+    std::mem::size_of::<Block>()
+        + block.payload.iter().map(transaction_size).sum::<usize>()
+        + block.evidence.len() * std::mem::size_of::<EquivocationEvidence>()
+        + block.justify.signers.0.len()
+}
+
+/// A vote stored in a per-view map (keyed by its author).
+pub fn vote_size() -> usize {
+    std::mem::size_of::<Vote>() + std::mem::size_of::<PublicKey>()
+}
+
+/// A hash in a seen-message cache (map entry plus its place in the eviction queue).
+pub fn seen_entry_size() -> usize {
+    2 * (std::mem::size_of::<crate::crypto::Hash>() + std::mem::size_of::<u64>())
+}
@@ -0,0 +1,167 @@
+//! Versioned upgrades of the `RedbStorage` layout.
+//!
+//! The meta table holds the `schema_version` of a database. On open, every migration
+//! above it runs in order and the version is bumped after each, so an interrupted upgrade
+//! resumes where it stopped. Databases written by a newer node are refused.
+//!
+//! Any change to a stored type or table layout must bump `SCHEMA_VERSION` and add the
+//! migration that upgrades existing databases to it.
+
+use crate::crypto::Hash;
+use crate::storage::{
+    AccountInfo, ConsensusState, StorageError, TABLE_ACCOUNTS, TABLE_CODE, TABLE_META,
+    write_consensus_state,
+};
+use alloy_primitives::{Bytes, U256};
+use redb::Database;
+use serde::Deserialize;
+
+/// Layout version written by this build.
+pub const SCHEMA_VERSION: u32 = 2;
+
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Upgrades a database from `version - 1` to `version`.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub run: fn(&Database) -> Result<(), StorageError>,
+}
+
+/// Every migration, in version order.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "move contract code out of account records",
+        run: migrate_inline_code,
+    },
+    Migration {
+        version: 2,
+        description: "split the consensus state into its own tables",
+        run: migrate_consensus_state,
+    },
+];
+
+/// Version of `db`; databases from before versioning are version 0.
+pub fn schema_version(db: &Database) -> Result<u32, StorageError> {
+    let read_txn = db.begin_read()?;
+    let table = read_txn.open_table(TABLE_META)?;
+    match table.get(SCHEMA_VERSION_KEY)? {
+        Some(val) => Ok(bincode::deserialize(&val.value())?),
+        None => Ok(0),
+    }
+}
+
+fn set_schema_version(db: &Database, version: u32) -> Result<(), StorageError> {
+    let write_txn = db.begin_write()?;
+    {
+        let mut table = write_txn.open_table(TABLE_META)?;
+        table.insert(SCHEMA_VERSION_KEY, bincode::serialize(&version)?)?;
+    }
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// Bring `db` up to `SCHEMA_VERSION`, or refuse it if it was written by a newer node.
+pub fn migrate(db: &Database) -> Result<(), StorageError> {
+    let version = schema_version(db)?;
+    if version > SCHEMA_VERSION {
+        return Err(StorageError::UnsupportedSchemaVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        tracing::info!(
+            "Migrating database to schema version {}: {}",
+            migration.version,
+            migration.description
+        );
+        (migration.run)(db)?;
+        set_schema_version(db, migration.version)?;
+    }
+    if version < SCHEMA_VERSION {
+        set_schema_version(db, SCHEMA_VERSION)?;
+    }
+    Ok(())
+}
+
+// Account records written before code was moved out of them
+#[derive(Deserialize)]
+struct LegacyAccountInfo {
+    nonce: u64,
+    balance: U256,
+    code_hash: Hash,
+    code: Option<Bytes>,
+}
+
+/// Move code stored inline in account records to the code table, and rewrite the records
+/// without it.
+fn migrate_inline_code(db: &Database) -> Result<(), StorageError> {
+    // Set by nodes that ran this migration before schema versions existed
+    const MARKER: &str = "accounts_without_code";
+    let mut accounts = Vec::new();
+    {
+        let read_txn = db.begin_read()?;
+        if read_txn.open_table(TABLE_META)?.get(MARKER)?.is_some() {
+            return Ok(());
+        }
+        let table = read_txn.open_table(TABLE_ACCOUNTS)?;
+        for entry in table.range::<&[u8; 20]>(..)? {
+            let (key, val) = entry?;
+            // Records already in the new format have no trailing code field
+            if let Ok(legacy) = bincode::deserialize::<LegacyAccountInfo>(&val.value()) {
+                accounts.push((*key.value(), legacy));
+            }
+        }
+    }
+
+    let write_txn = db.begin_write()?;
+    {
+        let mut table = write_txn.open_table(TABLE_ACCOUNTS)?;
+        let mut code_table = write_txn.open_table(TABLE_CODE)?;
+        for (address, legacy) in &accounts {
+            let mut code_hash = legacy.code_hash;
+            if let Some(code) = legacy.code.as_ref().filter(|c| !c.is_empty()) {
+                if code_hash == Hash::default() {
+                    code_hash = Hash(alloy_primitives::keccak256(code).0);
+                }
+                code_table.insert(&code_hash.0, bincode::serialize(&code.to_vec())?)?;
+            }
+            let info = AccountInfo {
+                nonce: legacy.nonce,
+                balance: legacy.balance,
+                code_hash,
+            };
+            table.insert(address, bincode::serialize(&info)?)?;
+        }
+    }
+    write_txn.commit()?;
+    if !accounts.is_empty() {
+        tracing::info!("Moved the code of {} account records", accounts.len());
+    }
+    Ok(())
+}
+
+/// Rewrite the consensus state, stored as one record until now, across the meta, validator
+/// scores, rewards and validator queues tables.
+fn migrate_consensus_state(db: &Database) -> Result<(), StorageError> {
+    const LEGACY_KEY: &str = "consensus_state";
+    let state: Option<ConsensusState> = {
+        let read_txn = db.begin_read()?;
+        let table = read_txn.open_table(TABLE_META)?;
+        match table.get(LEGACY_KEY)? {
+            Some(val) => Some(bincode::deserialize(&val.value())?),
+            None => None,
+        }
+    };
+    let Some(state) = state else {
+        return Ok(());
+    };
+
+    let write_txn = db.begin_write()?;
+    write_consensus_state(&write_txn, &state)?;
+    write_txn.open_table(TABLE_META)?.remove(LEGACY_KEY)?;
+    write_txn.commit()?;
+    Ok(())
+}
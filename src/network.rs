@@ -1,13 +1,37 @@
-use crate::types::{Block, EquivocationEvidence, Transaction, Vote};
+use crate::channels::{ChannelMetrics, DEFAULT_CHANNEL_CAPACITY};
+use crate::health::HealthMonitor;
+use crate::storage::{KnownPeer, Storage};
+use crate::types::{
+    AggregateVote, Block, ChainParams, CompactBlock, EquivocationEvidence,
+    ProposalEquivocationEvidence, Transaction, Vote,
+};
 use futures::StreamExt;
 use libp2p::{
-    Multiaddr, gossipsub, mdns, noise, swarm::NetworkBehaviour, swarm::SwarmEvent, tcp, yamux,
+    Multiaddr, PeerId, Swarm, gossipsub, identity, mdns,
+    multiaddr::Protocol,
+    noise,
+    swarm::{ConnectionId, NetworkBehaviour, SwarmEvent, dial_opts::DialOpts},
+    tcp, yamux,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::collections::hash_map::DefaultHasher;
 use std::error::Error;
 use std::hash::{Hash, Hasher};
-use std::time::Duration;
-use tokio::sync::mpsc;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+
+/// Stored peers not seen for this long are dropped from the peer store.
+const PEER_STORE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Name of the command channel in `ChannelMetrics`.
+pub const COMMAND_CHANNEL: &str = "network.commands";
+
+/// How long `Network::shutdown` waits for connections to close.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
 
 /// Network Behaviour combining Gossipsub (for consensus messages) and mDNS (for local discovery).
 #[derive(NetworkBehaviour)]
@@ -20,38 +44,885 @@ pub struct SimplexBehaviour {
 #[derive(Debug)]
 pub enum NetworkEvent {
     VoteReceived(Vote),
+    AggregateVoteReceived(AggregateVote), // Checked against the committee by consensus
     EvidenceReceived(EquivocationEvidence),
-    BlockReceived(Block),
+    ProposalEvidenceReceived(ProposalEquivocationEvidence),
+    BlockReceived(CompactBlock, String), // Compact Block + PeerId
     TransactionReceived(Transaction),
     SyncMessageReceived(crate::types::SyncMessage, String), // Message + PeerId
     PeerConnected(String),
 }
 
+/// Classes of network events, most urgent first. The consensus loop always takes the
+/// highest class waiting, so e.g. a transaction flood cannot delay votes into a view
+/// timeout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EventPriority {
+    /// Votes, aggregates and equivocation evidence.
+    Votes,
+    Blocks,
+    /// Sync messages and new peers (which trigger a sync).
+    Sync,
+    Transactions,
+}
+
+impl NetworkEvent {
+    pub fn priority(&self) -> EventPriority {
+        match self {
+            NetworkEvent::VoteReceived(_)
+            | NetworkEvent::AggregateVoteReceived(_)
+            | NetworkEvent::EvidenceReceived(_)
+            | NetworkEvent::ProposalEvidenceReceived(_) => EventPriority::Votes,
+            NetworkEvent::BlockReceived(..) => EventPriority::Blocks,
+            NetworkEvent::SyncMessageReceived(..) | NetworkEvent::PeerConnected(_) => {
+                EventPriority::Sync
+            }
+            NetworkEvent::TransactionReceived(_) => EventPriority::Transactions,
+        }
+    }
+}
+
+/// Capacity of each event queue. When a queue is full the network task waits for the
+/// consensus loop (backpressure on gossip), except for transactions, which are dropped:
+/// peers re-announce pending transactions and the pool fetches missing ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventQueueConfig {
+    pub votes: usize,
+    pub blocks: usize,
+    pub sync: usize,
+    pub transactions: usize,
+}
+
+impl Default for EventQueueConfig {
+    fn default() -> Self {
+        Self {
+            votes: 1024,
+            blocks: 64,
+            sync: 64,
+            transactions: 1024,
+        }
+    }
+}
+
+impl EventPriority {
+    /// Name of the queue in `ChannelMetrics`.
+    pub fn channel(self) -> &'static str {
+        match self {
+            EventPriority::Votes => "events.votes",
+            EventPriority::Blocks => "events.blocks",
+            EventPriority::Sync => "events.sync",
+            EventPriority::Transactions => "events.transactions",
+        }
+    }
+}
+
+/// Network task side of the event queues.
+#[derive(Clone)]
+pub struct EventSender {
+    votes: mpsc::Sender<NetworkEvent>,
+    blocks: mpsc::Sender<NetworkEvent>,
+    sync: mpsc::Sender<NetworkEvent>,
+    transactions: mpsc::Sender<NetworkEvent>,
+    metrics: ChannelMetrics,
+}
+
+impl EventSender {
+    /// Queue `event` by its priority. Returns false if it was dropped (a full transaction
+    /// queue, or the receiver is gone).
+    pub async fn send(&self, event: NetworkEvent) -> bool {
+        let priority = event.priority();
+        let queue = match priority {
+            EventPriority::Votes => &self.votes,
+            EventPriority::Blocks => &self.blocks,
+            EventPriority::Sync => &self.sync,
+            EventPriority::Transactions => {
+                return self
+                    .metrics
+                    .try_send(priority.channel(), &self.transactions, event);
+            }
+        };
+        self.metrics.send(priority.channel(), queue, event).await
+    }
+
+    /// Transactions dropped because their queue was full.
+    pub fn dropped_transactions(&self) -> u64 {
+        self.metrics
+            .stats(EventPriority::Transactions.channel())
+            .dropped
+    }
+}
+
+/// Consensus loop side of the event queues.
+pub struct EventReceiver {
+    votes: mpsc::Receiver<NetworkEvent>,
+    blocks: mpsc::Receiver<NetworkEvent>,
+    sync: mpsc::Receiver<NetworkEvent>,
+    transactions: mpsc::Receiver<NetworkEvent>,
+    metrics: ChannelMetrics,
+}
+
+impl EventReceiver {
+    /// The next event of the highest priority waiting. None once the sender is gone and
+    /// the queues are empty.
+    pub async fn recv(&mut self) -> Option<NetworkEvent> {
+        tokio::select! {
+            biased;
+            Some(event) = self.votes.recv() => Some(event),
+            Some(event) = self.blocks.recv() => Some(event),
+            Some(event) = self.sync.recv() => Some(event),
+            Some(event) = self.transactions.recv() => Some(event),
+            else => None,
+        }
+    }
+
+    /// Transactions dropped because their queue was full.
+    pub fn dropped_transactions(&self) -> u64 {
+        self.metrics
+            .stats(EventPriority::Transactions.channel())
+            .dropped
+    }
+}
+
+/// Bounded, prioritized queues between the network task and the consensus loop, counting
+/// their sends in `metrics`.
+pub fn event_channel(
+    config: EventQueueConfig,
+    metrics: ChannelMetrics,
+) -> (EventSender, EventReceiver) {
+    let (votes, votes_rx) = mpsc::channel(config.votes.max(1));
+    let (blocks, blocks_rx) = mpsc::channel(config.blocks.max(1));
+    let (sync, sync_rx) = mpsc::channel(config.sync.max(1));
+    let (transactions, transactions_rx) = mpsc::channel(config.transactions.max(1));
+    (
+        EventSender {
+            votes,
+            blocks,
+            sync,
+            transactions,
+            metrics: metrics.clone(),
+        },
+        EventReceiver {
+            votes: votes_rx,
+            blocks: blocks_rx,
+            sync: sync_rx,
+            transactions: transactions_rx,
+            metrics,
+        },
+    )
+}
+
+/// Gossipsub topics. Each message kind has its own topic (and limits), so e.g. a
+/// transaction flood cannot crowd out vote delivery.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GossipTopic {
+    Blocks,
+    Votes,
+    Txs,
+    Evidence, // Vote and proposal equivocation evidence
+    Sync,
+}
+
+impl GossipTopic {
+    pub const ALL: [GossipTopic; 5] = [
+        GossipTopic::Blocks,
+        GossipTopic::Votes,
+        GossipTopic::Txs,
+        GossipTopic::Evidence,
+        GossipTopic::Sync,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GossipTopic::Blocks => "ockham/blocks",
+            GossipTopic::Votes => "ockham/votes",
+            GossipTopic::Txs => "ockham/txs",
+            GossipTopic::Evidence => "ockham/evidence",
+            GossipTopic::Sync => "ockham/sync",
+        }
+    }
+
+    pub fn ident(self) -> gossipsub::IdentTopic {
+        gossipsub::IdentTopic::new(self.name())
+    }
+
+    pub fn from_hash(hash: &gossipsub::TopicHash) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.ident().hash() == *hash)
+    }
+}
+
+/// Size and rate limits for one topic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TopicLimit {
+    pub max_message_size: usize,
+    /// Messages a peer may relay per `rate_window` on this topic before they count as spam.
+    pub rate_limit: u32,
+}
+
+/// Per-topic limits.
+#[derive(Clone, Copy, Debug)]
+pub struct TopicLimits {
+    pub blocks: TopicLimit,
+    pub votes: TopicLimit,
+    pub txs: TopicLimit,
+    pub evidence: TopicLimit,
+    pub sync: TopicLimit,
+}
+
+impl TopicLimits {
+    pub fn get(&self, topic: GossipTopic) -> TopicLimit {
+        match topic {
+            GossipTopic::Blocks => self.blocks,
+            GossipTopic::Votes => self.votes,
+            GossipTopic::Txs => self.txs,
+            GossipTopic::Evidence => self.evidence,
+            GossipTopic::Sync => self.sync,
+        }
+    }
+
+    /// The largest message any topic accepts (the gossipsub transmit limit).
+    pub fn max_message_size(&self) -> usize {
+        GossipTopic::ALL
+            .into_iter()
+            .map(|t| self.get(t).max_message_size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Headroom above the chain's size limits for the message envelope (a sync response
+    /// around a block, or a block on its own topic).
+    const ENVELOPE_HEADROOM: usize = 64 * 1024;
+
+    /// These limits, raised where needed to carry the largest transaction and block
+    /// `params` allow, so valid blocks are never dropped as oversized gossip.
+    pub fn covering(mut self, params: &ChainParams) -> Self {
+        let tx = (params.max_tx_size as usize).saturating_add(Self::ENVELOPE_HEADROOM);
+        let block = (params.max_block_size as usize).saturating_add(Self::ENVELOPE_HEADROOM);
+        self.txs.max_message_size = self.txs.max_message_size.max(tx);
+        self.blocks.max_message_size = self.blocks.max_message_size.max(block);
+        self.sync.max_message_size = self.sync.max_message_size.max(block);
+        self.evidence.max_message_size =
+            self.evidence.max_message_size.max(block.saturating_mul(2));
+        self
+    }
+}
+
+impl Default for TopicLimits {
+    fn default() -> Self {
+        const KIB: usize = 1024;
+        Self {
+            blocks: TopicLimit {
+                max_message_size: 4096 * KIB,
+                rate_limit: 50,
+            },
+            votes: TopicLimit {
+                max_message_size: 16 * KIB,
+                rate_limit: 500,
+            },
+            txs: TopicLimit {
+                max_message_size: 128 * KIB,
+                rate_limit: 1000,
+            },
+            // Proposal evidence carries two full blocks
+            evidence: TopicLimit {
+                max_message_size: 8192 * KIB,
+                rate_limit: 20,
+            },
+            sync: TopicLimit {
+                max_message_size: 4096 * KIB,
+                rate_limit: 200,
+            },
+        }
+    }
+}
+
+/// Why a peer is penalized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    InvalidMessage,   // Undecodable, oversized or off-topic payload, or malformed evidence
+    InvalidSignature, // Block, vote or transaction with a bad signature
+    Spam,             // Over the per-peer message rate
+}
+
+/// Per-peer scoring settings. Scores start at 0, drop on misbehavior and recover over time.
+#[derive(Clone, Debug)]
+pub struct PeerScoreConfig {
+    pub invalid_message_penalty: i64,
+    pub invalid_signature_penalty: i64,
+    pub spam_penalty: i64,
+    /// Per-topic message size and rate limits (rates are counted per `rate_window`).
+    pub topic_limits: TopicLimits,
+    pub rate_window: Duration,
+    /// Peers at or below this score are disconnected and banned.
+    pub ban_threshold: i64,
+    pub ban_duration: Duration,
+    /// A negative score recovers by one point per interval.
+    pub recovery_interval: Duration,
+}
+
+impl Default for PeerScoreConfig {
+    fn default() -> Self {
+        Self {
+            invalid_message_penalty: 10,
+            invalid_signature_penalty: 25,
+            spam_penalty: 5,
+            topic_limits: TopicLimits::default(),
+            rate_window: Duration::from_secs(1),
+            ban_threshold: -100,
+            ban_duration: Duration::from_secs(600),
+            recovery_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+struct PeerRecord {
+    score: i64,
+    last_recovery: Instant,
+    windows: HashMap<GossipTopic, (Instant, u32)>, // Window start, messages in window
+    banned_until: Option<Instant>,
+}
+
+/// Tracks peer misbehavior and decides which peers to ban.
+/// Time is passed in explicitly so the policy can be driven (and tested) without a swarm.
+pub struct PeerManager {
+    config: PeerScoreConfig,
+    peers: HashMap<PeerId, PeerRecord>,
+}
+
+impl PeerManager {
+    pub fn new(config: PeerScoreConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn score(&self, peer: &PeerId) -> i64 {
+        self.peers.get(peer).map(|p| p.score).unwrap_or(0)
+    }
+
+    pub fn is_banned(&self, peer: &PeerId, now: Instant) -> bool {
+        self.peers
+            .get(peer)
+            .and_then(|p| p.banned_until)
+            .is_some_and(|until| now < until)
+    }
+
+    /// Count a message relayed by `peer` on `topic`. Fails with `Spam` once the topic's
+    /// rate limit is exceeded.
+    pub fn record_message(
+        &mut self,
+        peer: &PeerId,
+        topic: GossipTopic,
+        now: Instant,
+    ) -> Result<(), Misbehavior> {
+        let window = self.config.rate_window;
+        let limit = self.config.topic_limits.get(topic).rate_limit;
+        let record = self.record(peer, now);
+        let (start, count) = record.windows.entry(topic).or_insert((now, 0));
+        if now.duration_since(*start) >= window {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        if *count > limit {
+            return Err(Misbehavior::Spam);
+        }
+        Ok(())
+    }
+
+    /// Penalize `peer`. Returns true if this report got the peer banned.
+    pub fn report(&mut self, peer: &PeerId, misbehavior: Misbehavior, now: Instant) -> bool {
+        let penalty = match misbehavior {
+            Misbehavior::InvalidMessage => self.config.invalid_message_penalty,
+            Misbehavior::InvalidSignature => self.config.invalid_signature_penalty,
+            Misbehavior::Spam => self.config.spam_penalty,
+        };
+        let threshold = self.config.ban_threshold;
+        let ban_duration = self.config.ban_duration;
+        let record = self.record(peer, now);
+        record.score -= penalty;
+        if record.score <= threshold && record.banned_until.is_none() {
+            record.banned_until = Some(now + ban_duration);
+            return true;
+        }
+        false
+    }
+
+    /// Lift expired bans (the peer starts over with a clean score) and return those peers.
+    pub fn prune_expired_bans(&mut self, now: Instant) -> Vec<PeerId> {
+        let expired: Vec<PeerId> = self
+            .peers
+            .iter()
+            .filter(|(_, p)| p.banned_until.is_some_and(|until| now >= until))
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in &expired {
+            self.peers.remove(peer);
+        }
+        expired
+    }
+
+    fn record(&mut self, peer: &PeerId, now: Instant) -> &mut PeerRecord {
+        let recovery_interval = self.config.recovery_interval;
+        let record = self.peers.entry(*peer).or_insert_with(|| PeerRecord {
+            score: 0,
+            last_recovery: now,
+            windows: HashMap::new(),
+            banned_until: None,
+        });
+        // Lazy recovery towards 0 (banned peers serve their full ban first)
+        if record.banned_until.is_none() && !recovery_interval.is_zero() {
+            let steps = (now.duration_since(record.last_recovery).as_millis()
+                / recovery_interval.as_millis()) as i64;
+            if steps > 0 {
+                record.score = (record.score + steps).min(0);
+                record.last_recovery = now;
+            }
+        }
+        record
+    }
+}
+
+/// Network settings.
+#[derive(Clone)]
+pub struct NetworkConfig {
+    pub port: u16,
+    /// Interface the listener binds to.
+    pub listen_addr: IpAddr,
+    /// Address peers reach us on, when not the listen address (e.g. a container's host,
+    /// `--nat`). Advertised with the listen port.
+    pub external_addr: Option<IpAddr>,
+    /// Node key, so the peer id survives restarts (`load_or_create_node_key`). None: a
+    /// fresh one on every start.
+    pub node_key: Option<identity::Keypair>,
+    /// Static peers: dialed at startup and redialed (with backoff) whenever disconnected.
+    pub bootnodes: Vec<Multiaddr>,
+    /// Topics to subscribe to (all by default).
+    pub topics: Vec<GossipTopic>,
+    /// Outbound connections the network task maintains from the peer store.
+    pub target_peers: usize,
+    pub redial_backoff: Duration,
+    pub max_redial_backoff: Duration,
+    pub peer_score: PeerScoreConfig,
+    /// Known-good peers persist here across restarts (None: in memory only).
+    pub peer_store: Option<Arc<dyn Storage>>,
+    /// Receives the connected peer count.
+    pub health: Option<HealthMonitor>,
+    pub event_queues: EventQueueConfig,
+    /// Capacity of the command channel to the network task.
+    pub command_queue: usize,
+    /// Counts the sends on the command channel and event queues.
+    pub channel_metrics: ChannelMetrics,
+    /// Chain gossiped votes must be signed for; others are dropped and penalized.
+    pub chain_id: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            port: 0,
+            listen_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            external_addr: None,
+            node_key: None,
+            bootnodes: vec![],
+            topics: GossipTopic::ALL.to_vec(),
+            target_peers: 8,
+            redial_backoff: Duration::from_secs(1),
+            max_redial_backoff: Duration::from_secs(60),
+            peer_score: PeerScoreConfig::default(),
+            peer_store: None,
+            health: None,
+            event_queues: EventQueueConfig::default(),
+            command_queue: DEFAULT_CHANNEL_CAPACITY,
+            channel_metrics: ChannelMetrics::new(),
+            chain_id: crate::types::DEFAULT_CHAIN_ID,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DialState {
+    Idle,
+    Dialing,
+    Connected,
+}
+
+struct DialEntry {
+    is_static: bool,
+    failures: u32,
+    next_attempt: Instant,
+    state: DialState,
+}
+
+/// Outbound dial schedule: which addresses to (re)dial and when, with exponential backoff.
+/// Static entries (bootnodes) are always redialed; the others only up to the target peer count.
+pub struct Dialer {
+    base_backoff: Duration,
+    max_backoff: Duration,
+    entries: HashMap<Multiaddr, DialEntry>,
+}
+
+impl Dialer {
+    pub fn new(base_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            base_backoff,
+            max_backoff,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Add a dial candidate, due immediately. Known addresses keep their schedule.
+    pub fn add(&mut self, addr: Multiaddr, is_static: bool, now: Instant) {
+        let entry = self.entries.entry(addr).or_insert(DialEntry {
+            is_static,
+            failures: 0,
+            next_attempt: now,
+            state: DialState::Idle,
+        });
+        entry.is_static |= is_static;
+    }
+
+    pub fn remove(&mut self, addr: &Multiaddr) {
+        self.entries.remove(addr);
+    }
+
+    /// Outbound connections currently up.
+    pub fn connected(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|e| e.state == DialState::Connected)
+            .count()
+    }
+
+    /// Addresses to dial now: every due static entry plus up to `limit` others (oldest first).
+    /// They count as dialing until the outcome is reported.
+    pub fn due(&mut self, now: Instant, limit: usize) -> Vec<Multiaddr> {
+        let mut candidates: Vec<(&Multiaddr, &DialEntry)> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.state == DialState::Idle && e.next_attempt <= now)
+            .collect();
+        candidates.sort_by_key(|(_, e)| (!e.is_static, e.next_attempt));
+
+        let mut others = 0;
+        let mut due = Vec::new();
+        for (addr, entry) in candidates {
+            if !entry.is_static {
+                if others >= limit {
+                    continue;
+                }
+                others += 1;
+            }
+            due.push(addr.clone());
+        }
+        for addr in &due {
+            if let Some(entry) = self.entries.get_mut(addr) {
+                entry.state = DialState::Dialing;
+            }
+        }
+        due
+    }
+
+    pub fn on_connected(&mut self, addr: &Multiaddr) {
+        if let Some(entry) = self.entries.get_mut(addr) {
+            entry.state = DialState::Connected;
+            entry.failures = 0;
+        }
+    }
+
+    /// The dial failed: back off exponentially (capped at the max backoff).
+    pub fn on_failure(&mut self, addr: &Multiaddr, now: Instant) {
+        let (base, max) = (self.base_backoff, self.max_backoff);
+        if let Some(entry) = self.entries.get_mut(addr) {
+            entry.failures = entry.failures.saturating_add(1);
+            let backoff = base.saturating_mul(1 << (entry.failures - 1).min(16));
+            entry.next_attempt = now + backoff.min(max);
+            entry.state = DialState::Idle;
+        }
+    }
+
+    /// The connection dropped: redial after the base backoff.
+    pub fn on_disconnected(&mut self, addr: &Multiaddr, now: Instant) {
+        if let Some(entry) = self.entries.get_mut(addr) {
+            entry.next_attempt = now + self.base_backoff;
+            entry.state = DialState::Idle;
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Load the node key from `path`, or create it there (readable by the owner only) if
+/// there is none yet.
+pub fn load_or_create_node_key(
+    path: impl AsRef<Path>,
+) -> Result<identity::Keypair, Box<dyn Error>> {
+    let path = path.as_ref();
+    if path.exists() {
+        let bytes = std::fs::read(path)?;
+        return Ok(identity::Keypair::from_protobuf_encoding(&bytes)?);
+    }
+    let key = identity::Keypair::generate_ed25519();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(path)?, &key.to_protobuf_encoding()?)?;
+    Ok(key)
+}
+
+/// Address other nodes dial to reach `peer_id` on `ip`:`port`, e.g. as a bootnode.
+pub fn node_multiaddr(ip: IpAddr, port: u16, peer_id: PeerId) -> Multiaddr {
+    Multiaddr::from(ip)
+        .with(Protocol::Tcp(port))
+        .with(Protocol::P2p(peer_id))
+}
+
+/// `address` with its IP replaced by `ip`.
+fn with_ip(address: &Multiaddr, ip: IpAddr) -> Multiaddr {
+    address
+        .iter()
+        .map(|protocol| match protocol {
+            Protocol::Ip4(_) | Protocol::Ip6(_) => Protocol::from(ip),
+            other => other,
+        })
+        .collect()
+}
+
+/// Known-good peers from the store (stale entries are dropped from it).
+fn load_peer_store(store: &dyn Storage) -> Vec<Multiaddr> {
+    let min_seen = unix_now().saturating_sub(PEER_STORE_MAX_AGE.as_secs());
+    let mut peers = store.get_peers().unwrap_or_default();
+    peers.sort_by_key(|p| std::cmp::Reverse(p.last_seen));
+    let mut addrs = Vec::new();
+    for peer in peers {
+        match peer.address.parse::<Multiaddr>() {
+            Ok(addr) if peer.last_seen >= min_seen => addrs.push(addr),
+            _ => {
+                let _ = store.remove_peer(&peer.peer_id);
+            }
+        }
+    }
+    addrs
+}
+
+fn remember_peer(store: &Option<Arc<dyn Storage>>, peer_id: &PeerId, addr: &Multiaddr) {
+    if let Some(store) = store {
+        let _ = store.save_peer(&KnownPeer {
+            peer_id: peer_id.to_string(),
+            address: addr.to_string(),
+            last_seen: unix_now(),
+        });
+    }
+}
+
+/// Gossipsub peer-scoring parameters for the consensus topics.
+fn peer_score_params() -> (gossipsub::PeerScoreParams, gossipsub::PeerScoreThresholds) {
+    let mut params = gossipsub::PeerScoreParams {
+        app_specific_weight: 1.0, // PeerManager score
+        // Local testnets run every validator on one host
+        ip_colocation_factor_weight: 0.0,
+        ..Default::default()
+    };
+    for topic in GossipTopic::ALL {
+        params.topics.insert(
+            topic.ident().hash(),
+            gossipsub::TopicScoreParams {
+                topic_weight: 1.0,
+                invalid_message_deliveries_weight: -10.0,
+                invalid_message_deliveries_decay: 0.5,
+                // Views without traffic are not a fault
+                mesh_message_deliveries_weight: 0.0,
+                mesh_failure_penalty_weight: 0.0,
+                ..Default::default()
+            },
+        );
+    }
+    let thresholds = gossipsub::PeerScoreThresholds {
+        gossip_threshold: -10.0,
+        publish_threshold: -50.0,
+        graylist_threshold: -80.0,
+        accept_px_threshold: 10.0,
+        opportunistic_graft_threshold: 20.0,
+    };
+    (params, thresholds)
+}
+
+/// Decode a gossip message on `topic`, checking its size and the signatures of signed payloads
+/// (votes are signed for `chain_id`).
+fn decode_message(
+    topic: GossipTopic,
+    limit: TopicLimit,
+    chain_id: u64,
+    message: &gossipsub::Message,
+) -> Result<NetworkEvent, Misbehavior> {
+    if message.data.len() > limit.max_message_size {
+        return Err(Misbehavior::InvalidMessage);
+    }
+    let data = &message.data;
+    match topic {
+        GossipTopic::Blocks => {
+            let compact = serde_json::from_slice::<CompactBlock>(data)
+                .map_err(|_| Misbehavior::InvalidMessage)?;
+            // Dummy blocks are never gossiped
+            if compact.header.is_dummy {
+                return Err(Misbehavior::InvalidMessage);
+            }
+            // The header signature covers the body through `body_hash`
+            if !compact.header.verify_signature() {
+                return Err(Misbehavior::InvalidSignature);
+            }
+            let peer_id = message.source.map(|p| p.to_string()).unwrap_or_default();
+            Ok(NetworkEvent::BlockReceived(compact, peer_id))
+        }
+        GossipTopic::Votes => {
+            if let Ok(vote) = serde_json::from_slice::<Vote>(data) {
+                if !vote.verify_signature(chain_id) {
+                    return Err(Misbehavior::InvalidSignature);
+                }
+                Ok(NetworkEvent::VoteReceived(vote))
+            } else if let Ok(aggregate) = serde_json::from_slice::<AggregateVote>(data) {
+                if aggregate.signers.is_empty() {
+                    return Err(Misbehavior::InvalidMessage);
+                }
+                Ok(NetworkEvent::AggregateVoteReceived(aggregate))
+            } else {
+                Err(Misbehavior::InvalidMessage)
+            }
+        }
+        GossipTopic::Txs => {
+            let tx = serde_json::from_slice::<Transaction>(data)
+                .map_err(|_| Misbehavior::InvalidMessage)?;
+            if !crate::crypto::verify(&tx.public_key, &tx.sighash().0, &tx.signature) {
+                return Err(Misbehavior::InvalidSignature);
+            }
+            Ok(NetworkEvent::TransactionReceived(tx))
+        }
+        GossipTopic::Evidence => {
+            if let Ok(evidence) = serde_json::from_slice::<EquivocationEvidence>(data) {
+                Ok(NetworkEvent::EvidenceReceived(evidence))
+            } else if let Ok(evidence) =
+                serde_json::from_slice::<ProposalEquivocationEvidence>(data)
+            {
+                if !evidence.is_valid() {
+                    return Err(Misbehavior::InvalidMessage);
+                }
+                Ok(NetworkEvent::ProposalEvidenceReceived(evidence))
+            } else {
+                Err(Misbehavior::InvalidMessage)
+            }
+        }
+        GossipTopic::Sync => {
+            let sync_msg = serde_json::from_slice::<crate::types::SyncMessage>(data)
+                .map_err(|_| Misbehavior::InvalidMessage)?;
+            let peer_id = message.source.map(|p| p.to_string()).unwrap_or_default();
+            Ok(NetworkEvent::SyncMessageReceived(sync_msg, peer_id))
+        }
+    }
+}
+
+fn publish<T: serde::Serialize>(
+    swarm: &mut Swarm<SimplexBehaviour>,
+    topic: GossipTopic,
+    message: &T,
+) {
+    let data = serde_json::to_vec(message).unwrap();
+    if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic.ident(), data) {
+        match e {
+            gossipsub::PublishError::Duplicate => {}
+            _ => tracing::warn!("Publish error on {}: {e:?}", topic.name()),
+        }
+    }
+}
+
+/// Cut a banned peer off: no gossip, no explicit peering, no connection.
+fn ban_peer(swarm: &mut Swarm<SimplexBehaviour>, peer_id: &PeerId) {
+    tracing::warn!("Banning peer: {peer_id}");
+    let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+    gossipsub.remove_explicit_peer(peer_id);
+    gossipsub.blacklist_peer(peer_id);
+    let _ = swarm.disconnect_peer_id(*peer_id);
+}
+
+/// A connected peer, as reported by `admin_peers`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub address: Option<String>,
+    pub score: i64,
+    /// We dialed it (bootnode, stored or admin-added peer).
+    pub outbound: bool,
+}
+
+/// Identity of the local node on the p2p network.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LocalPeerInfo {
+    pub peer_id: String,
+    pub listen_addrs: Vec<String>,
+    /// Addresses advertised to peers (`NetworkConfig::external_addr`).
+    #[serde(default)]
+    pub external_addrs: Vec<String>,
+}
+
 /// Commands sent from the application to the Network module.
 #[derive(Debug)]
 enum NetworkCommand {
     Broadcastblock(Block),
     BroadcastVote(Vote),
+    BroadcastAggregate(AggregateVote),
     BroadcastEvidence(EquivocationEvidence),
+    BroadcastProposalEvidence(ProposalEquivocationEvidence),
     BroadcastTransaction(Transaction),
     BroadcastSync(crate::types::SyncMessage),
     Dial(Multiaddr),
+    Peers(oneshot::Sender<Vec<PeerInfo>>),
+    AddPeer(Multiaddr),
+    RemovePeer(PeerId, oneshot::Sender<bool>),
+    LocalInfo(oneshot::Sender<LocalPeerInfo>),
+    Shutdown(oneshot::Sender<usize>),
 }
 
 /// The Network Interface.
 /// Manages the `Swarm` in a background task and communicates via channels.
+/// Other tasks (e.g. the admin RPC) talk to it through a `NetworkHandle`.
 pub struct Network {
     command_sender: mpsc::Sender<NetworkCommand>,
-    event_receiver: mpsc::Receiver<NetworkEvent>,
+    event_receiver: EventReceiver,
+    metrics: ChannelMetrics,
 }
 
 impl Network {
     pub async fn new(port: u16) -> Result<Self, Box<dyn Error>> {
-        let (command_sender, mut command_receiver) = mpsc::channel(100);
-        let (event_sender, event_receiver) = mpsc::channel(100);
+        Self::with_config(NetworkConfig {
+            port,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn with_config(config: NetworkConfig) -> Result<Self, Box<dyn Error>> {
+        let (command_sender, mut command_receiver) = mpsc::channel(config.command_queue.max(1));
+        let metrics = config.channel_metrics.clone();
+        let (event_sender, event_receiver) = event_channel(config.event_queues, metrics.clone());
+
+        let topic_limits = config.peer_score.topic_limits;
+        let chain_id = config.chain_id;
+        let external_addr = config.external_addr;
 
         // 1. Setup Swarm
-        let mut swarm = libp2p::SwarmBuilder::with_new_identity()
+        let node_key = config
+            .node_key
+            .clone()
+            .unwrap_or_else(identity::Keypair::generate_ed25519);
+        let mut swarm = libp2p::SwarmBuilder::with_existing_identity(node_key)
             .with_tokio()
             .with_tcp(
                 tcp::Config::default(),
@@ -70,14 +941,20 @@ impl Network {
                     .history_length(10) // Keep message history longer to relay to late joiners
                     .history_gossip(10) // Advertise history to more peers
                     .validation_mode(gossipsub::ValidationMode::Strict)
+                    .validate_messages() // Forward only after decode_message accepts
+                    .max_transmit_size(topic_limits.max_message_size())
                     .message_id_fn(message_id_fn)
                     .build()
                     .map_err(std::io::Error::other)?;
 
-                let gossipsub = gossipsub::Behaviour::new(
+                let mut gossipsub = gossipsub::Behaviour::new(
                     gossipsub::MessageAuthenticity::Signed(key.clone()),
                     gossipsub_config,
                 )?;
+                let (score_params, score_thresholds) = peer_score_params();
+                gossipsub
+                    .with_peer_score(score_params, score_thresholds)
+                    .map_err(std::io::Error::other)?;
 
                 // mDNS configuration
                 let mdns = mdns::tokio::Behaviour::new(
@@ -89,116 +966,250 @@ impl Network {
             })?
             .build();
 
-        // 1b. Listen on localhost with specified port
-        let addr = format!("/ip4/127.0.0.1/tcp/{}", port).parse()?;
+        // 1b. Listen on the configured interface and port
+        let addr = Multiaddr::from(config.listen_addr).with(Protocol::Tcp(config.port));
         swarm.listen_on(addr)?;
 
         // 2. Subscribe to topics
-        let topic = gossipsub::IdentTopic::new("simplex-consensus");
-        swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
+        for topic in &config.topics {
+            swarm.behaviour_mut().gossipsub.subscribe(&topic.ident())?;
+        }
 
-        // 3. Spawn background Task
+        // 3. Outbound Peers: bootnodes, then the peer store
+        let mut dialer = Dialer::new(config.redial_backoff, config.max_redial_backoff);
+        let now = Instant::now();
+        for addr in &config.bootnodes {
+            dialer.add(addr.clone(), true, now);
+        }
+        if let Some(store) = &config.peer_store {
+            for addr in load_peer_store(store.as_ref()) {
+                dialer.add(addr, false, now);
+            }
+        }
+        let peer_store = config.peer_store.clone();
+        let health = config.health.clone();
+        let target_peers = config.target_peers;
+        let mut dialing: HashMap<ConnectionId, Multiaddr> = HashMap::new();
+        let mut outbound: HashMap<PeerId, Multiaddr> = HashMap::new();
+        let mut connected: HashMap<PeerId, Multiaddr> = HashMap::new();
+        let mut maintenance = tokio::time::interval(Duration::from_secs(1));
+
+        // 4. Spawn background Task
+        let mut peers = PeerManager::new(config.peer_score);
         tokio::spawn(async move {
             loop {
                 tokio::select! {
+                    _ = maintenance.tick() => {
+                        let now = Instant::now();
+                        let wanted = target_peers.saturating_sub(dialer.connected());
+                        for addr in dialer.due(now, wanted) {
+                            let opts = DialOpts::unknown_peer_id().address(addr.clone()).build();
+                            let connection_id = opts.connection_id();
+                            match swarm.dial(opts) {
+                                Ok(()) => {
+                                    dialing.insert(connection_id, addr);
+                                }
+                                Err(e) => {
+                                    tracing::debug!("Dial error ({addr}): {e:?}");
+                                    dialer.on_failure(&addr, now);
+                                }
+                            }
+                        }
+                    },
                     event = swarm.select_next_some() => match event {
                         SwarmEvent::NewListenAddr { address, .. } => {
-                            println!("Swarm listening on {address:?}");
+                            tracing::info!("Swarm listening on {address:?}");
+                            if let Some(ip) = external_addr {
+                                let external = with_ip(&address, ip);
+                                tracing::info!("Advertising {external}");
+                                swarm.add_external_address(external);
+                            }
                         },
-                        SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                            println!("Connection established with peer: {peer_id}");
+                        SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, .. } => {
+                            let now = Instant::now();
+                            for peer in peers.prune_expired_bans(now) {
+                                swarm.behaviour_mut().gossipsub.remove_blacklisted_peer(&peer);
+                            }
+                            let dialed = dialing.remove(&connection_id);
+                            if peers.is_banned(&peer_id, now) {
+                                if let Some(addr) = dialed {
+                                    dialer.remove(&addr);
+                                }
+                                let _ = swarm.disconnect_peer_id(peer_id);
+                                continue;
+                            }
+                            if let Some(addr) = dialed {
+                                dialer.on_connected(&addr);
+                                remember_peer(&peer_store, &peer_id, &addr);
+                                outbound.insert(peer_id, addr);
+                            }
+                            tracing::info!("Connection established with peer: {peer_id}");
+                            connected.insert(peer_id, endpoint.get_remote_address().clone());
+                            if let Some(health) = &health {
+                                health.set_peer_count(swarm.connected_peers().count());
+                            }
                             swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
                             let _ = event_sender.send(NetworkEvent::PeerConnected(peer_id.to_string())).await;
                         },
-                        SwarmEvent::OutgoingConnectionError { error, .. } => {
-                            println!("Outgoing connection error: {error:?}");
+                        SwarmEvent::OutgoingConnectionError { connection_id, error, .. } => {
+                            tracing::debug!("Outgoing connection error: {error:?}");
+                            if let Some(addr) = dialing.remove(&connection_id) {
+                                dialer.on_failure(&addr, Instant::now());
+                            }
+                        },
+                        SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } => {
+                            connected.remove(&peer_id);
+                            if let Some(health) = &health {
+                                health.set_peer_count(swarm.connected_peers().count());
+                            }
+                            if let Some(addr) = outbound.remove(&peer_id) {
+                                tracing::info!("Lost outbound peer {peer_id}, redialing {addr}");
+                                remember_peer(&peer_store, &peer_id, &addr);
+                                dialer.on_disconnected(&addr, Instant::now());
+                            }
                         },
                         SwarmEvent::Behaviour(SimplexBehaviourEvent::Mdns(mdns::Event::Discovered(list))) => {
                             for (peer_id, _multiaddr) in list {
-                                println!("mDNS discovered a new peer: {peer_id}");
+                                if peers.is_banned(&peer_id, Instant::now()) {
+                                    continue;
+                                }
+                                tracing::info!("mDNS discovered a new peer: {peer_id}");
                                 swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
                                 let _ = event_sender.send(NetworkEvent::PeerConnected(peer_id.to_string())).await;
                             }
                         },
                         SwarmEvent::Behaviour(SimplexBehaviourEvent::Mdns(mdns::Event::Expired(list))) => {
                              for (peer_id, _multiaddr) in list {
-                                println!("mDNS discover peer has expired: {peer_id}");
+                                tracing::info!("mDNS discover peer has expired: {peer_id}");
                                 swarm.behaviour_mut().gossipsub.remove_explicit_peer(&peer_id);
                             }
                         },
-                        SwarmEvent::Behaviour(SimplexBehaviourEvent::Gossipsub(gossipsub::Event::Message { propagation_source: _peer_id, message_id: _id, message })) => {
-                            // Deserialize message
-                             if let Ok(block) = serde_json::from_slice::<Block>(&message.data) {
-                                 let _ = event_sender.send(NetworkEvent::BlockReceived(block)).await;
-                             } else if let Ok(vote) = serde_json::from_slice::<Vote>(&message.data) {
-                                 let _ = event_sender.send(NetworkEvent::VoteReceived(vote)).await;
-                             } else if let Ok(evidence) = serde_json::from_slice::<EquivocationEvidence>(&message.data) {
-                                 let _ = event_sender.send(NetworkEvent::EvidenceReceived(evidence)).await;
-                             } else if let Ok(tx) = serde_json::from_slice::<Transaction>(&message.data) {
-                                let _ = event_sender.send(NetworkEvent::TransactionReceived(tx)).await;
-                             } else if let Ok(sync_msg) = serde_json::from_slice::<crate::types::SyncMessage>(&message.data) {
-                                let peer_id = message.source.map(|p| p.to_string()).unwrap_or_default();
-                                let _ = event_sender.send(NetworkEvent::SyncMessageReceived(sync_msg, peer_id)).await;
-                             }
+                        SwarmEvent::Behaviour(SimplexBehaviourEvent::Gossipsub(gossipsub::Event::Message { propagation_source, message_id, message })) => {
+                            let now = Instant::now();
+                            let verdict = if peers.is_banned(&propagation_source, now) {
+                                Err(None)
+                            } else {
+                                GossipTopic::from_hash(&message.topic)
+                                    .ok_or(Misbehavior::InvalidMessage)
+                                    .and_then(|topic| {
+                                        peers.record_message(&propagation_source, topic, now)?;
+                                        decode_message(topic, topic_limits.get(topic), chain_id, &message)
+                                    })
+                                    .map_err(Some)
+                            };
+                            let acceptance = match verdict {
+                                Ok(event) => {
+                                    // Votes travel one hop: peers fold them into the
+                                    // aggregates they gossip instead of forwarding them
+                                    let acceptance = match event {
+                                        NetworkEvent::VoteReceived(_) => gossipsub::MessageAcceptance::Ignore,
+                                        _ => gossipsub::MessageAcceptance::Accept,
+                                    };
+                                    let _ = event_sender.send(event).await;
+                                    acceptance
+                                }
+                                Err(None) => gossipsub::MessageAcceptance::Ignore,
+                                Err(Some(misbehavior)) => {
+                                    tracing::warn!("Peer {propagation_source} misbehaved: {misbehavior:?}");
+                                    let banned = peers.report(&propagation_source, misbehavior, now);
+                                    let score = peers.score(&propagation_source) as f64;
+                                    swarm.behaviour_mut().gossipsub.set_application_score(&propagation_source, score);
+                                    if banned {
+                                        ban_peer(&mut swarm, &propagation_source);
+                                        if let Some(addr) = outbound.remove(&propagation_source) {
+                                            dialer.remove(&addr);
+                                        }
+                                        if let Some(store) = &peer_store {
+                                            let _ = store.remove_peer(&propagation_source.to_string());
+                                        }
+                                    }
+                                    match misbehavior {
+                                        Misbehavior::Spam => gossipsub::MessageAcceptance::Ignore,
+                                        _ => gossipsub::MessageAcceptance::Reject,
+                                    }
+                                }
+                            };
+                            let _ = swarm.behaviour_mut().gossipsub.report_message_validation_result(&message_id, &propagation_source, acceptance);
                         },
                         _ => {}
                     },
                     command = command_receiver.recv() => match command {
                         Some(NetworkCommand::Broadcastblock(block)) => {
-                            let data = serde_json::to_vec(&block).unwrap();
-                            let topic = gossipsub::IdentTopic::new("simplex-consensus");
-                             if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic, data) {
-                                match e {
-                                    gossipsub::PublishError::Duplicate => {},
-                                    _ => println!("Publish error: {e:?}"),
-                                }
-                             }
+                            publish(&mut swarm, GossipTopic::Blocks, &CompactBlock::new(&block));
+                        },
+                        Some(NetworkCommand::BroadcastVote(vote)) => {
+                            publish(&mut swarm, GossipTopic::Votes, &vote);
+                        },
+                        Some(NetworkCommand::BroadcastAggregate(aggregate)) => {
+                            publish(&mut swarm, GossipTopic::Votes, &aggregate);
+                        },
+                        Some(NetworkCommand::BroadcastEvidence(evidence)) => {
+                            publish(&mut swarm, GossipTopic::Evidence, &evidence);
+                        },
+                        Some(NetworkCommand::BroadcastProposalEvidence(evidence)) => {
+                            publish(&mut swarm, GossipTopic::Evidence, &evidence);
+                        },
+                        Some(NetworkCommand::BroadcastTransaction(tx)) => {
+                            publish(&mut swarm, GossipTopic::Txs, &tx);
+                        },
+                        Some(NetworkCommand::BroadcastSync(msg)) => {
+                            publish(&mut swarm, GossipTopic::Sync, &msg);
                         },
-                         Some(NetworkCommand::BroadcastVote(vote)) => {
-                              let data = serde_json::to_vec(&vote).unwrap();
-                              let topic = gossipsub::IdentTopic::new("simplex-consensus");
-                              if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic, data) {
-                                 match e {
-                                     gossipsub::PublishError::Duplicate => {},
-                                     _ => println!("Publish error: {e:?}"),
-                                 }
-                              }
-                         },
-                          Some(NetworkCommand::BroadcastEvidence(evidence)) => {
-                               let data = serde_json::to_vec(&evidence).unwrap();
-                               let topic = gossipsub::IdentTopic::new("simplex-consensus");
-                               if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic, data) {
-                                  match e {
-                                      gossipsub::PublishError::Duplicate => {},
-                                      _ => println!("Publish error: {e:?}"),
-                                  }
-                               }
-                          },
-                          Some(NetworkCommand::BroadcastTransaction(tx)) => {
-                               let data = serde_json::to_vec(&tx).unwrap();
-                               let topic = gossipsub::IdentTopic::new("simplex-consensus");
-                               if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic, data) {
-                                  match e {
-                                      gossipsub::PublishError::Duplicate => {},
-                                      _ => println!("Publish error: {e:?}"),
-                                  }
-                               }
-                          },
-                         Some(NetworkCommand::BroadcastSync(msg)) => {
-                              let data = serde_json::to_vec(&msg).unwrap();
-                              let topic = gossipsub::IdentTopic::new("simplex-consensus");
-                              if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic, data) {
-                                 match e {
-                                     gossipsub::PublishError::Duplicate => {},
-                                     _ => println!("Publish error: {e:?}"),
-                                 }
-                              }
-                         },
                         Some(NetworkCommand::Dial(addr)) => {
                              if let Err(e) = swarm.dial(addr) {
-                                println!("Dial error: {e:?}");
+                                tracing::warn!("Dial error: {e:?}");
                              }
                         },
+                        Some(NetworkCommand::Peers(reply)) => {
+                            let list = swarm
+                                .connected_peers()
+                                .map(|peer_id| PeerInfo {
+                                    peer_id: peer_id.to_string(),
+                                    address: connected.get(peer_id).map(|a| a.to_string()),
+                                    score: peers.score(peer_id),
+                                    outbound: outbound.contains_key(peer_id),
+                                })
+                                .collect();
+                            let _ = reply.send(list);
+                        },
+                        Some(NetworkCommand::AddPeer(addr)) => {
+                            // Static: redialed with backoff like a bootnode
+                            tracing::info!("Adding peer {addr}");
+                            dialer.add(addr, true, Instant::now());
+                        },
+                        Some(NetworkCommand::RemovePeer(peer_id, reply)) => {
+                            tracing::info!("Removing peer {peer_id}");
+                            if let Some(addr) = outbound.remove(&peer_id) {
+                                dialer.remove(&addr);
+                            }
+                            if let Some(store) = &peer_store {
+                                let _ = store.remove_peer(&peer_id.to_string());
+                            }
+                            let _ = reply.send(swarm.disconnect_peer_id(peer_id).is_ok());
+                        },
+                        Some(NetworkCommand::LocalInfo(reply)) => {
+                            let _ = reply.send(LocalPeerInfo {
+                                peer_id: swarm.local_peer_id().to_string(),
+                                listen_addrs: swarm.listeners().map(|a| a.to_string()).collect(),
+                                external_addrs: swarm.external_addresses().map(|a| a.to_string()).collect(),
+                            });
+                        },
+                        Some(NetworkCommand::Shutdown(reply)) => {
+                            // Close connections (flushing queued messages) before the swarm is dropped
+                            let peers: Vec<PeerId> = swarm.connected_peers().copied().collect();
+                            for peer_id in &peers {
+                                let _ = swarm.disconnect_peer_id(*peer_id);
+                            }
+                            let _ = tokio::time::timeout(SHUTDOWN_GRACE, async {
+                                while swarm.connected_peers().next().is_some() {
+                                    swarm.select_next_some().await;
+                                }
+                            })
+                            .await;
+                            tracing::info!("Network closed ({} peers disconnected)", peers.len());
+                            let _ = reply.send(peers.len());
+                            break;
+                        },
                         None => break, // Channel closed
                     }
                 }
@@ -208,54 +1219,140 @@ impl Network {
         Ok(Network {
             command_sender,
             event_receiver,
+            metrics,
         })
     }
 
+    /// Queue a command for the network task, waiting while the channel is full.
+    async fn command(&self, command: NetworkCommand) {
+        self.metrics
+            .send(COMMAND_CHANNEL, &self.command_sender, command)
+            .await;
+    }
+
     pub async fn dial(&self, addr: &str) {
         if let Ok(multiaddr) = addr.parse() {
-            let _ = self
-                .command_sender
-                .send(NetworkCommand::Dial(multiaddr))
-                .await;
+            self.command(NetworkCommand::Dial(multiaddr)).await;
         }
     }
 
+    /// Gossip `block` as a compact block (transactions by hash).
     pub async fn broadcast_block(&self, block: Block) {
-        let _ = self
-            .command_sender
-            .send(NetworkCommand::Broadcastblock(block))
-            .await;
+        self.command(NetworkCommand::Broadcastblock(block)).await;
     }
 
     pub async fn broadcast_vote(&self, vote: Vote) {
-        let _ = self
-            .command_sender
-            .send(NetworkCommand::BroadcastVote(vote))
+        self.command(NetworkCommand::BroadcastVote(vote)).await;
+    }
+
+    /// Gossip an aggregate of the votes for one target (see `SimplexState::on_aggregate_vote`).
+    pub async fn broadcast_aggregate_vote(&self, aggregate: AggregateVote) {
+        self.command(NetworkCommand::BroadcastAggregate(aggregate))
             .await;
     }
 
     pub async fn broadcast_evidence(&self, evidence: EquivocationEvidence) {
-        let _ = self
-            .command_sender
-            .send(NetworkCommand::BroadcastEvidence(evidence))
+        self.command(NetworkCommand::BroadcastEvidence(evidence))
             .await;
     }
 
-    pub async fn broadcast_sync(&self, msg: crate::types::SyncMessage) {
-        let _ = self
-            .command_sender
-            .send(NetworkCommand::BroadcastSync(msg))
+    pub async fn broadcast_proposal_evidence(&self, evidence: ProposalEquivocationEvidence) {
+        self.command(NetworkCommand::BroadcastProposalEvidence(evidence))
             .await;
     }
 
+    pub async fn broadcast_sync(&self, msg: crate::types::SyncMessage) {
+        self.command(NetworkCommand::BroadcastSync(msg)).await;
+    }
+
+    /// Dropped when the command channel is full: local transactions are gossiped again
+    /// until included.
     pub async fn broadcast_transaction(&self, tx: Transaction) {
-        let _ = self
-            .command_sender
-            .send(NetworkCommand::BroadcastTransaction(tx))
-            .await;
+        self.metrics.try_send(
+            COMMAND_CHANNEL,
+            &self.command_sender,
+            NetworkCommand::BroadcastTransaction(tx),
+        );
     }
 
+    /// The next event, most urgent class first (see `EventPriority`).
     pub async fn next_event(&mut self) -> Option<NetworkEvent> {
         self.event_receiver.recv().await
     }
+
+    /// Gossiped transactions dropped because the consensus loop fell behind.
+    pub fn dropped_transactions(&self) -> u64 {
+        self.event_receiver.dropped_transactions()
+    }
+
+    /// Send outcomes of the command channel and event queues.
+    pub fn channel_metrics(&self) -> &ChannelMetrics {
+        &self.metrics
+    }
+
+    pub fn handle(&self) -> NetworkHandle {
+        NetworkHandle {
+            command_sender: self.command_sender.clone(),
+        }
+    }
+
+    /// Disconnect every peer and stop the network task. Returns how many peers were
+    /// disconnected (None if the task had already stopped).
+    pub async fn shutdown(&self) -> Option<usize> {
+        let (reply, response) = oneshot::channel();
+        self.command_sender
+            .send(NetworkCommand::Shutdown(reply))
+            .await
+            .ok()?;
+        response.await.ok()
+    }
+}
+
+/// Cloneable access to peer management on a running `Network`.
+#[derive(Clone)]
+pub struct NetworkHandle {
+    command_sender: mpsc::Sender<NetworkCommand>,
+}
+
+impl NetworkHandle {
+    /// Connected peers (None if the network task has stopped).
+    pub async fn peers(&self) -> Option<Vec<PeerInfo>> {
+        let (reply, response) = oneshot::channel();
+        self.command_sender
+            .send(NetworkCommand::Peers(reply))
+            .await
+            .ok()?;
+        response.await.ok()
+    }
+
+    /// Dial `addr` and keep redialing it whenever the connection drops.
+    pub async fn add_peer(&self, addr: Multiaddr) -> bool {
+        self.command_sender
+            .send(NetworkCommand::AddPeer(addr))
+            .await
+            .is_ok()
+    }
+
+    /// Disconnect `peer_id` and forget its address. Returns false if it was not connected.
+    pub async fn remove_peer(&self, peer_id: PeerId) -> bool {
+        let (reply, response) = oneshot::channel();
+        if self
+            .command_sender
+            .send(NetworkCommand::RemovePeer(peer_id, reply))
+            .await
+            .is_err()
+        {
+            return false;
+        }
+        response.await.unwrap_or(false)
+    }
+
+    pub async fn local_info(&self) -> Option<LocalPeerInfo> {
+        let (reply, response) = oneshot::channel();
+        self.command_sender
+            .send(NetworkCommand::LocalInfo(reply))
+            .await
+            .ok()?;
+        response.await.ok()
+    }
 }
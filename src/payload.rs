@@ -0,0 +1,194 @@
+//! Block payload builders: how a proposer picks and orders pool transactions.
+//!
+//! `SimplexState` asks its `PayloadBuilder` for the payload of each proposal
+//! (`with_payload_builder`), so selection policies can be swapped without touching
+//! consensus. Builders only see the pool's candidates; execution still drops
+//! transactions that turn out invalid. Payloads stay within a byte budget, so the
+//! block can be gossiped (`ChainParams::max_block_size`). With a deadline, builders return what they have
+//! selected when it passes, so a huge pool cannot make the leader miss its view.
+
+use crate::types::{Address, Transaction, U256, wire_size};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Constraints of the block being built.
+#[derive(Clone, Debug)]
+pub struct PayloadRequest<'a> {
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    /// Senders whose transactions go first (see `ProposerConfig::operator_accounts`).
+    pub operators: &'a HashSet<Address>,
+    /// Encoded bytes (`wire_size`, plus a list separator each) the selected transactions
+    /// may take in total.
+    pub max_size: u64,
+    /// Stop selecting at this time (see `ProposerConfig::time_budget`).
+    pub deadline: Option<Instant>,
+}
+
+impl PayloadRequest<'_> {
+    /// Whether the deadline has passed: the payload selected so far is final.
+    pub fn expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Selected transactions, in block order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Payload {
+    pub transactions: Vec<Transaction>,
+    /// Number of leading operator transactions.
+    pub operator_txs: usize,
+}
+
+pub trait PayloadBuilder: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Build a payload out of `candidates`, the pooled transactions affording `base_fee`.
+    fn build(&self, candidates: Vec<Transaction>, request: &PayloadRequest) -> Payload;
+}
+
+/// Tip paid to the proposer: min(max_priority_fee, max_fee - base_fee).
+pub fn effective_tip(tx: &Transaction, base_fee: U256) -> U256 {
+    std::cmp::min(
+        tx.max_priority_fee_per_gas,
+        tx.max_fee_per_gas.saturating_sub(base_fee),
+    )
+}
+
+/// Greedy by effective tip across all senders, skipping transactions that do not fit.
+/// A sender's transactions with different tips may end up out of nonce order.
+pub struct PriorityFeeBuilder;
+
+impl PayloadBuilder for PriorityFeeBuilder {
+    fn name(&self) -> &'static str {
+        "priority-fee"
+    }
+
+    fn build(&self, mut candidates: Vec<Transaction>, request: &PayloadRequest) -> Payload {
+        let base_fee = request.base_fee;
+        // Tip descending, then nonce ascending for the same sender, then by public key
+        candidates.sort_by(|a, b| {
+            effective_tip(b, base_fee)
+                .cmp(&effective_tip(a, base_fee))
+                .then_with(|| {
+                    if a.public_key == b.public_key {
+                        a.nonce.cmp(&b.nonce)
+                    } else {
+                        a.public_key.cmp(&b.public_key)
+                    }
+                })
+        });
+
+        // Operator transactions go first (stable, so per-sender order is kept)
+        let (operator_txs, other_txs): (Vec<Transaction>, Vec<Transaction>) = candidates
+            .into_iter()
+            .partition(|tx| request.operators.contains(&tx.sender()));
+        let operator_count = operator_txs.len();
+
+        let mut payload = Payload::default();
+        let mut gas = 0u64;
+        let mut size = 0u64;
+        for (i, tx) in operator_txs.into_iter().chain(other_txs).enumerate() {
+            if request.expired() {
+                break;
+            }
+            let tx_size = wire_size(&tx) as u64 + 1;
+            if gas + tx.gas_limit <= request.gas_limit
+                && size.saturating_add(tx_size) <= request.max_size
+            {
+                gas += tx.gas_limit;
+                size += tx_size;
+                payload.transactions.push(tx);
+                if i < operator_count {
+                    payload.operator_txs += 1;
+                }
+            }
+            if gas >= request.gas_limit {
+                break;
+            }
+        }
+        payload
+    }
+}
+
+/// Greedy by effective tip over the next transaction of each sender, so a sender's
+/// transactions are always included in nonce order. A sender whose next transaction does
+/// not fit contributes nothing more (its later nonces could not execute before it).
+/// Of several transactions with the same sender and nonce, the best paying is used.
+pub struct NonceOrderedBuilder;
+
+impl PayloadBuilder for NonceOrderedBuilder {
+    fn name(&self) -> &'static str {
+        "nonce-ordered"
+    }
+
+    fn build(&self, candidates: Vec<Transaction>, request: &PayloadRequest) -> Payload {
+        let base_fee = request.base_fee;
+        let mut queues: HashMap<Address, BTreeMap<u64, Transaction>> = HashMap::new();
+        for tx in candidates {
+            let queue = queues.entry(tx.sender()).or_default();
+            match queue.get(&tx.nonce) {
+                Some(existing)
+                    if effective_tip(existing, base_fee) >= effective_tip(&tx, base_fee) => {}
+                _ => {
+                    queue.insert(tx.nonce, tx);
+                }
+            }
+        }
+
+        // Next transaction of each sender: operators first, then tip descending, then sender
+        let head = |sender: Address, queue: &BTreeMap<u64, Transaction>| {
+            let (_, tx) = queue.first_key_value()?;
+            let operator = request.operators.contains(&sender);
+            Some((operator, effective_tip(tx, base_fee), Reverse(sender)))
+        };
+        let mut heads: BinaryHeap<_> = queues
+            .iter()
+            .filter_map(|(sender, queue)| head(*sender, queue))
+            .collect();
+
+        let mut payload = Payload::default();
+        let mut gas = 0u64;
+        let mut size = 0u64;
+        while let Some((operator, _, Reverse(sender))) = heads.pop() {
+            if request.expired() {
+                break;
+            }
+            let Some(queue) = queues.get_mut(&sender) else {
+                continue;
+            };
+            let Some((_, tx)) = queue.pop_first() else {
+                continue;
+            };
+            let tx_size = wire_size(&tx) as u64 + 1;
+            if gas + tx.gas_limit > request.gas_limit
+                || size.saturating_add(tx_size) > request.max_size
+            {
+                continue; // Drops the sender's remaining transactions
+            }
+            gas += tx.gas_limit;
+            size += tx_size;
+            payload.transactions.push(tx);
+            if operator {
+                payload.operator_txs += 1;
+            }
+            if gas >= request.gas_limit {
+                break;
+            }
+            heads.extend(head(sender, queue));
+        }
+        payload
+    }
+}
+
+/// Builder selected by `name` (the `--payload-builder` option), if it exists.
+pub fn builder_by_name(name: &str) -> Option<Arc<dyn PayloadBuilder>> {
+    match name {
+        "priority-fee" => Some(Arc::new(PriorityFeeBuilder)),
+        "nonce-ordered" => Some(Arc::new(NonceOrderedBuilder)),
+        _ => None,
+    }
+}
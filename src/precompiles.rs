@@ -0,0 +1,201 @@
+use crate::state::StateManager;
+use crate::types::{Address, Block, Log, Transaction};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Staking system contract (stake / unstake / withdraw).
+pub use crate::system_contracts::STAKING_ADDRESS;
+
+/// Light client system contract (counterparty headers and token transfers).
+pub use crate::system_contracts::LIGHT_CLIENT_ADDRESS;
+
+/// Governance system contract (chain parameter proposals and votes).
+pub use crate::system_contracts::GOVERNANCE_ADDRESS;
+
+/// Encrypted mempool system contract (ciphertexts awaiting decryption by the committee).
+pub use crate::system_contracts::ENCRYPTED_MEMPOOL_ADDRESS;
+
+/// BLS12-381 signature verification (min_sig scheme used by consensus).
+pub const BLS_VERIFY_ADDRESS: Address = Address::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00,
+]);
+
+pub const BLS_VERIFY_GAS: u64 = 150_000;
+
+#[derive(Debug, Error)]
+pub enum PrecompileError {
+    #[error("Out of gas")]
+    OutOfGas,
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Reverted: {0}")]
+    Revert(String),
+    #[error("State error: {0}")]
+    State(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrecompileOutput {
+    pub gas_used: u64,
+    pub output: Vec<u8>,
+    pub logs: Vec<Log>,
+}
+
+/// Execution context handed to a precompile invoked by a top-level transaction.
+pub struct PrecompileContext<'a> {
+    pub db: &'a mut StateManager,
+    pub block: &'a Block,
+    pub tx: &'a Transaction,
+}
+
+/// A chain-specific contract implemented natively.
+/// Handlers must validate before writing: state changes are kept even if `call` errors.
+pub trait Precompile: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn call(
+        &self,
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError>;
+}
+
+/// Registry of Address -> Precompile handler consulted by the Executor before revm.
+#[derive(Clone, Default)]
+pub struct PrecompileRegistry {
+    handlers: HashMap<Address, Arc<dyn Precompile>>,
+}
+
+impl PrecompileRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the standard Ethereum precompiles (ecrecover, sha256, ripemd160,
+    /// identity, modexp, bn128, blake2f) plus the Ockham specific ones.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        let standard = revm::precompile::Precompiles::latest();
+        for address in standard.addresses() {
+            if let Some(precompile) = standard.get(address) {
+                registry.register(*address, Arc::new(StandardPrecompile(precompile)));
+            }
+        }
+        registry.register(BLS_VERIFY_ADDRESS, Arc::new(BlsVerifyPrecompile));
+        registry.register(
+            STAKING_ADDRESS,
+            Arc::new(crate::system_contracts::StakingContract),
+        );
+        registry.register(
+            LIGHT_CLIENT_ADDRESS,
+            Arc::new(crate::system_contracts::LightClientContract),
+        );
+        registry.register(
+            GOVERNANCE_ADDRESS,
+            Arc::new(crate::system_contracts::GovernanceContract),
+        );
+        registry.register(
+            ENCRYPTED_MEMPOOL_ADDRESS,
+            Arc::new(crate::system_contracts::EncryptedMempoolContract),
+        );
+        registry
+    }
+
+    /// Register (or replace) the handler at `address`.
+    pub fn register(&mut self, address: Address, handler: Arc<dyn Precompile>) {
+        self.handlers.insert(address, handler);
+    }
+
+    pub fn get(&self, address: &Address) -> Option<Arc<dyn Precompile>> {
+        self.handlers.get(address).cloned()
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.handlers.contains_key(address)
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.handlers.keys().copied().collect()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Standard Ethereum precompiles (delegated to revm)
+// -----------------------------------------------------------------------------
+pub struct StandardPrecompile(revm::precompile::Precompile);
+
+impl Precompile for StandardPrecompile {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+
+    fn call(
+        &self,
+        _ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        let result = match &self.0 {
+            revm::precompile::Precompile::Standard(f) => f(input, gas_limit),
+            revm::precompile::Precompile::Env(f) => {
+                f(input, gas_limit, &revm::primitives::Env::default())
+            }
+        };
+        match result {
+            Ok((gas_used, output)) => Ok(PrecompileOutput {
+                gas_used,
+                output,
+                logs: vec![],
+            }),
+            Err(revm::precompile::Error::OutOfGas) => Err(PrecompileError::OutOfGas),
+            Err(e) => Err(PrecompileError::InvalidInput(format!("{:?}", e))),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// BLS Verify
+// Input: public key (96 bytes) ++ signature (48 bytes) ++ message
+// Output: 32-byte word, 1 if valid, 0 otherwise
+// -----------------------------------------------------------------------------
+pub struct BlsVerifyPrecompile;
+
+impl Precompile for BlsVerifyPrecompile {
+    fn name(&self) -> &'static str {
+        "bls_verify"
+    }
+
+    fn call(
+        &self,
+        _ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        if gas_limit < BLS_VERIFY_GAS {
+            return Err(PrecompileError::OutOfGas);
+        }
+        if input.len() < 96 + 48 {
+            return Err(PrecompileError::InvalidInput("input too short".into()));
+        }
+        let pk = blst::min_sig::PublicKey::from_bytes(&input[..96])
+            .map_err(|e| PrecompileError::InvalidInput(format!("{:?}", e)))?;
+        let sig = blst::min_sig::Signature::from_bytes(&input[96..144])
+            .map_err(|e| PrecompileError::InvalidInput(format!("{:?}", e)))?;
+
+        let valid = crate::crypto::verify(
+            &crate::crypto::PublicKey(pk),
+            &input[144..],
+            &crate::crypto::Signature(sig),
+        );
+
+        let mut output = vec![0u8; 32];
+        output[31] = valid as u8;
+        Ok(PrecompileOutput {
+            gas_used: BLS_VERIFY_GAS,
+            output,
+            logs: vec![],
+        })
+    }
+}
@@ -0,0 +1,300 @@
+use futures::future::Either;
+use jsonrpsee::server::MethodResponse;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::Request;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// JSON-RPC error code of rate-limited calls (the "limit exceeded" code of EIP-1474, an
+/// HTTP 429 counterpart).
+pub const RATE_LIMITED_CODE: i32 = -32005;
+
+/// Buckets kept before full (idle) ones are dropped.
+pub const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Token bucket: up to `burst` calls at once, refilled at `per_sec` calls per second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quota {
+    pub per_sec: u32,
+    pub burst: u32,
+}
+
+impl std::str::FromStr for Quota {
+    type Err = String;
+
+    /// `<per_sec>:<burst>`, e.g. `10:50`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid rate limit {:?} (expected <per_sec>:<burst>)", s);
+        let (per_sec, burst) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            per_sec: per_sec.parse().map_err(|_| invalid())?,
+            burst: burst.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Limits applied to each client of the RPC server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// `send_transaction` and the other calls that add to the mempool or state.
+    pub transactions: Quota,
+    /// Calls that execute the EVM or scan blocks and receipts.
+    pub heavy_reads: Quota,
+    /// Identify clients by the `X-Forwarded-For` / `X-Real-IP` header of a reverse proxy.
+    /// Without a proxy in front the headers are client-controlled, so each connection is
+    /// its own client.
+    pub trust_proxy: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            transactions: Quota {
+                per_sec: 10,
+                burst: 50,
+            },
+            heavy_reads: Quota {
+                per_sec: 20,
+                burst: 100,
+            },
+            trust_proxy: false,
+        }
+    }
+}
+
+/// Methods that are rate limited, by cost.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum MethodClass {
+    Transaction,
+    HeavyRead,
+}
+
+impl MethodClass {
+    /// Class of `method`; other methods are cheap and not limited.
+    pub fn of(method: &str) -> Option<Self> {
+        match method {
+            "send_transaction" | "faucet_request" | "dev_fundAccount" => Some(Self::Transaction),
+            "call"
+            | "estimate_gas"
+            | "get_logs"
+            | "get_proof"
+            | "get_account_proof"
+            | "get_transaction_proof"
+            | "get_transaction_receipt"
+            | "get_state_diff"
+            | "bridge_getUpdates" => Some(Self::HeavyRead),
+            _ => None,
+        }
+    }
+}
+
+/// Who a call is accounted to.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ClientKey {
+    /// Address reported by a trusted reverse proxy.
+    Ip(IpAddr),
+    /// A connection to the server, numbered in accept order.
+    Connection(u64),
+    /// Calls that did not go through `ClientKeyLayer` share one bucket.
+    Unknown,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets per client and method class.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Arc<Mutex<HashMap<(ClientKey, MethodClass), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    fn quota(&self, class: MethodClass) -> Quota {
+        match class {
+            MethodClass::Transaction => self.config.transactions,
+            MethodClass::HeavyRead => self.config.heavy_reads,
+        }
+    }
+
+    /// Take a token for a call of `class` by `client` at `now`. Returns how long until a
+    /// token is available when the bucket is empty.
+    pub fn check(
+        &self,
+        client: &ClientKey,
+        class: MethodClass,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let quota = self.quota(class);
+        let burst = quota.burst as f64;
+        let per_sec = quota.per_sec as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|(_, class), bucket| {
+                let quota = self.quota(*class);
+                let refilled = bucket.tokens
+                    + now.saturating_duration_since(bucket.updated).as_secs_f64()
+                        * quota.per_sec as f64;
+                refilled < quota.burst as f64
+            });
+        }
+
+        let bucket = buckets.entry((client.clone(), class)).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        if per_sec == 0.0 {
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+    }
+}
+
+/// HTTP middleware tagging each request with its `ClientKey`. The server builds the
+/// middleware once per connection, which is what numbers the connections.
+///
+/// Install with `tower::ServiceBuilder::new().layer(ClientKeyLayer::new(trust_proxy))`.
+#[derive(Clone)]
+pub struct ClientKeyLayer {
+    trust_proxy: bool,
+    connections: Arc<AtomicU64>,
+}
+
+impl ClientKeyLayer {
+    pub fn new(trust_proxy: bool) -> Self {
+        Self {
+            trust_proxy,
+            connections: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<S> tower::Layer<S> for ClientKeyLayer {
+    type Service = ClientKeyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientKeyService {
+            inner,
+            trust_proxy: self.trust_proxy,
+            connection: self.connections.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ClientKeyService<S> {
+    inner: S,
+    trust_proxy: bool,
+    connection: u64,
+}
+
+impl<S, B> tower::Service<http::Request<B>> for ClientKeyService<S>
+where
+    S: tower::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        let forwarded = self
+            .trust_proxy
+            .then(|| forwarded_ip(request.headers()))
+            .flatten();
+        let key = forwarded
+            .map(ClientKey::Ip)
+            .unwrap_or(ClientKey::Connection(self.connection));
+        request.extensions_mut().insert(key);
+        self.inner.call(request)
+    }
+}
+
+/// Client address set by a reverse proxy: the first `X-Forwarded-For` entry, or
+/// `X-Real-IP`.
+pub fn forwarded_ip(headers: &http::HeaderMap) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    header("x-forwarded-for")
+        .and_then(|v| v.split(',').next())
+        .or_else(|| header("x-real-ip"))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// RPC middleware rejecting calls over their client's quota with a `RATE_LIMITED_CODE`
+/// error (each call of a batch counts).
+///
+/// Install with `RpcServiceBuilder::new().layer_fn(move |s| RpcRateLimit::new(s, limiter.clone()))`.
+#[derive(Clone)]
+pub struct RpcRateLimit<S> {
+    inner: S,
+    limiter: RateLimiter,
+}
+
+impl<S> RpcRateLimit<S> {
+    pub fn new(inner: S, limiter: RateLimiter) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcRateLimit<S>
+where
+    S: RpcServiceT<'a>,
+{
+    type Future = Either<S::Future, std::future::Ready<MethodResponse>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        if let Some(class) = MethodClass::of(request.method_name()) {
+            let client = request
+                .extensions()
+                .get::<ClientKey>()
+                .cloned()
+                .unwrap_or(ClientKey::Unknown);
+            if let Err(retry_after) = self.limiter.check(&client, class, Instant::now()) {
+                tracing::debug!(
+                    "Rate limited {:?} calling {}",
+                    client,
+                    request.method_name()
+                );
+                let error = jsonrpsee::types::ErrorObject::owned(
+                    RATE_LIMITED_CODE,
+                    "Rate limit exceeded",
+                    Some(serde_json::json!({
+                        "retry_after_ms": retry_after.as_millis().min(u64::MAX as u128) as u64
+                    })),
+                );
+                return Either::Right(std::future::ready(MethodResponse::error(
+                    request.id(),
+                    error,
+                )));
+            }
+        }
+        Either::Left(self.inner.call(request))
+    }
+}
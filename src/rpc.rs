@@ -1,13 +1,33 @@
-use crate::crypto::Hash;
-use crate::storage::{ConsensusState, Storage};
+use crate::bridge::{BridgeCommittee, BridgeUpdate, MAX_BRIDGE_UPDATES};
+use crate::crypto::{Hash, PrivateKey, PublicKey, Signature, hash_data, sign, verify};
+use crate::events::{EventRecord, EventStream};
+use crate::faucet::{Faucet, FaucetError};
+use crate::health::{HealthMonitor, HealthReport, unix_now};
+use crate::light::{AccountProof, FinalityProof, FinalizedBlock, ProofRequest, TransactionProof};
+use crate::network::{NetworkHandle, PeerInfo};
+use crate::state::{StateDiff, StateProof};
+use crate::storage::{ConsensusState, Storage, StorageError, ValidatorSet};
 use crate::tx_pool::TxPool;
-use crate::types::{Address, Block, Transaction, U256};
-use jsonrpsee::core::{RpcResult, async_trait};
+use crate::types::{
+    Address, Block, BlockHeader, Bloom, CommitteeTransition, Log, Receipt, Transaction, U256, View,
+    bloom_contains,
+};
+use crate::vm::{ExecutionError, decode_revert_reason};
+use jsonrpsee::core::{RpcResult, SubscriptionResult, async_trait};
 use jsonrpsee::proc_macros::rpc;
-use serde::Deserialize;
+use jsonrpsee::server::middleware::http::HostFilterLayer;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::{MethodResponse, PendingSubscriptionSink, SubscriptionMessage};
+use jsonrpsee::types::Request;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
 use std::sync::Arc;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::Instrument;
+use tracing::instrument::Instrumented;
 
-#[derive(Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CallRequest {
     pub from: Option<Address>,
     pub to: Option<Address>,
@@ -15,15 +35,237 @@ pub struct CallRequest {
     pub gas_price: Option<U256>,
     pub value: Option<U256>,
     pub data: Option<crate::types::Bytes>,
+    /// Accounts and slots warmed up before the call (EIP-2930).
+    #[serde(default)]
+    pub access_list: Vec<crate::types::AccessListItem>,
 }
+/// Error data of a reverted `call` / `estimate_gas`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevertData {
+    /// Decoded `Error(string)` message (or `Panic(..)` code), if any.
+    pub reason: Option<String>,
+    /// Raw revert output.
+    pub output: crate::types::Bytes,
+    pub gas_used: u64,
+}
+
+/// Which validator set `get_validator_set` returns: `{"epoch": n}` or `{"view": n}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpochOrView {
+    Epoch(u64),
+    /// The epoch in effect at this view.
+    View(View),
+}
+
+/// Most blocks one `get_state_diff` call spans.
+pub const MAX_STATE_DIFF_BLOCKS: usize = 1024;
+
+/// Most blocks one `fee_history` call spans.
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Blocks searched back from the latest block by `get_transaction_receipt`.
+pub const RECEIPT_LOOKUP_DEPTH: usize = 1024;
+
+/// Largest request body the server accepts (a batch counts as one request).
+pub const MAX_RPC_REQUEST_SIZE: u32 = 2 * 1024 * 1024;
+/// Largest response body the server sends (`get_logs` and block queries are the big ones).
+pub const MAX_RPC_RESPONSE_SIZE: u32 = 10 * 1024 * 1024;
+/// Calls allowed in one batch request.
+pub const MAX_RPC_BATCH_SIZE: u32 = 100;
+/// Concurrent connections, unless `--rpc-max-connections` says otherwise.
+pub const DEFAULT_RPC_MAX_CONNECTIONS: u32 = 100;
+/// Distinct method names tracked by `RpcMetrics`; calls to others (clients can send any
+/// name) are counted under `OTHER_METHODS`.
+pub const MAX_TRACKED_METHODS: usize = 256;
+pub const OTHER_METHODS: &str = "<other>";
+
+/// Who can reach the RPC server: the interface it listens on, and the origins browsers may
+/// call it from and `Host` headers it answers (for dApps and reverse proxies).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcAccessConfig {
+    /// 127.0.0.1 keeps the server local; 0.0.0.0 listens on every interface.
+    pub listen_addr: IpAddr,
+    /// Origins allowed by CORS ("*" allows any). Empty sends no CORS headers, so browsers
+    /// on other origins are blocked.
+    pub cors_origins: Vec<String>,
+    /// Accepted `Host` headers ("host", "host:port" or "host:*"). Empty accepts any.
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for RpcAccessConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: IpAddr::V4(std::net::Ipv4Addr::LOCALHOST),
+            cors_origins: vec![],
+            allowed_hosts: vec![],
+        }
+    }
+}
+
+impl RpcAccessConfig {
+    /// HTTP middleware answering CORS preflights and tagging responses, if any origin is
+    /// allowed.
+    pub fn cors_layer(&self) -> Result<Option<CorsLayer>, String> {
+        if self.cors_origins.is_empty() {
+            return Ok(None);
+        }
+        let origins = if self.cors_origins.iter().any(|o| o == "*") {
+            AllowOrigin::any()
+        } else {
+            let list = self
+                .cors_origins
+                .iter()
+                .map(|o| o.parse().map_err(|_| format!("Invalid CORS origin: {}", o)))
+                .collect::<Result<Vec<_>, _>>()?;
+            AllowOrigin::list(list)
+        };
+        Ok(Some(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods(Any)
+                .allow_headers(Any),
+        ))
+    }
+
+    /// HTTP middleware rejecting requests for other hosts, if hosts are restricted.
+    pub fn host_filter(&self) -> Result<Option<HostFilterLayer>, String> {
+        if self.allowed_hosts.is_empty() {
+            return Ok(None);
+        }
+        HostFilterLayer::new(self.allowed_hosts.iter().map(String::as_str))
+            .map(Some)
+            .map_err(|e| format!("Invalid allowed host: {}", e))
+    }
+}
+
+/// Result of `get_transaction_receipt`: the receipt with where the transaction landed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionReceipt {
+    pub transaction_hash: Hash,
+    pub block_hash: Hash,
+    pub view: View,
+    /// Position of the transaction in the block.
+    pub index: usize,
+    /// Address of the contract created by a successful deployment.
+    pub contract_address: Option<Address>,
+    pub receipt: Receipt,
+}
+
+/// Filter of `get_logs`: logs emitted by `address` (any if unset) that carry every topic
+/// of `topics`, in blocks with a height within `from_height..=to_height`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LogFilter {
+    pub from_height: Option<u64>,
+    pub to_height: Option<u64>,
+    pub address: Option<Address>,
+    #[serde(default)]
+    pub topics: Vec<Hash>,
+}
+
+impl LogFilter {
+    /// Whether a block or receipt with `bloom` may hold matching logs.
+    pub fn matches_bloom(&self, bloom: &Bloom) -> bool {
+        self.address
+            .is_none_or(|address| bloom_contains(bloom, address.as_slice()))
+            && self
+                .topics
+                .iter()
+                .all(|topic| bloom_contains(bloom, &topic.0))
+    }
+
+    pub fn matches(&self, log: &Log) -> bool {
+        self.address.is_none_or(|address| log.address == address)
+            && self.topics.iter().all(|topic| log.topics.contains(topic))
+    }
+}
+
+/// A log returned by `get_logs`, with where it was emitted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchedLog {
+    pub block_hash: Hash,
+    pub height: u64,
+    pub transaction_index: usize,
+    pub log_index: usize,
+    pub log: Log,
+}
+
+/// Result of `fee_history`: one entry per finalized block, oldest first.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeHistory {
+    /// Height of the oldest block.
+    pub oldest_block: u64,
+    /// Base fee of each block, followed by the base fee of the block after the newest.
+    pub base_fee_per_gas: Vec<U256>,
+    /// Gas used by each block over its gas limit.
+    pub gas_used_ratio: Vec<f64>,
+    /// Priority fee per gas of each block at the requested percentiles of its gas used
+    /// (transactions by increasing tip, weighted by their gas); zero for empty blocks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reward: Vec<Vec<U256>>,
+}
+
+/// Result of `admin_nodeInfo`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeInfo {
+    pub peer_id: String,
+    pub listen_addrs: Vec<String>,
+    pub chain_id: u64,
+    pub version: String,
+}
+
+/// A response signed with the node's validator key, so that clients can detect responses
+/// altered by a proxy. The signature covers the method name and the canonical (JSON)
+/// encoding of the result.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedResponse<T> {
+    pub method: String,
+    pub result: T,
+    pub signer: PublicKey,
+    pub signature: Signature,
+}
+
+impl<T: Serialize> SignedResponse<T> {
+    pub fn new(method: &str, result: T, key: &PrivateKey) -> Self {
+        let signature = sign(key, &Self::commitment(method, &result).0);
+        Self {
+            method: method.to_string(),
+            result,
+            signer: key.public_key(),
+            signature,
+        }
+    }
+
+    fn commitment(method: &str, result: &T) -> Hash {
+        hash_data(&("ockham-rpc-response", method, result))
+    }
+
+    /// Check the signature (callers must also check that `signer` is a node they trust).
+    pub fn verify(&self) -> bool {
+        verify(
+            &self.signer,
+            &Self::commitment(&self.method, &self.result).0,
+            &self.signature,
+        )
+    }
+}
+
 #[rpc(server)]
 pub trait OckhamRpc {
     #[method(name = "get_block_by_hash")]
     fn get_block_by_hash(&self, hash: Hash) -> RpcResult<Option<Block>>;
 
+    /// The block header only (no transactions or evidence).
+    #[method(name = "get_header_by_hash")]
+    fn get_header_by_hash(&self, hash: Hash) -> RpcResult<Option<BlockHeader>>;
+
     #[method(name = "get_latest_block")]
     fn get_latest_block(&self) -> RpcResult<Option<Block>>;
 
+    /// `get_latest_block` signed by the node (requires a signing key to be configured).
+    #[method(name = "get_latest_block_signed")]
+    fn get_latest_block_signed(&self) -> RpcResult<SignedResponse<Option<Block>>>;
+
     #[method(name = "get_status")]
     fn get_status(&self) -> RpcResult<Option<ConsensusState>>;
 
@@ -33,8 +275,10 @@ pub trait OckhamRpc {
     #[method(name = "get_balance")]
     fn get_balance(&self, address: Address) -> RpcResult<U256>;
 
+    /// Nonce of `address` at `block` ("latest", the default, or "pending" to count the
+    /// sender's transactions waiting in the pool).
     #[method(name = "get_transaction_count")]
-    fn get_transaction_count(&self, address: Address) -> RpcResult<u64>;
+    fn get_transaction_count(&self, address: Address, block: Option<String>) -> RpcResult<u64>;
 
     #[method(name = "chain_id")]
     fn chain_id(&self) -> RpcResult<u64>;
@@ -42,6 +286,17 @@ pub trait OckhamRpc {
     #[method(name = "suggest_base_fee")]
     fn suggest_base_fee(&self) -> RpcResult<U256>;
 
+    /// Base fees, gas usage and priority fees at `percentiles` (ascending, within 0..=100)
+    /// of the last `block_count` finalized blocks up to `newest_block` ("latest", the
+    /// default, or a height), at most `MAX_FEE_HISTORY_BLOCKS`. For fee estimation.
+    #[method(name = "fee_history")]
+    fn fee_history(
+        &self,
+        block_count: u64,
+        newest_block: Option<String>,
+        percentiles: Vec<f64>,
+    ) -> RpcResult<FeeHistory>;
+
     #[method(name = "call")]
     fn call(&self, request: CallRequest, _block: Option<String>) -> RpcResult<crate::types::Bytes>;
 
@@ -53,6 +308,110 @@ pub trait OckhamRpc {
 
     #[method(name = "get_block_by_number")]
     fn get_block_by_number(&self, number: String) -> RpcResult<Option<Block>>;
+
+    #[method(name = "get_committee_transition")]
+    fn get_committee_transition(&self, epoch: u64) -> RpcResult<Option<CommitteeTransition>>;
+
+    /// Committee, pending and exiting validators with their stakes: as snapshotted at the
+    /// start of an epoch, or the live sets without `at`.
+    #[method(name = "get_validator_set")]
+    fn get_validator_set(&self, at: Option<EpochOrView>) -> RpcResult<Option<ValidatorSet>>;
+
+    /// Receipt of the transaction with `hash` (as returned by `send_transaction`), if it
+    /// was executed within the last `RECEIPT_LOOKUP_DEPTH` blocks.
+    #[method(name = "get_transaction_receipt")]
+    fn get_transaction_receipt(&self, hash: Hash) -> RpcResult<Option<TransactionReceipt>>;
+
+    /// Logs matching `filter` in the last `RECEIPT_LOOKUP_DEPTH` blocks, oldest first.
+    /// Blocks and receipts whose logs bloom rules the filter out are skipped.
+    #[method(name = "get_logs")]
+    fn get_logs(&self, filter: LogFilter) -> RpcResult<Vec<MatchedLog>>;
+
+    /// The block with its notarization and finalization certificates, if it is finalized.
+    #[method(name = "get_finality_proof")]
+    fn get_finality_proof(&self, block_hash: Hash) -> RpcResult<Option<FinalizedBlock>>;
+
+    /// Merkle branch of a transaction to the transactions root of its block, with the
+    /// block's finality proof. None until the block (within the last
+    /// `RECEIPT_LOOKUP_DEPTH` blocks) is finalized.
+    #[method(name = "get_transaction_proof")]
+    fn get_transaction_proof(&self, tx_hash: Hash) -> RpcResult<Option<TransactionProof>>;
+
+    /// Finalized blocks with a finalization certificate from `from_view` on, packaged for
+    /// bridge relayers (at most `limit`, capped at `MAX_BRIDGE_UPDATES`). Relayers poll it
+    /// with the view after the last update they relayed.
+    #[method(name = "bridge_getUpdates")]
+    fn bridge_get_updates(&self, from_view: u64, limit: u64) -> RpcResult<Vec<BridgeUpdate>>;
+
+    /// The current committee, encoded for the bridge contract.
+    #[method(name = "bridge_getCommittee")]
+    fn bridge_get_committee(&self) -> RpcResult<Option<BridgeCommittee>>;
+
+    /// Merkle proof of an account at the committed head. Light nodes fetch it from full
+    /// nodes and only return it once it verifies against a finalized header.
+    #[method(name = "get_account_proof")]
+    async fn get_account_proof(&self, address: Address) -> RpcResult<Option<AccountProof>>;
+
+    /// Account and storage slot proofs against the state root of `block` (default: the
+    /// committed head), checkable with `state::verify_proof`.
+    #[method(name = "get_proof")]
+    fn get_proof(
+        &self,
+        address: Address,
+        slots: Vec<U256>,
+        block: Option<String>,
+    ) -> RpcResult<Option<StateProof>>;
+
+    /// Accounts and storage slots changed by the committed blocks after `from_block` up to
+    /// and including `to_block` (a descendant at most `MAX_STATE_DIFF_BLOCKS` blocks later),
+    /// with their values before and after.
+    #[method(name = "get_state_diff")]
+    fn get_state_diff(&self, from_block: Hash, to_block: Hash) -> RpcResult<StateDiff>;
+
+    /// `get_finality_proof` signed by the node (requires a signing key to be configured).
+    #[method(name = "get_finality_proof_signed")]
+    fn get_finality_proof_signed(
+        &self,
+        block_hash: Hash,
+    ) -> RpcResult<SignedResponse<Option<FinalizedBlock>>>;
+
+    /// Sync status, peer count, last finalization and database status.
+    #[method(name = "node_health")]
+    fn node_health(&self) -> RpcResult<HealthReport>;
+
+    /// Connected peers with their addresses and scores.
+    #[method(name = "admin_peers")]
+    async fn admin_peers(&self) -> RpcResult<Vec<PeerInfo>>;
+
+    /// Dial `multiaddr` and redial it whenever the connection drops.
+    #[method(name = "admin_addPeer")]
+    async fn admin_add_peer(&self, multiaddr: String) -> RpcResult<bool>;
+
+    /// Disconnect a peer and forget its address. Returns false if it was not connected.
+    #[method(name = "admin_removePeer")]
+    async fn admin_remove_peer(&self, peer_id: String) -> RpcResult<bool>;
+
+    #[method(name = "admin_nodeInfo")]
+    async fn admin_node_info(&self) -> RpcResult<NodeInfo>;
+
+    /// Credit `amount` to `address` directly in the node's state (only on a chain whose
+    /// sole validator is this node). Returns the new balance.
+    #[method(name = "dev_fundAccount")]
+    fn dev_fund_account(&self, address: Address, amount: U256) -> RpcResult<U256>;
+
+    /// Credit the faucet's fixed drip to `address`, once per cooldown per address.
+    /// Returns the new balance.
+    #[method(name = "faucet_request")]
+    fn faucet_request(&self, address: Address) -> RpcResult<U256>;
+
+    /// Consensus events (views, proposals, QCs, finalizations, timeouts, evidence) as they
+    /// happen, over WebSocket. Subscribers that fall behind skip the oldest events.
+    #[subscription(
+        name = "subscribe_consensus_events" => "consensus_event",
+        unsubscribe = "unsubscribe_consensus_events",
+        item = EventRecord
+    )]
+    async fn subscribe_consensus_events(&self) -> SubscriptionResult;
 }
 
 pub struct OckhamRpcImpl {
@@ -61,6 +420,12 @@ pub struct OckhamRpcImpl {
     executor: crate::vm::Executor,
     block_gas_limit: u64,
     broadcast_sender: tokio::sync::mpsc::Sender<Transaction>,
+    signing_key: Option<PrivateKey>,
+    health: Option<HealthMonitor>,
+    network: Option<NetworkHandle>,
+    light: Option<tokio::sync::mpsc::Sender<ProofRequest>>,
+    faucet: Option<Faucet>,
+    events: Option<EventStream>,
 }
 
 impl OckhamRpcImpl {
@@ -77,8 +442,81 @@ impl OckhamRpcImpl {
             executor,
             block_gas_limit,
             broadcast_sender,
+            signing_key: None,
+            health: None,
+            network: None,
+            light: None,
+            faucet: None,
+            events: None,
         }
     }
+
+    /// Enable the `*_signed` variants of critical queries, signed with `key`.
+    pub fn with_signing_key(mut self, key: PrivateKey) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Enable `node_health`, reporting from `monitor`.
+    pub fn with_health(mut self, monitor: HealthMonitor) -> Self {
+        self.health = Some(monitor);
+        self
+    }
+
+    /// Enable the `admin_*` methods, managing peers through `network`.
+    pub fn with_admin(mut self, network: NetworkHandle) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Light mode: answer `get_account_proof` by asking full nodes through `requests`.
+    pub fn with_light_client(mut self, requests: tokio::sync::mpsc::Sender<ProofRequest>) -> Self {
+        self.light = Some(requests);
+        self
+    }
+
+    /// Dev mode: enable `dev_fundAccount` and `faucet_request`, crediting through `faucet`.
+    pub fn with_faucet(mut self, faucet: Faucet) -> Self {
+        self.faucet = Some(faucet);
+        self
+    }
+
+    /// Enable `subscribe_consensus_events`, serving the events of `events`.
+    pub fn with_events(mut self, events: EventStream) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn faucet(&self) -> RpcResult<&Faucet> {
+        self.faucet.as_ref().ok_or_else(|| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                "Dev RPC is not enabled on this node",
+                None::<()>,
+            )
+        })
+    }
+
+    fn admin(&self) -> RpcResult<&NetworkHandle> {
+        self.network.as_ref().ok_or_else(|| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                "Admin RPC is not enabled on this node",
+                None::<()>,
+            )
+        })
+    }
+
+    fn sign_response<T: Serialize>(&self, method: &str, result: T) -> RpcResult<SignedResponse<T>> {
+        let key = self.signing_key.as_ref().ok_or_else(|| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                "Response signing is not enabled on this node",
+                None::<()>,
+            )
+        })?;
+        Ok(SignedResponse::new(method, result, key))
+    }
 }
 
 #[async_trait]
@@ -94,6 +532,17 @@ impl OckhamRpcServer for OckhamRpcImpl {
         Ok(block)
     }
 
+    fn get_header_by_hash(&self, hash: Hash) -> RpcResult<Option<BlockHeader>> {
+        let header = self.storage.get_header(&hash).map_err(|e| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        })?;
+        Ok(header)
+    }
+
     fn get_latest_block(&self) -> RpcResult<Option<Block>> {
         let state = self.storage.get_consensus_state().map_err(|e| {
             jsonrpsee::types::ErrorObject::owned(
@@ -117,6 +566,11 @@ impl OckhamRpcServer for OckhamRpcImpl {
         }
     }
 
+    fn get_latest_block_signed(&self) -> RpcResult<SignedResponse<Option<Block>>> {
+        let block = self.get_latest_block()?;
+        self.sign_response("get_latest_block", block)
+    }
+
     fn get_status(&self) -> RpcResult<Option<ConsensusState>> {
         let state = self.storage.get_consensus_state().map_err(|e| {
             jsonrpsee::types::ErrorObject::owned(
@@ -131,18 +585,22 @@ impl OckhamRpcServer for OckhamRpcImpl {
     fn send_transaction(&self, tx: Transaction) -> RpcResult<Hash> {
         let hash = crate::crypto::hash_data(&tx);
         // Validate? (TxPool does some validation)
-        self.tx_pool.add_transaction(tx.clone()).map_err(|e| {
-            jsonrpsee::types::ErrorObject::owned(
-                -32000,
-                format!("TxPool error: {:?}", e),
-                None::<()>,
-            )
-        })?;
+        self.tx_pool
+            .add_local_transaction(tx.clone())
+            .map_err(|e| {
+                jsonrpsee::types::ErrorObject::owned(
+                    -32000,
+                    format!("TxPool error: {:?}", e),
+                    None::<()>,
+                )
+            })?;
 
         // Broadcast
         let sender = self.broadcast_sender.clone();
         tokio::spawn(async move {
-            let _ = sender.send(tx).await;
+            if sender.send(tx).await.is_err() {
+                tracing::warn!("Broadcast channel closed, transaction not gossiped");
+            }
         });
 
         Ok(hash)
@@ -160,7 +618,7 @@ impl OckhamRpcServer for OckhamRpcImpl {
         Ok(account.map(|a| a.balance).unwrap_or_default())
     }
 
-    fn get_transaction_count(&self, address: Address) -> RpcResult<u64> {
+    fn get_transaction_count(&self, address: Address, block: Option<String>) -> RpcResult<u64> {
         let account = self.storage.get_account(&address).map_err(|e| {
             jsonrpsee::types::ErrorObject::owned(
                 -32000,
@@ -168,12 +626,21 @@ impl OckhamRpcServer for OckhamRpcImpl {
                 None::<()>,
             )
         })?;
+        let nonce = account.map(|a| a.nonce).unwrap_or_default();
 
-        Ok(account.map(|a| a.nonce).unwrap_or_default())
+        match block.as_deref().unwrap_or("latest") {
+            "latest" => Ok(nonce),
+            "pending" => Ok(self.tx_pool.pending_nonce(address, nonce)),
+            other => Err(jsonrpsee::types::ErrorObject::owned(
+                -32602,
+                format!("Unsupported block tag: {}", other),
+                None::<()>,
+            )),
+        }
     }
 
     fn chain_id(&self) -> RpcResult<u64> {
-        Ok(1337) // TODO: Config
+        Ok(self.executor.chain_id)
     }
 
     fn suggest_base_fee(&self) -> RpcResult<U256> {
@@ -202,29 +669,97 @@ impl OckhamRpcServer for OckhamRpcImpl {
             }
         };
 
-        // Logic mirror from consensus.rs
-        let elasticity_multiplier = 2;
-        let base_fee_max_change_denominator = 8;
-        let target_gas = self.block_gas_limit / elasticity_multiplier;
-
-        let parent_gas_used = block.gas_used;
-        let parent_base_fee = block.base_fee_per_gas;
-
-        if parent_gas_used == target_gas {
-            Ok(parent_base_fee)
-        } else if parent_gas_used > target_gas {
-            let gas_used_delta = parent_gas_used - target_gas;
-            let base_fee_increase = parent_base_fee * U256::from(gas_used_delta)
-                / U256::from(target_gas)
-                / U256::from(base_fee_max_change_denominator);
-            Ok(parent_base_fee + base_fee_increase)
-        } else {
-            let gas_used_delta = target_gas - parent_gas_used;
-            let base_fee_decrease = parent_base_fee * U256::from(gas_used_delta)
-                / U256::from(target_gas)
-                / U256::from(base_fee_max_change_denominator);
-            Ok(parent_base_fee.saturating_sub(base_fee_decrease))
+        // Same rule as the proposer, with the live parameters
+        Ok(crate::fees::next_base_fee(&s.params, &block))
+    }
+
+    fn fee_history(
+        &self,
+        block_count: u64,
+        newest_block: Option<String>,
+        percentiles: Vec<f64>,
+    ) -> RpcResult<FeeHistory> {
+        let error =
+            |code, message: String| jsonrpsee::types::ErrorObject::owned(code, message, None::<()>);
+        let storage_error = |e: StorageError| error(-32000, format!("Storage error: {:?}", e));
+        if percentiles.iter().any(|p| !(0.0..=100.0).contains(p))
+            || percentiles.windows(2).any(|pair| pair[0] > pair[1])
+        {
+            return Err(error(
+                -32602,
+                "Percentiles must be ascending, within 0 to 100".into(),
+            ));
+        }
+
+        let Some(head) = self.storage.get_chain_head().map_err(storage_error)? else {
+            return Ok(FeeHistory::default());
+        };
+        let newest = match newest_block.as_deref().unwrap_or("latest") {
+            "latest" | "finalized" => head.block_hash,
+            number => {
+                let height = match number.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => number.parse::<u64>(),
+                }
+                .map_err(|_| error(-32602, format!("Invalid block: {}", number)))?;
+                self.storage
+                    .get_block_hash(height)
+                    .map_err(storage_error)?
+                    .ok_or_else(|| error(-32602, format!("Unknown block: {}", number)))?
+            }
+        };
+        let params = self
+            .storage
+            .get_consensus_state()
+            .map_err(storage_error)?
+            .map(|state| state.params)
+            .unwrap_or_default();
+
+        // Back from the newest block, skipping dummy blocks
+        let mut blocks = Vec::new();
+        let mut block_hash = newest;
+        while (blocks.len() as u64) < block_count.min(MAX_FEE_HISTORY_BLOCKS) {
+            let Some(block) = self.storage.get_block(&block_hash).map_err(storage_error)? else {
+                break;
+            };
+            let parent = block.parent_hash;
+            let genesis = block.height == 0;
+            if !block.is_dummy {
+                blocks.push((block_hash, block));
+            }
+            if genesis || parent == Hash::default() {
+                break;
+            }
+            block_hash = parent;
+        }
+        blocks.reverse();
+
+        let mut history = FeeHistory {
+            oldest_block: blocks.first().map_or(0, |(_, block)| block.height),
+            ..Default::default()
+        };
+        for (block_hash, block) in &blocks {
+            history.base_fee_per_gas.push(block.base_fee_per_gas);
+            history.gas_used_ratio.push(
+                block.gas_used as f64 / crate::fees::header_gas_limit(&params, block).max(1) as f64,
+            );
+            if !percentiles.is_empty() {
+                let receipts = self
+                    .storage
+                    .get_receipts(block_hash)
+                    .map_err(storage_error)?
+                    .unwrap_or_default();
+                history
+                    .reward
+                    .push(reward_percentiles(block, &receipts, &percentiles));
+            }
         }
+        if let Some((_, newest)) = blocks.last() {
+            history
+                .base_fee_per_gas
+                .push(crate::fees::next_base_fee(&params, newest));
+        }
+        Ok(history)
     }
 
     fn call(&self, request: CallRequest, _block: Option<String>) -> RpcResult<crate::types::Bytes> {
@@ -235,14 +770,8 @@ impl OckhamRpcServer for OckhamRpcImpl {
 
         let (_, output) = self
             .executor
-            .execute_ephemeral(caller, request.to, value, data, gas, vec![])
-            .map_err(|e| {
-                jsonrpsee::types::ErrorObject::owned(
-                    -32000,
-                    format!("Execution Error: {:?}", e),
-                    None::<()>,
-                )
-            })?;
+            .execute_ephemeral(caller, request.to, value, data, gas, request.access_list)
+            .map_err(execution_error)?;
 
         Ok(crate::types::Bytes::from(output))
     }
@@ -255,14 +784,8 @@ impl OckhamRpcServer for OckhamRpcImpl {
 
         let (gas_used, _) = self
             .executor
-            .execute_ephemeral(caller, request.to, value, data, gas, vec![])
-            .map_err(|e| {
-                jsonrpsee::types::ErrorObject::owned(
-                    -32000,
-                    format!("Execution Error: {:?}", e),
-                    None::<()>,
-                )
-            })?;
+            .execute_ephemeral(caller, request.to, value, data, gas, request.access_list)
+            .map_err(execution_error)?;
 
         Ok(gas_used)
     }
@@ -277,9 +800,7 @@ impl OckhamRpcServer for OckhamRpcImpl {
         })?;
 
         if let Some(info) = account {
-            if let Some(code) = info.code {
-                Ok(code)
-            } else if info.code_hash != Hash::default() {
+            if info.code_hash != Hash::default() {
                 let code = self
                     .storage
                     .get_code(&info.code_hash)
@@ -324,4 +845,671 @@ impl OckhamRpcServer for OckhamRpcImpl {
             Ok(None)
         }
     }
+
+    fn get_transaction_receipt(&self, hash: Hash) -> RpcResult<Option<TransactionReceipt>> {
+        let storage_error = |e: StorageError| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        };
+        let Some(state) = self.storage.get_consensus_state().map_err(storage_error)? else {
+            return Ok(None);
+        };
+
+        let mut block_hash = state.preferred_block;
+        for _ in 0..RECEIPT_LOOKUP_DEPTH {
+            let Some(block) = self.storage.get_block(&block_hash).map_err(storage_error)? else {
+                break;
+            };
+            if let Some(index) = block.payload.iter().position(|tx| hash_data(tx) == hash) {
+                let receipt = self
+                    .storage
+                    .get_receipts(&block_hash)
+                    .map_err(storage_error)?
+                    .and_then(|receipts| receipts.get(index).cloned());
+                return Ok(receipt.map(|receipt| TransactionReceipt {
+                    transaction_hash: hash,
+                    block_hash,
+                    view: block.view,
+                    index,
+                    contract_address: receipt.contract_address,
+                    receipt,
+                }));
+            }
+            if block.view == 0 {
+                break;
+            }
+            block_hash = block.parent_hash;
+        }
+        Ok(None)
+    }
+
+    fn get_logs(&self, filter: LogFilter) -> RpcResult<Vec<MatchedLog>> {
+        let storage_error = |e: StorageError| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        };
+        let Some(state) = self.storage.get_consensus_state().map_err(storage_error)? else {
+            return Ok(vec![]);
+        };
+
+        let mut matched = Vec::new();
+        let mut block_hash = state.preferred_block;
+        for _ in 0..RECEIPT_LOOKUP_DEPTH {
+            let Some(header) = self
+                .storage
+                .get_header(&block_hash)
+                .map_err(storage_error)?
+            else {
+                break;
+            };
+            if filter.from_height.is_some_and(|from| header.height < from) {
+                break;
+            }
+            // Only executed (committed) blocks have receipts
+            if filter.to_height.is_none_or(|to| header.height <= to)
+                && filter.matches_bloom(&header.logs_bloom)
+                && let Some(receipts) = self
+                    .storage
+                    .get_receipts(&block_hash)
+                    .map_err(storage_error)?
+            {
+                let block_logs = receipts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, receipt)| filter.matches_bloom(&receipt.logs_bloom))
+                    .flat_map(|(transaction_index, receipt)| {
+                        receipt
+                            .logs
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, log)| filter.matches(log))
+                            .map(move |(log_index, log)| MatchedLog {
+                                block_hash,
+                                height: header.height,
+                                transaction_index,
+                                log_index,
+                                log: log.clone(),
+                            })
+                    });
+                // Newest block first for now; reversed below
+                matched.extend(block_logs.rev());
+            }
+            if header.height == 0 {
+                break;
+            }
+            block_hash = header.parent_hash;
+        }
+        matched.reverse();
+        Ok(matched)
+    }
+
+    fn get_committee_transition(&self, epoch: u64) -> RpcResult<Option<CommitteeTransition>> {
+        let transition = self.storage.get_committee_transition(epoch).map_err(|e| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        })?;
+        Ok(transition)
+    }
+
+    fn get_validator_set(&self, at: Option<EpochOrView>) -> RpcResult<Option<ValidatorSet>> {
+        let storage_error = |e: StorageError| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        };
+        match at {
+            Some(EpochOrView::Epoch(epoch)) => {
+                self.storage.get_validator_set(epoch).map_err(storage_error)
+            }
+            Some(EpochOrView::View(view)) => self
+                .storage
+                .get_validator_set_at(view)
+                .map_err(storage_error),
+            None => {
+                let Some(state) = self.storage.get_consensus_state().map_err(storage_error)? else {
+                    return Ok(None);
+                };
+                let latest = self
+                    .storage
+                    .get_latest_validator_set()
+                    .map_err(storage_error)?;
+                let epoch = latest.map_or(0, |set| set.epoch);
+                ValidatorSet::from_state(epoch, state.view, &state, self.storage.as_ref())
+                    .map(Some)
+                    .map_err(storage_error)
+            }
+        }
+    }
+
+    fn get_finality_proof(&self, block_hash: Hash) -> RpcResult<Option<FinalizedBlock>> {
+        let storage_error = |e: StorageError| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        };
+        let Some(header) = self.storage.get_block(&block_hash).map_err(storage_error)? else {
+            return Ok(None);
+        };
+        let notarization = self.storage.get_qc(header.view).map_err(storage_error)?;
+        let finalization = self
+            .storage
+            .get_finality_qc(header.view)
+            .map_err(storage_error)?;
+        match (notarization, finalization) {
+            (Some(notarization), Some(finalization))
+                if notarization.block_hash == block_hash
+                    && finalization.block_hash == block_hash =>
+            {
+                Ok(Some(FinalizedBlock {
+                    header,
+                    proof: FinalityProof {
+                        notarization,
+                        finalization,
+                    },
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn get_transaction_proof(&self, tx_hash: Hash) -> RpcResult<Option<TransactionProof>> {
+        let storage_error = |e: StorageError| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        };
+        let Some(state) = self.storage.get_consensus_state().map_err(storage_error)? else {
+            return Ok(None);
+        };
+
+        let mut block_hash = state.preferred_block;
+        for _ in 0..RECEIPT_LOOKUP_DEPTH {
+            let Some(block) = self.storage.get_block(&block_hash).map_err(storage_error)? else {
+                break;
+            };
+            if let Some(index) = block.payload.iter().position(|tx| hash_data(tx) == tx_hash) {
+                let Some(finalized) = self.get_finality_proof(block_hash)? else {
+                    return Ok(None);
+                };
+                return Ok(block
+                    .transaction_proof(index)
+                    .map(|branch| TransactionProof {
+                        transaction: block.payload[index].clone(),
+                        branch,
+                        block: finalized,
+                    }));
+            }
+            if block.view == 0 {
+                break;
+            }
+            block_hash = block.parent_hash;
+        }
+        Ok(None)
+    }
+
+    fn bridge_get_updates(&self, from_view: u64, limit: u64) -> RpcResult<Vec<BridgeUpdate>> {
+        let storage_error = |e: StorageError| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        };
+        let Some(state) = self.storage.get_consensus_state().map_err(storage_error)? else {
+            return Ok(vec![]);
+        };
+        // Committees that may have produced the blocks: the current one and those
+        // installed by hand-overs
+        let mut committees = vec![state.committee];
+        for epoch in 1.. {
+            let Some(transition) = self
+                .storage
+                .get_committee_transition(epoch)
+                .map_err(storage_error)?
+            else {
+                break;
+            };
+            committees.push(transition.new_committee);
+        }
+
+        let limit = limit.min(MAX_BRIDGE_UPDATES) as usize;
+        let mut updates = Vec::new();
+        for view in from_view..=state.finalized_height {
+            if updates.len() >= limit {
+                break;
+            }
+            let Some(qc) = self.storage.get_finality_qc(view).map_err(storage_error)? else {
+                continue;
+            };
+            let Some(finalized) = self.get_finality_proof(qc.block_hash)? else {
+                continue;
+            };
+            let Some(committee) = committees
+                .iter()
+                .find(|c| hash_data(c) == finalized.header.committee_hash)
+            else {
+                continue;
+            };
+            if let Ok(update) = BridgeUpdate::new(&finalized, committee, self.executor.chain_id) {
+                updates.push(update);
+            }
+        }
+        Ok(updates)
+    }
+
+    fn bridge_get_committee(&self) -> RpcResult<Option<BridgeCommittee>> {
+        let state = self.storage.get_consensus_state().map_err(|e| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        })?;
+        Ok(state.map(|state| BridgeCommittee::new(&state.committee, self.executor.chain_id)))
+    }
+
+    fn get_proof(
+        &self,
+        address: Address,
+        slots: Vec<U256>,
+        block: Option<String>,
+    ) -> RpcResult<Option<StateProof>> {
+        let head = self.storage.get_chain_head().map_err(|e| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        })?;
+        let Some(head) = head else {
+            return Ok(None);
+        };
+        let state_root = match block.as_deref() {
+            None | Some("latest") => head.state_root,
+            Some(number) => match self.get_block_by_number(number.to_string())? {
+                Some(block) if block.view <= head.view => block.state_root,
+                Some(block) => {
+                    return Err(jsonrpsee::types::ErrorObject::owned(
+                        -32000,
+                        format!("State of view {} is not committed yet", block.view),
+                        None::<()>,
+                    ));
+                }
+                None => return Ok(None),
+            },
+        };
+
+        let proof = crate::state::StateManager::new(self.storage.clone(), Some(state_root))
+            .get_proof(address, &slots)
+            .map_err(|e| jsonrpsee::types::ErrorObject::owned(-32000, e.to_string(), None::<()>))?;
+        Ok(Some(proof))
+    }
+
+    fn get_state_diff(&self, from_block: Hash, to_block: Hash) -> RpcResult<StateDiff> {
+        let error =
+            |code, message: String| jsonrpsee::types::ErrorObject::owned(code, message, None::<()>);
+        let storage_error = |e: StorageError| error(-32000, format!("Storage error: {:?}", e));
+        let from = self
+            .storage
+            .get_header(&from_block)
+            .map_err(storage_error)?
+            .ok_or_else(|| error(-32602, format!("Unknown block {:?}", from_block)))?;
+
+        // Walk back from `to_block`, then apply the diffs oldest first
+        let mut diffs = vec![];
+        let mut block_hash = to_block;
+        while block_hash != from_block {
+            let header = self
+                .storage
+                .get_header(&block_hash)
+                .map_err(storage_error)?
+                .ok_or_else(|| error(-32602, format!("Unknown block {:?}", block_hash)))?;
+            if header.height <= from.height || diffs.len() >= MAX_STATE_DIFF_BLOCKS {
+                return Err(error(
+                    -32602,
+                    format!(
+                        "Block {:?} is not a descendant of {:?} within {} blocks",
+                        to_block, from_block, MAX_STATE_DIFF_BLOCKS
+                    ),
+                ));
+            }
+            let diff = self
+                .storage
+                .get_state_diff(&block_hash)
+                .map_err(storage_error)?
+                .ok_or_else(|| {
+                    error(
+                        -32000,
+                        format!(
+                            "No state diff stored for block {:?} (not committed)",
+                            block_hash
+                        ),
+                    )
+                })?;
+            diffs.push(diff);
+            block_hash = header.parent_hash;
+        }
+
+        let mut total = StateDiff::default();
+        for diff in diffs.into_iter().rev() {
+            total.merge(diff);
+        }
+        Ok(total.without_noops())
+    }
+
+    fn get_finality_proof_signed(
+        &self,
+        block_hash: Hash,
+    ) -> RpcResult<SignedResponse<Option<FinalizedBlock>>> {
+        let proof = self.get_finality_proof(block_hash)?;
+        self.sign_response("get_finality_proof", proof)
+    }
+
+    async fn get_account_proof(&self, address: Address) -> RpcResult<Option<AccountProof>> {
+        if let Some(light) = &self.light {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            light
+                .send((address, tx))
+                .await
+                .map_err(|_| network_stopped())?;
+            // No full node answered in time
+            return Ok(tokio::time::timeout(PROOF_TIMEOUT, rx)
+                .await
+                .ok()
+                .and_then(Result::ok));
+        }
+
+        let head = self.storage.get_chain_head().map_err(|e| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                format!("Storage error: {:?}", e),
+                None::<()>,
+            )
+        })?;
+        let Some(head) = head else {
+            return Ok(None);
+        };
+        let state = self.executor.state.lock().unwrap();
+        let proof = AccountProof::from_state(&head, &state, address)
+            .map_err(|e| jsonrpsee::types::ErrorObject::owned(-32000, e.to_string(), None::<()>))?;
+        Ok(Some(proof))
+    }
+
+    fn node_health(&self) -> RpcResult<HealthReport> {
+        let health = self.health.as_ref().ok_or_else(|| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32000,
+                "Health monitoring is not enabled on this node",
+                None::<()>,
+            )
+        })?;
+        Ok(health.report(self.storage.as_ref(), unix_now()))
+    }
+
+    async fn admin_peers(&self) -> RpcResult<Vec<PeerInfo>> {
+        self.admin()?.peers().await.ok_or_else(network_stopped)
+    }
+
+    async fn admin_add_peer(&self, multiaddr: String) -> RpcResult<bool> {
+        let addr = multiaddr.parse().map_err(|e| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32602,
+                format!("Invalid multiaddr: {}", e),
+                None::<()>,
+            )
+        })?;
+        Ok(self.admin()?.add_peer(addr).await)
+    }
+
+    async fn admin_remove_peer(&self, peer_id: String) -> RpcResult<bool> {
+        let peer_id = peer_id.parse().map_err(|e| {
+            jsonrpsee::types::ErrorObject::owned(
+                -32602,
+                format!("Invalid peer id: {}", e),
+                None::<()>,
+            )
+        })?;
+        Ok(self.admin()?.remove_peer(peer_id).await)
+    }
+
+    async fn admin_node_info(&self) -> RpcResult<NodeInfo> {
+        let local = self
+            .admin()?
+            .local_info()
+            .await
+            .ok_or_else(network_stopped)?;
+        Ok(NodeInfo {
+            peer_id: local.peer_id,
+            listen_addrs: local.listen_addrs,
+            chain_id: self.chain_id()?,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+    }
+
+    fn dev_fund_account(&self, address: Address, amount: U256) -> RpcResult<U256> {
+        self.faucet()?.fund(address, amount).map_err(faucet_error)
+    }
+
+    fn faucet_request(&self, address: Address) -> RpcResult<U256> {
+        self.faucet()?
+            .drip(address, unix_now())
+            .map_err(faucet_error)
+    }
+
+    async fn subscribe_consensus_events(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        let Some(events) = &self.events else {
+            pending
+                .reject(jsonrpsee::types::ErrorObject::owned(
+                    -32000,
+                    "Consensus events are not enabled on this node",
+                    None::<()>,
+                ))
+                .await;
+            return Ok(());
+        };
+        let mut receiver = events.subscribe();
+        let sink = pending.accept().await?;
+        loop {
+            tokio::select! {
+                _ = sink.closed() => return Ok(()),
+                received = receiver.recv() => match received {
+                    Ok(record) => {
+                        let message = SubscriptionMessage::from_json(&record)?;
+                        if sink.send(message).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("Event subscriber fell behind: skipped {} events", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+            }
+        }
+    }
+}
+
+/// Priority fee per gas paid in `block` at each of `percentiles` of its gas used: its
+/// transactions sorted by tip, each weighing the gas it used.
+fn reward_percentiles(block: &Block, receipts: &[Receipt], percentiles: &[f64]) -> Vec<U256> {
+    let mut tips: Vec<(U256, u64)> = block
+        .payload
+        .iter()
+        .zip(receipts)
+        .map(|(tx, receipt)| (tx.effective_tip(block.base_fee_per_gas), receipt.gas_used))
+        .collect();
+    if tips.is_empty() {
+        return vec![U256::ZERO; percentiles.len()];
+    }
+    tips.sort_by_key(|(tip, _)| *tip);
+
+    let total_gas: u64 = tips.iter().map(|(_, gas)| gas).sum();
+    let (mut index, mut cumulative_gas) = (0, tips[0].1);
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = (total_gas as f64 * percentile / 100.0) as u64;
+            while cumulative_gas < threshold && index + 1 < tips.len() {
+                index += 1;
+                cumulative_gas += tips[index].1;
+            }
+            tips[index].0
+        })
+        .collect()
+}
+
+/// Reverts map to error code 3 ("execution reverted", as in Ethereum JSON-RPC) with
+/// the decoded reason in the error data.
+fn execution_error(e: ExecutionError) -> jsonrpsee::types::ErrorObjectOwned {
+    match e {
+        ExecutionError::Revert { gas_used, output } => {
+            let reason = decode_revert_reason(&output);
+            let message = match &reason {
+                Some(reason) => format!("execution reverted: {}", reason),
+                None => "execution reverted".to_string(),
+            };
+            let data = RevertData {
+                reason,
+                output: output.into(),
+                gas_used,
+            };
+            jsonrpsee::types::ErrorObject::owned(3, message, Some(data))
+        }
+        e => jsonrpsee::types::ErrorObject::owned(
+            -32000,
+            format!("Execution Error: {:?}", e),
+            None::<()>,
+        ),
+    }
+}
+
+/// How long a light node waits for full nodes to answer an account proof request.
+const PROOF_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+fn faucet_error(e: FaucetError) -> jsonrpsee::types::ErrorObjectOwned {
+    let code = match e {
+        FaucetError::RateLimited(_) => -32005,
+        FaucetError::NotSoleValidator | FaucetError::State(_) => -32000,
+    };
+    jsonrpsee::types::ErrorObject::owned(code, e.to_string(), None::<()>)
+}
+
+fn network_stopped() -> jsonrpsee::types::ErrorObjectOwned {
+    jsonrpsee::types::ErrorObject::owned(-32000, "Network task has stopped", None::<()>)
+}
+
+/// Calls, errors and total latency of one RPC method.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodMetrics {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_micros: u64,
+}
+
+/// Per-method call counters, shared by the `RpcCallMetrics` middleware and whoever
+/// reports them.
+#[derive(Clone, Default)]
+pub struct RpcMetrics(Arc<std::sync::Mutex<HashMap<String, MethodMetrics>>>);
+
+impl RpcMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, method: &str, elapsed: std::time::Duration, error: bool) {
+        let mut methods = self.0.lock().unwrap();
+        let key = if methods.contains_key(method) || methods.len() < MAX_TRACKED_METHODS {
+            method
+        } else {
+            OTHER_METHODS
+        };
+        let entry = methods.entry(key.to_string()).or_default();
+        entry.calls += 1;
+        entry.errors += error as u64;
+        entry.total_micros = entry
+            .total_micros
+            .saturating_add(elapsed.as_micros() as u64);
+    }
+
+    /// Counters by method name.
+    pub fn snapshot(&self) -> BTreeMap<String, MethodMetrics> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(method, metrics)| (method.clone(), metrics.clone()))
+            .collect()
+    }
+}
+
+/// RPC middleware counting the calls, errors and latency of each method (every call of
+/// a batch counts).
+///
+/// Install with `RpcServiceBuilder::new().layer_fn(move |s| RpcCallMetrics::new(s, metrics.clone()))`.
+#[derive(Clone)]
+pub struct RpcCallMetrics<S> {
+    inner: S,
+    metrics: RpcMetrics,
+}
+
+impl<S> RpcCallMetrics<S> {
+    pub fn new(inner: S, metrics: RpcMetrics) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcCallMetrics<S>
+where
+    S: RpcServiceT<'a>,
+    S::Future: Send + 'a,
+{
+    type Future = futures::future::BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let method = request.method_name().to_string();
+        let metrics = self.metrics.clone();
+        let started = std::time::Instant::now();
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await;
+            metrics.record(&method, started.elapsed(), response.is_error());
+            response
+        })
+    }
+}
+
+/// RPC middleware that runs every request inside a `rpc` tracing span (method and id).
+///
+/// Install with `RpcServiceBuilder::new().layer_fn(RpcTracing)`.
+#[derive(Clone)]
+pub struct RpcTracing<S>(pub S);
+
+impl<'a, S> RpcServiceT<'a> for RpcTracing<S>
+where
+    S: RpcServiceT<'a>,
+{
+    type Future = Instrumented<S::Future>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let span = tracing::info_span!("rpc", method = %request.method_name(), id = ?request.id());
+        self.0.call(request).instrument(span)
+    }
 }
@@ -0,0 +1,75 @@
+use crate::crypto::Hash;
+use std::collections::{HashMap, VecDeque};
+
+/// Bounded LRU set of recently seen message hashes.
+///
+/// Gossip redelivers messages and our own broadcasts loop back, so consensus checks
+/// this cache first and processes each message once. When full, the least recently
+/// seen hash is evicted.
+pub struct SeenCache {
+    capacity: usize,
+    next_seq: u64,
+    // Hash -> sequence number of its latest sighting
+    entries: HashMap<Hash, u64>,
+    // Sightings, oldest first (entries superseded by a later sighting are skipped)
+    order: VecDeque<(Hash, u64)>,
+}
+
+impl SeenCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_seq: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Mark `hash` as seen. Returns false if it already was (a duplicate).
+    pub fn insert(&mut self, hash: Hash) -> bool {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let is_new = self.entries.insert(hash, seq).is_none();
+        self.order.push_back((hash, seq));
+
+        if self.entries.len() > self.capacity {
+            self.evict_oldest(self.entries.len() - self.capacity);
+        }
+        // Keep stale sightings from piling up when the same hashes repeat
+        if self.order.len() > 2 * self.capacity.max(1) {
+            let entries = &self.entries;
+            self.order.retain(|(h, s)| entries.get(h) == Some(s));
+        }
+        is_new
+    }
+
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.entries.contains_key(hash)
+    }
+
+    /// Forget `hash`, so that a redelivery is processed again.
+    pub fn remove(&mut self, hash: &Hash) {
+        self.entries.remove(hash);
+    }
+
+    /// Drop the `n` least recently seen hashes.
+    pub fn evict_oldest(&mut self, n: usize) {
+        let mut evicted = 0;
+        while evicted < n
+            && let Some((hash, seq)) = self.order.pop_front()
+        {
+            if self.entries.get(&hash) == Some(&seq) {
+                self.entries.remove(&hash);
+                evicted += 1;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,54 @@
+use crate::types::View;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long a shutting-down node waits for in-flight proposal and block executions.
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a graceful shutdown did, logged as the node exits.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShutdownSummary {
+    pub view: View,
+    pub finalized_height: View,
+    /// Our proposals whose execution finished during the drain (stored, not broadcast).
+    pub drained_proposals: usize,
+    /// Incoming blocks whose validation finished during the drain (stored).
+    pub drained_blocks: usize,
+    /// Executions still running when the drain timed out (their results are lost).
+    pub abandoned: usize,
+    /// Local transactions written to the pool journal.
+    pub journaled_txs: usize,
+    /// Peers disconnected when the network closed (None if the network task had stopped).
+    pub disconnected_peers: Option<usize>,
+    pub errors: Vec<String>,
+}
+
+impl ShutdownSummary {
+    /// Everything in flight was persisted and nothing failed.
+    pub fn is_clean(&self) -> bool {
+        self.abandoned == 0 && self.errors.is_empty() && self.disconnected_peers.is_some()
+    }
+}
+
+impl std::fmt::Display for ShutdownSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "view {}, finalized {}, drained {} proposals and {} blocks, {} abandoned, {} txs journaled, ",
+            self.view,
+            self.finalized_height,
+            self.drained_proposals,
+            self.drained_blocks,
+            self.abandoned,
+            self.journaled_txs
+        )?;
+        match self.disconnected_peers {
+            Some(peers) => write!(f, "{} peers disconnected", peers)?,
+            None => write!(f, "network already stopped")?,
+        }
+        for error in &self.errors {
+            write!(f, "; error: {}", error)?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
 use crate::crypto::{Hash, hash_data};
 use alloy_primitives::{Address, keccak256};
 
-use crate::storage::Storage;
+use crate::lru::Lru;
+use crate::memory::{MemoryBudget, MemoryHandle};
+use crate::storage::{AccountInfo, BLOCK_HASH_HISTORY, CachedStorage, Storage};
 use revm::Database;
 use revm::primitives::{AccountInfo as RevmAccountInfo, B256, Bytecode, U256};
 use sparse_merkle_tree::{H256, SparseMerkleTree};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
@@ -97,11 +100,38 @@ impl From<SerdeBranchNode> for BranchNode {
 #[derive(Clone)]
 pub struct OckhamSmtStore {
     storage: Arc<dyn Storage>,
+    /// Account whose storage trie this store holds (`None` for the account trie).
+    namespace: Option<Address>,
 }
 
 impl OckhamSmtStore {
     pub fn new(storage: Arc<dyn Storage>) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            namespace: None,
+        }
+    }
+
+    /// Store for the storage trie of `address`. Its nodes share the SMT tables with the
+    /// account trie, under keys namespaced by the address.
+    pub fn for_account(storage: Arc<dyn Storage>, address: Address) -> Self {
+        Self {
+            storage,
+            namespace: Some(address),
+        }
+    }
+
+    fn key(&self, key: H256) -> Hash {
+        let key: [u8; 32] = key.into();
+        match &self.namespace {
+            Some(address) => {
+                let mut preimage = Vec::with_capacity(52);
+                preimage.extend_from_slice(address.as_slice());
+                preimage.extend_from_slice(&key);
+                Hash(keccak256(preimage).0)
+            }
+            None => Hash(key),
+        }
     }
 }
 
@@ -110,7 +140,7 @@ impl StoreReadOps<H256> for OckhamSmtStore {
         &self,
         branch_key: &BranchKey,
     ) -> Result<Option<BranchNode>, sparse_merkle_tree::error::Error> {
-        let node_hash = Hash(branch_key.node_key.into());
+        let node_hash = self.key(branch_key.node_key);
         match self.storage.get_smt_branch(branch_key.height, &node_hash) {
             Ok(Some(bytes)) => {
                 let serde_node: SerdeBranchNode = bincode::deserialize(&bytes)
@@ -123,7 +153,7 @@ impl StoreReadOps<H256> for OckhamSmtStore {
     }
 
     fn get_leaf(&self, leaf_key: &H256) -> Result<Option<H256>, sparse_merkle_tree::error::Error> {
-        let hash = Hash((*leaf_key).into());
+        let hash = self.key(*leaf_key);
         match self.storage.get_smt_leaf(&hash) {
             Ok(Some(bytes)) => {
                 let val: [u8; 32] = bincode::deserialize(&bytes)
@@ -146,7 +176,7 @@ impl StoreWriteOps<H256> for OckhamSmtStore {
         let bytes = bincode::serialize(&serde_node)
             .map_err(|e| sparse_merkle_tree::error::Error::Store(e.to_string()))?;
 
-        let hash = Hash(node_key.node_key.into());
+        let hash = self.key(node_key.node_key);
         self.storage
             .save_smt_branch(node_key.height, &hash, &bytes)
             .map_err(|e| sparse_merkle_tree::error::Error::Store(e.to_string()))
@@ -161,7 +191,7 @@ impl StoreWriteOps<H256> for OckhamSmtStore {
         let bytes = bincode::serialize(&leaf_bytes)
             .map_err(|e| sparse_merkle_tree::error::Error::Store(e.to_string()))?;
 
-        let hash = Hash(leaf_key.into());
+        let hash = self.key(leaf_key);
         self.storage
             .save_smt_leaf(&hash, &bytes)
             .map_err(|e| sparse_merkle_tree::error::Error::Store(e.to_string()))
@@ -182,9 +212,188 @@ impl StoreWriteOps<H256> for OckhamSmtStore {
 pub type SmtStore = OckhamSmtStore;
 pub type StateTree = SparseMerkleTree<sparse_merkle_tree::blake2b::Blake2bHasher, H256, SmtStore>;
 
+// --- Hot State Cache ---
+
+/// Entries kept by the state cache when no size is configured.
+pub const DEFAULT_STATE_CACHE_ENTRIES: usize = 100_000;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) enum CacheKey {
+    Account(Address),
+    Slot(Address, U256),
+    Code(Hash),
+}
+
+#[derive(Clone)]
+pub(crate) enum CacheValue {
+    Account(Option<AccountInfo>),
+    Slot(U256),
+    Code(alloy_primitives::Bytes),
+}
+
+impl CacheValue {
+    fn size(&self) -> usize {
+        let heap = match self {
+            CacheValue::Code(code) => code.len(),
+            _ => 0,
+        };
+        // Entry in the map plus its place in the eviction queue
+        2 * std::mem::size_of::<CacheKey>() + std::mem::size_of::<CacheValue>() + heap
+    }
+}
+
+/// State cache size and hit counts, per kind of lookup.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateCacheMetrics {
+    pub entries: usize,
+    pub bytes: usize,
+    pub account_hits: u64,
+    pub account_misses: u64,
+    pub storage_hits: u64,
+    pub storage_misses: u64,
+    pub code_hits: u64,
+    pub code_misses: u64,
+    pub evicted: u64,
+}
+
+impl StateCacheMetrics {
+    /// Share of lookups served from the cache (0 before any lookup).
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.account_hits + self.storage_hits + self.code_hits;
+        let lookups = hits + self.account_misses + self.storage_misses + self.code_misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        hits as f64 / lookups as f64
+    }
+}
+
+/// LRU cache of committed accounts, storage slots and code, in front of the database.
+///
+/// Read through `CachedStorage` (see `StateManager::with_cache`): a miss reads the database
+/// and fills the entry, a write replaces it. Bounded by an entry count and, when
+/// registered, by its share of the node `MemoryBudget`.
+pub struct StateCache {
+    inner: Mutex<CacheInner>,
+    memory: Option<MemoryHandle>,
+}
+
+struct CacheInner {
+    capacity: usize,
+    // Bumped by every write, so that a read racing with a write does not fill a stale value
+    writes: u64,
+    entries: Lru<CacheKey, CacheValue>,
+    metrics: StateCacheMetrics,
+}
+
+impl StateCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                capacity,
+                writes: 0,
+                entries: Lru::new(),
+                metrics: StateCacheMetrics::default(),
+            }),
+            memory: None,
+        }
+    }
+
+    /// Account the cache against `budget`; when over its share, the least recently used
+    /// entries are evicted.
+    pub fn with_memory_budget(mut self, budget: &MemoryBudget) -> Self {
+        self.memory = Some(budget.register("state_cache"));
+        self
+    }
+
+    pub fn metrics(&self) -> StateCacheMetrics {
+        self.inner.lock().unwrap().metrics.clone()
+    }
+
+    /// The cached value of `key`, or the write counter to pass to `fill` after a miss.
+    pub(crate) fn get(&self, key: &CacheKey) -> Result<CacheValue, u64> {
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        let found = inner.entries.get(key).cloned();
+        let metrics = &mut inner.metrics;
+        let (hits, misses) = match key {
+            CacheKey::Account(_) => (&mut metrics.account_hits, &mut metrics.account_misses),
+            CacheKey::Slot(..) => (&mut metrics.storage_hits, &mut metrics.storage_misses),
+            CacheKey::Code(_) => (&mut metrics.code_hits, &mut metrics.code_misses),
+        };
+        match found {
+            Some(value) => {
+                *hits += 1;
+                Ok(value)
+            }
+            None => {
+                *misses += 1;
+                Err(inner.writes)
+            }
+        }
+    }
+
+    /// Cache a value read from the database, unless a write happened since the miss.
+    pub(crate) fn fill(&self, key: CacheKey, value: CacheValue, writes: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.writes == writes {
+            self.insert(&mut inner, key, value);
+        }
+    }
+
+    /// Replace the entry of a key written to the database.
+    pub(crate) fn write(&self, key: CacheKey, value: CacheValue) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.writes += 1;
+        self.insert(&mut inner, key, value);
+    }
+
+    fn insert(&self, inner: &mut CacheInner, key: CacheKey, value: CacheValue) {
+        let size = value.size();
+        if let Some(old) = inner.entries.insert(key, value) {
+            inner.metrics.bytes -= old.size();
+        } else {
+            inner.metrics.entries += 1;
+        }
+        inner.metrics.bytes += size;
+
+        let count = inner.entries.len().saturating_sub(inner.capacity);
+        let bytes = self.memory.as_ref().map_or(0, |memory| {
+            memory.set_usage(inner.metrics.bytes);
+            memory.excess()
+        });
+        inner.evict_oldest(count, bytes);
+        if let Some(memory) = &self.memory {
+            memory.set_usage(inner.metrics.bytes);
+        }
+    }
+}
+
+impl CacheInner {
+    /// Drop least recently used entries until at least `count` entries and `bytes` bytes
+    /// are freed.
+    fn evict_oldest(&mut self, count: usize, bytes: usize) {
+        let (mut evicted, mut freed) = (0, 0);
+        while (evicted < count || freed < bytes)
+            && let Some((_, value)) = self.entries.pop_oldest()
+        {
+            freed += value.size();
+            self.metrics.bytes -= value.size();
+            self.metrics.entries -= 1;
+            self.metrics.evicted += 1;
+            evicted += 1;
+        }
+    }
+}
+
 pub struct StateManager {
     tree: Arc<Mutex<StateTree>>,
     storage: Arc<dyn Storage>,
+    cache: Option<Arc<StateCache>>,
+    // Height and parent of the block being executed (what `BLOCKHASH` resolves against)
+    block_context: Option<(u64, Hash)>,
+    // Changes committed since `record_diff` (per-block state diffs)
+    diff: Mutex<Option<StateDiff>>,
 }
 
 impl StateManager {
@@ -198,6 +407,9 @@ impl StateManager {
         Self {
             tree: Arc::new(Mutex::new(tree)),
             storage,
+            cache: None,
+            block_context: None,
+            diff: Mutex::new(None),
         }
     }
 
@@ -205,9 +417,21 @@ impl StateManager {
         Self {
             tree: Arc::new(Mutex::new(tree)),
             storage,
+            cache: None,
+            block_context: None,
+            diff: Mutex::new(None),
         }
     }
 
+    /// Read accounts, storage slots and code through `cache`. Overlays built on
+    /// `backing_storage` (forks for proposals and validation) read through it too; writes
+    /// must also go through `backing_storage` to keep it current.
+    pub fn with_cache(mut self, cache: Arc<StateCache>) -> Self {
+        self.storage = Arc::new(CachedStorage::new(self.storage, cache.clone()));
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn fork(&self, new_root: Hash, storage: Arc<dyn Storage>) -> Self {
         // Create a new SmtStore backed by the provided storage (e.g. Overlay)
         let store = SmtStore::new(storage.clone());
@@ -215,9 +439,55 @@ impl StateManager {
         Self {
             tree: Arc::new(Mutex::new(new_tree)),
             storage,
+            cache: self.cache.clone(),
+            block_context: None,
+            diff: Mutex::new(None),
         }
     }
 
+    /// An owned view of this state at its current root, reading the same storage and cache
+    /// (for runtimes that keep their state reader, like the WASM host).
+    pub fn view(&self) -> Self {
+        let mut view = self.fork(self.root(), self.storage.clone());
+        view.block_context = self.block_context;
+        view
+    }
+
+    /// Move this state to `root`, keeping its storage and cache (crash recovery).
+    pub fn reset(&mut self, root: Hash) {
+        let store = SmtStore::new(self.storage.clone());
+        *self.tree.lock().unwrap() = SparseMerkleTree::new(H256::from(root.0), store);
+    }
+
+    /// Execute the next transactions as part of the block at `height` on top of `parent_hash`.
+    pub fn set_block_context(&mut self, height: u64, parent_hash: Hash) {
+        self.block_context = Some((height, parent_hash));
+    }
+
+    /// Record the changes of the following commits, until `take_diff`.
+    pub fn record_diff(&self) {
+        *self.diff.lock().unwrap() = Some(StateDiff::default());
+    }
+
+    /// Stop recording and return the changes committed since `record_diff`.
+    pub fn take_diff(&self) -> Option<StateDiff> {
+        self.diff
+            .lock()
+            .unwrap()
+            .take()
+            .map(StateDiff::without_noops)
+    }
+
+    /// Backing storage of this state view (persistent DB or an overlay).
+    pub fn backing_storage(&self) -> Arc<dyn Storage> {
+        self.storage.clone()
+    }
+
+    /// Hit rates of the state cache, if one is configured.
+    pub fn cache_metrics(&self) -> Option<StateCacheMetrics> {
+        self.cache.as_ref().map(|cache| cache.metrics())
+    }
+
     pub fn snapshot(&self) -> StateTree {
         let tree = self.tree.lock().unwrap();
         let root = *tree.root();
@@ -226,8 +496,7 @@ impl StateManager {
     }
 
     pub fn update_account(&self, address: Address, account_hash: Hash) -> Result<Hash, StateError> {
-        let key_hash = keccak256(address);
-        let key = H256::from(key_hash.0);
+        let key = account_key(&address);
         let value = H256::from(account_hash.0);
 
         let mut tree = self.tree.lock().unwrap();
@@ -247,17 +516,41 @@ impl StateManager {
         Hash(root_bytes)
     }
 
-    pub fn commit_account(
-        &self,
-        address: Address,
-        info: crate::storage::AccountInfo,
-    ) -> Result<(), StateError> {
+    pub fn commit_account(&self, address: Address, info: AccountInfo) -> Result<(), StateError> {
+        if let Some(diff) = self.diff.lock().unwrap().as_mut() {
+            let before = self
+                .storage
+                .get_account(&address)
+                .map_err(|e| StateError::Smt(e.to_string()))?;
+            diff.record_account(address, before, info.clone());
+        }
         self.storage
             .save_account(&address, &info)
             .map_err(|e| StateError::Smt(e.to_string()))?;
 
-        let hash = hash_data(&info);
-        self.update_account(address, hash)?;
+        let storage_root = self.storage_root(&address)?;
+        self.update_account(address, account_leaf(&hash_data(&info), &storage_root))?;
+        Ok(())
+    }
+
+    /// Store `code` under its hash. Code is kept once however many accounts use it.
+    pub fn commit_code(
+        &self,
+        code_hash: Hash,
+        code: &alloy_primitives::Bytes,
+    ) -> Result<(), StateError> {
+        if code.is_empty() {
+            return Ok(());
+        }
+        let stored = self
+            .storage
+            .get_code(&code_hash)
+            .map_err(|e| StateError::Smt(e.to_string()))?;
+        if stored.is_none() {
+            self.storage
+                .save_code(&code_hash, code)
+                .map_err(|e| StateError::Smt(e.to_string()))?;
+        }
         Ok(())
     }
 
@@ -267,11 +560,102 @@ impl StateManager {
         index: U256,
         value: U256,
     ) -> Result<(), StateError> {
+        if let Some(diff) = self.diff.lock().unwrap().as_mut() {
+            let before = self
+                .storage
+                .get_storage(&address, &index)
+                .map_err(|e| StateError::Smt(e.to_string()))?;
+            diff.record_storage(address, index, before, value);
+        }
         self.storage
             .save_storage(&address, &index, &value)
+            .map_err(|e| StateError::Smt(e.to_string()))?;
+
+        // Commit the slot into the account's storage trie (zero value removes the leaf)
+        let mut storage_tree = self.storage_tree(address, self.storage_root(&address)?);
+        storage_tree
+            .update(slot_key(&index), H256::from(value.to_be_bytes::<32>()))
+            .map_err(|e| StateError::Smt(format!("{:?}", e)))?;
+        let storage_root = Hash((*storage_tree.root()).into());
+        self.storage
+            .save_storage_root(&address, &storage_root)
+            .map_err(|e| StateError::Smt(e.to_string()))?;
+
+        // Fold the new storage root into the account leaf
+        let account_hash = self
+            .storage
+            .get_account(&address)
+            .map_err(|e| StateError::Smt(e.to_string()))?
+            .map(|info| hash_data(&info))
+            .unwrap_or_default();
+        self.update_account(address, account_leaf(&account_hash, &storage_root))?;
+        Ok(())
+    }
+
+    /// Root of the storage trie of `address` in the latest state.
+    fn storage_root(&self, address: &Address) -> Result<Hash, StateError> {
+        self.storage
+            .get_storage_root(address)
             .map_err(|e| StateError::Smt(e.to_string()))
     }
 
+    fn storage_tree(&self, address: Address, root: Hash) -> StateTree {
+        let store = SmtStore::for_account(self.storage.clone(), address);
+        SparseMerkleTree::new(H256::from(root.0), store)
+    }
+
+    /// Inclusion (or exclusion) proofs for `address` and its `slots` against the current
+    /// root. Use `fork` to prove against an older committed root.
+    pub fn get_proof(&self, address: Address, slots: &[U256]) -> Result<StateProof, StateError> {
+        let tree = self.tree.lock().unwrap();
+        let key = account_key(&address);
+        let leaf = get_leaf(&tree, &key)?;
+        let account_hash = Hash(leaf.into());
+
+        // The flat tables only hold the latest state; they are the preimage of an older
+        // leaf only if the account and its storage have not changed since.
+        let mut storage_root = if leaf.is_zero() {
+            Hash::default()
+        } else {
+            self.storage_root(&address)?
+        };
+        let account = self
+            .storage
+            .get_account(&address)
+            .map_err(|e| StateError::Smt(e.to_string()))?
+            .filter(|info| {
+                !leaf.is_zero() && account_leaf(&hash_data(info), &storage_root) == account_hash
+            });
+
+        let mut storage_proofs = Vec::with_capacity(slots.len());
+        if account.is_some() || account_leaf(&Hash::default(), &storage_root) == account_hash {
+            let storage_tree = self.storage_tree(address, storage_root);
+            for index in slots {
+                let key = slot_key(index);
+                storage_proofs.push(StorageProof {
+                    key: *index,
+                    value: U256::from_be_bytes(<[u8; 32]>::from(get_leaf(&storage_tree, &key)?)),
+                    proof: prove_leaf(&storage_tree, key)?,
+                });
+            }
+        } else {
+            // Storage at this root is unknown: nothing about it can be proven
+            storage_root = Hash::default();
+        }
+
+        let mut root = [0u8; 32];
+        root.copy_from_slice(tree.root().as_slice());
+        Ok(StateProof {
+            state_root: Hash(root),
+            address,
+            account_hash,
+            account,
+            storage_root,
+            account_proof: prove_leaf(&tree, key)?,
+            storage_proofs,
+        })
+    }
+
     pub fn get_consensus_state(
         &self,
     ) -> Result<Option<crate::storage::ConsensusState>, StateError> {
@@ -288,6 +672,322 @@ impl StateManager {
             .save_consensus_state(state)
             .map_err(|e| StateError::Smt(e.to_string()))
     }
+
+    pub fn get_reward(&self, validator: &crate::crypto::PublicKey) -> Result<U256, StateError> {
+        self.storage
+            .get_reward(validator)
+            .map_err(|e| StateError::Smt(e.to_string()))
+    }
+
+    pub fn save_reward(
+        &self,
+        validator: &crate::crypto::PublicKey,
+        amount: U256,
+    ) -> Result<(), StateError> {
+        self.storage
+            .save_reward(validator, amount)
+            .map_err(|e| StateError::Smt(e.to_string()))
+    }
+
+    pub fn save_receipts(
+        &self,
+        block_hash: &Hash,
+        receipts: &[crate::types::Receipt],
+    ) -> Result<(), StateError> {
+        self.storage
+            .save_receipts(block_hash, receipts)
+            .map_err(|e| StateError::Smt(e.to_string()))
+    }
+
+    pub fn save_block_hash(&self, height: u64, block_hash: &Hash) -> Result<(), StateError> {
+        self.storage
+            .save_block_hash(height, block_hash)
+            .map_err(|e| StateError::Smt(e.to_string()))
+    }
+}
+
+fn account_key(address: &Address) -> H256 {
+    H256::from(keccak256(address).0)
+}
+
+fn slot_key(index: &U256) -> H256 {
+    H256::from(keccak256(index.to_be_bytes::<32>()).0)
+}
+
+/// Leaf of an account in the state tree: the hash of its info folded with the root of its
+/// storage trie. Accounts without storage keep `hash_data(&info)` as their leaf.
+pub fn account_leaf(account_hash: &Hash, storage_root: &Hash) -> Hash {
+    if *storage_root == Hash::default() {
+        *account_hash
+    } else {
+        hash_data(&(account_hash, storage_root))
+    }
+}
+
+fn get_leaf(tree: &StateTree, key: &H256) -> Result<H256, StateError> {
+    tree.get(key)
+        .map_err(|e| StateError::Smt(format!("{:?}", e)))
+}
+
+fn prove_leaf(tree: &StateTree, key: H256) -> Result<Vec<u8>, StateError> {
+    tree.merkle_proof(vec![key])
+        .and_then(|proof| proof.compile(vec![key]))
+        .map(|proof| proof.0)
+        .map_err(|e| StateError::Smt(format!("{:?}", e)))
+}
+
+/// Proof of an account and some of its storage slots against a state root.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateProof {
+    pub state_root: Hash,
+    pub address: Address,
+    /// The account leaf (see `account_leaf`), zero if the account does not exist.
+    pub account_hash: Hash,
+    /// Account folded into `account_hash`, when the node still has it.
+    pub account: Option<AccountInfo>,
+    /// Root of the account's storage trie, which `storage_proofs` are against. Zero when
+    /// the account has no storage, or its storage at this root is no longer known.
+    pub storage_root: Hash,
+    /// Compiled sparse Merkle tree proof of the account leaf.
+    pub account_proof: Vec<u8>,
+    pub storage_proofs: Vec<StorageProof>,
+}
+
+/// Proof of one storage slot (a zero value proves the slot is empty).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StorageProof {
+    pub key: U256,
+    pub value: U256,
+    pub proof: Vec<u8>,
+}
+
+fn verify_leaf(root: &Hash, key: H256, value: H256, proof: &[u8]) -> bool {
+    sparse_merkle_tree::CompiledMerkleProof(proof.to_vec())
+        .verify::<sparse_merkle_tree::blake2b::Blake2bHasher>(
+            &H256::from(root.0),
+            vec![(key, value)],
+        )
+        .unwrap_or(false)
+}
+
+/// Check every proof in `proof` against `root`, without access to any state.
+pub fn verify_proof(root: &Hash, proof: &StateProof) -> bool {
+    if proof.state_root != *root {
+        return false;
+    }
+    // The account and storage root must fold into the leaf, unless neither is known
+    let account_hash = proof.account.as_ref().map(hash_data).unwrap_or_default();
+    if account_leaf(&account_hash, &proof.storage_root) != proof.account_hash
+        && (proof.account.is_some() || !proof.storage_proofs.is_empty())
+    {
+        return false;
+    }
+    verify_leaf(
+        root,
+        account_key(&proof.address),
+        H256::from(proof.account_hash.0),
+        &proof.account_proof,
+    ) && proof.storage_proofs.iter().all(|slot| {
+        verify_leaf(
+            &proof.storage_root,
+            slot_key(&slot.key),
+            H256::from(slot.value.to_be_bytes::<32>()),
+            &slot.proof,
+        )
+    })
+}
+
+/// Value of an account field or slot before and after a change.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+/// Changes of one account: its info (`None` before it existed) and storage slots.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AccountDiff {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<Change<Option<AccountInfo>>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<U256, Change<U256>>,
+}
+
+/// Accounts and slots changed by one or more consecutive committed blocks. Values
+/// changed and then restored are left out.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateDiff {
+    pub accounts: BTreeMap<Address, AccountDiff>,
+}
+
+impl StateDiff {
+    pub fn record_account(
+        &mut self,
+        address: Address,
+        before: Option<AccountInfo>,
+        after: AccountInfo,
+    ) {
+        let account = self.accounts.entry(address).or_default();
+        match &mut account.info {
+            Some(change) => change.after = Some(after),
+            None => {
+                account.info = Some(Change {
+                    before,
+                    after: Some(after),
+                })
+            }
+        }
+    }
+
+    pub fn record_storage(&mut self, address: Address, index: U256, before: U256, after: U256) {
+        self.accounts
+            .entry(address)
+            .or_default()
+            .storage
+            .entry(index)
+            .and_modify(|change| change.after = after)
+            .or_insert(Change { before, after });
+    }
+
+    /// Fold in the diff of the blocks committed right after ours.
+    pub fn merge(&mut self, later: StateDiff) {
+        for (address, diff) in later.accounts {
+            if let Some(info) = diff.info {
+                let account = self.accounts.entry(address).or_default();
+                match &mut account.info {
+                    Some(change) => change.after = info.after,
+                    None => account.info = Some(info),
+                }
+            }
+            for (index, change) in diff.storage {
+                self.record_storage(address, index, change.before, change.after);
+            }
+        }
+    }
+
+    /// Drop the values that ended up unchanged, and accounts left without changes.
+    pub fn without_noops(mut self) -> Self {
+        for diff in self.accounts.values_mut() {
+            if diff
+                .info
+                .as_ref()
+                .is_some_and(|change| change.before == change.after)
+            {
+                diff.info = None;
+            }
+            diff.storage
+                .retain(|_, change| change.before != change.after);
+        }
+        self.accounts
+            .retain(|_, diff| diff.info.is_some() || !diff.storage.is_empty());
+        self
+    }
+}
+
+/// Pre-state read while executing one block: the accounts, slots and code it touched, and
+/// the SMT nodes needed to prove and update them. With the parent's state root it is
+/// enough to re-execute the block without any other state.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StateWitness {
+    /// Accounts read, `None` for accounts that did not exist.
+    pub accounts: Vec<(Address, Option<AccountInfo>)>,
+    pub storage: Vec<(Address, U256, U256)>,
+    /// Storage trie roots read, each with its account in `accounts`.
+    #[serde(default)]
+    pub storage_roots: Vec<(Address, Hash)>,
+    pub code: Vec<(Hash, alloy_primitives::Bytes)>,
+    pub smt_branches: Vec<(u8, Hash, Vec<u8>)>,
+    pub smt_leaves: Vec<(Hash, Vec<u8>)>,
+    /// Blocks read by `BLOCKHASH`.
+    pub blocks: Vec<crate::types::Block>,
+}
+
+impl StateWitness {
+    /// A standalone store holding only the witnessed data.
+    pub fn to_storage(&self) -> crate::storage::MemStorage {
+        let storage = crate::storage::MemStorage::new();
+        // MemStorage writes cannot fail
+        for (address, info) in &self.accounts {
+            if let Some(info) = info {
+                let _ = storage.save_account(address, info);
+            }
+        }
+        for (address, index, value) in &self.storage {
+            let _ = storage.save_storage(address, index, value);
+        }
+        for (address, root) in &self.storage_roots {
+            let _ = storage.save_storage_root(address, root);
+        }
+        for (hash, code) in &self.code {
+            let _ = storage.save_code(hash, code);
+        }
+        for (height, node_key, node) in &self.smt_branches {
+            let _ = storage.save_smt_branch(*height, node_key, node);
+        }
+        for (hash, node) in &self.smt_leaves {
+            let _ = storage.save_smt_leaf(hash, node);
+        }
+        for block in &self.blocks {
+            let _ = storage.save_block(block);
+        }
+        storage
+    }
+
+    /// Check every witnessed account and slot against `root`, so a forged witness cannot
+    /// make an invalid block look valid.
+    pub fn verify(&self, root: &Hash) -> bool {
+        if self
+            .code
+            .iter()
+            .any(|(hash, code)| keccak256(code).0 != hash.0)
+        {
+            return false;
+        }
+        // A storage root is only bound by the state through the account it is folded with
+        if self
+            .storage_roots
+            .iter()
+            .any(|(address, _)| !self.accounts.iter().any(|(a, _)| a == address))
+        {
+            return false;
+        }
+
+        let state = StateManager::new(Arc::new(self.to_storage()), Some(*root));
+        let mut addresses: Vec<Address> = self.accounts.iter().map(|(a, _)| *a).collect();
+        addresses.extend(self.storage.iter().map(|(a, _, _)| *a));
+        addresses.sort();
+        addresses.dedup();
+
+        addresses.into_iter().all(|address| {
+            let slots: Vec<(U256, U256)> = self
+                .storage
+                .iter()
+                .filter(|(a, _, _)| *a == address)
+                .map(|(_, index, value)| (*index, *value))
+                .collect();
+            let indices: Vec<U256> = slots.iter().map(|(index, _)| *index).collect();
+            let Ok(proof) = state.get_proof(address, &indices) else {
+                return false;
+            };
+            let account_matches = match self.accounts.iter().find(|(a, _)| *a == address) {
+                Some((_, account)) => {
+                    proof.account == *account
+                        && (account.is_some()
+                            || proof.account_hash
+                                == account_leaf(&Hash::default(), &proof.storage_root))
+                }
+                None => true,
+            };
+            account_matches
+                && verify_proof(root, &proof)
+                && proof.storage_proofs.len() == slots.len()
+                && proof
+                    .storage_proofs
+                    .iter()
+                    .zip(&slots)
+                    .all(|(slot, (_, value))| slot.value == *value)
+        })
+    }
 }
 
 impl Database for StateManager {
@@ -299,9 +999,7 @@ impl Database for StateManager {
             .get_account(&address)
             .map_err(|e| StateError::Smt(e.to_string()))?
         {
-            let code = if let Some(c) = info.code {
-                Some(Bytecode::new_raw(c))
-            } else if info.code_hash != Hash::default() {
+            let code = if info.code_hash != Hash::default() {
                 let code_bytes = self
                     .storage
                     .get_code(&info.code_hash)
@@ -341,48 +1039,44 @@ impl Database for StateManager {
     }
 
     fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
-        // Attempt to convert U256 to u64 for view comparison
-        let requested_view: u64 = match number.try_into() {
-            Ok(v) => v,
+        // Only the 256 blocks below the one being executed are visible
+        let Some((height, parent_hash)) = self.block_context else {
+            return Ok(B256::ZERO);
+        };
+        let requested: u64 = match number.try_into() {
+            Ok(h) => h,
             Err(_) => return Ok(B256::ZERO),
         };
+        if requested >= height || height - requested > BLOCK_HASH_HISTORY {
+            return Ok(B256::ZERO);
+        }
 
-        // Get the current consensus state to start search from the head
-        let state = self
+        // Committed blocks are indexed by height (the finalized chain is a prefix of ours)
+        if let Some(hash) = self
             .storage
-            .get_consensus_state()
-            .map_err(|e| StateError::Smt(e.to_string()))?;
-
-        let mut current_hash = match state {
-            Some(s) => s.preferred_block,
-            None => return Ok(B256::ZERO),
-        };
-
-        // EVM blockhash is limited to the last 256 blocks
-        for _ in 0..256 {
-            if current_hash == Hash::default() {
-                break;
-            }
+            .get_block_hash(requested)
+            .map_err(|e| StateError::Smt(e.to_string()))?
+        {
+            return Ok(B256::from(hash.0));
+        }
 
-            let block = self
+        // Not committed here yet: walk back from the parent, the same on every node
+        let mut current_hash = parent_hash;
+        while current_hash != Hash::default() {
+            let Some(block) = self
                 .storage
                 .get_block(&current_hash)
-                .map_err(|e| StateError::Smt(e.to_string()))?;
-
-            if let Some(b) = block {
-                if b.view == requested_view {
-                    return Ok(B256::from(current_hash.0));
-                }
-                if b.view < requested_view {
-                    // Given that we traverse backwards, if we see a view smaller
-                    // than requested, the block is not in the canonical chain.
-                    break;
-                }
-                current_hash = b.parent_hash;
-            } else {
-                // Block missing from storage, stop search
+                .map_err(|e| StateError::Smt(e.to_string()))?
+            else {
+                break;
+            };
+            if block.height == requested {
+                return Ok(B256::from(current_hash.0));
+            }
+            if block.height < requested {
                 break;
             }
+            current_hash = block.parent_hash;
         }
 
         Ok(B256::ZERO)
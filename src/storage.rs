@@ -1,24 +1,52 @@
 use crate::crypto::{Hash, PublicKey};
-use crate::types::{Address, Block, QuorumCertificate, View};
+use crate::state::{CacheKey, CacheValue, StateCache, StateDiff, StateWitness};
+use crate::types::{
+    Address, Block, BlockBody, BlockHeader, ChainParams, CommitteeTransition, ParamsProposal,
+    QuorumCertificate, Receipt, Transaction, View, VoteType,
+};
 use alloy_primitives::{Bytes, U256};
-use redb::{Database, TableDefinition};
+use redb::{Database, Durability, ReadableTable, TableDefinition};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
+// Blocks written before the header / body split; read as a fallback
 const TABLE_BLOCKS: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("blocks");
+const TABLE_HEADERS: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("headers"); // Key: Block Hash
+const TABLE_BODIES: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("bodies"); // Key: Block Hash
 const TABLE_QCS: TableDefinition<u64, Vec<u8>> = TableDefinition::new("qcs");
-const TABLE_META: TableDefinition<&str, Vec<u8>> = TableDefinition::new("meta");
+const TABLE_FINALITY_QCS: TableDefinition<u64, Vec<u8>> = TableDefinition::new("finality_qcs");
+pub(crate) const TABLE_META: TableDefinition<&str, Vec<u8>> = TableDefinition::new("meta");
+const TABLE_COMMITTEE_TRANSITIONS: TableDefinition<u64, Vec<u8>> =
+    TableDefinition::new("committee_transitions"); // Key: Epoch
+const TABLE_VALIDATOR_SETS: TableDefinition<u64, Vec<u8>> = TableDefinition::new("validator_sets"); // Key: Epoch
+const TABLE_RECEIPTS: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("receipts"); // Key: Block Hash
+const TABLE_BLOCK_HASHES: TableDefinition<u64, Vec<u8>> = TableDefinition::new("block_hashes"); // Key: Height
+const TABLE_WITNESSES: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("witnesses"); // Key: Block Hash
+const TABLE_STATE_DIFFS: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("state_diffs"); // Key: Block Hash
+const TABLE_PEERS: TableDefinition<&str, Vec<u8>> = TableDefinition::new("peers"); // Key: PeerId
+const TABLE_VOTE_JOURNAL: TableDefinition<u64, Vec<u8>> = TableDefinition::new("vote_journal"); // Key: View
+pub(crate) const TABLE_VALIDATOR_SCORES: TableDefinition<&[u8], Vec<u8>> =
+    TableDefinition::new("validator_scores"); // Key: Public Key
+pub(crate) const TABLE_REWARDS: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("rewards"); // Key: Public Key
+pub(crate) const TABLE_VALIDATOR_QUEUES: TableDefinition<&str, Vec<u8>> =
+    TableDefinition::new("validator_queues"); // Key: "pending" / "exiting"
 
 // New Tables for EVM State
-const TABLE_ACCOUNTS: TableDefinition<&[u8; 20], Vec<u8>> = TableDefinition::new("accounts");
+pub(crate) const TABLE_ACCOUNTS: TableDefinition<&[u8; 20], Vec<u8>> =
+    TableDefinition::new("accounts");
 const TABLE_STORAGE: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("storage"); // Key: Address + StorageKey
-const TABLE_CODE: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("code");
+pub(crate) const TABLE_CODE: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("code");
+const TABLE_STORAGE_ROOTS: TableDefinition<&[u8; 20], Vec<u8>> =
+    TableDefinition::new("storage_roots");
 const TABLE_SMT_LEAVES: TableDefinition<&[u8; 32], Vec<u8>> = TableDefinition::new("smt_leaves");
 const TABLE_SMT_BRANCHES: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("smt_branches");
 
+/// Committed block hashes kept by height: the window the EVM `BLOCKHASH` opcode can read.
+pub const BLOCK_HASH_HISTORY: u64 = 256;
+
 #[derive(Error, Debug)]
 pub enum StorageError {
     #[error("Redb error: {0}")]
@@ -37,6 +65,10 @@ pub enum StorageError {
     Commit(Box<redb::CommitError>),
     #[error("Custom error: {0}")]
     Custom(String),
+    #[error(
+        "Database schema version {found} is newer than the supported version {supported}; upgrade the node or use another data directory"
+    )]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
 }
 
 impl From<redb::Error> for StorageError {
@@ -75,7 +107,9 @@ impl From<redb::CommitError> for StorageError {
     }
 }
 
-/// Persistent State that needs to be saved atomically (or somewhat atomically)
+/// Persistent State that needs to be saved atomically (or somewhat atomically).
+/// `RedbStorage` keeps its parts apart (head pointers, scores, rewards, validator queues),
+/// so they can also be read and written on their own.
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct ConsensusState {
     pub view: View,
@@ -86,17 +120,247 @@ pub struct ConsensusState {
     pub committee: Vec<PublicKey>,
     pub pending_validators: Vec<(PublicKey, View)>,
     pub exiting_validators: Vec<(PublicKey, View)>,
-    pub stakes: HashMap<Address, U256>,
     pub inactivity_scores: HashMap<PublicKey, u64>,
+    pub params: ChainParams,
+    /// Unclaimed proposer/attester rewards (see the staking contract's `claimRewards`).
+    #[serde(default)]
+    pub rewards: HashMap<PublicKey, U256>,
+    /// Open and approved governance proposals (see the governance contract).
+    #[serde(default)]
+    pub proposals: Vec<ParamsProposal>,
+    /// Consecutive block QCs each committee member is missing from (inactivity leak).
+    #[serde(default)]
+    pub missed_attestations: HashMap<PublicKey, u64>,
+}
+
+impl ConsensusState {
+    /// State of a new chain whose genesis block is `genesis_hash`.
+    pub fn genesis(genesis_hash: Hash, committee: Vec<PublicKey>, params: ChainParams) -> Self {
+        Self {
+            view: 1,
+            finalized_height: 0,
+            preferred_block: genesis_hash,
+            preferred_view: 0,
+            last_voted_view: 0,
+            committee,
+            pending_validators: vec![],
+            exiting_validators: vec![],
+            inactivity_scores: HashMap::new(),
+            params,
+            rewards: HashMap::new(),
+            proposals: vec![],
+            missed_attestations: HashMap::new(),
+        }
+    }
+
+    pub fn head(&self) -> ConsensusHead {
+        ConsensusHead {
+            view: self.view,
+            finalized_height: self.finalized_height,
+            preferred_block: self.preferred_block,
+            preferred_view: self.preferred_view,
+            last_voted_view: self.last_voted_view,
+        }
+    }
+
+    pub fn set_head(&mut self, head: &ConsensusHead) {
+        self.view = head.view;
+        self.finalized_height = head.finalized_height;
+        self.preferred_block = head.preferred_block;
+        self.preferred_view = head.preferred_view;
+        self.last_voted_view = head.last_voted_view;
+    }
+
+    pub fn scores(&self, validator: &PublicKey) -> ValidatorScores {
+        ValidatorScores {
+            inactivity: self.inactivity_scores.get(validator).copied().unwrap_or(0),
+            missed_attestations: self
+                .missed_attestations
+                .get(validator)
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Zero scores are not kept.
+    pub fn set_scores(&mut self, validator: &PublicKey, scores: ValidatorScores) {
+        for (map, score) in [
+            (&mut self.inactivity_scores, scores.inactivity),
+            (&mut self.missed_attestations, scores.missed_attestations),
+        ] {
+            if score == 0 {
+                map.remove(validator);
+            } else {
+                map.insert(validator.clone(), score);
+            }
+        }
+    }
+
+    pub fn queues(&self) -> ValidatorQueues {
+        ValidatorQueues {
+            pending: self.pending_validators.clone(),
+            exiting: self.exiting_validators.clone(),
+        }
+    }
+
+    pub fn set_queues(&mut self, queues: ValidatorQueues) {
+        self.pending_validators = queues.pending;
+        self.exiting_validators = queues.exiting;
+    }
+}
+
+/// Where consensus is: the part of `ConsensusState` rewritten on nearly every event
+/// (`Storage::save_consensus_head`).
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct ConsensusHead {
+    pub view: View,
+    pub finalized_height: View,
+    pub preferred_block: Hash,
+    pub preferred_view: View,
+    pub last_voted_view: View,
+}
+
+/// Liveness record of a validator (`ConsensusState::inactivity_scores` and
+/// `missed_attestations`).
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidatorScores {
+    pub inactivity: u64,
+    pub missed_attestations: u64,
+}
+
+/// Validators joining (`pending`) or leaving (`exiting`) the committee, with the view
+/// they do.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorQueues {
+    pub pending: Vec<(PublicKey, View)>,
+    pub exiting: Vec<(PublicKey, View)>,
+}
+
+// What `RedbStorage` keeps of a `ConsensusState` in the meta table, besides its head
+#[derive(Serialize, Deserialize, Default)]
+struct ConsensusMeta {
+    committee: Vec<PublicKey>,
+    params: ChainParams,
+    proposals: Vec<ParamsProposal>,
+}
+
+/// An entry of the vote journal: we signed a `vote_type` vote for `block_hash` in `view`,
+/// at `timestamp` (unix seconds).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VoteRecord {
+    pub view: View,
+    pub block_hash: Hash,
+    pub vote_type: VoteType,
+    pub timestamp: u64,
+}
+
+/// When appends to the vote journal reach the disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JournalSync {
+    /// Every append is fsynced before the vote is sent: no vote survives a crash unrecorded.
+    #[default]
+    Always,
+    /// Appends are flushed with the next synced write: faster, but a crash (of the machine,
+    /// not only the process) may lose the latest records.
+    Deferred,
 }
 
-/// Account Information stored in the Global State
+/// Account Information stored in the Global State.
+/// Bytecode is stored once, under its hash (`get_code`).
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AccountInfo {
     pub nonce: u64,
     pub balance: U256,
-    pub code_hash: Hash,     // keccak256(code)
-    pub code: Option<Bytes>, // Cache code here or just check TABLE_CODE
+    pub code_hash: Hash, // keccak256(code)
+}
+
+/// The last finalized block whose state has been committed, and the resulting state root.
+/// Crash recovery restarts the executor from here.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChainHead {
+    pub view: View,
+    pub block_hash: Hash,
+    pub state_root: Hash,
+}
+
+/// A validator as reported by `get_validator_set`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorInfo {
+    pub public_key: PublicKey,
+    pub address: Address,
+    pub stake: U256,
+    pub inactivity_score: u64,
+    /// Consecutive block QCs the validator is missing from.
+    #[serde(default)]
+    pub missed_attestations: u64,
+    /// Unclaimed rewards.
+    pub rewards: U256,
+    /// Activation view (pending) or exit view (exiting); None for committee members.
+    pub scheduled_view: Option<View>,
+}
+
+/// The committee, pending and exiting validators with their stakes. A snapshot is stored
+/// at the start of every epoch (genesis, then each committee change).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorSet {
+    pub epoch: u64,
+    /// View the set was taken at.
+    pub view: View,
+    pub committee: Vec<ValidatorInfo>,
+    pub pending: Vec<ValidatorInfo>,
+    pub exiting: Vec<ValidatorInfo>,
+}
+
+impl ValidatorSet {
+    /// The validator sets of `state`, with stakes read from the staking contract.
+    pub fn from_state(
+        epoch: u64,
+        view: View,
+        state: &ConsensusState,
+        storage: &dyn Storage,
+    ) -> Result<Self, StorageError> {
+        use crate::system_contracts::staking;
+        let info = |pk: &PublicKey, scheduled_view| -> Result<ValidatorInfo, StorageError> {
+            let address = staking::validator_address(pk);
+            Ok(ValidatorInfo {
+                public_key: pk.clone(),
+                address,
+                stake: storage
+                    .get_storage(&staking::STAKING_ADDRESS, &staking::stake_slot(address))?,
+                inactivity_score: state.inactivity_scores.get(pk).copied().unwrap_or(0),
+                missed_attestations: state.missed_attestations.get(pk).copied().unwrap_or(0),
+                rewards: state.rewards.get(pk).copied().unwrap_or(U256::ZERO),
+                scheduled_view,
+            })
+        };
+        Ok(Self {
+            epoch,
+            view,
+            committee: state
+                .committee
+                .iter()
+                .map(|pk| info(pk, None))
+                .collect::<Result<_, _>>()?,
+            pending: state
+                .pending_validators
+                .iter()
+                .map(|(pk, view)| info(pk, Some(*view)))
+                .collect::<Result<_, _>>()?,
+            exiting: state
+                .exiting_validators
+                .iter()
+                .map(|(pk, view)| info(pk, Some(*view)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// A peer we have dialed successfully (persistent peer store).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KnownPeer {
+    pub peer_id: String,
+    pub address: String, // Multiaddr we reached it at
+    pub last_seen: u64,  // Unix seconds
 }
 
 impl Default for AccountInfo {
@@ -105,7 +369,6 @@ impl Default for AccountInfo {
             nonce: 0,
             balance: U256::ZERO,
             code_hash: Hash::default(), // Should be empty hash?
-            code: None,
         }
     }
 }
@@ -113,13 +376,88 @@ impl Default for AccountInfo {
 pub trait Storage: Send + Sync {
     fn save_block(&self, block: &Block) -> Result<(), StorageError>;
     fn get_block(&self, hash: &Hash) -> Result<Option<Block>, StorageError>;
+    /// The block without its body, for queries that do not need the transactions.
+    fn get_header(&self, hash: &Hash) -> Result<Option<BlockHeader>, StorageError>;
 
     fn save_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError>;
     fn get_qc(&self, view: View) -> Result<Option<QuorumCertificate>, StorageError>;
 
+    // Finalization Certificates (aggregated Finalize votes)
+    fn save_finality_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError>;
+    fn get_finality_qc(&self, view: View) -> Result<Option<QuorumCertificate>, StorageError>;
+
     fn save_consensus_state(&self, state: &ConsensusState) -> Result<(), StorageError>;
     fn get_consensus_state(&self) -> Result<Option<ConsensusState>, StorageError>;
 
+    // Parts of the Consensus State, read and written without the rest
+    fn save_consensus_head(&self, head: &ConsensusHead) -> Result<(), StorageError>;
+    fn get_consensus_head(&self) -> Result<Option<ConsensusHead>, StorageError>;
+    fn save_validator_scores(
+        &self,
+        validator: &PublicKey,
+        scores: ValidatorScores,
+    ) -> Result<(), StorageError>;
+    fn get_validator_scores(&self, validator: &PublicKey) -> Result<ValidatorScores, StorageError>;
+    /// Unclaimed rewards of `validator` (zero removes the entry).
+    fn save_reward(&self, validator: &PublicKey, amount: U256) -> Result<(), StorageError>;
+    fn get_reward(&self, validator: &PublicKey) -> Result<U256, StorageError>;
+    fn save_validator_queues(&self, queues: &ValidatorQueues) -> Result<(), StorageError>;
+    fn get_validator_queues(&self) -> Result<ValidatorQueues, StorageError>;
+
+    // Committed Chain Head
+    fn save_chain_head(&self, head: &ChainHead) -> Result<(), StorageError>;
+    fn get_chain_head(&self) -> Result<Option<ChainHead>, StorageError>;
+
+    // Committee Hand-over Chain
+    fn save_committee_transition(
+        &self,
+        transition: &CommitteeTransition,
+    ) -> Result<(), StorageError>;
+    fn get_committee_transition(
+        &self,
+        epoch: u64,
+    ) -> Result<Option<CommitteeTransition>, StorageError>;
+    fn get_latest_committee_transition(&self) -> Result<Option<CommitteeTransition>, StorageError>;
+
+    // Validator set snapshots by epoch
+    fn save_validator_set(&self, set: &ValidatorSet) -> Result<(), StorageError>;
+    fn get_validator_set(&self, epoch: u64) -> Result<Option<ValidatorSet>, StorageError>;
+    fn get_latest_validator_set(&self) -> Result<Option<ValidatorSet>, StorageError>;
+    /// Snapshot of the epoch started at or before `view`.
+    fn get_validator_set_at(&self, view: View) -> Result<Option<ValidatorSet>, StorageError>;
+
+    // Receipts of executed (finalized) blocks
+    fn save_receipts(&self, block_hash: &Hash, receipts: &[Receipt]) -> Result<(), StorageError>;
+    fn get_receipts(&self, block_hash: &Hash) -> Result<Option<Vec<Receipt>>, StorageError>;
+
+    // Hashes of committed blocks by height; saving one drops the entry `BLOCK_HASH_HISTORY` below
+    fn save_block_hash(&self, height: u64, block_hash: &Hash) -> Result<(), StorageError>;
+    fn get_block_hash(&self, height: u64) -> Result<Option<Hash>, StorageError>;
+
+    // State witnesses of validated blocks
+    fn save_witness(&self, block_hash: &Hash, witness: &StateWitness) -> Result<(), StorageError>;
+    fn get_witness(&self, block_hash: &Hash) -> Result<Option<StateWitness>, StorageError>;
+
+    // State changes of committed blocks
+    fn save_state_diff(&self, block_hash: &Hash, diff: &StateDiff) -> Result<(), StorageError>;
+    fn get_state_diff(&self, block_hash: &Hash) -> Result<Option<StateDiff>, StorageError>;
+
+    // Peer Store
+    fn save_peer(&self, peer: &KnownPeer) -> Result<(), StorageError>;
+    fn get_peers(&self) -> Result<Vec<KnownPeer>, StorageError>;
+    fn remove_peer(&self, peer_id: &str) -> Result<(), StorageError>;
+
+    // Transaction pool journal: local transactions kept across restarts
+    fn save_pool_journal(&self, txs: &[Transaction]) -> Result<(), StorageError>;
+    fn get_pool_journal(&self) -> Result<Vec<Transaction>, StorageError>;
+
+    // Vote journal: write-ahead log of our own votes, appended before each one is sent
+    fn append_vote_record(&self, record: &VoteRecord) -> Result<(), StorageError>;
+    /// Records of `from_view` and later views, in view order.
+    fn get_vote_records(&self, from_view: View) -> Result<Vec<VoteRecord>, StorageError>;
+    /// Drop the records of views below `view`.
+    fn prune_vote_records(&self, view: View) -> Result<(), StorageError>;
+
     // EVM State
     fn get_account(&self, address: &Address) -> Result<Option<AccountInfo>, StorageError>;
     fn save_account(&self, address: &Address, info: &AccountInfo) -> Result<(), StorageError>;
@@ -132,6 +470,13 @@ pub trait Storage: Send + Sync {
         index: &U256,
         value: &U256,
     ) -> Result<(), StorageError>;
+    /// Root of the storage trie of an account (zero if it has no storage).
+    fn get_storage_root(&self, address: &Address) -> Result<Hash, StorageError>;
+    fn save_storage_root(&self, address: &Address, root: &Hash) -> Result<(), StorageError>;
+
+    // State Enumeration (export / snapshots)
+    fn get_all_accounts(&self) -> Result<Vec<(Address, AccountInfo)>, StorageError>;
+    fn get_all_storage(&self, address: &Address) -> Result<Vec<(U256, U256)>, StorageError>;
 
     // SMT Storage
     fn get_smt_branch(&self, height: u8, node_key: &Hash) -> Result<Option<Vec<u8>>, StorageError>;
@@ -148,13 +493,26 @@ pub type SmtBranchMap = HashMap<(u8, Hash), Vec<u8>>;
 
 #[derive(Clone, Default)]
 pub struct MemStorage {
-    blocks: Arc<Mutex<HashMap<Hash, Block>>>,
+    headers: Arc<Mutex<HashMap<Hash, BlockHeader>>>,
+    bodies: Arc<Mutex<HashMap<Hash, BlockBody>>>,
     qcs: Arc<Mutex<HashMap<View, QuorumCertificate>>>,
+    finality_qcs: Arc<Mutex<HashMap<View, QuorumCertificate>>>,
     state: Arc<Mutex<Option<ConsensusState>>>,
+    chain_head: Arc<Mutex<Option<ChainHead>>>,
+    transitions: Arc<Mutex<BTreeMap<u64, CommitteeTransition>>>,
+    validator_sets: Arc<Mutex<BTreeMap<u64, ValidatorSet>>>,
+    receipts: Arc<Mutex<HashMap<Hash, Vec<Receipt>>>>,
+    block_hashes: Arc<Mutex<HashMap<u64, Hash>>>,
+    witnesses: Arc<Mutex<HashMap<Hash, StateWitness>>>,
+    state_diffs: Arc<Mutex<HashMap<Hash, StateDiff>>>,
+    peers: Arc<Mutex<HashMap<String, KnownPeer>>>,
+    pool_journal: Arc<Mutex<Vec<Transaction>>>,
+    vote_journal: Arc<Mutex<BTreeMap<View, Vec<VoteRecord>>>>,
     // EVM State
     accounts: Arc<Mutex<HashMap<Address, AccountInfo>>>,
     code: Arc<Mutex<HashMap<Hash, Bytes>>>,
     storage: Arc<Mutex<HashMap<(Address, U256), U256>>>,
+    storage_roots: Arc<Mutex<HashMap<Address, Hash>>>,
     smt_leaves: Arc<Mutex<HashMap<Hash, Vec<u8>>>>,
     smt_branches: Arc<Mutex<SmtBranchMap>>,
 }
@@ -163,17 +521,37 @@ impl MemStorage {
     pub fn new() -> Self {
         Self::default()
     }
+
+    // A part of the consensus state saved first creates the rest with defaults
+    fn update_state(&self, update: impl FnOnce(&mut ConsensusState)) {
+        update(
+            self.state
+                .lock()
+                .unwrap()
+                .get_or_insert_with(Default::default),
+        );
+    }
 }
 
 impl Storage for MemStorage {
     fn save_block(&self, block: &Block) -> Result<(), StorageError> {
         let hash = crate::crypto::hash_data(block);
-        self.blocks.lock().unwrap().insert(hash, block.clone());
+        let (header, body) = block.clone().split();
+        self.headers.lock().unwrap().insert(hash, header);
+        self.bodies.lock().unwrap().insert(hash, body);
         Ok(())
     }
 
     fn get_block(&self, hash: &Hash) -> Result<Option<Block>, StorageError> {
-        Ok(self.blocks.lock().unwrap().get(hash).cloned())
+        let header = self.headers.lock().unwrap().get(hash).cloned();
+        let body = self.bodies.lock().unwrap().get(hash).cloned();
+        Ok(header
+            .zip(body)
+            .and_then(|(header, body)| Block::from_parts(header, body)))
+    }
+
+    fn get_header(&self, hash: &Hash) -> Result<Option<BlockHeader>, StorageError> {
+        Ok(self.headers.lock().unwrap().get(hash).cloned())
     }
 
     fn save_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError> {
@@ -185,6 +563,18 @@ impl Storage for MemStorage {
         Ok(self.qcs.lock().unwrap().get(&view).cloned())
     }
 
+    fn save_finality_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError> {
+        self.finality_qcs
+            .lock()
+            .unwrap()
+            .insert(qc.view, qc.clone());
+        Ok(())
+    }
+
+    fn get_finality_qc(&self, view: View) -> Result<Option<QuorumCertificate>, StorageError> {
+        Ok(self.finality_qcs.lock().unwrap().get(&view).cloned())
+    }
+
     fn save_consensus_state(&self, state: &ConsensusState) -> Result<(), StorageError> {
         *self.state.lock().unwrap() = Some(state.clone());
         Ok(())
@@ -194,6 +584,241 @@ impl Storage for MemStorage {
         Ok(self.state.lock().unwrap().clone())
     }
 
+    fn save_consensus_head(&self, head: &ConsensusHead) -> Result<(), StorageError> {
+        self.update_state(|state| state.set_head(head));
+        Ok(())
+    }
+
+    fn get_consensus_head(&self) -> Result<Option<ConsensusHead>, StorageError> {
+        Ok(self.state.lock().unwrap().as_ref().map(|s| s.head()))
+    }
+
+    fn save_validator_scores(
+        &self,
+        validator: &PublicKey,
+        scores: ValidatorScores,
+    ) -> Result<(), StorageError> {
+        self.update_state(|state| state.set_scores(validator, scores));
+        Ok(())
+    }
+
+    fn get_validator_scores(&self, validator: &PublicKey) -> Result<ValidatorScores, StorageError> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.scores(validator))
+            .unwrap_or_default())
+    }
+
+    fn save_reward(&self, validator: &PublicKey, amount: U256) -> Result<(), StorageError> {
+        self.update_state(|state| {
+            if amount == U256::ZERO {
+                state.rewards.remove(validator);
+            } else {
+                state.rewards.insert(validator.clone(), amount);
+            }
+        });
+        Ok(())
+    }
+
+    fn get_reward(&self, validator: &PublicKey) -> Result<U256, StorageError> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|s| s.rewards.get(validator).copied())
+            .unwrap_or(U256::ZERO))
+    }
+
+    fn save_validator_queues(&self, queues: &ValidatorQueues) -> Result<(), StorageError> {
+        self.update_state(|state| state.set_queues(queues.clone()));
+        Ok(())
+    }
+
+    fn get_validator_queues(&self) -> Result<ValidatorQueues, StorageError> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.queues())
+            .unwrap_or_default())
+    }
+
+    fn save_chain_head(&self, head: &ChainHead) -> Result<(), StorageError> {
+        *self.chain_head.lock().unwrap() = Some(head.clone());
+        Ok(())
+    }
+
+    fn get_chain_head(&self) -> Result<Option<ChainHead>, StorageError> {
+        Ok(self.chain_head.lock().unwrap().clone())
+    }
+
+    fn save_committee_transition(
+        &self,
+        transition: &CommitteeTransition,
+    ) -> Result<(), StorageError> {
+        self.transitions
+            .lock()
+            .unwrap()
+            .insert(transition.epoch, transition.clone());
+        Ok(())
+    }
+
+    fn get_committee_transition(
+        &self,
+        epoch: u64,
+    ) -> Result<Option<CommitteeTransition>, StorageError> {
+        Ok(self.transitions.lock().unwrap().get(&epoch).cloned())
+    }
+
+    fn get_latest_committee_transition(&self) -> Result<Option<CommitteeTransition>, StorageError> {
+        Ok(self
+            .transitions
+            .lock()
+            .unwrap()
+            .values()
+            .next_back()
+            .cloned())
+    }
+
+    fn save_validator_set(&self, set: &ValidatorSet) -> Result<(), StorageError> {
+        self.validator_sets
+            .lock()
+            .unwrap()
+            .insert(set.epoch, set.clone());
+        Ok(())
+    }
+
+    fn get_validator_set(&self, epoch: u64) -> Result<Option<ValidatorSet>, StorageError> {
+        Ok(self.validator_sets.lock().unwrap().get(&epoch).cloned())
+    }
+
+    fn get_latest_validator_set(&self) -> Result<Option<ValidatorSet>, StorageError> {
+        Ok(self
+            .validator_sets
+            .lock()
+            .unwrap()
+            .values()
+            .next_back()
+            .cloned())
+    }
+
+    fn get_validator_set_at(&self, view: View) -> Result<Option<ValidatorSet>, StorageError> {
+        Ok(self
+            .validator_sets
+            .lock()
+            .unwrap()
+            .values()
+            .rev()
+            .find(|set| set.view <= view)
+            .cloned())
+    }
+
+    fn save_receipts(&self, block_hash: &Hash, receipts: &[Receipt]) -> Result<(), StorageError> {
+        self.receipts
+            .lock()
+            .unwrap()
+            .insert(*block_hash, receipts.to_vec());
+        Ok(())
+    }
+
+    fn get_receipts(&self, block_hash: &Hash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        Ok(self.receipts.lock().unwrap().get(block_hash).cloned())
+    }
+
+    fn save_block_hash(&self, height: u64, block_hash: &Hash) -> Result<(), StorageError> {
+        let mut block_hashes = self.block_hashes.lock().unwrap();
+        block_hashes.insert(height, *block_hash);
+        if let Some(expired) = height.checked_sub(BLOCK_HASH_HISTORY) {
+            block_hashes.remove(&expired);
+        }
+        Ok(())
+    }
+
+    fn get_block_hash(&self, height: u64) -> Result<Option<Hash>, StorageError> {
+        Ok(self.block_hashes.lock().unwrap().get(&height).copied())
+    }
+
+    fn save_witness(&self, block_hash: &Hash, witness: &StateWitness) -> Result<(), StorageError> {
+        self.witnesses
+            .lock()
+            .unwrap()
+            .insert(*block_hash, witness.clone());
+        Ok(())
+    }
+
+    fn get_witness(&self, block_hash: &Hash) -> Result<Option<StateWitness>, StorageError> {
+        Ok(self.witnesses.lock().unwrap().get(block_hash).cloned())
+    }
+
+    fn save_state_diff(&self, block_hash: &Hash, diff: &StateDiff) -> Result<(), StorageError> {
+        self.state_diffs
+            .lock()
+            .unwrap()
+            .insert(*block_hash, diff.clone());
+        Ok(())
+    }
+
+    fn get_state_diff(&self, block_hash: &Hash) -> Result<Option<StateDiff>, StorageError> {
+        Ok(self.state_diffs.lock().unwrap().get(block_hash).cloned())
+    }
+
+    fn save_peer(&self, peer: &KnownPeer) -> Result<(), StorageError> {
+        self.peers
+            .lock()
+            .unwrap()
+            .insert(peer.peer_id.clone(), peer.clone());
+        Ok(())
+    }
+
+    fn get_peers(&self) -> Result<Vec<KnownPeer>, StorageError> {
+        Ok(self.peers.lock().unwrap().values().cloned().collect())
+    }
+
+    fn remove_peer(&self, peer_id: &str) -> Result<(), StorageError> {
+        self.peers.lock().unwrap().remove(peer_id);
+        Ok(())
+    }
+
+    fn save_pool_journal(&self, txs: &[Transaction]) -> Result<(), StorageError> {
+        *self.pool_journal.lock().unwrap() = txs.to_vec();
+        Ok(())
+    }
+
+    fn get_pool_journal(&self) -> Result<Vec<Transaction>, StorageError> {
+        Ok(self.pool_journal.lock().unwrap().clone())
+    }
+
+    fn append_vote_record(&self, record: &VoteRecord) -> Result<(), StorageError> {
+        self.vote_journal
+            .lock()
+            .unwrap()
+            .entry(record.view)
+            .or_default()
+            .push(record.clone());
+        Ok(())
+    }
+
+    fn get_vote_records(&self, from_view: View) -> Result<Vec<VoteRecord>, StorageError> {
+        Ok(self
+            .vote_journal
+            .lock()
+            .unwrap()
+            .range(from_view..)
+            .flat_map(|(_, records)| records.iter().cloned())
+            .collect())
+    }
+
+    fn prune_vote_records(&self, view: View) -> Result<(), StorageError> {
+        let mut journal = self.vote_journal.lock().unwrap();
+        *journal = journal.split_off(&view);
+        Ok(())
+    }
+
     fn get_account(&self, address: &Address) -> Result<Option<AccountInfo>, StorageError> {
         Ok(self.accounts.lock().unwrap().get(address).cloned())
     }
@@ -235,6 +860,42 @@ impl Storage for MemStorage {
         Ok(())
     }
 
+    fn get_storage_root(&self, address: &Address) -> Result<Hash, StorageError> {
+        Ok(self
+            .storage_roots
+            .lock()
+            .unwrap()
+            .get(address)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn save_storage_root(&self, address: &Address, root: &Hash) -> Result<(), StorageError> {
+        self.storage_roots.lock().unwrap().insert(*address, *root);
+        Ok(())
+    }
+
+    fn get_all_accounts(&self) -> Result<Vec<(Address, AccountInfo)>, StorageError> {
+        Ok(self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(a, i)| (*a, i.clone()))
+            .collect())
+    }
+
+    fn get_all_storage(&self, address: &Address) -> Result<Vec<(U256, U256)>, StorageError> {
+        Ok(self
+            .storage
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((a, _), _)| a == address)
+            .map(|((_, k), v)| (*k, *v))
+            .collect())
+    }
+
     fn get_smt_branch(&self, height: u8, node_key: &Hash) -> Result<Option<Vec<u8>>, StorageError> {
         Ok(self
             .smt_branches
@@ -267,11 +928,92 @@ impl Storage for MemStorage {
     }
 }
 
+const CONSENSUS_HEAD_KEY: &str = "consensus_head";
+const CONSENSUS_META_KEY: &str = "consensus_meta";
+
+/// Write `state` across the meta and consensus tables, rewriting only the rows that
+/// changed (most events touch a few validators, if any).
+pub(crate) fn write_consensus_state(
+    write_txn: &redb::WriteTransaction,
+    state: &ConsensusState,
+) -> Result<(), StorageError> {
+    let mut meta = write_txn.open_table(TABLE_META)?;
+    meta.insert(CONSENSUS_HEAD_KEY, bincode::serialize(&state.head())?)?;
+    let rest = ConsensusMeta {
+        committee: state.committee.clone(),
+        params: state.params.clone(),
+        proposals: state.proposals.clone(),
+    };
+    meta.insert(CONSENSUS_META_KEY, bincode::serialize(&rest)?)?;
+
+    let validators = state
+        .inactivity_scores
+        .keys()
+        .chain(state.missed_attestations.keys());
+    let mut scores = BTreeMap::new();
+    for validator in validators {
+        scores.insert(
+            bincode::serialize(validator)?,
+            bincode::serialize(&state.scores(validator))?,
+        );
+    }
+    sync_rows(&mut write_txn.open_table(TABLE_VALIDATOR_SCORES)?, scores)?;
+
+    let mut rewards = BTreeMap::new();
+    for (validator, amount) in state.rewards.iter().filter(|(_, a)| **a > U256::ZERO) {
+        rewards.insert(bincode::serialize(validator)?, bincode::serialize(amount)?);
+    }
+    sync_rows(&mut write_txn.open_table(TABLE_REWARDS)?, rewards)?;
+
+    let mut queues = write_txn.open_table(TABLE_VALIDATOR_QUEUES)?;
+    if read_queues(&queues)? != state.queues() {
+        queues.insert("pending", bincode::serialize(&state.pending_validators)?)?;
+        queues.insert("exiting", bincode::serialize(&state.exiting_validators)?)?;
+    }
+    Ok(())
+}
+
+// Make `table` hold exactly `rows`, leaving the unchanged ones alone
+fn sync_rows(
+    table: &mut redb::Table<&[u8], Vec<u8>>,
+    rows: BTreeMap<Vec<u8>, Vec<u8>>,
+) -> Result<(), StorageError> {
+    let existing = table
+        .iter()?
+        .map(|entry| entry.map(|(key, val)| (key.value().to_vec(), val.value())))
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    for key in existing.keys().filter(|key| !rows.contains_key(*key)) {
+        table.remove(key.as_slice())?;
+    }
+    for (key, val) in rows {
+        if existing.get(&key) != Some(&val) {
+            table.insert(key.as_slice(), val)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_queues(
+    table: &impl ReadableTable<&'static str, Vec<u8>>,
+) -> Result<ValidatorQueues, StorageError> {
+    let queue = |name: &str| -> Result<Vec<(PublicKey, View)>, StorageError> {
+        match table.get(name)? {
+            Some(val) => Ok(bincode::deserialize(&val.value())?),
+            None => Ok(vec![]),
+        }
+    };
+    Ok(ValidatorQueues {
+        pending: queue("pending")?,
+        exiting: queue("exiting")?,
+    })
+}
+
 // -----------------------------------------------------------------------------
 // Redb Storage
 // -----------------------------------------------------------------------------
 pub struct RedbStorage {
     db: Database,
+    journal_sync: JournalSync,
 }
 
 impl RedbStorage {
@@ -286,27 +1028,54 @@ impl RedbStorage {
         let write_txn = db.begin_write()?;
         {
             let _ = write_txn.open_table(TABLE_BLOCKS)?;
+            let _ = write_txn.open_table(TABLE_HEADERS)?;
+            let _ = write_txn.open_table(TABLE_BODIES)?;
             let _ = write_txn.open_table(TABLE_QCS)?;
+            let _ = write_txn.open_table(TABLE_FINALITY_QCS)?;
             let _ = write_txn.open_table(TABLE_META)?;
+            let _ = write_txn.open_table(TABLE_COMMITTEE_TRANSITIONS)?;
+            let _ = write_txn.open_table(TABLE_VALIDATOR_SETS)?;
+            let _ = write_txn.open_table(TABLE_RECEIPTS)?;
+            let _ = write_txn.open_table(TABLE_BLOCK_HASHES)?;
+            let _ = write_txn.open_table(TABLE_WITNESSES)?;
+            let _ = write_txn.open_table(TABLE_STATE_DIFFS)?;
+            let _ = write_txn.open_table(TABLE_PEERS)?;
+            let _ = write_txn.open_table(TABLE_VOTE_JOURNAL)?;
+            let _ = write_txn.open_table(TABLE_VALIDATOR_SCORES)?;
+            let _ = write_txn.open_table(TABLE_REWARDS)?;
+            let _ = write_txn.open_table(TABLE_VALIDATOR_QUEUES)?;
             let _ = write_txn.open_table(TABLE_ACCOUNTS)?;
             let _ = write_txn.open_table(TABLE_STORAGE)?;
             let _ = write_txn.open_table(TABLE_CODE)?;
+            let _ = write_txn.open_table(TABLE_STORAGE_ROOTS)?;
             let _ = write_txn.open_table(TABLE_SMT_LEAVES)?;
             let _ = write_txn.open_table(TABLE_SMT_BRANCHES)?;
         }
         write_txn.commit()?;
-        Ok(Self { db })
+        crate::migrations::migrate(&db)?;
+        Ok(Self {
+            db,
+            journal_sync: JournalSync::default(),
+        })
+    }
+
+    /// Sync vote journal appends as `sync` says (every append by default).
+    pub fn with_journal_sync(mut self, sync: JournalSync) -> Self {
+        self.journal_sync = sync;
+        self
     }
 }
 
 impl Storage for RedbStorage {
     fn save_block(&self, block: &Block) -> Result<(), StorageError> {
         let hash = crate::crypto::hash_data(block);
+        let (header, body) = block.clone().split();
         let write_txn = self.db.begin_write()?;
         {
-            let mut table = write_txn.open_table(TABLE_BLOCKS)?;
-            let val = bincode::serialize(block)?;
-            table.insert(&hash.0, val)?;
+            let mut headers = write_txn.open_table(TABLE_HEADERS)?;
+            headers.insert(&hash.0, bincode::serialize(&header)?)?;
+            let mut bodies = write_txn.open_table(TABLE_BODIES)?;
+            bodies.insert(&hash.0, bincode::serialize(&body)?)?;
         }
         write_txn.commit()?;
         Ok(())
@@ -314,8 +1083,17 @@ impl Storage for RedbStorage {
 
     fn get_block(&self, hash: &Hash) -> Result<Option<Block>, StorageError> {
         let read_txn = self.db.begin_read()?;
-        let table = read_txn.open_table(TABLE_BLOCKS)?;
-        if let Some(val) = table.get(&hash.0)? {
+        let headers = read_txn.open_table(TABLE_HEADERS)?;
+        let bodies = read_txn.open_table(TABLE_BODIES)?;
+        if let (Some(header), Some(body)) = (headers.get(&hash.0)?, bodies.get(&hash.0)?) {
+            let header: BlockHeader = bincode::deserialize(&header.value())?;
+            let body: BlockBody = bincode::deserialize(&body.value())?;
+            return Block::from_parts(header, body)
+                .map(Some)
+                .ok_or_else(|| StorageError::Custom("Block body does not match header".into()));
+        }
+        let legacy = read_txn.open_table(TABLE_BLOCKS)?;
+        if let Some(val) = legacy.get(&hash.0)? {
             let block = bincode::deserialize(&val.value())?;
             Ok(Some(block))
         } else {
@@ -323,6 +1101,21 @@ impl Storage for RedbStorage {
         }
     }
 
+    fn get_header(&self, hash: &Hash) -> Result<Option<BlockHeader>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let headers = read_txn.open_table(TABLE_HEADERS)?;
+        if let Some(val) = headers.get(&hash.0)? {
+            return Ok(Some(bincode::deserialize(&val.value())?));
+        }
+        let legacy = read_txn.open_table(TABLE_BLOCKS)?;
+        if let Some(val) = legacy.get(&hash.0)? {
+            let block: Block = bincode::deserialize(&val.value())?;
+            Ok(Some(block.header()))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn save_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError> {
         let write_txn = self.db.begin_write()?;
         {
@@ -345,28 +1138,454 @@ impl Storage for RedbStorage {
         }
     }
 
-    fn save_consensus_state(&self, state: &ConsensusState) -> Result<(), StorageError> {
+    fn save_finality_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError> {
         let write_txn = self.db.begin_write()?;
         {
-            let mut table = write_txn.open_table(TABLE_META)?;
-            let val = bincode::serialize(state)?;
-            table.insert("consensus_state", val)?;
+            let mut table = write_txn.open_table(TABLE_FINALITY_QCS)?;
+            let val = bincode::serialize(qc)?;
+            table.insert(qc.view, val)?;
         }
         write_txn.commit()?;
         Ok(())
     }
 
+    fn get_finality_qc(&self, view: View) -> Result<Option<QuorumCertificate>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_FINALITY_QCS)?;
+        if let Some(val) = table.get(view)? {
+            let qc = bincode::deserialize(&val.value())?;
+            Ok(Some(qc))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save_consensus_state(&self, state: &ConsensusState) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        write_consensus_state(&write_txn, state)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
     fn get_consensus_state(&self) -> Result<Option<ConsensusState>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let meta = read_txn.open_table(TABLE_META)?;
+        let Some(head) = meta.get(CONSENSUS_HEAD_KEY)? else {
+            return Ok(None);
+        };
+        let head: ConsensusHead = bincode::deserialize(&head.value())?;
+        let rest: ConsensusMeta = match meta.get(CONSENSUS_META_KEY)? {
+            Some(val) => bincode::deserialize(&val.value())?,
+            None => ConsensusMeta::default(),
+        };
+        let mut state = ConsensusState {
+            committee: rest.committee,
+            params: rest.params,
+            proposals: rest.proposals,
+            ..Default::default()
+        };
+        state.set_head(&head);
+
+        for entry in read_txn.open_table(TABLE_VALIDATOR_SCORES)?.iter()? {
+            let (key, val) = entry?;
+            state.set_scores(
+                &bincode::deserialize(key.value())?,
+                bincode::deserialize(&val.value())?,
+            );
+        }
+        for entry in read_txn.open_table(TABLE_REWARDS)?.iter()? {
+            let (key, val) = entry?;
+            state.rewards.insert(
+                bincode::deserialize(key.value())?,
+                bincode::deserialize(&val.value())?,
+            );
+        }
+        state.set_queues(read_queues(&read_txn.open_table(TABLE_VALIDATOR_QUEUES)?)?);
+        Ok(Some(state))
+    }
+
+    fn save_consensus_head(&self, head: &ConsensusHead) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_META)?;
+            table.insert(CONSENSUS_HEAD_KEY, bincode::serialize(head)?)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_consensus_head(&self) -> Result<Option<ConsensusHead>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_META)?;
+        match table.get(CONSENSUS_HEAD_KEY)? {
+            Some(val) => Ok(Some(bincode::deserialize(&val.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_validator_scores(
+        &self,
+        validator: &PublicKey,
+        scores: ValidatorScores,
+    ) -> Result<(), StorageError> {
+        let key = bincode::serialize(validator)?;
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_VALIDATOR_SCORES)?;
+            if scores == ValidatorScores::default() {
+                table.remove(key.as_slice())?;
+            } else {
+                table.insert(key.as_slice(), bincode::serialize(&scores)?)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_validator_scores(&self, validator: &PublicKey) -> Result<ValidatorScores, StorageError> {
+        let key = bincode::serialize(validator)?;
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_VALIDATOR_SCORES)?;
+        match table.get(key.as_slice())? {
+            Some(val) => Ok(bincode::deserialize(&val.value())?),
+            None => Ok(ValidatorScores::default()),
+        }
+    }
+
+    fn save_reward(&self, validator: &PublicKey, amount: U256) -> Result<(), StorageError> {
+        let key = bincode::serialize(validator)?;
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_REWARDS)?;
+            if amount == U256::ZERO {
+                table.remove(key.as_slice())?;
+            } else {
+                table.insert(key.as_slice(), bincode::serialize(&amount)?)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_reward(&self, validator: &PublicKey) -> Result<U256, StorageError> {
+        let key = bincode::serialize(validator)?;
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_REWARDS)?;
+        match table.get(key.as_slice())? {
+            Some(val) => Ok(bincode::deserialize(&val.value())?),
+            None => Ok(U256::ZERO),
+        }
+    }
+
+    fn save_validator_queues(&self, queues: &ValidatorQueues) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_VALIDATOR_QUEUES)?;
+            table.insert("pending", bincode::serialize(&queues.pending)?)?;
+            table.insert("exiting", bincode::serialize(&queues.exiting)?)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_validator_queues(&self) -> Result<ValidatorQueues, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        read_queues(&read_txn.open_table(TABLE_VALIDATOR_QUEUES)?)
+    }
+
+    fn save_chain_head(&self, head: &ChainHead) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_META)?;
+            let val = bincode::serialize(head)?;
+            table.insert("chain_head", val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_chain_head(&self) -> Result<Option<ChainHead>, StorageError> {
         let read_txn = self.db.begin_read()?;
         let table = read_txn.open_table(TABLE_META)?;
-        if let Some(val) = table.get("consensus_state")? {
-            let state = bincode::deserialize(&val.value())?;
-            Ok(Some(state))
+        if let Some(val) = table.get("chain_head")? {
+            let head = bincode::deserialize(&val.value())?;
+            Ok(Some(head))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save_committee_transition(
+        &self,
+        transition: &CommitteeTransition,
+    ) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_COMMITTEE_TRANSITIONS)?;
+            let val = bincode::serialize(transition)?;
+            table.insert(transition.epoch, val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_committee_transition(
+        &self,
+        epoch: u64,
+    ) -> Result<Option<CommitteeTransition>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_COMMITTEE_TRANSITIONS)?;
+        if let Some(val) = table.get(epoch)? {
+            let transition = bincode::deserialize(&val.value())?;
+            Ok(Some(transition))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_latest_committee_transition(&self) -> Result<Option<CommitteeTransition>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_COMMITTEE_TRANSITIONS)?;
+        if let Some(entry) = table.range::<u64>(..)?.next_back() {
+            let (_, val) = entry?;
+            let transition = bincode::deserialize(&val.value())?;
+            Ok(Some(transition))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save_validator_set(&self, set: &ValidatorSet) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_VALIDATOR_SETS)?;
+            let val = bincode::serialize(set)?;
+            table.insert(set.epoch, val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_validator_set(&self, epoch: u64) -> Result<Option<ValidatorSet>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_VALIDATOR_SETS)?;
+        if let Some(val) = table.get(epoch)? {
+            let set = bincode::deserialize(&val.value())?;
+            Ok(Some(set))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_latest_validator_set(&self) -> Result<Option<ValidatorSet>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_VALIDATOR_SETS)?;
+        if let Some(entry) = table.range::<u64>(..)?.next_back() {
+            let (_, val) = entry?;
+            let set = bincode::deserialize(&val.value())?;
+            Ok(Some(set))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_validator_set_at(&self, view: View) -> Result<Option<ValidatorSet>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_VALIDATOR_SETS)?;
+        // Epochs start at increasing views: walk back from the latest
+        for entry in table.range::<u64>(..)?.rev() {
+            let (_, val) = entry?;
+            let set: ValidatorSet = bincode::deserialize(&val.value())?;
+            if set.view <= view {
+                return Ok(Some(set));
+            }
+        }
+        Ok(None)
+    }
+
+    fn save_receipts(&self, block_hash: &Hash, receipts: &[Receipt]) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_RECEIPTS)?;
+            let val = bincode::serialize(receipts)?;
+            table.insert(&block_hash.0, val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_receipts(&self, block_hash: &Hash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_RECEIPTS)?;
+        if let Some(val) = table.get(&block_hash.0)? {
+            let receipts = bincode::deserialize(&val.value())?;
+            Ok(Some(receipts))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save_block_hash(&self, height: u64, block_hash: &Hash) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_BLOCK_HASHES)?;
+            let val = bincode::serialize(block_hash)?;
+            table.insert(height, val)?;
+            if let Some(expired) = height.checked_sub(BLOCK_HASH_HISTORY) {
+                table.remove(expired)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_block_hash(&self, height: u64) -> Result<Option<Hash>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_BLOCK_HASHES)?;
+        if let Some(val) = table.get(height)? {
+            let hash = bincode::deserialize(&val.value())?;
+            Ok(Some(hash))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save_witness(&self, block_hash: &Hash, witness: &StateWitness) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_WITNESSES)?;
+            let val = bincode::serialize(witness)?;
+            table.insert(&block_hash.0, val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_witness(&self, block_hash: &Hash) -> Result<Option<StateWitness>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_WITNESSES)?;
+        if let Some(val) = table.get(&block_hash.0)? {
+            let witness = bincode::deserialize(&val.value())?;
+            Ok(Some(witness))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save_state_diff(&self, block_hash: &Hash, diff: &StateDiff) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_STATE_DIFFS)?;
+            let val = bincode::serialize(diff)?;
+            table.insert(&block_hash.0, val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_state_diff(&self, block_hash: &Hash) -> Result<Option<StateDiff>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_STATE_DIFFS)?;
+        if let Some(val) = table.get(&block_hash.0)? {
+            let diff = bincode::deserialize(&val.value())?;
+            Ok(Some(diff))
         } else {
             Ok(None)
         }
     }
 
+    fn save_peer(&self, peer: &KnownPeer) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_PEERS)?;
+            let val = bincode::serialize(peer)?;
+            table.insert(peer.peer_id.as_str(), val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_peers(&self) -> Result<Vec<KnownPeer>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_PEERS)?;
+        let mut peers = Vec::new();
+        for entry in table.range::<&str>(..)? {
+            let (_, val) = entry?;
+            peers.push(bincode::deserialize(&val.value())?);
+        }
+        Ok(peers)
+    }
+
+    fn remove_peer(&self, peer_id: &str) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_PEERS)?;
+            table.remove(peer_id)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn save_pool_journal(&self, txs: &[Transaction]) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_META)?;
+            let val = bincode::serialize(txs)?;
+            table.insert("pool_journal", val)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_pool_journal(&self) -> Result<Vec<Transaction>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_META)?;
+        if let Some(val) = table.get("pool_journal")? {
+            Ok(bincode::deserialize(&val.value())?)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn append_vote_record(&self, record: &VoteRecord) -> Result<(), StorageError> {
+        let mut write_txn = self.db.begin_write()?;
+        if self.journal_sync == JournalSync::Deferred {
+            write_txn.set_durability(Durability::None);
+        }
+        {
+            let mut table = write_txn.open_table(TABLE_VOTE_JOURNAL)?;
+            let mut records: Vec<VoteRecord> = match table.get(record.view)? {
+                Some(val) => bincode::deserialize(&val.value())?,
+                None => vec![],
+            };
+            records.push(record.clone());
+            table.insert(record.view, bincode::serialize(&records)?)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_vote_records(&self, from_view: View) -> Result<Vec<VoteRecord>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_VOTE_JOURNAL)?;
+        let mut records = vec![];
+        for entry in table.range(from_view..)? {
+            let (_, val) = entry?;
+            records.extend(bincode::deserialize::<Vec<VoteRecord>>(&val.value())?);
+        }
+        Ok(records)
+    }
+
+    fn prune_vote_records(&self, view: View) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_VOTE_JOURNAL)?;
+            table.retain(|key, _| key >= view)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
     fn get_account(&self, address: &Address) -> Result<Option<AccountInfo>, StorageError> {
         let read_txn = self.db.begin_read()?;
         let table = read_txn.open_table(TABLE_ACCOUNTS)?;
@@ -450,6 +1669,55 @@ impl Storage for RedbStorage {
         Ok(())
     }
 
+    fn get_storage_root(&self, address: &Address) -> Result<Hash, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_STORAGE_ROOTS)?;
+        if let Some(val) = table.get(&*address.0)? {
+            Ok(bincode::deserialize(&val.value())?)
+        } else {
+            Ok(Hash::default())
+        }
+    }
+
+    fn save_storage_root(&self, address: &Address, root: &Hash) -> Result<(), StorageError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TABLE_STORAGE_ROOTS)?;
+            table.insert(&*address.0, bincode::serialize(root)?)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_all_accounts(&self) -> Result<Vec<(Address, AccountInfo)>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_ACCOUNTS)?;
+        let mut accounts = Vec::new();
+        for entry in table.range::<&[u8; 20]>(..)? {
+            let (key, val) = entry?;
+            let info = bincode::deserialize(&val.value())?;
+            accounts.push((Address::from_slice(key.value()), info));
+        }
+        Ok(accounts)
+    }
+
+    fn get_all_storage(&self, address: &Address) -> Result<Vec<(U256, U256)>, StorageError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TABLE_STORAGE)?;
+        let mut slots = Vec::new();
+        // Keys are Address (20 bytes) ++ Index (32 bytes), so an account's slots are contiguous
+        for entry in table.range::<&[u8]>(address.as_slice()..)? {
+            let (key, val) = entry?;
+            let key = key.value();
+            if !key.starts_with(address.as_slice()) {
+                break;
+            }
+            let index = U256::from_be_slice(&key[20..]);
+            slots.push((index, bincode::deserialize(&val.value())?));
+        }
+        Ok(slots)
+    }
+
     fn get_smt_branch(&self, height: u8, node_key: &Hash) -> Result<Option<Vec<u8>>, StorageError> {
         let read_txn = self.db.begin_read()?;
         let table = read_txn.open_table(TABLE_SMT_BRANCHES)?;
@@ -510,9 +1778,27 @@ pub struct StateOverlay {
     // Overlay Cache
     accounts: Arc<Mutex<HashMap<Address, AccountInfo>>>,
     storage: Arc<Mutex<HashMap<(Address, U256), U256>>>,
+    storage_roots: Arc<Mutex<HashMap<Address, Hash>>>,
     code: Arc<Mutex<HashMap<Hash, Bytes>>>,
     smt_leaves: Arc<Mutex<HashMap<Hash, Vec<u8>>>>,
     smt_branches: Arc<Mutex<SmtBranchMap>>,
+    // Consensus state as written by execution, copied from `inner` on the first write
+    consensus_state: Arc<Mutex<Option<ConsensusState>>>,
+    receipts: Arc<Mutex<HashMap<Hash, Vec<Receipt>>>>,
+    block_hashes: Arc<Mutex<HashMap<u64, Hash>>>,
+    // Reads that fell through to `inner` (the pre-state), when recording a witness
+    reads: Option<Arc<Mutex<WitnessReads>>>,
+}
+
+#[derive(Default)]
+struct WitnessReads {
+    accounts: HashMap<Address, Option<AccountInfo>>,
+    storage: HashMap<(Address, U256), U256>,
+    storage_roots: HashMap<Address, Hash>,
+    code: HashMap<Hash, Bytes>,
+    smt_branches: SmtBranchMap,
+    smt_leaves: HashMap<Hash, Vec<u8>>,
+    blocks: HashMap<Hash, Block>,
 }
 
 impl StateOverlay {
@@ -521,20 +1807,140 @@ impl StateOverlay {
             inner,
             accounts: Arc::new(Mutex::new(HashMap::new())),
             storage: Arc::new(Mutex::new(HashMap::new())),
+            storage_roots: Arc::new(Mutex::new(HashMap::new())),
             code: Arc::new(Mutex::new(HashMap::new())),
             smt_leaves: Arc::new(Mutex::new(HashMap::new())),
             smt_branches: Arc::new(Mutex::new(HashMap::new())),
+            consensus_state: Arc::new(Mutex::new(None)),
+            receipts: Arc::new(Mutex::new(HashMap::new())),
+            block_hashes: Arc::new(Mutex::new(HashMap::new())),
+            reads: None,
         }
     }
-}
 
-impl Storage for StateOverlay {
-    fn save_block(&self, _block: &Block) -> Result<(), StorageError> {
-        // We typically don't need to save blocks in overlay during execution,
-        // but if validation needs to save it to be read back?
-        // SimplexState::validate_and_store_block saves it.
-        // But for validation we might just keep it in memory?
-        // Let's pass through to inner? NO. Inner is persistent.
+    /// An overlay that also records the pre-state it reads, see `witness`.
+    pub fn recording(inner: Arc<dyn Storage>) -> Self {
+        Self {
+            reads: Some(Arc::new(Mutex::new(WitnessReads::default()))),
+            ..Self::new(inner)
+        }
+    }
+
+    /// Everything read from the underlying storage so far (empty unless recording).
+    pub fn witness(&self) -> StateWitness {
+        let Some(reads) = &self.reads else {
+            return StateWitness::default();
+        };
+        let reads = reads.lock().unwrap();
+        StateWitness {
+            accounts: reads
+                .accounts
+                .iter()
+                .map(|(a, i)| (*a, i.clone()))
+                .collect(),
+            storage: reads
+                .storage
+                .iter()
+                .map(|((a, k), v)| (*a, *k, *v))
+                .collect(),
+            storage_roots: reads.storage_roots.iter().map(|(a, r)| (*a, *r)).collect(),
+            code: reads.code.iter().map(|(h, c)| (*h, c.clone())).collect(),
+            smt_branches: reads
+                .smt_branches
+                .iter()
+                .map(|((height, key), node)| (*height, *key, node.clone()))
+                .collect(),
+            smt_leaves: reads
+                .smt_leaves
+                .iter()
+                .map(|(h, node)| (*h, node.clone()))
+                .collect(),
+            blocks: reads.blocks.values().cloned().collect(),
+        }
+    }
+
+    /// Write everything written to this overlay through to the underlying storage, as if
+    /// the execution had run on it directly. The consensus head (the driver's pointers)
+    /// is left as it is there.
+    pub fn flush(&self) -> Result<(), StorageError> {
+        for (hash, code) in self.code.lock().unwrap().iter() {
+            self.inner.save_code(hash, code)?;
+        }
+        for (address, info) in self.accounts.lock().unwrap().iter() {
+            self.inner.save_account(address, info)?;
+        }
+        for ((address, index), value) in self.storage.lock().unwrap().iter() {
+            self.inner.save_storage(address, index, value)?;
+        }
+        for (address, root) in self.storage_roots.lock().unwrap().iter() {
+            self.inner.save_storage_root(address, root)?;
+        }
+        for ((height, node_key), node) in self.smt_branches.lock().unwrap().iter() {
+            self.inner.save_smt_branch(*height, node_key, node)?;
+        }
+        for (hash, node) in self.smt_leaves.lock().unwrap().iter() {
+            self.inner.save_smt_leaf(hash, node)?;
+        }
+        for (block_hash, receipts) in self.receipts.lock().unwrap().iter() {
+            self.inner.save_receipts(block_hash, receipts)?;
+        }
+        for (height, block_hash) in self.block_hashes.lock().unwrap().iter() {
+            self.inner.save_block_hash(*height, block_hash)?;
+        }
+        if let Some(mut state) = self.consensus_state.lock().unwrap().clone() {
+            if let Some(head) = self.inner.get_consensus_head()? {
+                state.set_head(&head);
+            }
+            self.inner.save_consensus_state(&state)?;
+        }
+        Ok(())
+    }
+
+    fn update_consensus_state(
+        &self,
+        update: impl FnOnce(&mut ConsensusState),
+    ) -> Result<(), StorageError> {
+        let mut buffered = self.consensus_state.lock().unwrap();
+        if buffered.is_none() {
+            *buffered = self.inner.get_consensus_state()?;
+        }
+        update(buffered.get_or_insert_with(Default::default));
+        Ok(())
+    }
+
+    fn record(&self, f: impl FnOnce(&mut WitnessReads)) {
+        if let Some(reads) = &self.reads {
+            f(&mut reads.lock().unwrap());
+        }
+    }
+
+    /// Record the pre-state of an account together with its storage root: the state tree
+    /// only commits to the two folded into one leaf.
+    fn record_account(&self, address: &Address) -> Result<(), StorageError> {
+        let Some(reads) = &self.reads else {
+            return Ok(());
+        };
+        if reads.lock().unwrap().accounts.contains_key(address) {
+            return Ok(());
+        }
+        let info = self.inner.get_account(address)?;
+        let root = self.inner.get_storage_root(address)?;
+        let mut reads = reads.lock().unwrap();
+        reads.accounts.insert(*address, info);
+        if root != Hash::default() {
+            reads.storage_roots.insert(*address, root);
+        }
+        Ok(())
+    }
+}
+
+impl Storage for StateOverlay {
+    fn save_block(&self, _block: &Block) -> Result<(), StorageError> {
+        // We typically don't need to save blocks in overlay during execution,
+        // but if validation needs to save it to be read back?
+        // SimplexState::validate_and_store_block saves it.
+        // But for validation we might just keep it in memory?
+        // Let's pass through to inner? NO. Inner is persistent.
         // We should PROHIBIT saving blocks to persistent DB via overlay?
         // OR we just use a MemStorage for blocks in Overlay?
         // For this refactor, we are mostly concerned with STATE (Accounts/Storage).
@@ -546,7 +1952,17 @@ impl Storage for StateOverlay {
     }
 
     fn get_block(&self, hash: &Hash) -> Result<Option<Block>, StorageError> {
-        self.inner.get_block(hash)
+        let block = self.inner.get_block(hash)?;
+        if let Some(block) = &block {
+            self.record(|r| {
+                r.blocks.insert(*hash, block.clone());
+            });
+        }
+        Ok(block)
+    }
+
+    fn get_header(&self, hash: &Hash) -> Result<Option<BlockHeader>, StorageError> {
+        self.inner.get_header(hash)
     }
 
     fn save_qc(&self, _qc: &QuorumCertificate) -> Result<(), StorageError> {
@@ -558,19 +1974,207 @@ impl Storage for StateOverlay {
         self.inner.get_qc(view)
     }
 
-    fn save_consensus_state(&self, _state: &ConsensusState) -> Result<(), StorageError> {
+    fn save_finality_qc(&self, _qc: &QuorumCertificate) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_finality_qc(&self, view: View) -> Result<Option<QuorumCertificate>, StorageError> {
+        self.inner.get_finality_qc(view)
+    }
+
+    fn save_consensus_state(&self, state: &ConsensusState) -> Result<(), StorageError> {
+        *self.consensus_state.lock().unwrap() = Some(state.clone());
         Ok(())
     }
 
     fn get_consensus_state(&self) -> Result<Option<ConsensusState>, StorageError> {
+        if let Some(state) = self.consensus_state.lock().unwrap().as_ref() {
+            return Ok(Some(state.clone()));
+        }
         self.inner.get_consensus_state()
     }
 
+    fn save_consensus_head(&self, _head: &ConsensusHead) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_consensus_head(&self) -> Result<Option<ConsensusHead>, StorageError> {
+        self.inner.get_consensus_head()
+    }
+
+    fn save_validator_scores(
+        &self,
+        validator: &PublicKey,
+        scores: ValidatorScores,
+    ) -> Result<(), StorageError> {
+        self.update_consensus_state(|state| state.set_scores(validator, scores))
+    }
+
+    fn get_validator_scores(&self, validator: &PublicKey) -> Result<ValidatorScores, StorageError> {
+        if let Some(state) = self.consensus_state.lock().unwrap().as_ref() {
+            return Ok(state.scores(validator));
+        }
+        self.inner.get_validator_scores(validator)
+    }
+
+    fn save_reward(&self, validator: &PublicKey, amount: U256) -> Result<(), StorageError> {
+        self.update_consensus_state(|state| {
+            if amount == U256::ZERO {
+                state.rewards.remove(validator);
+            } else {
+                state.rewards.insert(validator.clone(), amount);
+            }
+        })
+    }
+
+    fn get_reward(&self, validator: &PublicKey) -> Result<U256, StorageError> {
+        if let Some(state) = self.consensus_state.lock().unwrap().as_ref() {
+            return Ok(state.rewards.get(validator).copied().unwrap_or(U256::ZERO));
+        }
+        self.inner.get_reward(validator)
+    }
+
+    fn save_validator_queues(&self, queues: &ValidatorQueues) -> Result<(), StorageError> {
+        self.update_consensus_state(|state| state.set_queues(queues.clone()))
+    }
+
+    fn get_validator_queues(&self) -> Result<ValidatorQueues, StorageError> {
+        if let Some(state) = self.consensus_state.lock().unwrap().as_ref() {
+            return Ok(state.queues());
+        }
+        self.inner.get_validator_queues()
+    }
+
+    fn save_chain_head(&self, _head: &ChainHead) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_chain_head(&self) -> Result<Option<ChainHead>, StorageError> {
+        self.inner.get_chain_head()
+    }
+
+    fn save_committee_transition(
+        &self,
+        _transition: &CommitteeTransition,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_committee_transition(
+        &self,
+        epoch: u64,
+    ) -> Result<Option<CommitteeTransition>, StorageError> {
+        self.inner.get_committee_transition(epoch)
+    }
+
+    fn get_latest_committee_transition(&self) -> Result<Option<CommitteeTransition>, StorageError> {
+        self.inner.get_latest_committee_transition()
+    }
+
+    fn save_validator_set(&self, _set: &ValidatorSet) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_validator_set(&self, epoch: u64) -> Result<Option<ValidatorSet>, StorageError> {
+        self.inner.get_validator_set(epoch)
+    }
+
+    fn get_latest_validator_set(&self) -> Result<Option<ValidatorSet>, StorageError> {
+        self.inner.get_latest_validator_set()
+    }
+
+    fn get_validator_set_at(&self, view: View) -> Result<Option<ValidatorSet>, StorageError> {
+        self.inner.get_validator_set_at(view)
+    }
+
+    fn save_receipts(&self, block_hash: &Hash, receipts: &[Receipt]) -> Result<(), StorageError> {
+        self.receipts
+            .lock()
+            .unwrap()
+            .insert(*block_hash, receipts.to_vec());
+        Ok(())
+    }
+
+    fn get_receipts(&self, block_hash: &Hash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        if let Some(receipts) = self.receipts.lock().unwrap().get(block_hash) {
+            return Ok(Some(receipts.clone()));
+        }
+        self.inner.get_receipts(block_hash)
+    }
+
+    fn save_block_hash(&self, height: u64, block_hash: &Hash) -> Result<(), StorageError> {
+        self.block_hashes
+            .lock()
+            .unwrap()
+            .insert(height, *block_hash);
+        Ok(())
+    }
+
+    fn get_block_hash(&self, height: u64) -> Result<Option<Hash>, StorageError> {
+        if let Some(block_hash) = self.block_hashes.lock().unwrap().get(&height) {
+            return Ok(Some(*block_hash));
+        }
+        self.inner.get_block_hash(height)
+    }
+
+    fn save_witness(
+        &self,
+        _block_hash: &Hash,
+        _witness: &StateWitness,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_witness(&self, block_hash: &Hash) -> Result<Option<StateWitness>, StorageError> {
+        self.inner.get_witness(block_hash)
+    }
+
+    fn save_state_diff(&self, _block_hash: &Hash, _diff: &StateDiff) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_state_diff(&self, block_hash: &Hash) -> Result<Option<StateDiff>, StorageError> {
+        self.inner.get_state_diff(block_hash)
+    }
+
+    fn save_peer(&self, _peer: &KnownPeer) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_peers(&self) -> Result<Vec<KnownPeer>, StorageError> {
+        self.inner.get_peers()
+    }
+
+    fn remove_peer(&self, _peer_id: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn save_pool_journal(&self, _txs: &[Transaction]) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_pool_journal(&self) -> Result<Vec<Transaction>, StorageError> {
+        self.inner.get_pool_journal()
+    }
+
+    fn append_vote_record(&self, _record: &VoteRecord) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn get_vote_records(&self, from_view: View) -> Result<Vec<VoteRecord>, StorageError> {
+        self.inner.get_vote_records(from_view)
+    }
+
+    fn prune_vote_records(&self, _view: View) -> Result<(), StorageError> {
+        Ok(())
+    }
+
     // EVM State - Check Overlay First
     fn get_account(&self, address: &Address) -> Result<Option<AccountInfo>, StorageError> {
         if let Some(info) = self.accounts.lock().unwrap().get(address) {
             return Ok(Some(info.clone()));
         }
+        self.record_account(address)?;
         self.inner.get_account(address)
     }
 
@@ -583,7 +2187,13 @@ impl Storage for StateOverlay {
         if let Some(code) = self.code.lock().unwrap().get(hash) {
             return Ok(Some(code.clone()));
         }
-        self.inner.get_code(hash)
+        let code = self.inner.get_code(hash)?;
+        if let Some(code) = &code {
+            self.record(|r| {
+                r.code.insert(*hash, code.clone());
+            });
+        }
+        Ok(code)
     }
 
     fn save_code(&self, hash: &Hash, code: &Bytes) -> Result<(), StorageError> {
@@ -595,7 +2205,13 @@ impl Storage for StateOverlay {
         if let Some(val) = self.storage.lock().unwrap().get(&(*address, *index)) {
             return Ok(*val);
         }
-        self.inner.get_storage(address, index)
+        let value = self.inner.get_storage(address, index)?;
+        // Slots are proven against the account's storage root
+        self.record_account(address)?;
+        self.record(|r| {
+            r.storage.insert((*address, *index), value);
+        });
+        Ok(value)
     }
 
     fn save_storage(
@@ -611,11 +2227,50 @@ impl Storage for StateOverlay {
         Ok(())
     }
 
+    fn get_storage_root(&self, address: &Address) -> Result<Hash, StorageError> {
+        if let Some(root) = self.storage_roots.lock().unwrap().get(address) {
+            return Ok(*root);
+        }
+        self.record_account(address)?;
+        self.inner.get_storage_root(address)
+    }
+
+    fn save_storage_root(&self, address: &Address, root: &Hash) -> Result<(), StorageError> {
+        self.storage_roots.lock().unwrap().insert(*address, *root);
+        Ok(())
+    }
+
+    fn get_all_accounts(&self) -> Result<Vec<(Address, AccountInfo)>, StorageError> {
+        let mut accounts: HashMap<Address, AccountInfo> =
+            self.inner.get_all_accounts()?.into_iter().collect();
+        for (address, info) in self.accounts.lock().unwrap().iter() {
+            accounts.insert(*address, info.clone());
+        }
+        Ok(accounts.into_iter().collect())
+    }
+
+    fn get_all_storage(&self, address: &Address) -> Result<Vec<(U256, U256)>, StorageError> {
+        let mut slots: HashMap<U256, U256> =
+            self.inner.get_all_storage(address)?.into_iter().collect();
+        for ((a, index), value) in self.storage.lock().unwrap().iter() {
+            if a == address {
+                slots.insert(*index, *value);
+            }
+        }
+        Ok(slots.into_iter().collect())
+    }
+
     fn get_smt_branch(&self, height: u8, node_key: &Hash) -> Result<Option<Vec<u8>>, StorageError> {
         if let Some(node) = self.smt_branches.lock().unwrap().get(&(height, *node_key)) {
             return Ok(Some(node.clone()));
         }
-        self.inner.get_smt_branch(height, node_key)
+        let node = self.inner.get_smt_branch(height, node_key)?;
+        if let Some(node) = &node {
+            self.record(|r| {
+                r.smt_branches.insert((height, *node_key), node.clone());
+            });
+        }
+        Ok(node)
     }
 
     fn save_smt_branch(
@@ -635,7 +2290,13 @@ impl Storage for StateOverlay {
         if let Some(node) = self.smt_leaves.lock().unwrap().get(hash) {
             return Ok(Some(node.clone()));
         }
-        self.inner.get_smt_leaf(hash)
+        let node = self.inner.get_smt_leaf(hash)?;
+        if let Some(node) = &node {
+            self.record(|r| {
+                r.smt_leaves.insert(*hash, node.clone());
+            });
+        }
+        Ok(node)
     }
 
     fn save_smt_leaf(&self, hash: &Hash, node: &[u8]) -> Result<(), StorageError> {
@@ -643,3 +2304,306 @@ impl Storage for StateOverlay {
         Ok(())
     }
 }
+
+// -----------------------------------------------------------------------------
+// Cached Storage (hot accounts, slots and code in front of the database)
+// -----------------------------------------------------------------------------
+
+/// `Storage` that serves accounts, storage slots and code from a `StateCache`, filling it
+/// on misses. Writes go to the inner storage and replace the cached entry; everything else
+/// is passed through.
+pub struct CachedStorage {
+    inner: Arc<dyn Storage>,
+    cache: Arc<StateCache>,
+}
+
+impl CachedStorage {
+    pub fn new(inner: Arc<dyn Storage>, cache: Arc<StateCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+impl Storage for CachedStorage {
+    fn save_block(&self, block: &Block) -> Result<(), StorageError> {
+        self.inner.save_block(block)
+    }
+
+    fn get_block(&self, hash: &Hash) -> Result<Option<Block>, StorageError> {
+        self.inner.get_block(hash)
+    }
+
+    fn get_header(&self, hash: &Hash) -> Result<Option<BlockHeader>, StorageError> {
+        self.inner.get_header(hash)
+    }
+
+    fn save_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError> {
+        self.inner.save_qc(qc)
+    }
+
+    fn get_qc(&self, view: View) -> Result<Option<QuorumCertificate>, StorageError> {
+        self.inner.get_qc(view)
+    }
+
+    fn save_finality_qc(&self, qc: &QuorumCertificate) -> Result<(), StorageError> {
+        self.inner.save_finality_qc(qc)
+    }
+
+    fn get_finality_qc(&self, view: View) -> Result<Option<QuorumCertificate>, StorageError> {
+        self.inner.get_finality_qc(view)
+    }
+
+    fn save_consensus_state(&self, state: &ConsensusState) -> Result<(), StorageError> {
+        self.inner.save_consensus_state(state)
+    }
+
+    fn get_consensus_state(&self) -> Result<Option<ConsensusState>, StorageError> {
+        self.inner.get_consensus_state()
+    }
+
+    fn save_consensus_head(&self, head: &ConsensusHead) -> Result<(), StorageError> {
+        self.inner.save_consensus_head(head)
+    }
+
+    fn get_consensus_head(&self) -> Result<Option<ConsensusHead>, StorageError> {
+        self.inner.get_consensus_head()
+    }
+
+    fn save_validator_scores(
+        &self,
+        validator: &PublicKey,
+        scores: ValidatorScores,
+    ) -> Result<(), StorageError> {
+        self.inner.save_validator_scores(validator, scores)
+    }
+
+    fn get_validator_scores(&self, validator: &PublicKey) -> Result<ValidatorScores, StorageError> {
+        self.inner.get_validator_scores(validator)
+    }
+
+    fn save_reward(&self, validator: &PublicKey, amount: U256) -> Result<(), StorageError> {
+        self.inner.save_reward(validator, amount)
+    }
+
+    fn get_reward(&self, validator: &PublicKey) -> Result<U256, StorageError> {
+        self.inner.get_reward(validator)
+    }
+
+    fn save_validator_queues(&self, queues: &ValidatorQueues) -> Result<(), StorageError> {
+        self.inner.save_validator_queues(queues)
+    }
+
+    fn get_validator_queues(&self) -> Result<ValidatorQueues, StorageError> {
+        self.inner.get_validator_queues()
+    }
+
+    fn save_chain_head(&self, head: &ChainHead) -> Result<(), StorageError> {
+        self.inner.save_chain_head(head)
+    }
+
+    fn get_chain_head(&self) -> Result<Option<ChainHead>, StorageError> {
+        self.inner.get_chain_head()
+    }
+
+    fn save_committee_transition(
+        &self,
+        transition: &CommitteeTransition,
+    ) -> Result<(), StorageError> {
+        self.inner.save_committee_transition(transition)
+    }
+
+    fn get_committee_transition(
+        &self,
+        epoch: u64,
+    ) -> Result<Option<CommitteeTransition>, StorageError> {
+        self.inner.get_committee_transition(epoch)
+    }
+
+    fn get_latest_committee_transition(&self) -> Result<Option<CommitteeTransition>, StorageError> {
+        self.inner.get_latest_committee_transition()
+    }
+
+    fn save_validator_set(&self, set: &ValidatorSet) -> Result<(), StorageError> {
+        self.inner.save_validator_set(set)
+    }
+
+    fn get_validator_set(&self, epoch: u64) -> Result<Option<ValidatorSet>, StorageError> {
+        self.inner.get_validator_set(epoch)
+    }
+
+    fn get_latest_validator_set(&self) -> Result<Option<ValidatorSet>, StorageError> {
+        self.inner.get_latest_validator_set()
+    }
+
+    fn get_validator_set_at(&self, view: View) -> Result<Option<ValidatorSet>, StorageError> {
+        self.inner.get_validator_set_at(view)
+    }
+
+    fn save_receipts(&self, block_hash: &Hash, receipts: &[Receipt]) -> Result<(), StorageError> {
+        self.inner.save_receipts(block_hash, receipts)
+    }
+
+    fn get_receipts(&self, block_hash: &Hash) -> Result<Option<Vec<Receipt>>, StorageError> {
+        self.inner.get_receipts(block_hash)
+    }
+
+    fn save_block_hash(&self, height: u64, block_hash: &Hash) -> Result<(), StorageError> {
+        self.inner.save_block_hash(height, block_hash)
+    }
+
+    fn get_block_hash(&self, height: u64) -> Result<Option<Hash>, StorageError> {
+        self.inner.get_block_hash(height)
+    }
+
+    fn save_witness(&self, block_hash: &Hash, witness: &StateWitness) -> Result<(), StorageError> {
+        self.inner.save_witness(block_hash, witness)
+    }
+
+    fn get_witness(&self, block_hash: &Hash) -> Result<Option<StateWitness>, StorageError> {
+        self.inner.get_witness(block_hash)
+    }
+
+    fn save_state_diff(&self, block_hash: &Hash, diff: &StateDiff) -> Result<(), StorageError> {
+        self.inner.save_state_diff(block_hash, diff)
+    }
+
+    fn get_state_diff(&self, block_hash: &Hash) -> Result<Option<StateDiff>, StorageError> {
+        self.inner.get_state_diff(block_hash)
+    }
+
+    fn save_peer(&self, peer: &KnownPeer) -> Result<(), StorageError> {
+        self.inner.save_peer(peer)
+    }
+
+    fn get_peers(&self) -> Result<Vec<KnownPeer>, StorageError> {
+        self.inner.get_peers()
+    }
+
+    fn remove_peer(&self, peer_id: &str) -> Result<(), StorageError> {
+        self.inner.remove_peer(peer_id)
+    }
+
+    fn save_pool_journal(&self, txs: &[Transaction]) -> Result<(), StorageError> {
+        self.inner.save_pool_journal(txs)
+    }
+
+    fn get_pool_journal(&self) -> Result<Vec<Transaction>, StorageError> {
+        self.inner.get_pool_journal()
+    }
+
+    fn append_vote_record(&self, record: &VoteRecord) -> Result<(), StorageError> {
+        self.inner.append_vote_record(record)
+    }
+
+    fn get_vote_records(&self, from_view: View) -> Result<Vec<VoteRecord>, StorageError> {
+        self.inner.get_vote_records(from_view)
+    }
+
+    fn prune_vote_records(&self, view: View) -> Result<(), StorageError> {
+        self.inner.prune_vote_records(view)
+    }
+
+    // EVM State - Check Cache First
+    fn get_account(&self, address: &Address) -> Result<Option<AccountInfo>, StorageError> {
+        let key = CacheKey::Account(*address);
+        let writes = match self.cache.get(&key) {
+            Ok(CacheValue::Account(info)) => return Ok(info),
+            Ok(_) => unreachable!("account key holds an account"),
+            Err(writes) => writes,
+        };
+        let info = self.inner.get_account(address)?;
+        self.cache
+            .fill(key, CacheValue::Account(info.clone()), writes);
+        Ok(info)
+    }
+
+    fn save_account(&self, address: &Address, info: &AccountInfo) -> Result<(), StorageError> {
+        self.inner.save_account(address, info)?;
+        self.cache.write(
+            CacheKey::Account(*address),
+            CacheValue::Account(Some(info.clone())),
+        );
+        Ok(())
+    }
+
+    fn get_code(&self, hash: &Hash) -> Result<Option<Bytes>, StorageError> {
+        let key = CacheKey::Code(*hash);
+        let writes = match self.cache.get(&key) {
+            Ok(CacheValue::Code(code)) => return Ok(Some(code)),
+            Ok(_) => unreachable!("code key holds code"),
+            Err(writes) => writes,
+        };
+        let code = self.inner.get_code(hash)?;
+        if let Some(code) = &code {
+            self.cache.fill(key, CacheValue::Code(code.clone()), writes);
+        }
+        Ok(code)
+    }
+
+    fn save_code(&self, hash: &Hash, code: &Bytes) -> Result<(), StorageError> {
+        self.inner.save_code(hash, code)?;
+        self.cache
+            .write(CacheKey::Code(*hash), CacheValue::Code(code.clone()));
+        Ok(())
+    }
+
+    fn get_storage(&self, address: &Address, index: &U256) -> Result<U256, StorageError> {
+        let key = CacheKey::Slot(*address, *index);
+        let writes = match self.cache.get(&key) {
+            Ok(CacheValue::Slot(value)) => return Ok(value),
+            Ok(_) => unreachable!("slot key holds a slot"),
+            Err(writes) => writes,
+        };
+        let value = self.inner.get_storage(address, index)?;
+        self.cache.fill(key, CacheValue::Slot(value), writes);
+        Ok(value)
+    }
+
+    fn save_storage(
+        &self,
+        address: &Address,
+        index: &U256,
+        value: &U256,
+    ) -> Result<(), StorageError> {
+        self.inner.save_storage(address, index, value)?;
+        self.cache
+            .write(CacheKey::Slot(*address, *index), CacheValue::Slot(*value));
+        Ok(())
+    }
+
+    fn get_storage_root(&self, address: &Address) -> Result<Hash, StorageError> {
+        self.inner.get_storage_root(address)
+    }
+
+    fn save_storage_root(&self, address: &Address, root: &Hash) -> Result<(), StorageError> {
+        self.inner.save_storage_root(address, root)
+    }
+
+    fn get_all_accounts(&self) -> Result<Vec<(Address, AccountInfo)>, StorageError> {
+        self.inner.get_all_accounts()
+    }
+
+    fn get_all_storage(&self, address: &Address) -> Result<Vec<(U256, U256)>, StorageError> {
+        self.inner.get_all_storage(address)
+    }
+
+    fn get_smt_branch(&self, height: u8, node_key: &Hash) -> Result<Option<Vec<u8>>, StorageError> {
+        self.inner.get_smt_branch(height, node_key)
+    }
+
+    fn save_smt_branch(
+        &self,
+        height: u8,
+        node_key: &Hash,
+        node: &[u8],
+    ) -> Result<(), StorageError> {
+        self.inner.save_smt_branch(height, node_key, node)
+    }
+
+    fn get_smt_leaf(&self, hash: &Hash) -> Result<Option<Vec<u8>>, StorageError> {
+        self.inner.get_smt_leaf(hash)
+    }
+
+    fn save_smt_leaf(&self, hash: &Hash, node: &[u8]) -> Result<(), StorageError> {
+        self.inner.save_smt_leaf(hash, node)
+    }
+}
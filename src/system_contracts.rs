@@ -0,0 +1,117 @@
+//! Natively implemented contracts living at reserved addresses.
+//! Unlike plain precompiles they own state: their data lives in the contract account's
+//! storage slots (committed through the StateManager, so it is covered by the state root).
+//! They are dispatched through the PrecompileRegistry like any other native handler.
+
+pub mod encrypted_mempool;
+pub mod governance;
+pub mod light_client;
+pub mod staking;
+
+pub use encrypted_mempool::{ENCRYPTED_MEMPOOL_ADDRESS, EncryptedMempoolContract};
+pub use governance::{GOVERNANCE_ADDRESS, GovernanceContract};
+pub use light_client::{LIGHT_CLIENT_ADDRESS, LightClientContract};
+pub use staking::{STAKING_ADDRESS, StakingContract};
+
+use crate::crypto::Hash;
+use crate::precompiles::PrecompileError;
+use crate::state::{StateError, StateManager};
+use crate::storage::AccountInfo;
+use crate::types::{Address, U256, keccak256};
+use revm::Database;
+
+/// Function selector: first 4 bytes of keccak256 of the signature, e.g. `stake()`.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Event topic 0: keccak256 of the event signature, e.g. `Staked(address,uint256)`.
+pub fn event_topic(signature: &str) -> Hash {
+    Hash(keccak256(signature.as_bytes()).into())
+}
+
+/// ABI encoding of an address as a 32-byte word (topic or data).
+pub fn address_word(address: Address) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address.as_slice());
+    word
+}
+
+/// Storage slot of `mapping(address => ..)` declared at `slot` (Solidity layout).
+pub fn mapping_slot(key: Address, slot: u64) -> U256 {
+    let mut preimage = Vec::with_capacity(64);
+    preimage.extend_from_slice(&address_word(key));
+    preimage.extend_from_slice(&U256::from(slot).to_be_bytes::<32>());
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+/// Storage slot of `mapping(uint256 => ..)` (or `bytes32 =>`) declared at `slot`; apply it
+/// twice for a nested mapping.
+pub fn uint_mapping_slot(key: U256, slot: U256) -> U256 {
+    let mut preimage = Vec::with_capacity(64);
+    preimage.extend_from_slice(&key.to_be_bytes::<32>());
+    preimage.extend_from_slice(&slot.to_be_bytes::<32>());
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+pub(crate) fn adjust_balance(
+    db: &mut StateManager,
+    address: Address,
+    f: impl FnOnce(U256) -> U256,
+) -> Result<(), StateError> {
+    let acc = db.basic(address)?.unwrap_or_default();
+    let info = AccountInfo {
+        nonce: acc.nonce,
+        balance: f(acc.balance),
+        code_hash: Hash(acc.code_hash.0),
+    };
+    db.commit_account(address, info)
+}
+
+pub(crate) fn charge(gas_limit: u64, cost: u64) -> Result<(), PrecompileError> {
+    if gas_limit < cost {
+        return Err(PrecompileError::OutOfGas);
+    }
+    Ok(())
+}
+
+pub(crate) fn state_err(e: impl std::fmt::Display) -> PrecompileError {
+    PrecompileError::State(e.to_string())
+}
+
+pub(crate) fn revert(reason: &str) -> PrecompileError {
+    PrecompileError::Revert(reason.into())
+}
+
+/// The ABI word of argument `index`.
+pub(crate) fn arg(input: &[u8], index: usize) -> Result<U256, PrecompileError> {
+    let at = 4 + index * 32;
+    input
+        .get(at..at + 32)
+        .map(U256::from_be_slice)
+        .ok_or_else(|| revert("input too short"))
+}
+
+pub(crate) fn arg_u64(input: &[u8], index: usize) -> Result<u64, PrecompileError> {
+    u64::try_from(arg(input, index)?).map_err(|_| revert("argument out of range"))
+}
+
+/// The contents of the `bytes` argument `index`, decoded from JSON.
+pub(crate) fn arg_json<T: serde::de::DeserializeOwned>(
+    input: &[u8],
+    index: usize,
+) -> Result<T, PrecompileError> {
+    let args = &input[4..];
+    let offset = usize::try_from(arg(input, index)?).map_err(|_| revert("bad offset"))?;
+    let len = args
+        .get(offset..offset.saturating_add(32))
+        .map(U256::from_be_slice)
+        .and_then(|len| usize::try_from(len).ok())
+        .ok_or_else(|| revert("bad offset"))?;
+    let data = offset
+        .checked_add(32)
+        .and_then(|start| args.get(start..start.checked_add(len)?))
+        .ok_or_else(|| revert("input too short"))?;
+    serde_json::from_slice(data).map_err(|e| PrecompileError::Revert(format!("bad payload: {}", e)))
+}
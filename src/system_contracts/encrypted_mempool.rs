@@ -0,0 +1,204 @@
+//! Encrypted mempool system contract (0x1003): commit-reveal ordering of transactions
+//! encrypted to the committee, so a leader cannot front-run what it cannot read.
+//!
+//! ABI:
+//! - `submit(bytes envelope)` (0xef7fa71b): record `envelope` (the JSON of an
+//!   `EncryptedTransaction`) as pending. Anyone can submit (a relayer's account may pay the
+//!   fee); only enabled when `ChainParams::encrypted_mempool` is set.
+//!
+//! Once the block including the submission is notarized, committee members gossip their
+//! decryption shares. A later proposer aggregates them into the `DecryptionKey` it puts in
+//! its block metadata, with the decrypted transaction leading its payload. The executor
+//! rejects the block if a key does not `open` its ciphertext into that transaction; a
+//! transaction whose ciphertext is no longer pending (`check_pending`) is not executed.
+//! Decryption needs the shares of the whole committee; ciphertexts not revealed within
+//! `ChainParams::decryption_window` views can no longer be.
+//!
+//! Events: `CiphertextSubmitted(bytes32 indexed id, uint64 view)`.
+//!
+//! Storage: slots 0 and 1 are mappings (by ciphertext id) of the pending commitment and
+//! of the view it was submitted in.
+
+use super::{arg_json, charge, event_topic, revert, state_err, uint_mapping_slot};
+use crate::crypto::{Hash, PublicKey, verify, verify_aggregate};
+use crate::precompiles::{Precompile, PrecompileContext, PrecompileError, PrecompileOutput};
+use crate::state::{StateError, StateManager};
+use crate::types::{
+    Address, Bytes, DecryptionKey, EncryptedTransaction, Log, Transaction, U256, View,
+};
+use revm::Database;
+
+pub const ENCRYPTED_MEMPOOL_ADDRESS: Address = Address::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x03,
+]);
+
+pub const SUBMIT_GAS: u64 = 40_000;
+/// Per ciphertext byte, on top of `SUBMIT_GAS`.
+pub const SUBMIT_BYTE_GAS: u64 = 16;
+pub const MAX_CIPHERTEXT_SIZE: usize = 64 * 1024;
+
+pub const SUBMIT_SELECTOR: [u8; 4] = [0xef, 0x7f, 0xa7, 0x1b];
+
+pub const CIPHERTEXT_SUBMITTED_EVENT: &str = "CiphertextSubmitted(bytes32,uint64)";
+
+const COMMITMENT_SLOT: u64 = 0;
+const VIEW_SLOT: u64 = 1;
+
+/// Call data of `submit(envelope)`.
+pub fn submit_data(envelope: &EncryptedTransaction) -> Bytes {
+    let payload = serde_json::to_vec(envelope).unwrap_or_default();
+    let mut data = SUBMIT_SELECTOR.to_vec();
+    data.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(payload.len()).to_be_bytes::<32>());
+    data.extend_from_slice(&payload);
+    data.resize(data.len() + (32 - payload.len() % 32) % 32, 0);
+    data.into()
+}
+
+/// The envelope submitted by `tx`, if it is a `submit` call.
+pub fn submitted_envelope(tx: &Transaction) -> Option<EncryptedTransaction> {
+    if tx.to != Some(ENCRYPTED_MEMPOOL_ADDRESS) || !tx.data.starts_with(&SUBMIT_SELECTOR) {
+        return None;
+    }
+    arg_json(&tx.data, 0).ok()
+}
+
+fn slot(id: Hash, slot: u64) -> U256 {
+    uint_mapping_slot(U256::from_be_bytes(id.0), U256::from(slot))
+}
+
+/// Commitment and submission view of the pending ciphertext `id`.
+pub fn pending(db: &mut StateManager, id: Hash) -> Result<Option<(Hash, View)>, StateError> {
+    let commitment = db.storage(ENCRYPTED_MEMPOOL_ADDRESS, slot(id, COMMITMENT_SLOT))?;
+    if commitment.is_zero() {
+        return Ok(None);
+    }
+    let view = db.storage(ENCRYPTED_MEMPOOL_ADDRESS, slot(id, VIEW_SLOT))?;
+    Ok(Some((
+        Hash(commitment.to_be_bytes::<32>()),
+        view.to::<u64>(),
+    )))
+}
+
+/// Open the ciphertext of a revealed key (called by the executor): the key must be the
+/// aggregate of `committee`'s shares and yield a signed transaction.
+pub fn open(committee: &[PublicKey], key: &DecryptionKey) -> Result<Transaction, &'static str> {
+    if !verify_aggregate(committee, &key.envelope.id().0, &key.key) {
+        return Err("invalid decryption key");
+    }
+    let tx = key
+        .envelope
+        .decrypt(&key.key)
+        .ok_or("ciphertext does not decrypt to a transaction")?;
+    if !verify(&tx.public_key, &tx.sighash().0, &tx.signature) {
+        return Err("decrypted transaction has an invalid signature");
+    }
+    Ok(tx)
+}
+
+/// Why `envelope` cannot be revealed in `view`, if it cannot: it is not pending (never
+/// included, or already revealed) or was submitted more than `window` views ago.
+pub fn check_pending(
+    db: &mut StateManager,
+    envelope: &EncryptedTransaction,
+    view: View,
+    window: View,
+) -> Result<Option<&'static str>, StateError> {
+    Ok(match pending(db, envelope.id())? {
+        None => Some("ciphertext not pending"),
+        Some((commitment, _)) if commitment != envelope.commitment() => {
+            Some("ciphertext does not match its commitment")
+        }
+        Some((_, submitted)) if view > submitted.saturating_add(window) => {
+            Some("decryption window elapsed")
+        }
+        Some(_) => None,
+    })
+}
+
+/// Drop the revealed ciphertext `id`.
+pub fn clear(db: &mut StateManager, id: Hash) -> Result<(), StateError> {
+    db.commit_storage(
+        ENCRYPTED_MEMPOOL_ADDRESS,
+        slot(id, COMMITMENT_SLOT),
+        U256::ZERO,
+    )?;
+    db.commit_storage(ENCRYPTED_MEMPOOL_ADDRESS, slot(id, VIEW_SLOT), U256::ZERO)
+}
+
+pub struct EncryptedMempoolContract;
+
+impl EncryptedMempoolContract {
+    fn submit(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, SUBMIT_GAS)?;
+        let envelope: EncryptedTransaction = arg_json(input, 0)?;
+        if envelope.ciphertext.len() > MAX_CIPHERTEXT_SIZE {
+            return Err(revert("ciphertext too large"));
+        }
+        let gas_used = SUBMIT_GAS + SUBMIT_BYTE_GAS * envelope.ciphertext.len() as u64;
+        charge(gas_limit, gas_used)?;
+        let view = ctx.block.view;
+        let db = &mut *ctx.db;
+
+        let enabled = db
+            .get_consensus_state()
+            .map_err(state_err)?
+            .is_some_and(|state| state.params.encrypted_mempool);
+        if !enabled {
+            return Err(revert("encrypted mempool disabled"));
+        }
+        let id = envelope.id();
+        if pending(db, id).map_err(state_err)?.is_some() {
+            return Err(revert("ciphertext already submitted"));
+        }
+        let commitment = U256::from_be_bytes(envelope.commitment().0);
+        db.commit_storage(
+            ENCRYPTED_MEMPOOL_ADDRESS,
+            slot(id, COMMITMENT_SLOT),
+            commitment,
+        )
+        .map_err(state_err)?;
+        db.commit_storage(
+            ENCRYPTED_MEMPOOL_ADDRESS,
+            slot(id, VIEW_SLOT),
+            U256::from(view),
+        )
+        .map_err(state_err)?;
+        tracing::info!("Ciphertext {:?} Submitted in View {}", id, view);
+
+        Ok(PrecompileOutput {
+            gas_used,
+            output: id.0.to_vec(),
+            logs: vec![Log {
+                address: ENCRYPTED_MEMPOOL_ADDRESS,
+                topics: vec![event_topic(CIPHERTEXT_SUBMITTED_EVENT), id],
+                data: Bytes::from(U256::from(view).to_be_bytes::<32>().to_vec()),
+            }],
+        })
+    }
+}
+
+impl Precompile for EncryptedMempoolContract {
+    fn name(&self) -> &'static str {
+        "encrypted_mempool"
+    }
+
+    fn call(
+        &self,
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        if input.len() < 4 {
+            return Err(revert("missing function selector"));
+        }
+        match [input[0], input[1], input[2], input[3]] {
+            SUBMIT_SELECTOR => Self::submit(ctx, input, gas_limit),
+            _ => Err(revert("unknown function selector")),
+        }
+    }
+}
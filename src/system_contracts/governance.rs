@@ -0,0 +1,275 @@
+//! Governance system contract (0x1002): stake-weighted changes of the chain parameters.
+//!
+//! ABI:
+//! - `propose(bytes params)` (0x37558af5): propose replacing the chain parameters with
+//!   `params` (the JSON of `ChainParams`). Only committee members propose; the proposer's
+//!   vote is counted. Returns the proposal id.
+//! - `vote(uint64 proposalId)` (0xf9b89ba9): vote in favour of an open proposal.
+//!
+//! A proposal is approved once its voters hold more than 2/3 of the committee's stake, and
+//! takes effect at the next epoch boundary (a multiple of `ChainParams::epoch_length`): the
+//! executor swaps the parameters at the end of that block, so they apply from the next
+//! one. Proposals not approved within `VOTING_PERIOD` views are dropped.
+//!
+//! Events: `ProposalCreated(uint64 indexed, uint64 votingEnds)`,
+//! `Voted(address indexed, uint64 proposalId)`, `ProposalApproved(uint64 indexed, uint64
+//! enactView)`.
+//!
+//! Storage: slot 0 is the proposal count. The proposals themselves stay in ConsensusState,
+//! like the staking queues, since the executor enacts them.
+
+use super::{address_word, arg_json, arg_u64, charge, event_topic, revert, staking, state_err};
+use crate::crypto::{Hash, PublicKey};
+use crate::precompiles::{Precompile, PrecompileContext, PrecompileError, PrecompileOutput};
+use crate::state::{StateError, StateManager};
+use crate::storage::ConsensusState;
+use crate::types::{Address, Bytes, ChainParams, Log, ParamsProposal, U256, View};
+use revm::Database;
+
+pub const GOVERNANCE_ADDRESS: Address = Address::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x02,
+]);
+
+/// Default epoch length (see `ChainParams::epoch_length`).
+pub const EPOCH_LENGTH: View = 100;
+/// Views a proposal stays open for votes.
+pub const VOTING_PERIOD: View = 1_000;
+
+pub const PROPOSE_GAS: u64 = 50_000;
+pub const VOTE_GAS: u64 = 30_000;
+
+pub const PROPOSE_SELECTOR: [u8; 4] = [0x37, 0x55, 0x8a, 0xf5];
+pub const VOTE_SELECTOR: [u8; 4] = [0xf9, 0xb8, 0x9b, 0xa9];
+
+pub const PROPOSAL_CREATED_EVENT: &str = "ProposalCreated(uint64,uint64)";
+pub const VOTED_EVENT: &str = "Voted(address,uint64)";
+pub const PROPOSAL_APPROVED_EVENT: &str = "ProposalApproved(uint64,uint64)";
+
+const PROPOSAL_COUNT_SLOT: u64 = 0;
+
+/// Call data of `propose(params)`.
+pub fn propose_data(params: &ChainParams) -> Bytes {
+    let payload = serde_json::to_vec(params).unwrap_or_default();
+    let mut data = PROPOSE_SELECTOR.to_vec();
+    data.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(payload.len()).to_be_bytes::<32>());
+    data.extend_from_slice(&payload);
+    data.resize(data.len() + (32 - payload.len() % 32) % 32, 0);
+    data.into()
+}
+
+/// Call data of `vote(proposalId)`.
+pub fn vote_data(proposal_id: u64) -> Bytes {
+    let mut data = VOTE_SELECTOR.to_vec();
+    data.extend_from_slice(&U256::from(proposal_id).to_be_bytes::<32>());
+    data.into()
+}
+
+pub fn proposal_count(db: &mut StateManager) -> Result<u64, StateError> {
+    Ok(db
+        .storage(GOVERNANCE_ADDRESS, U256::from(PROPOSAL_COUNT_SLOT))?
+        .to::<u64>())
+}
+
+/// Stake of the `voters` still in the committee, and of the whole committee.
+pub fn tally(
+    db: &mut StateManager,
+    committee: &[PublicKey],
+    voters: &[PublicKey],
+) -> Result<(U256, U256), StateError> {
+    let mut in_favour = U256::ZERO;
+    let mut total = U256::ZERO;
+    for member in committee {
+        let stake = staking::stake_of(db, staking::validator_address(member))?;
+        total += stake;
+        if voters.contains(member) {
+            in_favour += stake;
+        }
+    }
+    Ok((in_favour, total))
+}
+
+/// More than 2/3 of the committee's stake is in favour.
+pub fn is_approved(in_favour: U256, total: U256) -> bool {
+    !total.is_zero() && in_favour * U256::from(3) > total * U256::from(2)
+}
+
+/// End-of-block processing (called by the executor): apply the approved proposals whose
+/// epoch boundary is reached, and drop the ones whose voting period ended. Returns whether
+/// `state` changed.
+pub fn enact_proposals(state: &mut ConsensusState, view: View) -> bool {
+    let before = state.proposals.len();
+    let (due, open): (Vec<_>, Vec<_>) = std::mem::take(&mut state.proposals)
+        .into_iter()
+        .partition(|p| p.enact_view.is_some_and(|at| at <= view));
+    state.proposals = open;
+    for proposal in due {
+        tracing::info!("Chain Params Updated by Proposal {}", proposal.id);
+        state.params = proposal.params;
+    }
+    state
+        .proposals
+        .retain(|p| p.enact_view.is_some() || view < p.created_view.saturating_add(VOTING_PERIOD));
+    state.proposals.len() != before
+}
+
+fn event(signature: &str, indexed: [u8; 32], data: U256) -> Log {
+    Log {
+        address: GOVERNANCE_ADDRESS,
+        topics: vec![event_topic(signature), Hash(indexed)],
+        data: Bytes::from(data.to_be_bytes::<32>().to_vec()),
+    }
+}
+
+pub struct GovernanceContract;
+
+impl GovernanceContract {
+    // Schedule `proposal` if its voters now hold a supermajority
+    fn try_approve(
+        db: &mut StateManager,
+        state: &ConsensusState,
+        proposal: &mut ParamsProposal,
+        view: View,
+        logs: &mut Vec<Log>,
+    ) -> Result<(), PrecompileError> {
+        let (in_favour, total) = tally(db, &state.committee, &proposal.votes).map_err(state_err)?;
+        if is_approved(in_favour, total) {
+            let enact_view = state.params.next_epoch_boundary(view);
+            proposal.enact_view = Some(enact_view);
+            tracing::info!(
+                "Proposal {} Approved: enacted at view {}",
+                proposal.id,
+                enact_view
+            );
+            logs.push(event(
+                PROPOSAL_APPROVED_EVENT,
+                U256::from(proposal.id).to_be_bytes::<32>(),
+                U256::from(enact_view),
+            ));
+        }
+        Ok(())
+    }
+
+    fn propose(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, PROPOSE_GAS)?;
+        let params: ChainParams = arg_json(input, 0)?;
+        params.validate().map_err(revert)?;
+        let pk = ctx.tx.public_key.clone();
+        let view = ctx.block.view;
+        let db = &mut *ctx.db;
+
+        let mut state = db
+            .get_consensus_state()
+            .map_err(state_err)?
+            .ok_or_else(|| state_err("missing consensus state"))?;
+        if !state.committee.contains(&pk) {
+            return Err(revert("not a committee member"));
+        }
+
+        let id = proposal_count(db).map_err(state_err)?;
+        db.commit_storage(
+            GOVERNANCE_ADDRESS,
+            U256::from(PROPOSAL_COUNT_SLOT),
+            U256::from(id + 1),
+        )
+        .map_err(state_err)?;
+        let voting_ends = view.saturating_add(VOTING_PERIOD);
+        let mut logs = vec![event(
+            PROPOSAL_CREATED_EVENT,
+            U256::from(id).to_be_bytes::<32>(),
+            U256::from(voting_ends),
+        )];
+        let mut proposal = ParamsProposal {
+            id,
+            proposer: pk.clone(),
+            params,
+            created_view: view,
+            votes: vec![pk],
+            enact_view: None,
+        };
+        Self::try_approve(db, &state, &mut proposal, view, &mut logs)?;
+        state.proposals.push(proposal);
+        db.save_consensus_state(&state).map_err(state_err)?;
+        tracing::info!("Proposal {} Created, open until view {}", id, voting_ends);
+
+        Ok(PrecompileOutput {
+            gas_used: PROPOSE_GAS,
+            output: U256::from(id).to_be_bytes::<32>().to_vec(),
+            logs,
+        })
+    }
+
+    fn vote(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, VOTE_GAS)?;
+        let id = arg_u64(input, 0)?;
+        let pk = ctx.tx.public_key.clone();
+        let view = ctx.block.view;
+        let db = &mut *ctx.db;
+
+        let mut state = db
+            .get_consensus_state()
+            .map_err(state_err)?
+            .ok_or_else(|| state_err("missing consensus state"))?;
+        if !state.committee.contains(&pk) {
+            return Err(revert("not a committee member"));
+        }
+        let position = state
+            .proposals
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| revert("unknown proposal"))?;
+        let mut proposal = state.proposals.remove(position);
+        if proposal.enact_view.is_some() {
+            return Err(revert("proposal already approved"));
+        }
+        if view >= proposal.created_view.saturating_add(VOTING_PERIOD) {
+            return Err(revert("voting closed"));
+        }
+        if proposal.votes.contains(&pk) {
+            return Err(revert("already voted"));
+        }
+
+        let voter = ctx.tx.sender();
+        let mut logs = vec![event(VOTED_EVENT, address_word(voter), U256::from(id))];
+        proposal.votes.push(pk);
+        Self::try_approve(db, &state, &mut proposal, view, &mut logs)?;
+        state.proposals.insert(position, proposal);
+        db.save_consensus_state(&state).map_err(state_err)?;
+
+        Ok(PrecompileOutput {
+            gas_used: VOTE_GAS,
+            output: vec![],
+            logs,
+        })
+    }
+}
+
+impl Precompile for GovernanceContract {
+    fn name(&self) -> &'static str {
+        "governance"
+    }
+
+    fn call(
+        &self,
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        if input.len() < 4 {
+            return Err(revert("missing function selector"));
+        }
+        match [input[0], input[1], input[2], input[3]] {
+            PROPOSE_SELECTOR => Self::propose(ctx, input, gas_limit),
+            VOTE_SELECTOR => Self::vote(ctx, input, gas_limit),
+            _ => Err(revert("unknown function selector")),
+        }
+    }
+}
@@ -0,0 +1,488 @@
+//! Light client system contract (0x1001): tracks finalized headers of counterparty chains
+//! (other Ockham networks) and moves native tokens between them.
+//!
+//! ABI (payloads are the JSON of the types below, passed as `bytes`):
+//! - `createClient(uint64 chainId, bytes committee)` (0x34dc5db0): track the chain
+//!   `chainId`, trusting `committee` (a `Vec<PublicKey>`). Returns the client id.
+//! - `updateClient(uint64 clientId, bytes update)` (0x4c77df7f): store the roots of a
+//!   block finalized by the client's committee (`ClientUpdate`).
+//! - `rotateCommittee(uint64 clientId, bytes rotation)` (0xa42a64d5): follow a committee
+//!   hand-over signed by the client's committee (`CommitteeRotation`).
+//! - `sendTransfer(uint64 clientId, uint64 destChainId, address recipient)` (payable,
+//!   0x21467bee): lock `msg.value` in the escrow of `clientId` for `recipient` on the chain
+//!   `destChainId`, which must be the one the client tracks.
+//! - `receiveTransfer(uint64 clientId, bytes proof)` (0x2061e222): release to its
+//!   recipient the value of a successful `sendTransfer` to this chain, proven against a
+//!   stored header of the client (`TransferProof`), from the escrow of that client. Each
+//!   transfer is released once.
+//!
+//! Events: `ClientCreated(uint64 indexed, uint64 chainId)`,
+//! `ClientUpdated(uint64 indexed, uint64 view)`, `CommitteeRotated(uint64 indexed, uint64
+//! epoch)`, `TransferSent(address indexed, uint256)`,
+//! `TransferReceived(address indexed, uint256)`.
+//!
+//! Storage: slot 0 is the client count; per client, slots 1-4 are mappings of the
+//! committee hash, chain id, epoch and latest view, slots 5-8 nested mappings (client, view)
+//! of the verified flag, state, transactions and receipts roots, and slot 9 a nested
+//! mapping (client, transaction hash) of released transfers; slot 10 maps clients to their
+//! escrow. Transfers are escrowed per client: value sent out through a client is held in the
+//! contract balance, and only transfers proven against that client's headers release it.
+//! Anyone can create a client trusting any committee, so a client never pays out what was
+//! locked through another.
+
+use super::{
+    address_word, adjust_balance, arg, arg_json, arg_u64, charge, event_topic, revert, state_err,
+    uint_mapping_slot,
+};
+use crate::crypto::{Hash, PublicKey, hash_data};
+use crate::light::{FinalizedBlock, verify_finality};
+use crate::precompiles::{Precompile, PrecompileContext, PrecompileError, PrecompileOutput};
+use crate::state::{StateError, StateManager};
+use crate::types::{
+    Address, Bytes, CommitteeTransition, Log, MerkleProof, Receipt, Transaction, U256, View,
+};
+use revm::Database;
+use serde::{Deserialize, Serialize};
+
+pub const LIGHT_CLIENT_ADDRESS: Address = Address::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x01,
+]);
+
+pub const CREATE_CLIENT_GAS: u64 = 50_000;
+pub const UPDATE_CLIENT_GAS: u64 = 200_000; // Two aggregate signature checks
+pub const ROTATE_COMMITTEE_GAS: u64 = 150_000;
+pub const SEND_TRANSFER_GAS: u64 = 30_000;
+pub const RECEIVE_TRANSFER_GAS: u64 = 60_000;
+
+pub const CREATE_CLIENT_SELECTOR: [u8; 4] = [0x34, 0xdc, 0x5d, 0xb0];
+pub const UPDATE_CLIENT_SELECTOR: [u8; 4] = [0x4c, 0x77, 0xdf, 0x7f];
+pub const ROTATE_COMMITTEE_SELECTOR: [u8; 4] = [0xa4, 0x2a, 0x64, 0xd5];
+pub const SEND_TRANSFER_SELECTOR: [u8; 4] = [0x21, 0x46, 0x7b, 0xee];
+pub const RECEIVE_TRANSFER_SELECTOR: [u8; 4] = [0x20, 0x61, 0xe2, 0x22];
+
+pub const CLIENT_CREATED_EVENT: &str = "ClientCreated(uint64,uint64)";
+pub const CLIENT_UPDATED_EVENT: &str = "ClientUpdated(uint64,uint64)";
+pub const COMMITTEE_ROTATED_EVENT: &str = "CommitteeRotated(uint64,uint64)";
+pub const TRANSFER_SENT_EVENT: &str = "TransferSent(address,uint256)";
+pub const TRANSFER_RECEIVED_EVENT: &str = "TransferReceived(address,uint256)";
+
+const CLIENT_COUNT_SLOT: u64 = 0;
+const COMMITTEE_HASH_SLOT: u64 = 1;
+const CHAIN_ID_SLOT: u64 = 2;
+const EPOCH_SLOT: u64 = 3;
+const LATEST_VIEW_SLOT: u64 = 4;
+const VERIFIED_SLOT: u64 = 5;
+const STATE_ROOT_SLOT: u64 = 6;
+const TRANSACTIONS_ROOT_SLOT: u64 = 7;
+const RECEIPTS_ROOT_SLOT: u64 = 8;
+const RECEIVED_SLOT: u64 = 9;
+const ESCROW_SLOT: u64 = 10;
+
+/// `updateClient` payload: a finalized block of the counterparty with the committee that
+/// finalized it (whose hash the client stores).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClientUpdate {
+    pub committee: Vec<PublicKey>,
+    pub block: FinalizedBlock,
+}
+
+/// `rotateCommittee` payload: the client's current committee and its signed hand-over.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommitteeRotation {
+    pub committee: Vec<PublicKey>,
+    pub transition: CommitteeTransition,
+}
+
+/// `receiveTransfer` payload: a `sendTransfer` transaction of the counterparty with its
+/// receipt, both proven against the block finalized at `view`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransferProof {
+    pub view: View,
+    pub transaction: Transaction,
+    pub transaction_proof: MerkleProof,
+    pub receipt: Receipt,
+    pub receipt_proof: MerkleProof,
+}
+
+/// Roots of a counterparty block verified by a client.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrustedRoots {
+    pub state_root: Hash,
+    pub transactions_root: Hash,
+    pub receipts_root: Hash,
+}
+
+/// Call data of one of the `(uint64, bytes)` functions.
+pub fn call_data(selector: [u8; 4], id: u64, payload: &[u8]) -> Bytes {
+    let mut data = selector.to_vec();
+    data.extend_from_slice(&U256::from(id).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(64).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(payload.len()).to_be_bytes::<32>());
+    data.extend_from_slice(payload);
+    data.resize(data.len() + (32 - payload.len() % 32) % 32, 0);
+    data.into()
+}
+
+/// Call data of `sendTransfer(clientId, destChainId, recipient)`.
+pub fn send_transfer_data(client_id: u64, dest_chain_id: u64, recipient: Address) -> Bytes {
+    let mut data = SEND_TRANSFER_SELECTOR.to_vec();
+    data.extend_from_slice(&U256::from(client_id).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(dest_chain_id).to_be_bytes::<32>());
+    data.extend_from_slice(&address_word(recipient));
+    data.into()
+}
+
+fn client_slot(slot: u64, client_id: u64) -> U256 {
+    uint_mapping_slot(U256::from(client_id), U256::from(slot))
+}
+
+fn nested_slot(slot: u64, client_id: u64, key: U256) -> U256 {
+    uint_mapping_slot(key, client_slot(slot, client_id))
+}
+
+fn read(db: &mut StateManager, slot: U256) -> Result<U256, StateError> {
+    db.storage(LIGHT_CLIENT_ADDRESS, slot)
+}
+
+fn write(db: &StateManager, slot: U256, value: U256) -> Result<(), StateError> {
+    db.commit_storage(LIGHT_CLIENT_ADDRESS, slot, value)
+}
+
+fn hash_word(hash: &Hash) -> U256 {
+    U256::from_be_bytes(hash.0)
+}
+
+pub fn client_count(db: &mut StateManager) -> Result<u64, StateError> {
+    Ok(read(db, U256::from(CLIENT_COUNT_SLOT))?.to::<u64>())
+}
+
+/// Latest finalized view of the counterparty verified by `client_id`.
+pub fn latest_view(db: &mut StateManager, client_id: u64) -> Result<View, StateError> {
+    Ok(read(db, client_slot(LATEST_VIEW_SLOT, client_id))?.to::<u64>())
+}
+
+/// Value locked through `client_id` and not released yet.
+pub fn escrow(db: &mut StateManager, client_id: u64) -> Result<U256, StateError> {
+    read(db, client_slot(ESCROW_SLOT, client_id))
+}
+
+/// Roots of the counterparty block finalized at `view`, if `client_id` verified it.
+pub fn trusted_roots(
+    db: &mut StateManager,
+    client_id: u64,
+    view: View,
+) -> Result<Option<TrustedRoots>, StateError> {
+    let view = U256::from(view);
+    if read(db, nested_slot(VERIFIED_SLOT, client_id, view))?.is_zero() {
+        return Ok(None);
+    }
+    let mut root = |slot| -> Result<Hash, StateError> {
+        Ok(Hash(
+            read(db, nested_slot(slot, client_id, view))?.to_be_bytes::<32>(),
+        ))
+    };
+    Ok(Some(TrustedRoots {
+        state_root: root(STATE_ROOT_SLOT)?,
+        transactions_root: root(TRANSACTIONS_ROOT_SLOT)?,
+        receipts_root: root(RECEIPTS_ROOT_SLOT)?,
+    }))
+}
+
+fn event(signature: &str, indexed: [u8; 32], data: U256) -> Log {
+    Log {
+        address: LIGHT_CLIENT_ADDRESS,
+        topics: vec![event_topic(signature), Hash(indexed)],
+        data: Bytes::from(data.to_be_bytes::<32>().to_vec()),
+    }
+}
+
+// The client's stored committee hash; reverts for unknown clients
+fn committee_hash(db: &mut StateManager, client_id: u64) -> Result<Hash, PrecompileError> {
+    if client_id >= client_count(db).map_err(state_err)? {
+        return Err(revert("unknown client"));
+    }
+    let hash = read(db, client_slot(COMMITTEE_HASH_SLOT, client_id)).map_err(state_err)?;
+    Ok(Hash(hash.to_be_bytes::<32>()))
+}
+
+pub struct LightClientContract;
+
+impl LightClientContract {
+    fn create_client(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, CREATE_CLIENT_GAS)?;
+        let chain_id = arg_u64(input, 0)?;
+        let committee: Vec<PublicKey> = arg_json(input, 1)?;
+        if committee.is_empty() {
+            return Err(revert("empty committee"));
+        }
+        let db = &mut *ctx.db;
+
+        let client_id = client_count(db).map_err(state_err)?;
+        write(db, U256::from(CLIENT_COUNT_SLOT), U256::from(client_id + 1)).map_err(state_err)?;
+        write(
+            db,
+            client_slot(COMMITTEE_HASH_SLOT, client_id),
+            hash_word(&hash_data(&committee)),
+        )
+        .map_err(state_err)?;
+        write(
+            db,
+            client_slot(CHAIN_ID_SLOT, client_id),
+            U256::from(chain_id),
+        )
+        .map_err(state_err)?;
+        tracing::info!("Light Client {} created for chain {}", client_id, chain_id);
+
+        Ok(PrecompileOutput {
+            gas_used: CREATE_CLIENT_GAS,
+            output: U256::from(client_id).to_be_bytes::<32>().to_vec(),
+            logs: vec![event(
+                CLIENT_CREATED_EVENT,
+                U256::from(client_id).to_be_bytes::<32>(),
+                U256::from(chain_id),
+            )],
+        })
+    }
+
+    fn update_client(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, UPDATE_CLIENT_GAS)?;
+        let client_id = arg_u64(input, 0)?;
+        let update: ClientUpdate = arg_json(input, 1)?;
+        let db = &mut *ctx.db;
+
+        if hash_data(&update.committee) != committee_hash(db, client_id)? {
+            return Err(revert("not the client's committee"));
+        }
+        let header = &update.block.header;
+        let chain_id = read(db, client_slot(CHAIN_ID_SLOT, client_id))
+            .map_err(state_err)?
+            .to::<u64>();
+        verify_finality(header, &update.block.proof, &update.committee, chain_id)
+            .map_err(|e| PrecompileError::Revert(e.to_string()))?;
+
+        let view = U256::from(header.view);
+        for (slot, value) in [
+            (VERIFIED_SLOT, U256::from(1)),
+            (STATE_ROOT_SLOT, hash_word(&header.state_root)),
+            (TRANSACTIONS_ROOT_SLOT, hash_word(&header.transactions_root)),
+            (RECEIPTS_ROOT_SLOT, hash_word(&header.receipts_root)),
+        ] {
+            write(db, nested_slot(slot, client_id, view), value).map_err(state_err)?;
+        }
+        if header.view > latest_view(db, client_id).map_err(state_err)? {
+            write(db, client_slot(LATEST_VIEW_SLOT, client_id), view).map_err(state_err)?;
+        }
+
+        Ok(PrecompileOutput {
+            gas_used: UPDATE_CLIENT_GAS,
+            output: vec![],
+            logs: vec![event(
+                CLIENT_UPDATED_EVENT,
+                U256::from(client_id).to_be_bytes::<32>(),
+                view,
+            )],
+        })
+    }
+
+    fn rotate_committee(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, ROTATE_COMMITTEE_GAS)?;
+        let client_id = arg_u64(input, 0)?;
+        let rotation: CommitteeRotation = arg_json(input, 1)?;
+        let db = &mut *ctx.db;
+
+        if hash_data(&rotation.committee) != committee_hash(db, client_id)? {
+            return Err(revert("not the client's committee"));
+        }
+        let epoch = read(db, client_slot(EPOCH_SLOT, client_id))
+            .map_err(state_err)?
+            .to::<u64>();
+        let transition = &rotation.transition;
+        if transition.epoch != epoch + 1 {
+            return Err(PrecompileError::Revert(format!(
+                "expected epoch {}, got {}",
+                epoch + 1,
+                transition.epoch
+            )));
+        }
+        if !transition.verify(&rotation.committee) {
+            return Err(revert("invalid committee transition"));
+        }
+
+        write(
+            db,
+            client_slot(COMMITTEE_HASH_SLOT, client_id),
+            hash_word(&transition.new_committee_hash()),
+        )
+        .map_err(state_err)?;
+        write(
+            db,
+            client_slot(EPOCH_SLOT, client_id),
+            U256::from(transition.epoch),
+        )
+        .map_err(state_err)?;
+
+        Ok(PrecompileOutput {
+            gas_used: ROTATE_COMMITTEE_GAS,
+            output: vec![],
+            logs: vec![event(
+                COMMITTEE_ROTATED_EVENT,
+                U256::from(client_id).to_be_bytes::<32>(),
+                U256::from(transition.epoch),
+            )],
+        })
+    }
+
+    fn send_transfer(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, SEND_TRANSFER_GAS)?;
+        let client_id = arg_u64(input, 0)?;
+        let dest_chain_id = arg_u64(input, 1)?;
+        let recipient = Address::from_slice(&arg(input, 2)?.to_be_bytes::<32>()[12..]);
+        if dest_chain_id == ctx.tx.chain_id {
+            return Err(revert("transfer to this chain"));
+        }
+        if ctx.tx.value.is_zero() {
+            return Err(revert("nothing to transfer"));
+        }
+        let db = &mut *ctx.db;
+        committee_hash(db, client_id)?;
+        let tracked = read(db, client_slot(CHAIN_ID_SLOT, client_id)).map_err(state_err)?;
+        if tracked != U256::from(dest_chain_id) {
+            return Err(revert("client of another chain"));
+        }
+
+        // The value is locked in the contract balance by the executor, and accounted to the
+        // client it can be released through
+        let locked = escrow(db, client_id).map_err(state_err)?;
+        write(
+            db,
+            client_slot(ESCROW_SLOT, client_id),
+            locked + ctx.tx.value,
+        )
+        .map_err(state_err)?;
+        Ok(PrecompileOutput {
+            gas_used: SEND_TRANSFER_GAS,
+            output: vec![],
+            logs: vec![event(
+                TRANSFER_SENT_EVENT,
+                address_word(recipient),
+                ctx.tx.value,
+            )],
+        })
+    }
+
+    fn receive_transfer(
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, RECEIVE_TRANSFER_GAS)?;
+        let client_id = arg_u64(input, 0)?;
+        let proof: TransferProof = arg_json(input, 1)?;
+        let chain_id = ctx.tx.chain_id;
+        let db = &mut *ctx.db;
+
+        // 1. The transfer is in a verified block of the counterparty, and succeeded
+        committee_hash(db, client_id)?;
+        let roots = trusted_roots(db, client_id, proof.view)
+            .map_err(state_err)?
+            .ok_or_else(|| revert("unverified view"))?;
+        let tx = &proof.transaction;
+        if !proof
+            .transaction_proof
+            .verify(&roots.transactions_root, &tx.encode())
+        {
+            return Err(revert("invalid transaction proof"));
+        }
+        if proof.receipt_proof.index != proof.transaction_proof.index
+            || !proof
+                .receipt_proof
+                .verify(&roots.receipts_root, &proof.receipt.encode())
+        {
+            return Err(revert("invalid receipt proof"));
+        }
+        if proof.receipt.status != 1 {
+            return Err(revert("transfer failed on the counterparty"));
+        }
+
+        // 2. It is a transfer from the client's chain to this one
+        let counterparty = read(db, client_slot(CHAIN_ID_SLOT, client_id)).map_err(state_err)?;
+        if U256::from(tx.chain_id) != counterparty {
+            return Err(revert("transaction of another chain"));
+        }
+        if tx.to != Some(LIGHT_CLIENT_ADDRESS)
+            || tx.data.get(..4) != Some(&SEND_TRANSFER_SELECTOR[..])
+        {
+            return Err(revert("not a transfer"));
+        }
+        if arg_u64(&tx.data, 1)? != chain_id {
+            return Err(revert("transfer to another chain"));
+        }
+        let recipient = Address::from_slice(&arg(&tx.data, 2)?.to_be_bytes::<32>()[12..]);
+
+        // 3. Released once, from the escrow of this client only
+        let received = nested_slot(RECEIVED_SLOT, client_id, hash_word(&hash_data(tx)));
+        if !read(db, received).map_err(state_err)?.is_zero() {
+            return Err(revert("transfer already received"));
+        }
+        let locked = escrow(db, client_id).map_err(state_err)?;
+        if locked < tx.value {
+            return Err(revert("insufficient escrow"));
+        }
+        write(db, received, U256::from(1)).map_err(state_err)?;
+        write(db, client_slot(ESCROW_SLOT, client_id), locked - tx.value).map_err(state_err)?;
+        adjust_balance(db, LIGHT_CLIENT_ADDRESS, |b| b - tx.value).map_err(state_err)?;
+        adjust_balance(db, recipient, |b| b + tx.value).map_err(state_err)?;
+        tracing::info!("Transfer Received: {:?} for {:?}", tx.value, recipient);
+
+        Ok(PrecompileOutput {
+            gas_used: RECEIVE_TRANSFER_GAS,
+            output: vec![],
+            logs: vec![event(
+                TRANSFER_RECEIVED_EVENT,
+                address_word(recipient),
+                tx.value,
+            )],
+        })
+    }
+}
+
+impl Precompile for LightClientContract {
+    fn name(&self) -> &'static str {
+        "light_client"
+    }
+
+    fn call(
+        &self,
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        if input.len() < 4 {
+            return Err(revert("missing function selector"));
+        }
+        match [input[0], input[1], input[2], input[3]] {
+            CREATE_CLIENT_SELECTOR => Self::create_client(ctx, input, gas_limit),
+            UPDATE_CLIENT_SELECTOR => Self::update_client(ctx, input, gas_limit),
+            ROTATE_COMMITTEE_SELECTOR => Self::rotate_committee(ctx, input, gas_limit),
+            SEND_TRANSFER_SELECTOR => Self::send_transfer(ctx, input, gas_limit),
+            RECEIVE_TRANSFER_SELECTOR => Self::receive_transfer(ctx, input, gas_limit),
+            _ => Err(revert("unknown function selector")),
+        }
+    }
+}
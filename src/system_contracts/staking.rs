@@ -0,0 +1,322 @@
+//! Staking system contract (0x1000).
+//!
+//! ABI:
+//! - `stake()` (payable, 0x3a4b66f1): lock `msg.value` and queue the caller for activation.
+//! - `unstake()` (0x2e17de78): schedule the caller's exit from the committee.
+//! - `withdraw()` (0x3ccfd60b): return the stake once the caller is no longer bonded and
+//!   its unbonding period (`ChainParams::unbonding_period`) has passed since it left the
+//!   committee. Stake stays slashable until then.
+//! - `claimRewards()` (0x372500ab): pay out the caller's accrued proposer/attester rewards.
+//!
+//! Events: `Staked(address indexed, uint256)`, `Unstaked(address indexed, uint64 exitView)`,
+//! `Withdrawn(address indexed, uint256)`, `RewardsClaimed(address indexed, uint256)`.
+//!
+//! Storage: slot 0 is `mapping(address => uint256) stakes`, slot 1 is
+//! `mapping(address => uint64) exitedAt` (view the validator left the committee, 0 if
+//! none); locked funds are held in the contract balance. The activation/exit queues stay
+//! in ConsensusState, since consensus rotates the committee from them at the end of every
+//! block; so do accrued rewards, which the executor credits on every block and which are
+//! minted when claimed.
+
+use super::{address_word, adjust_balance, charge, event_topic, mapping_slot, state_err};
+use crate::crypto::{Hash, PublicKey};
+use crate::precompiles::{Precompile, PrecompileContext, PrecompileError, PrecompileOutput};
+use crate::state::{StateError, StateManager};
+use crate::storage::{AccountInfo, Storage, StorageError};
+use crate::types::{Address, Bytes, Log, U256, View, keccak256};
+use revm::Database;
+
+pub const STAKING_ADDRESS: Address = Address::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x00,
+]);
+
+/// Default minimum stake (see `ChainParams::min_stake`).
+pub const MIN_STAKE: u64 = 2000;
+pub const ACTIVATION_DELAY: View = 10;
+pub const EXIT_DELAY: View = 10;
+/// Default views between leaving the committee and withdrawing (see
+/// `ChainParams::withdrawal_delay`).
+pub const WITHDRAWAL_DELAY: View = 100;
+/// Default reward for proposing a block (see `ChainParams::proposer_reward`).
+pub const PROPOSER_REWARD: u64 = 20;
+/// Default reward per signature in a block's justify QC (see `ChainParams::attester_reward`).
+pub const ATTESTER_REWARD: u64 = 5;
+
+pub const STAKE_GAS: u64 = 40_000;
+pub const UNSTAKE_GAS: u64 = 25_000;
+pub const WITHDRAW_GAS: u64 = 30_000;
+pub const CLAIM_REWARDS_GAS: u64 = 30_000;
+
+pub const STAKE_SELECTOR: [u8; 4] = [0x3a, 0x4b, 0x66, 0xf1];
+pub const UNSTAKE_SELECTOR: [u8; 4] = [0x2e, 0x17, 0xde, 0x78];
+pub const WITHDRAW_SELECTOR: [u8; 4] = [0x3c, 0xcf, 0xd6, 0x0b];
+pub const CLAIM_REWARDS_SELECTOR: [u8; 4] = [0x37, 0x25, 0x00, 0xab];
+
+pub const STAKED_EVENT: &str = "Staked(address,uint256)";
+pub const UNSTAKED_EVENT: &str = "Unstaked(address,uint64)";
+pub const WITHDRAWN_EVENT: &str = "Withdrawn(address,uint256)";
+pub const REWARDS_CLAIMED_EVENT: &str = "RewardsClaimed(address,uint256)";
+
+const STAKES_SLOT: u64 = 0;
+const EXITED_AT_SLOT: u64 = 1;
+
+/// Address controlled by a validator key (receives refunds, indexes the stake).
+pub fn validator_address(pk: &PublicKey) -> Address {
+    Address::from_slice(&keccak256(pk.0.to_bytes())[12..])
+}
+
+pub fn stake_slot(validator: Address) -> U256 {
+    mapping_slot(validator, STAKES_SLOT)
+}
+
+pub fn stake_of(db: &mut StateManager, validator: Address) -> Result<U256, StateError> {
+    db.storage(STAKING_ADDRESS, stake_slot(validator))
+}
+
+fn set_stake(db: &StateManager, validator: Address, amount: U256) -> Result<(), StateError> {
+    db.commit_storage(STAKING_ADDRESS, stake_slot(validator), amount)
+}
+
+pub fn exited_at_slot(validator: Address) -> U256 {
+    mapping_slot(validator, EXITED_AT_SLOT)
+}
+
+/// View at which the validator last left the committee, if it has not withdrawn since.
+pub fn exited_at(db: &mut StateManager, validator: Address) -> Result<Option<View>, StateError> {
+    let view = db.storage(STAKING_ADDRESS, exited_at_slot(validator))?;
+    Ok((view != U256::ZERO).then(|| view.saturating_to()))
+}
+
+/// Start the unbonding period of a validator leaving the committee (or the pending queue)
+/// at `view`.
+pub fn record_exit(db: &StateManager, validator: Address, view: View) -> Result<(), StateError> {
+    db.commit_storage(STAKING_ADDRESS, exited_at_slot(validator), U256::from(view))
+}
+
+/// Burn up to `amount` of a validator's stake (equivocation or inactivity penalties).
+/// Returns the remaining stake, or None if the validator has nothing staked.
+pub fn slash(
+    db: &mut StateManager,
+    validator: Address,
+    amount: U256,
+) -> Result<Option<U256>, StateError> {
+    let stake = stake_of(db, validator)?;
+    if stake == U256::ZERO {
+        return Ok(None);
+    }
+    let remaining = stake.saturating_sub(amount);
+    set_stake(db, validator, remaining)?;
+    adjust_balance(db, STAKING_ADDRESS, |b| b.saturating_sub(stake - remaining))?;
+    Ok(Some(remaining))
+}
+
+/// Seed the stakes of the genesis committee and fund the contract with the locked amount.
+pub fn init_genesis(
+    storage: &dyn Storage,
+    committee: &[PublicKey],
+    stake: U256,
+) -> Result<(), StorageError> {
+    for pk in committee {
+        storage.save_storage(&STAKING_ADDRESS, &stake_slot(validator_address(pk)), &stake)?;
+    }
+    let mut account = storage
+        .get_account(&STAKING_ADDRESS)?
+        .unwrap_or_else(|| AccountInfo {
+            code_hash: Hash(keccak256([]).into()),
+            ..Default::default()
+        });
+    account.balance += stake * U256::from(committee.len());
+    storage.save_account(&STAKING_ADDRESS, &account)
+}
+
+fn event(signature: &str, validator: Address, data: U256) -> Log {
+    Log {
+        address: STAKING_ADDRESS,
+        topics: vec![event_topic(signature), Hash(address_word(validator))],
+        data: Bytes::from(data.to_be_bytes::<32>().to_vec()),
+    }
+}
+
+pub struct StakingContract;
+
+impl StakingContract {
+    fn stake(
+        ctx: &mut PrecompileContext<'_>,
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, STAKE_GAS)?;
+        let tx = ctx.tx;
+        let db = &mut *ctx.db;
+
+        let mut state = db
+            .get_consensus_state()
+            .map_err(state_err)?
+            .ok_or_else(|| state_err("missing consensus state"))?;
+        if tx.value < state.params.min_stake {
+            return Err(PrecompileError::Revert(format!(
+                "stake {} below minimum {}",
+                tx.value, state.params.min_stake
+            )));
+        }
+
+        // 1. Lock Funds (value is moved into the contract balance by the executor)
+        let validator = tx.sender();
+        let current = stake_of(db, validator).map_err(state_err)?;
+        set_stake(db, validator, current + tx.value).map_err(state_err)?;
+
+        // 2. Add to Pending (if not already active/pending)
+        let pk = tx.public_key.clone();
+        let is_active = state.committee.contains(&pk);
+        let is_pending = state.pending_validators.iter().any(|(p, _)| *p == pk);
+        if !is_active && !is_pending {
+            let activation_view = ctx.block.view + ACTIVATION_DELAY;
+            state.pending_validators.push((pk.clone(), activation_view));
+            db.save_consensus_state(&state).map_err(state_err)?;
+            tracing::info!("Validator Pending: {:?} until view {}", pk, activation_view);
+        }
+
+        Ok(PrecompileOutput {
+            gas_used: STAKE_GAS,
+            output: vec![],
+            logs: vec![event(STAKED_EVENT, validator, tx.value)],
+        })
+    }
+
+    fn unstake(
+        ctx: &mut PrecompileContext<'_>,
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, UNSTAKE_GAS)?;
+        let tx = ctx.tx;
+        let db = &mut *ctx.db;
+
+        let mut state = db
+            .get_consensus_state()
+            .map_err(state_err)?
+            .ok_or_else(|| state_err("missing consensus state"))?;
+
+        // Must be Active (and not already leaving) to Unstake
+        let pk = tx.public_key.clone();
+        if !state.committee.contains(&pk) {
+            return Err(PrecompileError::Revert("not an active validator".into()));
+        }
+        if state.exiting_validators.iter().any(|(p, _)| *p == pk) {
+            return Err(PrecompileError::Revert("exit already scheduled".into()));
+        }
+
+        let exit_view = ctx.block.view + EXIT_DELAY;
+        state.exiting_validators.push((pk.clone(), exit_view));
+        db.save_consensus_state(&state).map_err(state_err)?;
+        tracing::info!("Validator Exiting: {:?} at view {}", pk, exit_view);
+
+        Ok(PrecompileOutput {
+            gas_used: UNSTAKE_GAS,
+            output: vec![],
+            logs: vec![event(UNSTAKED_EVENT, tx.sender(), U256::from(exit_view))],
+        })
+    }
+
+    fn withdraw(
+        ctx: &mut PrecompileContext<'_>,
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, WITHDRAW_GAS)?;
+        let tx = ctx.tx;
+        let db = &mut *ctx.db;
+
+        let state = db
+            .get_consensus_state()
+            .map_err(state_err)?
+            .ok_or_else(|| state_err("missing consensus state"))?;
+
+        let pk = tx.public_key.clone();
+        let is_active = state.committee.contains(&pk);
+        let is_pending = state.pending_validators.iter().any(|(p, _)| *p == pk);
+        let is_exiting = state.exiting_validators.iter().any(|(p, _)| *p == pk);
+        if is_active || is_pending || is_exiting {
+            return Err(PrecompileError::Revert("validator still bonded".into()));
+        }
+
+        let validator = tx.sender();
+        let stake = stake_of(db, validator).map_err(state_err)?;
+        if stake == U256::ZERO {
+            return Err(PrecompileError::Revert("nothing to withdraw".into()));
+        }
+
+        // Unbonding: stake stays locked (and slashable) for a while after the exit
+        if let Some(exited) = exited_at(db, validator).map_err(state_err)? {
+            let unlocked_at = exited.saturating_add(state.params.unbonding_period());
+            if ctx.block.view < unlocked_at {
+                return Err(PrecompileError::Revert(format!(
+                    "stake unbonding until view {}",
+                    unlocked_at
+                )));
+            }
+        }
+
+        // Refund from the contract balance
+        set_stake(db, validator, U256::ZERO).map_err(state_err)?;
+        db.commit_storage(STAKING_ADDRESS, exited_at_slot(validator), U256::ZERO)
+            .map_err(state_err)?;
+        adjust_balance(db, STAKING_ADDRESS, |b| b.saturating_sub(stake)).map_err(state_err)?;
+        adjust_balance(db, validator, |b| b + stake).map_err(state_err)?;
+        tracing::info!("Withdrawn Stake: {:?} for {:?}", stake, validator);
+
+        Ok(PrecompileOutput {
+            gas_used: WITHDRAW_GAS,
+            output: vec![],
+            logs: vec![event(WITHDRAWN_EVENT, validator, stake)],
+        })
+    }
+
+    fn claim_rewards(
+        ctx: &mut PrecompileContext<'_>,
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        charge(gas_limit, CLAIM_REWARDS_GAS)?;
+        let tx = ctx.tx;
+        let db = &mut *ctx.db;
+
+        let reward = db.get_reward(&tx.public_key).map_err(state_err)?;
+        if reward == U256::ZERO {
+            return Err(PrecompileError::Revert("no rewards to claim".into()));
+        }
+        db.save_reward(&tx.public_key, U256::ZERO)
+            .map_err(state_err)?;
+
+        // Rewards are newly issued, not paid from locked stake
+        let validator = tx.sender();
+        adjust_balance(db, validator, |b| b + reward).map_err(state_err)?;
+        tracing::info!("Claimed Rewards: {:?} for {:?}", reward, validator);
+
+        Ok(PrecompileOutput {
+            gas_used: CLAIM_REWARDS_GAS,
+            output: vec![],
+            logs: vec![event(REWARDS_CLAIMED_EVENT, validator, reward)],
+        })
+    }
+}
+
+impl Precompile for StakingContract {
+    fn name(&self) -> &'static str {
+        "staking"
+    }
+
+    fn call(
+        &self,
+        ctx: &mut PrecompileContext<'_>,
+        input: &[u8],
+        gas_limit: u64,
+    ) -> Result<PrecompileOutput, PrecompileError> {
+        if input.len() < 4 {
+            return Err(PrecompileError::Revert("missing function selector".into()));
+        }
+        match [input[0], input[1], input[2], input[3]] {
+            STAKE_SELECTOR => Self::stake(ctx, gas_limit),
+            UNSTAKE_SELECTOR => Self::unstake(ctx, gas_limit),
+            WITHDRAW_SELECTOR => Self::withdraw(ctx, gas_limit),
+            CLAIM_REWARDS_SELECTOR => Self::claim_rewards(ctx, gas_limit),
+            _ => Err(PrecompileError::Revert("unknown function selector".into())),
+        }
+    }
+}
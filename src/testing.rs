@@ -0,0 +1,591 @@
+//! In-process multi-node simulation.
+//!
+//! `SimNetwork` wires N `SimplexState`s through an in-memory message bus driven by a
+//! simulated clock. Message latency and drops come from a seeded RNG and deliveries
+//! are ordered by (time, send order), so a run is reproducible from its `SimConfig`.
+//! Nodes can be killed, restarted from their storage, partitioned or turned
+//! Byzantine, and every finalization is checked against the others for safety.
+//! With `wall_clock`, blocks carry real timestamps, so the network can also be driven in
+//! real time (`crate::devnet`).
+
+use crate::consensus::{ConsensusAction, SimplexState, unix_time};
+use crate::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, hash_data, sign};
+use crate::state::StateManager;
+use crate::storage::{MemStorage, Storage};
+use crate::tx_pool::TxPool;
+use crate::types::{
+    Address, AggregateVote, Block, DEFAULT_BLOCK_GAS_LIMIT, DEFAULT_CHAIN_ID, DecryptionShare,
+    EquivocationEvidence, ProposalEquivocationEvidence, View, Vote, VoteType, vote_message,
+};
+use crate::vm::Executor;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+    pub nodes: usize,
+    pub seed: u64,
+    /// Latency of each message is drawn uniformly from this range (inclusive).
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    /// Probability (0.0..=1.0) that a message between two nodes is lost.
+    pub drop_rate: f64,
+    pub view_timeout_ms: u64,
+    /// Clock resolution: `step` advances time by this much.
+    pub tick_ms: u64,
+    /// Stamp blocks with the system time instead of 0 (runs are then not reproducible).
+    pub wall_clock: bool,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 4,
+            seed: 0,
+            min_latency_ms: 10,
+            max_latency_ms: 50,
+            drop_rate: 0.0,
+            view_timeout_ms: 3_000,
+            tick_ms: 100,
+            wall_clock: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SimMessage {
+    Block(Block),
+    Vote(Vote),
+    Aggregate(AggregateVote),
+    Request(Hash),
+    Response(Block),
+    Evidence(EquivocationEvidence),
+    ProposalEvidence(ProposalEquivocationEvidence),
+    DecryptionShares(Vec<DecryptionShare>),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimStats {
+    pub sent: u64,
+    pub delivered: u64,
+    pub dropped: u64,
+    /// Equivocation or double proposal evidence broadcast by any node.
+    pub evidence: u64,
+    /// Vote aggregates relayed by any node.
+    pub aggregates: u64,
+}
+
+/// A way for a Byzantine node to deviate from the protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Misbehavior {
+    /// Send a second, conflicting notarize vote alongside every real one.
+    EquivocateVotes,
+    /// Send a second, differently signed block alongside every proposal.
+    ConflictingProposals,
+    /// Never send its proposals.
+    WithholdProposals,
+    /// Propose blocks with a state root that does not match their execution.
+    InvalidStateRoot,
+    /// Send nothing at all (while still following the chain locally).
+    Stall,
+}
+
+/// Rewrites the outgoing messages of an otherwise honest `SimplexState`.
+pub struct ByzantineNode {
+    pub behaviors: HashSet<Misbehavior>,
+    key: PrivateKey,
+}
+
+impl ByzantineNode {
+    pub fn new(key: PrivateKey, behaviors: &[Misbehavior]) -> Self {
+        Self {
+            behaviors: behaviors.iter().copied().collect(),
+            key,
+        }
+    }
+
+    pub fn has(&self, behavior: Misbehavior) -> bool {
+        self.behaviors.contains(&behavior)
+    }
+
+    /// The actions the node actually sends instead of `actions`.
+    pub fn corrupt(&self, actions: Vec<ConsensusAction>) -> Vec<ConsensusAction> {
+        if self.has(Misbehavior::Stall) {
+            return vec![];
+        }
+        let mut out = Vec::new();
+        for action in actions {
+            match action {
+                ConsensusAction::BroadcastBlock(block) if !block.is_dummy => {
+                    if self.has(Misbehavior::WithholdProposals) {
+                        continue;
+                    }
+                    let mut block = block;
+                    if self.has(Misbehavior::InvalidStateRoot) {
+                        block.state_root = hash_data(&block.state_root);
+                        block.sign(&self.key);
+                    }
+                    if self.has(Misbehavior::ConflictingProposals) {
+                        let mut twin = block.clone();
+                        twin.metadata.fee_recipient = Address::from_slice(&[0xbb; 20]);
+                        twin.sign(&self.key);
+                        out.push(ConsensusAction::BroadcastBlock(twin));
+                    }
+                    out.push(ConsensusAction::BroadcastBlock(block));
+                }
+                ConsensusAction::BroadcastVote(vote)
+                    if vote.vote_type == VoteType::Notarize
+                        && vote.block_hash != Hash::default()
+                        && self.has(Misbehavior::EquivocateVotes) =>
+                {
+                    let block_hash = hash_data(&vote.block_hash);
+                    let twin = Vote {
+                        block_hash,
+                        signature: sign(
+                            &self.key,
+                            &vote_message(vote.vote_type, vote.view, &block_hash, DEFAULT_CHAIN_ID),
+                        ),
+                        ..vote.clone()
+                    };
+                    out.push(ConsensusAction::BroadcastVote(vote));
+                    out.push(ConsensusAction::BroadcastVote(twin));
+                }
+                action => out.push(action),
+            }
+        }
+        out
+    }
+}
+
+struct SimNode {
+    state: SimplexState,
+    tx_pool: Arc<TxPool>,
+    executor: Executor,
+    timer_start: u64,
+    last_view: View,
+}
+
+pub struct SimNetwork {
+    pub config: SimConfig,
+    keys: Vec<(PublicKey, PrivateKey)>,
+    committee: Vec<PublicKey>,
+    storages: Vec<Arc<MemStorage>>,
+    nodes: Vec<Option<SimNode>>,
+    byzantine: HashMap<usize, ByzantineNode>,
+    // (deliver at, send order) -> (from, to, message)
+    queue: BTreeMap<(u64, u64), (usize, usize, SimMessage)>,
+    // Messages across the partition, delivered when it heals
+    held: Vec<(usize, usize, SimMessage)>,
+    partition: Option<HashSet<usize>>,
+    rng: StdRng,
+    seq: u64,
+    now: u64,
+    stats: SimStats,
+    // Finalized (non-dummy) blocks by view, across all nodes
+    finalized: BTreeMap<View, Hash>,
+}
+
+impl SimNetwork {
+    /// Start every node of a fresh committee (keys derived from the node index).
+    pub fn new(config: SimConfig) -> Self {
+        let n = config.nodes;
+        let keys: Vec<_> = (0..n as u64).map(generate_keypair_from_id).collect();
+        let committee = keys.iter().map(|k| k.0.clone()).collect();
+        let mut net = Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            keys,
+            committee,
+            storages: (0..n).map(|_| Arc::new(MemStorage::new())).collect(),
+            nodes: (0..n).map(|_| None).collect(),
+            byzantine: HashMap::new(),
+            queue: BTreeMap::new(),
+            held: Vec::new(),
+            partition: None,
+            seq: 0,
+            now: 0,
+            stats: SimStats::default(),
+            finalized: BTreeMap::new(),
+        };
+        for i in 0..n {
+            net.restart(i);
+        }
+        net
+    }
+
+    /// Current simulated time in milliseconds.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn stats(&self) -> &SimStats {
+        &self.stats
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, i: usize) -> Option<&SimplexState> {
+        self.nodes[i].as_ref().map(|n| &n.state)
+    }
+
+    pub fn node_mut(&mut self, i: usize) -> Option<&mut SimplexState> {
+        self.nodes[i].as_mut().map(|n| &mut n.state)
+    }
+
+    pub fn storage(&self, i: usize) -> Arc<MemStorage> {
+        self.storages[i].clone()
+    }
+
+    /// Transaction pool of a live node (replaced when it restarts).
+    pub fn tx_pool(&self, i: usize) -> Option<Arc<TxPool>> {
+        self.nodes[i].as_ref().map(|n| n.tx_pool.clone())
+    }
+
+    /// Executor of a live node (replaced when it restarts).
+    pub fn executor(&self, i: usize) -> Option<Executor> {
+        self.nodes[i].as_ref().map(|n| n.executor.clone())
+    }
+
+    pub fn is_alive(&self, i: usize) -> bool {
+        self.nodes[i].is_some()
+    }
+
+    pub fn leader_of(&self, view: View) -> usize {
+        (view as usize) % self.committee.len()
+    }
+
+    /// Make node `i` misbehave from now on (it survives restarts).
+    pub fn make_byzantine(&mut self, i: usize, behaviors: &[Misbehavior]) {
+        let node = ByzantineNode::new(self.keys[i].1.clone(), behaviors);
+        self.byzantine.insert(i, node);
+    }
+
+    pub fn is_byzantine(&self, i: usize) -> bool {
+        self.byzantine.contains_key(&i)
+    }
+
+    /// Stop a node. Its storage survives; messages to it are lost while it is down.
+    pub fn kill(&mut self, i: usize) {
+        self.nodes[i] = None;
+    }
+
+    /// Start (or restart) a node from its storage with a fresh tx pool, like `main` does.
+    pub fn restart(&mut self, i: usize) {
+        let storage = self.storages[i].clone();
+        let initial_root = storage
+            .get_chain_head()
+            .ok()
+            .flatten()
+            .map(|head| head.state_root);
+        let tx_pool = Arc::new(TxPool::new(storage.clone()));
+        let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), initial_root)));
+        let executor = Executor::new(state_manager, DEFAULT_BLOCK_GAS_LIMIT);
+        let mut state = SimplexState::new(
+            self.keys[i].0.clone(),
+            self.keys[i].1.clone(),
+            self.committee.clone(),
+            storage,
+            tx_pool.clone(),
+            executor.clone(),
+            DEFAULT_BLOCK_GAS_LIMIT,
+        )
+        // Wall-clock timestamps would make runs differ
+        .with_clock(if self.config.wall_clock {
+            unix_time
+        } else {
+            || 0
+        });
+        if let Err(e) = state.recover() {
+            tracing::warn!("Sim node {} failed to recover: {:?}", i, e);
+        }
+        let last_view = state.current_view;
+        self.nodes[i] = Some(SimNode {
+            state,
+            tx_pool,
+            executor,
+            timer_start: self.now,
+            last_view,
+        });
+
+        // Consensus starts: propose right away if we lead the current view
+        let result = self.nodes[i].as_mut().map(|n| n.state.try_propose());
+        if let Some(Ok(actions)) = result {
+            self.dispatch(i, actions);
+        }
+    }
+
+    /// Cut `group` off from the rest of the committee. Messages across the cut are
+    /// held back until `heal`.
+    pub fn partition(&mut self, group: &[usize]) {
+        self.partition = Some(group.iter().copied().collect());
+    }
+
+    pub fn heal(&mut self) {
+        self.partition = None;
+        for (from, to, message) in std::mem::take(&mut self.held) {
+            self.enqueue(from, to, message);
+        }
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        self.partition.is_some()
+    }
+
+    /// Advance the clock by one tick: deliver the messages that are due, then fire
+    /// the view timers that expired.
+    pub fn step(&mut self) {
+        self.now += self.config.tick_ms;
+        while let Some(entry) = self.queue.first_entry()
+            && entry.key().0 <= self.now
+        {
+            let (from, to, message) = entry.remove();
+            self.deliver(from, to, message);
+        }
+        self.fire_timeouts();
+    }
+
+    /// Step until `done` holds (returns true) or `time_limit_ms` of simulated time
+    /// has passed (returns false).
+    pub fn run_until(
+        &mut self,
+        time_limit_ms: u64,
+        mut done: impl FnMut(&SimNetwork) -> bool,
+    ) -> bool {
+        let deadline = self.now + time_limit_ms;
+        while !done(self) {
+            if self.now >= deadline {
+                return false;
+            }
+            self.step();
+        }
+        true
+    }
+
+    /// Highest view any live node has reached.
+    pub fn max_view(&self) -> View {
+        self.nodes
+            .iter()
+            .flatten()
+            .map(|n| n.state.current_view)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Highest view with a finalized block, across all nodes.
+    pub fn last_finalized(&self) -> View {
+        self.finalized.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Finalized (non-dummy) blocks by view, across all nodes.
+    pub fn finalized(&self) -> &BTreeMap<View, Hash> {
+        &self.finalized
+    }
+
+    fn is_cut(&self, from: usize, to: usize) -> bool {
+        self.partition
+            .as_ref()
+            .is_some_and(|group| group.contains(&from) != group.contains(&to))
+    }
+
+    fn enqueue(&mut self, from: usize, to: usize, message: SimMessage) {
+        let latency = self
+            .rng
+            .gen_range(self.config.min_latency_ms..=self.config.max_latency_ms);
+        self.seq += 1;
+        self.queue
+            .insert((self.now + latency, self.seq), (from, to, message));
+    }
+
+    fn send(&mut self, from: usize, to: usize, message: SimMessage) {
+        self.stats.sent += 1;
+        if self.is_cut(from, to) {
+            self.held.push((from, to, message));
+        } else if self.config.drop_rate > 0.0 && self.rng.gen_bool(self.config.drop_rate) {
+            self.stats.dropped += 1;
+        } else {
+            self.enqueue(from, to, message);
+        }
+    }
+
+    fn broadcast(&mut self, from: usize, message: SimMessage) {
+        for to in 0..self.nodes.len() {
+            if to != from {
+                self.send(from, to, message.clone());
+            }
+        }
+    }
+
+    /// Route consensus actions the way the node's event loop does (own votes loop back).
+    /// A Byzantine node sends its corrupted actions but still processes its real votes.
+    fn dispatch(&mut self, from: usize, actions: Vec<ConsensusAction>) {
+        let outgoing = match self.byzantine.get(&from) {
+            Some(byzantine) => byzantine.corrupt(actions.clone()),
+            None => actions.clone(),
+        };
+        for action in outgoing {
+            self.send_action(from, action);
+        }
+        for action in actions {
+            if let ConsensusAction::BroadcastVote(vote) = action {
+                self.vote(from, vote);
+            }
+        }
+    }
+
+    fn send_action(&mut self, from: usize, action: ConsensusAction) {
+        match action {
+            ConsensusAction::BroadcastVote(vote) => self.broadcast(from, SimMessage::Vote(vote)),
+            ConsensusAction::BroadcastAggregate(aggregate) => {
+                self.stats.aggregates += 1;
+                self.broadcast(from, SimMessage::Aggregate(aggregate));
+            }
+            ConsensusAction::BroadcastBlock(block) => {
+                self.broadcast(from, SimMessage::Block(block))
+            }
+            ConsensusAction::BroadcastRequest(hash) => {
+                self.broadcast(from, SimMessage::Request(hash))
+            }
+            ConsensusAction::SendBlock(block, peer) => {
+                let to = peer.parse().expect("peer ids are node indices");
+                self.send(from, to, SimMessage::Response(block));
+            }
+            // Simulated gossip carries full blocks
+            ConsensusAction::RequestTransactions(..) | ConsensusAction::RequestBody(_) => {}
+            ConsensusAction::BroadcastEvidence(evidence) => {
+                self.stats.evidence += 1;
+                self.broadcast(from, SimMessage::Evidence(evidence));
+            }
+            ConsensusAction::BroadcastProposalEvidence(evidence) => {
+                self.stats.evidence += 1;
+                self.broadcast(from, SimMessage::ProposalEvidence(evidence));
+            }
+            ConsensusAction::BroadcastDecryptionShares(shares) => {
+                self.broadcast(from, SimMessage::DecryptionShares(shares))
+            }
+        }
+    }
+
+    fn deliver(&mut self, from: usize, to: usize, message: SimMessage) {
+        match message {
+            SimMessage::Vote(vote) => return self.vote(to, vote),
+            SimMessage::Aggregate(aggregate) => return self.aggregate(to, aggregate),
+            _ => {}
+        }
+        let Some(node) = self.nodes[to].as_mut() else {
+            return; // Dead nodes drop their messages
+        };
+        self.stats.delivered += 1;
+        let peer = from.to_string();
+        let result = match message {
+            SimMessage::Block(block) => node.state.on_proposal_from(block, peer),
+            SimMessage::Request(hash) => node.state.on_block_request(hash, peer),
+            SimMessage::Response(block) => node.state.on_block_response_from(block, peer),
+            SimMessage::Evidence(evidence) => {
+                node.state.evidence_pool.add_evidence(evidence);
+                Ok(vec![])
+            }
+            SimMessage::ProposalEvidence(evidence) => {
+                node.state.evidence_pool.add_proposal_evidence(evidence);
+                Ok(vec![])
+            }
+            SimMessage::DecryptionShares(shares) => {
+                node.state.on_decryption_shares(shares).map(|_| vec![])
+            }
+            SimMessage::Vote(_) | SimMessage::Aggregate(_) => unreachable!(),
+        };
+        self.observe_view(to);
+        if let Ok(actions) = result {
+            self.dispatch(to, actions);
+        }
+    }
+
+    fn vote(&mut self, to: usize, vote: Vote) {
+        let Some(node) = self.nodes[to].as_mut() else {
+            return;
+        };
+        self.stats.delivered += 1;
+        let before = node.state.finalized_height;
+        let result = node.state.on_vote(vote.clone());
+        let finalized_now = node.state.finalized_height > before;
+        self.observe_view(to);
+
+        // The finalize vote that crosses the threshold decides what gets committed
+        if finalized_now
+            && vote.vote_type == VoteType::Finalize
+            && vote.block_hash != Hash::default()
+        {
+            self.record_finalized(to, vote.view, vote.block_hash);
+        }
+        if let Ok(actions) = result {
+            self.dispatch(to, actions);
+        }
+    }
+
+    fn aggregate(&mut self, to: usize, aggregate: AggregateVote) {
+        let Some(node) = self.nodes[to].as_mut() else {
+            return;
+        };
+        self.stats.delivered += 1;
+        let before = node.state.finalized_height;
+        let result = node.state.on_aggregate_vote(aggregate.clone());
+        let finalized_now = node.state.finalized_height > before;
+        self.observe_view(to);
+
+        if finalized_now
+            && aggregate.vote_type == VoteType::Finalize
+            && aggregate.block_hash != Hash::default()
+        {
+            self.record_finalized(to, aggregate.view, aggregate.block_hash);
+        }
+        if let Ok(actions) = result {
+            self.dispatch(to, actions);
+        }
+    }
+
+    fn record_finalized(&mut self, node: usize, view: View, hash: Hash) {
+        let existing = *self.finalized.entry(view).or_insert(hash);
+        assert_eq!(
+            existing, hash,
+            "Double finalization in view {}: node {} finalized {:?}, another node {:?}",
+            view, node, hash, existing
+        );
+    }
+
+    /// Reset the view timer when a node advances (as the node's event loop does).
+    fn observe_view(&mut self, i: usize) {
+        let now = self.now;
+        if let Some(node) = self.nodes[i].as_mut()
+            && node.state.current_view > node.last_view
+        {
+            node.last_view = node.state.current_view;
+            node.timer_start = now;
+        }
+    }
+
+    fn fire_timeouts(&mut self) {
+        for i in 0..self.nodes.len() {
+            let now = self.now;
+            let timeout = self.config.view_timeout_ms;
+            let Some(node) = self.nodes[i].as_mut() else {
+                continue;
+            };
+            if now - node.timer_start < timeout {
+                continue;
+            }
+            node.timer_start = now;
+            let view = node.state.current_view;
+            let result = node.state.on_timeout(view);
+            if let Ok(actions) = result {
+                self.dispatch(i, actions);
+            }
+        }
+    }
+}
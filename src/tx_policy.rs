@@ -0,0 +1,136 @@
+//! Transaction pool admission policies.
+//!
+//! `TxPool` runs its `TxValidator`s on every transaction it is offered
+//! (`add_transaction`) and again on the pooled candidates of each block
+//! (`get_transactions_for_block`), so operators can add rules (`with_validator`)
+//! without patching the pool. `DefaultTxValidator` holds the pool's own checks and
+//! always runs first.
+
+use crate::crypto::verify;
+use crate::storage::Storage;
+use crate::tx_pool::PoolError;
+use crate::types::{Address, Transaction, U256, validate_tx_stateless, wire_size};
+use std::collections::HashSet;
+
+/// What a validator can look at besides the transaction.
+pub struct PoolContext<'a> {
+    /// Chain the pool accepts transactions for.
+    pub chain_id: u64,
+    /// Largest encoded transaction the chain accepts (`ChainParams::max_tx_size`).
+    pub max_tx_size: u64,
+    /// State of the latest executed block.
+    pub storage: &'a dyn Storage,
+}
+
+pub trait TxValidator: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Check a transaction before it enters the pool.
+    fn validate(&self, tx: &Transaction, ctx: &PoolContext) -> Result<(), PoolError>;
+
+    /// Whether a pooled transaction may go into a block paying `base_fee`. Checked again
+    /// because the state, or the policy, may have changed since admission.
+    fn allow_in_block(&self, _tx: &Transaction, _base_fee: U256, _ctx: &PoolContext) -> bool {
+        true
+    }
+}
+
+/// Chain id, size, signature, stateless validity (`validate_tx_stateless`) and nonce (not
+/// below the account's) of the transaction.
+pub struct DefaultTxValidator;
+
+impl TxValidator for DefaultTxValidator {
+    fn name(&self) -> &'static str {
+        "default"
+    }
+
+    fn validate(&self, tx: &Transaction, ctx: &PoolContext) -> Result<(), PoolError> {
+        // The signature covers the chain id, so no replays across chains
+        if tx.chain_id != ctx.chain_id {
+            return Err(PoolError::InvalidChainId(ctx.chain_id, tx.chain_id));
+        }
+
+        // Blocks could not carry it (nor could gossip, past some size)
+        let size = wire_size(tx);
+        if size as u64 > ctx.max_tx_size {
+            return Err(PoolError::TooLarge(size, ctx.max_tx_size));
+        }
+
+        let sighash = tx.sighash();
+        if !verify(&tx.public_key, &sighash.0, &tx.signature) {
+            return Err(PoolError::InvalidSignature);
+        }
+
+        // The base fee is checked when the transaction is picked for a block
+        validate_tx_stateless(tx, None)?;
+
+        let account_nonce = ctx
+            .storage
+            .get_account(&tx.sender())
+            .map_err(|e| PoolError::StorageError(e.to_string()))?
+            .map_or(0, |account| account.nonce);
+        if tx.nonce < account_nonce {
+            return Err(PoolError::InvalidNonce(account_nonce, tx.nonce));
+        }
+        Ok(())
+    }
+}
+
+/// Rejects transactions sent from, or to, the listed addresses.
+pub struct AddressBlocklist(pub HashSet<Address>);
+
+impl AddressBlocklist {
+    fn blocked(&self, tx: &Transaction) -> Option<Address> {
+        let sender = tx.sender();
+        if self.0.contains(&sender) {
+            return Some(sender);
+        }
+        tx.to.filter(|to| self.0.contains(to))
+    }
+}
+
+impl TxValidator for AddressBlocklist {
+    fn name(&self) -> &'static str {
+        "address-blocklist"
+    }
+
+    fn validate(&self, tx: &Transaction, _ctx: &PoolContext) -> Result<(), PoolError> {
+        match self.blocked(tx) {
+            Some(address) => Err(PoolError::Rejected(
+                self.name(),
+                format!("address {} is blocked", address),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn allow_in_block(&self, tx: &Transaction, _base_fee: U256, _ctx: &PoolContext) -> bool {
+        self.blocked(tx).is_none()
+    }
+}
+
+/// Rejects transactions offering less than a minimum priority fee (tip) per gas.
+pub struct MinPriorityFee(pub U256);
+
+impl TxValidator for MinPriorityFee {
+    fn name(&self) -> &'static str {
+        "min-priority-fee"
+    }
+
+    fn validate(&self, tx: &Transaction, _ctx: &PoolContext) -> Result<(), PoolError> {
+        if tx.max_priority_fee_per_gas < self.0 {
+            return Err(PoolError::Rejected(
+                self.name(),
+                format!(
+                    "priority fee {} below the minimum {}",
+                    tx.max_priority_fee_per_gas, self.0
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn allow_in_block(&self, tx: &Transaction, base_fee: U256, _ctx: &PoolContext) -> bool {
+        crate::payload::effective_tip(tx, base_fee) >= self.0
+    }
+}
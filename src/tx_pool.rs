@@ -1,20 +1,45 @@
-use crate::crypto::{Hash, verify};
+use crate::crypto::Hash;
+use crate::memory::{MemoryBudget, MemoryHandle, transaction_size};
+use crate::payload::{PayloadBuilder, PayloadRequest, PriorityFeeBuilder};
 use crate::storage::Storage;
-use crate::types::Transaction;
-use std::collections::{HashMap, VecDeque};
+use crate::tx_policy::{DefaultTxValidator, PoolContext, TxValidator};
+use crate::types::{Address, Block, ChainParams, DEFAULT_CHAIN_ID, Transaction, View};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use thiserror::Error;
 
+/// How often local transactions still pending are gossiped again and the pool contents
+/// announced to peers. Longer than the gossipsub duplicate cache, so a re-publish is not
+/// dropped as a duplicate.
+pub const REBROADCAST_INTERVAL: Duration = Duration::from_secs(120);
+
+/// Most transaction hashes announced in one `SyncMessage::PoolHashes`.
+pub const MAX_ANNOUNCED_HASHES: usize = 4096;
+
+/// Byte budget of the transactions in one `SyncMessage::ResponsePoolTransactions`.
+pub const MAX_POOL_RESPONSE_BYTES: usize = 1024 * 1024;
+
 #[derive(Debug, Error)]
 pub enum PoolError {
     #[error("Transaction already exists")]
     AlreadyExists,
     #[error("Invalid signature")]
     InvalidSignature,
+    #[error("Invalid chain id: expected {0}, got {1}")]
+    InvalidChainId(u64, u64),
     #[error("Invalid Nonce: expected {0}, got {1}")]
     InvalidNonce(u64, u64),
     #[error("Storage Error: {0}")]
     StorageError(String),
+    #[error("Transaction too large: {0} bytes, limit {1}")]
+    TooLarge(usize, u64),
+    #[error("Pool memory budget exceeded")]
+    PoolFull,
+    #[error("Invalid transaction: {0}")]
+    Invalid(#[from] crate::types::TxValidityError),
+    #[error("Rejected by {0} policy: {1}")]
+    Rejected(&'static str, String),
 }
 
 /// A simple Transaction Pool (Mempool).
@@ -28,6 +53,22 @@ pub struct TxPool {
     queue: Arc<Mutex<VecDeque<Hash>>>,
     // Storage access for nonce check
     storage: Arc<dyn Storage>,
+    // Registration with the node memory budget (None = unbounded)
+    memory: Option<Arc<MemoryHandle>>,
+    // Transactions for other chains are rejected
+    chain_id: u64,
+    // Transactions submitted to this node (RPC), rebroadcast until included
+    local: Arc<Mutex<HashSet<Hash>>>,
+    // Admission policies, `DefaultTxValidator` first
+    validators: Vec<Arc<dyn TxValidator>>,
+    // Transactions taken by stored blocks not finalized yet, by block hash
+    included: Arc<Mutex<HashMap<Hash, IncludedBlock>>>,
+}
+
+// Transactions of a block that may still be abandoned, with whether each was local
+struct IncludedBlock {
+    view: View,
+    txs: Vec<(Transaction, bool)>,
 }
 
 impl TxPool {
@@ -36,32 +77,72 @@ impl TxPool {
             transactions: Arc::new(Mutex::new(HashMap::new())),
             queue: Arc::new(Mutex::new(VecDeque::new())),
             storage,
+            memory: None,
+            chain_id: DEFAULT_CHAIN_ID,
+            local: Arc::new(Mutex::new(HashSet::new())),
+            validators: vec![Arc::new(DefaultTxValidator)],
+            included: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    /// Add a transaction to the pool.
-    pub fn add_transaction(&self, tx: Transaction) -> Result<(), PoolError> {
-        // 1. Validate Signature
-        let sighash = tx.sighash();
-        if !verify(&tx.public_key, &sighash.0, &tx.signature) {
-            return Err(PoolError::InvalidSignature);
+    /// Accept transactions for `chain_id` (the chain spec's) instead of the default.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Also run `validator` on the transactions offered to the pool and on the block
+    /// candidates, after the default checks and the validators added before it.
+    pub fn with_validator(mut self, validator: Arc<dyn TxValidator>) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    /// Names of the admission policies, in the order they run.
+    pub fn validators(&self) -> Vec<&'static str> {
+        self.validators.iter().map(|v| v.name()).collect()
+    }
+
+    fn context(&self) -> PoolContext<'_> {
+        PoolContext {
+            chain_id: self.chain_id,
+            max_tx_size: self.chain_params().max_tx_size,
+            storage: self.storage.as_ref(),
         }
+    }
 
-        // 2. Validate Nonce
-        // Get sender account state
-        let sender = tx.sender();
-        let account_nonce = if let Some(account) = self
-            .storage
-            .get_account(&sender)
-            .map_err(|e| PoolError::StorageError(e.to_string()))?
-        {
-            account.nonce
-        } else {
-            0
-        };
+    /// Parameters of the latest executed block (the defaults before genesis).
+    fn chain_params(&self) -> ChainParams {
+        self.storage
+            .get_consensus_state()
+            .ok()
+            .flatten()
+            .map(|state| state.params)
+            .unwrap_or_default()
+    }
 
-        if tx.nonce < account_nonce {
-            return Err(PoolError::InvalidNonce(account_nonce, tx.nonce));
+    /// Account the pool against `budget`; the lowest-tip transactions are evicted when
+    /// the pool exceeds its share.
+    pub fn with_memory_budget(mut self, budget: &MemoryBudget) -> Self {
+        let handle = budget.register("tx_pool");
+        handle.set_usage(
+            self.transactions
+                .lock()
+                .unwrap()
+                .values()
+                .map(transaction_size)
+                .sum(),
+        );
+        self.memory = Some(Arc::new(handle));
+        self
+    }
+
+    /// Add a transaction to the pool.
+    pub fn add_transaction(&self, tx: Transaction) -> Result<(), PoolError> {
+        // Chain id, signature and nonce (`DefaultTxValidator`), then the operator's policies
+        let ctx = self.context();
+        for validator in &self.validators {
+            validator.validate(&tx, &ctx)?;
         }
 
         // TODO: Also check if nonce is already in pool? (Pending Nonce)
@@ -74,62 +155,163 @@ impl TxPool {
             return Err(PoolError::AlreadyExists);
         }
 
+        let size = transaction_size(&tx);
         text_map.insert(hash, tx);
-        self.queue.lock().unwrap().push_back(hash);
+        let mut queue = self.queue.lock().unwrap();
+        queue.push_back(hash);
+
+        if let Some(memory) = &self.memory {
+            memory.set_usage(memory.usage() + size);
+            Self::evict_excess(memory, &mut text_map, &mut queue);
+            if !text_map.contains_key(&hash) {
+                return Err(PoolError::PoolFull);
+            }
+        }
 
         Ok(())
     }
 
-    /// Get a batch of transactions for a new block, respecting the gas limit.
-    /// Ordered by Gas Price (max_fee_per_gas) Descending.
-    pub fn get_transactions_for_block(
-        &self,
-        block_gas_limit: u64,
-        base_fee: crate::types::U256,
-    ) -> Vec<Transaction> {
-        let mut pending = Vec::new();
+    /// Add a transaction submitted to this node; it is rebroadcast until it leaves the pool.
+    pub fn add_local_transaction(&self, tx: Transaction) -> Result<(), PoolError> {
+        let hash = crate::crypto::hash_data(&tx);
+        self.add_transaction(tx)?;
+        self.local.lock().unwrap().insert(hash);
+        Ok(())
+    }
+
+    /// Local transactions still pending (included or evicted ones are forgotten).
+    pub fn local_transactions(&self) -> Vec<Transaction> {
         let map = self.transactions.lock().unwrap();
+        let mut local = self.local.lock().unwrap();
+        local.retain(|hash| map.contains_key(hash));
+        local
+            .iter()
+            .filter_map(|hash| map.get(hash).cloned())
+            .collect()
+    }
 
-        // 1. Collect and Filter transactions
-        let mut all_txs: Vec<&Transaction> = map
-            .values()
-            .filter(|tx| tx.max_fee_per_gas >= base_fee)
-            .collect();
+    /// Hashes of the pending transactions, oldest first (at most `limit`).
+    pub fn hashes(&self, limit: usize) -> Vec<Hash> {
+        self.queue
+            .lock()
+            .unwrap()
+            .iter()
+            .take(limit)
+            .copied()
+            .collect()
+    }
 
-        // 2. Sort by Effective Tip Descending
-        // Effective Tip = min(max_priority_fee, max_fee - base_fee)
-        all_txs.sort_by(|a, b| {
-            let tip_a = std::cmp::min(a.max_priority_fee_per_gas, a.max_fee_per_gas - base_fee);
-            let tip_b = std::cmp::min(b.max_priority_fee_per_gas, b.max_fee_per_gas - base_fee);
-            let cmp = tip_b.cmp(&tip_a); // Descending
-            if cmp == std::cmp::Ordering::Equal {
-                // Secondary sort: Nonce Ascending for same sender
-                if a.public_key == b.public_key {
-                    a.nonce.cmp(&b.nonce)
-                } else {
-                    // Tertiary sort: Deterministic (Public Key)
-                    a.public_key.cmp(&b.public_key)
-                }
-            } else {
-                cmp
+    /// The announced `hashes` this pool does not hold.
+    pub fn missing(&self, hashes: &[Hash]) -> Vec<Hash> {
+        let map = self.transactions.lock().unwrap();
+        hashes
+            .iter()
+            .filter(|hash| !map.contains_key(hash))
+            .copied()
+            .collect()
+    }
+
+    /// The pooled transactions among `hashes`, up to `max_bytes` of them.
+    pub fn get_transactions(&self, hashes: &[Hash], max_bytes: usize) -> Vec<Transaction> {
+        let map = self.transactions.lock().unwrap();
+        let mut size = 0;
+        let mut txs = Vec::new();
+        for tx in hashes.iter().filter_map(|hash| map.get(hash)) {
+            size += transaction_size(tx);
+            if size > max_bytes {
+                break;
             }
-        });
+            txs.push(tx.clone());
+        }
+        txs
+    }
 
-        // 3. Select fitting transactions
-        let mut current_gas = 0u64;
+    /// Drop the lowest-tip transactions (highest nonce first) until the pool is back
+    /// within its memory allowance.
+    fn evict_excess(
+        memory: &MemoryHandle,
+        map: &mut HashMap<Hash, Transaction>,
+        queue: &mut VecDeque<Hash>,
+    ) {
+        let excess = memory.excess();
+        if excess == 0 {
+            return;
+        }
 
-        for tx in all_txs {
-            if current_gas + tx.gas_limit <= block_gas_limit {
-                pending.push(tx.clone());
-                current_gas += tx.gas_limit;
-            }
-            // Optimize: If block is full, break?
-            if current_gas >= block_gas_limit {
+        let mut candidates: Vec<(&Hash, &Transaction)> = map.iter().collect();
+        candidates.sort_by(|(_, a), (_, b)| {
+            a.max_priority_fee_per_gas
+                .cmp(&b.max_priority_fee_per_gas)
+                .then(b.nonce.cmp(&a.nonce))
+        });
+
+        let mut freed = 0;
+        let mut evicted = HashSet::new();
+        for (hash, tx) in candidates {
+            if freed >= excess {
                 break;
             }
+            freed += transaction_size(tx);
+            evicted.insert(*hash);
         }
 
-        pending
+        map.retain(|h, _| !evicted.contains(h));
+        queue.retain(|h| !evicted.contains(h));
+        memory.set_usage(memory.usage().saturating_sub(freed));
+        tracing::warn!(
+            "TxPool over memory budget: evicted {} transactions ({} bytes)",
+            evicted.len(),
+            freed
+        );
+    }
+
+    /// Get a batch of transactions for a new block, respecting the gas limit.
+    /// Ordered by Gas Price (max_fee_per_gas) Descending.
+    pub fn get_transactions_for_block(
+        &self,
+        block_gas_limit: u64,
+        base_fee: crate::types::U256,
+    ) -> Vec<Transaction> {
+        self.get_transactions_for_block_prioritized(block_gas_limit, base_fee, &HashSet::new())
+            .0
+    }
+
+    /// Like `get_transactions_for_block`, but transactions sent by `operators` are selected
+    /// first and placed at the top of the batch.
+    /// Returns the batch and the number of leading operator transactions.
+    pub fn get_transactions_for_block_prioritized(
+        &self,
+        block_gas_limit: u64,
+        base_fee: crate::types::U256,
+        operators: &HashSet<Address>,
+    ) -> (Vec<Transaction>, usize) {
+        let request = PayloadRequest {
+            gas_limit: block_gas_limit,
+            base_fee,
+            operators,
+            max_size: self.chain_params().max_block_size,
+            deadline: None,
+        };
+        let payload = PriorityFeeBuilder.build(self.candidates(base_fee), &request);
+        (payload.transactions, payload.operator_txs)
+    }
+
+    /// Pooled transactions whose max fee covers `base_fee` and that the admission
+    /// policies still allow (input of a `PayloadBuilder`).
+    pub fn candidates(&self, base_fee: crate::types::U256) -> Vec<Transaction> {
+        let ctx = self.context();
+        self.transactions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|tx| tx.max_fee_per_gas >= base_fee)
+            .filter(|tx| {
+                self.validators
+                    .iter()
+                    .all(|v| v.allow_in_block(tx, base_fee, &ctx))
+            })
+            .cloned()
+            .collect()
     }
 
     /// Remove transactions that were included in a block.
@@ -139,7 +321,10 @@ impl TxPool {
 
         for tx in txs {
             let hash = crate::crypto::hash_data(tx);
-            if map.remove(&hash).is_some() {
+            if let Some(removed) = map.remove(&hash) {
+                if let Some(memory) = &self.memory {
+                    memory.set_usage(memory.usage().saturating_sub(transaction_size(&removed)));
+                }
                 // Remove from queue is O(N). Vector might be better or LinkedHashMap.
                 // For MVP, simplistic rebuild or filter.
                 // Or just keep it simple.
@@ -150,6 +335,114 @@ impl TxPool {
         }
     }
 
+    /// Remove the transactions of `block` (stored, not finalized yet), keeping them aside
+    /// until the block is finalized or abandoned (see `settle_blocks`).
+    pub fn include_block(&self, block_hash: Hash, block: &Block) {
+        if block.payload.is_empty() {
+            return;
+        }
+        let txs = {
+            let local = self.local.lock().unwrap();
+            block
+                .payload
+                .iter()
+                .map(|tx| (tx.clone(), local.contains(&crate::crypto::hash_data(tx))))
+                .collect()
+        };
+        self.remove_transactions(&block.payload);
+        self.included.lock().unwrap().insert(
+            block_hash,
+            IncludedBlock {
+                view: block.view,
+                txs,
+            },
+        );
+    }
+
+    /// Blocks whose transactions are kept aside, with their views.
+    pub fn included_blocks(&self) -> Vec<(Hash, View)> {
+        self.included
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(hash, block)| (*hash, block.view))
+            .collect()
+    }
+
+    /// Forget the transactions of the `finalized` blocks, and return those of the
+    /// `abandoned` ones to the pool. Transactions no longer valid (e.g. included by the
+    /// finalized chain) are dropped. Returns how many were re-added.
+    pub fn settle_blocks(&self, finalized: &[Hash], abandoned: &[Hash]) -> usize {
+        let abandoned: Vec<IncludedBlock> = {
+            let mut included = self.included.lock().unwrap();
+            for hash in finalized {
+                included.remove(hash);
+            }
+            abandoned
+                .iter()
+                .filter_map(|hash| included.remove(hash))
+                .collect()
+        };
+        abandoned
+            .into_iter()
+            .flat_map(|block| block.txs)
+            .filter(|(tx, local)| {
+                let added = if *local {
+                    self.add_local_transaction(tx.clone())
+                } else {
+                    self.add_transaction(tx.clone())
+                };
+                added.is_ok()
+            })
+            .count()
+    }
+
+    /// Next nonce of `sender` after its pooled transactions that continue `account_nonce`
+    /// without a gap.
+    pub fn pending_nonce(&self, sender: Address, account_nonce: u64) -> u64 {
+        let nonces: HashSet<u64> = self
+            .transactions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|tx| tx.sender() == sender)
+            .map(|tx| tx.nonce)
+            .collect();
+        let mut nonce = account_nonce;
+        while nonces.contains(&nonce) {
+            nonce += 1;
+        }
+        nonce
+    }
+
+    pub fn get_transaction(&self, hash: &Hash) -> Option<Transaction> {
+        self.transactions.lock().unwrap().get(hash).cloned()
+    }
+
+    /// Write the pending local transactions to the storage journal (on shutdown), in
+    /// nonce order per sender. Returns how many were written.
+    pub fn save_journal(&self) -> Result<usize, PoolError> {
+        let mut local = self.local_transactions();
+        local.sort_by_key(|tx| (tx.sender(), tx.nonce));
+        self.storage
+            .save_pool_journal(&local)
+            .map_err(|e| PoolError::StorageError(e.to_string()))?;
+        Ok(local.len())
+    }
+
+    /// Re-add the journaled local transactions (on startup). Ones that no longer apply
+    /// (e.g. included before the restart) are skipped. Returns how many were re-added.
+    pub fn load_journal(&self) -> Result<usize, PoolError> {
+        let journal = self
+            .storage
+            .get_pool_journal()
+            .map_err(|e| PoolError::StorageError(e.to_string()))?;
+        Ok(journal
+            .into_iter()
+            .filter(|tx| self.add_local_transaction(tx.clone()).is_ok())
+            .count())
+    }
+
     pub fn len(&self) -> usize {
         self.transactions.lock().unwrap().len()
     }
@@ -164,7 +457,7 @@ mod tests {
     use super::*;
     use crate::crypto::{generate_keypair, sign};
     use crate::storage::MemStorage;
-    use crate::types::{Address, Bytes, U256}; // AccessListItem not used in test but needed if we construct
+    use crate::types::{Bytes, U256}; // AccessListItem not used in test but needed if we construct
 
     #[test]
     fn test_add_transaction_validation() {
@@ -219,7 +512,6 @@ mod tests {
             nonce: 5,
             balance: U256::ZERO,
             code_hash: crate::crypto::Hash::default(),
-            code: None,
         };
         storage.save_account(&sender, &account).unwrap();
 
@@ -236,5 +528,276 @@ mod tests {
             }
             _ => panic!("Expected InvalidNonce"),
         }
+
+        // 5. Gas limit below the intrinsic gas of the calldata
+        let mut no_gas = tx.clone();
+        no_gas.nonce = 5;
+        no_gas.data = Bytes::from(vec![1u8; 10]);
+        no_gas.signature = sign(&sk, &no_gas.sighash().0);
+        assert!(matches!(
+            pool.add_transaction(no_gas),
+            Err(PoolError::Invalid(
+                crate::types::TxValidityError::IntrinsicGasTooLow { .. }
+            ))
+        ));
+
+        // 5.1 Calldata too large to gossip (enough gas for it)
+        let mut huge = tx.clone();
+        huge.nonce = 5;
+        huge.data = Bytes::from(vec![1u8; crate::types::DEFAULT_MAX_TX_SIZE as usize]);
+        huge.gas_limit = huge.intrinsic_gas();
+        huge.signature = sign(&sk, &huge.sighash().0);
+        assert!(matches!(
+            pool.add_transaction(huge),
+            Err(PoolError::TooLarge(_, crate::types::DEFAULT_MAX_TX_SIZE))
+        ));
+
+        // 6. Other chains
+        let mut other_chain = tx.clone();
+        other_chain.chain_id = 1;
+        other_chain.signature = sign(&sk, &other_chain.sighash().0);
+        assert!(matches!(
+            pool.add_transaction(other_chain.clone()),
+            Err(PoolError::InvalidChainId(1337, 1))
+        ));
+        let pool = TxPool::new(storage).with_chain_id(1);
+        other_chain.nonce = 5;
+        other_chain.signature = sign(&sk, &other_chain.sighash().0);
+        assert!(pool.add_transaction(other_chain).is_ok());
+    }
+
+    #[test]
+    fn test_local_transactions_and_pool_sync() {
+        let storage = Arc::new(MemStorage::new());
+        let pool = TxPool::new(storage.clone());
+        let peer = TxPool::new(storage);
+        let (pk, sk) = generate_keypair();
+        let txs: Vec<Transaction> = (0..3)
+            .map(|nonce| {
+                let mut tx = Transaction {
+                    chain_id: 1337,
+                    nonce,
+                    max_priority_fee_per_gas: U256::ZERO,
+                    max_fee_per_gas: U256::from(10_000_000),
+                    gas_limit: 21000,
+                    to: Some(Address::ZERO),
+                    value: U256::ZERO,
+                    data: Bytes::from(vec![]),
+                    access_list: vec![],
+                    public_key: pk.clone(),
+                    signature: crate::crypto::Signature::default(),
+                };
+                tx.signature = sign(&sk, &tx.sighash().0);
+                tx
+            })
+            .collect();
+
+        // Only transactions submitted locally are rebroadcast, until they are included
+        pool.add_local_transaction(txs[0].clone()).unwrap();
+        pool.add_local_transaction(txs[1].clone()).unwrap();
+        pool.add_transaction(txs[2].clone()).unwrap();
+        assert_eq!(pool.local_transactions().len(), 2);
+        pool.remove_transactions(&txs[..1]);
+        assert_eq!(pool.local_transactions(), vec![txs[1].clone()]);
+
+        // A peer that only saw one of them fetches the others from the announcement
+        peer.add_transaction(txs[1].clone()).unwrap();
+        let announced = pool.hashes(MAX_ANNOUNCED_HASHES);
+        assert_eq!(announced.len(), 2);
+        let missing = peer.missing(&announced);
+        assert_eq!(missing, vec![crate::crypto::hash_data(&txs[2])]);
+        for tx in pool.get_transactions(&missing, MAX_POOL_RESPONSE_BYTES) {
+            peer.add_transaction(tx).unwrap();
+        }
+        assert!(peer.missing(&announced).is_empty());
+        assert!(pool.get_transactions(&announced, 1).is_empty());
+
+        // The pending nonce skips the pooled transactions, up to the first gap
+        let sender = txs[0].sender();
+        assert_eq!(peer.pending_nonce(sender, 0), 0);
+        assert_eq!(peer.pending_nonce(sender, 1), 3);
+        assert_eq!(pool.pending_nonce(Address::ZERO, 7), 7);
+    }
+
+    #[test]
+    fn test_pool_journal_roundtrip() {
+        let storage = Arc::new(MemStorage::new());
+        let pool = TxPool::new(storage.clone());
+        let (pk, sk) = generate_keypair();
+        let txs: Vec<Transaction> = (0..3)
+            .map(|nonce| {
+                let mut tx = Transaction {
+                    chain_id: 1337,
+                    nonce,
+                    max_priority_fee_per_gas: U256::ZERO,
+                    max_fee_per_gas: U256::from(10_000_000),
+                    gas_limit: 21000,
+                    to: Some(Address::ZERO),
+                    value: U256::ZERO,
+                    data: Bytes::from(vec![]),
+                    access_list: vec![],
+                    public_key: pk.clone(),
+                    signature: crate::crypto::Signature::default(),
+                };
+                tx.signature = sign(&sk, &tx.sighash().0);
+                tx
+            })
+            .collect();
+        pool.add_local_transaction(txs[2].clone()).unwrap();
+        pool.add_local_transaction(txs[0].clone()).unwrap();
+        pool.add_transaction(txs[1].clone()).unwrap();
+
+        // Only local transactions are journaled, in nonce order
+        assert_eq!(pool.save_journal().unwrap(), 2);
+        assert_eq!(
+            storage.get_pool_journal().unwrap(),
+            vec![txs[0].clone(), txs[2].clone()]
+        );
+
+        // A restarted node re-adds them, skipping the ones included meanwhile
+        let account = crate::storage::AccountInfo {
+            nonce: 1,
+            balance: U256::ZERO,
+            code_hash: crate::crypto::Hash::default(),
+        };
+        storage.save_account(&txs[0].sender(), &account).unwrap();
+        let restarted = TxPool::new(storage);
+        assert_eq!(restarted.load_journal().unwrap(), 1);
+        assert_eq!(restarted.local_transactions(), vec![txs[2].clone()]);
+    }
+
+    #[test]
+    fn test_pool_validators() {
+        use crate::tx_policy::{AddressBlocklist, MinPriorityFee};
+
+        let storage = Arc::new(MemStorage::new());
+        let (pk, sk) = generate_keypair();
+        let make_tx = |nonce: u64, tip: u64, to: Address| {
+            let mut tx = Transaction {
+                chain_id: 1337,
+                nonce,
+                max_priority_fee_per_gas: U256::from(tip),
+                max_fee_per_gas: U256::from(10_000_000),
+                gas_limit: 21000,
+                to: Some(to),
+                value: U256::ZERO,
+                data: Bytes::from(vec![]),
+                access_list: vec![],
+                public_key: pk.clone(),
+                signature: crate::crypto::Signature::default(),
+            };
+            tx.signature = sign(&sk, &tx.sighash().0);
+            tx
+        };
+        let blocked = Address::repeat_byte(0xbb);
+        let pool = TxPool::new(storage)
+            .with_validator(Arc::new(MinPriorityFee(U256::from(5))))
+            .with_validator(Arc::new(AddressBlocklist(HashSet::from([blocked]))));
+        assert_eq!(
+            pool.validators(),
+            vec!["default", "min-priority-fee", "address-blocklist"]
+        );
+
+        // The default checks still run first
+        let mut bad_sig = make_tx(0, 10, Address::ZERO);
+        bad_sig.nonce = 1;
+        assert!(matches!(
+            pool.add_transaction(bad_sig),
+            Err(PoolError::InvalidSignature)
+        ));
+        assert!(matches!(
+            pool.add_transaction(make_tx(0, 1, Address::ZERO)),
+            Err(PoolError::Rejected("min-priority-fee", _))
+        ));
+        assert!(matches!(
+            pool.add_transaction(make_tx(0, 10, blocked)),
+            Err(PoolError::Rejected("address-blocklist", _))
+        ));
+        pool.add_transaction(make_tx(0, 10, Address::ZERO)).unwrap();
+        assert_eq!(pool.len(), 1);
+
+        // At a base fee leaving less than the minimum tip, the transaction waits in the pool
+        assert_eq!(
+            pool.get_transactions_for_block(1_000_000, U256::from(1_000))
+                .len(),
+            1
+        );
+        assert!(
+            pool.get_transactions_for_block(1_000_000, U256::from(9_999_999))
+                .is_empty()
+        );
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_abandoned_blocks_return_transactions() {
+        let storage = Arc::new(MemStorage::new());
+        let pool = TxPool::new(storage.clone());
+        let (pk, sk) = generate_keypair();
+        let txs: Vec<Transaction> = (0..3)
+            .map(|nonce| {
+                let mut tx = Transaction {
+                    chain_id: 1337,
+                    nonce,
+                    max_priority_fee_per_gas: U256::ZERO,
+                    max_fee_per_gas: U256::from(10_000_000),
+                    gas_limit: 21000,
+                    to: Some(Address::ZERO),
+                    value: U256::ZERO,
+                    data: Bytes::from(vec![]),
+                    access_list: vec![],
+                    public_key: pk.clone(),
+                    signature: crate::crypto::Signature::default(),
+                };
+                tx.signature = sign(&sk, &tx.sighash().0);
+                tx
+            })
+            .collect();
+        pool.add_local_transaction(txs[0].clone()).unwrap();
+        pool.add_transaction(txs[1].clone()).unwrap();
+        pool.add_transaction(txs[2].clone()).unwrap();
+
+        // Two competing blocks take the transactions out of the pool
+        let block = |view: View, payload: &[Transaction]| {
+            Block::new(
+                pk.clone(),
+                view,
+                Hash::default(),
+                crate::types::QuorumCertificate::default(),
+                Hash::default(),
+                Hash::default(),
+                payload.to_vec(),
+                U256::ZERO,
+                0,
+                vec![],
+                Hash::default(),
+            )
+        };
+        let (kept, lost) = (block(1, &txs[..1]), block(2, &txs[..2]));
+        let (kept_hash, lost_hash) = (
+            crate::crypto::hash_data(&kept),
+            crate::crypto::hash_data(&lost),
+        );
+        pool.include_block(kept_hash, &kept);
+        pool.include_block(lost_hash, &lost);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.included_blocks().len(), 2);
+
+        // The first is finalized (its transaction executed), the other abandoned: only
+        // what is still valid comes back, local status included
+        let account = crate::storage::AccountInfo {
+            nonce: 1,
+            balance: U256::ZERO,
+            code_hash: Hash::default(),
+        };
+        storage.save_account(&txs[0].sender(), &account).unwrap();
+        assert_eq!(pool.settle_blocks(&[kept_hash], &[lost_hash]), 1);
+        assert!(pool.included_blocks().is_empty());
+        assert_eq!(pool.len(), 2);
+        assert!(
+            pool.get_transaction(&crate::crypto::hash_data(&txs[1]))
+                .is_some()
+        );
+        assert!(pool.local_transactions().is_empty());
     }
 }
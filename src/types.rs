@@ -1,19 +1,46 @@
 use crate::crypto::{Hash, PublicKey, Signature};
 pub use alloy_primitives::{Address, Bytes, FixedBytes, U256, keccak256};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 /// The View number definition (u64).
 pub type View = u64;
 
+pub const DEFAULT_CHAIN_ID: u64 = 1337;
 pub const DEFAULT_BLOCK_GAS_LIMIT: u64 = 30_000_000;
 pub const INITIAL_BASE_FEE: u64 = 10_000_000; // 0.01 Gwei
+/// Default cap on an encoded transaction, well within the gossip limit of the txs topic.
+pub const DEFAULT_MAX_TX_SIZE: u64 = 64 * 1024;
+/// Default cap on an encoded block, well within the gossip limits of the blocks and sync
+/// topics.
+pub const DEFAULT_MAX_BLOCK_SIZE: u64 = 2 * 1024 * 1024;
 
+/// Bytes `value` takes in a gossip message (the network encodes messages as JSON).
+pub fn wire_size<T: Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(usize::MAX, |data| data.len())
+}
+
+/// Intrinsic gas of each address of an access list (EIP-2930).
+pub const ACCESS_LIST_ADDRESS_GAS: u64 = 2400;
+/// Intrinsic gas of each storage key of an access list (EIP-2930).
+pub const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1900;
+
+/// Account and storage slots a transaction declares it will touch (EIP-2930). They are
+/// warm from the start of execution: later accesses cost the warm price.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AccessListItem {
     pub address: Address,
     pub storage_keys: Vec<U256>,
 }
 
+/// `access_list` in the form of revm's `TxEnv`.
+pub fn revm_access_list(access_list: &[AccessListItem]) -> Vec<(Address, Vec<U256>)> {
+    access_list
+        .iter()
+        .map(|item| (item.address, item.storage_keys.clone()))
+        .collect()
+}
+
 /// EIP-1559 style Transaction
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Transaction {
@@ -65,6 +92,105 @@ impl Transaction {
         );
         crate::crypto::hash_data(&data)
     }
+
+    /// Priority fee per gas paid to the fee recipient in a block with `base_fee` (EIP-1559):
+    /// the tip, capped by what the max fee leaves above the base fee.
+    pub fn effective_tip(&self, base_fee: U256) -> U256 {
+        std::cmp::min(
+            self.max_priority_fee_per_gas,
+            self.max_fee_per_gas.saturating_sub(base_fee),
+        )
+    }
+
+    /// Gas charged before execution: 21000, plus 4 per zero and 16 per non-zero calldata
+    /// byte, plus 32000 for a contract creation, plus the access list (EIP-2930).
+    pub fn intrinsic_gas(&self) -> u64 {
+        let zero_bytes = self.data.iter().filter(|b| **b == 0).count() as u64;
+        let data_gas = 4 * zero_bytes + 16 * (self.data.len() as u64 - zero_bytes);
+        let access_list_gas = self
+            .access_list
+            .iter()
+            .map(|item| {
+                ACCESS_LIST_ADDRESS_GAS
+                    + ACCESS_LIST_STORAGE_KEY_GAS * item.storage_keys.len() as u64
+            })
+            .sum::<u64>();
+        21_000 + data_gas + if self.is_create() { 32_000 } else { 0 } + access_list_gas
+    }
+
+    /// Canonical encoding committed by the transactions root: big-endian integers and
+    /// length-prefixed lists, in field order, with the public key and signature.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.chain_id.to_be_bytes());
+        out.extend_from_slice(&self.nonce.to_be_bytes());
+        out.extend_from_slice(&self.max_priority_fee_per_gas.to_be_bytes::<32>());
+        out.extend_from_slice(&self.max_fee_per_gas.to_be_bytes::<32>());
+        out.extend_from_slice(&self.gas_limit.to_be_bytes());
+        match &self.to {
+            Some(to) => {
+                out.push(1);
+                out.extend_from_slice(to.as_slice());
+            }
+            None => out.push(0),
+        }
+        out.extend_from_slice(&self.value.to_be_bytes::<32>());
+        out.extend_from_slice(&(self.data.len() as u64).to_be_bytes());
+        out.extend_from_slice(&self.data);
+        out.extend_from_slice(&(self.access_list.len() as u64).to_be_bytes());
+        for item in &self.access_list {
+            out.extend_from_slice(item.address.as_slice());
+            out.extend_from_slice(&(item.storage_keys.len() as u64).to_be_bytes());
+            for key in &item.storage_keys {
+                out.extend_from_slice(&key.to_be_bytes::<32>());
+            }
+        }
+        out.extend_from_slice(&self.public_key.0.to_bytes());
+        out.extend_from_slice(&self.signature.0.to_bytes());
+        out
+    }
+}
+
+/// EIP-1559 validity rule a transaction breaks, regardless of the state.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum TxValidityError {
+    #[error("Gas limit {gas_limit} below intrinsic gas {intrinsic}")]
+    IntrinsicGasTooLow { gas_limit: u64, intrinsic: u64 },
+    #[error("Max priority fee {tip} above max fee {max_fee}")]
+    TipAboveMaxFee { tip: U256, max_fee: U256 },
+    #[error("Max fee {max_fee} below base fee {base_fee}")]
+    FeeBelowBaseFee { max_fee: U256, base_fee: U256 },
+}
+
+/// Checks of `tx` that need no state, shared by the pool and the executor. The fee
+/// rule is only checked against a known `base_fee` (the pool keeps transactions until
+/// the base fee drops to their max fee).
+pub fn validate_tx_stateless(
+    tx: &Transaction,
+    base_fee: Option<U256>,
+) -> Result<(), TxValidityError> {
+    let intrinsic = tx.intrinsic_gas();
+    if tx.gas_limit < intrinsic {
+        return Err(TxValidityError::IntrinsicGasTooLow {
+            gas_limit: tx.gas_limit,
+            intrinsic,
+        });
+    }
+    if tx.max_priority_fee_per_gas > tx.max_fee_per_gas {
+        return Err(TxValidityError::TipAboveMaxFee {
+            tip: tx.max_priority_fee_per_gas,
+            max_fee: tx.max_fee_per_gas,
+        });
+    }
+    if let Some(base_fee) = base_fee
+        && tx.max_fee_per_gas < base_fee
+    {
+        return Err(TxValidityError::FeeBelowBaseFee {
+            max_fee: tx.max_fee_per_gas,
+            base_fee,
+        });
+    }
+    Ok(())
 }
 
 /// A Block in the Simplex chain.
@@ -72,20 +198,49 @@ impl Transaction {
 pub struct Block {
     pub author: PublicKey,
     pub view: View,
+    #[serde(default)]
+    pub height: u64, // Parent's height + 1 (views skip on timeouts); EVM NUMBER
+    #[serde(default)]
+    pub timestamp: u64, // Unix seconds, >= parent's + min_block_interval; EVM TIMESTAMP
     pub parent_hash: Hash,
     pub justify: QuorumCertificate, // The QC that justifies this block (usually for parent)
+    #[serde(default)]
+    pub transactions_root: Hash, // Merkle root of the payload (see `calculate_transactions_root`)
     pub state_root: Hash,           // Global State Root after execution
     pub receipts_root: Hash,        // Merkle root of transaction receipts
+    #[serde(default)]
+    pub logs_bloom: Bloom, // Union of the receipts' blooms
     pub payload: Vec<Transaction>,  // Transactions
     pub is_dummy: bool,             // Simplex specific: Dummy blocks for timeout
 
     // EIP-1559
     pub base_fee_per_gas: U256,
     pub gas_used: u64,
+    #[serde(default)]
+    pub gas_limit: u64, // `ChainParams::block_gas_limit` when proposed; sets the base fee target
 
     // On-Chain Committee
     pub evidence: Vec<EquivocationEvidence>,
-    pub committee_hash: Hash, // Hash of the active committee for this view
+    pub committee_hash: Hash, // Hash of the committee of this view's epoch (`SimplexState::committee_at`)
+    #[serde(default)]
+    pub proposal_evidence: Vec<ProposalEquivocationEvidence>,
+
+    // Proposer Settings
+    #[serde(default)]
+    pub metadata: ProposalMetadata,
+
+    // Author signature over `sighash()`
+    #[serde(default)]
+    pub signature: Signature,
+}
+
+/// Proposer-chosen settings carried in (and committed by) the block.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProposalMetadata {
+    pub fee_recipient: Address, // Receives priority fees (EVM coinbase)
+    pub operator_txs: u32, // Payload txs placed from the proposer's operator allowlist, after the revealed ones
+    #[serde(default)]
+    pub decryption_keys: Vec<DecryptionKey>, // Revealed ciphertexts; their txs lead the payload
 }
 
 impl Block {
@@ -106,16 +261,24 @@ impl Block {
         Self {
             author,
             view,
+            height: 0,
+            timestamp: 0,
             parent_hash,
             justify,
+            transactions_root: calculate_transactions_root(&payload),
             state_root,
             receipts_root,
+            logs_bloom: Bloom::ZERO,
             payload,
             is_dummy: false,
             base_fee_per_gas,
             gas_used,
+            gas_limit: DEFAULT_BLOCK_GAS_LIMIT,
             evidence,
             committee_hash,
+            proposal_evidence: vec![],
+            metadata: ProposalMetadata::default(),
+            signature: Signature::default(),
         }
     }
 
@@ -128,25 +291,411 @@ impl Block {
         Self {
             author,
             view,
+            height: 0,
+            timestamp: 0,
             parent_hash,
             justify,
+            transactions_root: Hash::default(),
             state_root: Hash::default(),
             receipts_root: Hash::default(),
+            logs_bloom: Bloom::ZERO,
             payload: vec![],
             is_dummy: true,
             base_fee_per_gas: U256::from(INITIAL_BASE_FEE), // Default base fee for dummy
             gas_used: 0,
+            gas_limit: 0,
             evidence: vec![],
             committee_hash: Hash::default(),
+            proposal_evidence: vec![],
+            metadata: ProposalMetadata::default(),
+            signature: Signature::default(),
         }
     }
+
+    /// Hash signed by the author: the header sighash, which commits to the body through
+    /// `body_hash`. Covers every field except the signature.
+    pub fn sighash(&self) -> Hash {
+        self.header().sighash()
+    }
+
+    pub fn sign(&mut self, key: &crate::crypto::PrivateKey) {
+        self.signature = crate::crypto::sign(key, &self.sighash().0);
+    }
+
+    pub fn verify_signature(&self) -> bool {
+        crate::crypto::verify(&self.author, &self.sighash().0, &self.signature)
+    }
+
+    pub fn body_hash(&self) -> Hash {
+        body_hash(&self.payload, &self.evidence, &self.proposal_evidence)
+    }
+
+    /// Proof that the transaction at `index` of the payload is committed by
+    /// `transactions_root`.
+    pub fn transaction_proof(&self, index: usize) -> Option<MerkleProof> {
+        let leaves: Vec<[u8; 32]> = self
+            .payload
+            .iter()
+            .map(|tx| keccak256(tx.encode()).0)
+            .collect();
+        MerkleProof::new(leaves, index)
+    }
+
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            author: self.author.clone(),
+            view: self.view,
+            height: self.height,
+            timestamp: self.timestamp,
+            parent_hash: self.parent_hash,
+            justify: self.justify.clone(),
+            transactions_root: self.transactions_root,
+            state_root: self.state_root,
+            receipts_root: self.receipts_root,
+            logs_bloom: self.logs_bloom,
+            is_dummy: self.is_dummy,
+            base_fee_per_gas: self.base_fee_per_gas,
+            gas_used: self.gas_used,
+            gas_limit: self.gas_limit,
+            committee_hash: self.committee_hash,
+            metadata: self.metadata.clone(),
+            body_hash: self.body_hash(),
+            tx_count: self.payload.len() as u64,
+            signature: self.signature.clone(),
+        }
+    }
+
+    /// Split into the header and the body, e.g. to store them separately.
+    pub fn split(self) -> (BlockHeader, BlockBody) {
+        let header = self.header();
+        let body = BlockBody {
+            payload: self.payload,
+            evidence: self.evidence,
+            proposal_evidence: self.proposal_evidence,
+        };
+        (header, body)
+    }
+
+    /// Join a header with its body. None if the body is not the one the header commits to.
+    pub fn from_parts(header: BlockHeader, body: BlockBody) -> Option<Self> {
+        if body.hash() != header.body_hash {
+            return None;
+        }
+        Some(Self {
+            author: header.author,
+            view: header.view,
+            height: header.height,
+            timestamp: header.timestamp,
+            parent_hash: header.parent_hash,
+            justify: header.justify,
+            transactions_root: header.transactions_root,
+            state_root: header.state_root,
+            receipts_root: header.receipts_root,
+            logs_bloom: header.logs_bloom,
+            payload: body.payload,
+            is_dummy: header.is_dummy,
+            base_fee_per_gas: header.base_fee_per_gas,
+            gas_used: header.gas_used,
+            gas_limit: header.gas_limit,
+            evidence: body.evidence,
+            committee_hash: header.committee_hash,
+            proposal_evidence: body.proposal_evidence,
+            metadata: header.metadata,
+            signature: header.signature,
+        })
+    }
+}
+
+fn body_hash(
+    payload: &[Transaction],
+    evidence: &[EquivocationEvidence],
+    proposal_evidence: &[ProposalEquivocationEvidence],
+) -> Hash {
+    crate::crypto::hash_data(&("body", payload, evidence, proposal_evidence))
+}
+
+/// A block without its transactions and evidence, which are committed by `body_hash`.
+/// The author signs the header, so it can be checked (and relayed) without the body.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub author: PublicKey,
+    pub view: View,
+    #[serde(default)]
+    pub height: u64,
+    #[serde(default)]
+    pub timestamp: u64,
+    pub parent_hash: Hash,
+    pub justify: QuorumCertificate,
+    #[serde(default)]
+    pub transactions_root: Hash,
+    pub state_root: Hash,
+    pub receipts_root: Hash,
+    #[serde(default)]
+    pub logs_bloom: Bloom,
+    pub is_dummy: bool,
+    pub base_fee_per_gas: U256,
+    pub gas_used: u64,
+    #[serde(default)]
+    pub gas_limit: u64,
+    pub committee_hash: Hash,
+    pub metadata: ProposalMetadata,
+    pub body_hash: Hash,
+    pub tx_count: u64,
+    pub signature: Signature,
+}
+
+impl BlockHeader {
+    pub fn sighash(&self) -> Hash {
+        let data = (
+            "block",
+            &self.author,
+            self.view,
+            self.height,
+            self.timestamp,
+            &self.parent_hash,
+            &self.justify,
+            (
+                &self.transactions_root,
+                &self.state_root,
+                &self.receipts_root,
+                &self.logs_bloom,
+            ),
+            self.is_dummy,
+            (&self.base_fee_per_gas, self.gas_used, self.gas_limit),
+            &self.committee_hash,
+            &self.metadata,
+            &self.body_hash,
+            self.tx_count,
+        );
+        crate::crypto::hash_data(&data)
+    }
+
+    pub fn verify_signature(&self) -> bool {
+        crate::crypto::verify(&self.author, &self.sighash().0, &self.signature)
+    }
+}
+
+/// Transactions and evidence of a block.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct BlockBody {
+    pub payload: Vec<Transaction>,
+    pub evidence: Vec<EquivocationEvidence>,
+    pub proposal_evidence: Vec<ProposalEquivocationEvidence>,
+}
+
+impl BlockBody {
+    pub fn hash(&self) -> Hash {
+        body_hash(&self.payload, &self.evidence, &self.proposal_evidence)
+    }
+}
+
+/// Gossiped form of a proposal: the signed header, with the transactions replaced by their
+/// hashes. Receivers fill them in from their pool and fetch only the missing ones
+/// (`SyncMessage::RequestTransactions`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompactBlock {
+    pub block_hash: Hash,
+    pub header: BlockHeader,
+    pub tx_ids: Vec<Hash>,
+    pub evidence: Vec<EquivocationEvidence>,
+    pub proposal_evidence: Vec<ProposalEquivocationEvidence>,
+}
+
+impl CompactBlock {
+    pub fn new(block: &Block) -> Self {
+        Self {
+            block_hash: crate::crypto::hash_data(block),
+            header: block.header(),
+            tx_ids: block.payload.iter().map(crate::crypto::hash_data).collect(),
+            evidence: block.evidence.clone(),
+            proposal_evidence: block.proposal_evidence.clone(),
+        }
+    }
+
+    /// Rebuild the block with transactions from `lookup`. None if one is missing, or if
+    /// the result is not the announced block.
+    pub fn reconstruct(&self, lookup: impl Fn(&Hash) -> Option<Transaction>) -> Option<Block> {
+        let payload = self.tx_ids.iter().map(lookup).collect::<Option<Vec<_>>>()?;
+        let body = BlockBody {
+            payload,
+            evidence: self.evidence.clone(),
+            proposal_evidence: self.proposal_evidence.clone(),
+        };
+        self.with_body(body)
+    }
+
+    /// Join the header with a fetched body, checking it against the announced block hash.
+    pub fn with_body(&self, body: BlockBody) -> Option<Block> {
+        let block = Block::from_parts(self.header.clone(), body)?;
+        (crate::crypto::hash_data(&block) == self.block_hash).then_some(block)
+    }
 }
 
 /// Type of vote: Notarize (for block validity) or Finalize (for view completeness)
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum VoteType {
     Notarize,
     Finalize,
+    Handover, // Outgoing committee signing a CommitteeTransition commitment
+}
+
+impl VoteType {
+    fn tag(self) -> u8 {
+        match self {
+            VoteType::Notarize => 0,
+            VoteType::Finalize => 1,
+            VoteType::Handover => 2,
+        }
+    }
+}
+
+/// Message signed by a vote: `type || view || block_hash || chain_id`, so a signature only
+/// counts for its own vote type, view and chain (e.g. a Notarize vote for the dummy block
+/// of one view cannot be replayed as a Finalize vote, or in another view). Hand-over votes
+/// sign the transition commitment itself, which `CommitteeTransition::verify` checks.
+pub fn vote_message(vote_type: VoteType, view: View, block_hash: &Hash, chain_id: u64) -> Vec<u8> {
+    if vote_type == VoteType::Handover {
+        return block_hash.0.to_vec();
+    }
+    let mut message = Vec::with_capacity(1 + 8 + 32 + 8);
+    message.push(vote_type.tag());
+    message.extend_from_slice(&view.to_be_bytes());
+    message.extend_from_slice(&block_hash.0);
+    message.extend_from_slice(&chain_id.to_be_bytes());
+    message
+}
+
+/// EVM hardfork (opcode set and gas schedule) the chain executes transactions with.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Hardfork {
+    #[default]
+    Shanghai,
+    Cancun,
+}
+
+/// Chain parameters: block limits, rewards, slashing and liveness penalties. Set at
+/// genesis and changed through the governance contract; fields missing from older
+/// encodings take their defaults.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ChainParams {
+    pub slash_amount: U256,          // Burned per equivocation (double vote)
+    pub proposal_slash_amount: U256, // Burned per double proposal
+    pub min_stake: U256,             // Validators below this leave the committee
+    pub inactivity_threshold: u64,   // Missed leader slots before removal
+    pub inactivity_penalty: U256,    // Burned per missed leader slot
+    pub attestation_grace: u64,      // Consecutive block QCs a member may miss before leaking
+    pub attestation_penalty: U256,   // Burned per QC missed past the grace, times the overshoot
+    pub evidence_max_age: View,      // Equivocation evidence older than this (in views) expires
+    pub withdrawal_delay: View, // Views after leaving the committee before stake can be withdrawn
+    pub proposer_reward: U256,  // Accrued by the author of each block
+    pub attester_reward: U256,  // Accrued by each signer of a block's justify QC
+    pub block_gas_limit: u64,   // Gas available to the transactions of a block
+    pub elasticity_multiplier: u64, // Block gas limit / target gas (EIP-1559)
+    pub base_fee_max_change_denominator: u64, // Bounds the base fee change per block (EIP-1559)
+    pub epoch_length: View,     // Parameter changes take effect at multiples of this view
+    pub encrypted_mempool: bool, // Accept encrypted transactions (`encrypted_mempool` contract)
+    pub decryption_window: View, // Views a ciphertext can be revealed in after its inclusion
+    pub min_block_interval: u64, // Seconds from a block's parent to its timestamp (0: none)
+    pub max_tx_size: u64,       // Encoded bytes of a transaction (`wire_size`)
+    pub max_block_size: u64,    // Encoded bytes of a block (`wire_size`)
+}
+
+impl Default for ChainParams {
+    fn default() -> Self {
+        use crate::system_contracts::{governance, staking};
+        Self {
+            slash_amount: U256::from(1000u64),
+            proposal_slash_amount: U256::from(2000u64),
+            min_stake: U256::from(staking::MIN_STAKE),
+            inactivity_threshold: 50,
+            inactivity_penalty: U256::from(10u64),
+            attestation_grace: 16,
+            attestation_penalty: U256::from(1u64),
+            evidence_max_age: 100,
+            withdrawal_delay: staking::WITHDRAWAL_DELAY,
+            proposer_reward: U256::from(staking::PROPOSER_REWARD),
+            attester_reward: U256::from(staking::ATTESTER_REWARD),
+            block_gas_limit: DEFAULT_BLOCK_GAS_LIMIT,
+            elasticity_multiplier: 2,
+            base_fee_max_change_denominator: 8,
+            epoch_length: governance::EPOCH_LENGTH,
+            encrypted_mempool: false,
+            decryption_window: 16,
+            min_block_interval: 0,
+            max_tx_size: DEFAULT_MAX_TX_SIZE,
+            max_block_size: DEFAULT_MAX_BLOCK_SIZE,
+        }
+    }
+}
+
+impl ChainParams {
+    /// Evidence can only be included in blocks within `evidence_max_age` views of the offence.
+    pub fn is_evidence_expired(&self, evidence: &EquivocationEvidence, view: View) -> bool {
+        self.is_offence_expired(evidence.vote_a.view, view)
+    }
+
+    pub fn is_proposal_evidence_expired(
+        &self,
+        evidence: &ProposalEquivocationEvidence,
+        view: View,
+    ) -> bool {
+        self.is_offence_expired(evidence.view(), view)
+    }
+
+    fn is_offence_expired(&self, offence_view: View, view: View) -> bool {
+        view.saturating_sub(offence_view) > self.evidence_max_age
+    }
+
+    /// Views between leaving the committee and withdrawing the stake: at least
+    /// `withdrawal_delay`, and never shorter than the evidence window, so offences from the
+    /// last views in the committee can still be slashed.
+    pub fn unbonding_period(&self) -> View {
+        self.withdrawal_delay.max(self.evidence_max_age)
+    }
+
+    /// First epoch boundary after `view`, where approved parameter changes take effect.
+    pub fn next_epoch_boundary(&self, view: View) -> View {
+        let epoch_length = self.epoch_length.max(1);
+        (view / epoch_length + 1) * epoch_length
+    }
+
+    /// Whether the chain can run with these parameters (checked before a proposal).
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.block_gas_limit == 0 {
+            return Err("zero block gas limit");
+        }
+        if self.elasticity_multiplier == 0 || self.block_gas_limit < self.elasticity_multiplier {
+            return Err("invalid elasticity multiplier");
+        }
+        if self.base_fee_max_change_denominator == 0 {
+            return Err("zero base fee change denominator");
+        }
+        if self.epoch_length == 0 {
+            return Err("zero epoch length");
+        }
+        if self.encrypted_mempool && self.decryption_window == 0 {
+            return Err("zero decryption window");
+        }
+        if self.max_tx_size == 0 || self.max_block_size < self.max_tx_size {
+            return Err("invalid transaction or block size limit");
+        }
+        Ok(())
+    }
+}
+
+/// A governance proposal to replace the chain parameters (see the governance contract).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ParamsProposal {
+    pub id: u64,
+    pub proposer: PublicKey,
+    pub params: ChainParams,
+    pub created_view: View,
+    /// Committee members in favour; tallied by their current stake.
+    pub votes: Vec<PublicKey>,
+    /// Set once approved: the epoch boundary at which `params` take effect.
+    pub enact_view: Option<View>,
 }
 
 /// Evidence of double-voting (Equivocation)
@@ -156,6 +705,42 @@ pub struct EquivocationEvidence {
     pub vote_b: Vote,
 }
 
+/// Evidence of a leader proposing two different blocks for the same view.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalEquivocationEvidence {
+    pub block_a: Block,
+    pub block_b: Block,
+}
+
+impl ProposalEquivocationEvidence {
+    pub fn offender(&self) -> &PublicKey {
+        &self.block_a.author
+    }
+
+    pub fn view(&self) -> View {
+        self.block_a.view
+    }
+
+    /// Same author and view, different blocks, both signed by the author.
+    pub fn is_valid(&self) -> bool {
+        self.block_a.author == self.block_b.author
+            && self.block_a.view == self.block_b.view
+            && !self.block_a.is_dummy
+            && !self.block_b.is_dummy
+            && self.block_a.sighash() != self.block_b.sighash()
+            && self.block_a.verify_signature()
+            && self.block_b.verify_signature()
+    }
+}
+
+impl PartialEq for ProposalEquivocationEvidence {
+    fn eq(&self, other: &Self) -> bool {
+        use crate::crypto::hash_data;
+        hash_data(&self.block_a) == hash_data(&other.block_a)
+            && hash_data(&self.block_b) == hash_data(&other.block_b)
+    }
+}
+
 /// A Vote from a validator for a specific block (Notarization) or view (Finalization/Timeout).
 /// In Simplex, a timeout creates a vote for a "Dummy Block" (Notarize ZeroHash).
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -167,13 +752,357 @@ pub struct Vote {
     pub signature: Signature,
 }
 
+impl Vote {
+    /// The message `signature` covers on `chain_id` (see `vote_message`).
+    pub fn message(&self, chain_id: u64) -> Vec<u8> {
+        vote_message(self.vote_type, self.view, &self.block_hash, chain_id)
+    }
+
+    pub fn verify_signature(&self, chain_id: u64) -> bool {
+        crate::crypto::verify(&self.author, &self.message(chain_id), &self.signature)
+    }
+}
+
+/// Signers of a certificate as a bitmap over a committee: bit `i` (byte `i / 8`, least
+/// significant bit first) is set if member `i` signed.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct SignerBitmap(pub Vec<u8>);
+
+impl SignerBitmap {
+    /// Bitmap of `signers` over `committee`; None if one of them is not a member.
+    pub fn from_signers<'a>(
+        committee: &[PublicKey],
+        signers: impl IntoIterator<Item = &'a PublicKey>,
+    ) -> Option<Self> {
+        let mut bitmap = Self::default();
+        for signer in signers {
+            bitmap.insert(committee.iter().position(|member| member == signer)?);
+        }
+        Some(bitmap)
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        if self.0.len() <= index / 8 {
+            self.0.resize(index / 8 + 1, 0);
+        }
+        self.0[index / 8] |= 1 << (index % 8);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if let Some(byte) = self.0.get_mut(index / 8) {
+            *byte &= !(1 << (index % 8));
+        }
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.0
+            .get(index / 8)
+            .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    /// Number of signers.
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|byte| byte.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Committee indices of the signers, in increasing order.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.0.len() * 8).filter(|index| self.contains(*index))
+    }
+
+    pub fn is_disjoint(&self, other: &SignerBitmap) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a & b == 0)
+    }
+
+    /// Add the signers of `other`.
+    pub fn union_with(&mut self, other: &SignerBitmap) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (byte, other) in self.0.iter_mut().zip(&other.0) {
+            *byte |= other;
+        }
+    }
+
+    /// Keep the first `n` signers only.
+    pub fn truncate(&mut self, n: usize) {
+        let dropped: Vec<usize> = self.indices().skip(n).collect();
+        for index in dropped {
+            self.remove(index);
+        }
+    }
+
+    /// The members of `committee` that signed; None if a bit is set past its end.
+    pub fn resolve(&self, committee: &[PublicKey]) -> Option<Vec<PublicKey>> {
+        self.indices()
+            .map(|index| committee.get(index).cloned())
+            .collect()
+    }
+}
+
 /// A Quorum Certificate (QC) proves that 2f+1 validators voted for a block.
+/// Signers are a bitmap over the committee of `epoch`, which verifiers resolve to keys.
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct QuorumCertificate {
     pub view: View,
     pub block_hash: Hash,
-    pub signature: Signature,    // Aggregated signature
-    pub signers: Vec<PublicKey>, // Public keys of signers
+    pub signature: Signature, // Aggregated signature
+    #[serde(default)]
+    pub epoch: u64, // Epoch whose committee ordering `signers` follows
+    pub signers: SignerBitmap, // Committee members that signed
+}
+
+impl QuorumCertificate {
+    /// Whether the aggregate of the signers' Notarize votes on `chain_id` verifies, for the
+    /// signers resolved against `committee` (the genesis QC, view 0, always does). The
+    /// quorum size is not checked.
+    pub fn verify_signature(&self, committee: &[PublicKey], chain_id: u64) -> bool {
+        self.view == 0
+            || self.signers.resolve(committee).is_some_and(|signers| {
+                let message =
+                    vote_message(VoteType::Notarize, self.view, &self.block_hash, chain_id);
+                crate::crypto::verify_aggregate(&signers, &message, &self.signature)
+            })
+    }
+}
+
+/// Votes of several committee members for the same target, combined into one signature.
+/// Nodes gossip these (growing) aggregates instead of relaying every vote they receive.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AggregateVote {
+    pub view: View,
+    pub block_hash: Hash,
+    pub vote_type: VoteType,
+    pub epoch: u64, // Epoch whose committee ordering `signers` follows
+    pub signers: SignerBitmap,
+    pub signature: Signature, // Aggregated signature of the signers
+}
+
+impl AggregateVote {
+    /// Aggregate of the single `vote` of committee member `index`.
+    pub fn from_vote(vote: &Vote, epoch: u64, index: usize) -> Self {
+        let mut signers = SignerBitmap::default();
+        signers.insert(index);
+        Self {
+            view: vote.view,
+            block_hash: vote.block_hash,
+            vote_type: vote.vote_type,
+            epoch,
+            signers,
+            signature: vote.signature.clone(),
+        }
+    }
+
+    /// Add the signatures of `other`, an aggregate for the same target. BLS aggregates
+    /// cannot drop a signer, so this only succeeds if their signers are disjoint.
+    pub fn merge(&mut self, other: &AggregateVote) -> bool {
+        if (self.view, self.block_hash, self.vote_type, self.epoch)
+            != (other.view, other.block_hash, other.vote_type, other.epoch)
+            || !self.signers.is_disjoint(&other.signers)
+        {
+            return false;
+        }
+        let Some(signature) =
+            crate::crypto::aggregate(&[self.signature.clone(), other.signature.clone()])
+        else {
+            return false;
+        };
+        self.signature = signature;
+        self.signers.union_with(&other.signers);
+        true
+    }
+
+    /// Whether the signature verifies for the signers resolved against `committee`.
+    pub fn verify(&self, committee: &[PublicKey], chain_id: u64) -> bool {
+        !self.signers.is_empty()
+            && self.signers.resolve(committee).is_some_and(|signers| {
+                let message = vote_message(self.vote_type, self.view, &self.block_hash, chain_id);
+                crate::crypto::verify_aggregate(&signers, &message, &self.signature)
+            })
+    }
+
+    /// The certificate this aggregate amounts to (once it has a quorum of signers).
+    pub fn to_qc(&self) -> QuorumCertificate {
+        QuorumCertificate {
+            view: self.view,
+            block_hash: self.block_hash,
+            signature: self.signature.clone(),
+            epoch: self.epoch,
+            signers: self.signers.clone(),
+        }
+    }
+}
+
+/// A transaction encrypted to the committee (encrypted mempool, see the `encrypted_mempool`
+/// system contract), so its contents are unknown when its position is fixed. It is
+/// decrypted once every member has released its `DecryptionShare` for `id()`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EncryptedTransaction {
+    pub ephemeral: PublicKey, // R = r·g2
+    pub ciphertext: Bytes,
+    pub mac: Hash, // sha256(key || ciphertext)
+}
+
+impl EncryptedTransaction {
+    /// Encrypt the signed `tx` to `committee` (None if the committee is empty).
+    pub fn encrypt(tx: &Transaction, committee: &[PublicKey]) -> Option<Self> {
+        let committee_key = crate::crypto::aggregate_public_keys(committee)?;
+        let (ephemeral, r) = crate::crypto::generate_keypair();
+        let tag = Self::tag(&ephemeral);
+        let key = crate::crypto::pairing_key(&crate::crypto::sign(&r, &tag.0), &committee_key)?;
+        let plaintext = serde_json::to_vec(tx).ok()?;
+        let ciphertext = crate::crypto::apply_keystream(&key, &plaintext);
+        Some(Self {
+            mac: Self::mac(&key, &ciphertext),
+            ephemeral,
+            ciphertext: ciphertext.into(),
+        })
+    }
+
+    /// What members sign as their decryption share.
+    pub fn id(&self) -> Hash {
+        Self::tag(&self.ephemeral)
+    }
+
+    /// Commitment stored on chain when the ciphertext is included.
+    pub fn commitment(&self) -> Hash {
+        crate::crypto::hash_data(self)
+    }
+
+    /// Decrypt with the aggregate of the committee's shares. None if `key` is not it, or
+    /// the plaintext is not a transaction.
+    pub fn decrypt(&self, key: &Signature) -> Option<Transaction> {
+        let key = crate::crypto::pairing_key(key, &self.ephemeral)?;
+        if Self::mac(&key, &self.ciphertext) != self.mac {
+            return None;
+        }
+        serde_json::from_slice(&crate::crypto::apply_keystream(&key, &self.ciphertext)).ok()
+    }
+
+    fn tag(ephemeral: &PublicKey) -> Hash {
+        crate::crypto::hash_data(&("ockham-decrypt", ephemeral))
+    }
+
+    fn mac(key: &Hash, ciphertext: &[u8]) -> Hash {
+        crate::crypto::hash_data(&(key, ciphertext))
+    }
+}
+
+/// A committee member's share of the key of an encrypted transaction: its signature on
+/// the ciphertext's `id`, released once the ciphertext is in a notarized block.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DecryptionShare {
+    pub id: Hash,
+    pub author: PublicKey,
+    pub share: Signature,
+}
+
+impl DecryptionShare {
+    pub fn verify(&self) -> bool {
+        crate::crypto::verify(&self.author, &self.id.0, &self.share)
+    }
+}
+
+/// Key of an encrypted transaction revealed by a proposal (the aggregate of the whole
+/// committee's shares), with the ciphertext it opens.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DecryptionKey {
+    pub envelope: EncryptedTransaction,
+    pub key: Signature,
+}
+
+/// Hand-over certificate produced when a finalized block changes the validator set.
+/// The outgoing committee signs `commitment()`, which binds the incoming committee, so a
+/// light client that trusts epoch N's committee can adopt epoch N+1's without executing blocks.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct CommitteeTransition {
+    pub epoch: u64, // Epoch started by the new committee (genesis committee is epoch 0)
+    pub view: View, // Finalized view whose execution changed the committee
+    pub block_hash: Hash, // Block finalized at `view`
+    pub old_committee_hash: Hash,
+    pub new_committee: Vec<PublicKey>,
+    pub signature: Signature, // Aggregated Handover signature of the old committee
+    pub signers: Vec<PublicKey>, // Old committee members that signed
+}
+
+impl CommitteeTransition {
+    /// Unsigned transition from `old_committee` to `new_committee`.
+    pub fn new(
+        epoch: u64,
+        view: View,
+        block_hash: Hash,
+        old_committee: &[PublicKey],
+        new_committee: Vec<PublicKey>,
+    ) -> Self {
+        Self {
+            epoch,
+            view,
+            block_hash,
+            old_committee_hash: crate::crypto::hash_data(&old_committee),
+            new_committee,
+            signature: Signature::default(),
+            signers: vec![],
+        }
+    }
+
+    pub fn new_committee_hash(&self) -> Hash {
+        crate::crypto::hash_data(&self.new_committee)
+    }
+
+    /// The message signed by the outgoing committee.
+    pub fn commitment(&self) -> Hash {
+        crate::crypto::hash_data(&(
+            "committee-transition",
+            self.epoch,
+            self.view,
+            self.block_hash,
+            self.old_committee_hash,
+            self.new_committee_hash(),
+        ))
+    }
+
+    /// Check that a quorum (2f+1) of `old_committee` signed this transition.
+    pub fn verify(&self, old_committee: &[PublicKey]) -> bool {
+        if crate::crypto::hash_data(&old_committee) != self.old_committee_hash {
+            return false;
+        }
+        let mut seen = std::collections::HashSet::new();
+        for signer in &self.signers {
+            if !old_committee.contains(signer) || !seen.insert(signer) {
+                return false;
+            }
+        }
+        let threshold = (old_committee.len() * 2) / 3 + 1;
+        if self.signers.len() < threshold {
+            return false;
+        }
+        crate::crypto::verify_aggregate(&self.signers, &self.commitment().0, &self.signature)
+    }
+
+    /// Walk a hand-over chain starting from the genesis committee.
+    /// Returns the committee of the last epoch, or None if any link fails to verify.
+    pub fn verify_chain(
+        genesis_committee: &[PublicKey],
+        transitions: &[CommitteeTransition],
+    ) -> Option<Vec<PublicKey>> {
+        let mut committee = genesis_committee.to_vec();
+        let mut epoch = 0;
+        for transition in transitions {
+            if transition.epoch != epoch + 1 || !transition.verify(&committee) {
+                return None;
+            }
+            committee = transition.new_committee.clone();
+            epoch = transition.epoch;
+        }
+        Some(committee)
+    }
 }
 
 /// Log entry from contract execution
@@ -184,44 +1113,201 @@ pub struct Log {
     pub data: Bytes,
 }
 
+/// 2048-bit Ethereum logs bloom over log addresses and topics.
+pub type Bloom = FixedBytes<256>;
+
+/// Add `input` (an address or a topic) to `bloom`: three bits picked by its keccak hash.
+pub fn bloom_accrue(bloom: &mut Bloom, input: &[u8]) {
+    let hash = keccak256(input);
+    for i in 0..3 {
+        let bit = ((usize::from(hash[2 * i]) << 8) | usize::from(hash[2 * i + 1])) & 2047;
+        bloom[255 - bit / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// Whether `input` may have been added to `bloom` (false positives, no false negatives).
+pub fn bloom_contains(bloom: &Bloom, input: &[u8]) -> bool {
+    let mut probe = Bloom::ZERO;
+    bloom_accrue(&mut probe, input);
+    probe.iter().zip(bloom.iter()).all(|(p, b)| p & b == *p)
+}
+
+/// Bloom of the addresses and topics of `logs`.
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    let mut bloom = Bloom::ZERO;
+    for log in logs {
+        bloom_accrue(&mut bloom, log.address.as_slice());
+        for topic in &log.topics {
+            bloom_accrue(&mut bloom, &topic.0);
+        }
+    }
+    bloom
+}
+
 /// Transaction Receipt
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Receipt {
     pub status: u8, // 1 = Success, 0 = Revert
     pub cumulative_gas_used: u64,
     pub logs: Vec<Log>,
-    // bloom ignored for simplicity in this iteration
+    /// Bloom of the addresses and topics of `logs`.
+    #[serde(default)]
+    pub logs_bloom: Bloom,
+    /// Gas used by this transaction alone.
+    #[serde(default)]
+    pub gas_used: u64,
+    /// Revert data of a failed transaction (e.g. an ABI `Error(string)`); empty on success.
+    #[serde(default)]
+    pub revert_output: Bytes,
+    /// Address of the contract created by a successful deployment.
+    #[serde(default)]
+    pub contract_address: Option<Address>,
 }
 
-/// Helper to calculate Merkle Root of receipts (Simplified)
-/// In a real implementation this would use a Patricia Trie or proper Merkle Tree.
-#[allow(clippy::manual_is_multiple_of)]
-#[allow(clippy::clone_on_copy)]
+impl Receipt {
+    /// Canonical encoding committed by the receipts root: big-endian integers and
+    /// length-prefixed lists, in field order.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![self.status];
+        out.extend_from_slice(&self.cumulative_gas_used.to_be_bytes());
+        out.extend_from_slice(&self.gas_used.to_be_bytes());
+        out.extend_from_slice(self.logs_bloom.as_slice());
+        out.extend_from_slice(&(self.logs.len() as u64).to_be_bytes());
+        for log in &self.logs {
+            out.extend_from_slice(log.address.as_slice());
+            out.extend_from_slice(&(log.topics.len() as u64).to_be_bytes());
+            for topic in &log.topics {
+                out.extend_from_slice(&topic.0);
+            }
+            out.extend_from_slice(&(log.data.len() as u64).to_be_bytes());
+            out.extend_from_slice(&log.data);
+        }
+        out.extend_from_slice(&(self.revert_output.len() as u64).to_be_bytes());
+        out.extend_from_slice(&self.revert_output);
+        match &self.contract_address {
+            Some(address) => {
+                out.push(1);
+                out.extend_from_slice(address.as_slice());
+            }
+            None => out.push(0),
+        }
+        out
+    }
+}
+
+/// Root committing to the receipts of a block, SSZ style: the canonical encodings
+/// (`Receipt::encode`) are hashed into leaves of a binary Merkle tree padded with zero
+/// leaves to a power of two, and the count is mixed into the tree root. Zero for no receipts.
 pub fn calculate_receipts_root(receipts: &[Receipt]) -> Hash {
-    if receipts.is_empty() {
+    merkle_root(
+        receipts
+            .iter()
+            .map(|receipt| keccak256(receipt.encode()).0)
+            .collect(),
+    )
+}
+
+/// Proof that the receipt at `index` is committed by `calculate_receipts_root(receipts)`.
+pub fn receipt_proof(receipts: &[Receipt], index: usize) -> Option<MerkleProof> {
+    let leaves = receipts
+        .iter()
+        .map(|receipt| keccak256(receipt.encode()).0)
+        .collect();
+    MerkleProof::new(leaves, index)
+}
+
+/// Root committing to the transactions of a block, built like the receipts root over
+/// `Transaction::encode`. Zero for an empty payload.
+pub fn calculate_transactions_root(transactions: &[Transaction]) -> Hash {
+    merkle_root(
+        transactions
+            .iter()
+            .map(|tx| keccak256(tx.encode()).0)
+            .collect(),
+    )
+}
+
+fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak256([*left, *right].concat()).0
+}
+
+fn mix_in_length(root: [u8; 32], count: u64) -> Hash {
+    let mut length = [0u8; 32];
+    length[..8].copy_from_slice(&count.to_le_bytes());
+    Hash(merkle_parent(&root, &length))
+}
+
+/// Merkle root of `leaves` padded with zero leaves to a power of two, with the count mixed
+/// in. Zero for no leaves.
+fn merkle_root(mut nodes: Vec<[u8; 32]>) -> Hash {
+    if nodes.is_empty() {
         return Hash::default();
     }
+    let count = nodes.len() as u64;
+    nodes.resize(nodes.len().next_power_of_two(), [0u8; 32]);
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| merkle_parent(&pair[0], &pair[1]))
+            .collect();
+    }
+    mix_in_length(nodes[0], count)
+}
+
+/// Inclusion proof of one leaf in a root built by `calculate_transactions_root` (or
+/// `calculate_receipts_root`): its position, the leaf count and the sibling hashes from
+/// the leaf up.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub index: u64,
+    pub count: u64,
+    pub siblings: Vec<Hash>,
+}
 
-    // Simple Merkle Tree Construction
-    let mut leaves: Vec<Hash> = receipts.iter().map(crate::crypto::hash_data).collect();
+impl MerkleProof {
+    /// Proof of the leaf at `index` of `nodes`. None if out of range.
+    fn new(mut nodes: Vec<[u8; 32]>, index: usize) -> Option<Self> {
+        if index >= nodes.len() {
+            return None;
+        }
+        let count = nodes.len() as u64;
+        nodes.resize(nodes.len().next_power_of_two(), [0u8; 32]);
+        let mut siblings = Vec::new();
+        let mut position = index;
+        while nodes.len() > 1 {
+            siblings.push(Hash(nodes[position ^ 1]));
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| merkle_parent(&pair[0], &pair[1]))
+                .collect();
+            position /= 2;
+        }
+        Some(Self {
+            index: index as u64,
+            count,
+            siblings,
+        })
+    }
 
-    while leaves.len() > 1 {
-        if leaves.len() % 2 != 0 {
-            leaves.push(*leaves.last().unwrap());
+    /// Whether `encoded` (e.g. `Transaction::encode`) is the leaf at `index` under `root`.
+    pub fn verify(&self, root: &Hash, encoded: &[u8]) -> bool {
+        if self.index >= self.count
+            || self.siblings.len() as u32 != self.count.next_power_of_two().trailing_zeros()
+        {
+            return false;
         }
-        let mut next_level = Vec::new();
-        for chunk in leaves.chunks(2) {
-            let left = &chunk[0];
-            let right = &chunk[1];
-            // Hash(left ++ right)
-            let mut data = Vec::with_capacity(64);
-            data.extend_from_slice(&left.0);
-            data.extend_from_slice(&right.0);
-            next_level.push(Hash(keccak256(&data).into()));
+        let mut node = keccak256(encoded).0;
+        let mut position = self.index;
+        for sibling in &self.siblings {
+            node = if position % 2 == 0 {
+                merkle_parent(&node, &sibling.0)
+            } else {
+                merkle_parent(&sibling.0, &node)
+            };
+            position /= 2;
         }
-        leaves = next_level;
+        mix_in_length(node, self.count) == *root
     }
-    leaves[0]
 }
 
 /// Messages used for Block Synchronization
@@ -229,4 +1315,32 @@ pub fn calculate_receipts_root(receipts: &[Receipt]) -> Hash {
 pub enum SyncMessage {
     RequestBlock(Hash),
     ResponseBlock(Box<Block>),
+    /// Light clients ask full nodes for an account proof at their committed head.
+    RequestAccountProof(Address),
+    ResponseAccountProof(Box<crate::light::AccountProof>),
+    /// State witness of a validated block, for stateless validation.
+    RequestWitness(Hash),
+    ResponseWitness(Hash, Box<crate::state::StateWitness>),
+    /// Transactions of a gossiped compact block missing from our pool, by hash.
+    RequestTransactions(Hash, Vec<Hash>),
+    ResponseTransactions(Hash, Vec<Transaction>),
+    /// Whole body of a compact block whose transaction list does not match its header.
+    RequestBody(Hash),
+    ResponseBody(Hash, Box<BlockBody>),
+    /// Periodic announcement of the sender's pending transactions (mempool anti-entropy).
+    PoolHashes(Vec<Hash>),
+    /// Announced pool transactions missing from our pool, by hash.
+    RequestPoolTransactions(Vec<Hash>),
+    ResponsePoolTransactions(Vec<Transaction>),
+    /// Committee members' shares of the keys of notarized encrypted transactions.
+    DecryptionShares(Vec<DecryptionShare>),
+    /// Where the sender's chain is, announced on peer connect so that a rejoining node
+    /// catches up without waiting for the next block. `high_qc` is the sender's highest
+    /// certificate, which lets the receiver jump straight to the view after it.
+    Status {
+        current_view: View,
+        finalized_height: View,
+        head_hash: Hash,
+        high_qc: QuorumCertificate,
+    },
 }
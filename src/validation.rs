@@ -0,0 +1,149 @@
+//! Block validation off the consensus event loop.
+//!
+//! With `SimplexState::with_async_validation`, incoming blocks pass the cheap checks
+//! (signature, parent, committee, evidence) on the event loop, and their re-execution is
+//! queued as a `ValidationJob`. A `ValidationPool` runs the jobs on worker threads and
+//! delivers `BlockValidated` events back to `SimplexState::on_block_validated`, which
+//! stores the block and votes.
+
+use crate::consensus::ConsensusError;
+use crate::crypto::Hash;
+use crate::engine::ExecutionEngine;
+use crate::state::StateWitness;
+use crate::types::{Block, View};
+use crate::vm::ExecutionError;
+use std::sync::{Arc, Mutex, mpsc};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Worker threads used when `--validation-workers` is not given.
+pub const DEFAULT_VALIDATION_WORKERS: usize = 2;
+
+/// Re-execution of a block, prepared on the event loop: the block and the state to run it
+/// on (its parent's state, or its witness when validating statelessly).
+pub struct ValidationJob {
+    pub block: Block,
+    /// Peer that sent the block, for orphan quotas.
+    pub peer: Option<String>,
+    /// Arrived as a sync response rather than a live proposal (no vote is cast).
+    pub sync: bool,
+    pub(crate) engine: Arc<dyn ExecutionEngine>,
+    /// State root of the parent block.
+    pub(crate) parent_root: Hash,
+    /// Checked witness to execute on (stateless validation only).
+    pub(crate) witness: Option<StateWitness>,
+}
+
+impl ValidationJob {
+    pub fn view(&self) -> View {
+        self.block.view
+    }
+
+    /// Execute the block and compare its state and receipts roots.
+    pub fn run(self) -> BlockValidated {
+        let result = check_execution(
+            &self.block,
+            self.engine.as_ref(),
+            self.parent_root,
+            self.witness.as_ref(),
+        );
+        BlockValidated {
+            block: self.block,
+            peer: self.peer,
+            sync: self.sync,
+            result,
+        }
+    }
+}
+
+/// Outcome of a `ValidationJob`: the witness recorded during execution on success.
+#[derive(Debug)]
+pub struct BlockValidated {
+    pub block: Block,
+    pub peer: Option<String>,
+    pub sync: bool,
+    pub result: Result<Option<StateWitness>, ConsensusError>,
+}
+
+/// Re-execute `block` with `engine` on its parent state (or `witness`) and check the
+/// roots it commits to. Returns the witness recorded when executing on our own state.
+pub(crate) fn check_execution(
+    block: &Block,
+    engine: &dyn ExecutionEngine,
+    parent_root: Hash,
+    witness: Option<&StateWitness>,
+) -> Result<Option<StateWitness>, ConsensusError> {
+    // The engine overwrites the roots and gas used; left as they are, the block keeps its
+    // hash, under which the engine may have cached its execution
+    let mut executed_block = block.clone();
+
+    let recorded = engine
+        .execute_and_validate(&mut executed_block, parent_root, witness)
+        .map_err(|e| {
+            tracing::error!("Block Execution Failed: {:?}", e);
+            match e {
+                ExecutionError::GasLimitExceeded { gas, limit } => {
+                    ConsensusError::GasLimitExceeded { gas, limit }
+                }
+                _ => ConsensusError::InvalidBlock,
+            }
+        })?;
+
+    if block.state_root != executed_block.state_root {
+        tracing::error!(
+            "Invalid State Root: expected {:?}, got {:?}",
+            block.state_root,
+            executed_block.state_root
+        );
+        return Err(ConsensusError::InvalidStateRoot);
+    }
+
+    if executed_block.receipts_root != block.receipts_root
+        || executed_block.logs_bloom != block.logs_bloom
+    {
+        tracing::error!(
+            "Invalid Receipts Root: expected {:?}, got {:?}",
+            block.receipts_root,
+            executed_block.receipts_root
+        );
+        return Err(ConsensusError::InvalidReceiptsRoot);
+    }
+    Ok(recorded)
+}
+
+/// Fixed set of threads running `ValidationJob`s; each result is sent to `results`.
+/// The workers stop once the pool and the results receiver are dropped.
+pub struct ValidationPool {
+    jobs: mpsc::Sender<ValidationJob>,
+}
+
+impl ValidationPool {
+    pub fn new(workers: usize, results: UnboundedSender<BlockValidated>) -> Self {
+        let (jobs, receiver) = mpsc::channel::<ValidationJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            let results = results.clone();
+            std::thread::Builder::new()
+                .name(format!("validation-{}", i))
+                .spawn(move || {
+                    loop {
+                        let job = match receiver.lock().unwrap().recv() {
+                            Ok(job) => job,
+                            Err(_) => break,
+                        };
+                        if results.send(job.run()).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .expect("Failed to spawn validation worker");
+        }
+        Self { jobs }
+    }
+
+    pub fn submit(&self, job: ValidationJob) {
+        if self.jobs.send(job).is_err() {
+            tracing::warn!("Validation workers stopped, dropping a validation job");
+        }
+    }
+}
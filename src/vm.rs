@@ -1,14 +1,25 @@
-use crate::crypto::Hash;
+use crate::crypto::{Hash, PublicKey, hash_data};
+use crate::engine::{DEFAULT_EXECUTION_CACHE_ENTRIES, ExecutionCache};
+use crate::precompiles::{PrecompileContext, PrecompileRegistry};
 use crate::state::StateManager;
-use crate::types::Block;
+use crate::storage::ConsensusState;
+use crate::system_contracts::{encrypted_mempool, governance, staking};
+use crate::types::{
+    Block, Bloom, Hardfork, View, calculate_transactions_root, logs_bloom, validate_tx_stateless,
+};
 use revm::Database; // Import for .basic() method
 use revm::{
     EVM,
-    primitives::{Address, CreateScheme, ExecutionResult, ResultAndState, TransactTo, U256},
+    primitives::{
+        Address, B256, CreateScheme, Env, ExecutionResult, ResultAndState, SpecId, TransactTo, U256,
+    },
 };
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use thiserror::Error;
 
+pub mod wasm;
+
 #[derive(Debug, Error)]
 pub enum ExecutionError {
     #[error("EVM Error: {0}")]
@@ -17,6 +28,59 @@ pub enum ExecutionError {
     State(String),
     #[error("Transaction Error: {0}")]
     Transaction(String),
+    #[error("WASM Error: {0}")]
+    Wasm(String),
+    #[error("Execution Reverted: {}", revert_message(.output))]
+    Revert { gas_used: u64, output: Vec<u8> },
+    #[error("Block gas limit exceeded: {gas} > {limit}")]
+    GasLimitExceeded { gas: u64, limit: u64 },
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(#[from] crate::types::TxValidityError),
+}
+
+/// Selectors of Solidity's `Error(string)` and `Panic(uint256)` revert payloads.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Human-readable reason carried by revert data, if it is an `Error(string)` or a
+/// `Panic(uint256)` payload.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    let (selector, data) = output.split_at_checked(4)?;
+    if selector == ERROR_SELECTOR {
+        let offset = abi_word(data, 0)?;
+        let len = abi_word(data, offset)?;
+        let start = offset.checked_add(32)?;
+        let message = data.get(start..start.checked_add(len)?)?;
+        return Some(String::from_utf8_lossy(message).into_owned());
+    }
+    if selector == PANIC_SELECTOR {
+        let code = U256::from_be_slice(data.get(..32)?);
+        return Some(format!("Panic(0x{:x})", code));
+    }
+    None
+}
+
+/// ABI-encode `reason` as an `Error(string)` revert payload.
+pub fn encode_revert_reason(reason: &str) -> Vec<u8> {
+    let mut out = ERROR_SELECTOR.to_vec();
+    out.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+    out.extend_from_slice(&U256::from(reason.len()).to_be_bytes::<32>());
+    out.extend_from_slice(reason.as_bytes());
+    out.resize(out.len() + (32 - reason.len() % 32) % 32, 0);
+    out
+}
+
+// A 32-byte ABI word at `at`, as an offset or length
+fn abi_word(data: &[u8], at: usize) -> Option<usize> {
+    let word = data.get(at..at.checked_add(32)?)?;
+    if word[..24].iter().any(|b| *b != 0) {
+        return None;
+    }
+    usize::try_from(u64::from_be_bytes(word[24..].try_into().ok()?)).ok()
+}
+
+fn revert_message(output: &[u8]) -> String {
+    decode_revert_reason(output).unwrap_or_else(|| format!("0x{}", hex::encode(output)))
 }
 
 #[cfg(test)]
@@ -38,6 +102,13 @@ mod tests {
 pub struct Executor {
     pub state: Arc<Mutex<StateManager>>,
     pub block_gas_limit: u64,
+    pub precompiles: Arc<PrecompileRegistry>,
+    /// Chain whose transactions this executor accepts (also returned by `CHAINID`).
+    pub chain_id: u64,
+    /// EVM rules transactions execute with (the chain spec's).
+    pub hardfork: Hardfork,
+    /// Outcomes of validated blocks, reused when they are seen again or committed.
+    pub execution_cache: Arc<ExecutionCache>,
 }
 
 impl Executor {
@@ -45,108 +116,151 @@ impl Executor {
         Self {
             state,
             block_gas_limit,
+            precompiles: Arc::new(PrecompileRegistry::with_defaults()),
+            chain_id: crate::types::DEFAULT_CHAIN_ID,
+            hardfork: Hardfork::default(),
+            execution_cache: Arc::new(ExecutionCache::new(DEFAULT_EXECUTION_CACHE_ENTRIES)),
         }
     }
 
+    /// Execute for `chain_id` (the chain spec's) instead of the default.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Execute with the rules of `hardfork` instead of the default.
+    pub fn with_hardfork(mut self, hardfork: Hardfork) -> Self {
+        self.hardfork = hardfork;
+        self
+    }
+
+    /// The same configuration on another state (e.g. a fork for a proposal or validation).
+    pub fn with_state(&self, state: Arc<Mutex<StateManager>>) -> Self {
+        Self {
+            state,
+            ..self.clone()
+        }
+    }
+
+    /// Keep validated execution outcomes in `cache` (e.g. one sized by `--execution-cache`).
+    pub fn with_execution_cache(mut self, cache: Arc<ExecutionCache>) -> Self {
+        self.execution_cache = cache;
+        self
+    }
+
+    /// Replace the precompile registry (e.g. to register chain-specific handlers).
+    pub fn with_precompiles(mut self, precompiles: Arc<PrecompileRegistry>) -> Self {
+        self.precompiles = precompiles;
+        self
+    }
+
     pub fn execute_block(&self, block: &mut Block) -> Result<(), ExecutionError> {
+        self.execute_block_until(block, None)
+    }
+
+    /// Execute `block` as `execute_block` does, but stop at `deadline`: the payload
+    /// transactions not executed by then (bar revealed ones) are cut from the block, whose
+    /// transactions root and metadata are updated. Only for building our own proposals.
+    #[tracing::instrument(
+        name = "execute_block",
+        skip_all,
+        fields(view = block.view, txs = block.payload.len(), gas_used = tracing::field::Empty)
+    )]
+    pub fn execute_block_until(
+        &self,
+        block: &mut Block,
+        deadline: Option<Instant>,
+    ) -> Result<(), ExecutionError> {
         // Validation: Ensure block gas limit is respected by consensus
         // Also consensus ensures parent hash linkage.
 
         let mut db = self.state.lock().unwrap();
+        db.set_block_context(block.height, block.parent_hash);
         let mut cumulative_gas_used = 0u64;
-        log::info!(
+        tracing::info!(
             "Executing block view {} with {} txs",
             block.view,
             block.payload.len()
         );
 
+        // Live parameters (as of the parent); the configured gas limit only without a state.
+        // The block's header gas limit applies, within the chain's cap
+        let live_params = db.get_consensus_state().ok().flatten().map(|s| s.params);
+        let block_gas_limit = match &live_params {
+            Some(params) => crate::fees::execution_gas_limit(params, block),
+            None => self.block_gas_limit,
+        };
+        let params = live_params.unwrap_or_default();
+
         // 0. Process Evidence (Slashing)
         for evidence in &block.evidence {
             let v1 = &evidence.vote_a;
             let v2 = &evidence.vote_b;
 
+            if params.is_evidence_expired(evidence, block.view) {
+                tracing::warn!("Evidence Invalid: Expired (offence at View {})", v1.view);
+                continue;
+            }
+
             // 1. Verify Structure
             if v1.author != v2.author {
-                log::warn!("Evidence Invalid: Different Authors");
+                tracing::warn!("Evidence Invalid: Different Authors");
                 continue;
             }
             if v1.view != v2.view {
-                log::warn!("Evidence Invalid: Different Views");
+                tracing::warn!("Evidence Invalid: Different Views");
                 continue;
             }
             if v1.block_hash == v2.block_hash {
-                log::warn!("Evidence Invalid: Same Block Hash (Not equivocation)");
+                tracing::warn!("Evidence Invalid: Same Block Hash (Not equivocation)");
                 continue;
             }
 
             // 2. Verify Signatures
-            let a_valid = crate::crypto::verify(&v1.author, &v1.block_hash.0, &v1.signature);
-            let b_valid = crate::crypto::verify(&v2.author, &v2.block_hash.0, &v2.signature);
-
-            if !a_valid || !b_valid {
-                log::warn!("Evidence Invalid: Bad Signatures");
+            if !v1.verify_signature(self.chain_id) || !v2.verify_signature(self.chain_id) {
+                tracing::warn!("Evidence Invalid: Bad Signatures");
                 continue;
             }
 
             // 3. Slash!
-            let offender = v1.author.clone();
-            // Need Address from PublicKey
-            let pk_bytes = offender.0.to_bytes();
-            let hash = crate::types::keccak256(pk_bytes);
-            let address = Address::from_slice(&hash[12..]);
-
-            let slashed_amount = U256::from(1000u64); // Fixed Slash Amount
-
-            if let Ok(Some(mut state)) = db.get_consensus_state() {
-                if let Some(stake) = state.stakes.get_mut(&address) {
-                    if *stake < slashed_amount {
-                        *stake = U256::ZERO;
-                    } else {
-                        *stake -= slashed_amount;
-                    }
-
-                    log::warn!(
-                        "Slashed Validator {:?} amount {:?}",
-                        address,
-                        slashed_amount
-                    );
+            Self::slash_offender(
+                &mut db,
+                &v1.author,
+                params.slash_amount,
+                params.min_stake,
+                block.view,
+            )?;
+        }
 
-                    // 4. Remove from Committee if low stake
-                    let min_stake = U256::from(2000u64);
-                    if *stake < min_stake {
-                        // Check Pending
-                        if let Some(pos) = state
-                            .pending_validators
-                            .iter()
-                            .position(|(pk, _)| *pk == offender)
-                        {
-                            state.pending_validators.remove(pos);
-                            log::warn!(
-                                "Validator Removed from Pending (Low Stake): {:?}",
-                                offender
-                            );
-                        }
-                        // Check Active
-                        if let Some(pos) = state.committee.iter().position(|x| *x == offender) {
-                            state.committee.remove(pos);
-                            log::warn!(
-                                "Validator Removed from Committee (Low Stake): {:?}",
-                                offender
-                            );
-                        }
-                    }
-                    db.save_consensus_state(&state).unwrap();
-                } else {
-                    log::warn!(
-                        "Validator {:?} has no stake entry found for address {:?}",
-                        offender,
-                        address
-                    );
-                }
+        // 0.1 Process Double-Proposal Evidence
+        let mut punished = std::collections::HashSet::new();
+        for evidence in &block.proposal_evidence {
+            if params.is_proposal_evidence_expired(evidence, block.view) {
+                tracing::warn!(
+                    "Proposal Evidence Invalid: Expired (offence at View {})",
+                    evidence.view()
+                );
+                continue;
+            }
+            if !evidence.is_valid() {
+                tracing::warn!("Proposal Evidence Invalid: Not a double proposal");
+                continue;
             }
+            // Slash once per offence, whichever pair of blocks reports it
+            if !punished.insert((evidence.offender().clone(), evidence.view())) {
+                continue;
+            }
+            Self::slash_offender(
+                &mut db,
+                evidence.offender(),
+                params.proposal_slash_amount,
+                params.min_stake,
+                block.view,
+            )?;
         }
 
-        // 0.5 Process Liveness (Leader Slashing)
+        // 0.5 Process Liveness (Leader Slashing and Inactivity Leak)
         if let Ok(Some(mut state)) = db.get_consensus_state() {
             let mut changed = false;
 
@@ -169,244 +283,301 @@ impl Executor {
                     let failed_leader_idx = (qc.view as usize) % committee_len;
                     // Safety check index
                     if let Some(failed_leader) = state.committee.get(failed_leader_idx).cloned() {
-                        log::warn!(
+                        tracing::warn!(
                             "Timeout QC for View {}. Penalizing Leader {:?}",
                             qc.view,
                             failed_leader
                         );
-
-                        // Increment Score
-                        let score = state
-                            .inactivity_scores
-                            .entry(failed_leader.clone())
-                            .or_insert(0);
-                        *score += 1;
-                        let current_score = *score;
+                        Self::penalize_inactive(
+                            &mut db,
+                            &mut state,
+                            &failed_leader,
+                            params.inactivity_penalty,
+                            params.inactivity_threshold,
+                            block.view,
+                        )?;
                         changed = true;
+                    }
+                }
+            }
 
-                        // Immediate Slash (Incremental)
-                        let penalty = U256::from(10u64);
-                        let pk_bytes = failed_leader.0.to_bytes();
-                        let hash = crate::types::keccak256(pk_bytes);
-                        let address = Address::from_slice(&hash[12..]);
-
-                        if let Some(stake) = state.stakes.get_mut(&address) {
-                            if *stake < penalty {
-                                *stake = U256::ZERO;
-                            } else {
-                                *stake -= penalty;
-                            }
-                            changed = true;
-                        } else {
-                            log::warn!(
-                                "Validator {:?} has no stake entry found for address {:?}",
-                                failed_leader,
-                                address
-                            );
-                        }
+            // 3. Inactivity Leak: members missing from consecutive block QCs (past the
+            // grace) are penalized in proportion to the length of their absence
+            if qc.block_hash != Hash::default() && qc.view > 0 {
+                for (index, member) in state.committee.clone().into_iter().enumerate() {
+                    if qc.signers.contains(index) {
+                        changed |= state.missed_attestations.remove(&member).is_some();
+                        continue;
+                    }
+                    let missed = state.missed_attestations.entry(member.clone()).or_insert(0);
+                    *missed += 1;
+                    let missed = *missed;
+                    changed = true;
 
-                        // Threshold Check
-                        if current_score > 50 {
-                            log::warn!(
-                                "Validator {:?} exceeded inactivity threshold ({}). Removing from committee.",
-                                failed_leader,
-                                current_score
-                            );
-                            if let Some(pos) =
-                                state.committee.iter().position(|x| *x == failed_leader)
-                            {
-                                state.committee.remove(pos);
-                                // Reset score
-                                state.inactivity_scores.remove(&failed_leader);
-                                changed = true;
-                            }
-                        }
+                    let overshoot = missed.saturating_sub(params.attestation_grace);
+                    if overshoot > 0 {
+                        tracing::warn!(
+                            "Validator {:?} missing from {} consecutive QCs. Leaking stake.",
+                            member,
+                            missed
+                        );
+                        Self::penalize_inactive(
+                            &mut db,
+                            &mut state,
+                            &member,
+                            params.attestation_penalty * U256::from(overshoot),
+                            params.inactivity_threshold,
+                            block.view,
+                        )?;
                     }
                 }
             }
 
             if changed {
-                db.save_consensus_state(&state).unwrap();
+                db.save_consensus_state(&state)
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
             }
         }
 
-        for tx in &block.payload {
-            if tx.gas_limit > self.block_gas_limit {
-                return Err(ExecutionError::Transaction(
-                    "Tx exceeds block gas limit".into(),
-                ));
+        // 0.6 Accrue Rewards (claimed through the staking contract)
+        if let Ok(Some(state)) = db.get_consensus_state() {
+            let qc = &block.justify;
+            // A timeout QC attests to no block
+            let attesters: Vec<PublicKey> = if qc.block_hash == Hash::default() {
+                vec![]
+            } else {
+                qc.signers
+                    .indices()
+                    .filter_map(|index| state.committee.get(index).cloned())
+                    .collect()
+            };
+            let rewards = std::iter::once((&block.author, params.proposer_reward))
+                .chain(attesters.iter().map(|pk| (pk, params.attester_reward)));
+            for (pk, amount) in rewards {
+                if amount > U256::ZERO {
+                    let reward = db
+                        .get_reward(pk)
+                        .map_err(|e| ExecutionError::State(e.to_string()))?;
+                    db.save_reward(pk, reward + amount)
+                        .map_err(|e| ExecutionError::State(e.to_string()))?;
+                }
+            }
+        }
+
+        // 0.7 Reveal Encrypted Transactions: each key opens a ciphertext into the
+        // transaction at its position, at the head of the payload
+        let revealed = block.metadata.decryption_keys.len();
+        if revealed > block.payload.len() {
+            return Err(ExecutionError::Transaction(
+                "More decryption keys than transactions".into(),
+            ));
+        }
+        if revealed > 0 {
+            let committee = db
+                .get_consensus_state()
+                .map_err(|e| ExecutionError::State(e.to_string()))?
+                .map(|state| state.committee)
+                .unwrap_or_default();
+            for (key, tx) in block.metadata.decryption_keys.iter().zip(&block.payload) {
+                let decrypted = encrypted_mempool::open(&committee, key)
+                    .map_err(|e| ExecutionError::Transaction(e.into()))?;
+                if decrypted != *tx {
+                    return Err(ExecutionError::Transaction(
+                        "Revealed transaction does not match the payload".into(),
+                    ));
+                }
+            }
+        }
+
+        // Each transaction may use up to its gas limit, so the block must afford all of
+        // them. Revealed transactions are checked one by one below: their proposer could
+        // not vet them before they were ordered
+        let declared_gas = block.payload[revealed..]
+            .iter()
+            .fold(0u64, |gas, tx| gas.saturating_add(tx.gas_limit));
+        if declared_gas > block_gas_limit {
+            return Err(ExecutionError::GasLimitExceeded {
+                gas: declared_gas,
+                limit: block_gas_limit,
+            });
+        }
+        for (i, tx) in block.payload.iter().enumerate() {
+            if tx.chain_id != self.chain_id {
+                return Err(ExecutionError::Transaction(format!(
+                    "Invalid chain id: expected {}, got {}",
+                    self.chain_id, tx.chain_id
+                )));
+            }
+            if i >= revealed {
+                validate_tx_stateless(tx, Some(block.base_fee_per_gas))?;
             }
         }
 
         let mut receipts = Vec::with_capacity(block.payload.len());
+        let mut cut = None;
 
         for (i, tx) in block.payload.iter().enumerate() {
+            if i >= revealed && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                cut = Some(i);
+                break;
+            }
+
             // 1. Validate signature (simple check here, or assume consensus did it?)
             if tx.sender() == Address::ZERO {
                 return Err(ExecutionError::Transaction("Invalid sender".into()));
             }
 
-            // 2. Setup EVM
-            let mut evm = EVM::new();
-            evm.database(&mut *db);
-
-            // SYSTEM CONTRACT INTERCEPTION (Address 0x1000)
-            let sys_contract = Address::from_slice(
-                &hex::decode("0000000000000000000000000000000000001000").unwrap(),
-            );
+            // A revealed transaction that cannot execute fails on its own, without gas
+            if i < revealed
+                && let Err(reason) = self.reveal(
+                    &mut db,
+                    block,
+                    i,
+                    params.decryption_window,
+                    block_gas_limit,
+                    cumulative_gas_used,
+                )?
+            {
+                tracing::warn!("Revealed Tx {} not executed: {}", i, reason);
+                receipts.push(crate::types::Receipt {
+                    status: 0,
+                    cumulative_gas_used,
+                    logs: vec![],
+                    logs_bloom: Bloom::default(),
+                    gas_used: 0,
+                    revert_output: encode_revert_reason(&reason).into(),
+                    contract_address: None,
+                });
+                continue;
+            }
 
-            if tx.to == Some(sys_contract) {
-                // System Contract Call
-                log::info!("System Contract Call detected from {:?}", tx.sender());
+            // What the revealed transactions used counts against the rest of the block
+            let gas_after = cumulative_gas_used.saturating_add(tx.gas_limit);
+            if i >= revealed && gas_after > block_gas_limit {
+                return Err(ExecutionError::GasLimitExceeded {
+                    gas: gas_after,
+                    limit: block_gas_limit,
+                });
+            }
 
-                // Simple Gas/Nonce deduction (Simulated for MVP)
-                let sender_acc = db.basic(tx.sender()).unwrap().unwrap();
-                if sender_acc.balance < tx.value {
-                    // + fee in real impl
+            // PRECOMPILE INTERCEPTION (standard precompiles, BLS verify, system contracts)
+            if let Some(handler) = tx.to.and_then(|to| self.precompiles.get(&to)) {
+                tracing::info!(
+                    "Precompile '{}' called by {:?}",
+                    handler.name(),
+                    tx.sender()
+                );
+
+                // Effective gas price (EIP-1559)
+                let gas_price = std::cmp::min(
+                    tx.max_fee_per_gas,
+                    block.base_fee_per_gas + tx.max_priority_fee_per_gas,
+                );
+                let sender_acc = db
+                    .basic(tx.sender())
+                    .map_err(|e| ExecutionError::State(e.to_string()))?
+                    .unwrap_or_default();
+                if sender_acc.balance < tx.value + gas_price * U256::from(tx.gas_limit) {
                     return Err(ExecutionError::Transaction("Insufficient Balance".into()));
                 }
 
-                // Decode Selector
-                if tx.data.len() >= 4 {
-                    let selector = &tx.data[0..4];
-                    match selector {
-                        // stake() -> 0x3a4b66f1
-                        [0x3a, 0x4b, 0x66, 0xf1] => {
-                            let min_stake = U256::from(2000u64); // Threshold
-                            if tx.value < min_stake {
-                                log::error!("Stake too low: {:?}", tx.value);
-                            } else if let Ok(Some(mut state)) = db.get_consensus_state() {
-                                let sender_pk = tx.public_key.clone();
-
-                                // 1. Lock Funds
-                                let current_stake =
-                                    *state.stakes.get(&tx.sender()).unwrap_or(&U256::ZERO);
-                                state.stakes.insert(tx.sender(), current_stake + tx.value);
-
-                                // 2. Add to Pending (if not already active/pending)
-                                let is_active = state.committee.contains(&sender_pk);
-                                let is_pending = state
-                                    .pending_validators
-                                    .iter()
-                                    .any(|(pk, _)| *pk == sender_pk);
-
-                                if !is_active && !is_pending {
-                                    let activation_view = block.view + 10; // Delay 10
-                                    state
-                                        .pending_validators
-                                        .push((sender_pk.clone(), activation_view));
-                                    log::info!(
-                                        "Validator Pending: {:?} until view {}",
-                                        sender_pk,
-                                        activation_view
-                                    );
-                                }
-                                db.save_consensus_state(&state).unwrap();
-                            }
-                        }
-                        // unstake() -> 0x2e17de78
-                        [0x2e, 0x17, 0xde, 0x78] => {
-                            if let Ok(Some(mut state)) = db.get_consensus_state() {
-                                let sender_pk = tx.public_key.clone();
-
-                                // Must be Active to Unstake
-                                if state.committee.contains(&sender_pk) {
-                                    // Schedule Exit
-                                    let exit_view = block.view + 10; // Delay 10
-                                    state
-                                        .exiting_validators
-                                        .push((sender_pk.clone(), exit_view));
-                                    log::info!(
-                                        "Validator Exiting: {:?} at view {}",
-                                        sender_pk,
-                                        exit_view
-                                    );
-                                    db.save_consensus_state(&state).unwrap();
-                                }
-                            }
-                        }
-                        // withdraw() -> 0x3ccfd60b
-                        [0x3c, 0xcf, 0xd6, 0x0b] => {
-                            if let Ok(Some(mut state)) = db.get_consensus_state() {
-                                let sender_pk = tx.public_key.clone();
-                                let sender_addr = tx.sender();
-
-                                let is_active = state.committee.contains(&sender_pk);
-                                let is_pending = state
-                                    .pending_validators
-                                    .iter()
-                                    .any(|(pk, _)| *pk == sender_pk);
-                                let is_exiting = state
-                                    .exiting_validators
-                                    .iter()
-                                    .any(|(pk, _)| *pk == sender_pk);
-
-                                #[allow(clippy::collapsible_if)]
-                                if let Some(stake) = state.stakes.get(&sender_addr).cloned() {
-                                    if !is_active
-                                        && !is_pending
-                                        && !is_exiting
-                                        && stake > U256::ZERO
-                                    {
-                                        // Refund
-                                        state.stakes.insert(sender_addr, U256::ZERO);
-                                        db.save_consensus_state(&state).unwrap();
-
-                                        // Credit Balance
-                                        let mut acc =
-                                            db.basic(sender_addr).unwrap().unwrap_or_default();
-                                        acc.balance += stake;
-
-                                        let new_info = crate::storage::AccountInfo {
-                                            nonce: acc.nonce,
-                                            balance: acc.balance,
-                                            code_hash: Hash(acc.code_hash.0),
-                                            code: acc.code.map(|c| c.original_bytes()),
-                                        };
-                                        db.commit_account(sender_addr, new_info).unwrap();
-
-                                        log::info!(
-                                            "Withdrawn Stake: {:?} for {:?}",
-                                            stake,
-                                            sender_addr
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        _ => {
-                            log::warn!("Unknown System Contract Function");
+                let mut ctx = PrecompileContext {
+                    db: &mut *db,
+                    block,
+                    tx,
+                };
+                let (status, gas_used, logs, revert_output) =
+                    match handler.call(&mut ctx, &tx.data, tx.gas_limit) {
+                        Ok(out) => (1u8, out.gas_used, out.logs, vec![]),
+                        Err(e) => {
+                            tracing::warn!("Precompile '{}' failed: {}", handler.name(), e);
+                            let reason = encode_revert_reason(&e.to_string());
+                            (0u8, tx.gas_limit, vec![], reason)
                         }
-                    }
-                }
+                    };
 
-                // Skip EVM Execution for this Tx, but record receipt?
-                // Deduct Balance manually
-                // CRITICAL FIX: Reload account info because it might have been modified by the System Contract Logic (e.g. withdraw refund)
-                let updated_acc = db.basic(tx.sender()).unwrap().unwrap_or_default();
+                // Skip EVM Execution for this Tx: charge the fee and move the value manually
+                // CRITICAL FIX: Reload account info because it might have been modified by the precompile (e.g. withdraw refund)
+                let updated_acc = db
+                    .basic(tx.sender())
+                    .map_err(|e| ExecutionError::State(e.to_string()))?
+                    .unwrap_or_default();
+                let value = if status == 1 { tx.value } else { U256::ZERO };
+                let fee = gas_price * U256::from(gas_used);
 
                 let new_info = crate::storage::AccountInfo {
                     nonce: updated_acc.nonce + 1,
-                    balance: updated_acc.balance - tx.value,
+                    balance: updated_acc.balance - value - fee,
                     code_hash: Hash(updated_acc.code_hash.0),
-                    code: updated_acc.code.map(|c| c.original_bytes()),
                 };
-                db.commit_account(tx.sender(), new_info).unwrap();
+                db.commit_account(tx.sender(), new_info)
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
+
+                // Value sent to a precompile is held by its account (e.g. locked stake)
+                if value > U256::ZERO {
+                    let to = tx.to.unwrap_or_default();
+                    let target = db
+                        .basic(to)
+                        .map_err(|e| ExecutionError::State(e.to_string()))?
+                        .unwrap_or_default();
+                    let target_info = crate::storage::AccountInfo {
+                        nonce: target.nonce,
+                        balance: target.balance + value,
+                        code_hash: Hash(target.code_hash.0),
+                    };
+                    db.commit_account(to, target_info)
+                        .map_err(|e| ExecutionError::State(e.to_string()))?;
+                }
 
-                // Credit 0x1000? (Optional, burn is fine for now or lock)
+                Self::pay_fee_recipient(&mut db, block, tx, gas_used)?;
 
-                // Push Receipt
+                cumulative_gas_used += gas_used;
                 receipts.push(crate::types::Receipt {
-                    status: 1,
+                    status,
                     cumulative_gas_used,
-                    logs: vec![],
+                    logs_bloom: logs_bloom(&logs),
+                    logs,
+                    gas_used,
+                    revert_output: revert_output.into(),
+                    contract_address: None,
                 });
 
                 continue; // Skip standard EVM
             }
 
-            // Set Block Info
-            evm.env.block.basefee = block.base_fee_per_gas;
+            // WASM ROUTING: creation payloads carrying the wasm magic, or calls into wasm code
+            let wasm_code = match tx.to {
+                None if wasm::is_wasm(&tx.data) => Some(tx.data.to_vec()),
+                None => None,
+                Some(to) => db
+                    .basic(to)
+                    .map_err(|e| ExecutionError::State(e.to_string()))?
+                    .and_then(|acc| acc.code)
+                    .map(|c| c.original_bytes().to_vec())
+                    .filter(|c| wasm::is_wasm(c)),
+            };
+
+            if let Some(code) = wasm_code {
+                let receipt =
+                    self.execute_wasm_tx(&mut db, block, tx, code, cumulative_gas_used)?;
+                cumulative_gas_used = receipt.cumulative_gas_used;
+                tracing::info!(
+                    "WASM Tx {} executed. Status: {}. Cumulative: {}",
+                    i,
+                    receipt.status,
+                    cumulative_gas_used
+                );
+                receipts.push(receipt);
+                continue; // Skip standard EVM
+            }
+
+            // 2. Setup EVM
+            let mut evm = EVM::new();
+            evm.database(&mut *db);
+
+            // Set Chain and Block Info
+            self.configure_env(&mut evm.env);
+            self.configure_block_env(block, block_gas_limit, &mut evm.env);
 
             // 3. Populate TxEnv
             let tx_env = &mut evm.env.tx;
@@ -422,6 +593,7 @@ impl Executor {
             tx_env.gas_price = tx.max_fee_per_gas;
             tx_env.gas_priority_fee = Some(tx.max_priority_fee_per_gas);
             tx_env.nonce = Some(tx.nonce);
+            tx_env.access_list = crate::types::revm_access_list(&tx.access_list);
 
             // 4. Execute
             let result_and_state = evm
@@ -432,21 +604,27 @@ impl Executor {
             let ResultAndState { result, state } = result_and_state;
 
             // Track gas and extract logs
-            let (gas_used, status, logs) = match result {
-                ExecutionResult::Success { gas_used, logs, .. } => (gas_used, 1u8, logs),
+            let (gas_used, status, logs, revert_output) = match result {
+                ExecutionResult::Success { gas_used, logs, .. } => {
+                    (gas_used, 1u8, logs, Default::default())
+                }
                 ExecutionResult::Revert { gas_used, output } => {
-                    log::warn!("Tx Reverted! Gas: {}, Output: {:?}", gas_used, output);
-                    (gas_used, 0u8, vec![])
+                    tracing::warn!(
+                        "Tx Reverted! Gas: {}, Reason: {}",
+                        gas_used,
+                        revert_message(&output)
+                    );
+                    (gas_used, 0u8, vec![], output)
                 }
                 ExecutionResult::Halt {
                     gas_used, reason, ..
                 } => {
-                    log::warn!("Tx Halted! Gas: {}, Reason: {:?}", gas_used, reason);
-                    (gas_used, 0u8, vec![])
+                    tracing::warn!("Tx Halted! Gas: {}, Reason: {:?}", gas_used, reason);
+                    (gas_used, 0u8, vec![], Default::default())
                 }
             };
             cumulative_gas_used += gas_used;
-            log::info!(
+            tracing::info!(
                 "Tx {} executed. Gas used: {}. Cumulative: {}",
                 i,
                 gas_used,
@@ -466,31 +644,60 @@ impl Executor {
             receipts.push(crate::types::Receipt {
                 status,
                 cumulative_gas_used,
+                logs_bloom: logs_bloom(&receipt_logs),
                 logs: receipt_logs,
+                gas_used,
+                revert_output: crate::types::Bytes::from(revert_output.to_vec()),
+                contract_address: (status == 1 && tx.is_create())
+                    .then(|| tx.sender().create(tx.nonce)),
             });
 
-            if status == 1 {
-                // Success
-                for (address, account) in state {
-                    let info = crate::storage::AccountInfo {
-                        nonce: account.info.nonce,
-                        balance: account.info.balance,
-                        code_hash: Hash(account.info.code_hash.0),
-                        code: account.info.code.map(|c| c.original_bytes()),
-                    };
-
-                    db.commit_account(address, info)
+            // A reverted or halted tx still pays for its gas and uses up its nonce: revm
+            // leaves the sender and the fee recipient charged and credited (touched), and
+            // undoes everything else
+            let success = status == 1;
+            for (address, account) in state {
+                if !success && !account.is_touched() {
+                    continue;
+                }
+                let code_hash = Hash(account.info.code_hash.0);
+                // Newly deployed code is stored once under its hash
+                if success && let Some(code) = &account.info.code {
+                    db.commit_code(code_hash, &code.original_bytes())
                         .map_err(|e| ExecutionError::State(e.to_string()))?;
+                }
+                let info = crate::storage::AccountInfo {
+                    nonce: account.info.nonce,
+                    balance: account.info.balance,
+                    code_hash,
+                };
 
-                    for (index, slot) in account.storage {
-                        let val = slot.present_value;
-                        db.commit_storage(address, index, val)
-                            .map_err(|e| ExecutionError::State(e.to_string()))?;
-                    }
+                db.commit_account(address, info)
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
+
+                if !success {
+                    continue;
+                }
+                for (index, slot) in account.storage {
+                    let val = slot.present_value;
+                    db.commit_storage(address, index, val)
+                        .map_err(|e| ExecutionError::State(e.to_string()))?;
                 }
             }
         }
 
+        // Out of time: the rest of the payload is left for later blocks
+        if let Some(cut) = cut {
+            tracing::warn!(
+                "Proposal deadline reached: keeping {} of {} transactions",
+                cut,
+                block.payload.len()
+            );
+            block.payload.truncate(cut);
+            block.metadata.operator_txs = block.metadata.operator_txs.min((cut - revealed) as u32);
+            block.transactions_root = calculate_transactions_root(&block.payload);
+        }
+
         // 6. Process Queues (End of Block)
         {
             // Use existing 'db' lock
@@ -523,12 +730,18 @@ impl Executor {
                 for (pk, _) in exited {
                     if let Some(pos) = state.committee.iter().position(|x| *x == pk) {
                         state.committee.remove(pos);
+                        staking::record_exit(&db, staking::validator_address(&pk), current_view)
+                            .map_err(|e| ExecutionError::State(e.to_string()))?;
                         changed = true;
                     }
                 }
 
+                // Parameter changes approved through governance
+                changed |= governance::enact_proposals(&mut state, current_view);
+
                 if changed {
-                    db.save_consensus_state(&state).unwrap();
+                    db.save_consensus_state(&state)
+                        .map_err(|e| ExecutionError::State(e.to_string()))?;
                 }
 
                 // Refresh State Root if consensus state changed?
@@ -539,8 +752,18 @@ impl Executor {
         // No need to re-lock, 'db' is still valid
         block.state_root = db.root();
         block.receipts_root = crate::types::calculate_receipts_root(&receipts);
+        block.logs_bloom = receipts
+            .iter()
+            .fold(Bloom::ZERO, |bloom, receipt| bloom | receipt.logs_bloom);
         block.gas_used = cumulative_gas_used;
-        log::info!(
+        tracing::Span::current().record("gas_used", cumulative_gas_used);
+        // Kept for indexers/exports (and BLOCKHASH); held by the overlay until it is flushed
+        let block_hash = hash_data(&*block);
+        db.save_receipts(&block_hash, &receipts)
+            .map_err(|e| ExecutionError::State(e.to_string()))?;
+        db.save_block_hash(block.height, &block_hash)
+            .map_err(|e| ExecutionError::State(e.to_string()))?;
+        tracing::info!(
             "Block Execution Complete. State Root: {:?}, Receipts Root: {:?}, Gas Used: {}",
             block.state_root,
             block.receipts_root,
@@ -550,6 +773,356 @@ impl Executor {
         Ok(())
     }
 
+    /// Raise the validator's inactivity score and burn `penalty` of its stake; past
+    /// `threshold` it leaves the committee, starting its unbonding period.
+    fn penalize_inactive(
+        db: &mut StateManager,
+        state: &mut ConsensusState,
+        validator: &PublicKey,
+        penalty: U256,
+        threshold: u64,
+        view: View,
+    ) -> Result<(), ExecutionError> {
+        // Increment Score
+        let score = state
+            .inactivity_scores
+            .entry(validator.clone())
+            .or_insert(0);
+        *score += 1;
+        let current_score = *score;
+
+        // Immediate Slash (Incremental)
+        let address = staking::validator_address(validator);
+        let slashed = staking::slash(db, address, penalty)
+            .map_err(|e| ExecutionError::State(e.to_string()))?;
+        if slashed.is_none() {
+            tracing::warn!(
+                "Validator {:?} has no stake entry found for address {:?}",
+                validator,
+                address
+            );
+        }
+
+        // Threshold Check
+        if current_score > threshold {
+            tracing::warn!(
+                "Validator {:?} exceeded inactivity threshold ({}). Removing from committee.",
+                validator,
+                current_score
+            );
+            if let Some(pos) = state.committee.iter().position(|x| x == validator) {
+                state.committee.remove(pos);
+                // Reset score
+                state.inactivity_scores.remove(validator);
+                state.missed_attestations.remove(validator);
+                staking::record_exit(db, address, view)
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Burn `amount` of the offender's stake; removes it from the committee (and pending
+    /// queue) when the remaining stake drops below `min_stake`, starting its unbonding period.
+    fn slash_offender(
+        db: &mut StateManager,
+        offender: &crate::crypto::PublicKey,
+        amount: U256,
+        min_stake: U256,
+        view: View,
+    ) -> Result<(), ExecutionError> {
+        let address = staking::validator_address(offender);
+        let remaining = staking::slash(db, address, amount)
+            .map_err(|e| ExecutionError::State(e.to_string()))?;
+        let Some(remaining) = remaining else {
+            tracing::warn!(
+                "Validator {:?} has no stake entry found for address {:?}",
+                offender,
+                address
+            );
+            return Ok(());
+        };
+        tracing::warn!("Slashed Validator {:?} amount {:?}", address, amount);
+
+        // Remove from Committee if low stake
+        if remaining < min_stake
+            && let Ok(Some(mut state)) = db.get_consensus_state()
+        {
+            let mut removed = false;
+            // Check Pending
+            if let Some(pos) = state
+                .pending_validators
+                .iter()
+                .position(|(pk, _)| pk == offender)
+            {
+                state.pending_validators.remove(pos);
+                removed = true;
+                tracing::warn!("Validator Removed from Pending (Low Stake): {:?}", offender);
+            }
+            // Check Active
+            if let Some(pos) = state.committee.iter().position(|x| x == offender) {
+                state.committee.remove(pos);
+                removed = true;
+                tracing::warn!(
+                    "Validator Removed from Committee (Low Stake): {:?}",
+                    offender
+                );
+            }
+            if removed {
+                staking::record_exit(db, address, view)
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
+            }
+            db.save_consensus_state(&state)
+                .map_err(|e| ExecutionError::State(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Consume the ciphertext revealed as the `index`th transaction of `block`, and check
+    /// that this transaction can execute on the current state: what the pool would have
+    /// checked at admission (and revm checks before executing), against what remains of
+    /// the block. The reason it cannot, if so.
+    fn reveal(
+        &self,
+        db: &mut StateManager,
+        block: &Block,
+        index: usize,
+        window: View,
+        block_gas_limit: u64,
+        cumulative_gas_used: u64,
+    ) -> Result<Result<(), String>, ExecutionError> {
+        let envelope = &block.metadata.decryption_keys[index].envelope;
+        let tx = &block.payload[index];
+        let state_err = |e: crate::state::StateError| ExecutionError::State(e.to_string());
+        if let Some(reason) =
+            encrypted_mempool::check_pending(db, envelope, block.view, window).map_err(state_err)?
+        {
+            return Ok(Err(reason.into()));
+        }
+        encrypted_mempool::clear(db, envelope.id()).map_err(state_err)?;
+
+        if tx.chain_id != self.chain_id {
+            return Ok(Err(format!("invalid chain id {}", tx.chain_id)));
+        }
+        if cumulative_gas_used.saturating_add(tx.gas_limit) > block_gas_limit {
+            return Ok(Err("exceeds the block gas limit".into()));
+        }
+        if let Err(e) = validate_tx_stateless(tx, Some(block.base_fee_per_gas)) {
+            return Ok(Err(e.to_string()));
+        }
+        let account = db
+            .basic(tx.sender())
+            .map_err(state_err)?
+            .unwrap_or_default();
+        if account.nonce != tx.nonce {
+            return Ok(Err(format!(
+                "nonce {} (expected {})",
+                tx.nonce, account.nonce
+            )));
+        }
+        let cost = tx
+            .max_fee_per_gas
+            .saturating_mul(U256::from(tx.gas_limit))
+            .saturating_add(tx.value);
+        if account.balance < cost {
+            return Ok(Err("insufficient balance".into()));
+        }
+        Ok(Ok(()))
+    }
+
+    /// Credit the priority fee of a natively executed tx to the block's fee recipient
+    /// (revm does the same for EVM txs through the coinbase).
+    fn pay_fee_recipient(
+        db: &mut StateManager,
+        block: &Block,
+        tx: &crate::types::Transaction,
+        gas_used: u64,
+    ) -> Result<(), ExecutionError> {
+        let tip = tx.effective_tip(block.base_fee_per_gas) * U256::from(gas_used);
+        if tip == U256::ZERO {
+            return Ok(());
+        }
+
+        let recipient = block.metadata.fee_recipient;
+        let acc = db
+            .basic(recipient)
+            .map_err(|e| ExecutionError::State(e.to_string()))?
+            .unwrap_or_default();
+        db.commit_account(
+            recipient,
+            crate::storage::AccountInfo {
+                nonce: acc.nonce,
+                balance: acc.balance + tip,
+                code_hash: Hash(acc.code_hash.0),
+            },
+        )
+        .map_err(|e| ExecutionError::State(e.to_string()))
+    }
+
+    /// Execute a WASM deployment or call and commit its effects.
+    /// The sender always pays for the gas it consumed (intrinsic gas included) and has its nonce
+    /// bumped; value transfer, contract storage and code are only committed on success. A
+    /// transaction with the wrong nonce fails in its receipt without touching state.
+    fn execute_wasm_tx(
+        &self,
+        db: &mut StateManager,
+        block: &Block,
+        tx: &crate::types::Transaction,
+        code: Vec<u8>,
+        cumulative_gas_used: u64,
+    ) -> Result<crate::types::Receipt, ExecutionError> {
+        let sender = tx.sender();
+        let (address, entry) = match tx.to {
+            Some(to) => (to, "call"),
+            None => (sender.create(tx.nonce), "deploy"),
+        };
+
+        let sender_acc = db
+            .basic(sender)
+            .map_err(|e| ExecutionError::State(e.to_string()))?
+            .unwrap_or_default();
+        // A nonce that does not match fails the transaction in its receipt, like the EVM path
+        if sender_acc.nonce != tx.nonce {
+            let reason = format!("nonce {} (expected {})", tx.nonce, sender_acc.nonce);
+            return Ok(crate::types::Receipt {
+                status: 0,
+                cumulative_gas_used,
+                logs: vec![],
+                logs_bloom: Bloom::default(),
+                gas_used: 0,
+                revert_output: encode_revert_reason(&reason).into(),
+                contract_address: None,
+            });
+        }
+
+        // Effective gas price (EIP-1559)
+        let gas_price = std::cmp::min(
+            tx.max_fee_per_gas,
+            block.base_fee_per_gas + tx.max_priority_fee_per_gas,
+        );
+        let max_fee = gas_price * U256::from(tx.gas_limit);
+        if sender_acc.balance < tx.value + max_fee {
+            return Err(ExecutionError::Transaction("Insufficient Balance".into()));
+        }
+
+        // The intrinsic gas is paid up front; the module gets what is left of the limit
+        let intrinsic = tx.intrinsic_gas();
+        let call = wasm::WasmCall {
+            caller: sender,
+            address,
+            value: tx.value,
+            input: if entry == "deploy" {
+                crate::types::Bytes::default()
+            } else {
+                tx.data.clone()
+            },
+            gas_limit: tx.gas_limit.saturating_sub(intrinsic),
+        };
+        let mut outcome = wasm::execute(db.view(), &code, entry, call)?;
+        outcome.gas_used += intrinsic;
+
+        // Code deposit for deployments
+        if outcome.success && entry == "deploy" {
+            let deposit = wasm::GAS_CODE_DEPOSIT_PER_BYTE * code.len() as u64;
+            if outcome.gas_used + deposit > tx.gas_limit {
+                outcome.success = false;
+                outcome.gas_used = tx.gas_limit;
+            } else {
+                outcome.gas_used += deposit;
+            }
+        }
+
+        let fee = gas_price * U256::from(outcome.gas_used);
+        let mut sender_info = crate::storage::AccountInfo {
+            nonce: sender_acc.nonce + 1,
+            balance: sender_acc.balance - fee,
+            code_hash: Hash(sender_acc.code_hash.0),
+        };
+
+        if outcome.success {
+            sender_info.balance -= tx.value;
+            db.commit_account(sender, sender_info)
+                .map_err(|e| ExecutionError::State(e.to_string()))?;
+
+            let contract = db
+                .basic(address)
+                .map_err(|e| ExecutionError::State(e.to_string()))?
+                .unwrap_or_default();
+            let code_hash = if entry == "deploy" {
+                let code_hash = Hash(crate::types::keccak256(&code).0);
+                db.commit_code(code_hash, &crate::types::Bytes::from(code))
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
+                code_hash
+            } else {
+                Hash(contract.code_hash.0)
+            };
+            db.commit_account(
+                address,
+                crate::storage::AccountInfo {
+                    nonce: contract.nonce,
+                    balance: contract.balance + tx.value,
+                    code_hash,
+                },
+            )
+            .map_err(|e| ExecutionError::State(e.to_string()))?;
+
+            for (index, value) in outcome.storage_writes {
+                db.commit_storage(address, index, value)
+                    .map_err(|e| ExecutionError::State(e.to_string()))?;
+            }
+        } else {
+            db.commit_account(sender, sender_info)
+                .map_err(|e| ExecutionError::State(e.to_string()))?;
+        }
+
+        Self::pay_fee_recipient(db, block, tx, outcome.gas_used)?;
+
+        let logs = if outcome.success {
+            outcome.logs
+        } else {
+            vec![]
+        };
+        Ok(crate::types::Receipt {
+            status: outcome.success as u8,
+            cumulative_gas_used: cumulative_gas_used + outcome.gas_used,
+            logs_bloom: logs_bloom(&logs),
+            logs,
+            gas_used: outcome.gas_used,
+            revert_output: if outcome.success {
+                Default::default()
+            } else {
+                outcome.output.into()
+            },
+            contract_address: (outcome.success && entry == "deploy").then_some(address),
+        })
+    }
+
+    /// Chain-level EVM settings: chain id and the hardfork's rules.
+    fn configure_env(&self, env: &mut Env) {
+        env.cfg.chain_id = self.chain_id;
+        env.cfg.spec_id = match self.hardfork {
+            Hardfork::Shanghai => SpecId::SHANGHAI,
+            Hardfork::Cancun => SpecId::CANCUN,
+        };
+        if self.hardfork >= Hardfork::Cancun {
+            // No blob transactions: the blob base fee stays at its minimum
+            env.block.set_blob_excess_gas_and_price(0);
+        }
+    }
+
+    /// Block context of EVM transactions (`NUMBER`, `TIMESTAMP`, `COINBASE`, `PREVRANDAO`, ...).
+    /// The randomness is the hash of the justifying QC's aggregate signature, which no
+    /// single validator controls.
+    fn configure_block_env(&self, block: &Block, block_gas_limit: u64, env: &mut Env) {
+        env.block.number = U256::from(block.height);
+        env.block.timestamp = U256::from(block.timestamp);
+        env.block.coinbase = block.metadata.fee_recipient;
+        env.block.basefee = block.base_fee_per_gas;
+        env.block.gas_limit = U256::from(block_gas_limit);
+        env.block.difficulty = U256::ZERO;
+        env.block.prevrandao = Some(B256::from(hash_data(&block.justify.signature).0));
+    }
+
     /// Execute a transaction ephemerally (no commit, for RPC 'call' and 'estimate_gas')
     pub fn execute_ephemeral(
         &self,
@@ -558,10 +1131,37 @@ impl Executor {
         value: U256,
         data: crate::types::Bytes,
         gas_limit: u64,
-        _access_list: Vec<crate::types::AccessListItem>, // Future proofing
+        access_list: Vec<crate::types::AccessListItem>,
     ) -> Result<(u64, Vec<u8>), ExecutionError> {
         let mut db = self.state.lock().unwrap();
 
+        // WASM contracts are simulated by the WASM runtime
+        if let Some(addr) = to {
+            let code = db
+                .basic(addr)
+                .map_err(|e| ExecutionError::State(e.to_string()))?
+                .and_then(|acc| acc.code)
+                .map(|c| c.original_bytes().to_vec())
+                .filter(|c| wasm::is_wasm(c));
+            if let Some(code) = code {
+                let call = wasm::WasmCall {
+                    caller,
+                    address: addr,
+                    value,
+                    input: data,
+                    gas_limit,
+                };
+                let outcome = wasm::execute(db.view(), &code, "call", call)?;
+                if !outcome.success {
+                    return Err(ExecutionError::Revert {
+                        gas_used: outcome.gas_used,
+                        output: outcome.output,
+                    });
+                }
+                return Ok((outcome.gas_used, outcome.output));
+            }
+        }
+
         // Setup EVM
         let mut evm = EVM::new();
         evm.database(&mut *db);
@@ -571,6 +1171,7 @@ impl Executor {
         // For accurate simulation, we should use the 'pending' block context or 'latest'.
         //db.get_consensus_state() gives us head.
         // For now, use defaults for BlockEnv.
+        self.configure_env(&mut evm.env);
 
         let tx_env = &mut evm.env.tx;
         tx_env.caller = caller;
@@ -585,6 +1186,7 @@ impl Executor {
         tx_env.gas_price = U256::ZERO; // Simulation usually 0 or free
         tx_env.gas_priority_fee = None;
         tx_env.nonce = None; // Ignore nonce for simulation
+        tx_env.access_list = crate::types::revm_access_list(&access_list);
 
         // Execute
         let result_and_state = evm
@@ -603,10 +1205,10 @@ impl Executor {
                 };
                 Ok((gas_used, data))
             }
-            ExecutionResult::Revert { gas_used, output } => {
-                // For 'call', we often want the revert data too.
-                Ok((gas_used, output.to_vec()))
-            }
+            ExecutionResult::Revert { gas_used, output } => Err(ExecutionError::Revert {
+                gas_used,
+                output: output.to_vec(),
+            }),
             ExecutionResult::Halt { reason, .. } => {
                 Err(ExecutionError::Evm(format!("Halted: {:?}", reason)))
             }
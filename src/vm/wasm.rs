@@ -0,0 +1,376 @@
+use crate::state::StateManager;
+use crate::types::{Address, Bytes, Log, U256};
+use crate::vm::ExecutionError;
+use revm::Database;
+use wasmi::core::Trap;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store};
+
+/// WASM binaries start with `\0asm`. Creation payloads and account code carrying this
+/// prefix are routed to the WASM runtime instead of revm.
+pub const WASM_MAGIC: [u8; 4] = [0x00, 0x61, 0x73, 0x6d];
+
+// Host function gas schedule, drawn from the same fuel as the 1 gas per instruction metering
+pub const GAS_STORAGE_READ: u64 = 200;
+pub const GAS_STORAGE_WRITE: u64 = 5_000;
+pub const GAS_BALANCE: u64 = 100;
+pub const GAS_LOG: u64 = 375;
+pub const GAS_LOG_TOPIC: u64 = 375;
+pub const GAS_PER_BYTE: u64 = 8;
+pub const GAS_CODE_DEPOSIT_PER_BYTE: u64 = 200;
+
+/// Check whether a code blob is a WASM module.
+pub fn is_wasm(code: &[u8]) -> bool {
+    code.len() >= WASM_MAGIC.len() && code[..WASM_MAGIC.len()] == WASM_MAGIC
+}
+
+/// Context of a single WASM contract invocation.
+#[derive(Clone, Debug)]
+pub struct WasmCall {
+    pub caller: Address,
+    pub address: Address,
+    pub value: U256,
+    pub input: Bytes,
+    pub gas_limit: u64,
+}
+
+/// Result of a WASM invocation. State changes are buffered and only applied by the caller
+/// (Executor) when `success` is set.
+#[derive(Clone, Debug, Default)]
+pub struct WasmOutcome {
+    pub success: bool,
+    pub gas_used: u64,
+    pub output: Vec<u8>,
+    pub logs: Vec<Log>,
+    pub storage_writes: Vec<(U256, U256)>,
+}
+
+/// Host state exposed to the guest through the `env` import module. Reads go through the
+/// same `Database` interface as the EVM.
+struct WasmHost {
+    state: StateManager,
+    call: WasmCall,
+    output: Vec<u8>,
+    logs: Vec<Log>,
+    storage_writes: Vec<(U256, U256)>,
+}
+
+impl WasmHost {
+    fn read_storage(&mut self, key: U256) -> Result<U256, Trap> {
+        // Reads observe the writes of the current invocation first
+        if let Some((_, v)) = self.storage_writes.iter().rev().find(|(k, _)| *k == key) {
+            return Ok(*v);
+        }
+        self.state
+            .storage(self.call.address, key)
+            .map_err(|e| Trap::new(format!("storage error: {}", e)))
+    }
+}
+
+// Host gas comes out of the fuel budget; running out of it uses up the whole budget
+fn charge(caller: &mut Caller<'_, WasmHost>, amount: u64) -> Result<(), Trap> {
+    if caller.consume_fuel(amount).is_err() {
+        let consumed = caller.fuel_consumed().unwrap_or(0);
+        let left = caller.data().call.gas_limit.saturating_sub(consumed);
+        let _ = caller.consume_fuel(left);
+        return Err(Trap::new("out of gas"));
+    }
+    Ok(())
+}
+
+fn memory(caller: &Caller<'_, WasmHost>) -> Result<Memory, Trap> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("missing memory export"))
+}
+
+// Bounds are checked against the guest memory before anything is allocated
+fn read_bytes(caller: &Caller<'_, WasmHost>, ptr: i32, len: usize) -> Result<Vec<u8>, Trap> {
+    let data = memory(caller)?.data(caller);
+    let start = ptr as u32 as usize;
+    let end = start
+        .checked_add(len)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| Trap::new("memory read out of bounds"))?;
+    Ok(data[start..end].to_vec())
+}
+
+fn write_bytes(caller: &mut Caller<'_, WasmHost>, ptr: i32, data: &[u8]) -> Result<(), Trap> {
+    memory(caller)?
+        .write(caller, ptr as u32 as usize, data)
+        .map_err(|e| Trap::new(format!("memory write: {}", e)))
+}
+
+fn read_word(caller: &Caller<'_, WasmHost>, ptr: i32) -> Result<U256, Trap> {
+    let bytes = read_bytes(caller, ptr, 32)?;
+    Ok(U256::from_be_slice(&bytes))
+}
+
+/// Register the host ABI:
+///
+/// - `storage_read(key_ptr, out_ptr)` / `storage_write(key_ptr, value_ptr)`: 32-byte words
+/// - `balance(addr_ptr, out_ptr)`: 20-byte address in, 32-byte big-endian balance out
+/// - `caller(out_ptr)`, `address(out_ptr)`: 20 bytes
+/// - `call_value(out_ptr)`: 32 bytes
+/// - `input_size() -> i32`, `input_read(out_ptr)`
+/// - `return_data(ptr, len)`
+/// - `emit_log(topics_ptr, topic_count, data_ptr, data_len)`
+/// - `revert(ptr, len)`: aborts execution, discarding state changes
+fn link_host(linker: &mut Linker<WasmHost>) -> Result<(), ExecutionError> {
+    let map_err = |e: wasmi::errors::LinkerError| ExecutionError::Wasm(e.to_string());
+
+    linker
+        .func_wrap(
+            "env",
+            "storage_read",
+            |mut caller: Caller<'_, WasmHost>, key_ptr: i32, out_ptr: i32| -> Result<(), Trap> {
+                charge(&mut caller, GAS_STORAGE_READ)?;
+                let key = read_word(&caller, key_ptr)?;
+                let value = caller.data_mut().read_storage(key)?;
+                write_bytes(&mut caller, out_ptr, &value.to_be_bytes::<32>())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "storage_write",
+            |mut caller: Caller<'_, WasmHost>, key_ptr: i32, value_ptr: i32| -> Result<(), Trap> {
+                charge(&mut caller, GAS_STORAGE_WRITE)?;
+                let key = read_word(&caller, key_ptr)?;
+                let value = read_word(&caller, value_ptr)?;
+                caller.data_mut().storage_writes.push((key, value));
+                Ok(())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "balance",
+            |mut caller: Caller<'_, WasmHost>, addr_ptr: i32, out_ptr: i32| -> Result<(), Trap> {
+                charge(&mut caller, GAS_BALANCE)?;
+                let address = Address::from_slice(&read_bytes(&caller, addr_ptr, 20)?);
+                let balance = caller
+                    .data_mut()
+                    .state
+                    .basic(address)
+                    .map_err(|e| Trap::new(format!("storage error: {}", e)))?
+                    .map(|a| a.balance)
+                    .unwrap_or_default();
+                write_bytes(&mut caller, out_ptr, &balance.to_be_bytes::<32>())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "caller",
+            |mut caller: Caller<'_, WasmHost>, out_ptr: i32| -> Result<(), Trap> {
+                let address = caller.data().call.caller;
+                write_bytes(&mut caller, out_ptr, address.as_slice())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "address",
+            |mut caller: Caller<'_, WasmHost>, out_ptr: i32| -> Result<(), Trap> {
+                let address = caller.data().call.address;
+                write_bytes(&mut caller, out_ptr, address.as_slice())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "call_value",
+            |mut caller: Caller<'_, WasmHost>, out_ptr: i32| -> Result<(), Trap> {
+                let value = caller.data().call.value;
+                write_bytes(&mut caller, out_ptr, &value.to_be_bytes::<32>())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap("env", "input_size", |caller: Caller<'_, WasmHost>| -> i32 {
+            caller.data().call.input.len() as i32
+        })
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "input_read",
+            |mut caller: Caller<'_, WasmHost>, out_ptr: i32| -> Result<(), Trap> {
+                let input = caller.data().call.input.clone();
+                charge(&mut caller, GAS_PER_BYTE * input.len() as u64)?;
+                write_bytes(&mut caller, out_ptr, &input)
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "return_data",
+            |mut caller: Caller<'_, WasmHost>, ptr: i32, len: i32| -> Result<(), Trap> {
+                charge(&mut caller, GAS_PER_BYTE * len as u32 as u64)?;
+                let data = read_bytes(&caller, ptr, len as u32 as usize)?;
+                caller.data_mut().output = data;
+                Ok(())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "emit_log",
+            |mut caller: Caller<'_, WasmHost>,
+             topics_ptr: i32,
+             topic_count: i32,
+             data_ptr: i32,
+             data_len: i32|
+             -> Result<(), Trap> {
+                let topic_count = topic_count as u32 as usize;
+                if topic_count > 4 {
+                    return Err(Trap::new("too many log topics"));
+                }
+                let data_len = data_len as u32 as usize;
+                charge(
+                    &mut caller,
+                    GAS_LOG + GAS_LOG_TOPIC * topic_count as u64 + GAS_PER_BYTE * data_len as u64,
+                )?;
+
+                let raw_topics = read_bytes(&caller, topics_ptr, topic_count * 32)?;
+                let topics = raw_topics
+                    .chunks(32)
+                    .map(|c| {
+                        let mut t = [0u8; 32];
+                        t.copy_from_slice(c);
+                        crate::crypto::Hash(t)
+                    })
+                    .collect();
+                let data = read_bytes(&caller, data_ptr, data_len)?;
+
+                let address = caller.data().call.address;
+                caller.data_mut().logs.push(Log {
+                    address,
+                    topics,
+                    data: Bytes::from(data),
+                });
+                Ok(())
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            "env",
+            "revert",
+            |mut caller: Caller<'_, WasmHost>, ptr: i32, len: i32| -> Result<(), Trap> {
+                charge(&mut caller, GAS_PER_BYTE * len as u32 as u64)?;
+                let data = read_bytes(&caller, ptr, len as u32 as usize)?;
+                caller.data_mut().output = data;
+                Err(Trap::new("reverted"))
+            },
+        )
+        .map_err(map_err)?;
+
+    Ok(())
+}
+
+/// Run `entry` (`deploy` or `call`) of a WASM module.
+///
+/// Fuel metering charges one unit of gas per executed instruction; host functions take their
+/// own cost from the same fuel, so `call.gas_limit` bounds both. Traps (including running out of gas) produce an unsuccessful outcome that still
+/// consumes gas, mirroring an EVM revert. Modules that do not compile or instantiate (e.g.
+/// unknown imports) fail the same way and consume the whole gas limit, like invalid EVM
+/// code. A missing `deploy` export is treated as a no-op constructor.
+///
+/// `state` is read through (a view of the executing state, see `StateManager::view`); the
+/// writes are returned in the outcome.
+pub fn execute(
+    state: StateManager,
+    code: &[u8],
+    entry: &str,
+    call: WasmCall,
+) -> Result<WasmOutcome, ExecutionError> {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+
+    let gas_limit = call.gas_limit;
+    let module = match Module::new(&engine, code) {
+        Ok(module) => module,
+        Err(e) => return Ok(invalid_module(gas_limit, e)),
+    };
+
+    let host = WasmHost {
+        state,
+        call,
+        output: vec![],
+        logs: vec![],
+        storage_writes: vec![],
+    };
+    let mut store = Store::new(&engine, host);
+    store
+        .add_fuel(gas_limit)
+        .map_err(|e| ExecutionError::Wasm(e.to_string()))?;
+
+    let mut linker = <Linker<WasmHost>>::new(&engine);
+    link_host(&mut linker)?;
+
+    let instance = match linker
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+    {
+        Ok(instance) => instance,
+        Err(e) => return Ok(invalid_module(gas_limit, e)),
+    };
+
+    let result = match instance.get_typed_func::<(), ()>(&store, entry) {
+        Ok(func) => func.call(&mut store, ()).map_err(|e| e.to_string()),
+        Err(_) if entry == "deploy" => Ok(()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    let gas_used = store.fuel_consumed().unwrap_or(0).min(gas_limit);
+    let host = store.into_data();
+
+    match result {
+        Ok(()) => Ok(WasmOutcome {
+            success: true,
+            gas_used,
+            output: host.output,
+            logs: host.logs,
+            storage_writes: host.storage_writes,
+        }),
+        Err(reason) => {
+            tracing::warn!("WASM execution trapped: {}", reason);
+            Ok(WasmOutcome {
+                success: false,
+                gas_used,
+                output: host.output,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+// Outcome of a module that cannot run: failed, with all its gas consumed
+fn invalid_module(gas_limit: u64, error: impl std::fmt::Display) -> WasmOutcome {
+    tracing::warn!("WASM module rejected: {}", error);
+    WasmOutcome {
+        success: false,
+        gas_used: gas_limit,
+        ..Default::default()
+    }
+}
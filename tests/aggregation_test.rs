@@ -0,0 +1,144 @@
+use ockham::consensus::{ConsensusAction, ConsensusError, SimplexState};
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, sign};
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::{AggregateVote, DEFAULT_CHAIN_ID, Vote, VoteType, vote_message};
+use std::sync::{Arc, Mutex};
+
+fn make_node(keys: &[(PublicKey, PrivateKey)]) -> SimplexState {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+}
+
+fn make_vote(
+    key: &(PublicKey, PrivateKey),
+    view: u64,
+    block_hash: Hash,
+    vote_type: VoteType,
+) -> Vote {
+    Vote {
+        view,
+        block_hash,
+        vote_type,
+        author: key.0.clone(),
+        signature: sign(
+            &key.1,
+            &vote_message(vote_type, view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    }
+}
+
+// Aggregate of the votes of `signers` (indices into the node's committee)
+fn make_aggregate(
+    node: &SimplexState,
+    keys: &[(PublicKey, PrivateKey)],
+    signers: &[usize],
+    view: u64,
+    vote_type: VoteType,
+) -> AggregateVote {
+    let mut aggregate: Option<AggregateVote> = None;
+    for &index in signers {
+        let key = keys.iter().find(|k| k.0 == node.committee[index]).unwrap();
+        let vote = make_vote(key, view, Hash::default(), vote_type);
+        let single = AggregateVote::from_vote(&vote, node.epoch, index);
+        match &mut aggregate {
+            Some(aggregate) => assert!(aggregate.merge(&single)),
+            None => aggregate = Some(single),
+        }
+    }
+    aggregate.unwrap()
+}
+
+fn relayed(actions: &[ConsensusAction]) -> Vec<usize> {
+    actions
+        .iter()
+        .filter_map(|a| match a {
+            ConsensusAction::BroadcastAggregate(aggregate) => Some(aggregate.signers.len()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_votes_relayed_as_aggregates() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let mut node = make_node(&keys);
+
+    // A single vote is not worth relaying; each doubling (and the quorum) is
+    let first = node
+        .on_vote(make_vote(&keys[1], 2, Hash::default(), VoteType::Notarize))
+        .unwrap();
+    assert!(relayed(&first).is_empty());
+    let second = node
+        .on_vote(make_vote(&keys[2], 2, Hash::default(), VoteType::Notarize))
+        .unwrap();
+    assert_eq!(relayed(&second), vec![2]);
+    let third = node
+        .on_vote(make_vote(&keys[3], 2, Hash::default(), VoteType::Notarize))
+        .unwrap();
+    assert_eq!(relayed(&third), vec![3]);
+    assert!(node.storage.get_qc(2).unwrap().is_some());
+
+    // Nothing new to tell: the same aggregate coming back is not relayed again
+    let aggregate = node.aggregates[&(2, Hash::default(), VoteType::Notarize)]
+        .aggregate
+        .clone();
+    assert!(relayed(&node.on_aggregate_vote(aggregate).unwrap()).is_empty());
+}
+
+#[test]
+fn test_certificates_from_aggregates() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let mut node = make_node(&keys);
+    let me = node.committee.iter().position(|m| *m == keys[0].0).unwrap();
+    let others: Vec<usize> = (0..4).filter(|i| *i != me).collect();
+
+    // Overlapping aggregates cannot be combined (the larger one is kept)...
+    let a = make_aggregate(&node, &keys, &others[..2], 3, VoteType::Notarize);
+    let b = make_aggregate(&node, &keys, &others[1..], 3, VoteType::Notarize);
+    node.on_aggregate_vote(a).unwrap();
+    node.on_aggregate_vote(b).unwrap();
+    assert!(node.storage.get_qc(3).unwrap().is_none());
+
+    // ...disjoint ones add up to a quorum
+    let c = make_aggregate(&node, &keys, &others[2..], 3, VoteType::Notarize);
+    node.on_aggregate_vote(c).unwrap();
+    let qc = node.storage.get_qc(3).unwrap().expect("QC from aggregates");
+    assert!(qc.signers.len() >= 3);
+    assert!(qc.verify_signature(&node.committee, DEFAULT_CHAIN_ID));
+
+    // A quorum aggregate of Finalize votes finalizes the view
+    let finalize = make_aggregate(&node, &keys, &others, 3, VoteType::Finalize);
+    node.on_aggregate_vote(finalize).unwrap();
+    assert_eq!(node.finalized_height, 3);
+    assert!(node.aggregates.keys().all(|(view, _, _)| *view >= 3));
+
+    // Forged aggregates are rejected
+    let mut forged = make_aggregate(&node, &keys, &others[..1], 5, VoteType::Notarize);
+    forged.signers.insert(me);
+    assert!(matches!(
+        node.on_aggregate_vote(forged),
+        Err(ConsensusError::InvalidSignature)
+    ));
+}
+
+#[test]
+fn test_simulation_relays_aggregates() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 4));
+    assert!(net.stats().aggregates > 0);
+}
@@ -0,0 +1,109 @@
+use ockham::archive::{ArchiveError, ChainArchive};
+use ockham::consensus::SimplexState;
+use ockham::crypto::{generate_keypair_from_id, hash_data};
+use ockham::state::StateManager;
+use ockham::storage::{MemStorage, Storage};
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::vm::Executor;
+use std::sync::{Arc, Mutex};
+
+/// A fresh data directory with the genesis state of the simulated committee.
+fn fresh_node() -> (Arc<MemStorage>, Executor) {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let storage = Arc::new(MemStorage::new());
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let executor = Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        keys.iter().map(|k| k.0.clone()).collect(),
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        executor.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    (storage, executor)
+}
+
+#[test]
+fn test_export_import_roundtrip() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 4));
+    let archive = ChainArchive::export(net.storage(0).as_ref(), None).unwrap();
+    assert!(archive.blocks.len() >= 2);
+    let last = archive.blocks.last().unwrap().clone();
+    assert!(last.finalization.is_some());
+
+    let path = std::env::temp_dir().join(format!("ockham_archive_{}", std::process::id()));
+    archive.save(&path).unwrap();
+    let loaded = ChainArchive::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    // Every block is re-validated and committed
+    let (storage, executor) = fresh_node();
+    let report = loaded.import(storage.as_ref(), &executor).unwrap();
+    assert_eq!(report.blocks, archive.blocks.len());
+    assert_eq!(report.head.block_hash, hash_data(&last.block));
+    assert_eq!(
+        report.head.state_root,
+        executor.state.lock().unwrap().root()
+    );
+    assert_eq!(storage.get_chain_head().unwrap(), Some(report.head.clone()));
+    let state = storage.get_consensus_state().unwrap().unwrap();
+    assert_eq!(state.finalized_height, last.block.view);
+    for archived in &archive.blocks {
+        assert!(
+            storage
+                .get_block(&hash_data(&archived.block))
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    // Only into a fresh data directory
+    assert!(matches!(
+        loaded.import(storage.as_ref(), &executor),
+        Err(ArchiveError::NotFresh(_))
+    ));
+}
+
+#[test]
+fn test_import_rejects_invalid_archives() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 4));
+    let archive = ChainArchive::export(net.storage(0).as_ref(), None).unwrap();
+    let first_view = archive.blocks[0].block.view;
+
+    // A block re-signed by its proposer with another state root is not the notarized one
+    let mut forged = archive.clone();
+    let block = &mut forged.blocks[0].block;
+    block.state_root = hash_data(&block.state_root);
+    let author = (0..4)
+        .map(generate_keypair_from_id)
+        .find(|k| k.0 == block.author)
+        .unwrap();
+    block.sign(&author.1);
+    let (storage, executor) = fresh_node();
+    assert!(matches!(
+        forged.import(storage.as_ref(), &executor),
+        Err(ArchiveError::Certificate(view, _)) if view == first_view
+    ));
+
+    // Gaps in the chain
+    let mut gapped = archive.clone();
+    gapped.blocks.remove(0);
+    let (storage, executor) = fresh_node();
+    assert!(matches!(
+        gapped.import(storage.as_ref(), &executor),
+        Err(ArchiveError::BrokenChain(_))
+    ));
+
+    // Blocks past the last finalization certificate are not provably final
+    let mut unfinal = archive;
+    unfinal.blocks.last_mut().unwrap().finalization = None;
+    let (storage, executor) = fresh_node();
+    assert!(matches!(
+        unfinal.import(storage.as_ref(), &executor),
+        Err(ArchiveError::NotFinal)
+    ));
+}
@@ -0,0 +1,227 @@
+use ockham::consensus::{ConsensusAction, SimplexState};
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, hash_data, sign};
+use ockham::storage::{MemStorage, RedbStorage, Storage};
+use ockham::tx_pool::TxPool;
+use ockham::types::{
+    Address, Block, BlockBody, Bytes, CompactBlock, QuorumCertificate, Transaction, U256,
+};
+use std::sync::{Arc, Mutex};
+
+fn make_tx(id: u64) -> Transaction {
+    let (pk, sk) = generate_keypair_from_id(id);
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::from(1u64),
+        max_fee_per_gas: U256::from(100_000_000u64),
+        gas_limit: 21000,
+        to: Some(Address::ZERO),
+        value: U256::ZERO,
+        data: Bytes::new(),
+        access_list: vec![],
+        public_key: pk,
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    tx
+}
+
+fn make_block(key: &(PublicKey, PrivateKey), view: u64, payload: Vec<Transaction>) -> Block {
+    let mut block = Block::new(
+        key.0.clone(),
+        view,
+        Hash::default(),
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        payload,
+        U256::ZERO,
+        0,
+        vec![],
+        Hash::default(),
+    );
+    block.sign(&key.1);
+    block
+}
+
+#[test]
+fn test_header_body_split() {
+    let key = generate_keypair_from_id(0);
+    let block = make_block(&key, 1, vec![make_tx(10), make_tx(11)]);
+    let (header, body) = block.clone().split();
+    assert_eq!(header.tx_count, 2);
+    assert_eq!(header.body_hash, body.hash());
+    // The header signature alone authenticates the block
+    assert!(header.verify_signature());
+
+    let joined = Block::from_parts(header.clone(), body.clone()).unwrap();
+    assert_eq!(hash_data(&joined), hash_data(&block));
+
+    // A different body is rejected, and breaks the block signature
+    let mut other = body;
+    other.payload.pop();
+    assert!(Block::from_parts(header, other.clone()).is_none());
+    let mut tampered = block;
+    tampered.payload = other.payload;
+    assert!(!tampered.verify_signature());
+}
+
+#[test]
+fn test_transaction_inclusion_proofs() {
+    let key = generate_keypair_from_id(0);
+    let payload: Vec<Transaction> = (10..13).map(make_tx).collect();
+    let block = make_block(&key, 1, payload.clone());
+    assert_eq!(
+        block.transactions_root,
+        ockham::types::calculate_transactions_root(&payload)
+    );
+    assert_eq!(block.header().transactions_root, block.transactions_root);
+
+    for (index, tx) in payload.iter().enumerate() {
+        let proof = block.transaction_proof(index).unwrap();
+        assert!(proof.verify(&block.transactions_root, &tx.encode()));
+        // Not at another position, nor under another root
+        assert!(!proof.verify(&block.transactions_root, &payload[(index + 1) % 3].encode()));
+        assert!(!proof.verify(&Hash([1u8; 32]), &tx.encode()));
+    }
+    assert!(block.transaction_proof(3).is_none());
+
+    // A proof cannot claim a padding leaf of the tree
+    let mut padding = block.transaction_proof(2).unwrap();
+    padding.index = 3;
+    assert!(!padding.verify(&block.transactions_root, &[0u8; 32]));
+
+    // The root commits to the order of the payload
+    let reordered: Vec<Transaction> = payload.iter().rev().cloned().collect();
+    assert_ne!(
+        ockham::types::calculate_transactions_root(&reordered),
+        block.transactions_root
+    );
+    assert_eq!(
+        ockham::types::calculate_transactions_root(&[]),
+        Hash::default()
+    );
+}
+
+fn check_split_storage(storage: &dyn Storage) {
+    let key = generate_keypair_from_id(0);
+    let block = make_block(&key, 1, vec![make_tx(10)]);
+    let hash = hash_data(&block);
+    storage.save_block(&block).unwrap();
+
+    let header = storage.get_header(&hash).unwrap().unwrap();
+    assert_eq!(hash_data(&header), hash_data(&block.header()));
+    let stored = storage.get_block(&hash).unwrap().unwrap();
+    assert_eq!(hash_data(&stored), hash);
+    assert!(storage.get_header(&Hash([9u8; 32])).unwrap().is_none());
+}
+
+#[test]
+fn test_split_storage_mem() {
+    check_split_storage(&MemStorage::new());
+}
+
+#[test]
+fn test_split_storage_redb() {
+    let path = std::env::temp_dir().join(format!("ockham_block_split_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    check_split_storage(&RedbStorage::new(&path).unwrap());
+    let _ = std::fs::remove_file(&path);
+}
+
+fn make_node(keys: &[(PublicKey, PrivateKey)]) -> SimplexState {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+}
+
+#[test]
+fn test_compact_block_relay() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let mut node = make_node(&keys);
+    let view = node.current_view.max(1);
+    let known = make_tx(10);
+    let missing = make_tx(11);
+    node.tx_pool.add_transaction(known.clone()).unwrap();
+
+    // Every transaction is in the pool: rebuilt and processed as a proposal
+    let block = make_block(&keys[1], view, vec![known.clone()]);
+    let compact = CompactBlock::new(&block);
+    assert_eq!(compact.tx_ids, vec![hash_data(&known)]);
+    let _ = node.on_compact_block(compact, "peer".to_string());
+    assert!(node.seen_blocks.contains(&hash_data(&block)));
+    assert!(node.pending_blocks.is_empty());
+
+    // Only the missing transaction is requested
+    let block = make_block(&keys[2], view, vec![known.clone(), missing.clone()]);
+    let block_hash = hash_data(&block);
+    let actions = node
+        .on_compact_block(CompactBlock::new(&block), "peer".to_string())
+        .unwrap();
+    assert!(matches!(
+        &actions[..],
+        [ConsensusAction::RequestTransactions(h, ids)]
+            if *h == block_hash && *ids == vec![hash_data(&missing)]
+    ));
+    assert!(!node.seen_blocks.contains(&block_hash));
+
+    // Transactions that are not in the block are ignored
+    assert!(
+        node.on_block_transactions(block_hash, vec![make_tx(12)])
+            .unwrap()
+            .is_empty()
+    );
+    assert!(node.pending_blocks.contains_key(&block_hash));
+
+    // Served from the pool of a peer that has it
+    let peer = make_node(&keys);
+    peer.tx_pool.add_transaction(missing.clone()).unwrap();
+    let served = peer.on_transactions_request(block_hash, &[hash_data(&missing)]);
+    let _ = node.on_block_transactions(block_hash, served);
+    assert!(node.pending_blocks.is_empty());
+    assert!(node.seen_blocks.contains(&block_hash));
+}
+
+#[test]
+fn test_compact_block_body_fallback() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let mut node = make_node(&keys);
+    let view = node.current_view.max(1);
+    let known = make_tx(10);
+    node.tx_pool.add_transaction(known.clone()).unwrap();
+
+    // The transaction list does not match the signed header: the whole body is fetched
+    let block = make_block(&keys[1], view, vec![known.clone(), make_tx(11)]);
+    let block_hash = hash_data(&block);
+    let mut compact = CompactBlock::new(&block);
+    compact.tx_ids.pop();
+    let actions = node.on_compact_block(compact, "peer".to_string()).unwrap();
+    assert!(matches!(actions[..], [ConsensusAction::RequestBody(h)] if h == block_hash));
+
+    // A body that is not the announced one is ignored
+    assert!(
+        node.on_block_body(block_hash, BlockBody::default())
+            .unwrap()
+            .is_empty()
+    );
+    assert!(node.pending_blocks.contains_key(&block_hash));
+
+    let (_, body) = block.split();
+    let _ = node.on_block_body(block_hash, body);
+    assert!(node.pending_blocks.is_empty());
+    assert!(node.seen_blocks.contains(&block_hash));
+}
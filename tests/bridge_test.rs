@@ -0,0 +1,140 @@
+use ockham::bridge::{BridgeCommittee, BridgeUpdate, G1_POINT_SIZE, G2_POINT_SIZE};
+use ockham::crypto::{Hash, PublicKey, Signature, verify_aggregate};
+use ockham::light::FinalityError;
+use ockham::rpc::{OckhamRpcImpl, OckhamRpcServer};
+use ockham::storage::MemStorage;
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::{DEFAULT_CHAIN_ID, U256, VoteType, vote_message};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+fn make_rpc(storage: Arc<MemStorage>) -> OckhamRpcImpl {
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    )
+}
+
+// Back from the EIP-2537 encoding to blst's uncompressed serialization
+fn decode_g1(encoded: &[u8]) -> Signature {
+    let point = [&encoded[16..64], &encoded[80..128]].concat();
+    Signature(blst::min_sig::Signature::deserialize(&point).unwrap())
+}
+
+fn decode_g2(encoded: &[u8]) -> PublicKey {
+    let mut point = Vec::new();
+    for coordinate in encoded.chunks(128) {
+        point.extend_from_slice(&coordinate[80..128]); // c1
+        point.extend_from_slice(&coordinate[16..64]); // c0
+    }
+    PublicKey(blst::min_sig::PublicKey::deserialize(&point).unwrap())
+}
+
+#[test]
+fn test_bridge_updates_follow_finality() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 4));
+    let committee = net.node(0).unwrap().committee.clone();
+    let rpc = make_rpc(net.storage(0));
+
+    let updates = rpc.bridge_get_updates(0, 100).unwrap();
+    assert!(!updates.is_empty());
+    assert!(
+        updates
+            .windows(2)
+            .all(|w| w[0].header.view < w[1].header.view)
+    );
+    for update in &updates {
+        // The preimage is what the certificate's block hash commits to
+        let digest: [u8; 32] = Sha256::digest(&update.preimage).into();
+        assert_eq!(Hash(digest), update.header.block_hash);
+        assert_eq!(
+            net.finalized().get(&update.header.view),
+            Some(&update.header.block_hash)
+        );
+
+        // The bitmap and the decoded points check out like the contract would
+        let signers: Vec<PublicKey> = (0..committee.len())
+            .filter(|i| update.signers.bit(*i))
+            .map(|i| committee[i].clone())
+            .collect();
+        assert!(signers.len() >= 3);
+        assert_eq!(update.signature.len(), G1_POINT_SIZE);
+        let message = vote_message(
+            VoteType::Finalize,
+            update.header.view,
+            &update.header.block_hash,
+            DEFAULT_CHAIN_ID,
+        );
+        assert!(verify_aggregate(
+            &signers,
+            &message,
+            &decode_g1(&update.signature)
+        ));
+    }
+
+    // Relayers resume after the last update; the limit is honoured
+    let last = updates.last().unwrap().header.view;
+    assert!(rpc.bridge_get_updates(last + 1, 100).unwrap().is_empty());
+    assert_eq!(rpc.bridge_get_updates(0, 1).unwrap(), updates[..1]);
+}
+
+#[test]
+fn test_bridge_encoding() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 2));
+    let committee = net.node(0).unwrap().committee.clone();
+    let rpc = make_rpc(net.storage(0));
+
+    let encoded = rpc.bridge_get_committee().unwrap().unwrap();
+    assert_eq!(encoded, BridgeCommittee::new(&committee, DEFAULT_CHAIN_ID));
+    assert_eq!(encoded.public_keys.len(), committee.len());
+    for (key, pk) in encoded.public_keys.iter().zip(&committee) {
+        assert_eq!(key.len(), G2_POINT_SIZE);
+        assert_eq!(&decode_g2(key), pk);
+    }
+
+    // ABI layout: 10 static words, two offsets, then the signature and preimage tails
+    let update = rpc.bridge_get_updates(0, 1).unwrap().remove(0);
+    let abi = update.abi_encode();
+    let word = |i: usize| U256::from_be_slice(&abi[i * 32..(i + 1) * 32]);
+    assert_eq!(word(0), U256::from(update.header.view));
+    assert_eq!(&abi[3 * 32..4 * 32], &update.header.block_hash.0);
+    assert_eq!(word(9), update.signers);
+    assert_eq!(word(10), U256::from(12 * 32));
+    assert_eq!(word(12), U256::from(G1_POINT_SIZE));
+    assert_eq!(
+        &abi[13 * 32..13 * 32 + G1_POINT_SIZE],
+        &update.signature[..]
+    );
+    let preimage_at = word(11).to::<usize>();
+    assert_eq!(
+        abi[preimage_at..preimage_at + 32],
+        U256::from(update.preimage.len()).to_be_bytes::<32>()
+    );
+    assert_eq!(abi.len() % 32, 0);
+
+    // Packaging checks the certificate against the committee
+    let finalized = rpc
+        .get_finality_proof(update.header.block_hash)
+        .unwrap()
+        .unwrap();
+    let mut short = finalized.clone();
+    short.proof.finalization.signers.truncate(2);
+    assert_eq!(
+        BridgeUpdate::new(&short, &committee, DEFAULT_CHAIN_ID),
+        Err(FinalityError::InsufficientSigners("Finalization", 2, 3))
+    );
+    assert_eq!(
+        BridgeUpdate::new(&finalized, &committee[..3], DEFAULT_CHAIN_ID),
+        Err(FinalityError::CommitteeMismatch)
+    );
+}
@@ -0,0 +1,85 @@
+use ockham::storage::Storage;
+use ockham::testing::{Misbehavior, SimConfig, SimNetwork};
+use ockham::types::Block;
+
+/// Finalized blocks, read from the storage of honest node `honest`.
+fn finalized_blocks(net: &SimNetwork, honest: usize) -> Vec<Block> {
+    let storage = net.storage(honest);
+    net.finalized()
+        .values()
+        .filter_map(|hash| storage.get_block(hash).ok().flatten())
+        .collect()
+}
+
+#[test]
+fn test_equivocating_voter_is_slashed() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    net.make_byzantine(3, &[Misbehavior::EquivocateVotes]);
+
+    // The evidence is gossiped, then included (and executed) in a finalized block
+    let slashed = net.run_until(120_000, |net| {
+        finalized_blocks(net, 0)
+            .iter()
+            .any(|b| !b.evidence.is_empty())
+    });
+    assert!(slashed);
+    assert!(net.stats().evidence > 0);
+    let evidence = finalized_blocks(&net, 0)
+        .into_iter()
+        .flat_map(|b| b.evidence)
+        .next()
+        .unwrap();
+    assert_eq!(
+        evidence.vote_a.author,
+        net.node(3).unwrap().my_id,
+        "only the Byzantine node is accused"
+    );
+}
+
+#[test]
+fn test_conflicting_proposals_are_reported() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    net.make_byzantine(1, &[Misbehavior::ConflictingProposals]);
+
+    let reported = net.run_until(120_000, |net| {
+        finalized_blocks(net, 0)
+            .iter()
+            .any(|b| !b.proposal_evidence.is_empty())
+    });
+    assert!(reported);
+    for block in finalized_blocks(&net, 0) {
+        for evidence in &block.proposal_evidence {
+            assert_eq!(evidence.offender(), &net.node(1).unwrap().my_id);
+        }
+    }
+}
+
+#[test]
+fn test_bad_leaders_do_not_stall_the_chain() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    net.make_byzantine(1, &[Misbehavior::InvalidStateRoot]);
+    net.make_byzantine(2, &[Misbehavior::WithholdProposals]);
+
+    assert!(net.run_until(300_000, |net| net.last_finalized() >= 12));
+    // Nothing they proposed after turning Byzantine made it into the chain
+    for view in net.finalized().keys().filter(|v| **v > 1) {
+        let leader = net.leader_of(*view);
+        assert!(
+            leader != 1 && leader != 2,
+            "view {} led by {}",
+            view,
+            leader
+        );
+    }
+}
+
+#[test]
+fn test_stalled_node_tolerated() {
+    let mut net = SimNetwork::new(SimConfig {
+        seed: 3,
+        ..Default::default()
+    });
+    net.make_byzantine(0, &[Misbehavior::Stall]);
+    assert!(net.run_until(120_000, |net| net.last_finalized() >= 6));
+    assert_eq!(net.stats().evidence, 0);
+}
@@ -0,0 +1,152 @@
+use ockham::chain_spec::{ChainSpec, ChainSpecError, GenesisAccount, GenesisValidator};
+use ockham::consensus::SimplexState;
+use ockham::crypto::{Hash, generate_keypair_from_id};
+use ockham::storage::{MemStorage, Storage};
+use ockham::system_contracts::STAKING_ADDRESS;
+use ockham::types::{Address, Bytes, U256};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_export_genesis_roundtrip() {
+    let (pk, sk) = generate_keypair_from_id(0);
+    let committee = vec![pk.clone()];
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let _node = SimplexState::new(
+        pk.clone(),
+        sk,
+        committee,
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // A contract with code and storage
+    let contract = Address::from_slice(&[0x42; 20]);
+    let code = Bytes::from(vec![0x60, 0x00]);
+    storage
+        .save_account(
+            &contract,
+            &ockham::storage::AccountInfo {
+                nonce: 1,
+                balance: U256::from(7),
+                code_hash: Hash(ockham::types::keccak256(&code).into()),
+            },
+        )
+        .unwrap();
+    storage
+        .save_code(&Hash(ockham::types::keccak256(&code).into()), &code)
+        .unwrap();
+    storage
+        .save_storage(&contract, &U256::from(1), &U256::from(99))
+        .unwrap();
+
+    let spec = ChainSpec::export(storage.as_ref(), 1337, None).unwrap();
+    assert_eq!(spec.view, 0);
+    assert_eq!(spec.validators.len(), 1);
+    assert_eq!(spec.validators[0].public_key().unwrap(), pk);
+    assert_eq!(spec.validators[0].stake, U256::from(5000u64));
+    let exported = &spec.accounts[&contract];
+    assert_eq!(exported.code, Some(code));
+    assert_eq!(exported.storage[&U256::from(1)], U256::from(99));
+
+    // Non-finalized views are rejected
+    assert!(matches!(
+        ChainSpec::export(storage.as_ref(), 1337, Some(5)),
+        Err(ChainSpecError::NotFinalized(5, 0))
+    ));
+
+    // JSON roundtrip and re-import into a fresh node reproduce the same spec
+    let json = spec.to_json().unwrap();
+    let parsed = ChainSpec::from_json(&json).unwrap();
+    assert_eq!(parsed, spec);
+
+    let fresh = Arc::new(MemStorage::new());
+    fresh
+        .save_consensus_state(&storage.get_consensus_state().unwrap().unwrap())
+        .unwrap();
+    let fresh_state = ockham::state::StateManager::new(fresh.clone(), None);
+    parsed.apply(&fresh_state).unwrap();
+
+    let reexported = ChainSpec::export(fresh.as_ref(), 1337, None).unwrap();
+    assert_eq!(reexported, spec);
+    assert_eq!(reexported.to_json().unwrap(), json);
+}
+
+#[test]
+fn test_init_genesis_from_spec() {
+    let validators: Vec<_> = (1..3).map(generate_keypair_from_id).collect();
+    let funded = Address::from_slice(&[0x11; 20]);
+    let mut spec = ChainSpec {
+        chain_id: 1337,
+        validators: validators
+            .iter()
+            .zip([100u64, 200])
+            .map(|(k, stake)| GenesisValidator {
+                public_key: hex::encode(k.0.0.to_bytes()),
+                stake: U256::from(stake),
+            })
+            .collect(),
+        ..Default::default()
+    };
+    spec.accounts.insert(
+        funded,
+        GenesisAccount {
+            balance: U256::from(1_000_000u64),
+            ..Default::default()
+        },
+    );
+
+    let storage = Arc::new(MemStorage::new());
+    let root = spec.init_genesis(storage.clone()).unwrap();
+    assert_eq!(storage.get_chain_head().unwrap().unwrap().state_root, root);
+    assert!(matches!(
+        spec.init_genesis(storage.clone()),
+        Err(ChainSpecError::AlreadyInitialized)
+    ));
+
+    // The node starts from the spec's committee, not the one it is given
+    let (pk, sk) = generate_keypair_from_id(0);
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        Some(root),
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let node = SimplexState::new(
+        pk.clone(),
+        sk,
+        vec![pk],
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let committee: Vec<_> = validators.iter().map(|k| k.0.clone()).collect();
+    assert_eq!(node.committee, committee);
+
+    // Stakes are deposited in the staking contract, which holds their sum
+    let exported = ChainSpec::export(storage.as_ref(), 1337, None).unwrap();
+    assert_eq!(exported.validators, spec.validators);
+    assert_eq!(exported.accounts[&funded].balance, U256::from(1_000_000u64));
+    assert_eq!(
+        exported.accounts[&STAKING_ADDRESS].balance,
+        U256::from(300u64)
+    );
+
+    // A spec without validators cannot start a chain
+    let empty = ChainSpec::default();
+    assert!(matches!(
+        empty.init_genesis(Arc::new(MemStorage::new())),
+        Err(ChainSpecError::NoValidators)
+    ));
+}
@@ -0,0 +1,235 @@
+//! Scripted chaos scenarios on an in-process devnet.
+//!
+//! Runs on `ockham::testing::SimNetwork` (in-memory message bus, simulated clock).
+//! The runner injects faults when the network reaches a given view and checks:
+//! - no two different blocks are ever finalized for the same view (across all nodes),
+//! - after each disruption ends, a block is finalized again within `max_recovery_views`.
+//!
+//! Heavy, so ignored by default: `cargo test --test chaos_test -- --ignored`
+
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::View;
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug)]
+enum Chaos {
+    /// Kill the leader of `view` for good, just before the network enters that view.
+    KillLeader { view: View },
+    /// Cut `group` off from the rest of the committee for `duration_ms`.
+    /// Messages across the cut are held back and delivered when the partition heals.
+    Partition {
+        at_view: View,
+        group: Vec<usize>,
+        duration_ms: u64,
+    },
+    /// Stop `node` for `downtime_ms`, then restart it from its storage with an empty tx pool.
+    Restart {
+        at_view: View,
+        node: usize,
+        downtime_ms: u64,
+    },
+}
+
+impl Chaos {
+    fn trigger_view(&self) -> View {
+        match self {
+            Chaos::KillLeader { view } => view.saturating_sub(1),
+            Chaos::Partition { at_view, .. } | Chaos::Restart { at_view, .. } => *at_view,
+        }
+    }
+}
+
+struct Scenario {
+    name: &'static str,
+    nodes: usize,
+    chaos: Vec<Chaos>,
+    /// The run succeeds once a block at or above this view is finalized (and all chaos is over).
+    finalize_until: View,
+    max_recovery_views: View,
+    time_limit_ms: u64,
+}
+
+/// Disruptions in progress, ended by the runner when their time comes.
+#[derive(Default)]
+struct Disruptions {
+    heal_at: Option<u64>,
+    restarts: Vec<(usize, u64)>, // (node, restart time)
+}
+
+impl Disruptions {
+    fn apply(&mut self, net: &mut SimNetwork, chaos: Chaos) {
+        println!("[{}ms] Chaos: {:?}", net.now(), chaos);
+        match chaos {
+            Chaos::KillLeader { view } => net.kill(net.leader_of(view)),
+            Chaos::Partition {
+                group, duration_ms, ..
+            } => {
+                net.partition(&group);
+                self.heal_at = Some(net.now() + duration_ms);
+            }
+            Chaos::Restart {
+                node, downtime_ms, ..
+            } => {
+                net.kill(node);
+                self.restarts.push((node, net.now() + downtime_ms));
+            }
+        }
+    }
+
+    /// Heal partitions and restart nodes that are due. Returns true if a disruption ended.
+    fn end_due(&mut self, net: &mut SimNetwork) -> bool {
+        let now = net.now();
+        let mut ended = false;
+        if self.heal_at.is_some_and(|at| now >= at) {
+            println!("[{}ms] Partition healed", now);
+            self.heal_at = None;
+            net.heal();
+            ended = true;
+        }
+        let (due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.restarts)
+            .into_iter()
+            .partition(|(_, at)| now >= *at);
+        self.restarts = pending;
+        for (node, _) in due {
+            println!("[{}ms] Restarting node {}", now, node);
+            net.restart(node);
+            ended = true;
+        }
+        ended
+    }
+
+    fn active(&self) -> bool {
+        self.heal_at.is_some() || !self.restarts.is_empty()
+    }
+}
+
+fn run(scenario: Scenario) {
+    let mut net = SimNetwork::new(SimConfig {
+        nodes: scenario.nodes,
+        ..Default::default()
+    });
+    let mut disruptions = Disruptions::default();
+    let mut chaos: VecDeque<Chaos> = scenario.chaos.into_iter().collect();
+    // (view by which finality must resume, finalized view to beat)
+    let mut recovery: Option<(View, View)> = None;
+
+    while net.now() < scenario.time_limit_ms {
+        while chaos
+            .front()
+            .is_some_and(|c| net.max_view() >= c.trigger_view())
+        {
+            let event = chaos.pop_front().unwrap();
+            let permanent = matches!(event, Chaos::KillLeader { .. });
+            disruptions.apply(&mut net, event);
+            if permanent {
+                recovery = Some((
+                    net.max_view() + scenario.max_recovery_views,
+                    net.last_finalized(),
+                ));
+            }
+        }
+        if disruptions.end_due(&mut net) {
+            recovery = Some((
+                net.max_view() + scenario.max_recovery_views,
+                net.last_finalized(),
+            ));
+        }
+
+        net.step();
+        assert_eq!(
+            net.stats().evidence,
+            0,
+            "{}: unexpected evidence",
+            scenario.name
+        );
+
+        if let Some((deadline, base)) = recovery {
+            if net.last_finalized() > base {
+                println!(
+                    "[{}ms] Finality resumed at view {}",
+                    net.now(),
+                    net.last_finalized()
+                );
+                recovery = None;
+            } else if !disruptions.active() {
+                assert!(
+                    net.max_view() <= deadline,
+                    "{}: finality did not resume within {} views (stuck at view {}, last finalized {})",
+                    scenario.name,
+                    scenario.max_recovery_views,
+                    net.max_view(),
+                    base
+                );
+            }
+        }
+
+        if chaos.is_empty()
+            && !disruptions.active()
+            && recovery.is_none()
+            && net.last_finalized() >= scenario.finalize_until
+        {
+            println!(
+                "{}: finalized view {} after {}ms",
+                scenario.name,
+                net.last_finalized(),
+                net.now()
+            );
+            return;
+        }
+    }
+
+    panic!(
+        "{}: time limit reached at view {} (last finalized {}, wanted {})",
+        scenario.name,
+        net.max_view(),
+        net.last_finalized(),
+        scenario.finalize_until
+    );
+}
+
+#[test]
+#[ignore]
+fn test_chaos_kill_leader() {
+    run(Scenario {
+        name: "kill_leader",
+        nodes: 4,
+        chaos: vec![Chaos::KillLeader { view: 6 }],
+        finalize_until: 20,
+        max_recovery_views: 4,
+        time_limit_ms: 300_000,
+    });
+}
+
+#[test]
+#[ignore]
+fn test_chaos_partition_half_committee() {
+    run(Scenario {
+        name: "partition_half_committee",
+        nodes: 4,
+        chaos: vec![Chaos::Partition {
+            at_view: 5,
+            group: vec![0, 1],
+            duration_ms: 30_000,
+        }],
+        finalize_until: 20,
+        max_recovery_views: 4,
+        time_limit_ms: 300_000,
+    });
+}
+
+#[test]
+#[ignore]
+fn test_chaos_restart_with_empty_pool() {
+    run(Scenario {
+        name: "restart_with_empty_pool",
+        nodes: 4,
+        chaos: vec![Chaos::Restart {
+            at_view: 4,
+            node: 2,
+            downtime_ms: 10_000,
+        }],
+        finalize_until: 20,
+        max_recovery_views: 6,
+        time_limit_ms: 300_000,
+    });
+}
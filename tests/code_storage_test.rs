@@ -0,0 +1,99 @@
+use ockham::crypto::Hash;
+use ockham::state::StateManager;
+use ockham::storage::{AccountInfo, MemStorage, RedbStorage, Storage};
+use ockham::types::{Address, Bytes, U256, keccak256};
+use redb::{Database, TableDefinition};
+use serde::Serialize;
+use std::sync::Arc;
+
+// Account record layout before code was moved out of it
+#[derive(Serialize)]
+struct LegacyAccountInfo {
+    nonce: u64,
+    balance: U256,
+    code_hash: Hash,
+    code: Option<Bytes>,
+}
+
+#[test]
+fn test_inline_code_migration() {
+    let path = std::env::temp_dir().join(format!("ockham_code_migration_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let code = Bytes::from(vec![0x60, 0x00, 0x60, 0x00]);
+    let code_hash = Hash(keccak256(&code).into());
+    let contract = Address::from([0x42; 20]);
+    let user = Address::from([0x43; 20]);
+
+    {
+        let accounts: TableDefinition<&[u8; 20], Vec<u8>> = TableDefinition::new("accounts");
+        let db = Database::create(&path).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(accounts).unwrap();
+            for (address, code) in [(contract, Some(code.clone())), (user, None)] {
+                let legacy = LegacyAccountInfo {
+                    nonce: 1,
+                    balance: U256::from(7u64),
+                    code_hash: if code.is_some() {
+                        code_hash
+                    } else {
+                        Hash::default()
+                    },
+                    code,
+                };
+                table
+                    .insert(&address.0.0, bincode::serialize(&legacy).unwrap())
+                    .unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+    }
+
+    let storage = RedbStorage::new(&path).unwrap();
+    let info = storage.get_account(&contract).unwrap().unwrap();
+    assert_eq!((info.nonce, info.code_hash), (1, code_hash));
+    assert_eq!(storage.get_code(&code_hash).unwrap(), Some(code));
+    assert_eq!(
+        storage.get_account(&user).unwrap().unwrap().balance,
+        U256::from(7u64)
+    );
+    drop(storage);
+
+    // Reopening does not run the migration again over new-format records
+    let storage = RedbStorage::new(&path).unwrap();
+    assert_eq!(storage.get_account(&contract).unwrap(), Some(info));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_code_stored_once() {
+    let storage = Arc::new(MemStorage::new());
+    let state = StateManager::new(storage.clone(), None);
+    let code = Bytes::from(vec![0x60, 0x01]);
+    let code_hash = Hash(keccak256(&code).into());
+
+    for i in 1..=2u8 {
+        state.commit_code(code_hash, &code).unwrap();
+        state
+            .commit_account(
+                Address::from([i; 20]),
+                AccountInfo {
+                    nonce: 1,
+                    balance: U256::ZERO,
+                    code_hash,
+                },
+            )
+            .unwrap();
+    }
+    assert_eq!(storage.get_code(&code_hash).unwrap(), Some(code));
+    // Empty code is never stored
+    state
+        .commit_code(Hash(keccak256([]).into()), &Bytes::new())
+        .unwrap();
+    assert!(
+        storage
+            .get_code(&Hash(keccak256([]).into()))
+            .unwrap()
+            .is_none()
+    );
+}
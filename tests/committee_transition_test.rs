@@ -0,0 +1,118 @@
+use ockham::consensus::SimplexState;
+use ockham::crypto::{Hash, PublicKey, aggregate, generate_keypair_from_id, sign};
+use ockham::storage::{MemStorage, RedbStorage, Storage};
+use ockham::types::CommitteeTransition;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_committee_transition_verification() {
+    let keys: Vec<_> = (0..5).map(generate_keypair_from_id).collect();
+    let old_committee: Vec<_> = keys[..4].iter().map(|(pk, _)| pk.clone()).collect();
+    let new_committee: Vec<_> = keys[1..].iter().map(|(pk, _)| pk.clone()).collect();
+
+    let unsigned = CommitteeTransition::new(
+        1,
+        10,
+        Hash([7u8; 32]),
+        &old_committee,
+        new_committee.clone(),
+    );
+    let commitment = unsigned.commitment();
+
+    let signed_by = |ids: &[usize]| {
+        let mut t = unsigned.clone();
+        let sigs: Vec<_> = ids
+            .iter()
+            .map(|&i| sign(&keys[i].1, &commitment.0))
+            .collect();
+        t.signature = aggregate(&sigs).unwrap();
+        t.signers = ids.iter().map(|&i| keys[i].0.clone()).collect();
+        t
+    };
+
+    // 3 of 4 is a quorum
+    let transition = signed_by(&[0, 1, 2]);
+    assert!(transition.verify(&old_committee));
+
+    // 2 of 4 is not
+    assert!(!signed_by(&[0, 1]).verify(&old_committee));
+
+    // Signer outside the outgoing committee
+    assert!(!signed_by(&[0, 1, 4]).verify(&old_committee));
+
+    // Duplicate signer does not count twice
+    assert!(!signed_by(&[0, 1, 1]).verify(&old_committee));
+
+    // Wrong outgoing committee
+    assert!(!transition.verify(&new_committee));
+
+    // Tampering with the incoming committee breaks the signature
+    let mut tampered = transition.clone();
+    tampered.new_committee.pop();
+    assert!(!tampered.verify(&old_committee));
+
+    // Chain must start at epoch 1 and link committees
+    assert_eq!(
+        CommitteeTransition::verify_chain(&old_committee, std::slice::from_ref(&transition)),
+        Some(new_committee.clone())
+    );
+    assert_eq!(
+        CommitteeTransition::verify_chain(&new_committee, &[transition.clone()]),
+        None
+    );
+    let mut skipped = transition;
+    skipped.epoch = 2;
+    assert_eq!(
+        CommitteeTransition::verify_chain(&old_committee, &[skipped]),
+        None
+    );
+}
+
+fn check_historical_committees(storage: Arc<dyn Storage>) {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|(pk, _)| pk.clone()).collect();
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let node = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee.clone(),
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // Epoch 1 starts with the committee change finalized at view 10
+    let genesis = storage.get_validator_set(0).unwrap().unwrap();
+    let mut next = genesis.clone();
+    next.epoch = 1;
+    next.view = 10;
+    next.committee.pop();
+    storage.save_validator_set(&next).unwrap();
+
+    assert_eq!(storage.get_validator_set_at(9).unwrap(), Some(genesis));
+    assert_eq!(storage.get_validator_set_at(10).unwrap(), Some(next));
+
+    // Certificates up to the changing block are the outgoing committee's
+    assert_eq!(node.committee_at(1), Some(committee.clone()));
+    assert_eq!(node.committee_at(10), Some(committee.clone()));
+    assert_eq!(node.committee_at(11), Some(committee[..3].to_vec()));
+}
+
+#[test]
+fn test_historical_committees_mem() {
+    check_historical_committees(Arc::new(MemStorage::new()));
+}
+
+#[test]
+fn test_historical_committees_redb() {
+    let path = std::env::temp_dir().join(format!("ockham_committees_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    check_historical_committees(Arc::new(RedbStorage::new(&path).unwrap()));
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,81 @@
+use ockham::conformance::{ConformanceSuite, SUITE_VERSION, VectorCase};
+use ockham::crypto::Hash;
+
+#[test]
+fn test_conformance_suite_roundtrip() {
+    let suite = ConformanceSuite::generate();
+    assert_eq!(suite.version, SUITE_VERSION);
+    for kind in ["block/", "vote/", "qc/", "transaction/", "state/"] {
+        assert!(
+            suite.vectors.iter().any(|v| v.name.starts_with(kind)),
+            "Missing {} vectors",
+            kind
+        );
+    }
+
+    // Generation is deterministic and the implementation passes its own vectors
+    assert_eq!(
+        suite.to_json().unwrap(),
+        ConformanceSuite::generate().to_json().unwrap()
+    );
+    let report = suite.run();
+    assert!(report.is_success(), "{:?}", report.failures);
+    assert_eq!(report.passed, suite.vectors.len());
+
+    // Vectors survive the JSON encoding other implementations consume
+    let path = std::env::temp_dir().join(format!("ockham_conformance_{}.json", std::process::id()));
+    suite.save(&path).unwrap();
+    let loaded = ConformanceSuite::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(loaded.run().is_success());
+
+    // Negative cases are recorded as invalid, not skipped
+    let invalid = |name: &str| match &suite.vectors.iter().find(|v| v.name == name).unwrap().case {
+        VectorCase::Block {
+            signature_valid, ..
+        }
+        | VectorCase::Vote {
+            signature_valid, ..
+        }
+        | VectorCase::Transaction {
+            signature_valid, ..
+        } => !signature_valid,
+        VectorCase::QuorumCertificate { valid, .. } => !valid,
+        VectorCase::StateTransition { .. } => false,
+    };
+    assert!(invalid("block/wrong_signer"));
+    assert!(invalid("vote/wrong_author"));
+    assert!(invalid("vote/wrong_type"));
+    assert!(invalid("qc/wrong_block"));
+    assert!(invalid("transaction/modified_after_signing"));
+}
+
+#[test]
+fn test_conformance_detects_divergence() {
+    let mut suite = ConformanceSuite::generate();
+    let vector = suite
+        .vectors
+        .iter_mut()
+        .find(|v| v.name == "state/transfer")
+        .unwrap();
+    if let VectorCase::StateTransition {
+        expected_state_root,
+        ..
+    } = &mut vector.case
+    {
+        *expected_state_root = Hash([0xff; 32]);
+    }
+
+    let report = suite.run();
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].0, "state/transfer");
+    assert!(report.failures[0].1.starts_with("state_root"));
+
+    // Suites from a newer format are refused rather than misread
+    let json = suite.to_json().unwrap().replacen(
+        &format!("\"version\": {}", SUITE_VERSION),
+        "\"version\": 999",
+        1,
+    );
+    assert!(ConformanceSuite::from_json(&json).is_err());
+}
@@ -0,0 +1,114 @@
+use ockham::consensus::SimplexState;
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, sign};
+use ockham::seen_cache::SeenCache;
+use ockham::types::{DEFAULT_CHAIN_ID, Vote, VoteType, vote_message};
+use std::sync::{Arc, Mutex};
+
+fn make_node(keys: &[(PublicKey, PrivateKey)]) -> SimplexState {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+}
+
+fn make_vote(
+    key: &(PublicKey, PrivateKey),
+    view: u64,
+    block_hash: Hash,
+    vote_type: VoteType,
+) -> Vote {
+    Vote {
+        view,
+        block_hash,
+        vote_type,
+        author: key.0.clone(),
+        signature: sign(
+            &key.1,
+            &vote_message(vote_type, view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    }
+}
+
+#[test]
+fn test_seen_cache_lru() {
+    let h = |i: u8| Hash([i; 32]);
+    let mut cache = SeenCache::new(2);
+    assert!(cache.insert(h(1)));
+    assert!(cache.insert(h(2)));
+    assert!(!cache.insert(h(1))); // Duplicate, and now the most recent
+
+    // Full: the least recently seen hash (2) goes
+    assert!(cache.insert(h(3)));
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains(&h(1)));
+    assert!(!cache.contains(&h(2)));
+
+    // Forgotten hashes count as new again
+    cache.remove(&h(1));
+    assert!(cache.insert(h(1)));
+    cache.evict_oldest(1);
+    assert_eq!(cache.len(), 1);
+    assert!(cache.contains(&h(1)));
+
+    // Repeated sightings do not grow the cache
+    for _ in 0..100 {
+        cache.insert(h(1));
+    }
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn test_duplicate_vote_ignored() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let mut node = make_node(&keys);
+    let vote = make_vote(&keys[1], 3, Hash([7; 32]), VoteType::Notarize);
+
+    node.on_vote(vote.clone()).unwrap();
+    assert!(node.on_vote(vote).unwrap().is_empty());
+    assert_eq!(node.votes_received[&3].len(), 1);
+    assert_eq!(node.seen_votes.len(), 1);
+}
+
+#[test]
+fn test_votes_below_finalized_height_pruned() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let mut node = make_node(&keys);
+    for view in 1..=4 {
+        node.on_vote(make_vote(&keys[1], view, Hash([7; 32]), VoteType::Notarize))
+            .unwrap();
+    }
+    assert_eq!(node.votes_received.len(), 4);
+
+    // Finalize view 3 (a dummy block, so no state commit is needed)
+    for key in &keys[..3] {
+        node.on_vote(make_vote(key, 3, Hash::default(), VoteType::Finalize))
+            .unwrap();
+    }
+    assert_eq!(node.finalized_height, 3);
+
+    let mut views: Vec<_> = node.votes_received.keys().copied().collect();
+    views.sort_unstable();
+    assert_eq!(views, vec![3, 4]);
+    assert!(node.finalize_votes_received.keys().all(|v| *v >= 3));
+
+    // Late votes for settled views are dropped
+    node.on_vote(make_vote(&keys[2], 2, Hash([7; 32]), VoteType::Notarize))
+        .unwrap();
+    node.on_vote(make_vote(&keys[3], 1, Hash::default(), VoteType::Finalize))
+        .unwrap();
+    assert!(!node.votes_received.contains_key(&2));
+    assert!(!node.finalize_votes_received.contains_key(&1));
+}
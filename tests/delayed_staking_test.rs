@@ -1,7 +1,11 @@
 use ockham::consensus::SimplexState;
 use ockham::crypto::{Hash, generate_keypair_from_id, hash_data, sign};
 use ockham::storage::{MemStorage, Storage};
-use ockham::types::{Address, Block, QuorumCertificate, Transaction, U256};
+use ockham::system_contracts::staking;
+use ockham::types::{
+    Address, Block, DEFAULT_CHAIN_ID, QuorumCertificate, SignerBitmap, Transaction, U256, VoteType,
+    vote_message,
+};
 use revm::Database;
 use std::sync::Arc;
 
@@ -33,6 +37,12 @@ fn test_delayed_staking_lifecycle() {
         ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
     );
 
+    // Short unbonding period, so Bob can withdraw right after leaving
+    let mut state = storage.get_consensus_state().unwrap().unwrap();
+    state.params.withdrawal_delay = 1;
+    state.params.evidence_max_age = 1;
+    storage.save_consensus_state(&state).unwrap();
+
     let bob_addr =
         ockham::types::Address::from_slice(&ockham::types::keccak256(bob_pk.0.to_bytes())[12..]);
 
@@ -41,7 +51,7 @@ fn test_delayed_staking_lifecycle() {
     // -------------------------------------------------------------
     println!("--- Funding Bob ---");
     let tx_fund = Transaction {
-        chain_id: 1,
+        chain_id: 1337,
         nonce: 0,
         max_priority_fee_per_gas: U256::ZERO,
         max_fee_per_gas: U256::ZERO,
@@ -84,16 +94,21 @@ fn test_delayed_staking_lifecycle() {
     );
 
     // Calculate Roots
+    b1.height = 1;
     prepare_block(&mut b1, storage.clone());
+    b1.sign(&alice_sk);
     let b1_hash = hash_data(&b1);
 
     alice.on_proposal(b1.clone()).unwrap();
     let vote_fin_1 = ockham::types::Vote {
         view: 1,
         block_hash: b1_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: alice_pk.clone(),
-        signature: sign(&alice_sk, &b1_hash.0),
+        signature: sign(
+            &alice_sk,
+            &vote_message(VoteType::Finalize, 1, &b1_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(vote_fin_1).unwrap();
 
@@ -111,7 +126,7 @@ fn test_delayed_staking_lifecycle() {
     println!("--- Bob Staking ---");
     let stake_call = hex::decode("3a4b66f1").unwrap();
     let tx_stake = Transaction {
-        chain_id: 1,
+        chain_id: 1337,
         nonce: 0,
         max_priority_fee_per_gas: U256::ZERO,
         max_fee_per_gas: U256::ZERO,
@@ -128,12 +143,16 @@ fn test_delayed_staking_lifecycle() {
     let mut tx_stake_signed = tx_stake.clone();
     tx_stake_signed.signature = sign(&bob_sk, &tx_stake.sighash().0);
 
-    let sig1 = sign(&alice_sk, &b1_hash.0);
+    let sig1 = sign(
+        &alice_sk,
+        &vote_message(VoteType::Notarize, 1, &b1_hash, DEFAULT_CHAIN_ID),
+    );
     let qc1 = QuorumCertificate {
         view: 1,
         block_hash: b1_hash,
         signature: sig1,
-        signers: vec![alice_pk.clone()],
+        epoch: 0,
+        signers: SignerBitmap::from_signers(&committee, [&alice_pk]).unwrap(),
     };
 
     let mut b2 = Block::new(
@@ -149,16 +168,21 @@ fn test_delayed_staking_lifecycle() {
         vec![],
         hash_data(&committee),
     );
+    b2.height = 2;
     prepare_block(&mut b2, storage.clone());
+    b2.sign(&alice_sk);
     let b2_hash = hash_data(&b2);
 
     alice.on_proposal(b2.clone()).unwrap();
     let vote_fin_2 = ockham::types::Vote {
         view: 2,
         block_hash: b2_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: alice_pk.clone(),
-        signature: sign(&alice_sk, &b2_hash.0),
+        signature: sign(
+            &alice_sk,
+            &vote_message(VoteType::Finalize, 2, &b2_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(vote_fin_2).unwrap();
 
@@ -177,7 +201,9 @@ fn test_delayed_staking_lifecycle() {
         assert_eq!(state.pending_validators[0].1, 12);
         println!("Bob Pending until view 12");
 
-        let stake = state.stakes.get(&bob_addr).cloned().unwrap_or_default();
+        let stake = storage
+            .get_storage(&staking::STAKING_ADDRESS, &staking::stake_slot(bob_addr))
+            .unwrap();
         println!("DEBUG: Bob Stake in Storage: {}", stake);
         assert_eq!(stake, U256::from(2000u64));
     }
@@ -186,12 +212,16 @@ fn test_delayed_staking_lifecycle() {
     // STAGE 2: ACTIVATE (Block 12)
     // -------------------------------------------------------------
     // Propose B12 extending B2.
-    let sig2 = sign(&alice_sk, &b2_hash.0);
+    let sig2 = sign(
+        &alice_sk,
+        &vote_message(VoteType::Notarize, 2, &b2_hash, DEFAULT_CHAIN_ID),
+    );
     let qc2 = QuorumCertificate {
         view: 2,
         block_hash: b2_hash,
         signature: sig2,
-        signers: vec![alice_pk.clone()],
+        epoch: 0,
+        signers: SignerBitmap::from_signers(&committee, [&alice_pk]).unwrap(),
     };
 
     let mut b12 = Block::new(
@@ -207,16 +237,21 @@ fn test_delayed_staking_lifecycle() {
         vec![],
         hash_data(&committee),
     );
+    b12.height = 3;
     prepare_block(&mut b12, storage.clone());
+    b12.sign(&alice_sk);
     let b12_hash = hash_data(&b12);
 
     alice.on_proposal(b12.clone()).unwrap();
     let vote_fin_12 = ockham::types::Vote {
         view: 12,
         block_hash: b12_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: alice_pk.clone(),
-        signature: sign(&alice_sk, &b12_hash.0),
+        signature: sign(
+            &alice_sk,
+            &vote_message(VoteType::Finalize, 12, &b12_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(vote_fin_12).unwrap();
 
@@ -226,18 +261,33 @@ fn test_delayed_staking_lifecycle() {
         assert!(state.pending_validators.is_empty());
         println!("Bob Active");
 
-        let stake = state.stakes.get(&bob_addr).cloned().unwrap_or_default();
+        let stake = storage
+            .get_storage(&staking::STAKING_ADDRESS, &staking::stake_slot(bob_addr))
+            .unwrap();
         println!("DEBUG: Bob Stake after Activation: {}", stake);
         assert_eq!(stake, U256::from(2000u64));
     }
 
+    // Hand-over to [Alice, Bob] is certified by Alice alone (old committee)
+    {
+        let transition = storage.get_committee_transition(1).unwrap().unwrap();
+        assert_eq!(transition.view, 12);
+        assert_eq!(transition.block_hash, b12_hash);
+        assert_eq!(
+            transition.new_committee,
+            vec![alice_pk.clone(), bob_pk.clone()]
+        );
+        assert!(transition.verify(&committee));
+        assert_eq!(alice.epoch, 1);
+    }
+
     // -------------------------------------------------------------
     // STAGE 3: UNSTAKE (Block 13)
     // -------------------------------------------------------------
     println!("--- Bob Unstaking ---");
     let unstake_call = hex::decode("2e17de78").unwrap();
     let mut tx_unstake = Transaction {
-        chain_id: 1,
+        chain_id: 1337,
         nonce: 1,
         max_priority_fee_per_gas: U256::ZERO,
         max_fee_per_gas: U256::ZERO,
@@ -281,20 +331,24 @@ fn test_delayed_staking_lifecycle() {
     // B13 (View 13).
     // QC for B12 needs Alice signature.
 
-    let sig12 = sign(&alice_sk, &b12_hash.0);
+    let sig12 = sign(
+        &alice_sk,
+        &vote_message(VoteType::Notarize, 12, &b12_hash, DEFAULT_CHAIN_ID),
+    );
     // Sig12 needs to be Aggregate format if using verify_aggregate?
     // Alice is 1/1 (Bob not active yet in QC view).
     let qc12 = QuorumCertificate {
         view: 12,
         block_hash: b12_hash,
         signature: sig12,
-        signers: vec![alice_pk.clone()],
+        epoch: 0,
+        signers: SignerBitmap::from_signers(&committee, [&alice_pk]).unwrap(),
     };
 
     // But B13 Block Committee Hash?
-    // Use new committee [Alice, Bob].
+    // Use new committee [Alice, Bob], which also makes Bob the leader of View 13 (13 % 2).
     let mut b13 = Block::new(
-        alice_pk.clone(),
+        bob_pk.clone(),
         13,
         b12_hash,
         qc12,
@@ -306,7 +360,9 @@ fn test_delayed_staking_lifecycle() {
         vec![],
         hash_data(&new_committee),
     );
+    b13.height = 4;
     prepare_block(&mut b13, storage.clone());
+    b13.sign(&bob_sk);
     let b13_hash = hash_data(&b13);
 
     alice.on_proposal(b13.clone()).unwrap();
@@ -319,18 +375,24 @@ fn test_delayed_staking_lifecycle() {
     let vote_fin_13_a = ockham::types::Vote {
         view: 13,
         block_hash: b13_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: alice_pk.clone(),
-        signature: sign(&alice_sk, &b13_hash.0),
+        signature: sign(
+            &alice_sk,
+            &vote_message(VoteType::Finalize, 13, &b13_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(vote_fin_13_a).unwrap();
 
     let vote_fin_13_b = ockham::types::Vote {
         view: 13,
         block_hash: b13_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: bob_pk.clone(),
-        signature: sign(&bob_sk, &b13_hash.0),
+        signature: sign(
+            &bob_sk,
+            &vote_message(VoteType::Finalize, 13, &b13_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(vote_fin_13_b).unwrap(); // Should trigger finalize
 
@@ -341,7 +403,9 @@ fn test_delayed_staking_lifecycle() {
         assert_eq!(state.exiting_validators[0].1, 23);
         println!("Bob Exiting until 23");
 
-        let stake = state.stakes.get(&bob_addr).cloned().unwrap_or_default();
+        let stake = storage
+            .get_storage(&staking::STAKING_ADDRESS, &staking::stake_slot(bob_addr))
+            .unwrap();
         println!("DEBUG: Bob Stake after Unstake: {}", stake);
         assert_eq!(stake, U256::from(2000u64));
     }
@@ -352,18 +416,26 @@ fn test_delayed_staking_lifecycle() {
 
     // Propose B23 extending B13.
     // QC for B13 needs Alice+Bob.
-    let s13_a = sign(&alice_sk, &b13_hash.0);
-    let s13_b = sign(&bob_sk, &b13_hash.0);
+    let s13_a = sign(
+        &alice_sk,
+        &vote_message(VoteType::Notarize, 13, &b13_hash, DEFAULT_CHAIN_ID),
+    );
+    let s13_b = sign(
+        &bob_sk,
+        &vote_message(VoteType::Notarize, 13, &b13_hash, DEFAULT_CHAIN_ID),
+    );
     let agg13 = ockham::crypto::aggregate(&[s13_a, s13_b]).unwrap();
     let qc13 = QuorumCertificate {
         view: 13,
         block_hash: b13_hash,
         signature: agg13,
-        signers: vec![alice_pk.clone(), bob_pk.clone()],
-    }; // Order? Sorted usually.
+        epoch: 1,
+        signers: SignerBitmap::from_signers(&new_committee, [&alice_pk, &bob_pk]).unwrap(),
+    };
 
+    // Bob still leads View 23 (23 % 2)
     let mut b23 = Block::new(
-        alice_pk.clone(),
+        bob_pk.clone(),
         23,
         b13_hash,
         qc13,
@@ -375,7 +447,9 @@ fn test_delayed_staking_lifecycle() {
         vec![],
         hash_data(&new_committee),
     );
+    b23.height = 5;
     prepare_block(&mut b23, storage.clone());
+    b23.sign(&bob_sk);
     let b23_hash = hash_data(&b23);
 
     alice.on_proposal(b23.clone()).unwrap();
@@ -383,17 +457,23 @@ fn test_delayed_staking_lifecycle() {
     let v23a = ockham::types::Vote {
         view: 23,
         block_hash: b23_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: alice_pk.clone(),
-        signature: sign(&alice_sk, &b23_hash.0),
+        signature: sign(
+            &alice_sk,
+            &vote_message(VoteType::Finalize, 23, &b23_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(v23a).unwrap();
     let v23b = ockham::types::Vote {
         view: 23,
         block_hash: b23_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: bob_pk.clone(),
-        signature: sign(&bob_sk, &b23_hash.0),
+        signature: sign(
+            &bob_sk,
+            &vote_message(VoteType::Finalize, 23, &b23_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(v23b).unwrap();
 
@@ -403,16 +483,54 @@ fn test_delayed_staking_lifecycle() {
         println!("Bob Removed");
 
         // Stake should still be there
-        let stake = state.stakes.get(&bob_addr).cloned().unwrap_or_default();
+        let stake = storage
+            .get_storage(&staking::STAKING_ADDRESS, &staking::stake_slot(bob_addr))
+            .unwrap();
         println!("DEBUG: Bob Stake after Removal: {}", stake);
         assert_eq!(stake, U256::from(2000u64));
     }
 
+    // Hand-over back to [Alice] needs both outgoing members: pending until Bob signs
+    {
+        assert!(storage.get_committee_transition(2).unwrap().is_none());
+        let commitment = ockham::types::CommitteeTransition::new(
+            2,
+            23,
+            b23_hash,
+            &new_committee,
+            committee.clone(),
+        )
+        .commitment();
+        assert!(alice.pending_transitions.contains_key(&commitment));
+
+        let handover_bob = ockham::types::Vote {
+            view: 23,
+            block_hash: commitment,
+            vote_type: VoteType::Handover,
+            author: bob_pk.clone(),
+            signature: sign(&bob_sk, &commitment.0),
+        };
+        alice.on_vote(handover_bob).unwrap();
+
+        let transition = storage.get_committee_transition(2).unwrap().unwrap();
+        assert_eq!(transition.signers.len(), 2);
+        assert!(alice.pending_transitions.is_empty());
+
+        let chain = vec![
+            storage.get_committee_transition(1).unwrap().unwrap(),
+            transition,
+        ];
+        assert_eq!(
+            ockham::types::CommitteeTransition::verify_chain(&committee, &chain),
+            Some(committee.clone())
+        );
+    }
+
     // Withdraw (Block 24)
     println!("--- Bob Withdrawing ---");
     let withdraw_call = hex::decode("3ccfd60b").unwrap();
     let mut tx_withdraw = Transaction {
-        chain_id: 1,
+        chain_id: 1337,
         nonce: 2,
         max_priority_fee_per_gas: U256::ZERO,
         max_fee_per_gas: U256::ZERO,
@@ -430,14 +548,21 @@ fn test_delayed_staking_lifecycle() {
 
     // B24. Committee is just Alice again.
     // QC for B23 (Alice+Bob).
-    let s23a = sign(&alice_sk, &b23_hash.0);
-    let s23b = sign(&bob_sk, &b23_hash.0);
+    let s23a = sign(
+        &alice_sk,
+        &vote_message(VoteType::Notarize, 23, &b23_hash, DEFAULT_CHAIN_ID),
+    );
+    let s23b = sign(
+        &bob_sk,
+        &vote_message(VoteType::Notarize, 23, &b23_hash, DEFAULT_CHAIN_ID),
+    );
     let agg23 = ockham::crypto::aggregate(&[s23a, s23b]).unwrap();
     let qc23 = QuorumCertificate {
         view: 23,
         block_hash: b23_hash,
         signature: agg23,
-        signers: vec![alice_pk.clone(), bob_pk.clone()],
+        epoch: 1,
+        signers: SignerBitmap::from_signers(&new_committee, [&alice_pk, &bob_pk]).unwrap(),
     };
 
     let mut b24 = Block::new(
@@ -453,7 +578,9 @@ fn test_delayed_staking_lifecycle() {
         vec![],
         hash_data(&committee),
     );
+    b24.height = 6;
     prepare_block(&mut b24, storage.clone());
+    b24.sign(&alice_sk);
     let b24_hash = hash_data(&b24);
 
     alice.on_proposal(b24.clone()).unwrap();
@@ -461,9 +588,12 @@ fn test_delayed_staking_lifecycle() {
     let v24 = ockham::types::Vote {
         view: 24,
         block_hash: b24_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: alice_pk.clone(),
-        signature: sign(&alice_sk, &b24_hash.0),
+        signature: sign(
+            &alice_sk,
+            &vote_message(VoteType::Finalize, 24, &b24_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     alice.on_vote(v24).unwrap();
 
@@ -0,0 +1,120 @@
+use jsonrpsee::server::Server;
+use ockham::client::OckhamClient;
+use ockham::crypto::{Hash, generate_keypair_from_id, hash_data};
+use ockham::rpc::{OckhamRpcImpl, OckhamRpcServer};
+use ockham::storage::{AccountInfo, ConsensusState, MemStorage, Storage};
+use ockham::tx_pool::TxPool;
+use ockham::types::{Block, Bytes, INITIAL_BASE_FEE, QuorumCertificate, U256};
+use ockham::vm::Executor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Init code returning the runtime code `PUSH1 42 PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN`.
+const DEPLOY_CODE: &str = "600a600c600039600a6000f3602a60005260206000f3";
+
+/// Execute the pooled transactions in a block on top of `parent`, as the latest block.
+fn produce_block(storage: &Arc<MemStorage>, pool: &TxPool, executor: &Executor, parent: Hash) {
+    let payload = pool.get_transactions_for_block(
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        U256::from(INITIAL_BASE_FEE),
+    );
+    let (pk, _) = generate_keypair_from_id(1);
+    let mut block = Block::new(
+        pk,
+        1,
+        parent,
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        payload,
+        U256::from(INITIAL_BASE_FEE),
+        0,
+        vec![],
+        Hash::default(),
+    );
+    block.height = 1;
+    executor.execute_block(&mut block).unwrap();
+    pool.remove_transactions(&block.payload);
+    storage.save_block(&block).unwrap();
+    storage
+        .save_consensus_state(&ConsensusState {
+            view: 2,
+            preferred_block: hash_data(&block),
+            preferred_view: 1,
+            ..Default::default()
+        })
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_deploy_then_call_contract() {
+    let storage = Arc::new(MemStorage::new());
+    let (_, sk) = generate_keypair_from_id(0);
+    let deployer = OckhamClient::address_of(&sk);
+    storage
+        .save_account(
+            &deployer,
+            &AccountInfo {
+                nonce: 0,
+                balance: U256::from(10u64).pow(U256::from(18u64)),
+                code_hash: Hash(ockham::types::keccak256([]).into()),
+            },
+        )
+        .unwrap();
+
+    let state = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = Executor::new(state, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let pool = Arc::new(TxPool::new(storage.clone()));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        pool.clone(),
+        executor.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+    let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", server.local_addr().unwrap());
+    let handle = server.start(rpc.into_rpc());
+    let client = OckhamClient::new(&url).unwrap();
+
+    // The deployment waits for its receipt while a block is produced from the pool
+    let deploy = client.deploy_contract_and_wait(
+        Bytes::from(hex::decode(DEPLOY_CODE).unwrap()),
+        Bytes::new(),
+        &sk,
+        Duration::from_secs(10),
+    );
+    let produce = async {
+        while pool.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        produce_block(&storage, &pool, &executor, Hash::default());
+    };
+    let (address, ()) = tokio::join!(deploy, produce);
+    let address = address.unwrap();
+    assert_eq!(address, deployer.create(0));
+
+    // The receipt stores the address, and the contract answers calls
+    let receipts = storage
+        .get_receipts(
+            &storage
+                .get_consensus_state()
+                .unwrap()
+                .unwrap()
+                .preferred_block,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(receipts[0].contract_address, Some(address));
+    let output = client
+        .call_contract(address, [0; 4], Bytes::new())
+        .await
+        .unwrap();
+    assert_eq!(U256::from_be_slice(&output), U256::from(42));
+
+    handle.stop().unwrap();
+}
@@ -0,0 +1,55 @@
+use ockham::client::OckhamClient;
+use ockham::crypto::generate_keypair_from_id;
+use ockham::devnet::{Devnet, DevnetConfig};
+use ockham::storage::Storage;
+use ockham::types::{Address, Bytes, U256};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_devnet_finalizes_and_serves_rpc() {
+    let mut devnet = Devnet::start(DevnetConfig {
+        validators: 4,
+        rpc_port: 0,
+        tick: Duration::from_millis(10),
+        view_timeout: Duration::from_secs(1),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(devnet.rpc_addrs().len(), 4);
+
+    // The committee finalizes on its own
+    assert!(
+        devnet
+            .run_until(Duration::from_secs(20), |net| net.last_finalized() >= 3)
+            .await
+    );
+    let client = OckhamClient::new(&format!("http://{}", devnet.rpc_addrs()[2])).unwrap();
+    assert!(client.get_latest_block().await.unwrap().is_some());
+
+    // A transaction sent to one validator is gossiped to the others and finalized
+    let (_, key) = generate_keypair_from_id(0);
+    let client = OckhamClient::new(&format!("http://{}", devnet.rpc_addrs()[1])).unwrap();
+    let (sent, _) = tokio::join!(
+        client.send_transaction(None, Some(Address::ZERO), U256::from(1), Bytes::new(), &key),
+        devnet.run_until(Duration::from_millis(200), |_| false)
+    );
+    let tx_hash = sent.unwrap();
+    assert!(
+        devnet
+            .run_until(Duration::from_secs(20), |net| {
+                let storage = net.storage(3);
+                net.finalized().values().any(|hash| {
+                    storage.get_block(hash).unwrap().is_some_and(|block| {
+                        block
+                            .payload
+                            .iter()
+                            .any(|tx| ockham::crypto::hash_data(tx) == tx_hash)
+                    })
+                })
+            })
+            .await
+    );
+
+    devnet.stop().await;
+}
@@ -0,0 +1,158 @@
+use ockham::crypto::{PrivateKey, PublicKey, Signature, aggregate, generate_keypair_from_id, sign};
+use ockham::storage::Storage;
+use ockham::system_contracts::encrypted_mempool::{
+    self, ENCRYPTED_MEMPOOL_ADDRESS, SUBMIT_SELECTOR,
+};
+use ockham::system_contracts::selector;
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::{
+    Address, Bytes, DEFAULT_CHAIN_ID, DecryptionKey, DecryptionShare, EncryptedTransaction,
+    INITIAL_BASE_FEE, Transaction, U256,
+};
+
+fn make_tx(
+    key: &(PublicKey, PrivateKey),
+    nonce: u64,
+    to: Option<Address>,
+    value: u64,
+    data: Bytes,
+) -> Transaction {
+    let mut tx = Transaction {
+        chain_id: DEFAULT_CHAIN_ID,
+        nonce,
+        max_priority_fee_per_gas: U256::from(1_000_000u64),
+        max_fee_per_gas: U256::from(4 * INITIAL_BASE_FEE),
+        gas_limit: 1_000_000,
+        to,
+        value: U256::from(value),
+        data,
+        access_list: vec![],
+        public_key: key.0.clone(),
+        signature: Signature::default(),
+    };
+    tx.signature = sign(&key.1, &tx.sighash().0);
+    tx
+}
+
+fn share(key: &(PublicKey, PrivateKey), envelope: &EncryptedTransaction) -> DecryptionShare {
+    let id = envelope.id();
+    DecryptionShare {
+        id,
+        author: key.0.clone(),
+        share: sign(&key.1, &id.0),
+    }
+}
+
+#[test]
+fn test_decrypt_with_committee_shares() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let tx = make_tx(&keys[0], 0, Some(Address::repeat_byte(7)), 1, Bytes::new());
+
+    let envelope = EncryptedTransaction::encrypt(&tx, &committee).unwrap();
+    assert!(!envelope.ciphertext.is_empty());
+    let shares: Vec<DecryptionShare> = keys.iter().map(|k| share(k, &envelope)).collect();
+    assert!(shares.iter().all(|s| s.verify()));
+
+    // Every member's share is needed
+    let signatures: Vec<Signature> = shares.iter().map(|s| s.share.clone()).collect();
+    let key = aggregate(&signatures).unwrap();
+    assert_eq!(envelope.decrypt(&key), Some(tx.clone()));
+    let partial = aggregate(&signatures[..3]).unwrap();
+    assert_eq!(envelope.decrypt(&partial), None);
+
+    let revealed = DecryptionKey {
+        envelope: envelope.clone(),
+        key,
+    };
+    assert_eq!(
+        encrypted_mempool::open(&committee, &revealed),
+        Ok(tx.clone())
+    );
+    assert!(encrypted_mempool::open(&committee[..3], &revealed).is_err());
+
+    // Tampered ciphertexts fail the MAC
+    let mut tampered = revealed.clone();
+    let mut ciphertext = tampered.envelope.ciphertext.to_vec();
+    ciphertext[0] ^= 1;
+    tampered.envelope.ciphertext = ciphertext.into();
+    assert_eq!(tampered.envelope.decrypt(&tampered.key), None);
+
+    // A share signed by someone else does not verify
+    let mut forged = share(&keys[1], &envelope);
+    forged.author = keys[2].0.clone();
+    assert!(!forged.verify());
+
+    // Each encryption uses a fresh ephemeral key
+    let again = EncryptedTransaction::encrypt(&tx, &committee).unwrap();
+    assert_ne!(again.id(), envelope.id());
+}
+
+#[test]
+fn test_submit_call_data() {
+    assert_eq!(SUBMIT_SELECTOR, selector("submit(bytes)"));
+
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let inner = make_tx(&keys[1], 0, None, 0, Bytes::new());
+    let envelope = EncryptedTransaction::encrypt(&inner, &committee).unwrap();
+    let submit = make_tx(
+        &keys[0],
+        0,
+        Some(ENCRYPTED_MEMPOOL_ADDRESS),
+        0,
+        encrypted_mempool::submit_data(&envelope),
+    );
+    assert_eq!(
+        encrypted_mempool::submitted_envelope(&submit),
+        Some(envelope)
+    );
+    assert_eq!(encrypted_mempool::submitted_envelope(&inner), None);
+}
+
+#[test]
+fn test_simulation_reveals_encrypted_transaction() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    for i in 0..net.len() {
+        let storage = net.storage(i);
+        let mut state = storage.get_consensus_state().unwrap().unwrap();
+        state.params.encrypted_mempool = true;
+        storage.save_consensus_state(&state).unwrap();
+    }
+
+    // The genesis account submits a transfer encrypted to the committee
+    let genesis = generate_keypair_from_id(0);
+    let recipient = Address::repeat_byte(0x42);
+    let committee = net.node(0).unwrap().committee.clone();
+    let inner = make_tx(&genesis, 1, Some(recipient), 5, Bytes::new());
+    let envelope = EncryptedTransaction::encrypt(&inner, &committee).unwrap();
+    let submit = make_tx(
+        &genesis,
+        0,
+        Some(ENCRYPTED_MEMPOOL_ADDRESS),
+        0,
+        encrypted_mempool::submit_data(&envelope),
+    );
+    for i in 0..net.len() {
+        net.node(i)
+            .unwrap()
+            .tx_pool
+            .add_transaction(submit.clone())
+            .unwrap();
+    }
+
+    let balance = |net: &SimNetwork| {
+        net.storage(0)
+            .get_account(&recipient)
+            .unwrap()
+            .map(|account| account.balance)
+            .unwrap_or_default()
+    };
+    assert!(net.run_until(120_000, |net| balance(net) == U256::from(5)));
+
+    // Nodes forget the ciphertext once a notarized block reveals it
+    let id = envelope.id();
+    assert!(net.run_until(60_000, |net| {
+        (0..net.len()).all(|i| !net.node(i).unwrap().ciphertexts.contains_key(&id))
+    }));
+}
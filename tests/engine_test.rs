@@ -0,0 +1,151 @@
+use ockham::consensus::{ConsensusAction, SimplexState};
+use ockham::crypto::{Hash, PublicKey, generate_keypair_from_id, hash_data};
+use ockham::engine::ExecutionEngine;
+use ockham::state::{StateManager, StateWitness};
+use ockham::storage::{ChainHead, MemStorage, Storage};
+use ockham::types::{Block, VoteType};
+use ockham::vm::{ExecutionError, Executor};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Another backend: the built-in executor, counting what consensus asks of it.
+struct CountingEngine {
+    inner: Executor,
+    executions: Arc<AtomicUsize>,
+}
+
+impl ExecutionEngine for CountingEngine {
+    fn execute_and_validate(
+        &self,
+        block: &mut Block,
+        parent_root: Hash,
+        witness: Option<&StateWitness>,
+    ) -> Result<Option<StateWitness>, ExecutionError> {
+        self.executions.fetch_add(1, Ordering::SeqCst);
+        self.inner.execute_and_validate(block, parent_root, witness)
+    }
+
+    fn commit(&self, block: &mut Block) -> Result<Hash, ExecutionError> {
+        self.inner.commit(block)
+    }
+
+    fn fork_choice_updated(&self, head: &ChainHead) {
+        self.inner.fork_choice_updated(head)
+    }
+
+    fn state_root(&self) -> Hash {
+        self.inner.state_root()
+    }
+}
+
+fn make_node(me: u64, executions: &Arc<AtomicUsize>) -> (SimplexState, Arc<MemStorage>, Executor) {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(MemStorage::new());
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let executor = Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let engine = CountingEngine {
+        inner: executor.clone(),
+        executions: executions.clone(),
+    };
+    let (pk, sk) = keys[me as usize].clone();
+    let node = SimplexState::new(
+        pk,
+        sk,
+        committee,
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        engine,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    (node, storage, executor)
+}
+
+fn propose(leader: &mut SimplexState) -> Block {
+    leader
+        .try_propose()
+        .unwrap()
+        .into_iter()
+        .find_map(|a| match a {
+            ConsensusAction::BroadcastBlock(b) => Some(b),
+            _ => None,
+        })
+        .expect("Leader should propose")
+}
+
+#[test]
+fn test_consensus_runs_on_custom_engine() {
+    let executions = Arc::new(AtomicUsize::new(0));
+
+    // The leader executes its proposal through the engine
+    let (mut leader, _, _) = make_node(1, &executions);
+    let block = propose(&mut leader);
+    assert_eq!(executions.load(Ordering::SeqCst), 1);
+
+    // A validator re-executes it through its engine before voting
+    let (mut validator, storage, _) = make_node(0, &executions);
+    let actions = validator.on_proposal(block.clone()).unwrap();
+    assert_eq!(executions.load(Ordering::SeqCst), 2);
+    assert!(actions.iter().any(|a| matches!(
+        a,
+        ConsensusAction::BroadcastVote(v)
+            if v.block_hash == hash_data(&block) && v.vote_type == VoteType::Notarize
+    )));
+    assert!(storage.get_witness(&hash_data(&block)).unwrap().is_some());
+}
+
+#[test]
+fn test_validated_execution_is_reused() {
+    let executions = Arc::new(AtomicUsize::new(0));
+    let (mut leader, _, _) = make_node(1, &executions);
+    let block = propose(&mut leader);
+    let block_hash = hash_data(&block);
+
+    let (mut validator, storage, executor) = make_node(0, &executions);
+    validator.on_proposal(block.clone()).unwrap();
+    let outcome = executor
+        .execution_cache
+        .outcome(&block_hash)
+        .expect("Validation should be cached");
+    assert_eq!(outcome.gas_used, block.gas_used);
+
+    // The same block again (e.g. through sync) is not executed again
+    let parent_root = storage
+        .get_block(&block.parent_hash)
+        .unwrap()
+        .map_or(Hash::default(), |parent| parent.state_root);
+    let mut again = block.clone();
+    assert!(
+        executor
+            .execute_and_validate(&mut again, parent_root, None)
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(hash_data(&again), block_hash);
+    assert_eq!(executor.execution_cache.metrics().validation_hits, 1);
+
+    // Commit writes the cached outcome, as executing it on a node that never saw the
+    // block does
+    let root = executor.commit(&mut block.clone()).unwrap();
+    let (_, fresh_storage, fresh) = make_node(2, &executions);
+    assert_eq!(fresh.commit(&mut block.clone()).unwrap(), root);
+    assert_eq!(root, block.state_root);
+
+    let metrics = executor.execution_cache.metrics();
+    assert_eq!((metrics.commit_hits, metrics.commit_misses), (1, 0));
+    assert_eq!(metrics.entries, 0);
+    assert_eq!(fresh.execution_cache.metrics().commit_misses, 1);
+    assert_eq!(
+        storage.get_receipts(&block_hash).unwrap(),
+        Some(outcome.receipts)
+    );
+    assert_eq!(
+        storage.get_receipts(&block_hash).unwrap(),
+        fresh_storage.get_receipts(&block_hash).unwrap()
+    );
+    assert_eq!(
+        storage.get_reward(&block.author).unwrap(),
+        fresh_storage.get_reward(&block.author).unwrap()
+    );
+    assert!(storage.get_reward(&block.author).unwrap() > ockham::types::U256::ZERO);
+}
@@ -0,0 +1,140 @@
+use ockham::channels::{ChannelMetrics, ChannelStats};
+use ockham::crypto::{Hash, Signature, generate_keypair_from_id, sign};
+use ockham::network::{EventPriority, EventQueueConfig, NetworkEvent, event_channel};
+use ockham::types::{Address, DEFAULT_CHAIN_ID, Transaction, U256, Vote, VoteType, vote_message};
+
+fn make_tx(nonce: u64) -> Transaction {
+    let (pk, sk) = generate_keypair_from_id(1);
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::from(10_000_000u64),
+        gas_limit: 21000,
+        to: Some(Address::ZERO),
+        value: U256::ZERO,
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: pk,
+        signature: Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    tx
+}
+
+fn make_vote(view: u64) -> Vote {
+    let (pk, sk) = generate_keypair_from_id(0);
+    let block_hash = Hash([view as u8; 32]);
+    Vote {
+        view,
+        block_hash,
+        vote_type: VoteType::Notarize,
+        author: pk,
+        signature: sign(
+            &sk,
+            &vote_message(VoteType::Notarize, view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    }
+}
+
+#[tokio::test]
+async fn test_votes_overtake_transactions() {
+    let metrics = ChannelMetrics::new();
+    let (sender, mut receiver) = event_channel(
+        EventQueueConfig {
+            transactions: 2,
+            ..Default::default()
+        },
+        metrics.clone(),
+    );
+
+    // A transaction flood fills its queue; the excess is dropped, not waited on
+    for nonce in 0..5 {
+        sender
+            .send(NetworkEvent::TransactionReceived(make_tx(nonce)))
+            .await;
+    }
+    assert_eq!(sender.dropped_transactions(), 3);
+    sender
+        .send(NetworkEvent::PeerConnected("peer".into()))
+        .await;
+    sender.send(NetworkEvent::VoteReceived(make_vote(1))).await;
+    sender.send(NetworkEvent::VoteReceived(make_vote(2))).await;
+
+    // Votes come first (in arrival order), then sync, then what is left of the flood
+    let mut order = vec![];
+    for _ in 0..5 {
+        let event = receiver.recv().await.unwrap();
+        if let NetworkEvent::VoteReceived(vote) = &event {
+            order.push((event.priority(), vote.view));
+        } else if let NetworkEvent::TransactionReceived(tx) = &event {
+            order.push((event.priority(), tx.nonce));
+        } else {
+            order.push((event.priority(), 0));
+        }
+    }
+    assert_eq!(
+        order,
+        vec![
+            (EventPriority::Votes, 1),
+            (EventPriority::Votes, 2),
+            (EventPriority::Sync, 0),
+            (EventPriority::Transactions, 0),
+            (EventPriority::Transactions, 1),
+        ]
+    );
+    assert_eq!(receiver.dropped_transactions(), 3);
+    assert_eq!(metrics.stats(EventPriority::Votes.channel()).sent, 2);
+    assert_eq!(metrics.stats(EventPriority::Transactions.channel()).sent, 2);
+
+    // The receiver ends once the network task is gone
+    drop(sender);
+    assert!(receiver.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn test_channel_backpressure() {
+    let metrics = ChannelMetrics::new();
+    let (sender, mut receiver) = tokio::sync::mpsc::channel::<u64>(1);
+
+    // A critical send waits for room instead of losing the message
+    assert!(metrics.send("critical", &sender, 1).await);
+    let waiting = {
+        let metrics = metrics.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move { metrics.send("critical", &sender, 2).await })
+    };
+    tokio::task::yield_now().await;
+    assert_eq!(receiver.recv().await, Some(1));
+    assert!(waiting.await.unwrap());
+    assert_eq!(receiver.recv().await, Some(2));
+
+    // A lossy send drops what does not fit
+    assert!(metrics.try_send("lossy", &sender, 3));
+    assert!(!metrics.try_send("lossy", &sender, 4));
+    assert_eq!(receiver.recv().await, Some(3));
+
+    // Sends to a closed channel are counted as lost
+    drop(receiver);
+    assert!(!metrics.send("critical", &sender, 5).await);
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(
+        snapshot["critical"],
+        ChannelStats {
+            sent: 2,
+            waited: 1,
+            dropped: 0,
+            closed: 1,
+        }
+    );
+    assert_eq!(
+        snapshot["lossy"],
+        ChannelStats {
+            sent: 1,
+            waited: 0,
+            dropped: 1,
+            closed: 0,
+        }
+    );
+}
@@ -0,0 +1,171 @@
+use jsonrpsee::rpc_params;
+use ockham::consensus::{ConsensusAction, SimplexState};
+use ockham::crypto::{Hash, PrivateKey, PublicKey, hash_data, sign};
+use ockham::events::{ConsensusEvent, EventRecord, EventStream, EvidenceKind};
+use ockham::rpc::{OckhamRpcImpl, OckhamRpcServer};
+use ockham::storage::MemStorage;
+use ockham::types::{
+    Block, DEFAULT_BLOCK_GAS_LIMIT, DEFAULT_CHAIN_ID, QuorumCertificate, U256, View, Vote,
+    VoteType, vote_message,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn vote(key: &(PublicKey, PrivateKey), view: View, block_hash: Hash, vote_type: VoteType) -> Vote {
+    Vote {
+        view,
+        block_hash,
+        vote_type,
+        author: key.0.clone(),
+        signature: sign(
+            &key.1,
+            &vote_message(vote_type, view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    }
+}
+
+#[tokio::test]
+async fn test_consensus_events_stream() {
+    // Committee of 4 (threshold 3), observed from node 0
+    let keys: Vec<(PublicKey, PrivateKey)> =
+        (0..4).map(|_| ockham::crypto::generate_keypair()).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, DEFAULT_BLOCK_GAS_LIMIT);
+    let events = EventStream::default();
+    let mut node0 = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee.clone(),
+        storage.clone(),
+        tx_pool.clone(),
+        executor.clone(),
+        DEFAULT_BLOCK_GAS_LIMIT,
+    )
+    .with_events(events.clone());
+
+    // Subscribed over RPC and written to a file before anything happens
+    let (tx_sender, _tx_receiver) = tokio::sync::mpsc::channel(10);
+    let rpc = OckhamRpcImpl::new(
+        storage,
+        tx_pool,
+        executor,
+        DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    )
+    .with_events(events.clone());
+    let module = rpc.into_rpc();
+    let mut subscription = module
+        .subscribe_unbounded("subscribe_consensus_events", rpc_params![])
+        .await
+        .unwrap();
+    let path = std::env::temp_dir().join(format!("ockham_events_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    tokio::spawn(ockham::events::write_jsonl(
+        events.subscribe(),
+        path.clone(),
+    ));
+
+    // View 1 (led by node 1): proposal, QC, finalization
+    let mut b1 = Block::new(
+        keys[1].0.clone(),
+        1,
+        node0.preferred_block,
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        vec![],
+        U256::ZERO,
+        0,
+        vec![],
+        hash_data(&committee),
+    );
+    b1.height = 1;
+    b1.sign(&keys[1].1);
+    let b1_hash = hash_data(&b1);
+    node0.on_proposal(b1).unwrap();
+    let mut finalize_votes = vec![];
+    for key in &keys {
+        for action in node0
+            .on_vote(vote(key, 1, b1_hash, VoteType::Notarize))
+            .unwrap()
+        {
+            if let ConsensusAction::BroadcastVote(v) = action
+                && v.vote_type == VoteType::Finalize
+            {
+                finalize_votes.push(v);
+            }
+        }
+    }
+    for v in finalize_votes {
+        node0.on_vote(v).unwrap();
+    }
+    for key in &keys[1..3] {
+        node0
+            .on_vote(vote(key, 1, b1_hash, VoteType::Finalize))
+            .unwrap();
+    }
+    assert_eq!(node0.finalized_height, 1);
+
+    // View 2: timeout, and node 3 votes for two blocks
+    node0.on_timeout(2).unwrap();
+    node0
+        .on_vote(vote(&keys[3], 2, Hash([1u8; 32]), VoteType::Notarize))
+        .unwrap();
+    node0
+        .on_vote(vote(&keys[3], 2, Hash([2u8; 32]), VoteType::Notarize))
+        .unwrap();
+
+    let expected = vec![
+        ConsensusEvent::ProposalReceived {
+            view: 1,
+            height: 1,
+            block_hash: b1_hash,
+            author: keys[1].0.clone(),
+        },
+        ConsensusEvent::QcFormed {
+            view: 1,
+            block_hash: b1_hash,
+            signers: 3,
+        },
+        ConsensusEvent::ViewStarted { view: 2 },
+        ConsensusEvent::Finalized {
+            view: 1,
+            block_hash: b1_hash,
+        },
+        ConsensusEvent::TimeoutTriggered { view: 2 },
+        ConsensusEvent::EvidenceDetected {
+            view: 2,
+            offender: keys[3].0.clone(),
+            kind: EvidenceKind::DoubleVote,
+        },
+    ];
+
+    // Subscribers receive them in order
+    for event in &expected {
+        let (record, _) = subscription.next::<EventRecord>().await.unwrap().unwrap();
+        assert_eq!(&record.event, event);
+    }
+
+    // And so does the file, one JSON record per line
+    let mut lines = vec![];
+    for _ in 0..100 {
+        lines = std::fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str::<EventRecord>(line).ok())
+            .map(|record| record.event)
+            .collect::<Vec<_>>();
+        if lines.len() >= expected.len() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(lines, expected);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,310 @@
+use ockham::chain_spec::ChainSpec;
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair, hash_data, sign};
+use ockham::storage::{AccountInfo, MemStorage, Storage};
+use ockham::types::{Address, Block, Bytes, Hardfork, QuorumCertificate, Transaction, U256};
+use ockham::vm::Executor;
+use std::sync::{Arc, Mutex};
+
+// Stores the block context in slots 0..=6:
+// NUMBER, TIMESTAMP, COINBASE, PREVRANDAO, CHAINID, BASEFEE, GASLIMIT.
+const BLOCK_ENV_CODE: &str = "4360005542600155416002554460035546600455486005554560065500";
+
+// Stores BLOCKHASH(NUMBER - 1), BLOCKHASH(NUMBER - 2) and BLOCKHASH(NUMBER) in slots 0..=2.
+const BLOCK_HASH_CODE: &str = "60014303406000556002430340600155434060025500";
+
+// TSTORE 42 at key 0, TLOAD it back and SSTORE it in slot 0 (EIP-1153, Cancun).
+const TRANSIENT_STORAGE_CODE: &str = "602a60005d60005c60005500";
+
+// SSTORE 1 in slot 0, then REVERT.
+const REVERTING_CODE: &str = "600160005560006000fd";
+
+// SLOAD slot 0.
+const SLOAD_CODE: &str = "6000545000";
+
+const CONTRACT: Address = Address::with_last_byte(0xc0);
+
+fn setup(code: &str, hardfork: Hardfork) -> (Arc<MemStorage>, Executor, PublicKey, PrivateKey) {
+    let storage = Arc::new(MemStorage::new());
+    let code = Bytes::from(hex::decode(code).unwrap());
+    let code_hash = Hash(ockham::types::keccak256(&code).into());
+    storage.save_code(&code_hash, &code).unwrap();
+    storage
+        .save_account(
+            &CONTRACT,
+            &AccountInfo {
+                nonce: 1,
+                balance: U256::ZERO,
+                code_hash,
+            },
+        )
+        .unwrap();
+
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT)
+        .with_hardfork(hardfork);
+    let (pk, sk) = generate_keypair();
+    (storage, executor, pk, sk)
+}
+
+fn call_block(pk: &PublicKey, sk: &PrivateKey, view: u64) -> Block {
+    let mut tx = Transaction {
+        chain_id: ockham::types::DEFAULT_CHAIN_ID,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::ZERO,
+        gas_limit: 1_000_000,
+        to: Some(CONTRACT),
+        value: U256::ZERO,
+        data: Bytes::new(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(sk, &tx.sighash().0);
+    Block::new(
+        pk.clone(),
+        view,
+        Hash::default(),
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        vec![tx],
+        U256::ZERO,
+        0,
+        vec![],
+        Hash::default(),
+    )
+}
+
+fn slot(storage: &MemStorage, index: u64) -> U256 {
+    storage.get_storage(&CONTRACT, &U256::from(index)).unwrap()
+}
+
+#[test]
+fn test_evm_block_environment() {
+    let (storage, executor, pk, sk) = setup(BLOCK_ENV_CODE, Hardfork::Shanghai);
+    let mut block = call_block(&pk, &sk, 7);
+    block.height = 5;
+    block.timestamp = 1_700_000_000;
+    block.metadata.fee_recipient = Address::with_last_byte(0xcc);
+    executor.execute_block(&mut block).unwrap();
+
+    assert_eq!(slot(&storage, 0), U256::from(5));
+    assert_eq!(slot(&storage, 1), U256::from(1_700_000_000u64));
+    assert_eq!(
+        slot(&storage, 2),
+        U256::from_be_slice(Address::with_last_byte(0xcc).as_slice())
+    );
+    // PREVRANDAO is the hash of the justifying QC's aggregate signature
+    assert_eq!(
+        slot(&storage, 3),
+        U256::from_be_bytes(hash_data(&block.justify.signature).0)
+    );
+    assert_eq!(
+        slot(&storage, 4),
+        U256::from(ockham::types::DEFAULT_CHAIN_ID)
+    );
+    assert_eq!(slot(&storage, 5), block.base_fee_per_gas);
+    assert_eq!(
+        slot(&storage, 6),
+        U256::from(ockham::types::DEFAULT_BLOCK_GAS_LIMIT)
+    );
+}
+
+#[test]
+fn test_evm_block_hash() {
+    let (storage, executor, pk, sk) = setup(BLOCK_HASH_CODE, Hardfork::Shanghai);
+
+    // Height 3 is committed (indexed); its child at height 4 is only stored
+    storage.save_block_hash(3, &Hash([3u8; 32])).unwrap();
+    let mut parent = call_block(&pk, &sk, 4);
+    parent.height = 4;
+    parent.parent_hash = Hash([3u8; 32]);
+    storage.save_block(&parent).unwrap();
+
+    let mut block = call_block(&pk, &sk, 5);
+    block.height = 5;
+    block.parent_hash = hash_data(&parent);
+    executor.execute_block(&mut block).unwrap();
+
+    assert_eq!(slot(&storage, 0), U256::from_be_bytes(hash_data(&parent).0));
+    assert_eq!(slot(&storage, 1), U256::from_be_bytes([3u8; 32]));
+    // The current block's own hash is not available
+    assert_eq!(slot(&storage, 2), U256::ZERO);
+    // Executing it indexed its hash for its descendants
+    assert_eq!(storage.get_block_hash(5).unwrap(), Some(hash_data(&block)));
+}
+
+#[test]
+fn test_block_hash_history_window() {
+    let storage = MemStorage::new();
+    for height in 0..=ockham::storage::BLOCK_HASH_HISTORY {
+        storage
+            .save_block_hash(height, &Hash([height as u8; 32]))
+            .unwrap();
+    }
+    assert_eq!(storage.get_block_hash(0).unwrap(), None);
+    assert_eq!(storage.get_block_hash(1).unwrap(), Some(Hash([1u8; 32])));
+}
+
+#[test]
+fn test_evm_hardfork_opcodes() {
+    // Transient storage does not exist before Cancun: the call halts
+    let (storage, executor, pk, sk) = setup(TRANSIENT_STORAGE_CODE, Hardfork::Shanghai);
+    let mut block = call_block(&pk, &sk, 1);
+    executor.execute_block(&mut block).unwrap();
+    let receipts = storage.get_receipts(&hash_data(&block)).unwrap().unwrap();
+    assert_eq!(receipts[0].status, 0);
+    assert_eq!(slot(&storage, 0), U256::ZERO);
+
+    let (storage, executor, pk, sk) = setup(TRANSIENT_STORAGE_CODE, Hardfork::Cancun);
+    let mut block = call_block(&pk, &sk, 1);
+    executor.execute_block(&mut block).unwrap();
+    let receipts = storage.get_receipts(&hash_data(&block)).unwrap().unwrap();
+    assert_eq!(receipts[0].status, 1);
+    assert_eq!(slot(&storage, 0), U256::from(42));
+}
+
+#[test]
+fn test_chain_spec_hardfork() {
+    // Specs written before the field existed run Shanghai
+    let spec =
+        ChainSpec::from_json(r#"{"chain_id": 1337, "view": 0, "validators": [], "accounts": {}}"#)
+            .unwrap();
+    assert_eq!(spec.hardfork, Hardfork::Shanghai);
+
+    let spec = ChainSpec {
+        hardfork: Hardfork::Cancun,
+        ..spec
+    };
+    let json = spec.to_json().unwrap();
+    assert!(json.contains(r#""hardfork": "cancun""#));
+    assert_eq!(ChainSpec::from_json(&json).unwrap(), spec);
+}
+
+#[test]
+fn test_reverted_tx_consumes_nonce_and_gas() {
+    let (storage, executor, pk, sk) = setup(REVERTING_CODE, Hardfork::Shanghai);
+    let mut block = call_block(&pk, &sk, 1);
+    let tx = &mut block.payload[0];
+    tx.max_fee_per_gas = U256::from(1u64);
+    tx.max_priority_fee_per_gas = U256::from(1u64);
+    tx.signature = sign(&sk, &tx.sighash().0);
+    let sender = tx.sender();
+    let funds = U256::from(10_000_000u64);
+    storage
+        .save_account(
+            &sender,
+            &AccountInfo {
+                nonce: 0,
+                balance: funds,
+                code_hash: Hash(ockham::types::keccak256([]).into()),
+            },
+        )
+        .unwrap();
+    let mut replay = block.clone();
+
+    executor.execute_block(&mut block).unwrap();
+    let receipts = storage.get_receipts(&hash_data(&block)).unwrap().unwrap();
+    assert_eq!(receipts[0].status, 0);
+    assert!(receipts[0].gas_used > 0);
+
+    // The write is undone, but the nonce is used and the gas paid for
+    assert_eq!(slot(&storage, 0), U256::ZERO);
+    let account = storage.get_account(&sender).unwrap().unwrap();
+    assert_eq!(account.nonce, 1);
+    assert_eq!(account.balance, funds - U256::from(receipts[0].gas_used));
+
+    // So the same transaction cannot be included again for free
+    replay.view = 2;
+    assert!(executor.execute_block(&mut replay).is_err());
+}
+
+#[test]
+fn test_tx_stateless_validity() {
+    use ockham::types::{TxValidityError, validate_tx_stateless};
+
+    let (storage, executor, pk, sk) = setup(BLOCK_ENV_CODE, Hardfork::Shanghai);
+    let block = call_block(&pk, &sk, 1);
+    let tx = &block.payload[0];
+    assert_eq!(tx.intrinsic_gas(), 21_000);
+    assert_eq!(validate_tx_stateless(tx, Some(U256::ZERO)), Ok(()));
+
+    let mut calldata = tx.clone();
+    calldata.data = Bytes::from(vec![0, 1, 0]);
+    calldata.gas_limit = 21_023;
+    assert_eq!(calldata.intrinsic_gas(), 21_024);
+    assert_eq!(
+        validate_tx_stateless(&calldata, None),
+        Err(TxValidityError::IntrinsicGasTooLow {
+            gas_limit: 21_023,
+            intrinsic: 21_024,
+        })
+    );
+
+    let mut tip = tx.clone();
+    tip.max_priority_fee_per_gas = U256::from(2u64);
+    tip.max_fee_per_gas = U256::from(1u64);
+    assert!(matches!(
+        validate_tx_stateless(&tip, None),
+        Err(TxValidityError::TipAboveMaxFee { .. })
+    ));
+
+    // The fee rule only applies against a base fee; the executor rejects the block
+    assert_eq!(validate_tx_stateless(tx, None), Ok(()));
+    assert!(matches!(
+        validate_tx_stateless(tx, Some(U256::from(7u64))),
+        Err(TxValidityError::FeeBelowBaseFee { .. })
+    ));
+    let mut underpriced = block.clone();
+    underpriced.base_fee_per_gas = U256::from(7u64);
+    assert!(matches!(
+        executor.execute_block(&mut underpriced),
+        Err(ockham::vm::ExecutionError::InvalidTransaction(
+            TxValidityError::FeeBelowBaseFee { .. }
+        ))
+    ));
+    assert_eq!(slot(&storage, 6), U256::ZERO);
+}
+
+#[test]
+fn test_access_list_warms_slots() {
+    use ockham::types::{ACCESS_LIST_ADDRESS_GAS, ACCESS_LIST_STORAGE_KEY_GAS, AccessListItem};
+
+    let gas_used = |access_list: Vec<AccessListItem>| {
+        let (storage, executor, pk, sk) = setup(SLOAD_CODE, Hardfork::Shanghai);
+        let mut block = call_block(&pk, &sk, 1);
+        let tx = &mut block.payload[0];
+        tx.access_list = access_list;
+        tx.signature = sign(&sk, &tx.sighash().0);
+        executor.execute_block(&mut block).unwrap();
+        let receipts = storage.get_receipts(&hash_data(&block)).unwrap().unwrap();
+        assert_eq!(receipts[0].status, 1);
+        receipts[0].gas_used
+    };
+
+    // The declared slot is paid for upfront, then read at the warm price (100, not 2100)
+    let cold = gas_used(vec![]);
+    let warm = gas_used(vec![AccessListItem {
+        address: CONTRACT,
+        storage_keys: vec![U256::ZERO],
+    }]);
+    assert_eq!(
+        warm,
+        cold + ACCESS_LIST_ADDRESS_GAS + ACCESS_LIST_STORAGE_KEY_GAS - 2000
+    );
+
+    // A gas limit covering the call but not the access list is invalid
+    let (_, _, pk, sk) = setup(SLOAD_CODE, Hardfork::Shanghai);
+    let mut tx = call_block(&pk, &sk, 1).payload[0].clone();
+    tx.access_list = vec![AccessListItem {
+        address: CONTRACT,
+        storage_keys: vec![U256::ZERO, U256::from(1u64)],
+    }];
+    assert_eq!(tx.intrinsic_gas(), 21_000 + 2400 + 2 * 1900);
+    tx.gas_limit = 25_000;
+    assert!(ockham::types::validate_tx_stateless(&tx, None).is_err());
+}
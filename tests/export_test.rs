@@ -0,0 +1,152 @@
+use ockham::crypto::{Hash, generate_keypair_from_id, hash_data, sign};
+use ockham::export::{ChainExporter, ExportFormat};
+use ockham::storage::{ConsensusState, MemStorage, Storage};
+use ockham::types::{Address, Block, Bytes, QuorumCertificate, Receipt, Transaction, U256};
+use std::sync::Arc;
+
+fn setup() -> (Arc<MemStorage>, Hash) {
+    let (pk, sk) = generate_keypair_from_id(0);
+    let storage = Arc::new(MemStorage::new());
+
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::from(1u64),
+        max_fee_per_gas: U256::from(100u64),
+        gas_limit: 21000,
+        to: Some(Address::from_slice(&[0x11; 20])),
+        value: U256::from(42u64),
+        data: Bytes::from(vec![1, 2, 3]),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+
+    let block = Block::new(
+        pk,
+        1,
+        Hash::default(),
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        vec![tx],
+        U256::from(10u64),
+        21000,
+        vec![],
+        Hash::default(),
+    );
+    let block_hash = hash_data(&block);
+    storage.save_block(&block).unwrap();
+    storage
+        .save_qc(&QuorumCertificate {
+            view: 1,
+            block_hash,
+            signature: ockham::crypto::Signature::default(),
+            epoch: 0,
+            signers: Default::default(),
+        })
+        .unwrap();
+    storage
+        .save_receipts(
+            &block_hash,
+            &[Receipt {
+                status: 1,
+                cumulative_gas_used: 21000,
+                logs: vec![],
+                logs_bloom: Default::default(),
+                gas_used: 21000,
+                revert_output: Default::default(),
+                contract_address: None,
+            }],
+        )
+        .unwrap();
+    // View 2 timed out (dummy), view 3 is notarized but not finalized
+    storage
+        .save_qc(&QuorumCertificate {
+            view: 2,
+            ..Default::default()
+        })
+        .unwrap();
+    storage
+        .save_consensus_state(&ConsensusState {
+            finalized_height: 2,
+            ..Default::default()
+        })
+        .unwrap();
+    (storage, block_hash)
+}
+
+fn out_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("ockham_export_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_export_csv() {
+    let (storage, block_hash) = setup();
+    let dir = out_dir("csv");
+    let mut exporter = ChainExporter::new(storage.clone(), &dir, ExportFormat::Csv);
+
+    let batch = exporter.export_until(u64::MAX).unwrap().unwrap();
+    assert_eq!((batch.from, batch.to), (0, 2));
+    assert_eq!(batch.blocks.rows.len(), 1);
+    assert_eq!(exporter.next_view(), 3);
+
+    let blocks = std::fs::read_to_string(dir.join("blocks_0000000000_0000000002.csv")).unwrap();
+    let mut lines = blocks.lines();
+    assert!(lines.next().unwrap().starts_with("view,hash,parent_hash"));
+    assert!(
+        lines
+            .next()
+            .unwrap()
+            .starts_with(&format!("1,0x{}", hex::encode(block_hash.0)))
+    );
+    assert!(lines.next().is_none());
+
+    let txs = std::fs::read_to_string(dir.join("transactions_0000000000_0000000002.csv")).unwrap();
+    assert_eq!(txs.lines().count(), 2);
+    assert!(txs.contains(&format!("0x{}", hex::encode([0x11; 20]))));
+
+    let receipts = std::fs::read_to_string(dir.join("receipts_0000000000_0000000002.csv")).unwrap();
+    assert!(
+        receipts
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(",1,21000,21000,0")
+    );
+
+    // Follow: nothing new until more views are finalized
+    assert!(exporter.export_until(u64::MAX).unwrap().is_none());
+    storage
+        .save_consensus_state(&ConsensusState {
+            finalized_height: 3,
+            ..Default::default()
+        })
+        .unwrap();
+    let batch = exporter.export_until(u64::MAX).unwrap().unwrap();
+    assert_eq!((batch.from, batch.to), (3, 3));
+    assert!(batch.blocks.rows.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_export_parquet() {
+    let (storage, _) = setup();
+    let dir = out_dir("parquet");
+    let mut exporter = ChainExporter::new(storage, &dir, ExportFormat::Parquet);
+    exporter.export_until(u64::MAX).unwrap().unwrap();
+
+    for table in ["blocks", "transactions", "receipts"] {
+        let bytes =
+            std::fs::read(dir.join(format!("{}_0000000000_0000000002.parquet", table))).unwrap();
+        assert_eq!(&bytes[..4], b"PAR1");
+    }
+    assert!("PARQUET".parse::<ExportFormat>().is_ok());
+    assert!("json".parse::<ExportFormat>().is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,6 +1,6 @@
 use ockham::consensus::{ConsensusAction, SimplexState};
 use ockham::crypto::{PrivateKey, PublicKey, hash_data};
-use ockham::types::{Address, Bytes, Transaction, U256};
+use ockham::types::{Address, Bytes, DEFAULT_CHAIN_ID, Transaction, U256, VoteType, vote_message};
 
 #[test]
 fn test_state_ommitment_on_finalization() {
@@ -38,7 +38,7 @@ fn test_state_ommitment_on_finalization() {
     // Tx needs to be signed by Node 0 (Sender) and put in Node 1's Pool (Leader View 1)
     let dummy_sig = ockham::crypto::sign(&keys[0].1, &[0u8; 32]);
     let mut tx = Transaction {
-        chain_id: 1,
+        chain_id: 1337,
         nonce: 0,
         max_priority_fee_per_gas: U256::from(1_000_000),
         max_fee_per_gas: U256::from(20_000_000),
@@ -83,9 +83,12 @@ fn test_state_ommitment_on_finalization() {
     let create_vote = |idx: usize| ockham::types::Vote {
         view: 1,
         block_hash: b1_hash,
-        vote_type: ockham::types::VoteType::Finalize,
+        vote_type: VoteType::Finalize,
         author: keys[idx].0.clone(),
-        signature: ockham::crypto::sign(&keys[idx].1, &b1_hash.0),
+        signature: ockham::crypto::sign(
+            &keys[idx].1,
+            &vote_message(VoteType::Finalize, 1, &b1_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     node0.on_vote(create_vote(0)).unwrap();
     node0.on_vote(create_vote(1)).unwrap();
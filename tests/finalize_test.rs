@@ -1,11 +1,11 @@
 #![allow(clippy::collapsible_if)]
 use ockham::consensus::{ConsensusAction, SimplexState};
 use ockham::crypto::{PrivateKey, PublicKey, hash_data};
-use ockham::types::{Block, QuorumCertificate, VoteType};
+use ockham::types::{Block, DEFAULT_CHAIN_ID, QuorumCertificate, VoteType, vote_message};
 
 #[test]
 fn test_explicit_finalization() {
-    let _ = env_logger::builder().is_test(true).try_init();
+    let _ = tracing_subscriber::fmt().with_test_writer().try_init();
 
     // 1. Setup Committee (4 nodes) -> f=1, threshold=3
     let keys: Vec<(PublicKey, PrivateKey)> =
@@ -31,11 +31,11 @@ fn test_explicit_finalization() {
         ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
     );
 
-    // 2. Proposal for View 1
+    // 2. Proposal for View 1 by its leader (node 1)
     let genesis_hash = node0.preferred_block;
     let qc0 = QuorumCertificate::default();
-    let b1 = Block::new(
-        keys[0].0.clone(),
+    let mut b1 = Block::new(
+        keys[1].0.clone(),
         1,
         genesis_hash,
         qc0,
@@ -47,6 +47,8 @@ fn test_explicit_finalization() {
         vec![],
         hash_data(&committee),
     );
+    b1.height = 1;
+    b1.sign(&keys[1].1);
 
     // 3. Node 0 receives Block 1 -> Should Vote (Notarize)
     let actions = node0.on_proposal(b1.clone()).unwrap();
@@ -72,7 +74,10 @@ fn test_explicit_finalization() {
     let votes: Vec<_> = keys
         .iter()
         .map(|(pk, sk)| {
-            let sig = ockham::crypto::sign(sk, &b1_hash.0);
+            let sig = ockham::crypto::sign(
+                sk,
+                &vote_message(VoteType::Notarize, 1, &b1_hash, DEFAULT_CHAIN_ID),
+            );
             ockham::types::Vote {
                 view: 1,
                 block_hash: b1_hash,
@@ -118,7 +123,10 @@ fn test_explicit_finalization() {
 
     // Fabricate finalize votes from Node 1, 2
     for (pk, sk) in keys.iter().skip(1).take(2) {
-        let sig = ockham::crypto::sign(sk, &b1_hash.0);
+        let sig = ockham::crypto::sign(
+            sk,
+            &vote_message(VoteType::Finalize, 1, &b1_hash, DEFAULT_CHAIN_ID),
+        );
         let fvote = ockham::types::Vote {
             view: 1,
             block_hash: b1_hash,
@@ -0,0 +1,194 @@
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair, sign};
+use ockham::precompiles::{Precompile, PrecompileContext, PrecompileError, PrecompileOutput};
+use ockham::state::StateManager;
+use ockham::storage::{ConsensusState, MemStorage, Storage};
+use ockham::system_contracts::governance::{self, GOVERNANCE_ADDRESS, GovernanceContract};
+use ockham::system_contracts::{event_topic, selector, staking};
+use ockham::types::{Block, Bytes, ChainParams, QuorumCertificate, Transaction, U256};
+use std::sync::{Arc, Mutex};
+
+fn transaction(key: &(PublicKey, PrivateKey), data: Bytes, gas_limit: u64) -> Transaction {
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::ZERO,
+        gas_limit,
+        to: Some(GOVERNANCE_ADDRESS),
+        value: U256::ZERO,
+        data,
+        access_list: vec![],
+        public_key: key.0.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&key.1, &tx.sighash().0);
+    tx
+}
+
+fn block(author: &PublicKey, view: u64, payload: Vec<Transaction>) -> Block {
+    Block::new(
+        author.clone(),
+        view,
+        Hash::default(),
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        payload,
+        U256::ZERO,
+        0,
+        vec![],
+        Hash::default(),
+    )
+}
+
+#[test]
+fn test_governance_selectors() {
+    assert_eq!(selector("propose(bytes)"), governance::PROPOSE_SELECTOR);
+    assert_eq!(selector("vote(uint64)"), governance::VOTE_SELECTOR);
+    assert!(ockham::precompiles::PrecompileRegistry::with_defaults().contains(&GOVERNANCE_ADDRESS));
+}
+
+#[test]
+fn test_params_change_by_stake_weighted_vote() {
+    // Committee of three: A holds 5000, B and C 2000 each (more than 6000 approves)
+    let (a, b, c) = (generate_keypair(), generate_keypair(), generate_keypair());
+    let storage = Arc::new(MemStorage::new());
+    let mut state = ConsensusState {
+        committee: vec![a.0.clone(), b.0.clone(), c.0.clone()],
+        ..Default::default()
+    };
+    state.params.epoch_length = 10;
+    storage.save_consensus_state(&state).unwrap();
+    staking::init_genesis(&*storage, &[a.0.clone()], U256::from(5000u64)).unwrap();
+    staking::init_genesis(&*storage, &[b.0.clone(), c.0.clone()], U256::from(2000u64)).unwrap();
+
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let call = |key: &(PublicKey, PrivateKey),
+                view: u64,
+                data: Bytes|
+     -> Result<PrecompileOutput, PrecompileError> {
+        let tx = transaction(key, data, 100_000);
+        let block = block(&key.0, view, vec![]);
+        let mut db = state_manager.lock().unwrap();
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &block,
+            tx: &tx,
+        };
+        GovernanceContract.call(&mut ctx, &tx.data, 100_000)
+    };
+
+    // 1. Proposals come from committee members, with parameters the chain can run with
+    let new_params = ChainParams {
+        block_gas_limit: 20_000_000,
+        epoch_length: 10,
+        ..Default::default()
+    };
+    let outsider = generate_keypair();
+    assert!(call(&outsider, 3, governance::propose_data(&new_params)).is_err());
+    let broken = ChainParams {
+        block_gas_limit: 0,
+        ..new_params.clone()
+    };
+    assert!(call(&a, 3, governance::propose_data(&broken)).is_err());
+
+    let out = call(&a, 3, governance::propose_data(&new_params)).unwrap();
+    assert_eq!(U256::from_be_slice(&out.output), U256::ZERO);
+    assert_eq!(
+        out.logs[0].topics[0],
+        event_topic(governance::PROPOSAL_CREATED_EVENT)
+    );
+    assert_eq!(out.logs.len(), 1); // 5000 of 9000 is not enough
+    assert_eq!(
+        governance::proposal_count(&mut state_manager.lock().unwrap()).unwrap(),
+        1
+    );
+
+    // 2. B's vote brings the stake in favour to 7000: enacted at the next boundary (10)
+    assert!(call(&a, 4, governance::vote_data(0)).is_err()); // Already voted
+    assert!(call(&b, 4, governance::vote_data(1)).is_err()); // Unknown proposal
+    let out = call(&b, 4, governance::vote_data(0)).unwrap();
+    assert_eq!(out.logs[0].topics[0], event_topic(governance::VOTED_EVENT));
+    assert_eq!(
+        out.logs[1].topics[0],
+        event_topic(governance::PROPOSAL_APPROVED_EVENT)
+    );
+    assert_eq!(U256::from_be_slice(&out.logs[1].data), U256::from(10u64));
+    assert!(call(&c, 5, governance::vote_data(0)).is_err());
+
+    // 3. The executor swaps the parameters at the boundary
+    executor.execute_block(&mut block(&a.0, 9, vec![])).unwrap();
+    let params = storage.get_consensus_state().unwrap().unwrap().params;
+    assert_eq!(
+        params.block_gas_limit,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT
+    );
+    executor
+        .execute_block(&mut block(&a.0, 10, vec![]))
+        .unwrap();
+    let state = storage.get_consensus_state().unwrap().unwrap();
+    assert_eq!(state.params, new_params);
+    assert!(state.proposals.is_empty());
+
+    // 4. ... and reads them live: the configured limit no longer applies
+    let big = transaction(&a, Bytes::new(), 25_000_000);
+    assert!(
+        executor
+            .execute_block(&mut block(&a.0, 11, vec![big]))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_unapproved_proposals_expire() {
+    let (a, b) = (generate_keypair(), generate_keypair());
+    let storage = Arc::new(MemStorage::new());
+    storage
+        .save_consensus_state(&ConsensusState {
+            committee: vec![a.0.clone(), b.0.clone()],
+            ..Default::default()
+        })
+        .unwrap();
+    staking::init_genesis(&*storage, &[a.0.clone(), b.0.clone()], U256::from(2000u64)).unwrap();
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // Half of the stake is not a supermajority
+    let tx = transaction(
+        &a,
+        governance::propose_data(&ChainParams::default()),
+        100_000,
+    );
+    let proposal = block(&a.0, 1, vec![]);
+    {
+        let mut db = state_manager.lock().unwrap();
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &proposal,
+            tx: &tx,
+        };
+        let out = GovernanceContract
+            .call(&mut ctx, &tx.data, 100_000)
+            .unwrap();
+        assert_eq!(out.logs.len(), 1);
+    }
+
+    executor
+        .execute_block(&mut block(&a.0, governance::VOTING_PERIOD, vec![]))
+        .unwrap();
+    let state = storage.get_consensus_state().unwrap().unwrap();
+    assert_eq!(state.proposals.len(), 1);
+    executor
+        .execute_block(&mut block(&a.0, 1 + governance::VOTING_PERIOD, vec![]))
+        .unwrap();
+    let state = storage.get_consensus_state().unwrap().unwrap();
+    assert!(state.proposals.is_empty());
+    assert_eq!(state.params, ChainParams::default());
+}
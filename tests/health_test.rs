@@ -0,0 +1,80 @@
+use ockham::health::{HealthConfig, HealthMonitor, HealthReport, serve_http};
+use ockham::storage::MemStorage;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn storage() -> Arc<MemStorage> {
+    Arc::new(MemStorage::new())
+}
+
+#[test]
+fn test_health_report() {
+    let storage = storage();
+    let monitor = HealthMonitor::starting_at(HealthConfig::default(), 1_000);
+
+    // Fresh node: alive (grace period) but not ready without peers
+    let report = monitor.report(storage.as_ref(), 1_010);
+    assert!(report.healthy);
+    assert!(!report.ready);
+    assert_eq!(report.db_error, None);
+
+    monitor.set_peer_count(3);
+    monitor.observe_consensus(4, 2, 1_020);
+    let report = monitor.report(storage.as_ref(), 1_030);
+    assert!(report.ready);
+    assert_eq!(report.last_finalized_at, Some(1_020));
+    assert_eq!((report.current_view, report.finalized_view), (4, 2));
+
+    // Far behind the network: syncing, so alive but not ready
+    monitor.observe_network_view(20);
+    let report = monitor.report(storage.as_ref(), 1_030);
+    assert!(report.syncing);
+    assert!(report.healthy && !report.ready);
+
+    // Caught up, but no finality for too long: stuck
+    monitor.observe_consensus(20, 2, 1_040);
+    let report = monitor.report(storage.as_ref(), 1_020 + 301);
+    assert!(!report.syncing);
+    assert!(!report.healthy && !report.ready);
+
+    // Finality resumes
+    monitor.observe_consensus(21, 19, 1_400);
+    assert!(monitor.report(storage.as_ref(), 1_401).ready);
+}
+
+async fn get(port: u16, path: &str) -> (String, String) {
+    let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap();
+    stream
+        .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    (head.lines().next().unwrap().to_string(), body.to_string())
+}
+
+#[tokio::test]
+async fn test_health_http_probes() {
+    let monitor = HealthMonitor::new(HealthConfig::default());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(serve_http(listener, monitor.clone(), storage()));
+
+    let (status, body) = get(port, "/healthz").await;
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let report: HealthReport = serde_json::from_str(&body).unwrap();
+    assert!(report.healthy);
+
+    // No peers yet
+    let (status, _) = get(port, "/readyz").await;
+    assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+    monitor.set_peer_count(1);
+    let (status, _) = get(port, "/readyz").await;
+    assert_eq!(status, "HTTP/1.1 200 OK");
+
+    let (status, _) = get(port, "/metrics").await;
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+}
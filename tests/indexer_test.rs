@@ -0,0 +1,62 @@
+#![cfg(feature = "indexer")]
+
+use ockham::crypto::{generate_keypair_from_id, hash_data, sign};
+use ockham::indexer::Indexer;
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::{Address, Transaction, U256, View};
+
+#[test]
+fn test_indexer_follows_finalized_chain() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    let (pk, sk) = generate_keypair_from_id(0);
+    let recipient = Address::from([0x11; 20]);
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::from(100_000_000u64),
+        gas_limit: 21000,
+        to: Some(recipient),
+        value: U256::from(100u64),
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: pk,
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    let sender = tx.sender();
+    for i in 0..net.len() {
+        net.node(i)
+            .unwrap()
+            .tx_pool
+            .add_transaction(tx.clone())
+            .unwrap();
+    }
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 4));
+
+    let path = std::env::temp_dir().join(format!("ockham_index_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let indexer = Indexer::new(&path, net.storage(0)).unwrap();
+    assert!(indexer.index_until(View::MAX).unwrap() > 0);
+    assert_eq!(indexer.index_until(View::MAX).unwrap(), 0);
+    assert!(indexer.next_view().unwrap() > 4);
+
+    // The transfer is listed under both parties, with the recipient's new balance
+    let received = indexer.transactions_by_address(&recipient, 10).unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].hash, hash_data(&tx));
+    assert_eq!((received[0].from, received[0].status), (sender, 1));
+    assert_eq!(
+        indexer.transactions_by_address(&sender, 10).unwrap(),
+        received
+    );
+    let history = indexer.balance_history(&recipient, 10).unwrap();
+    assert_eq!(history[0].balance, U256::from(100u64));
+    assert_eq!(history[0].view, received[0].view);
+
+    let blocks = indexer.block_range(0, View::MAX).unwrap();
+    assert!(blocks.iter().any(|b| b.hash == received[0].block_hash));
+    assert!(blocks.windows(2).all(|w| w[0].view < w[1].view));
+    drop(indexer);
+    let _ = std::fs::remove_file(&path);
+}
@@ -33,7 +33,7 @@ async fn test_inject_transactions() {
         let value = U256::from(100);
         let data = Bytes::from(vec![]); // Simple transfer
 
-        match client.send_transaction(i, to, value, data, &sk).await {
+        match client.send_transaction(None, to, value, data, &sk).await {
             Ok(hash) => println!("Sent Tx {}: {:?}", i, hash),
             Err(e) => {
                 eprintln!("Failed to send Tx {}: {:?}", i, e);
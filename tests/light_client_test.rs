@@ -0,0 +1,308 @@
+use ockham::crypto::{
+    Hash, PublicKey, generate_keypair, generate_keypair_from_id, hash_data, sign,
+};
+use ockham::precompiles::{Precompile, PrecompileContext, PrecompileRegistry};
+use ockham::rpc::{OckhamRpcImpl, OckhamRpcServer};
+use ockham::state::StateManager;
+use ockham::storage::{AccountInfo, MemStorage, Storage};
+use ockham::system_contracts::light_client::{
+    self, ClientUpdate, LIGHT_CLIENT_ADDRESS, LightClientContract, TransferProof,
+};
+use ockham::system_contracts::{event_topic, selector};
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::{Address, Block, Bytes, QuorumCertificate, Transaction, U256};
+use std::sync::Arc;
+
+const COUNTERPARTY_CHAIN_ID: u64 = ockham::types::DEFAULT_CHAIN_ID;
+const CHAIN_ID: u64 = 7;
+
+fn make_rpc(storage: Arc<MemStorage>) -> OckhamRpcImpl {
+    let state_manager = Arc::new(std::sync::Mutex::new(StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    )
+}
+
+fn transaction(
+    chain_id: u64,
+    to: Address,
+    value: u64,
+    data: Bytes,
+    id: u64,
+    nonce: u64,
+) -> Transaction {
+    let (pk, sk) = generate_keypair_from_id(id);
+    let mut tx = Transaction {
+        chain_id,
+        nonce,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::from(100_000_000u64),
+        gas_limit: 200_000,
+        to: Some(to),
+        value: U256::from(value),
+        data,
+        access_list: vec![],
+        public_key: pk,
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    tx
+}
+
+#[test]
+fn test_light_client_selectors() {
+    assert_eq!(
+        selector("createClient(uint64,bytes)"),
+        light_client::CREATE_CLIENT_SELECTOR
+    );
+    assert_eq!(
+        selector("updateClient(uint64,bytes)"),
+        light_client::UPDATE_CLIENT_SELECTOR
+    );
+    assert_eq!(
+        selector("rotateCommittee(uint64,bytes)"),
+        light_client::ROTATE_COMMITTEE_SELECTOR
+    );
+    assert_eq!(
+        selector("sendTransfer(uint64,uint64,address)"),
+        light_client::SEND_TRANSFER_SELECTOR
+    );
+    assert_eq!(
+        selector("receiveTransfer(uint64,bytes)"),
+        light_client::RECEIVE_TRANSFER_SELECTOR
+    );
+    assert!(PrecompileRegistry::with_defaults().contains(&LIGHT_CLIENT_ADDRESS));
+}
+
+#[test]
+fn test_transfer_between_chains() {
+    // 1. On the counterparty, node 0's account creates a client of our chain and locks 500
+    // through it for `recipient`
+    let mut net = SimNetwork::new(SimConfig::default());
+    let recipient = Address::with_last_byte(0x77);
+    let our_committee: Vec<PublicKey> = (20..24).map(|i| generate_keypair_from_id(i).0).collect();
+    let create_ours = transaction(
+        COUNTERPARTY_CHAIN_ID,
+        LIGHT_CLIENT_ADDRESS,
+        0,
+        light_client::call_data(
+            light_client::CREATE_CLIENT_SELECTOR,
+            CHAIN_ID,
+            &serde_json::to_vec(&our_committee).unwrap(),
+        ),
+        0,
+        0,
+    );
+    let send = transaction(
+        COUNTERPARTY_CHAIN_ID,
+        LIGHT_CLIENT_ADDRESS,
+        500,
+        light_client::send_transfer_data(0, CHAIN_ID, recipient),
+        0,
+        1,
+    );
+    for i in 0..net.len() {
+        let pool = &net.node(i).unwrap().tx_pool;
+        pool.add_transaction(create_ours.clone()).unwrap();
+        pool.add_transaction(send.clone()).unwrap();
+    }
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 4));
+    let committee: Vec<PublicKey> = net.node(0).unwrap().committee.clone();
+    let counterparty = net.storage(0);
+    let escrowed = counterparty
+        .get_account(&LIGHT_CLIENT_ADDRESS)
+        .unwrap()
+        .unwrap();
+    assert_eq!(escrowed.balance, U256::from(500u64));
+    let mut counterparty_db = StateManager::new(counterparty.clone(), None);
+    assert_eq!(
+        light_client::escrow(&mut counterparty_db, 0).unwrap(),
+        U256::from(500u64)
+    );
+
+    // What a relayer fetches: the finalized block and the proofs of the transfer
+    let tx_proof = make_rpc(counterparty.clone())
+        .get_transaction_proof(hash_data(&send))
+        .unwrap()
+        .expect("The transfer should be finalized");
+    let block_hash = hash_data(&tx_proof.block.header);
+    let receipts = counterparty.get_receipts(&block_hash).unwrap().unwrap();
+    let index = tx_proof.branch.index as usize;
+    let proof = TransferProof {
+        view: tx_proof.block.header.view,
+        transaction: send.clone(),
+        transaction_proof: tx_proof.branch.clone(),
+        receipt: receipts[index].clone(),
+        receipt_proof: ockham::types::receipt_proof(&receipts, index).unwrap(),
+    };
+
+    // 2. Our chain, with 1000 locked through our client of the counterparty (the executor
+    // moves the value into the contract balance)
+    let storage = Arc::new(MemStorage::new());
+    storage
+        .save_account(
+            &LIGHT_CLIENT_ADDRESS,
+            &AccountInfo {
+                nonce: 0,
+                balance: U256::from(1_000u64),
+                code_hash: Hash(ockham::types::keccak256([]).into()),
+            },
+        )
+        .unwrap();
+    let mut db = StateManager::new(storage.clone(), None);
+    let (pk, _) = generate_keypair();
+    let block = Block::new_dummy(pk, 1, Hash::default(), QuorumCertificate::default());
+    let mut call = |data: Bytes, value: u64| {
+        let tx = transaction(CHAIN_ID, LIGHT_CLIENT_ADDRESS, value, data, 9, 0);
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &block,
+            tx: &tx,
+        };
+        LightClientContract.call(&mut ctx, &tx.data, 1_000_000)
+    };
+
+    let create = light_client::call_data(
+        light_client::CREATE_CLIENT_SELECTOR,
+        COUNTERPARTY_CHAIN_ID,
+        &serde_json::to_vec(&committee).unwrap(),
+    );
+    let out = call(create, 0).unwrap();
+    assert_eq!(U256::from_be_slice(&out.output), U256::ZERO);
+    assert_eq!(
+        out.logs[0].topics[0],
+        event_topic(light_client::CLIENT_CREATED_EVENT)
+    );
+    let outbound =
+        light_client::send_transfer_data(0, COUNTERPARTY_CHAIN_ID, Address::with_last_byte(0x42));
+    call(outbound, 1_000).unwrap();
+
+    let receive = light_client::call_data(
+        light_client::RECEIVE_TRANSFER_SELECTOR,
+        0,
+        &serde_json::to_vec(&proof).unwrap(),
+    );
+    // Not before the client verified the block
+    assert!(call(receive.clone(), 0).is_err());
+
+    // 3. Headers are only accepted from the client's committee
+    let update = |committee: Vec<PublicKey>| {
+        light_client::call_data(
+            light_client::UPDATE_CLIENT_SELECTOR,
+            0,
+            &serde_json::to_vec(&ClientUpdate {
+                committee,
+                block: tx_proof.block.clone(),
+            })
+            .unwrap(),
+        )
+    };
+    let others: Vec<PublicKey> = (10..14).map(|i| generate_keypair_from_id(i).0).collect();
+    assert!(call(update(others), 0).is_err());
+    let mut short = tx_proof.block.clone();
+    short.proof.finalization.signers.truncate(2);
+    let forged = light_client::call_data(
+        light_client::UPDATE_CLIENT_SELECTOR,
+        0,
+        &serde_json::to_vec(&ClientUpdate {
+            committee: committee.clone(),
+            block: short,
+        })
+        .unwrap(),
+    );
+    assert!(call(forged, 0).is_err());
+    call(update(committee.clone()), 0).unwrap();
+
+    // A client created by anyone else (here trusting the same committee, so the proof
+    // verifies) cannot release what was locked through ours
+    let rogue = light_client::call_data(
+        light_client::CREATE_CLIENT_SELECTOR,
+        COUNTERPARTY_CHAIN_ID,
+        &serde_json::to_vec(&committee).unwrap(),
+    );
+    call(rogue, 0).unwrap();
+    let rogue_update = light_client::call_data(
+        light_client::UPDATE_CLIENT_SELECTOR,
+        1,
+        &serde_json::to_vec(&ClientUpdate {
+            committee: committee.clone(),
+            block: tx_proof.block.clone(),
+        })
+        .unwrap(),
+    );
+    call(rogue_update, 0).unwrap();
+    let rogue_receive = light_client::call_data(
+        light_client::RECEIVE_TRANSFER_SELECTOR,
+        1,
+        &serde_json::to_vec(&proof).unwrap(),
+    );
+    assert!(call(rogue_receive.clone(), 0).is_err());
+
+    // 4. The transfer is released once, to its recipient
+    let out = call(receive.clone(), 0).unwrap();
+    assert_eq!(
+        out.logs[0].topics[0],
+        event_topic(light_client::TRANSFER_RECEIVED_EVENT)
+    );
+    assert!(call(receive, 0).is_err());
+
+    // A proof for another transaction of the block does not verify
+    let mut tampered = proof.clone();
+    tampered.transaction.value = U256::from(900u64);
+    let tampered = light_client::call_data(
+        light_client::RECEIVE_TRANSFER_SELECTOR,
+        0,
+        &serde_json::to_vec(&tampered).unwrap(),
+    );
+    assert!(call(tampered, 0).is_err());
+
+    // Transfers to this chain are received, not sent
+    assert!(
+        call(
+            light_client::send_transfer_data(0, CHAIN_ID, recipient),
+            500
+        )
+        .is_err()
+    );
+    // Nor locked through a client of another chain
+    let elsewhere = light_client::send_transfer_data(0, 99, recipient);
+    assert!(call(elsewhere, 500).is_err());
+    // Nor released through the rogue client once ours did
+    assert!(call(rogue_receive, 0).is_err());
+    drop(call);
+
+    let roots = light_client::trusted_roots(&mut db, 0, proof.view)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        roots.transactions_root,
+        tx_proof.block.header.transactions_root
+    );
+    assert_eq!(roots.state_root, tx_proof.block.header.state_root);
+    assert_eq!(light_client::latest_view(&mut db, 0).unwrap(), proof.view);
+    assert_eq!(
+        storage.get_account(&recipient).unwrap().unwrap().balance,
+        U256::from(500u64)
+    );
+    assert_eq!(
+        storage
+            .get_account(&LIGHT_CLIENT_ADDRESS)
+            .unwrap()
+            .unwrap()
+            .balance,
+        U256::from(500u64)
+    );
+    assert_eq!(
+        light_client::escrow(&mut db, 0).unwrap(),
+        U256::from(500u64)
+    );
+    assert_eq!(light_client::escrow(&mut db, 1).unwrap(), U256::ZERO);
+}
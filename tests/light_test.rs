@@ -0,0 +1,353 @@
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, hash_data, sign};
+use ockham::light::{AccountProof, FinalityError, LightClient, verify_finality};
+use ockham::rpc::{OckhamRpcImpl, OckhamRpcServer};
+use ockham::state::StateManager;
+use ockham::storage::{AccountInfo, ChainHead, MemStorage, Storage};
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::{
+    Address, Block, DEFAULT_CHAIN_ID, QuorumCertificate, SignerBitmap, SyncMessage, Transaction,
+    U256, Vote, VoteType, vote_message,
+};
+use std::sync::{Arc, Mutex};
+
+fn make_rpc(storage: Arc<MemStorage>) -> OckhamRpcImpl {
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    )
+}
+
+#[test]
+fn test_finality_proof_verifies() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 3));
+    let (view, block_hash) = net
+        .finalized()
+        .iter()
+        .map(|(v, h)| (*v, *h))
+        .find(|(_, h)| *h != Hash::default())
+        .unwrap();
+    let committee = net.node(0).unwrap().committee.clone();
+
+    let rpc = make_rpc(net.storage(0));
+    let finalized = rpc.get_finality_proof(block_hash).unwrap().unwrap();
+    assert_eq!(finalized.header.view, view);
+    let (header, proof) = (finalized.header, finalized.proof);
+    assert_eq!(
+        verify_finality(&header, &proof, &committee, DEFAULT_CHAIN_ID),
+        Ok(block_hash)
+    );
+
+    // Unknown blocks have no proof
+    assert!(rpc.get_finality_proof(Hash([7u8; 32])).unwrap().is_none());
+
+    // A different block for the same view
+    let mut forged = header.clone();
+    forged.gas_used += 1;
+    assert_eq!(
+        verify_finality(&forged, &proof, &committee, DEFAULT_CHAIN_ID),
+        Err(FinalityError::BlockMismatch("Notarization"))
+    );
+
+    // Another committee
+    let others: Vec<_> = (10..14)
+        .map(|i| ockham::crypto::generate_keypair_from_id(i).0)
+        .collect();
+    assert_eq!(
+        verify_finality(&header, &proof, &others, DEFAULT_CHAIN_ID),
+        Err(FinalityError::CommitteeMismatch)
+    );
+
+    // Too few finalizers
+    let mut short = proof.clone();
+    short.finalization.signers.truncate(2);
+    assert_eq!(
+        verify_finality(&header, &short, &committee, DEFAULT_CHAIN_ID),
+        Err(FinalityError::InsufficientSigners("Finalization", 2, 3))
+    );
+
+    // Signer list that does not match the aggregate signature
+    let mut swapped = proof.clone();
+    let missing = (0..committee.len()).find(|i| !swapped.finalization.signers.contains(*i));
+    if let Some(missing) = missing {
+        let first = swapped.finalization.signers.indices().next().unwrap();
+        swapped.finalization.signers.remove(first);
+        swapped.finalization.signers.insert(missing);
+        assert_eq!(
+            verify_finality(&header, &swapped, &committee, DEFAULT_CHAIN_ID),
+            Err(FinalityError::InvalidSignature("Finalization"))
+        );
+    }
+
+    // A signer past the end of the committee
+    let mut outsider = proof.clone();
+    outsider.finalization.signers.insert(committee.len());
+    assert_eq!(
+        verify_finality(&header, &outsider, &committee, DEFAULT_CHAIN_ID),
+        Err(FinalityError::UnknownSigner("Finalization"))
+    );
+
+    // The finalization certificate is persisted with the block
+    let storage = net.storage(1);
+    let qc = storage.get_finality_qc(view).unwrap().unwrap();
+    assert_eq!(qc.block_hash, hash_data(&header));
+}
+
+#[test]
+fn test_transaction_proof_verifies() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    let (pk, sk) = generate_keypair_from_id(0);
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::from(100_000_000u64),
+        gas_limit: 21000,
+        to: Some(Address::from([0x11; 20])),
+        value: U256::from(100u64),
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: pk,
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    for i in 0..net.len() {
+        net.node(i)
+            .unwrap()
+            .tx_pool
+            .add_transaction(tx.clone())
+            .unwrap();
+    }
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 4));
+    let committee = net.node(0).unwrap().committee.clone();
+
+    let rpc = make_rpc(net.storage(0));
+    let proof = rpc.get_transaction_proof(hash_data(&tx)).unwrap().unwrap();
+    assert_eq!(proof.transaction, tx);
+    let block_hash = proof.verify(&committee, DEFAULT_CHAIN_ID).unwrap();
+    assert!(net.finalized().values().any(|h| *h == block_hash));
+
+    // Unknown transactions have no proof
+    assert!(
+        rpc.get_transaction_proof(Hash([7u8; 32]))
+            .unwrap()
+            .is_none()
+    );
+
+    // Another transaction does not verify against the branch
+    let mut forged = proof.clone();
+    forged.transaction.value = U256::from(1_000_000u64);
+    assert_eq!(
+        forged.verify(&committee, DEFAULT_CHAIN_ID),
+        Err(FinalityError::NotIncluded)
+    );
+
+    // Nor does the branch under a block that is not final
+    let mut unfinalized = proof.clone();
+    unfinalized.block.proof.finalization.signers.truncate(2);
+    assert_eq!(
+        unfinalized.verify(&committee, DEFAULT_CHAIN_ID),
+        Err(FinalityError::InsufficientSigners("Finalization", 2, 3))
+    );
+}
+
+fn vote(key: &(PublicKey, PrivateKey), block: &Block, vote_type: VoteType) -> Vote {
+    let block_hash = hash_data(block);
+    Vote {
+        view: block.view,
+        block_hash,
+        vote_type,
+        author: key.0.clone(),
+        signature: sign(
+            &key.1,
+            &vote_message(vote_type, block.view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    }
+}
+
+#[test]
+fn test_light_client_follows_finality() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+
+    // Committed state with one funded account
+    let full_storage = Arc::new(MemStorage::new());
+    let state = StateManager::new(full_storage.clone(), None);
+    let alice = Address::from([0xaa; 20]);
+    let info = AccountInfo {
+        balance: U256::from(1_000u64),
+        ..Default::default()
+    };
+    state.commit_account(alice, info.clone()).unwrap();
+
+    let mut block = Block::new(
+        keys[1].0.clone(),
+        1,
+        Hash::default(),
+        QuorumCertificate::default(),
+        state.root(),
+        Hash::default(),
+        vec![],
+        U256::ZERO,
+        0,
+        vec![],
+        hash_data(&committee),
+    );
+    block.sign(&keys[1].1);
+    let block_hash = hash_data(&block);
+
+    let storage = Arc::new(MemStorage::new());
+    let mut light = LightClient::new(committee.clone(), storage.clone());
+
+    // Certified before the header arrives: the light client asks for it
+    for key in &keys[..3] {
+        light
+            .on_vote(vote(key, &block, VoteType::Notarize))
+            .unwrap();
+    }
+    let outsider = generate_keypair_from_id(9);
+    assert!(
+        light
+            .on_vote(vote(&outsider, &block, VoteType::Finalize))
+            .unwrap()
+            .is_empty()
+    );
+    let mut requests = vec![];
+    for key in &keys[..3] {
+        requests = light
+            .on_vote(vote(key, &block, VoteType::Finalize))
+            .unwrap();
+    }
+    assert!(matches!(requests[..], [SyncMessage::RequestBlock(h)] if h == block_hash));
+    assert!(light.head.is_none());
+
+    light.on_block(block.clone()).unwrap();
+    let head = light.head.clone().unwrap();
+    assert_eq!((head.view, head.block_hash), (1, block_hash));
+    assert_eq!(storage.get_chain_head().unwrap(), Some(head.clone()));
+    assert_eq!(
+        storage
+            .get_consensus_state()
+            .unwrap()
+            .unwrap()
+            .preferred_block,
+        block_hash
+    );
+
+    // Account proofs from a full node verify against the finalized header
+    let full_head = ChainHead {
+        view: 1,
+        block_hash,
+        state_root: state.root(),
+    };
+    let proof = AccountProof::from_state(&full_head, &state, alice).unwrap();
+    assert_eq!(proof.account, Some(info));
+    assert!(light.verify_account_proof(&proof).unwrap());
+
+    let absent = AccountProof::from_state(&full_head, &state, Address::from([0xbb; 20])).unwrap();
+    assert!(absent.account.is_none());
+    assert!(light.verify_account_proof(&absent).unwrap());
+
+    let mut inflated = proof.clone();
+    inflated.account.as_mut().unwrap().balance = U256::from(1_000_000u64);
+    assert!(!light.verify_account_proof(&inflated).unwrap());
+
+    let mut unknown = proof;
+    unknown.block_hash = Hash([3u8; 32]);
+    assert!(!light.verify_account_proof(&unknown).unwrap());
+}
+
+#[tokio::test]
+async fn test_rpc_account_proof() {
+    let storage = Arc::new(MemStorage::new());
+    let rpc = make_rpc(storage.clone());
+    // Nothing committed yet
+    assert!(
+        rpc.get_account_proof(Address::ZERO)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let state = StateManager::new(storage.clone(), None);
+    state
+        .commit_account(Address::ZERO, AccountInfo::default())
+        .unwrap();
+    let head = ChainHead {
+        view: 2,
+        block_hash: Hash([1u8; 32]),
+        state_root: state.root(),
+    };
+    storage.save_chain_head(&head).unwrap();
+
+    // The RPC proves against its executor's state, which must be at the head
+    let state_manager = Arc::new(Mutex::new(StateManager::new(
+        storage.clone(),
+        Some(head.state_root),
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+    let proof = rpc.get_account_proof(Address::ZERO).await.unwrap().unwrap();
+    assert_eq!(proof.block_hash, head.block_hash);
+    assert!(proof.verify(&head.state_root));
+}
+
+#[test]
+fn test_signer_bitmap() {
+    let committee: Vec<PublicKey> = (0..100).map(|i| generate_keypair_from_id(i).0).collect();
+    let signers: Vec<&PublicKey> = committee
+        .iter()
+        .step_by(3)
+        .chain(&committee[1..40])
+        .collect();
+    let bitmap = SignerBitmap::from_signers(&committee, signers.iter().copied()).unwrap();
+    assert_eq!(bitmap.len(), 34 + 26);
+    assert!(bitmap.contains(0) && bitmap.contains(39) && !bitmap.contains(41));
+
+    // Resolved in committee order; bits past the committee do not resolve
+    let resolved = bitmap.resolve(&committee).unwrap();
+    assert_eq!(resolved.len(), bitmap.len());
+    assert!(resolved.windows(2).all(|w| {
+        let position = |pk| committee.iter().position(|member| member == pk);
+        position(&w[0]) < position(&w[1])
+    }));
+    assert!(bitmap.resolve(&committee[..50]).is_none());
+    let (outsider, _) = generate_keypair_from_id(100);
+    assert!(SignerBitmap::from_signers(&committee, [&outsider]).is_none());
+
+    let mut short = bitmap.clone();
+    short.truncate(3);
+    assert_eq!(short.indices().collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(short.0.len(), 1);
+
+    // Size of a 2f+1 certificate of a 100-member committee, against listing the keys
+    let mut quorum = SignerBitmap::default();
+    (0..67).for_each(|i| quorum.insert(i));
+    let qc = QuorumCertificate {
+        view: 1,
+        block_hash: Hash([1u8; 32]),
+        signers: quorum,
+        ..Default::default()
+    };
+    let keys = serde_json::to_vec(&committee[..67]).unwrap().len();
+    let bitmap_qc = serde_json::to_vec(&qc).unwrap().len();
+    println!(
+        "QC with bitmap: {} bytes (signer keys alone: {} bytes)",
+        bitmap_qc, keys
+    );
+    assert!(bitmap_qc * 10 < keys);
+}
@@ -1,6 +1,7 @@
 use ockham::crypto::{Hash, PrivateKey, PublicKey};
 use ockham::storage::Storage;
-use ockham::types::{Block, QuorumCertificate, U256};
+use ockham::system_contracts::staking;
+use ockham::types::{Block, QuorumCertificate, SignerBitmap, U256};
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -33,7 +34,6 @@ fn test_liveness_slashing() {
         nonce: 0,
         balance: initial_balance,
         code_hash: Hash(ockham::types::keccak256([]).into()),
-        code: None,
     };
     storage.save_account(&victim_addr, &account).unwrap();
 
@@ -56,14 +56,14 @@ fn test_liveness_slashing() {
         committee: committee.clone(),
         pending_validators: vec![],
         exiting_validators: vec![],
-        stakes: {
-            let mut m = std::collections::HashMap::new();
-            m.insert(victim_addr, U256::from(1000u64));
-            m
-        },
         inactivity_scores: std::collections::HashMap::new(),
+        params: Default::default(),
+        rewards: std::collections::HashMap::new(),
+        proposals: vec![],
+        missed_attestations: std::collections::HashMap::new(),
     };
     storage.save_consensus_state(&initial_state).unwrap();
+    staking::init_genesis(storage.as_ref(), &[victim_id.clone()], U256::from(1000u64)).unwrap();
 
     // 2. Simulate Timeout of View 1 (Leader: Node 1)
     // View 1 -> 1 % 4 = 1. So Node 1 is Leader of View 1.
@@ -74,7 +74,8 @@ fn test_liveness_slashing() {
         view: timeout_view,
         block_hash: Hash::default(), // ZeroHash = Timeout
         signature: ockham::crypto::Signature::default(),
-        signers: vec![],
+        epoch: 0,
+        signers: Default::default(),
     };
 
     // Block Author: Node 2
@@ -99,11 +100,11 @@ fn test_liveness_slashing() {
 
     // 4. Verify Slashing
     {
-        let db = state_manager.lock().unwrap();
+        let mut db = state_manager.lock().unwrap();
         // Check Stake
+        let stake = staking::stake_of(&mut db, victim_addr).unwrap();
+        assert_eq!(stake, U256::from(990u64), "Stake should be slashed by 10");
         let state = db.get_consensus_state().unwrap().unwrap();
-        let stake = state.stakes.get(&victim_addr).expect("Stake should exist");
-        assert_eq!(*stake, U256::from(990u64), "Stake should be slashed by 10");
 
         // Check Score
         let score = state
@@ -128,7 +129,7 @@ fn test_liveness_slashing() {
     executor.execute_block(&mut block_to_exec).unwrap();
 
     {
-        let db = state_manager.lock().unwrap();
+        let mut db = state_manager.lock().unwrap();
         let state = db.get_consensus_state().unwrap().unwrap();
         let score = state.inactivity_scores.get(&keys[author_idx].0).unwrap();
         assert_eq!(*score, 4, "Author score should decrement");
@@ -136,8 +137,8 @@ fn test_liveness_slashing() {
         let victim_score = state.inactivity_scores.get(&victim_id).unwrap();
         assert_eq!(*victim_score, 2, "Victim score should increment again");
 
-        let stake = state.stakes.get(&victim_addr).unwrap();
-        assert_eq!(*stake, U256::from(980u64), "Stake slashed again");
+        let stake = staking::stake_of(&mut db, victim_addr).unwrap();
+        assert_eq!(stake, U256::from(980u64), "Stake slashed again");
     }
 
     // 6. Threshold Removal
@@ -166,3 +167,103 @@ fn test_liveness_slashing() {
 
     println!("Liveness Slashing Test Passed!");
 }
+
+#[test]
+fn test_inactivity_leak() {
+    // Node 1 stays silent: it never leads in these blocks, but misses their QCs
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..4)
+        .map(|i| ockham::crypto::generate_keypair_from_id(i as u64))
+        .collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let victim_id = keys[1].0.clone();
+    let victim_addr = staking::validator_address(&victim_id);
+
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    let params = ockham::types::ChainParams {
+        attestation_grace: 2,
+        attestation_penalty: U256::from(10u64),
+        inactivity_threshold: 3,
+        ..Default::default()
+    };
+    let initial_state = ockham::storage::ConsensusState {
+        view: 1,
+        committee: committee.clone(),
+        params,
+        ..Default::default()
+    };
+    storage.save_consensus_state(&initial_state).unwrap();
+    staking::init_genesis(storage.as_ref(), &[victim_id.clone()], U256::from(1000u64)).unwrap();
+
+    let block_signed_by = |signers: Vec<PublicKey>| {
+        let qc = QuorumCertificate {
+            view: 1,
+            block_hash: Hash([1u8; 32]),
+            signature: ockham::crypto::Signature::default(),
+            epoch: 0,
+            signers: SignerBitmap::from_signers(&committee, &signers).unwrap(),
+        };
+        Block::new(
+            keys[2].0.clone(),
+            2,
+            Hash([1u8; 32]),
+            qc,
+            Hash::default(),
+            Hash::default(),
+            vec![],
+            U256::ZERO,
+            0,
+            vec![],
+            Hash::default(),
+        )
+    };
+    let mut missed = block_signed_by(vec![
+        keys[0].0.clone(),
+        keys[2].0.clone(),
+        keys[3].0.clone(),
+    ]);
+    let mut attended = block_signed_by(committee.clone());
+    let status = || {
+        let mut db = state_manager.lock().unwrap();
+        let state = db.get_consensus_state().unwrap().unwrap();
+        let stake = staking::stake_of(&mut db, victim_addr).unwrap();
+        (
+            stake,
+            state.missed_attestations.get(&victim_id).copied(),
+            state.inactivity_scores.get(&victim_id).copied(),
+            state.committee.contains(&victim_id),
+        )
+    };
+
+    // 1. Within the grace: tracked, not penalized
+    for _ in 0..2 {
+        executor.execute_block(&mut missed).unwrap();
+    }
+    assert_eq!(status(), (U256::from(1000u64), Some(2), None, true));
+
+    // 2. Past the grace, the penalty grows with the absence
+    executor.execute_block(&mut missed).unwrap();
+    assert_eq!(status(), (U256::from(990u64), Some(3), Some(1), true));
+    executor.execute_block(&mut missed).unwrap();
+    assert_eq!(status(), (U256::from(970u64), Some(4), Some(2), true));
+
+    // 3. Attesting again resets the streak (the score only decays by leading)
+    executor.execute_block(&mut attended).unwrap();
+    assert_eq!(status(), (U256::from(970u64), None, Some(2), true));
+
+    // 4. Past the inactivity threshold, the validator leaves the committee
+    for _ in 0..4 {
+        executor.execute_block(&mut missed).unwrap();
+    }
+    assert_eq!(status(), (U256::from(940u64), None, None, false));
+    let mut db = state_manager.lock().unwrap();
+    assert_eq!(staking::exited_at(&mut db, victim_addr).unwrap(), Some(2));
+}
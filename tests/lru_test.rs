@@ -0,0 +1,26 @@
+use ockham::lru::Lru;
+
+#[test]
+fn test_lru_evicts_least_recently_used() {
+    let mut lru = Lru::new();
+    for key in 0..3 {
+        assert!(lru.insert(key, key * 10).is_none());
+    }
+
+    // Reading 0 and replacing 1 make 2 the oldest; peeking does not count as a use
+    assert_eq!(lru.get(&0), Some(&0));
+    assert_eq!(lru.insert(1, 11), Some(10));
+    assert_eq!(lru.peek(&2), Some(&20));
+    assert_eq!(lru.pop_oldest(), Some((2, 20)));
+    assert_eq!(lru.pop_oldest(), Some((0, 0)));
+
+    // Removed entries are skipped, however many uses they had
+    for _ in 0..10 {
+        lru.get(&1);
+    }
+    lru.insert(3, 30);
+    assert_eq!(lru.remove(&1), Some(11));
+    assert_eq!(lru.pop_oldest(), Some((3, 30)));
+    assert_eq!(lru.pop_oldest(), None);
+    assert!(lru.is_empty());
+}
@@ -0,0 +1,82 @@
+use ockham::crypto::{generate_keypair_from_id, sign};
+use ockham::memory::{MemoryBudget, transaction_size};
+use ockham::storage::MemStorage;
+use ockham::tx_pool::{PoolError, TxPool};
+use ockham::types::{Address, Bytes, Transaction, U256};
+use std::sync::Arc;
+
+fn make_tx(id: u64, tip: u64) -> Transaction {
+    let (pk, sk) = generate_keypair_from_id(id);
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::from(tip),
+        max_fee_per_gas: U256::from(100_000_000u64),
+        gas_limit: 21_400,
+        to: Some(Address::ZERO),
+        value: U256::ZERO,
+        data: Bytes::from(vec![0u8; 100]),
+        access_list: vec![],
+        public_key: pk,
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    tx
+}
+
+#[test]
+fn test_proportional_allowance() {
+    let budget = MemoryBudget::new(1000);
+    let a = budget.register("a");
+    let b = budget.register("b");
+
+    // Under the limit: no eviction requested
+    a.set_usage(300);
+    b.set_usage(600);
+    assert_eq!(a.excess(), 0);
+    assert_eq!(b.excess(), 0);
+
+    // Over the limit: each cache gets its proportional share
+    a.set_usage(1000);
+    b.set_usage(1000);
+    assert_eq!(budget.usage(), 2000);
+    assert_eq!(a.allowance(), 500);
+    assert_eq!(a.excess(), 500);
+    assert_eq!(b.excess(), 500);
+
+    // Dropping a handle releases its usage
+    drop(b);
+    assert_eq!(budget.usage(), 1000);
+    assert_eq!(a.excess(), 0);
+    assert_eq!(budget.usage_by_name(), vec![("a", 1000)]);
+}
+
+#[test]
+fn test_pool_evicts_lowest_tip() {
+    let tx_size = transaction_size(&make_tx(0, 1));
+    let budget = MemoryBudget::new(tx_size * 3);
+    let pool = TxPool::new(Arc::new(MemStorage::new())).with_memory_budget(&budget);
+
+    pool.add_transaction(make_tx(0, 5)).unwrap();
+    pool.add_transaction(make_tx(1, 1)).unwrap();
+    pool.add_transaction(make_tx(2, 7)).unwrap();
+    assert_eq!(pool.len(), 3);
+
+    // A fourth tx pushes the pool over budget: the lowest tip (1) is evicted
+    pool.add_transaction(make_tx(3, 9)).unwrap();
+    assert_eq!(pool.len(), 3);
+    assert!(budget.usage() <= budget.limit());
+    let tips: Vec<U256> = pool
+        .get_transactions_for_block(ockham::types::DEFAULT_BLOCK_GAS_LIMIT, U256::ZERO)
+        .iter()
+        .map(|tx| tx.max_priority_fee_per_gas)
+        .collect();
+    assert_eq!(tips, vec![U256::from(9), U256::from(7), U256::from(5)]);
+
+    // A tx that would itself be the first evicted is rejected
+    assert!(matches!(
+        pool.add_transaction(make_tx(4, 0)),
+        Err(PoolError::PoolFull)
+    ));
+    assert_eq!(pool.len(), 3);
+}
@@ -0,0 +1,141 @@
+use ockham::crypto::{Hash, generate_keypair_from_id};
+use ockham::migrations::{MIGRATIONS, SCHEMA_VERSION, schema_version};
+use ockham::storage::{
+    ConsensusState, RedbStorage, Storage, StorageError, ValidatorQueues, ValidatorScores,
+};
+use ockham::types::U256;
+use redb::{Database, TableDefinition};
+
+#[test]
+fn test_schema_versioning() {
+    let path = std::env::temp_dir().join(format!("ockham_schema_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(MIGRATIONS.last().map(|m| m.version), Some(SCHEMA_VERSION));
+
+    // New databases are stamped with the current version
+    drop(RedbStorage::new(&path).unwrap());
+    assert_eq!(
+        schema_version(&Database::open(&path).unwrap()).unwrap(),
+        SCHEMA_VERSION
+    );
+
+    // Databases from before versioning are migrated
+    {
+        let meta: TableDefinition<&str, Vec<u8>> = TableDefinition::new("meta");
+        let db = Database::open(&path).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        write_txn
+            .open_table(meta)
+            .unwrap()
+            .remove("schema_version")
+            .unwrap();
+        write_txn.commit().unwrap();
+        assert_eq!(schema_version(&db).unwrap(), 0);
+    }
+    drop(RedbStorage::new(&path).unwrap());
+
+    // A database written by a newer node is refused, and left as it is
+    let newer = SCHEMA_VERSION + 1;
+    {
+        let meta: TableDefinition<&str, Vec<u8>> = TableDefinition::new("meta");
+        let db = Database::open(&path).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        write_txn
+            .open_table(meta)
+            .unwrap()
+            .insert("schema_version", bincode::serialize(&newer).unwrap())
+            .unwrap();
+        write_txn.commit().unwrap();
+    }
+    match RedbStorage::new(&path) {
+        Err(StorageError::UnsupportedSchemaVersion { found, supported }) => {
+            assert_eq!((found, supported), (newer, SCHEMA_VERSION));
+        }
+        _ => panic!("expected the newer schema to be refused"),
+    }
+    assert_eq!(
+        schema_version(&Database::open(&path).unwrap()).unwrap(),
+        newer
+    );
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_consensus_state_split() {
+    let path = std::env::temp_dir().join(format!("ockham_consensus_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (alice, bob) = (generate_keypair_from_id(1).0, generate_keypair_from_id(2).0);
+    let mut state = ConsensusState::genesis(Hash([1; 32]), vec![alice.clone()], Default::default());
+    state.view = 7;
+    state.inactivity_scores.insert(alice.clone(), 3);
+    state.missed_attestations.insert(bob.clone(), 2);
+    state.rewards.insert(alice.clone(), U256::from(50u64));
+    state.pending_validators.push((bob.clone(), 20));
+
+    // A database from schema version 1 keeps the whole state in one meta record
+    drop(RedbStorage::new(&path).unwrap());
+    {
+        let meta: TableDefinition<&str, Vec<u8>> = TableDefinition::new("meta");
+        let db = Database::open(&path).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(meta).unwrap();
+            table
+                .insert("consensus_state", bincode::serialize(&state).unwrap())
+                .unwrap();
+            table
+                .insert("schema_version", bincode::serialize(&1u32).unwrap())
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+    }
+
+    // It is split on open, and reads back whole or in parts
+    let storage = RedbStorage::new(&path).unwrap();
+    let loaded = storage.get_consensus_state().unwrap().unwrap();
+    assert_eq!(loaded.head(), state.head());
+    assert_eq!(loaded.committee, state.committee);
+    assert_eq!(loaded.inactivity_scores, state.inactivity_scores);
+    assert_eq!(loaded.missed_attestations, state.missed_attestations);
+    assert_eq!(loaded.rewards, state.rewards);
+    assert_eq!(loaded.queues(), state.queues());
+    assert_eq!(
+        storage.get_validator_scores(&bob).unwrap(),
+        ValidatorScores {
+            inactivity: 0,
+            missed_attestations: 2,
+        }
+    );
+    assert_eq!(storage.get_reward(&alice).unwrap(), U256::from(50u64));
+
+    // Parts are written without the rest
+    let mut head = state.head();
+    head.view = 8;
+    storage.save_consensus_head(&head).unwrap();
+    storage.save_reward(&alice, U256::ZERO).unwrap();
+    storage.save_reward(&bob, U256::from(5u64)).unwrap();
+    storage
+        .save_validator_scores(&alice, ValidatorScores::default())
+        .unwrap();
+    let loaded = storage.get_consensus_state().unwrap().unwrap();
+    assert_eq!(loaded.view, 8);
+    assert_eq!(loaded.committee, state.committee);
+    assert!(loaded.inactivity_scores.is_empty());
+    assert_eq!(loaded.rewards.len(), 1);
+    assert_eq!(loaded.rewards[&bob], U256::from(5u64));
+    assert_eq!(loaded.pending_validators, state.pending_validators);
+
+    // A whole save drops what the state no longer has
+    let mut state = loaded;
+    state.missed_attestations.clear();
+    state.pending_validators.clear();
+    storage.save_consensus_state(&state).unwrap();
+    let loaded = storage.get_consensus_state().unwrap().unwrap();
+    assert!(loaded.missed_attestations.is_empty());
+    assert_eq!(
+        storage.get_validator_queues().unwrap(),
+        ValidatorQueues::default()
+    );
+    drop(storage);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,131 @@
+use ockham::consensus::{ConsensusAction, OrphanConfig, SimplexState};
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, hash_data};
+use ockham::types::{Block, QuorumCertificate, U256};
+use std::sync::{Arc, Mutex};
+
+fn make_node(keys: &[(PublicKey, PrivateKey)], config: OrphanConfig) -> SimplexState {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+    .with_orphan_config(config)
+}
+
+/// A block signed by the leader of `view` whose parent nobody has.
+fn make_orphan(keys: &[(PublicKey, PrivateKey)], view: u64) -> Block {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let leader = &keys[view as usize % keys.len()];
+    let mut block = Block::new(
+        leader.0.clone(),
+        view,
+        Hash([view as u8; 32]),
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        vec![],
+        U256::ZERO,
+        0,
+        vec![],
+        hash_data(&committee),
+    );
+    block.sign(&leader.1);
+    block
+}
+
+fn is_parent_request(actions: &[ConsensusAction]) -> bool {
+    matches!(actions, [ConsensusAction::BroadcastRequest(_)])
+}
+
+#[test]
+fn test_orphan_per_peer_quota() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let config = OrphanConfig {
+        max_per_peer: 2,
+        ..Default::default()
+    };
+    let mut node = make_node(&keys, config);
+
+    for view in 5..7 {
+        let actions = node
+            .on_proposal_from(make_orphan(&keys, view), "peer-a".into())
+            .unwrap();
+        assert!(is_parent_request(&actions));
+    }
+    // Over quota: neither buffered nor requested
+    let actions = node
+        .on_proposal_from(make_orphan(&keys, 7), "peer-a".into())
+        .unwrap();
+    assert!(actions.is_empty());
+
+    // Other peers have their own quota
+    let actions = node
+        .on_proposal_from(make_orphan(&keys, 8), "peer-b".into())
+        .unwrap();
+    assert!(is_parent_request(&actions));
+
+    let metrics = node.orphan_metrics();
+    assert_eq!(metrics.count, 3);
+    assert_eq!(metrics.rejected, 1);
+    assert!(metrics.bytes > 0);
+}
+
+#[test]
+fn test_orphan_buffer_full_keeps_lowest_views() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let config = OrphanConfig {
+        max_orphans: 2,
+        ..Default::default()
+    };
+    let mut node = make_node(&keys, config);
+    node.on_proposal(make_orphan(&keys, 5)).unwrap();
+    node.on_proposal(make_orphan(&keys, 9)).unwrap();
+
+    // A lower view displaces the highest one, a higher view is refused
+    node.on_proposal(make_orphan(&keys, 7)).unwrap();
+    assert!(node.on_proposal(make_orphan(&keys, 10)).unwrap().is_empty());
+
+    let mut views: Vec<u64> = node
+        .orphans
+        .values()
+        .flatten()
+        .map(|o| o.block.view)
+        .collect();
+    views.sort_unstable();
+    assert_eq!(views, vec![5, 7]);
+    let metrics = node.orphan_metrics();
+    assert_eq!((metrics.evicted, metrics.rejected), (1, 1));
+}
+
+#[test]
+fn test_orphan_expiry() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let config = OrphanConfig {
+        expiry_views: 3,
+        ..Default::default()
+    };
+    let mut node = make_node(&keys, config);
+    node.on_proposal(make_orphan(&keys, 5)).unwrap();
+
+    node.current_view = 3;
+    node.on_timeout(3).unwrap();
+    assert_eq!(node.orphan_metrics().count, 1);
+
+    node.current_view = 4;
+    node.on_timeout(4).unwrap();
+    let metrics = node.orphan_metrics();
+    assert_eq!((metrics.count, metrics.expired), (0, 1));
+    assert!(node.orphans.is_empty());
+}
@@ -0,0 +1,155 @@
+use ockham::crypto::{PrivateKey, PublicKey, Signature, generate_keypair_from_id, sign};
+use ockham::payload::{
+    NonceOrderedBuilder, PayloadBuilder, PayloadRequest, PriorityFeeBuilder, builder_by_name,
+};
+use ockham::types::{Address, Transaction, U256, wire_size};
+use std::collections::HashSet;
+
+fn make_tx(key: &(PublicKey, PrivateKey), nonce: u64, tip: u64, gas_limit: u64) -> Transaction {
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce,
+        max_priority_fee_per_gas: U256::from(tip),
+        max_fee_per_gas: U256::from(100_000_000u64),
+        gas_limit,
+        to: Some(Address::ZERO),
+        value: U256::ZERO,
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: key.0.clone(),
+        signature: Signature::default(),
+    };
+    tx.signature = sign(&key.1, &tx.sighash().0);
+    tx
+}
+
+fn order(txs: &[Transaction], alice: &Transaction) -> Vec<(bool, u64)> {
+    txs.iter()
+        .map(|tx| (tx.public_key == alice.public_key, tx.nonce))
+        .collect()
+}
+
+#[test]
+fn test_nonce_ordered_builder() {
+    let alice = generate_keypair_from_id(1);
+    let bob = generate_keypair_from_id(2);
+    let operators = HashSet::new();
+    let request = PayloadRequest {
+        gas_limit: 1_000_000,
+        base_fee: U256::ZERO,
+        operators: &operators,
+        max_size: u64::MAX,
+        deadline: None,
+    };
+    // Alice's second transaction pays more than her first
+    let candidates = vec![
+        make_tx(&alice, 1, 10, 21_000),
+        make_tx(&alice, 0, 1, 21_000),
+        make_tx(&bob, 0, 5, 21_000),
+    ];
+
+    // Tip sorting across senders puts Alice's nonce 1 before her nonce 0
+    let greedy = PriorityFeeBuilder.build(candidates.clone(), &request);
+    assert_eq!(
+        order(&greedy.transactions, &candidates[0]),
+        vec![(true, 1), (false, 0), (true, 0)]
+    );
+
+    let ordered = NonceOrderedBuilder.build(candidates.clone(), &request);
+    assert_eq!(
+        order(&ordered.transactions, &candidates[0]),
+        vec![(false, 0), (true, 0), (true, 1)]
+    );
+
+    // A sender whose next transaction does not fit contributes nothing more
+    let candidates = vec![
+        make_tx(&alice, 0, 10, 600_000),
+        make_tx(&alice, 1, 10, 21_000),
+        make_tx(&bob, 0, 5, 500_000),
+        make_tx(&bob, 1, 5, 21_000),
+    ];
+    let payload = NonceOrderedBuilder.build(candidates.clone(), &request);
+    assert_eq!(
+        order(&payload.transactions, &candidates[0]),
+        vec![(true, 0), (true, 1)]
+    );
+}
+
+#[test]
+fn test_payload_byte_budget() {
+    let alice = generate_keypair_from_id(1);
+    let bob = generate_keypair_from_id(2);
+    let mut big = make_tx(&alice, 0, 10, 500_000);
+    big.data = vec![1u8; 4096].into();
+    big.signature = sign(&alice.1, &big.sighash().0);
+    let candidates = vec![
+        big,
+        make_tx(&alice, 1, 10, 21_000),
+        make_tx(&bob, 0, 5, 21_000),
+        make_tx(&bob, 1, 5, 21_000),
+    ];
+    let operators = HashSet::new();
+    let request = PayloadRequest {
+        gas_limit: 1_000_000,
+        base_fee: U256::ZERO,
+        operators: &operators,
+        // Room for two of the small transactions, not three
+        max_size: 2 * candidates[1..]
+            .iter()
+            .map(|tx| wire_size(tx) as u64 + 1)
+            .max()
+            .unwrap(),
+        deadline: None,
+    };
+
+    // The best paying transaction does not fit in the byte budget, cheaper ones do
+    let greedy = PriorityFeeBuilder.build(candidates.clone(), &request);
+    assert_eq!(
+        order(&greedy.transactions, &candidates[0]),
+        vec![(true, 1), (false, 0)]
+    );
+
+    // Alice's first transaction does not fit, so neither does her second
+    let ordered = NonceOrderedBuilder.build(candidates.clone(), &request);
+    assert_eq!(
+        order(&ordered.transactions, &candidates[0]),
+        vec![(false, 0), (false, 1)]
+    );
+}
+
+#[test]
+fn test_nonce_ordered_builder_operators_first() {
+    let alice = generate_keypair_from_id(1);
+    let operator = generate_keypair_from_id(3);
+    let operators: HashSet<Address> = [make_tx(&operator, 0, 0, 21_000).sender()]
+        .into_iter()
+        .collect();
+    let request = PayloadRequest {
+        gas_limit: 1_000_000,
+        base_fee: U256::ZERO,
+        operators: &operators,
+        max_size: u64::MAX,
+        deadline: None,
+    };
+    let candidates = vec![
+        make_tx(&alice, 0, 1_000, 21_000),
+        make_tx(&operator, 1, 0, 21_000),
+        make_tx(&operator, 0, 0, 21_000),
+    ];
+    let payload = NonceOrderedBuilder.build(candidates.clone(), &request);
+    assert_eq!(payload.operator_txs, 2);
+    assert_eq!(
+        order(&payload.transactions, &candidates[0]),
+        vec![(false, 0), (false, 1), (true, 0)]
+    );
+
+    assert_eq!(
+        builder_by_name("nonce-ordered").unwrap().name(),
+        "nonce-ordered"
+    );
+    assert_eq!(
+        builder_by_name("priority-fee").unwrap().name(),
+        "priority-fee"
+    );
+    assert!(builder_by_name("fifo").is_none());
+}
@@ -0,0 +1,111 @@
+use libp2p::PeerId;
+use ockham::network::{
+    GossipTopic, Misbehavior, PeerManager, PeerScoreConfig, TopicLimit, TopicLimits,
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_peer_banned_below_threshold() {
+    let mut peers = PeerManager::new(PeerScoreConfig::default());
+    let bad = PeerId::random();
+    let good = PeerId::random();
+    let now = Instant::now();
+
+    // 25 points per bad signature: the fourth one hits -100
+    for _ in 0..3 {
+        assert!(!peers.report(&bad, Misbehavior::InvalidSignature, now));
+    }
+    assert_eq!(peers.score(&bad), -75);
+    assert!(!peers.is_banned(&bad, now));
+    assert!(peers.report(&bad, Misbehavior::InvalidSignature, now));
+    assert!(peers.is_banned(&bad, now));
+    // Only the first crossing reports a ban
+    assert!(!peers.report(&bad, Misbehavior::InvalidMessage, now));
+
+    assert_eq!(peers.score(&good), 0);
+    assert!(!peers.is_banned(&good, now));
+
+    // Bans expire and the peer starts over
+    let later = now + PeerScoreConfig::default().ban_duration;
+    assert!(!peers.is_banned(&bad, later));
+    assert_eq!(peers.prune_expired_bans(later), vec![bad]);
+    assert_eq!(peers.score(&bad), 0);
+}
+
+#[test]
+fn test_peer_rate_limit_and_recovery() {
+    let limit = TopicLimit {
+        max_message_size: 1024,
+        rate_limit: 3,
+    };
+    let config = PeerScoreConfig {
+        topic_limits: TopicLimits {
+            votes: limit,
+            txs: limit,
+            ..Default::default()
+        },
+        rate_window: Duration::from_secs(1),
+        recovery_interval: Duration::from_secs(10),
+        ..Default::default()
+    };
+    let mut peers = PeerManager::new(config);
+    let peer = PeerId::random();
+    let now = Instant::now();
+
+    for _ in 0..3 {
+        assert!(peers.record_message(&peer, GossipTopic::Votes, now).is_ok());
+    }
+    assert_eq!(
+        peers.record_message(&peer, GossipTopic::Votes, now),
+        Err(Misbehavior::Spam)
+    );
+    // Topics are limited independently: a tx flood does not eat the vote budget
+    for _ in 0..3 {
+        assert!(peers.record_message(&peer, GossipTopic::Txs, now).is_ok());
+    }
+    assert_eq!(
+        peers.record_message(&peer, GossipTopic::Txs, now),
+        Err(Misbehavior::Spam)
+    );
+
+    // A new window resets the count
+    let next_window = now + Duration::from_secs(1);
+    assert!(peers.record_message(&peer, next_window).is_ok());
+
+    // Scores recover one point per interval, but never above 0
+    peers.report(&peer, Misbehavior::InvalidMessage, now);
+    assert_eq!(peers.score(&peer), -10);
+    peers
+        .record_message(&peer, GossipTopic::Votes, now + Duration::from_secs(30))
+        .unwrap();
+    assert_eq!(peers.score(&peer), -7);
+    peers
+        .record_message(&peer, GossipTopic::Votes, now + Duration::from_secs(3600))
+        .unwrap();
+    assert_eq!(peers.score(&peer), 0);
+}
+
+#[test]
+fn test_gossip_topics() {
+    let names: Vec<_> = GossipTopic::ALL.iter().map(|t| t.name()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "ockham/blocks",
+            "ockham/votes",
+            "ockham/txs",
+            "ockham/evidence",
+            "ockham/sync"
+        ]
+    );
+    for topic in GossipTopic::ALL {
+        assert_eq!(GossipTopic::from_hash(&topic.ident().hash()), Some(topic));
+    }
+    let legacy = libp2p::gossipsub::IdentTopic::new("simplex-consensus");
+    assert_eq!(GossipTopic::from_hash(&legacy.hash()), None);
+
+    // The transport limit fits the largest topic (proposal evidence: two blocks)
+    let limits = TopicLimits::default();
+    assert_eq!(limits.max_message_size(), limits.evidence.max_message_size);
+    assert!(limits.votes.max_message_size < limits.blocks.max_message_size);
+}
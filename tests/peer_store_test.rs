@@ -0,0 +1,136 @@
+use libp2p::Multiaddr;
+use ockham::network::Dialer;
+use ockham::storage::{KnownPeer, MemStorage, RedbStorage, Storage};
+use std::time::{Duration, Instant};
+
+fn addr(port: u16) -> Multiaddr {
+    format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap()
+}
+
+#[test]
+fn test_redial_backoff() {
+    let mut dialer = Dialer::new(Duration::from_secs(1), Duration::from_secs(5));
+    let boot = addr(9000);
+    let now = Instant::now();
+    dialer.add(boot.clone(), true, now);
+
+    assert_eq!(dialer.due(now, 0), vec![boot.clone()]);
+    // In flight: not due again until the outcome is known
+    assert!(dialer.due(now, 0).is_empty());
+
+    // 1s, 2s, 4s, then capped at 5s
+    let mut t = now;
+    for backoff in [1, 2, 4, 5, 5] {
+        dialer.on_failure(&boot, t);
+        assert!(
+            dialer
+                .due(t + Duration::from_millis(backoff * 1000 - 1), 0)
+                .is_empty()
+        );
+        t += Duration::from_secs(backoff);
+        assert_eq!(dialer.due(t, 0), vec![boot.clone()]);
+    }
+
+    // A success resets the backoff; a disconnect redials after the base backoff
+    dialer.on_connected(&boot);
+    assert_eq!(dialer.connected(), 1);
+    dialer.on_disconnected(&boot, t);
+    assert_eq!(dialer.connected(), 0);
+    assert_eq!(
+        dialer.due(t + Duration::from_secs(1), 0),
+        vec![boot.clone()]
+    );
+    dialer.on_failure(&boot, t);
+    assert_eq!(dialer.due(t + Duration::from_secs(1), 0), vec![boot]);
+}
+
+#[test]
+fn test_target_peer_limit() {
+    let mut dialer = Dialer::new(Duration::from_secs(1), Duration::from_secs(60));
+    let now = Instant::now();
+    dialer.add(addr(9000), true, now);
+    for port in 9001..9005 {
+        dialer.add(addr(port), false, now);
+    }
+
+    // Static peers are always dialed; stored peers only up to the limit
+    let due = dialer.due(now, 2);
+    assert_eq!(due.len(), 3);
+    assert!(due.contains(&addr(9000)));
+
+    for a in &due {
+        dialer.on_connected(a);
+    }
+    assert_eq!(dialer.connected(), 3);
+    assert_eq!(dialer.due(now, 2).len(), 2);
+    // Banned peers are forgotten
+    dialer.remove(&addr(9000));
+    assert_eq!(dialer.connected(), 2);
+}
+
+fn check_peer_store(storage: &dyn Storage) {
+    let a = KnownPeer {
+        peer_id: "peer-a".into(),
+        address: addr(9001).to_string(),
+        last_seen: 100,
+    };
+    let b = KnownPeer {
+        peer_id: "peer-b".into(),
+        address: addr(9002).to_string(),
+        last_seen: 200,
+    };
+    storage.save_peer(&a).unwrap();
+    storage.save_peer(&b).unwrap();
+
+    // Saving again updates last_seen
+    let a2 = KnownPeer {
+        last_seen: 300,
+        ..a.clone()
+    };
+    storage.save_peer(&a2).unwrap();
+
+    let mut peers = storage.get_peers().unwrap();
+    peers.sort_by(|x, y| x.peer_id.cmp(&y.peer_id));
+    assert_eq!(peers, vec![a2, b.clone()]);
+
+    storage.remove_peer("peer-a").unwrap();
+    assert_eq!(storage.get_peers().unwrap(), vec![b]);
+}
+
+#[test]
+fn test_peer_store_mem() {
+    check_peer_store(&MemStorage::new());
+}
+
+#[test]
+fn test_peer_store_redb() {
+    let path = std::env::temp_dir().join(format!("ockham_peer_store_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let storage = RedbStorage::new(&path).unwrap();
+        check_peer_store(&storage);
+    }
+    // Survives a restart
+    let storage = RedbStorage::new(&path).unwrap();
+    assert_eq!(storage.get_peers().unwrap().len(), 1);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_node_key_persists() {
+    let path = std::env::temp_dir().join(format!("ockham_node_key_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    // Created on first use, then the same peer id on every load
+    let key = ockham::network::load_or_create_node_key(&path).unwrap();
+    let peer_id = key.public().to_peer_id();
+    let reloaded = ockham::network::load_or_create_node_key(&path).unwrap();
+    assert_eq!(reloaded.public().to_peer_id(), peer_id);
+
+    let ip = "10.0.0.7".parse().unwrap();
+    assert_eq!(
+        ockham::network::node_multiaddr(ip, 9000, peer_id).to_string(),
+        format!("/ip4/10.0.0.7/tcp/9000/p2p/{}", peer_id)
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -5,7 +5,7 @@ use std::fs;
 
 #[test]
 fn test_redb_persistence() {
-    let _ = env_logger::try_init();
+    let _ = tracing_subscriber::fmt().try_init();
 
     // 1. Setup temp DB path
     let db_path = "./db/test_persistence_redb.db";
@@ -0,0 +1,73 @@
+use ockham::crypto::{Hash, generate_keypair, sign};
+use ockham::precompiles::{
+    BLS_VERIFY_ADDRESS, PrecompileContext, PrecompileRegistry, STAKING_ADDRESS,
+};
+use ockham::storage::MemStorage;
+use ockham::types::{Address, Block, QuorumCertificate, Transaction, U256};
+use std::sync::Arc;
+
+#[test]
+fn test_precompile_registry() {
+    let registry = PrecompileRegistry::with_defaults();
+
+    // Standard precompiles (ecrecover, sha256) and Ockham specific ones
+    let ecrecover = Address::with_last_byte(1);
+    let sha256 = Address::with_last_byte(2);
+    assert!(registry.contains(&ecrecover));
+    assert!(registry.contains(&sha256));
+    assert!(registry.contains(&BLS_VERIFY_ADDRESS));
+    assert!(registry.contains(&STAKING_ADDRESS));
+    assert!(!registry.contains(&Address::ZERO));
+
+    let storage = Arc::new(MemStorage::new());
+    let mut db = ockham::state::StateManager::new(storage, None);
+    let (pk, sk) = generate_keypair();
+    let block = Block::new_dummy(pk.clone(), 1, Hash::default(), QuorumCertificate::default());
+    let tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::ZERO,
+        gas_limit: 1_000_000,
+        to: Some(BLS_VERIFY_ADDRESS),
+        value: U256::ZERO,
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    let mut ctx = PrecompileContext {
+        db: &mut db,
+        block: &block,
+        tx: &tx,
+    };
+
+    // sha256("")
+    let out = registry
+        .get(&sha256)
+        .unwrap()
+        .call(&mut ctx, &[], 1_000)
+        .unwrap();
+    assert_eq!(
+        hex::encode(out.output),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+
+    // BLS verify
+    let message = b"ockham";
+    let signature = sign(&sk, message);
+    let mut input = pk.0.to_bytes().to_vec();
+    input.extend_from_slice(&signature.0.to_bytes());
+    input.extend_from_slice(message);
+
+    let bls = registry.get(&BLS_VERIFY_ADDRESS).unwrap();
+    let out = bls.call(&mut ctx, &input, 1_000_000).unwrap();
+    assert_eq!(out.output[31], 1);
+
+    input.push(0); // tamper with the message
+    let out = bls.call(&mut ctx, &input, 1_000_000).unwrap();
+    assert_eq!(out.output[31], 0);
+
+    // Not enough gas
+    assert!(bls.call(&mut ctx, &input, 10).is_err());
+}
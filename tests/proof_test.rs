@@ -0,0 +1,161 @@
+use ockham::crypto::Hash;
+use ockham::rpc::{OckhamRpcImpl, OckhamRpcServer};
+use ockham::state::{StateManager, account_leaf, verify_proof};
+use ockham::storage::{AccountInfo, ChainHead, MemStorage, Storage};
+use ockham::types::{Address, U256};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_account_and_storage_proofs() {
+    let storage = Arc::new(MemStorage::new());
+    let state = StateManager::new(storage.clone(), None);
+    let contract = Address::from([0xcc; 20]);
+    let info = AccountInfo {
+        nonce: 1,
+        balance: U256::from(500u64),
+        ..Default::default()
+    };
+    state.commit_account(contract, info.clone()).unwrap();
+    state
+        .commit_storage(contract, U256::from(1u64), U256::from(42u64))
+        .unwrap();
+    let root = state.root();
+
+    let slots = [U256::from(1u64), U256::from(2u64)];
+    let proof = state.get_proof(contract, &slots).unwrap();
+    assert_eq!(proof.account, Some(info.clone()));
+    assert_eq!(proof.storage_proofs[0].value, U256::from(42u64));
+    assert_eq!(proof.storage_proofs[1].value, U256::ZERO); // Exclusion
+    assert!(verify_proof(&root, &proof));
+    assert!(!verify_proof(&Hash([1u8; 32]), &proof));
+
+    let mut tampered = proof.clone();
+    tampered.storage_proofs[0].value = U256::from(43u64);
+    assert!(!verify_proof(&root, &tampered));
+    let mut tampered = proof.clone();
+    tampered.account.as_mut().unwrap().balance = U256::from(1u64);
+    assert!(!verify_proof(&root, &tampered));
+
+    // Accounts that do not exist
+    let absent = state.get_proof(Address::from([0xdd; 20]), &[]).unwrap();
+    assert_eq!(absent.account_hash, Hash::default());
+    assert!(absent.account.is_none());
+    assert!(verify_proof(&root, &absent));
+
+    // Older roots stay provable; the changed account's preimage is gone
+    state
+        .commit_account(contract, AccountInfo { nonce: 2, ..info })
+        .unwrap();
+    let old = state
+        .fork(root, storage.clone())
+        .get_proof(contract, &slots)
+        .unwrap();
+    assert_eq!(old.account_hash, proof.account_hash);
+    assert!(old.account.is_none());
+    assert!(verify_proof(&root, &old));
+}
+
+#[test]
+fn test_rpc_get_proof() {
+    let storage = Arc::new(MemStorage::new());
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(
+            state_manager.clone(),
+            ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        ),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+    assert!(
+        rpc.get_proof(Address::ZERO, vec![], None)
+            .unwrap()
+            .is_none()
+    );
+
+    let root = {
+        let state = state_manager.lock().unwrap();
+        state
+            .commit_account(Address::ZERO, AccountInfo::default())
+            .unwrap();
+        state.root()
+    };
+    storage
+        .save_chain_head(&ChainHead {
+            view: 1,
+            block_hash: Hash([1u8; 32]),
+            state_root: root,
+        })
+        .unwrap();
+
+    let proof = rpc
+        .get_proof(Address::ZERO, vec![U256::from(7u64)], Some("latest".into()))
+        .unwrap()
+        .unwrap();
+    assert_eq!(proof.state_root, root);
+    assert!(verify_proof(&root, &proof));
+
+    // Unknown block numbers
+    assert!(
+        rpc.get_proof(Address::ZERO, vec![], Some("0x9".into()))
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_storage_trie_folded_into_account_leaf() {
+    let storage = Arc::new(MemStorage::new());
+    let state = StateManager::new(storage.clone(), None);
+    let contract = Address::from([0xcc; 20]);
+    let info = AccountInfo {
+        nonce: 1,
+        ..Default::default()
+    };
+    state.commit_account(contract, info.clone()).unwrap();
+    let without_storage = state.root();
+    assert_eq!(
+        storage.get_storage_root(&contract).unwrap(),
+        Hash::default()
+    );
+
+    // A storage write changes the account leaf, and so the state root
+    state
+        .commit_storage(contract, U256::from(1u64), U256::from(42u64))
+        .unwrap();
+    let storage_root = storage.get_storage_root(&contract).unwrap();
+    assert_ne!(storage_root, Hash::default());
+    assert_ne!(state.root(), without_storage);
+    let proof = state.get_proof(contract, &[U256::from(1u64)]).unwrap();
+    assert_eq!(proof.storage_root, storage_root);
+    assert_eq!(
+        proof.account_hash,
+        account_leaf(&ockham::crypto::hash_data(&info), &storage_root)
+    );
+    assert!(verify_proof(&state.root(), &proof));
+
+    // Slots are proven against the storage root the account leaf commits to
+    let mut tampered = proof.clone();
+    tampered.storage_root = Hash([7u8; 32]);
+    assert!(!verify_proof(&state.root(), &tampered));
+
+    // Each account has its own trie: the same slot elsewhere is empty
+    let other = state
+        .get_proof(Address::from([0xdd; 20]), &[U256::from(1u64)])
+        .unwrap();
+    assert_eq!(other.storage_proofs[0].value, U256::ZERO);
+    assert!(verify_proof(&state.root(), &other));
+
+    // Clearing the slot empties the trie and restores the previous root
+    state
+        .commit_storage(contract, U256::from(1u64), U256::ZERO)
+        .unwrap();
+    assert_eq!(
+        storage.get_storage_root(&contract).unwrap(),
+        Hash::default()
+    );
+    assert_eq!(state.root(), without_storage);
+}
@@ -0,0 +1,174 @@
+//! Property tests of Simplex safety and liveness on `ockham::testing::SimNetwork`.
+//!
+//! Each case builds a committee with random latencies, drops and view timeout, then
+//! drives a random sequence of partitions, crashes and restarts through it (proposals,
+//! votes and timeouts interleave as the simulated clock ticks). Invariants:
+//! - no two conflicting blocks are finalized: per view (checked by the harness), and
+//!   every finalized block descends from the lower finalized ones,
+//! - the finalized height of every node never decreases, restarts included,
+//! - once the faults stop and more than 2/3 of the committee is up and connected,
+//!   a new block is finalized.
+
+use ockham::crypto::Hash;
+use ockham::storage::Storage;
+use ockham::testing::{SimConfig, SimNetwork};
+use ockham::types::View;
+use proptest::prelude::*;
+use std::collections::HashSet;
+
+#[derive(Clone, Debug)]
+enum Op {
+    /// Let the network run for this long.
+    Run(u64),
+    /// Cut these nodes off from the others (until `Heal`).
+    Partition(Vec<usize>),
+    Heal,
+    Kill(usize),
+    /// Start a stopped node again from its storage.
+    Restart(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (1u64..=30).prop_map(|ticks| Op::Run(ticks * 100)),
+        1 => prop::collection::vec(0usize..7, 1..3).prop_map(Op::Partition),
+        1 => Just(Op::Heal),
+        1 => (0usize..7).prop_map(Op::Kill),
+        1 => (0usize..7).prop_map(Op::Restart),
+    ]
+}
+
+fn config() -> impl Strategy<Value = SimConfig> {
+    (4usize..=7, any::<u64>(), 1u64..=100, 0.0..0.1, 5u64..=30).prop_map(
+        |(nodes, seed, max_latency_ms, drop_rate, timeout_ticks)| SimConfig {
+            nodes,
+            seed,
+            min_latency_ms: 1,
+            max_latency_ms,
+            drop_rate,
+            view_timeout_ms: timeout_ticks * 100,
+            ..Default::default()
+        },
+    )
+}
+
+/// Nodes that can fail without stopping the rest from forming quorums.
+fn max_faulty(nodes: usize) -> usize {
+    nodes - ((nodes * 2) / 3 + 1)
+}
+
+/// Finalized height of every live node, checked to never go down.
+struct Heights(Vec<View>);
+
+impl Heights {
+    fn check(&mut self, net: &SimNetwork) {
+        for (i, last) in self.0.iter_mut().enumerate() {
+            if let Some(node) = net.node(i) {
+                assert!(
+                    node.finalized_height >= *last,
+                    "node {} finalized height went from {} to {}",
+                    i,
+                    last,
+                    node.finalized_height
+                );
+                *last = node.finalized_height;
+            }
+        }
+    }
+}
+
+/// Every finalized block is an ancestor of the highest one.
+fn check_single_chain(net: &SimNetwork) {
+    let Some((&top_view, &top)) = net.finalized().iter().next_back() else {
+        return;
+    };
+    let block =
+        |hash: &Hash| (0..net.len()).find_map(|i| net.storage(i).get_block(hash).ok().flatten());
+    let mut ancestors = HashSet::new();
+    let mut cursor = block(&top);
+    while let Some(b) = cursor {
+        if b.view == 0 {
+            break;
+        }
+        cursor = block(&b.parent_hash);
+        ancestors.insert(b.parent_hash);
+    }
+    for (view, hash) in net.finalized().range(..top_view) {
+        assert!(
+            ancestors.contains(hash),
+            "block finalized in view {} is not an ancestor of the one in view {}",
+            view,
+            top_view
+        );
+    }
+}
+
+fn run(config: SimConfig, ops: Vec<Op>, crashed: usize) {
+    let n = config.nodes;
+    let mut net = SimNetwork::new(config);
+    let mut heights = Heights(vec![0; n]);
+
+    for op in ops {
+        match op {
+            Op::Run(ms) => {
+                let deadline = net.now() + ms;
+                while net.now() < deadline {
+                    net.step();
+                    heights.check(&net);
+                }
+            }
+            Op::Partition(group) => {
+                let group: Vec<_> = group.into_iter().map(|i| i % n).collect();
+                net.partition(&group);
+            }
+            Op::Heal => net.heal(),
+            Op::Kill(i) => net.kill(i % n),
+            Op::Restart(i) => {
+                if !net.is_alive(i % n) {
+                    net.restart(i % n);
+                }
+            }
+        }
+        heights.check(&net);
+    }
+    check_single_chain(&net);
+
+    // The faults stop: messages get through again, and at most f nodes stay down
+    net.heal();
+    net.config.drop_rate = 0.0;
+    let crashed = crashed.min(max_faulty(n));
+    for i in 0..n {
+        if i < crashed {
+            net.kill(i);
+        } else if !net.is_alive(i) {
+            net.restart(i);
+        }
+    }
+    let base = net.last_finalized();
+    let progressed = net.run_until(180_000, |net| {
+        heights.check(net);
+        net.last_finalized() > base
+    });
+    assert!(
+        progressed,
+        "no block finalized after view {} with {} of {} nodes up",
+        base,
+        n - crashed,
+        n
+    );
+    check_single_chain(&net);
+    assert_eq!(net.stats().evidence, 0);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(24))]
+
+    #[test]
+    fn prop_simplex_safe_and_live(
+        config in config(),
+        ops in prop::collection::vec(op(), 0..12),
+        crashed in 0usize..=2,
+    ) {
+        run(config, ops, crashed);
+    }
+}
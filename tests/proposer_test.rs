@@ -0,0 +1,307 @@
+use ockham::consensus::{ConsensusAction, ProposalReady, ProposerConfig, SimplexState};
+use ockham::crypto::{Hash, generate_keypair_from_id, sign};
+use ockham::storage::{MemStorage, Storage};
+use ockham::types::{Address, Transaction, U256, calculate_transactions_root};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_operator_txs_and_fee_recipient() {
+    let (pk, sk) = generate_keypair_from_id(0);
+    let (op_pk, op_sk) = generate_keypair_from_id(1);
+    let committee = vec![pk.clone()];
+
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    let operator = Address::from_slice(&ockham::types::keccak256(op_pk.0.to_bytes())[12..]);
+    let fee_recipient = Address::from_slice(&[0xfe; 20]);
+
+    let mut node = SimplexState::new(
+        pk.clone(),
+        sk.clone(),
+        committee,
+        storage.clone(),
+        tx_pool.clone(),
+        executor.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+    .with_proposer_config(ProposerConfig {
+        fee_recipient,
+        operator_accounts: [operator].into_iter().collect(),
+        ..Default::default()
+    });
+
+    storage
+        .save_account(
+            &operator,
+            &ockham::storage::AccountInfo {
+                nonce: 0,
+                balance: U256::from(10u64).pow(U256::from(18)),
+                code_hash: Hash(ockham::types::keccak256([]).into()),
+            },
+        )
+        .unwrap();
+
+    let make_tx = |pk: &ockham::crypto::PublicKey, sk, tip: u64| {
+        let mut tx = Transaction {
+            chain_id: 1337,
+            nonce: 0,
+            max_priority_fee_per_gas: U256::from(tip),
+            max_fee_per_gas: U256::from(100_000_000u64),
+            gas_limit: 21000,
+            to: Some(Address::ZERO),
+            value: U256::ZERO,
+            data: vec![].into(),
+            access_list: vec![],
+            public_key: pk.clone(),
+            signature: ockham::crypto::Signature::default(),
+        };
+        tx.signature = sign(sk, &tx.sighash().0);
+        tx
+    };
+
+    // The regular tx pays a higher tip, the operator tx pays none
+    tx_pool
+        .add_transaction(make_tx(&pk, &sk, 1_000_000))
+        .unwrap();
+    tx_pool.add_transaction(make_tx(&op_pk, &op_sk, 0)).unwrap();
+
+    let actions = node.try_propose().unwrap();
+    let block = actions
+        .into_iter()
+        .find_map(|a| match a {
+            ConsensusAction::BroadcastBlock(b) => Some(b),
+            _ => None,
+        })
+        .expect("Leader should propose");
+
+    assert_eq!(block.payload.len(), 2);
+    assert_eq!(block.payload[0].sender(), operator);
+    assert_eq!(block.metadata.operator_txs, 1);
+    assert_eq!(block.metadata.fee_recipient, fee_recipient);
+
+    // An executor for another chain rejects the block's transactions
+    let other_chain = ockham::vm::Executor::new(
+        Arc::new(Mutex::new(ockham::state::StateManager::new(
+            Arc::new(MemStorage::new()),
+            None,
+        ))),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+    .with_chain_id(1);
+    assert!(other_chain.execute_block(&mut block.clone()).is_err());
+
+    // Priority fees go to the configured recipient
+    let mut committed = block.clone();
+    executor.execute_block(&mut committed).unwrap();
+    let recipient = storage.get_account(&fee_recipient).unwrap().unwrap();
+    assert_eq!(recipient.balance, U256::from(1_000_000u64 * 21000));
+}
+
+#[test]
+fn test_pipelined_proposal() {
+    let (pk, sk) = generate_keypair_from_id(0);
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        pk.clone(),
+        sk,
+        vec![pk],
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+    .with_proposal_pipeline();
+    let view = node.current_view;
+
+    // Becoming leader only builds the job; nothing is executed or sent yet
+    assert!(node.try_propose().unwrap().is_empty());
+    let job = node
+        .take_proposal_job()
+        .expect("Leader should build a proposal");
+    assert!(node.take_proposal_job().is_none());
+    assert_eq!(job.view(), view);
+    assert_eq!(node.last_voted_view, 0);
+
+    // Executed elsewhere, then signed, stored and voted for
+    let ready = std::thread::spawn(move || job.execute()).join().unwrap();
+    let actions = node.on_proposal_ready(ready).unwrap();
+    let block = actions
+        .iter()
+        .find_map(|a| match a {
+            ConsensusAction::BroadcastBlock(b) => Some(b.clone()),
+            _ => None,
+        })
+        .expect("Proposal should be broadcast");
+    assert!(block.verify_signature());
+    let block_hash = ockham::crypto::hash_data(&block);
+    assert!(storage.get_block(&block_hash).unwrap().is_some());
+    assert!(actions.iter().any(|a| matches!(
+        a,
+        ConsensusAction::BroadcastVote(v) if v.block_hash == block_hash
+    )));
+    assert_eq!(node.last_voted_view, view);
+
+    // Results for a view we already left are dropped
+    let stale = ProposalReady {
+        view: view - 1,
+        block: Some(block),
+    };
+    assert!(node.on_proposal_ready(stale).unwrap().is_empty());
+}
+
+static SLOT_CLOCK: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+#[test]
+fn test_minimum_block_interval() {
+    use std::sync::atomic::Ordering;
+
+    let (pk, sk) = generate_keypair_from_id(0);
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        pk.clone(),
+        sk,
+        vec![pk],
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+    .with_clock(|| SLOT_CLOCK.load(Ordering::SeqCst));
+    let mut state = storage.get_consensus_state().unwrap().unwrap();
+    state.params.min_block_interval = 10;
+    storage.save_consensus_state(&state).unwrap();
+
+    // The genesis is stamped 0: the first slot starts at 10
+    SLOT_CLOCK.store(4, Ordering::SeqCst);
+    assert!(node.try_propose().unwrap().is_empty());
+    assert_eq!(node.next_slot(), Some(10));
+    assert!(node.on_slot().unwrap().is_empty());
+    assert_eq!(node.next_slot(), Some(10));
+
+    SLOT_CLOCK.store(10, Ordering::SeqCst);
+    let mut queue = node.on_slot().unwrap();
+    assert_eq!(node.next_slot(), None);
+    let first = queue
+        .iter()
+        .find_map(|a| match a {
+            ConsensusAction::BroadcastBlock(b) => Some(b.clone()),
+            _ => None,
+        })
+        .expect("Leader should propose once its slot starts");
+    assert_eq!(first.timestamp, 10);
+
+    // Our own vote notarizes it; the next proposal waits for the following slot
+    while let Some(action) = queue.pop() {
+        if let ConsensusAction::BroadcastVote(vote) = action {
+            queue.extend(node.on_vote(vote).unwrap_or_default());
+        }
+    }
+    assert_eq!(node.current_view, first.view + 1);
+    assert_eq!(node.next_slot(), Some(20));
+
+    // A deferred proposal for a view we left is dropped
+    node.current_view += 1;
+    SLOT_CLOCK.store(25, Ordering::SeqCst);
+    assert!(node.on_slot().unwrap().is_empty());
+    assert_eq!(node.next_slot(), None);
+}
+
+#[test]
+fn test_proposal_time_budget() {
+    let (pk, sk) = generate_keypair_from_id(0);
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        pk.clone(),
+        sk.clone(),
+        vec![pk.clone()],
+        storage.clone(),
+        tx_pool.clone(),
+        executor.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+    .with_proposer_config(ProposerConfig {
+        time_budget: Some(Duration::ZERO),
+        ..Default::default()
+    });
+
+    let txs: Vec<Transaction> = (0..2)
+        .map(|nonce| {
+            let mut tx = Transaction {
+                chain_id: 1337,
+                nonce,
+                max_priority_fee_per_gas: U256::ZERO,
+                max_fee_per_gas: U256::from(100_000_000u64),
+                gas_limit: 21000,
+                to: Some(Address::ZERO),
+                value: U256::ZERO,
+                data: vec![].into(),
+                access_list: vec![],
+                public_key: pk.clone(),
+                signature: ockham::crypto::Signature::default(),
+            };
+            tx.signature = sign(&sk, &tx.sighash().0);
+            tx
+        })
+        .collect();
+    for tx in &txs {
+        tx_pool.add_transaction(tx.clone()).unwrap();
+    }
+
+    // Out of time before selecting anything: the leader still proposes, and votes, in time
+    let actions = node.try_propose().unwrap();
+    let block = actions
+        .iter()
+        .find_map(|a| match a {
+            ConsensusAction::BroadcastBlock(b) => Some(b.clone()),
+            _ => None,
+        })
+        .expect("Leader should propose");
+    assert!(block.payload.is_empty());
+    assert!(
+        actions
+            .iter()
+            .any(|a| matches!(a, ConsensusAction::BroadcastVote(_)))
+    );
+    assert_eq!(tx_pool.len(), 2);
+
+    // Execution past the deadline cuts the payload it has not reached
+    let mut late = block.clone();
+    late.payload = txs;
+    late.metadata.operator_txs = 1;
+    late.transactions_root = calculate_transactions_root(&late.payload);
+    executor
+        .execute_block_until(&mut late, Some(Instant::now()))
+        .unwrap();
+    assert!(late.payload.is_empty());
+    assert_eq!(late.metadata.operator_txs, 0);
+    assert_eq!(late.transactions_root, calculate_transactions_root(&[]));
+    assert_eq!(late.gas_used, 0);
+}
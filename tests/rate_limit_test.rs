@@ -0,0 +1,106 @@
+use ockham::rate_limit::{
+    ClientKey, MAX_TRACKED_CLIENTS, MethodClass, Quota, RateLimitConfig, RateLimiter, forwarded_ip,
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_token_buckets_per_client() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        transactions: Quota {
+            per_sec: 2,
+            burst: 3,
+        },
+        ..Default::default()
+    });
+    let alice = ClientKey::Connection(1);
+    let bob = ClientKey::Ip("10.0.0.2".parse().unwrap());
+    let start = Instant::now();
+
+    // A burst goes through, then calls wait for the refill
+    for _ in 0..3 {
+        assert!(
+            limiter
+                .check(&alice, MethodClass::Transaction, start)
+                .is_ok()
+        );
+    }
+    let retry = limiter
+        .check(&alice, MethodClass::Transaction, start)
+        .unwrap_err();
+    assert_eq!(retry, Duration::from_millis(500));
+
+    // Other clients and other classes have their own buckets
+    assert!(limiter.check(&bob, MethodClass::Transaction, start).is_ok());
+    assert!(limiter.check(&alice, MethodClass::HeavyRead, start).is_ok());
+
+    let later = start + Duration::from_millis(500);
+    assert!(
+        limiter
+            .check(&alice, MethodClass::Transaction, later)
+            .is_ok()
+    );
+    assert!(
+        limiter
+            .check(&alice, MethodClass::Transaction, later)
+            .is_err()
+    );
+
+    // Refills never exceed the burst
+    let idle = start + Duration::from_secs(60);
+    for _ in 0..3 {
+        assert!(
+            limiter
+                .check(&alice, MethodClass::Transaction, idle)
+                .is_ok()
+        );
+    }
+    assert!(
+        limiter
+            .check(&alice, MethodClass::Transaction, idle)
+            .is_err()
+    );
+
+    // Idle buckets are forgotten once too many clients are tracked
+    for i in 0..MAX_TRACKED_CLIENTS as u64 {
+        limiter
+            .check(
+                &ClientKey::Connection(100 + i),
+                MethodClass::HeavyRead,
+                idle,
+            )
+            .unwrap();
+    }
+    let much_later = idle + Duration::from_secs(60);
+    assert!(
+        limiter
+            .check(&ClientKey::Unknown, MethodClass::HeavyRead, much_later)
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_method_classes_and_config() {
+    assert_eq!(
+        MethodClass::of("send_transaction"),
+        Some(MethodClass::Transaction)
+    );
+    assert_eq!(MethodClass::of("get_logs"), Some(MethodClass::HeavyRead));
+    assert_eq!(MethodClass::of("chain_id"), None);
+
+    assert_eq!(
+        "5:20".parse::<Quota>(),
+        Ok(Quota {
+            per_sec: 5,
+            burst: 20
+        })
+    );
+    assert!("5".parse::<Quota>().is_err());
+    assert!("a:b".parse::<Quota>().is_err());
+
+    let mut headers = http::HeaderMap::new();
+    assert_eq!(forwarded_ip(&headers), None);
+    headers.insert("x-real-ip", "10.0.0.9".parse().unwrap());
+    assert_eq!(forwarded_ip(&headers), Some("10.0.0.9".parse().unwrap()));
+    headers.insert("x-forwarded-for", "203.0.113.7, 10.0.0.1".parse().unwrap());
+    assert_eq!(forwarded_ip(&headers), Some("203.0.113.7".parse().unwrap()));
+}
@@ -0,0 +1,179 @@
+use ockham::consensus::{ConsensusAction, SimplexState};
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, hash_data};
+use ockham::storage::{MemStorage, Storage, VoteRecord};
+use ockham::types::{Block, QuorumCertificate, U256, VoteType};
+use std::sync::{Arc, Mutex};
+
+// A fresh node (empty executor state) on top of `storage`, as after a restart.
+fn make_node(keys: &[(PublicKey, PrivateKey)], storage: Arc<MemStorage>) -> SimplexState {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+}
+
+fn make_block(
+    keys: &[(PublicKey, PrivateKey)],
+    view: u64,
+    parent: Hash,
+    state_root: Hash,
+) -> Block {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let mut block = Block::new(
+        keys[0].0.clone(),
+        view,
+        parent,
+        QuorumCertificate::default(),
+        state_root,
+        Hash::default(),
+        vec![],
+        U256::ZERO,
+        0,
+        vec![],
+        hash_data(&committee),
+    );
+    block.height = view; // No timeouts: one block per view
+    block.sign(&keys[0].1);
+    block
+}
+
+#[test]
+fn test_recovery_replays_interrupted_commit() {
+    let keys = vec![generate_keypair_from_id(0)];
+    let storage = Arc::new(MemStorage::new());
+    let mut node = make_node(&keys, storage.clone());
+    let genesis_hash = node.preferred_block;
+    assert_eq!(storage.get_chain_head().unwrap().unwrap().view, 0);
+
+    // View 1 is notarized...
+    let b1 = make_block(&keys, 1, genesis_hash, Hash::default());
+    let b1_hash = hash_data(&b1);
+    let actions = node.on_proposal(b1).unwrap();
+    for action in actions {
+        if let ConsensusAction::BroadcastVote(vote) = action {
+            node.on_vote(vote).unwrap();
+        }
+    }
+    assert!(storage.get_qc(1).unwrap().is_some());
+
+    // ...and finalized, but the node crashes before committing its state
+    let mut saved = storage.get_consensus_state().unwrap().unwrap();
+    saved.finalized_height = 1;
+    storage.save_consensus_state(&saved).unwrap();
+    drop(node);
+
+    let mut restarted = make_node(&keys, storage.clone());
+    let report = restarted.recover().unwrap();
+    assert_eq!(report.replayed, vec![1]);
+    assert!(report.discarded.is_empty());
+
+    let head = report.head.unwrap();
+    assert_eq!((head.view, head.block_hash), (1, b1_hash));
+    assert_eq!(storage.get_chain_head().unwrap(), Some(head.clone()));
+    assert_eq!(restarted.engine.state_root(), head.state_root);
+
+    // Recovering again is a no-op
+    let mut again = make_node(&keys, storage.clone());
+    let report = again.recover().unwrap();
+    assert!(report.replayed.is_empty());
+    assert_eq!(report.head, Some(head));
+}
+
+#[test]
+fn test_recovery_discards_invalid_unfinalized_blocks() {
+    let keys = vec![generate_keypair_from_id(0)];
+    let storage = Arc::new(MemStorage::new());
+    let node = make_node(&keys, storage.clone());
+    let genesis_hash = node.preferred_block;
+    drop(node);
+
+    // A stored (unfinalized) block that does not re-execute to its state root
+    let bad = make_block(&keys, 1, genesis_hash, Hash([9u8; 32]));
+    storage.save_block(&bad).unwrap();
+    let mut saved = storage.get_consensus_state().unwrap().unwrap();
+    saved.preferred_block = hash_data(&bad);
+    saved.preferred_view = 1;
+    storage.save_consensus_state(&saved).unwrap();
+
+    let mut restarted = make_node(&keys, storage.clone());
+    let report = restarted.recover().unwrap();
+    assert_eq!(report.discarded, vec![1]);
+    assert!(report.revalidated.is_empty());
+    assert_eq!(restarted.preferred_block, genesis_hash);
+    assert_eq!(restarted.preferred_view, 0);
+    let persisted = storage.get_consensus_state().unwrap().unwrap();
+    assert_eq!(persisted.preferred_block, genesis_hash);
+}
+
+#[test]
+fn test_recovery_honors_vote_journal() {
+    let keys = vec![generate_keypair_from_id(0)];
+    let storage = Arc::new(MemStorage::new());
+    let mut node = make_node(&keys, storage.clone());
+    let genesis_hash = node.preferred_block;
+
+    // Votes are journaled before they are sent
+    let b1 = make_block(&keys, 1, genesis_hash, Hash::default());
+    let b1_hash = hash_data(&b1);
+    node.on_proposal(b1.clone()).unwrap();
+    let records = storage.get_vote_records(1).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+        (records[0].view, records[0].block_hash, records[0].vote_type),
+        (1, b1_hash, VoteType::Notarize)
+    );
+    drop(node);
+
+    // A node that voted for another block in view 1, then crashed before saving its state
+    let fresh = Arc::new(MemStorage::new());
+    drop(make_node(&keys, fresh.clone()));
+    fresh
+        .append_vote_record(&VoteRecord {
+            view: 1,
+            block_hash: Hash([9u8; 32]),
+            vote_type: VoteType::Notarize,
+            timestamp: 0,
+        })
+        .unwrap();
+    assert_eq!(
+        fresh
+            .get_consensus_state()
+            .unwrap()
+            .unwrap()
+            .last_voted_view,
+        0
+    );
+
+    let mut restarted = make_node(&keys, fresh.clone());
+    restarted.recover().unwrap();
+    assert_eq!(restarted.last_voted_view, 1);
+    assert_eq!(
+        fresh
+            .get_consensus_state()
+            .unwrap()
+            .unwrap()
+            .last_voted_view,
+        1
+    );
+
+    // So it does not vote for b1 in the same view
+    let actions = restarted.on_proposal(b1).unwrap();
+    assert!(
+        !actions
+            .iter()
+            .any(|a| matches!(a, ConsensusAction::BroadcastVote(_)))
+    );
+    assert_eq!(fresh.get_vote_records(1).unwrap().len(), 1);
+}
@@ -0,0 +1,199 @@
+use ockham::consensus::{ConsensusAction, ConsensusError, SimplexState};
+use ockham::crypto::{
+    Hash, PrivateKey, PublicKey, Signature, generate_keypair_from_id, hash_data, sign,
+};
+use ockham::precompiles::BLS_VERIFY_ADDRESS;
+use ockham::storage::MemStorage;
+use ockham::types::{
+    Block, DEFAULT_BLOCK_GAS_LIMIT, DEFAULT_CHAIN_ID, DEFAULT_MAX_BLOCK_SIZE, DEFAULT_MAX_TX_SIZE,
+    EquivocationEvidence, QuorumCertificate, SignerBitmap, Transaction, U256, Vote, VoteType,
+    calculate_transactions_root, wire_size,
+};
+use std::sync::{Arc, Mutex};
+
+fn make_node(keys: &[(PublicKey, PrivateKey)]) -> SimplexState {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        DEFAULT_BLOCK_GAS_LIMIT,
+    )
+}
+
+// A transaction from an account without funds, sending everything there is.
+fn make_tx(key: &PrivateKey) -> Transaction {
+    let mut tx = Transaction {
+        chain_id: DEFAULT_CHAIN_ID,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::MAX,
+        max_fee_per_gas: U256::MAX,
+        gas_limit: 1_000_000,
+        to: Some(BLS_VERIFY_ADDRESS),
+        value: U256::MAX,
+        data: vec![0xff; 64].into(),
+        access_list: vec![],
+        public_key: key.public_key(),
+        signature: Signature::default(),
+    };
+    tx.signature = sign(key, &tx.sighash().0);
+    tx
+}
+
+/// Blocks a remote peer could send, each broken in a different way (validly signed by
+/// the leader, so they get past the signature check).
+fn malformed_blocks(keys: &[(PublicKey, PrivateKey)], valid: &Block) -> Vec<(&'static str, Block)> {
+    let outsider = generate_keypair_from_id(99);
+    let resign = |mut block: Block| {
+        block.sign(&keys[0].1);
+        block
+    };
+    let mut cases = vec![];
+
+    let mut block = valid.clone();
+    block.sign(&outsider.1);
+    cases.push(("foreign signature", block));
+
+    let mut block = valid.clone();
+    block.parent_hash = Hash([7u8; 32]);
+    cases.push(("unknown parent", resign(block)));
+
+    let mut block = valid.clone();
+    block.payload = vec![make_tx(&outsider.1)];
+    cases.push(("transactions root mismatch", resign(block.clone())));
+    block.transactions_root = calculate_transactions_root(&block.payload);
+    cases.push(("unfunded maximal transaction", resign(block)));
+
+    let mut block = valid.clone();
+    block.metadata.operator_txs = u32::MAX;
+    cases.push(("operator txs beyond the payload", resign(block)));
+
+    let mut block = valid.clone();
+    block.gas_used = u64::MAX;
+    cases.push(("gas used overflow", resign(block)));
+
+    let mut block = valid.clone();
+    block.height = u64::MAX;
+    cases.push(("height overflow", resign(block)));
+
+    let mut block = valid.clone();
+    block.timestamp = u64::MAX;
+    cases.push(("timestamp overflow", resign(block)));
+
+    let mut block = valid.clone();
+    block.committee_hash = Hash([1u8; 32]);
+    cases.push(("committee hash", resign(block)));
+
+    let mut block = valid.clone();
+    block.justify = QuorumCertificate {
+        view: u64::MAX,
+        block_hash: Hash([3u8; 32]),
+        signature: Signature::default(),
+        epoch: u64::MAX,
+        signers: SignerBitmap(vec![0xff; 1024]),
+    };
+    cases.push(("garbage justify QC", resign(block)));
+
+    let mut block = valid.clone();
+    let vote = |block_hash| Vote {
+        view: u64::MAX,
+        block_hash,
+        vote_type: VoteType::Notarize,
+        author: outsider.0.clone(),
+        signature: Signature::default(),
+    };
+    block.evidence = vec![EquivocationEvidence {
+        vote_a: vote(Hash([1u8; 32])),
+        vote_b: vote(Hash([2u8; 32])),
+    }];
+    cases.push(("forged evidence", resign(block)));
+
+    cases
+}
+
+/// An empty, signed proposal for view 1 on top of `parent`.
+fn first_block(keys: &[(PublicKey, PrivateKey)], parent: Hash) -> Block {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let mut block = Block::new(
+        keys[0].0.clone(),
+        1,
+        parent,
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        vec![],
+        U256::from(10_000_000),
+        0,
+        vec![],
+        hash_data(&committee),
+    );
+    block.height = 1;
+    block.sign(&keys[0].1);
+    block
+}
+
+#[test]
+fn test_malformed_blocks_do_not_crash_the_node() {
+    let keys = vec![generate_keypair_from_id(0)];
+    let mut node = make_node(&keys);
+    let valid = first_block(&keys, node.preferred_block);
+
+    // Neither as proposals nor as sync responses do they take the node down
+    for (name, block) in malformed_blocks(&keys, &valid) {
+        let proposal = node.on_proposal(block.clone());
+        let response = node.on_block_response(block);
+        println!("{}: {:?} / {:?}", name, proposal.is_ok(), response.is_ok());
+    }
+
+    // And it still votes for the next valid proposal (the leader equivocated in view 1)
+    let mut next = valid;
+    next.view = 2;
+    next.sign(&keys[0].1);
+    let actions = node.on_proposal(next).unwrap();
+    assert!(
+        actions
+            .iter()
+            .any(|a| matches!(a, ConsensusAction::BroadcastVote(_)))
+    );
+}
+
+#[test]
+fn test_oversized_block_is_rejected() {
+    let keys = vec![generate_keypair_from_id(0)];
+    let mut node = make_node(&keys);
+    let sender = generate_keypair_from_id(1);
+
+    // Each transaction is within the size limit, all of them together are not
+    let mut block = first_block(&keys, node.preferred_block);
+    block.payload = (0..40)
+        .map(|nonce| {
+            let mut tx = make_tx(&sender.1);
+            tx.nonce = nonce;
+            tx.data = vec![0xff; 30 * 1024].into();
+            tx.signature = sign(&sender.1, &tx.sighash().0);
+            tx
+        })
+        .collect();
+    assert!(wire_size(&block.payload[0]) as u64 <= DEFAULT_MAX_TX_SIZE);
+    block.transactions_root = calculate_transactions_root(&block.payload);
+    block.sign(&keys[0].1);
+
+    assert!(matches!(
+        node.on_proposal(block.clone()),
+        Err(ConsensusError::BlockTooLarge {
+            limit: DEFAULT_MAX_BLOCK_SIZE,
+            ..
+        })
+    ));
+    assert!(node.on_block_response(block).is_err());
+}
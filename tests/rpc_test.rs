@@ -18,8 +18,11 @@ async fn test_rpc_get_status() {
         committee: vec![],
         pending_validators: vec![],
         exiting_validators: vec![],
-        stakes: HashMap::new(),
         inactivity_scores: HashMap::new(),
+        params: Default::default(),
+        rewards: HashMap::new(),
+        proposals: vec![],
+        missed_attestations: HashMap::new(),
     };
     storage.save_consensus_state(&state).unwrap();
 
@@ -82,8 +85,11 @@ async fn test_rpc_get_block() {
         committee: vec![],
         pending_validators: vec![],
         exiting_validators: vec![],
-        stakes: HashMap::new(),
         inactivity_scores: HashMap::new(),
+        params: Default::default(),
+        rewards: HashMap::new(),
+        proposals: vec![],
+        missed_attestations: HashMap::new(),
     };
     storage.save_consensus_state(&state).unwrap();
 
@@ -142,7 +148,7 @@ async fn test_rpc_get_transaction_count() {
     let storage = Arc::new(MemStorage::new());
 
     // Create an account with a specific nonce
-    let (pk, _) = ockham::crypto::generate_keypair();
+    let (pk, sk) = ockham::crypto::generate_keypair();
     let pk_bytes = pk.0.to_bytes();
     let hash = ockham::types::keccak256(pk_bytes);
     let address = ockham::types::Address::from_slice(&hash[12..]);
@@ -151,7 +157,6 @@ async fn test_rpc_get_transaction_count() {
         nonce: 42,
         balance: ockham::types::U256::ZERO,
         code_hash: ockham::crypto::Hash(ockham::types::keccak256([]).into()),
-        code: None,
     };
     storage.save_account(&address, &account).unwrap();
 
@@ -164,24 +169,51 @@ async fn test_rpc_get_transaction_count() {
     let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
     let rpc = OckhamRpcImpl::new(
         storage,
-        tx_pool,
+        tx_pool.clone(),
         executor,
         ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
         tx_sender,
     );
 
     // Call RPC
-    let result = rpc.get_transaction_count(address);
+    let result = rpc.get_transaction_count(address, None);
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 42);
 
+    // "pending" counts the sender's transactions waiting in the pool
+    let mut tx = ockham::types::Transaction {
+        chain_id: 1337,
+        nonce: 42,
+        max_priority_fee_per_gas: ockham::types::U256::ZERO,
+        max_fee_per_gas: ockham::types::U256::from(10_000_000),
+        gas_limit: 21000,
+        to: Some(ockham::types::Address::ZERO),
+        value: ockham::types::U256::ZERO,
+        data: ockham::types::Bytes::new(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = ockham::crypto::sign(&sk, &tx.sighash().0);
+    tx_pool.add_transaction(tx).unwrap();
+    assert_eq!(rpc.get_transaction_count(address, None).unwrap(), 42);
+    assert_eq!(
+        rpc.get_transaction_count(address, Some("pending".into()))
+            .unwrap(),
+        43
+    );
+    assert!(
+        rpc.get_transaction_count(address, Some("earliest".into()))
+            .is_err()
+    );
+
     // Test non-existent account
     let (pk2, _) = ockham::crypto::generate_keypair();
     let pk2_bytes = pk2.0.to_bytes();
     let hash2 = ockham::types::keccak256(pk2_bytes);
     let address2 = ockham::types::Address::from_slice(&hash2[12..]);
 
-    let result2 = rpc.get_transaction_count(address2);
+    let result2 = rpc.get_transaction_count(address2, None);
     assert!(result2.is_ok());
     assert_eq!(result2.unwrap(), 0);
 }
@@ -203,7 +235,6 @@ async fn test_rpc_extended() {
         nonce: 1,
         balance: ockham::types::U256::from(100),
         code_hash,
-        code: Some(code.clone()),
     };
     storage.save_account(&address, &account).unwrap();
     storage.save_code(&code_hash, &code).unwrap();
@@ -236,6 +267,7 @@ async fn test_rpc_extended() {
         gas_price: None,
         value: None,
         data: None,
+        access_list: vec![],
     };
     let res_call = rpc.call(request, None);
     assert!(res_call.is_ok());
@@ -248,8 +280,865 @@ async fn test_rpc_extended() {
         gas_price: None,
         value: None,
         data: None,
+        access_list: vec![],
     };
     let res_est = rpc.estimate_gas(request_est, None);
     assert!(res_est.is_ok());
     println!("Estimated Gas: {}", res_est.unwrap());
 }
+
+#[tokio::test]
+async fn test_rpc_signed_response() {
+    let storage = Arc::new(MemStorage::new());
+    let (pk, sk) = ockham::crypto::generate_keypair_from_id(0);
+    let block = Block::new(
+        pk.clone(),
+        3,
+        ockham::crypto::Hash::default(),
+        QuorumCertificate::default(),
+        ockham::crypto::Hash::default(),
+        ockham::crypto::Hash::default(),
+        vec![],
+        ockham::types::U256::ZERO,
+        0,
+        vec![],
+        ockham::crypto::Hash::default(),
+    );
+    storage.save_block(&block).unwrap();
+    storage
+        .save_consensus_state(&ConsensusState {
+            preferred_block: ockham::crypto::hash_data(&block),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let make_rpc = || {
+        let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+            storage.clone(),
+            None,
+        )));
+        let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+        OckhamRpcImpl::new(
+            storage.clone(),
+            Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+            ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+            ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+            tx_sender,
+        )
+    };
+
+    // Signing is opt-in
+    assert!(make_rpc().get_latest_block_signed().is_err());
+
+    let rpc = make_rpc().with_signing_key(sk);
+    let response = rpc.get_latest_block_signed().unwrap();
+    assert_eq!(response.signer, pk);
+    assert_eq!(response.result.as_ref().unwrap().view, 3);
+    assert!(response.verify());
+
+    // Tampering with the result (or the method) invalidates the signature
+    let mut tampered = response.clone();
+    tampered.result.as_mut().unwrap().view = 4;
+    assert!(!tampered.verify());
+    let mut renamed = response;
+    renamed.method = "get_block_by_hash".to_string();
+    assert!(!renamed.verify());
+}
+
+#[tokio::test]
+async fn test_rpc_admin() {
+    let storage = Arc::new(MemStorage::new());
+    let make_rpc = || {
+        let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+            storage.clone(),
+            None,
+        )));
+        let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+        OckhamRpcImpl::new(
+            storage.clone(),
+            Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+            ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+            ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+            tx_sender,
+        )
+    };
+
+    // Admin is opt-in
+    assert!(make_rpc().admin_peers().await.is_err());
+
+    let network = ockham::network::Network::new(0).await.unwrap();
+    let rpc = make_rpc().with_admin(network.handle());
+
+    let info = rpc.admin_node_info().await.unwrap();
+    assert!(!info.peer_id.is_empty());
+    assert_eq!(info.chain_id, ockham::types::DEFAULT_CHAIN_ID);
+    assert!(rpc.admin_peers().await.unwrap().is_empty());
+
+    assert!(rpc.admin_add_peer("not a multiaddr".into()).await.is_err());
+    assert!(
+        rpc.admin_add_peer("/ip4/127.0.0.1/tcp/9".into())
+            .await
+            .unwrap()
+    );
+    assert!(rpc.admin_remove_peer("bogus".into()).await.is_err());
+    let stranger = libp2p::PeerId::random().to_string();
+    assert!(!rpc.admin_remove_peer(stranger).await.unwrap());
+}
+
+#[test]
+fn test_revert_reason_encoding() {
+    use ockham::vm::{decode_revert_reason, encode_revert_reason};
+    let long = "x".repeat(40);
+    for reason in ["nope", "", long.as_str()] {
+        let encoded = encode_revert_reason(reason);
+        assert_eq!(encoded.len() % 32, 4);
+        assert_eq!(decode_revert_reason(&encoded).as_deref(), Some(reason));
+    }
+
+    let mut panic = vec![0x4e, 0x48, 0x7b, 0x71];
+    panic.extend_from_slice(&[0u8; 31]);
+    panic.push(0x11);
+    assert_eq!(decode_revert_reason(&panic).as_deref(), Some("Panic(0x11)"));
+
+    assert_eq!(decode_revert_reason(&[0xde, 0xad]), None);
+    assert_eq!(
+        decode_revert_reason(&encode_revert_reason("nope")[..40]),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_rpc_call_revert_reason() {
+    let storage = Arc::new(MemStorage::new());
+    let address = ockham::types::Address::from_slice(&[0x42; 20]);
+
+    // CODECOPY the revert payload (appended after the 12-byte prologue) and REVERT with it
+    let payload = ockham::vm::encode_revert_reason("nope");
+    let len = payload.len() as u8;
+    let mut code_bytes = vec![
+        0x60, len, 0x60, 12, 0x60, 0x00, 0x39, // CODECOPY(0, 12, len)
+        0x60, len, 0x60, 0x00, 0xfd, // REVERT(0, len)
+    ];
+    code_bytes.extend_from_slice(&payload);
+    let code = ockham::types::Bytes::from(code_bytes.clone());
+    let code_hash = ockham::crypto::Hash(ockham::types::keccak256(&code_bytes).into());
+    storage
+        .save_account(
+            &address,
+            &ockham::storage::AccountInfo {
+                nonce: 1,
+                balance: ockham::types::U256::ZERO,
+                code_hash,
+            },
+        )
+        .unwrap();
+    storage.save_code(&code_hash, &code).unwrap();
+
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+    let request = || ockham::rpc::CallRequest {
+        from: None,
+        to: Some(address),
+        gas: Some(100_000),
+        gas_price: None,
+        value: None,
+        data: None,
+        access_list: vec![],
+    };
+
+    let err = rpc.call(request(), None).unwrap_err();
+    assert_eq!(err.code(), 3);
+    assert_eq!(err.message(), "execution reverted: nope");
+    let data: ockham::rpc::RevertData = serde_json::from_str(err.data().unwrap().get()).unwrap();
+    assert_eq!(data.reason.as_deref(), Some("nope"));
+    assert_eq!(data.output.to_vec(), payload);
+    assert!(data.gas_used > 0);
+
+    let err = rpc.estimate_gas(request(), None).unwrap_err();
+    assert_eq!(err.code(), 3);
+}
+
+#[tokio::test]
+async fn test_rpc_get_transaction_receipt() {
+    let storage = Arc::new(MemStorage::new());
+    let (pk, sk) = ockham::crypto::generate_keypair();
+    let sender =
+        ockham::types::Address::from_slice(&ockham::types::keccak256(pk.0.to_bytes())[12..]);
+    storage
+        .save_account(
+            &sender,
+            &ockham::storage::AccountInfo {
+                nonce: 0,
+                balance: ockham::types::U256::from(1_000_000u64),
+                code_hash: ockham::crypto::Hash(ockham::types::keccak256([]).into()),
+            },
+        )
+        .unwrap();
+
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+
+    // A deployment (init code `STOP`) executed in the latest block
+    let mut tx = ockham::types::Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: ockham::types::U256::ZERO,
+        max_fee_per_gas: ockham::types::U256::ZERO,
+        gas_limit: 100_000,
+        to: None,
+        value: ockham::types::U256::ZERO,
+        data: vec![0x00].into(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = ockham::crypto::sign(&sk, &tx.sighash().0);
+    let tx_hash = ockham::crypto::hash_data(&tx);
+    let mut block = Block::new(
+        pk,
+        1,
+        ockham::crypto::Hash::default(),
+        QuorumCertificate::default(),
+        ockham::crypto::Hash::default(),
+        ockham::crypto::Hash::default(),
+        vec![tx],
+        ockham::types::U256::ZERO,
+        0,
+        vec![],
+        ockham::crypto::Hash::default(),
+    );
+    executor.execute_block(&mut block).unwrap();
+    let block_hash = ockham::crypto::hash_data(&block);
+    storage.save_block(&block).unwrap();
+    storage
+        .save_consensus_state(&ConsensusState {
+            view: 2,
+            finalized_height: 1,
+            preferred_block: block_hash,
+            preferred_view: 1,
+            last_voted_view: 1,
+            committee: vec![],
+            pending_validators: vec![],
+            exiting_validators: vec![],
+            inactivity_scores: HashMap::new(),
+            params: Default::default(),
+            rewards: HashMap::new(),
+            proposals: vec![],
+            missed_attestations: HashMap::new(),
+        })
+        .unwrap();
+
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage)),
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+
+    let receipt = rpc.get_transaction_receipt(tx_hash).unwrap().unwrap();
+    assert_eq!(
+        (receipt.block_hash, receipt.view, receipt.index),
+        (block_hash, 1, 0)
+    );
+    assert_eq!(receipt.receipt.status, 1);
+    assert_eq!(receipt.contract_address, Some(sender.create(0)));
+
+    // Unknown transactions have no receipt
+    assert!(
+        rpc.get_transaction_receipt(ockham::crypto::Hash([7u8; 32]))
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_rpc_get_logs() {
+    let storage = Arc::new(MemStorage::new());
+    let (pk, sk) = ockham::crypto::generate_keypair();
+
+    // Runtime code: LOG1 with topic 0xaa and no data
+    let contract = ockham::types::Address::with_last_byte(0xc0);
+    let code: ockham::types::Bytes = hex::decode("60aa60006000a100").unwrap().into();
+    let code_hash = ockham::crypto::Hash(ockham::types::keccak256(&code).into());
+    storage.save_code(&code_hash, &code).unwrap();
+    storage
+        .save_account(
+            &contract,
+            &ockham::storage::AccountInfo {
+                nonce: 1,
+                balance: ockham::types::U256::ZERO,
+                code_hash,
+            },
+        )
+        .unwrap();
+
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+
+    let mut tx = ockham::types::Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: ockham::types::U256::ZERO,
+        max_fee_per_gas: ockham::types::U256::ZERO,
+        gas_limit: 100_000,
+        to: Some(contract),
+        value: ockham::types::U256::ZERO,
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = ockham::crypto::sign(&sk, &tx.sighash().0);
+    let mut block = Block::new(
+        pk,
+        1,
+        ockham::crypto::Hash::default(),
+        QuorumCertificate::default(),
+        ockham::crypto::Hash::default(),
+        ockham::crypto::Hash::default(),
+        vec![tx],
+        ockham::types::U256::ZERO,
+        0,
+        vec![],
+        ockham::crypto::Hash::default(),
+    );
+    block.height = 1;
+    executor.execute_block(&mut block).unwrap();
+    let block_hash = ockham::crypto::hash_data(&block);
+    storage.save_block(&block).unwrap();
+    storage
+        .save_consensus_state(&ConsensusState {
+            view: 2,
+            finalized_height: 1,
+            preferred_block: block_hash,
+            preferred_view: 1,
+            last_voted_view: 1,
+            committee: vec![],
+            pending_validators: vec![],
+            exiting_validators: vec![],
+            inactivity_scores: HashMap::new(),
+            params: Default::default(),
+            rewards: HashMap::new(),
+            proposals: vec![],
+            missed_attestations: HashMap::new(),
+        })
+        .unwrap();
+
+    // The block's bloom covers the emitting contract and the topic
+    let topic = ockham::crypto::Hash(ockham::types::U256::from(0xaa).to_be_bytes());
+    assert!(ockham::types::bloom_contains(
+        &block.logs_bloom,
+        contract.as_slice()
+    ));
+    assert!(ockham::types::bloom_contains(&block.logs_bloom, &topic.0));
+
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage)),
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+
+    let logs = rpc
+        .get_logs(ockham::rpc::LogFilter {
+            address: Some(contract),
+            topics: vec![topic],
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(
+        (
+            logs[0].block_hash,
+            logs[0].height,
+            logs[0].transaction_index
+        ),
+        (block_hash, 1, 0)
+    );
+    assert_eq!(logs[0].log.address, contract);
+
+    // Other topics, other emitters and later heights match nothing
+    let none = |filter| rpc.get_logs(filter).unwrap().is_empty();
+    assert!(none(ockham::rpc::LogFilter {
+        topics: vec![ockham::crypto::Hash([0xbb; 32])],
+        ..Default::default()
+    }));
+    assert!(none(ockham::rpc::LogFilter {
+        address: Some(ockham::types::Address::with_last_byte(0xc1)),
+        ..Default::default()
+    }));
+    assert!(none(ockham::rpc::LogFilter {
+        from_height: Some(2),
+        ..Default::default()
+    }));
+}
+
+#[test]
+fn test_receipts_root_commits_to_order() {
+    let receipt = |status, gas_used| ockham::types::Receipt {
+        status,
+        cumulative_gas_used: gas_used,
+        logs: vec![],
+        logs_bloom: Default::default(),
+        gas_used,
+        revert_output: Default::default(),
+        contract_address: None,
+    };
+    let (a, b, c) = (receipt(1, 21_000), receipt(0, 30_000), receipt(1, 50_000));
+    let root = ockham::types::calculate_receipts_root(&[a.clone(), b.clone(), c.clone()]);
+    assert_eq!(
+        root,
+        ockham::types::calculate_receipts_root(&[a.clone(), b.clone(), c.clone()])
+    );
+    assert_ne!(
+        root,
+        ockham::types::calculate_receipts_root(&[b.clone(), a.clone(), c.clone()])
+    );
+    // Padding leaves do not collide with real receipts: the count is committed
+    assert_ne!(
+        ockham::types::calculate_receipts_root(&[a.clone(), b.clone()]),
+        ockham::types::calculate_receipts_root(&[a, b, c])
+    );
+    assert_eq!(
+        ockham::types::calculate_receipts_root(&[]),
+        ockham::crypto::Hash::default()
+    );
+}
+
+#[test]
+fn test_rpc_get_validator_set() {
+    use ockham::rpc::EpochOrView;
+
+    let net = ockham::testing::SimNetwork::new(ockham::testing::SimConfig::default());
+    let storage = net.storage(0);
+    let committee = net.node(0).unwrap().committee.clone();
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+
+    // Genesis snapshot, with the genesis stakes
+    let genesis = rpc
+        .get_validator_set(Some(EpochOrView::Epoch(0)))
+        .unwrap()
+        .unwrap();
+    assert_eq!(genesis.view, 0);
+    let members: Vec<_> = genesis
+        .committee
+        .iter()
+        .map(|v| v.public_key.clone())
+        .collect();
+    assert_eq!(members, committee);
+    let stake = ockham::types::U256::from(ockham::consensus::GENESIS_STAKE);
+    assert!(genesis.committee.iter().all(|v| v.stake == stake));
+    assert!(genesis.pending.is_empty() && genesis.exiting.is_empty());
+
+    // A later epoch, started at view 20 without the last member
+    let mut next = genesis.clone();
+    next.epoch = 1;
+    next.view = 20;
+    next.committee.pop();
+    storage.save_validator_set(&next).unwrap();
+
+    let at = |at| rpc.get_validator_set(Some(at)).unwrap();
+    assert_eq!(at(EpochOrView::Epoch(1)), Some(next.clone()));
+    assert_eq!(at(EpochOrView::Epoch(2)), None);
+    assert_eq!(at(EpochOrView::View(25)), Some(next));
+    assert_eq!(at(EpochOrView::View(20)).unwrap().epoch, 1);
+    assert_eq!(at(EpochOrView::View(19)), Some(genesis.clone()));
+
+    // The live sets come from the consensus state, in the latest epoch
+    let live = rpc.get_validator_set(None).unwrap().unwrap();
+    assert_eq!(live.epoch, 1);
+    assert_eq!(live.committee, genesis.committee);
+}
+
+#[tokio::test]
+async fn test_rpc_dev_faucet() {
+    use ockham::faucet::{Faucet, FaucetConfig, FaucetError};
+    use ockham::types::{Address, U256};
+
+    let storage = Arc::new(MemStorage::new());
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let make_rpc = || {
+        let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+        OckhamRpcImpl::new(
+            storage.clone(),
+            Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+            ockham::vm::Executor::new(
+                state_manager.clone(),
+                ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+            ),
+            ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+            tx_sender,
+        )
+    };
+    let developer = Address::repeat_byte(0x77);
+
+    // The faucet is opt-in (--dev)
+    assert!(
+        make_rpc()
+            .dev_fund_account(developer, U256::from(1))
+            .is_err()
+    );
+    assert!(make_rpc().faucet_request(developer).is_err());
+
+    let config = FaucetConfig {
+        drip: U256::from(100u64),
+        cooldown_secs: 60,
+    };
+    let (validator, _) = ockham::crypto::generate_keypair_from_id(0);
+    let (other_validator, _) = ockham::crypto::generate_keypair_from_id(1);
+    let faucet = Faucet::new(state_manager.clone(), validator.clone(), config);
+    let rpc = make_rpc().with_faucet(faucet.clone());
+
+    // Credits outside of blocks would fork the state of any other validator
+    let mut consensus = ConsensusState {
+        committee: vec![validator.clone(), other_validator],
+        ..Default::default()
+    };
+    storage.save_consensus_state(&consensus).unwrap();
+    assert!(rpc.dev_fund_account(developer, U256::from(5u64)).is_err());
+    assert!(matches!(
+        faucet.drip(developer, ockham::health::unix_now()),
+        Err(FaucetError::NotSoleValidator)
+    ));
+    assert_eq!(rpc.get_balance(developer).unwrap(), U256::ZERO);
+
+    consensus.committee = vec![validator];
+    storage.save_consensus_state(&consensus).unwrap();
+    let root = state_manager.lock().unwrap().root();
+
+    // Direct funding credits any amount and updates the state tree
+    assert_eq!(
+        rpc.dev_fund_account(developer, U256::from(5u64)).unwrap(),
+        U256::from(5u64)
+    );
+    assert_eq!(rpc.get_balance(developer).unwrap(), U256::from(5u64));
+    assert_ne!(state_manager.lock().unwrap().root(), root);
+
+    // The public endpoint drips a fixed amount, once per cooldown per address
+    assert_eq!(rpc.faucet_request(developer).unwrap(), U256::from(105u64));
+    assert!(rpc.faucet_request(developer).is_err());
+    assert!(matches!(
+        faucet.drip(developer, ockham::health::unix_now() + 30),
+        Err(FaucetError::RateLimited(s)) if s <= 30
+    ));
+    assert_eq!(
+        faucet
+            .drip(developer, ockham::health::unix_now() + 60)
+            .unwrap(),
+        U256::from(205u64)
+    );
+    let other = Address::repeat_byte(0x78);
+    assert_eq!(rpc.faucet_request(other).unwrap(), U256::from(100u64));
+}
+
+#[test]
+fn test_rpc_metrics() {
+    use ockham::rpc::{MAX_TRACKED_METHODS, OTHER_METHODS, RpcMetrics};
+    use std::time::Duration;
+
+    let metrics = RpcMetrics::new();
+    let shared = metrics.clone();
+    shared.record("get_balance", Duration::from_micros(30), false);
+    shared.record("get_balance", Duration::from_micros(20), true);
+    shared.record("chain_id", Duration::from_micros(5), false);
+
+    let snapshot = metrics.snapshot();
+    let balance = &snapshot["get_balance"];
+    assert_eq!(
+        (balance.calls, balance.errors, balance.total_micros),
+        (2, 1, 50)
+    );
+    assert_eq!(snapshot["chain_id"].calls, 1);
+
+    // Arbitrary method names cannot grow the counters without bound
+    for i in 0..MAX_TRACKED_METHODS {
+        metrics.record(&format!("bogus_{}", i), Duration::ZERO, true);
+    }
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.len(), MAX_TRACKED_METHODS + 1);
+    assert_eq!(snapshot[OTHER_METHODS].calls, 2);
+    metrics.record("get_balance", Duration::ZERO, false);
+    assert_eq!(metrics.snapshot()["get_balance"].calls, 3);
+}
+
+#[test]
+fn test_rpc_access_config() {
+    use ockham::rpc::RpcAccessConfig;
+
+    // Local only, no CORS headers and any Host by default
+    let local = RpcAccessConfig::default();
+    assert!(local.listen_addr.is_loopback());
+    assert!(local.cors_layer().unwrap().is_none());
+    assert!(local.host_filter().unwrap().is_none());
+
+    let public = RpcAccessConfig {
+        listen_addr: "0.0.0.0".parse().unwrap(),
+        cors_origins: vec!["https://app.example.com".into()],
+        allowed_hosts: vec!["rpc.example.com".into(), "localhost:*".into()],
+    };
+    assert!(public.cors_layer().unwrap().is_some());
+    assert!(public.host_filter().unwrap().is_some());
+
+    let any_origin = RpcAccessConfig {
+        cors_origins: vec!["*".into()],
+        ..Default::default()
+    };
+    assert!(any_origin.cors_layer().unwrap().is_some());
+
+    let invalid = RpcAccessConfig {
+        cors_origins: vec!["bad\norigin".into()],
+        ..Default::default()
+    };
+    assert!(invalid.cors_layer().is_err());
+}
+
+#[test]
+fn test_rpc_get_state_diff() {
+    use ockham::state::{Change, StateDiff};
+    use ockham::storage::AccountInfo;
+    use ockham::types::{Address, U256};
+
+    let storage = Arc::new(MemStorage::new());
+    let (pk, _) = ockham::crypto::generate_keypair();
+    let account = |balance: u64| AccountInfo {
+        nonce: 0,
+        balance: U256::from(balance),
+        code_hash: ockham::crypto::Hash::default(),
+    };
+    let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+
+    // Three committed blocks: alice is created, bob's slot flips and flips back
+    let mut diffs = vec![
+        StateDiff::default(),
+        StateDiff::default(),
+        StateDiff::default(),
+    ];
+    diffs[0].record_account(alice, None, account(10));
+    diffs[0].record_storage(bob, U256::from(7), U256::ZERO, U256::from(1));
+    diffs[1].record_account(alice, Some(account(10)), account(4));
+    diffs[2].record_storage(bob, U256::from(7), U256::from(1), U256::ZERO);
+
+    let mut hashes = vec![];
+    let mut parent = ockham::crypto::Hash::default();
+    for (i, diff) in diffs.iter().enumerate() {
+        let mut block = Block::new(
+            pk.clone(),
+            i as u64 + 1,
+            parent,
+            QuorumCertificate::default(),
+            ockham::crypto::Hash::default(),
+            ockham::crypto::Hash::default(),
+            vec![],
+            U256::ZERO,
+            0,
+            vec![],
+            ockham::crypto::Hash::default(),
+        );
+        block.height = i as u64 + 1;
+        parent = ockham::crypto::hash_data(&block);
+        storage.save_block(&block).unwrap();
+        storage.save_state_diff(&parent, diff).unwrap();
+        hashes.push(parent);
+    }
+
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+
+    // Blocks 2..=3: alice's balance drops, bob's slot is back where it was
+    let diff = rpc.get_state_diff(hashes[0], hashes[2]).unwrap();
+    assert_eq!(diff.accounts.len(), 1);
+    assert_eq!(
+        diff.accounts[&alice].info,
+        Some(Change {
+            before: Some(account(10)),
+            after: Some(account(4)),
+        })
+    );
+    assert!(diff.accounts[&alice].storage.is_empty());
+
+    // Block 2 only
+    let diff = rpc.get_state_diff(hashes[0], hashes[1]).unwrap();
+    assert_eq!(diff.accounts.len(), 1);
+    assert!(
+        rpc.get_state_diff(hashes[1], hashes[1])
+            .unwrap()
+            .accounts
+            .is_empty()
+    );
+
+    // Not a descendant, or an unknown block
+    assert_eq!(
+        rpc.get_state_diff(hashes[2], hashes[0]).unwrap_err().code(),
+        -32602
+    );
+    assert_eq!(
+        rpc.get_state_diff(ockham::crypto::Hash([9; 32]), hashes[2])
+            .unwrap_err()
+            .code(),
+        -32602
+    );
+}
+
+#[test]
+fn test_rpc_fee_history() {
+    use ockham::types::{Receipt, Transaction, U256};
+
+    let storage = Arc::new(MemStorage::new());
+    let (pk, _) = ockham::crypto::generate_keypair();
+    let tx = |tip: u64| Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::from(tip),
+        max_fee_per_gas: U256::from(100),
+        gas_limit: 100_000,
+        to: None,
+        value: U256::ZERO,
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    let receipt = |gas_used: u64| Receipt {
+        status: 1,
+        cumulative_gas_used: gas_used,
+        logs: vec![],
+        logs_bloom: Default::default(),
+        gas_used,
+        revert_output: Default::default(),
+        contract_address: None,
+    };
+
+    // Block 1 pays tips of 1 (21000 gas) and 5 (63000 gas), block 2 is empty
+    let blocks = [
+        (vec![tx(1), tx(5)], vec![receipt(21_000), receipt(63_000)]),
+        (vec![], vec![]),
+    ];
+    let mut parent = ockham::crypto::Hash::default();
+    for (i, (payload, receipts)) in blocks.into_iter().enumerate() {
+        let gas_used = receipts.iter().map(|r| r.gas_used).sum();
+        let mut block = Block::new(
+            pk.clone(),
+            i as u64 + 1,
+            parent,
+            QuorumCertificate::default(),
+            ockham::crypto::Hash::default(),
+            ockham::crypto::Hash::default(),
+            payload,
+            U256::from(10),
+            gas_used,
+            vec![],
+            ockham::crypto::Hash::default(),
+        );
+        block.height = i as u64 + 1;
+        parent = ockham::crypto::hash_data(&block);
+        storage.save_block(&block).unwrap();
+        storage.save_receipts(&parent, &receipts).unwrap();
+        storage.save_block_hash(block.height, &parent).unwrap();
+    }
+    storage
+        .save_chain_head(&ockham::storage::ChainHead {
+            view: 2,
+            block_hash: parent,
+            state_root: ockham::crypto::Hash::default(),
+        })
+        .unwrap();
+
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let (tx_sender, _rx) = tokio::sync::mpsc::channel(100);
+    let rpc = OckhamRpcImpl::new(
+        storage.clone(),
+        Arc::new(ockham::tx_pool::TxPool::new(storage.clone())),
+        ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+        tx_sender,
+    );
+
+    let history = rpc.fee_history(10, None, vec![25.0, 75.0]).unwrap();
+    assert_eq!(history.oldest_block, 1);
+    assert_eq!(history.base_fee_per_gas.len(), 3);
+    assert_eq!(
+        history.base_fee_per_gas[..2],
+        [U256::from(10), U256::from(10)]
+    );
+    // The empty block lowers the next base fee
+    assert!(history.base_fee_per_gas[2] < U256::from(10));
+    assert_eq!(history.gas_used_ratio.len(), 2);
+    assert!(history.gas_used_ratio[0] > 0.0 && history.gas_used_ratio[1] == 0.0);
+    assert_eq!(
+        history.reward,
+        vec![
+            vec![U256::from(1), U256::from(5)],
+            vec![U256::ZERO, U256::ZERO],
+        ]
+    );
+
+    // Up to block 1 only, without rewards
+    let history = rpc.fee_history(10, Some("1".into()), vec![]).unwrap();
+    assert_eq!((history.oldest_block, history.gas_used_ratio.len()), (1, 1));
+    assert!(history.reward.is_empty());
+
+    // Percentiles out of order or range, or an unknown block
+    assert_eq!(
+        rpc.fee_history(10, None, vec![75.0, 25.0])
+            .unwrap_err()
+            .code(),
+        -32602
+    );
+    assert_eq!(
+        rpc.fee_history(10, None, vec![101.0]).unwrap_err().code(),
+        -32602
+    );
+    assert_eq!(
+        rpc.fee_history(10, Some("0x9".into()), vec![])
+            .unwrap_err()
+            .code(),
+        -32602
+    );
+}
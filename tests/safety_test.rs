@@ -1,7 +1,9 @@
-use ockham::consensus::{ConsensusAction, SimplexState};
-use ockham::crypto::{Hash, PrivateKey, PublicKey, hash_data};
+use ockham::consensus::{ConsensusAction, ConsensusError, SimplexState};
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, hash_data, sign};
 use ockham::storage::Storage;
-use ockham::types::{Block, QuorumCertificate, U256};
+use ockham::types::{
+    Block, DEFAULT_CHAIN_ID, QuorumCertificate, U256, Vote, VoteType, vote_message,
+};
 use std::sync::Arc;
 
 #[test]
@@ -40,12 +42,13 @@ fn test_prevent_double_voting() {
         view: 0,
         block_hash: genesis_hash,
         signature: ockham::crypto::sign(&keys[0].1, &Hash::default().0), // Mock valid sig structure (hash doesn't matter for genesis qc check)
-        signers: vec![],
+        epoch: 0,
+        signers: Default::default(),
     };
 
     let comm_hash = hash_data(&committee);
 
-    let block_a = Block::new(
+    let mut block_a = Block::new(
         keys[0].0.clone(),
         view,
         genesis_hash,
@@ -58,10 +61,13 @@ fn test_prevent_double_voting() {
         vec![],
         comm_hash,
     );
+    block_a.height = 1;
+    block_a.sign(&keys[0].1);
 
     // Block B (Different Payload/Hash)
     let mut block_b = block_a.clone();
     block_b.gas_used = 123; // Change something to change hash
+    block_b.sign(&keys[0].1);
 
     // 3. Receive Proposal A
     let actions_a = validator.on_proposal(block_a.clone()).unwrap();
@@ -90,3 +96,357 @@ fn test_prevent_double_voting() {
     let state_after = storage.get_consensus_state().unwrap().unwrap();
     assert_eq!(state_after.last_voted_view, view);
 }
+
+#[test]
+fn test_outsider_votes_cannot_form_qc() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..4).map(generate_keypair_from_id).collect();
+    let outsiders: Vec<(PublicKey, PrivateKey)> = (10..14).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    let view = 3;
+    let block_hash = Hash([7; 32]);
+    let vote = |key: &(PublicKey, PrivateKey), vote_type| Vote {
+        view,
+        block_hash,
+        vote_type,
+        author: key.0.clone(),
+        signature: sign(
+            &key.1,
+            &vote_message(vote_type, view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    };
+
+    // Two of the three votes needed come from the committee
+    for key in &keys[1..3] {
+        node.on_vote(vote(key, VoteType::Notarize)).unwrap();
+    }
+
+    // Validly signed votes from outside the committee are rejected, and not recorded
+    for key in &outsiders {
+        for vote_type in [VoteType::Notarize, VoteType::Finalize] {
+            assert!(matches!(
+                node.on_vote(vote(key, vote_type)),
+                Err(ConsensusError::NotInCommittee)
+            ));
+        }
+    }
+    assert_eq!(node.votes_received[&view].len(), 2);
+    assert!(!node.finalize_votes_received.contains_key(&view));
+    assert!(storage.get_qc(view).unwrap().is_none());
+
+    // The third committee vote forms the QC
+    node.on_vote(vote(&keys[3], VoteType::Notarize)).unwrap();
+    let qc = storage.get_qc(view).unwrap().unwrap();
+    assert_eq!(qc.block_hash, block_hash);
+    assert_eq!(qc.signers.len(), 3);
+}
+
+#[test]
+fn test_removed_validator_votes_rejected() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // Member 3 leaves with the committee change finalized at view 5 (epoch 1), which
+    // this node has stored but not switched to yet
+    let mut next = storage.get_validator_set(0).unwrap().unwrap();
+    next.epoch = 1;
+    next.view = 5;
+    next.committee.pop();
+    storage.save_validator_set(&next).unwrap();
+    assert!(node.committee.contains(&keys[3].0));
+
+    let block_hash = Hash([7; 32]);
+    let vote = |view| Vote {
+        view,
+        block_hash,
+        vote_type: VoteType::Notarize,
+        author: keys[3].0.clone(),
+        signature: sign(
+            &keys[3].1,
+            &vote_message(VoteType::Notarize, view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    };
+
+    // Its votes still count in the views of the old committee, not after it
+    node.on_vote(vote(3)).unwrap();
+    assert_eq!(node.votes_received[&3].len(), 1);
+    assert!(matches!(
+        node.on_vote(vote(7)),
+        Err(ConsensusError::NotInCommittee)
+    ));
+    assert!(!node.votes_received.contains_key(&7));
+}
+
+#[test]
+fn test_votes_aggregate_in_the_committee_of_their_view() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..7).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys[..4].iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee.clone(),
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // Epoch 1 starts at view 5 with three more members (quorum 5 of 7 instead of 3 of 4);
+    // this node has stored it but not switched to it yet
+    let mut next = storage.get_validator_set(0).unwrap().unwrap();
+    next.epoch = 1;
+    next.view = 5;
+    for (pk, _) in &keys[4..] {
+        let mut member = next.committee[0].clone();
+        member.public_key = pk.clone();
+        next.committee.push(member);
+    }
+    storage.save_validator_set(&next).unwrap();
+    let new_committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+
+    let block_hash = Hash([7; 32]);
+    let vote = |i: usize, view| Vote {
+        view,
+        block_hash,
+        vote_type: VoteType::Notarize,
+        author: keys[i].0.clone(),
+        signature: sign(
+            &keys[i].1,
+            &vote_message(VoteType::Notarize, view, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    };
+
+    // Before the boundary: 3 of the old 4 certify, in epoch 0
+    for i in 1..4 {
+        node.on_vote(vote(i, 2)).unwrap();
+    }
+    let qc = storage.get_qc(2).unwrap().unwrap();
+    assert_eq!(qc.epoch, 0);
+    assert!(qc.verify_signature(&committee, DEFAULT_CHAIN_ID));
+
+    // After it: the same 3 are short of the new quorum
+    for i in 1..4 {
+        node.on_vote(vote(i, 9)).unwrap();
+    }
+    assert!(storage.get_qc(9).unwrap().is_none());
+    for i in 4..6 {
+        node.on_vote(vote(i, 9)).unwrap();
+    }
+    let qc = storage.get_qc(9).unwrap().unwrap();
+    assert_eq!(qc.epoch, 1);
+    assert_eq!(qc.signers.len(), 5);
+    assert!(qc.verify_signature(&new_committee, DEFAULT_CHAIN_ID));
+}
+
+#[test]
+fn test_handover_votes_bounded_to_outgoing_committee() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..5).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys[..4].iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee.clone(),
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // Epoch 1 started at view 5 (same members), and its hand-over is persisted
+    let transition =
+        ockham::types::CommitteeTransition::new(1, 5, Hash([9; 32]), &committee, committee.clone());
+    storage.save_committee_transition(&transition).unwrap();
+    let mut next = storage.get_validator_set(0).unwrap().unwrap();
+    next.epoch = 1;
+    next.view = 5;
+    storage.save_validator_set(&next).unwrap();
+
+    let handover = |i: usize, view, commitment: Hash| Vote {
+        view,
+        block_hash: commitment,
+        vote_type: VoteType::Handover,
+        author: keys[i].0.clone(),
+        signature: sign(&keys[i].1, &commitment.0),
+    };
+
+    // Not a member of the outgoing committee
+    assert!(matches!(
+        node.on_vote(handover(4, 8, Hash([1; 32]))),
+        Err(ConsensusError::NotInCommittee)
+    ));
+    assert!(node.handover_votes_received.is_empty());
+
+    // Votes for the hand-over already persisted are ignored
+    node.on_vote(handover(2, 5, transition.commitment()))
+        .unwrap();
+    assert!(node.handover_votes_received.is_empty());
+
+    // Members' votes for the next one are buffered until we reach it...
+    node.on_vote(handover(1, 8, Hash([1; 32]))).unwrap();
+    assert_eq!(node.handover_votes_received.len(), 1);
+
+    // ...but commitments no transition of ours matches cannot grow past the memory budget
+    let budget = ockham::memory::MemoryBudget::new(1);
+    let mut node = node.with_memory_budget(&budget);
+    for i in 0..50u8 {
+        node.on_vote(handover(1, 8, Hash([i; 32]))).unwrap();
+    }
+    assert!(node.handover_votes_received.is_empty());
+}
+
+#[test]
+fn test_finalize_quorum_counted_per_block() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee.clone(),
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    let (hash_a, hash_b) = (Hash([1; 32]), Hash([2; 32]));
+    let finalize = |i: usize, block_hash: Hash| Vote {
+        view: 3,
+        block_hash,
+        vote_type: VoteType::Finalize,
+        author: keys[i].0.clone(),
+        signature: sign(
+            &keys[i].1,
+            &vote_message(VoteType::Finalize, 3, &block_hash, DEFAULT_CHAIN_ID),
+        ),
+    };
+
+    // Three votes, but split 2/1 across two blocks: no quorum for either
+    node.on_vote(finalize(1, hash_a)).unwrap();
+    node.on_vote(finalize(2, hash_b)).unwrap();
+    node.on_vote(finalize(3, hash_a)).unwrap();
+    assert_eq!(node.finalized_height, 0);
+    assert!(storage.get_finality_qc(3).unwrap().is_none());
+
+    // The third vote for A finalizes it; its certificate is kept even though the
+    // aggregate of the earlier votes was dropped
+    node.aggregates.clear();
+    node.on_vote(finalize(0, hash_a)).unwrap();
+    assert_eq!(node.finalized_height, 3);
+    let certificate = storage.get_finality_qc(3).unwrap().unwrap();
+    assert_eq!(certificate.block_hash, hash_a);
+    assert_eq!(certificate.signers.len(), 3);
+}
+
+#[test]
+fn test_vote_signature_bound_to_type_view_and_chain() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(std::sync::Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut node = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee,
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // A timeout vote (Notarize for the dummy block) of view 2
+    let dummy = Hash::default();
+    let timeout = Vote {
+        view: 2,
+        block_hash: dummy,
+        vote_type: VoteType::Notarize,
+        author: keys[1].0.clone(),
+        signature: sign(
+            &keys[1].1,
+            &vote_message(VoteType::Notarize, 2, &dummy, DEFAULT_CHAIN_ID),
+        ),
+    };
+    assert!(timeout.verify_signature(DEFAULT_CHAIN_ID));
+
+    // Its signature does not make a Finalize vote, a vote of another view, or one of
+    // another chain
+    for forged in [
+        Vote {
+            vote_type: VoteType::Finalize,
+            ..timeout.clone()
+        },
+        Vote {
+            view: 3,
+            ..timeout.clone()
+        },
+    ] {
+        assert!(matches!(
+            node.on_vote(forged),
+            Err(ConsensusError::InvalidSignature)
+        ));
+    }
+    assert!(!timeout.verify_signature(DEFAULT_CHAIN_ID + 1));
+    assert!(node.votes_received.is_empty());
+    assert!(node.finalize_votes_received.is_empty());
+
+    node.on_vote(timeout).unwrap();
+    assert_eq!(node.votes_received[&2].len(), 1);
+}
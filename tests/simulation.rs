@@ -1,5 +1,6 @@
 use ockham::consensus::{ConsensusAction, SimplexState};
 use ockham::crypto::{PrivateKey, PublicKey, hash_data};
+use ockham::testing::{SimConfig, SimNetwork};
 use ockham::types::{Block, QuorumCertificate};
 
 #[test]
@@ -41,11 +42,11 @@ fn test_three_chain_commit() {
     println!("Genesis: {:?}", nodes[0].preferred_block);
 
     // --- VIEW 1: PREPARE b1 ---
-    // Leader 0 creates Block 1 (parent = Genesis)
+    // Leader 1 (1 % 4) creates Block 1 (parent = Genesis)
     let genesis_hash = nodes[0].preferred_block;
     let qc0 = QuorumCertificate::default(); // genesis QC
-    let b1 = Block::new(
-        keys[0].0.clone(),
+    let mut b1 = Block::new(
+        keys[1].0.clone(),
         1,
         genesis_hash,
         qc0,
@@ -57,6 +58,8 @@ fn test_three_chain_commit() {
         vec![],
         hash_data(&committee),
     );
+    b1.height = 1;
+    b1.sign(&keys[1].1);
     let b1_hash = hash_data(&b1);
 
     println!("Block 1 Hash: {:?}", b1_hash);
@@ -110,15 +113,14 @@ fn test_three_chain_commit() {
     println!("QC1 Formed for View {}", qc1.view);
 
     // --- VIEW 2: PREPARE b2 ---
-    // Leader 1 (Node 1) proposes Block 2 (parent = b1)
-    // First, Node 1 needs to know about b1 and QC1 (sync/gossip)
-    // We manually update Node 1 state
-    nodes[1].storage.save_block(&b1).unwrap();
-
-    // Node 1 proposes b2
-    // Node 1 proposes b2
-    let b2 = Block::new(
-        keys[1].0.clone(),
+    // Leader 2 (Node 2) proposes Block 2 (parent = b1)
+    // First, Node 2 needs to know about b1 and QC1 (sync/gossip)
+    // We manually update Node 2 state
+    nodes[2].storage.save_block(&b1).unwrap();
+
+    // Node 2 proposes b2
+    let mut b2 = Block::new(
+        keys[2].0.clone(),
         2,
         b1_hash,
         qc1.clone(),
@@ -130,6 +132,8 @@ fn test_three_chain_commit() {
         vec![],
         hash_data(&committee),
     );
+    b2.height = 2;
+    b2.sign(&keys[2].1);
     let b2_hash = hash_data(&b2);
 
     // All nodes vote for b2
@@ -164,3 +168,43 @@ fn test_three_chain_commit() {
     assert!(nodes[0].storage.get_block(&b2_hash).unwrap().is_some());
     assert!(nodes[0].storage.get_qc(2).unwrap().is_some());
 }
+
+#[test]
+fn test_sim_network_liveness() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    assert!(net.run_until(60_000, |net| net.last_finalized() >= 5));
+    assert_eq!(net.stats().evidence, 0);
+    // Every node followed along
+    for i in 0..net.len() {
+        assert!(net.node(i).unwrap().finalized_height >= 4);
+    }
+}
+
+#[test]
+fn test_sim_network_deterministic() {
+    let config = SimConfig {
+        seed: 7,
+        drop_rate: 0.05,
+        ..Default::default()
+    };
+    let run = |config: SimConfig| {
+        let mut net = SimNetwork::new(config);
+        net.run_until(20_000, |_| false);
+        (net.finalized().clone(), net.stats().clone(), net.max_view())
+    };
+    let first = run(config.clone());
+    assert!(!first.0.is_empty());
+    assert!(first.1.dropped > 0);
+    assert_eq!(run(config), first);
+}
+
+#[test]
+fn test_sim_network_minority_partition() {
+    let mut net = SimNetwork::new(SimConfig::default());
+    net.partition(&[0]);
+    // The other three are a quorum and keep finalizing around the cut-off leader
+    assert!(net.run_until(120_000, |net| net.last_finalized() >= 6));
+    net.heal();
+    assert!(!net.is_partitioned());
+    assert_eq!(net.stats().evidence, 0);
+}
@@ -1,7 +1,10 @@
 use ockham::consensus::{ConsensusAction, SimplexState};
 use ockham::crypto::{Hash, PrivateKey, PublicKey};
 use ockham::storage::Storage;
-use ockham::types::{Block, QuorumCertificate, U256, Vote, VoteType};
+use ockham::system_contracts::staking;
+use ockham::types::{
+    Block, DEFAULT_CHAIN_ID, QuorumCertificate, U256, Vote, VoteType, vote_message,
+};
 use revm::Database;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -35,7 +38,6 @@ fn test_slashing_flow() {
         nonce: 0,
         balance: initial_balance,
         code_hash: Hash(ockham::types::keccak256([]).into()),
-        code: None,
     };
     storage.save_account(&offender_addr, &account).unwrap();
 
@@ -58,12 +60,11 @@ fn test_slashing_flow() {
         ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
     );
 
-    // Initialize Stakes for Offender
+    // Initialize Stakes for Offender (genesis committee already holds the genesis stake)
     {
         let mut db = state_manager.lock().unwrap();
-        let mut state = db.get_consensus_state().unwrap().unwrap();
-        state.stakes.insert(offender_addr, U256::from(5000u64));
-        db.save_consensus_state(&state).unwrap();
+        let stake = staking::stake_of(&mut db, offender_addr).unwrap();
+        assert_eq!(stake, U256::from(5000u64));
     }
 
     // 2. Create Equivocation Votes (View 2)
@@ -76,7 +77,10 @@ fn test_slashing_flow() {
         block_hash: block_a_hash,
         vote_type: VoteType::Notarize,
         author: offender_id.clone(),
-        signature: ockham::crypto::sign(&offender_key, &block_a_hash.0),
+        signature: ockham::crypto::sign(
+            &offender_key,
+            &vote_message(VoteType::Notarize, view, &block_a_hash, DEFAULT_CHAIN_ID),
+        ),
     };
 
     let vote_b = Vote {
@@ -84,7 +88,10 @@ fn test_slashing_flow() {
         block_hash: block_b_hash,
         vote_type: VoteType::Notarize,
         author: offender_id.clone(),
-        signature: ockham::crypto::sign(&offender_key, &block_b_hash.0),
+        signature: ockham::crypto::sign(
+            &offender_key,
+            &vote_message(VoteType::Notarize, view, &block_b_hash, DEFAULT_CHAIN_ID),
+        ),
     };
 
     // 3. Receive Vote A
@@ -129,7 +136,8 @@ fn test_slashing_flow() {
         view: 3,
         block_hash: Hash::default(), // Dummy parent
         signature: ockham::crypto::Signature::default(),
-        signers: vec![],
+        epoch: 0,
+        signers: Default::default(),
     };
     storage.save_qc(&qc_mock).unwrap();
     storage
@@ -171,18 +179,203 @@ fn test_slashing_flow() {
     // Let's inspect the state AFTER the block is committed/finalized.
     // Or we can manually run `executor.execute_block` against the raw storage to simulate finalization.
 
-    let state_manager = validator.executor.state.clone(); // This is the real state manager
-    let executor = ockham::vm::Executor::new(state_manager, 10_000_000);
+    let executor = ockham::vm::Executor::new(state_manager.clone(), 10_000_000); // The real state manager
 
     executor.execute_block(&mut block_to_exec).unwrap();
 
     // Check Stake
-    let mut db = validator.executor.state.lock().unwrap();
-    let state = db.get_consensus_state().unwrap().unwrap();
-    let stake = state.stakes.get(&offender_addr).unwrap();
+    let mut db = state_manager.lock().unwrap();
+    let stake = staking::stake_of(&mut db, offender_addr).unwrap();
 
     // Slashed amount is 1000. Initial 5000. Should be 4000.
-    assert_eq!(*stake, U256::from(4000u64), "Stake should be slashed");
+    assert_eq!(stake, U256::from(4000u64), "Stake should be slashed");
 
     println!("Slashing Test Passed!");
 }
+
+#[test]
+fn test_slashing_params_and_evidence_expiry() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..4)
+        .map(|i| ockham::crypto::generate_keypair_from_id(i as u64))
+        .collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let (offender_id, offender_key) = keys[1].clone();
+    let offender_addr = staking::validator_address(&offender_id);
+
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let _validator = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee.clone(),
+        storage.clone(),
+        tx_pool,
+        executor.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // Custom chain parameters
+    let mut state = storage.get_consensus_state().unwrap().unwrap();
+    state.params.slash_amount = U256::from(300u64);
+    state.params.evidence_max_age = 5;
+    storage.save_consensus_state(&state).unwrap();
+
+    let make_vote = |hash: Hash| Vote {
+        view: 2,
+        block_hash: hash,
+        vote_type: VoteType::Notarize,
+        author: offender_id.clone(),
+        signature: ockham::crypto::sign(
+            &offender_key,
+            &vote_message(VoteType::Notarize, 2, &hash, DEFAULT_CHAIN_ID),
+        ),
+    };
+    let evidence = ockham::types::EquivocationEvidence {
+        vote_a: make_vote(Hash([1u8; 32])),
+        vote_b: make_vote(Hash([2u8; 32])),
+    };
+    assert!(!state.params.is_evidence_expired(&evidence, 7));
+    assert!(state.params.is_evidence_expired(&evidence, 8));
+
+    let make_block = |view| {
+        Block::new(
+            keys[0].0.clone(),
+            view,
+            Hash::default(),
+            QuorumCertificate::default(),
+            Hash::default(),
+            Hash::default(),
+            vec![],
+            U256::from(ockham::types::INITIAL_BASE_FEE),
+            0,
+            vec![evidence.clone()],
+            ockham::crypto::hash_data(&committee),
+        )
+    };
+
+    // Expired evidence is ignored by execution
+    executor.execute_block(&mut make_block(20)).unwrap();
+    {
+        let mut db = state_manager.lock().unwrap();
+        let stake = staking::stake_of(&mut db, offender_addr).unwrap();
+        assert_eq!(
+            stake,
+            U256::from(5000u64),
+            "Expired evidence must not slash"
+        );
+    }
+
+    // Fresh evidence slashes the configured amount
+    executor.execute_block(&mut make_block(5)).unwrap();
+    {
+        let mut db = state_manager.lock().unwrap();
+        let stake = staking::stake_of(&mut db, offender_addr).unwrap();
+        assert_eq!(stake, U256::from(4700u64));
+    }
+
+    // The pool drops expired evidence and refuses to re-admit it
+    let mut pool = ockham::evidence_pool::EvidencePool::new();
+    assert!(pool.add_evidence(evidence.clone()));
+    pool.prune_expired(20, 5);
+    assert!(pool.is_empty());
+    assert!(!pool.add_evidence(evidence));
+}
+
+#[test]
+fn test_double_proposal_slashing() {
+    let keys: Vec<(PublicKey, PrivateKey)> = (0..4)
+        .map(|i| ockham::crypto::generate_keypair_from_id(i as u64))
+        .collect();
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    // Leader of View 1 (1 % 4) proposes twice
+    let offender_id = keys[1].0.clone();
+    let offender_addr = staking::validator_address(&offender_id);
+
+    let storage = Arc::new(ockham::storage::MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let mut validator = SimplexState::new(
+        keys[0].0.clone(),
+        keys[0].1.clone(),
+        committee.clone(),
+        storage.clone(),
+        tx_pool,
+        executor.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    let make_block = |base_fee: u64| {
+        let mut block = Block::new(
+            offender_id.clone(),
+            1,
+            Hash::default(),
+            QuorumCertificate::default(),
+            Hash::default(),
+            Hash::default(),
+            vec![],
+            U256::from(base_fee),
+            0,
+            vec![],
+            ockham::crypto::hash_data(&committee),
+        );
+        block.sign(&keys[1].1);
+        block
+    };
+
+    // Blocks not signed by their author are rejected and cannot frame the leader
+    let mut forged = make_block(5);
+    forged.sign(&keys[2].1);
+    assert!(matches!(
+        validator.on_proposal(forged),
+        Err(ockham::consensus::ConsensusError::InvalidSignature)
+    ));
+    assert!(validator.seen_proposals.is_empty());
+
+    // First proposal is recorded (whatever its validation outcome), the second is evidence
+    let _ = validator.on_proposal(make_block(1));
+    let actions = validator.on_proposal(make_block(2)).unwrap();
+    let evidence = match actions.as_slice() {
+        [ConsensusAction::BroadcastProposalEvidence(e)] => e.clone(),
+        other => panic!("Expected proposal evidence, got {:?}", other),
+    };
+    assert!(evidence.is_valid());
+    assert_eq!(evidence.offender(), &offender_id);
+    assert_eq!(validator.evidence_pool.get_all_proposals().len(), 1);
+
+    // Reporting the same offence again (either order) is a no-op
+    assert!(validator.on_proposal(make_block(2)).unwrap().is_empty());
+    let mut swapped = evidence.clone();
+    std::mem::swap(&mut swapped.block_a, &mut swapped.block_b);
+    assert!(
+        !validator
+            .evidence_pool
+            .add_proposal_evidence(swapped.clone())
+    );
+
+    // Execution slashes once per offence
+    let mut block = make_block(3);
+    block.view = 4;
+    block.proposal_evidence = vec![evidence, swapped];
+    executor.execute_block(&mut block).unwrap();
+
+    let mut db = state_manager.lock().unwrap();
+    let stake = staking::stake_of(&mut db, offender_addr).unwrap();
+    let expected = U256::from(ockham::consensus::GENESIS_STAKE)
+        - ockham::types::ChainParams::default().proposal_slash_amount;
+    assert_eq!(stake, expected);
+}
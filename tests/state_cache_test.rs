@@ -0,0 +1,94 @@
+use ockham::memory::MemoryBudget;
+use ockham::state::{StateCache, StateManager};
+use ockham::storage::{AccountInfo, MemStorage, Storage};
+use ockham::types::{Address, U256};
+use revm::Database;
+use std::sync::Arc;
+
+fn account(balance: u64) -> AccountInfo {
+    AccountInfo {
+        balance: U256::from(balance),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_state_cache_hits_and_writes() {
+    let storage = Arc::new(MemStorage::new());
+    let cache = Arc::new(StateCache::new(100));
+    let mut state = StateManager::new(storage.clone(), None).with_cache(cache.clone());
+    let alice = Address::from([0xaa; 20]);
+
+    // Missing accounts are cached too
+    assert!(state.basic(alice).unwrap().is_none());
+    assert!(state.basic(alice).unwrap().is_none());
+    let metrics = cache.metrics();
+    assert_eq!((metrics.account_hits, metrics.account_misses), (1, 1));
+
+    // A commit replaces the cached entry
+    state.commit_account(alice, account(7)).unwrap();
+    assert_eq!(
+        state.basic(alice).unwrap().unwrap().balance,
+        U256::from(7u64)
+    );
+    state
+        .commit_storage(alice, U256::from(1u64), U256::from(9u64))
+        .unwrap();
+    assert_eq!(
+        state.storage(alice, U256::from(1u64)).unwrap(),
+        U256::from(9u64)
+    );
+    let metrics = cache.metrics();
+    assert_eq!(metrics.account_hits, 2);
+    assert_eq!((metrics.storage_hits, metrics.storage_misses), (1, 0));
+    assert_eq!(state.cache_metrics(), Some(metrics.clone()));
+    assert!(metrics.hit_rate() > 0.5);
+
+    // Forks read through the cache, but their writes stay in their overlay
+    let backing = state.backing_storage();
+    let overlay = Arc::new(ockham::storage::StateOverlay::new(backing));
+    let mut fork = state.fork(state.root(), overlay);
+    fork.commit_account(alice, account(1)).unwrap();
+    assert_eq!(
+        fork.basic(alice).unwrap().unwrap().balance,
+        U256::from(1u64)
+    );
+    assert_eq!(
+        state.basic(alice).unwrap().unwrap().balance,
+        U256::from(7u64)
+    );
+    assert_eq!(
+        storage.get_account(&alice).unwrap().unwrap().balance,
+        U256::from(7u64)
+    );
+}
+
+#[test]
+fn test_state_cache_eviction() {
+    let storage = Arc::new(MemStorage::new());
+    let cache = Arc::new(StateCache::new(2));
+    let mut state = StateManager::new(storage.clone(), None).with_cache(cache.clone());
+    let addresses: Vec<Address> = (1..=3u8).map(|i| Address::from([i; 20])).collect();
+
+    // Least recently used first: the first address is read again, so the second goes
+    state.basic(addresses[0]).unwrap();
+    state.basic(addresses[1]).unwrap();
+    state.basic(addresses[0]).unwrap();
+    state.basic(addresses[2]).unwrap();
+    let metrics = cache.metrics();
+    assert_eq!((metrics.entries, metrics.evicted), (2, 1));
+    state.basic(addresses[0]).unwrap();
+    assert_eq!(cache.metrics().account_hits, 2);
+    state.basic(addresses[1]).unwrap();
+    assert_eq!(cache.metrics().account_misses, 4);
+
+    // Over the memory budget, entries are evicted down to the cache's share
+    let budget = MemoryBudget::new(1);
+    let cache = Arc::new(StateCache::new(100).with_memory_budget(&budget));
+    let mut state = StateManager::new(storage, None).with_cache(cache.clone());
+    for address in &addresses {
+        state.basic(*address).unwrap();
+    }
+    assert!(cache.metrics().entries <= 1);
+    assert_eq!(budget.usage(), cache.metrics().bytes);
+}
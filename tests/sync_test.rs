@@ -1,7 +1,9 @@
 use ockham::consensus::{ConsensusAction, SimplexState};
 use ockham::crypto::{Hash, generate_keypair_from_id, hash_data};
 use ockham::storage::MemStorage;
-use ockham::types::{Block, QuorumCertificate};
+use ockham::types::{
+    Block, DEFAULT_CHAIN_ID, QuorumCertificate, SignerBitmap, SyncMessage, VoteType, vote_message,
+};
 
 /// Helper to create a signed block
 fn create_block(
@@ -11,8 +13,8 @@ fn create_block(
     justify: QuorumCertificate,
     committee_hash: Hash,
 ) -> Block {
-    let (pk, _) = generate_keypair_from_id(author_id);
-    Block::new(
+    let (pk, sk) = generate_keypair_from_id(author_id);
+    let mut block = Block::new(
         pk,
         view,
         parent_hash,
@@ -24,7 +26,10 @@ fn create_block(
         0,
         vec![], // Evidence
         committee_hash,
-    )
+    );
+    block.height = view; // No timeouts: one block per view
+    block.sign(&sk);
+    block
 }
 
 #[test]
@@ -57,9 +62,9 @@ fn test_sync_orphan_processing() {
     let genesis_hash = bob.preferred_block;
     let genesis_qc = QuorumCertificate::default(); // Simplified for test
 
-    // Block 1 (View 1)
+    // Block 1 (View 1, led by Bob)
     let b1 = create_block(
-        0,
+        1,
         1,
         genesis_hash,
         genesis_qc.clone(),
@@ -68,29 +73,37 @@ fn test_sync_orphan_processing() {
     let b1_hash = hash_data(&b1);
 
     // Create valid QC for B1
-    let sig1 = ockham::crypto::sign(&alice_sk, &b1_hash.0);
+    let sig1 = ockham::crypto::sign(
+        &alice_sk,
+        &vote_message(VoteType::Notarize, 1, &b1_hash, DEFAULT_CHAIN_ID),
+    );
     let qc1 = QuorumCertificate {
         view: 1,
         block_hash: b1_hash,
         signature: sig1,
-        signers: vec![alice_pk.clone()],
+        epoch: 0,
+        signers: SignerBitmap::from_signers(&committee, [&alice_pk]).unwrap(),
     };
 
-    // Block 2 (View 2)
+    // Block 2 (View 2, led by Alice)
     let b2 = create_block(0, 2, b1_hash, qc1.clone(), hash_data(&committee));
     let b2_hash = hash_data(&b2);
 
     // Create valid QC for B2
-    let sig2 = ockham::crypto::sign(&alice_sk, &b2_hash.0);
+    let sig2 = ockham::crypto::sign(
+        &alice_sk,
+        &vote_message(VoteType::Notarize, 2, &b2_hash, DEFAULT_CHAIN_ID),
+    );
     let qc2 = QuorumCertificate {
         view: 2,
         block_hash: b2_hash,
         signature: sig2,
-        signers: vec![alice_pk.clone()],
+        epoch: 0,
+        signers: SignerBitmap::from_signers(&committee, [&alice_pk]).unwrap(),
     };
 
-    // Block 3 (View 3)
-    let b3 = create_block(0, 3, b2_hash, qc2.clone(), hash_data(&committee));
+    // Block 3 (View 3, led by Bob)
+    let b3 = create_block(1, 3, b2_hash, qc2.clone(), hash_data(&committee));
 
     // --- SCENARIO: Bob receives B3 first (gap) ---
     println!("Feeding Block 3 to Bob (Orphan)...");
@@ -195,3 +208,173 @@ fn test_sync_block_serving() {
         _ => panic!("Expected SendBlock"),
     }
 }
+
+#[test]
+fn test_status_fast_forward() {
+    let (alice_pk, alice_sk) = generate_keypair_from_id(0);
+    let (bob_pk, bob_sk) = generate_keypair_from_id(1);
+    let committee = vec![alice_pk.clone(), bob_pk.clone()];
+
+    // Bob rejoins at genesis
+    let storage = std::sync::Arc::new(MemStorage::new());
+    let tx_pool = std::sync::Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = std::sync::Arc::new(std::sync::Mutex::new(
+        ockham::state::StateManager::new(storage.clone(), None),
+    ));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let mut bob = SimplexState::new(
+        bob_pk.clone(),
+        bob_sk.clone(),
+        committee.clone(),
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let genesis_hash = bob.preferred_block;
+    match bob.status() {
+        SyncMessage::Status {
+            current_view,
+            head_hash,
+            high_qc,
+            ..
+        } => {
+            assert_eq!(current_view, bob.current_view);
+            assert_eq!(head_hash, genesis_hash);
+            assert_eq!(high_qc.view, 0);
+        }
+        other => panic!("Expected Status, got {:?}", other),
+    }
+
+    // The network moved on to view 6 with a block at view 5, certified by both members
+    let b5 = create_block(
+        0,
+        5,
+        genesis_hash,
+        QuorumCertificate::default(),
+        hash_data(&committee),
+    );
+    let b5_hash = hash_data(&b5);
+    let qc5 = |signers: &[&ockham::crypto::PrivateKey]| {
+        let sigs: Vec<_> = signers
+            .iter()
+            .map(|sk| {
+                ockham::crypto::sign(
+                    sk,
+                    &vote_message(VoteType::Notarize, 5, &b5_hash, DEFAULT_CHAIN_ID),
+                )
+            })
+            .collect();
+        let keys: Vec<_> = committee[..signers.len()].iter().collect();
+        QuorumCertificate {
+            view: 5,
+            block_hash: b5_hash,
+            signature: ockham::crypto::aggregate(&sigs).unwrap(),
+            epoch: 0,
+            signers: SignerBitmap::from_signers(&committee, keys).unwrap(),
+        }
+    };
+
+    // Without a quorum the claims only fetch the peer's head
+    let actions = bob.on_status(6, 4, b5_hash, qc5(&[&alice_sk])).unwrap();
+    assert!(matches!(actions[..], [ConsensusAction::BroadcastRequest(h)] if h == b5_hash));
+    assert_eq!(bob.current_view, 1);
+
+    // A quorum certificate jumps the pacemaker past it and fetches the certified block
+    let actions = bob
+        .on_status(6, 4, b5_hash, qc5(&[&alice_sk, &bob_sk]))
+        .unwrap();
+    assert_eq!(actions.len(), 1);
+    match &actions[0] {
+        ConsensusAction::BroadcastRequest(h) => assert_eq!(*h, b5_hash),
+        _ => panic!("Expected BroadcastRequest"),
+    }
+    assert_eq!(bob.current_view, 6);
+
+    // Stale statuses change nothing
+    let actions = bob
+        .on_status(3, 2, genesis_hash, QuorumCertificate::default())
+        .unwrap();
+    assert!(actions.is_empty());
+    assert_eq!(bob.current_view, 6);
+}
+
+#[test]
+fn test_sync_across_committee_change() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let committee: Vec<_> = keys.iter().map(|(pk, _)| pk.clone()).collect();
+    let next_committee = committee[..3].to_vec();
+
+    // Bob is still on the genesis committee
+    let storage = std::sync::Arc::new(MemStorage::new());
+    let tx_pool = std::sync::Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = std::sync::Arc::new(std::sync::Mutex::new(
+        ockham::state::StateManager::new(storage.clone(), None),
+    ));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let mut bob = SimplexState::new(
+        keys[1].0.clone(),
+        keys[1].1.clone(),
+        committee.clone(),
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // The committee change finalized at view 2 starts epoch 1
+    let genesis = bob.storage.get_validator_set(0).unwrap().unwrap();
+    let mut next = genesis.clone();
+    next.epoch = 1;
+    next.view = 2;
+    next.committee.pop();
+    bob.storage.save_validator_set(&next).unwrap();
+
+    let qc = |block: &Block| QuorumCertificate {
+        view: block.view,
+        block_hash: hash_data(block),
+        signature: ockham::crypto::sign(
+            &keys[0].1,
+            &vote_message(
+                VoteType::Notarize,
+                block.view,
+                &hash_data(block),
+                DEFAULT_CHAIN_ID,
+            ),
+        ),
+        epoch: 0,
+        signers: SignerBitmap::from_signers(&committee, [&keys[0].0]).unwrap(),
+    };
+    // Leaders rotate over the committee certifying each view
+    let b1 = create_block(
+        1,
+        1,
+        bob.preferred_block,
+        QuorumCertificate::default(),
+        hash_data(&committee),
+    );
+    let b2 = create_block(2, 2, hash_data(&b1), qc(&b1), hash_data(&committee));
+    let b3 = create_block(0, 3, hash_data(&b2), qc(&b2), hash_data(&next_committee));
+
+    // Each block commits to the committee of its own epoch
+    bob.on_block_response(b1.clone()).unwrap();
+    bob.on_block_response(b2.clone()).unwrap();
+    let stale = create_block(0, 3, hash_data(&b2), qc(&b2), hash_data(&committee));
+    assert!(matches!(
+        bob.on_block_response(stale),
+        Err(ockham::consensus::ConsensusError::InvalidBlock)
+    ));
+    // ... and is led in the rotation of that committee (member 3 would lead under the old one)
+    let usurped = create_block(3, 3, hash_data(&b2), qc(&b2), hash_data(&next_committee));
+    assert!(matches!(
+        bob.on_block_response(usurped),
+        Err(ockham::consensus::ConsensusError::NotLeader)
+    ));
+    bob.on_block_response(b3.clone()).unwrap();
+    for block in [&b1, &b2, &b3] {
+        assert!(bob.storage.get_block(&hash_data(block)).unwrap().is_some());
+    }
+}
@@ -0,0 +1,367 @@
+use ockham::crypto::{Hash, generate_keypair, sign};
+use ockham::precompiles::{Precompile, PrecompileContext};
+use ockham::storage::{ConsensusState, MemStorage, Storage};
+use ockham::system_contracts::{StakingContract, event_topic, staking};
+use ockham::types::{
+    Block, DEFAULT_CHAIN_ID, QuorumCertificate, SignerBitmap, Transaction, U256, vote_message,
+};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_staking_contract() {
+    let storage = Arc::new(MemStorage::new());
+    storage
+        .save_consensus_state(&ConsensusState::default())
+        .unwrap();
+    let (pk, sk) = generate_keypair();
+    let sender = staking::validator_address(&pk);
+    storage
+        .save_account(
+            &sender,
+            &ockham::storage::AccountInfo {
+                nonce: 0,
+                balance: U256::from(10_000u64),
+                code_hash: Hash(ockham::types::keccak256([]).into()),
+            },
+        )
+        .unwrap();
+
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    let make_tx = |nonce: u64, value: u64, selector: [u8; 4]| {
+        let mut tx = Transaction {
+            chain_id: 1337,
+            nonce,
+            max_priority_fee_per_gas: U256::ZERO,
+            max_fee_per_gas: U256::ZERO,
+            gas_limit: 100_000,
+            to: Some(staking::STAKING_ADDRESS),
+            value: U256::from(value),
+            data: selector.to_vec().into(),
+            access_list: vec![],
+            public_key: pk.clone(),
+            signature: ockham::crypto::Signature::default(),
+        };
+        tx.signature = sign(&sk, &tx.sighash().0);
+        tx
+    };
+    let make_block = |view, payload| {
+        Block::new(
+            pk.clone(),
+            view,
+            Hash::default(),
+            QuorumCertificate::default(),
+            Hash::default(),
+            Hash::default(),
+            payload,
+            U256::ZERO,
+            0,
+            vec![],
+            Hash::default(),
+        )
+    };
+
+    // 1. Direct call: ABI, events and reverts
+    {
+        let mut db = state_manager.lock().unwrap();
+        let block = make_block(1, vec![]);
+
+        let low = make_tx(0, 1, staking::STAKE_SELECTOR);
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &block,
+            tx: &low,
+        };
+        assert!(StakingContract.call(&mut ctx, &low.data, 100_000).is_err());
+
+        let tx = make_tx(0, 2000, staking::STAKE_SELECTOR);
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &block,
+            tx: &tx,
+        };
+        assert!(StakingContract.call(&mut ctx, &tx.data, 1_000).is_err()); // Out of gas
+
+        let out = StakingContract.call(&mut ctx, &tx.data, 100_000).unwrap();
+        assert_eq!(out.gas_used, staking::STAKE_GAS);
+        assert_eq!(out.logs.len(), 1);
+        assert_eq!(out.logs[0].address, staking::STAKING_ADDRESS);
+        assert_eq!(out.logs[0].topics[0], event_topic(staking::STAKED_EVENT));
+        assert_eq!(&out.logs[0].topics[1].0[12..], sender.as_slice());
+        assert_eq!(U256::from_be_slice(&out.logs[0].data), U256::from(2000u64));
+        assert_eq!(
+            staking::stake_of(&mut db, sender).unwrap(),
+            U256::from(2000u64)
+        );
+
+        // Not an active validator
+        let tx = make_tx(0, 0, staking::UNSTAKE_SELECTOR);
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &block,
+            tx: &tx,
+        };
+        assert!(StakingContract.call(&mut ctx, &tx.data, 100_000).is_err());
+
+        // Still pending activation
+        let tx = make_tx(0, 0, staking::WITHDRAW_SELECTOR);
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &block,
+            tx: &tx,
+        };
+        assert!(StakingContract.call(&mut ctx, &tx.data, 100_000).is_err());
+    }
+
+    // 2. Through the executor: gas is metered, value is locked in the contract,
+    //    and the stake slot is part of the state root
+    let root_before = state_manager.lock().unwrap().root();
+    let mut b2 = make_block(2, vec![make_tx(0, 3000, staking::STAKE_SELECTOR)]);
+    executor.execute_block(&mut b2).unwrap();
+    assert_eq!(b2.gas_used, staking::STAKE_GAS);
+    assert_ne!(b2.state_root, root_before);
+
+    let contract = storage
+        .get_account(&staking::STAKING_ADDRESS)
+        .unwrap()
+        .unwrap();
+    assert_eq!(contract.balance, U256::from(3000u64));
+    let account = storage.get_account(&sender).unwrap().unwrap();
+    assert_eq!(account.balance, U256::from(7000u64));
+    assert_eq!(account.nonce, 1);
+    assert_eq!(
+        storage
+            .get_storage(&staking::STAKING_ADDRESS, &staking::stake_slot(sender))
+            .unwrap(),
+        U256::from(5000u64)
+    );
+
+    // A reverted call consumes its gas and bumps the nonce, but moves no value
+    let mut b3 = make_block(3, vec![make_tx(1, 5, staking::STAKE_SELECTOR)]);
+    executor.execute_block(&mut b3).unwrap();
+    assert_eq!(b3.gas_used, 100_000);
+    let receipts = storage
+        .get_receipts(&ockham::crypto::hash_data(&b3))
+        .unwrap()
+        .unwrap();
+    assert_eq!((receipts[0].status, receipts[0].gas_used), (0, 100_000));
+    assert!(ockham::vm::decode_revert_reason(&receipts[0].revert_output).is_some());
+    let account = storage.get_account(&sender).unwrap().unwrap();
+    assert_eq!(account.balance, U256::from(7000u64));
+    assert_eq!(account.nonce, 2);
+}
+
+#[test]
+fn test_withdrawal_delay() {
+    let (pk, sk) = generate_keypair();
+    let validator = staking::validator_address(&pk);
+    let storage = Arc::new(MemStorage::new());
+    let mut state = ConsensusState {
+        committee: vec![pk.clone()],
+        exiting_validators: vec![(pk.clone(), 5)],
+        ..Default::default()
+    };
+    state.params.withdrawal_delay = 20;
+    state.params.evidence_max_age = 10;
+    assert_eq!(state.params.unbonding_period(), 20);
+    storage.save_consensus_state(&state).unwrap();
+    staking::init_genesis(&*storage, &[pk.clone()], U256::from(3000u64)).unwrap();
+
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    let make_block = |view, evidence| {
+        Block::new(
+            pk.clone(),
+            view,
+            Hash::default(),
+            QuorumCertificate::default(),
+            Hash::default(),
+            Hash::default(),
+            vec![],
+            U256::ZERO,
+            0,
+            evidence,
+            Hash::default(),
+        )
+    };
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::ZERO,
+        gas_limit: 100_000,
+        to: Some(staking::STAKING_ADDRESS),
+        value: U256::ZERO,
+        data: staking::WITHDRAW_SELECTOR.to_vec().into(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    let withdraw = |view| {
+        let block = make_block(view, vec![]);
+        let mut db = state_manager.lock().unwrap();
+        let mut ctx = PrecompileContext {
+            db: &mut db,
+            block: &block,
+            tx: &tx,
+        };
+        StakingContract.call(&mut ctx, &tx.data, 100_000)
+    };
+
+    // 1. Leaving the committee at view 5 starts the unbonding period
+    executor.execute_block(&mut make_block(5, vec![])).unwrap();
+    let state = storage.get_consensus_state().unwrap().unwrap();
+    assert!(!state.committee.contains(&pk));
+    assert_eq!(
+        staking::exited_at(&mut state_manager.lock().unwrap(), validator).unwrap(),
+        Some(5)
+    );
+    assert!(withdraw(10).is_err());
+
+    // 2. An offence from the last views in the committee is still slashed
+    let make_vote = |hash: Hash| ockham::types::Vote {
+        view: 4,
+        block_hash: hash,
+        vote_type: ockham::types::VoteType::Notarize,
+        author: pk.clone(),
+        signature: sign(
+            &sk,
+            &vote_message(
+                ockham::types::VoteType::Notarize,
+                4,
+                &hash,
+                DEFAULT_CHAIN_ID,
+            ),
+        ),
+    };
+    let evidence = ockham::types::EquivocationEvidence {
+        vote_a: make_vote(Hash([1u8; 32])),
+        vote_b: make_vote(Hash([2u8; 32])),
+    };
+    executor
+        .execute_block(&mut make_block(12, vec![evidence]))
+        .unwrap();
+    assert_eq!(
+        staking::stake_of(&mut state_manager.lock().unwrap(), validator).unwrap(),
+        U256::from(2000u64)
+    );
+    assert!(withdraw(24).is_err());
+
+    // 3. The rest is released once the period has passed
+    let out = withdraw(25).unwrap();
+    assert_eq!(U256::from_be_slice(&out.logs[0].data), U256::from(2000u64));
+    let mut db = state_manager.lock().unwrap();
+    assert_eq!(staking::stake_of(&mut db, validator).unwrap(), U256::ZERO);
+    assert_eq!(staking::exited_at(&mut db, validator).unwrap(), None);
+    assert_eq!(
+        storage.get_account(&validator).unwrap().unwrap().balance,
+        U256::from(2000u64)
+    );
+}
+
+#[test]
+fn test_claim_rewards() {
+    let (pk, sk) = generate_keypair();
+    let (attester, _) = generate_keypair();
+    let validator = staking::validator_address(&pk);
+    let storage = Arc::new(MemStorage::new());
+    storage
+        .save_consensus_state(&ConsensusState {
+            committee: vec![pk.clone(), attester.clone()],
+            ..Default::default()
+        })
+        .unwrap();
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(
+        state_manager.clone(),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+
+    // 1. The proposer and the signers of the justify QC accrue rewards
+    let justify = QuorumCertificate {
+        view: 1,
+        block_hash: Hash([1u8; 32]),
+        signers: SignerBitmap::from_signers(&[pk.clone(), attester.clone()], [&pk, &attester])
+            .unwrap(),
+        ..Default::default()
+    };
+    let mut block = Block::new(
+        pk.clone(),
+        2,
+        Hash([1u8; 32]),
+        justify,
+        Hash::default(),
+        Hash::default(),
+        vec![],
+        U256::ZERO,
+        0,
+        vec![],
+        Hash::default(),
+    );
+    executor.execute_block(&mut block).unwrap();
+    let proposer_reward = U256::from(staking::PROPOSER_REWARD);
+    let attester_reward = U256::from(staking::ATTESTER_REWARD);
+    let state = storage.get_consensus_state().unwrap().unwrap();
+    assert_eq!(state.rewards[&pk], proposer_reward + attester_reward);
+    assert_eq!(state.rewards[&attester], attester_reward);
+
+    // 2. Claiming pays out the caller's rewards once
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::ZERO,
+        max_fee_per_gas: U256::ZERO,
+        gas_limit: 100_000,
+        to: Some(staking::STAKING_ADDRESS),
+        value: U256::ZERO,
+        data: staking::CLAIM_REWARDS_SELECTOR.to_vec().into(),
+        access_list: vec![],
+        public_key: pk.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sk, &tx.sighash().0);
+    let mut db = state_manager.lock().unwrap();
+    let mut ctx = PrecompileContext {
+        db: &mut db,
+        block: &block,
+        tx: &tx,
+    };
+    let out = StakingContract.call(&mut ctx, &tx.data, 100_000).unwrap();
+    assert_eq!(out.gas_used, staking::CLAIM_REWARDS_GAS);
+    assert_eq!(
+        out.logs[0].topics[0],
+        event_topic(staking::REWARDS_CLAIMED_EVENT)
+    );
+    assert_eq!(&out.logs[0].topics[1].0[12..], validator.as_slice());
+    assert_eq!(
+        U256::from_be_slice(&out.logs[0].data),
+        proposer_reward + attester_reward
+    );
+    assert!(StakingContract.call(&mut ctx, &tx.data, 100_000).is_err());
+    drop(ctx);
+
+    assert_eq!(
+        storage.get_account(&validator).unwrap().unwrap().balance,
+        proposer_reward + attester_reward
+    );
+    let state = storage.get_consensus_state().unwrap().unwrap();
+    assert!(!state.rewards.contains_key(&pk));
+    assert_eq!(state.rewards[&attester], attester_reward);
+}
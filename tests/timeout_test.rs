@@ -1,6 +1,6 @@
 use ockham::consensus::{ConsensusAction, SimplexState};
 use ockham::crypto::{PrivateKey, PublicKey, hash_data, sign};
-use ockham::types::{Block, QuorumCertificate, Vote};
+use ockham::types::{Block, DEFAULT_CHAIN_ID, QuorumCertificate, Vote, VoteType, vote_message};
 
 #[test]
 fn test_timeout_chain_extension() {
@@ -34,7 +34,7 @@ fn test_timeout_chain_extension() {
     // --- VIEW 1 (Normal) ---
     // Create Block 1
     let qc0 = QuorumCertificate::default();
-    let b1 = Block::new(
+    let mut b1 = Block::new(
         keys[0].0.clone(),
         1,
         genesis_block_hash,
@@ -47,6 +47,8 @@ fn test_timeout_chain_extension() {
         vec![],
         hash_data(&committee),
     );
+    b1.height = 1;
+    b1.sign(&keys[0].1);
     let b1_hash = hash_data(&b1);
 
     // Node 0 processes B1
@@ -56,9 +58,12 @@ fn test_timeout_chain_extension() {
     let v1 = Vote {
         view: 1,
         block_hash: b1_hash,
-        vote_type: ockham::types::VoteType::Notarize,
+        vote_type: VoteType::Notarize,
         author: keys[0].0.clone(),
-        signature: sign(&keys[0].1, &b1_hash.0),
+        signature: sign(
+            &keys[0].1,
+            &vote_message(VoteType::Notarize, 1, &b1_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     node0.on_vote(v1).unwrap();
 
@@ -74,9 +79,12 @@ fn test_timeout_chain_extension() {
     let v2 = Vote {
         view: 2,
         block_hash: dummy_hash,
-        vote_type: ockham::types::VoteType::Notarize,
+        vote_type: VoteType::Notarize,
         author: keys[0].0.clone(),
-        signature: sign(&keys[0].1, &dummy_hash.0),
+        signature: sign(
+            &keys[0].1,
+            &vote_message(VoteType::Notarize, 2, &dummy_hash, DEFAULT_CHAIN_ID),
+        ),
     };
     node0.on_vote(v2).unwrap();
 
@@ -0,0 +1,313 @@
+use ockham::consensus::{ConsensusAction, ConsensusError, SimplexState};
+use ockham::crypto::{PrivateKey, PublicKey, generate_keypair_from_id, hash_data};
+use ockham::storage::{MemStorage, Storage};
+use ockham::types::{Block, VoteType};
+use ockham::validation::ValidationPool;
+use std::sync::{Arc, Mutex};
+
+fn make_node(keys: &[(PublicKey, PrivateKey)], me: usize) -> (SimplexState, Arc<MemStorage>) {
+    let committee: Vec<PublicKey> = keys.iter().map(|k| k.0.clone()).collect();
+    let storage = Arc::new(MemStorage::new());
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    let node = SimplexState::new(
+        keys[me].0.clone(),
+        keys[me].1.clone(),
+        committee,
+        storage.clone(),
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    (node, storage)
+}
+
+/// The view 1 proposal of the leader (committee member 1).
+fn proposal(keys: &[(PublicKey, PrivateKey)]) -> Block {
+    let (mut leader, _) = make_node(keys, 1);
+    leader
+        .try_propose()
+        .unwrap()
+        .into_iter()
+        .find_map(|a| match a {
+            ConsensusAction::BroadcastBlock(b) => Some(b),
+            _ => None,
+        })
+        .expect("Leader should propose")
+}
+
+#[test]
+fn test_async_validation_votes_on_completion() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+    let block_hash = hash_data(&block);
+    let (node, storage) = make_node(&keys, 0);
+    let mut node = node.with_async_validation();
+
+    // Only the cheap checks run on the event loop: no vote, nothing stored yet
+    assert!(node.on_proposal(block.clone()).unwrap().is_empty());
+    assert!(storage.get_block(&block_hash).unwrap().is_none());
+    let mut jobs = node.take_validation_jobs();
+    assert_eq!(jobs.len(), 1);
+    assert!(node.take_validation_jobs().is_empty());
+
+    // The vote is cast once the worker's result comes back
+    let job = jobs.pop().unwrap();
+    let validated = std::thread::spawn(move || job.run()).join().unwrap();
+    let actions = node.on_block_validated(validated).unwrap();
+    assert!(actions.iter().any(|a| matches!(
+        a,
+        ConsensusAction::BroadcastVote(v)
+            if v.block_hash == block_hash && v.vote_type == VoteType::Notarize
+    )));
+    assert!(storage.get_block(&block_hash).unwrap().is_some());
+    assert_eq!(node.last_voted_view, block.view);
+
+    // A block with a wrong state root is rejected when its result arrives
+    let mut forged = block;
+    forged.state_root = hash_data(&forged.state_root);
+    forged.sign(&keys[1].1);
+    let (node, storage) = make_node(&keys, 0);
+    let mut node = node.with_async_validation();
+    node.on_proposal(forged.clone()).unwrap();
+    let validated = node.take_validation_jobs().pop().unwrap().run();
+    assert!(matches!(
+        node.on_block_validated(validated),
+        Err(ConsensusError::InvalidStateRoot)
+    ));
+    assert!(storage.get_block(&hash_data(&forged)).unwrap().is_none());
+}
+
+#[test]
+fn test_proposal_from_non_leader_rejected() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+
+    // Another member signing the view 1 block as its own, live or through sync
+    let mut usurped = block.clone();
+    usurped.author = keys[2].0.clone();
+    usurped.sign(&keys[2].1);
+    let (mut node, storage) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(usurped.clone()),
+        Err(ConsensusError::NotLeader)
+    ));
+    assert!(matches!(
+        node.on_block_response(usurped.clone()),
+        Err(ConsensusError::NotLeader)
+    ));
+    assert!(storage.get_block(&hash_data(&usurped)).unwrap().is_none());
+    assert_eq!(node.last_voted_view, 0);
+
+    // The leader's own block is still voted for
+    assert!(!node.on_proposal(block).unwrap().is_empty());
+}
+
+#[test]
+fn test_forged_dummy_block_rejected() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+
+    // A non-leader's block with a payload, claiming to be a dummy to skip the checks
+    let mut forged = block.clone();
+    forged.author = keys[2].0.clone();
+    forged.is_dummy = true;
+    forged.height = 7;
+    forged.sign(&keys[2].1);
+    let (mut node, storage) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(forged.clone()),
+        Err(ConsensusError::InvalidBlock)
+    ));
+    assert!(matches!(
+        node.on_block_response(forged.clone()),
+        Err(ConsensusError::InvalidBlock)
+    ));
+    assert!(storage.get_block(&hash_data(&forged)).unwrap().is_none());
+    assert_eq!(node.last_voted_view, 0);
+    assert!(node.seen_proposals.is_empty());
+}
+
+#[tokio::test]
+async fn test_validation_pool() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+    let (node, _) = make_node(&keys, 0);
+    let mut node = node.with_async_validation();
+    node.on_block_response(block.clone()).unwrap();
+
+    let (sender, mut results) = tokio::sync::mpsc::unbounded_channel();
+    let pool = ValidationPool::new(2, sender);
+    for job in node.take_validation_jobs() {
+        pool.submit(job);
+    }
+    let validated = results.recv().await.unwrap();
+    assert!(validated.sync);
+    assert_eq!(hash_data(&validated.block), hash_data(&block));
+    assert!(validated.result.is_ok());
+    node.on_block_validated(validated).unwrap();
+    assert!(
+        node.storage
+            .get_block(&hash_data(&block))
+            .unwrap()
+            .is_some()
+    );
+}
+
+#[test]
+fn test_proposal_timestamp_bounds() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+    assert!(block.timestamp >= ockham::consensus::unix_time() - 60);
+
+    // Too far ahead of our clock
+    let mut future = block.clone();
+    future.timestamp += ockham::consensus::MAX_TIMESTAMP_DRIFT + 60;
+    future.sign(&keys[1].1);
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(future),
+        Err(ConsensusError::InvalidBlock)
+    ));
+
+    // Within the drift it is accepted and voted for
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(!node.on_proposal(block).unwrap().is_empty());
+}
+
+#[test]
+fn test_proposal_height_follows_parent() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+    assert_eq!(block.height, 1);
+
+    // A height that skips ahead of the parent is rejected
+    let mut skipped = block.clone();
+    skipped.height = block.view + 1;
+    skipped.sign(&keys[1].1);
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(skipped),
+        Err(ConsensusError::InvalidBlock)
+    ));
+}
+
+#[test]
+fn test_proposal_gas_limit_bounds() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let limit = ockham::types::DEFAULT_BLOCK_GAS_LIMIT;
+    let step = limit / ockham::fees::GAS_LIMIT_BOUND_DIVISOR;
+    assert_eq!(proposal(&keys).gas_limit, limit);
+
+    // A leader voting the limit down moves it by one step, which is accepted
+    let (leader, _) = make_node(&keys, 1);
+    let mut leader = leader.with_proposer_config(ockham::consensus::ProposerConfig {
+        gas_limit_target: Some(limit / 2),
+        ..Default::default()
+    });
+    let block = leader
+        .try_propose()
+        .unwrap()
+        .into_iter()
+        .find_map(|a| match a {
+            ConsensusAction::BroadcastBlock(b) => Some(b),
+            _ => None,
+        })
+        .expect("Leader should propose");
+    assert_eq!(block.gas_limit, limit - step);
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(!node.on_proposal(block.clone()).unwrap().is_empty());
+
+    // A header moving it further is rejected
+    let mut other = block;
+    other.gas_limit = limit - step - 1;
+    other.sign(&keys[1].1);
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(other),
+        Err(ConsensusError::InvalidBlock)
+    ));
+}
+
+#[test]
+fn test_proposal_transactions_root_matches_payload() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+    assert_eq!(
+        block.transactions_root,
+        ockham::types::calculate_transactions_root(&block.payload)
+    );
+
+    // A header committing to another payload is rejected, even when correctly signed
+    let mut tampered = block.clone();
+    tampered.transactions_root = ockham::crypto::Hash([7u8; 32]);
+    tampered.sign(&keys[1].1);
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(tampered),
+        Err(ConsensusError::InvalidBlock)
+    ));
+}
+
+#[test]
+fn test_block_gas_limit_enforced() {
+    let keys: Vec<_> = (0..4).map(generate_keypair_from_id).collect();
+    let block = proposal(&keys);
+    let limit = ockham::types::DEFAULT_BLOCK_GAS_LIMIT;
+    let (pk, sk) = generate_keypair_from_id(9);
+    let stuffing: Vec<_> = (0..2)
+        .map(|nonce| {
+            let mut tx = ockham::types::Transaction {
+                chain_id: 1337,
+                nonce,
+                max_priority_fee_per_gas: ockham::types::U256::ZERO,
+                max_fee_per_gas: ockham::types::U256::from(100_000_000u64),
+                gas_limit: limit / 2 + 1,
+                to: Some(ockham::types::Address::ZERO),
+                value: ockham::types::U256::ZERO,
+                data: ockham::types::Bytes::new(),
+                access_list: vec![],
+                public_key: pk.clone(),
+                signature: ockham::crypto::Signature::default(),
+            };
+            tx.signature = ockham::crypto::sign(&sk, &tx.sighash().0);
+            tx
+        })
+        .collect();
+
+    // Each transaction fits on its own, together they overflow the block
+    let mut stuffed = block.clone();
+    stuffed.payload = stuffing;
+    stuffed.transactions_root = ockham::types::calculate_transactions_root(&stuffed.payload);
+    stuffed.sign(&keys[1].1);
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(stuffed.clone()),
+        Err(ConsensusError::GasLimitExceeded { gas, limit: l }) if gas == limit + 2 && l == limit
+    ));
+
+    // Execution enforces it too (e.g. for blocks re-executed on recovery)
+    let state = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        Arc::new(MemStorage::new()),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state, limit);
+    assert!(matches!(
+        executor.execute_block(&mut stuffed),
+        Err(ockham::vm::ExecutionError::GasLimitExceeded { .. })
+    ));
+
+    // A header claiming more gas than the limit is rejected as well
+    let mut overclaimed = block;
+    overclaimed.gas_used = limit + 1;
+    overclaimed.sign(&keys[1].1);
+    let (mut node, _) = make_node(&keys, 0);
+    assert!(matches!(
+        node.on_proposal(overclaimed),
+        Err(ConsensusError::GasLimitExceeded { .. })
+    ));
+}
@@ -0,0 +1,351 @@
+use ockham::crypto::{Hash, generate_keypair, sign};
+use ockham::storage::{MemStorage, Storage};
+use ockham::types::{Address, Block, QuorumCertificate, Transaction, U256};
+use std::sync::{Arc, Mutex};
+
+// Counter contract: `deploy` stores 42 in slot 0, `call` increments it and emits a log.
+const COUNTER_WAT: &str = r#"
+(module
+  (import "env" "storage_read" (func $sread (param i32 i32)))
+  (import "env" "storage_write" (func $swrite (param i32 i32)))
+  (import "env" "emit_log" (func $log (param i32 i32 i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "deploy")
+    (i32.store8 (i32.const 63) (i32.const 42))
+    (call $swrite (i32.const 0) (i32.const 32)))
+  (func (export "call")
+    (call $sread (i32.const 0) (i32.const 32))
+    (i32.store8 (i32.const 63) (i32.add (i32.load8_u (i32.const 63)) (i32.const 1)))
+    (call $swrite (i32.const 0) (i32.const 32))
+    (call $log (i32.const 0) (i32.const 0) (i32.const 32) (i32.const 32)))
+)
+"#;
+
+#[test]
+fn test_wasm_deploy_and_call() {
+    let storage = Arc::new(MemStorage::new());
+    let (pk, sk) = generate_keypair();
+
+    let sender = Address::from_slice(&ockham::types::keccak256(pk.0.to_bytes())[12..]);
+    let account = ockham::storage::AccountInfo {
+        nonce: 0,
+        balance: U256::from(1_000_000u64),
+        code_hash: Hash(ockham::types::keccak256([]).into()),
+    };
+    storage.save_account(&sender, &account).unwrap();
+
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+
+    let make_tx = |nonce: u64, to: Option<Address>, data: Vec<u8>| {
+        let mut tx = Transaction {
+            chain_id: 1337,
+            nonce,
+            max_priority_fee_per_gas: U256::ZERO,
+            max_fee_per_gas: U256::ZERO,
+            gas_limit: 1_000_000,
+            to,
+            value: U256::ZERO,
+            data: data.into(),
+            access_list: vec![],
+            public_key: pk.clone(),
+            signature: ockham::crypto::Signature::default(),
+        };
+        tx.signature = sign(&sk, &tx.sighash().0);
+        tx
+    };
+
+    let code = wat::parse_str(COUNTER_WAT).unwrap();
+    assert!(ockham::vm::wasm::is_wasm(&code));
+    let contract = sender.create(0);
+
+    // 1. Deploy
+    let mut b1 = Block::new(
+        pk.clone(),
+        1,
+        Hash::default(),
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        vec![make_tx(0, None, code.clone())],
+        U256::ZERO,
+        0,
+        vec![],
+        Hash::default(),
+    );
+    executor.execute_block(&mut b1).unwrap();
+    assert!(b1.gas_used > 0);
+    assert_eq!(
+        storage.get_storage(&contract, &U256::ZERO).unwrap(),
+        U256::from(42)
+    );
+    // Accounts keep only the hash; the code is stored under it
+    let deployed = storage.get_account(&contract).unwrap().unwrap();
+    let stored = storage.get_code(&deployed.code_hash).unwrap().unwrap();
+    assert_eq!(stored.to_vec(), code);
+
+    // 2. Call (mixed with a plain EVM transfer in the same block)
+    let mut b2 = Block::new(
+        pk.clone(),
+        2,
+        Hash::default(),
+        QuorumCertificate::default(),
+        Hash::default(),
+        Hash::default(),
+        vec![
+            make_tx(1, Some(contract), vec![]),
+            make_tx(2, Some(Address::ZERO), vec![]),
+        ],
+        U256::ZERO,
+        0,
+        vec![],
+        Hash::default(),
+    );
+    executor.execute_block(&mut b2).unwrap();
+    assert_eq!(
+        storage.get_storage(&contract, &U256::ZERO).unwrap(),
+        U256::from(43)
+    );
+    assert_eq!(storage.get_account(&sender).unwrap().unwrap().nonce, 3);
+}
+
+// Writes slot 0 on deploy, then traps in `call` after writing it again.
+const TRAPPING_WAT: &str = r#"
+(module
+  (import "env" "storage_write" (func $swrite (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "deploy")
+    (i32.store8 (i32.const 63) (i32.const 7))
+    (call $swrite (i32.const 0) (i32.const 32)))
+  (func (export "call")
+    (i32.store8 (i32.const 63) (i32.const 8))
+    (call $swrite (i32.const 0) (i32.const 32))
+    unreachable)
+)
+"#;
+
+const UNKNOWN_IMPORT_WAT: &str = r#"
+(module
+  (import "env" "self_destruct" (func))
+  (func (export "deploy"))
+)
+"#;
+
+const GAS_LIMIT: u64 = 1_000_000;
+
+struct Setup {
+    storage: Arc<MemStorage>,
+    executor: ockham::vm::Executor,
+    pk: ockham::crypto::PublicKey,
+    sk: ockham::crypto::PrivateKey,
+    sender: Address,
+}
+
+fn setup() -> Setup {
+    let storage = Arc::new(MemStorage::new());
+    let (pk, sk) = generate_keypair();
+    let sender = Address::from_slice(&ockham::types::keccak256(pk.0.to_bytes())[12..]);
+    let account = ockham::storage::AccountInfo {
+        nonce: 0,
+        balance: U256::from(1_000_000u64),
+        code_hash: Hash(ockham::types::keccak256([]).into()),
+    };
+    storage.save_account(&sender, &account).unwrap();
+    let state_manager = Arc::new(Mutex::new(ockham::state::StateManager::new(
+        storage.clone(),
+        None,
+    )));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    Setup {
+        storage,
+        executor,
+        pk,
+        sk,
+        sender,
+    }
+}
+
+impl Setup {
+    fn tx(&self, nonce: u64, to: Option<Address>, data: Vec<u8>) -> Transaction {
+        let mut tx = Transaction {
+            chain_id: 1337,
+            nonce,
+            max_priority_fee_per_gas: U256::ZERO,
+            max_fee_per_gas: U256::ZERO,
+            gas_limit: GAS_LIMIT,
+            to,
+            value: U256::ZERO,
+            data: data.into(),
+            access_list: vec![],
+            public_key: self.pk.clone(),
+            signature: ockham::crypto::Signature::default(),
+        };
+        tx.signature = sign(&self.sk, &tx.sighash().0);
+        tx
+    }
+
+    /// Execute a block of `txs` and return its receipts.
+    fn execute(&self, view: u64, txs: Vec<Transaction>) -> Vec<ockham::types::Receipt> {
+        let mut block = Block::new(
+            self.pk.clone(),
+            view,
+            Hash::default(),
+            QuorumCertificate::default(),
+            Hash::default(),
+            Hash::default(),
+            txs,
+            U256::ZERO,
+            0,
+            vec![],
+            Hash::default(),
+        );
+        self.executor.execute_block(&mut block).unwrap();
+        self.storage
+            .get_receipts(&ockham::crypto::hash_data(&block))
+            .unwrap()
+            .unwrap()
+    }
+}
+
+/// A deployment whose module cannot run fails (charging its gas) without failing the
+/// block: the transactions after it still execute.
+fn assert_rejected_deployment(code: Vec<u8>) {
+    assert!(ockham::vm::wasm::is_wasm(&code));
+    let s = setup();
+    let receipts = s.execute(
+        1,
+        vec![s.tx(0, None, code), s.tx(1, Some(Address::ZERO), vec![])],
+    );
+    assert_eq!(receipts[0].status, 0);
+    assert_eq!(receipts[0].gas_used, GAS_LIMIT);
+    assert_eq!(receipts[0].contract_address, None);
+    assert_eq!(receipts[1].status, 1);
+    assert!(
+        s.storage
+            .get_account(&s.sender.create(0))
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(s.storage.get_account(&s.sender).unwrap().unwrap().nonce, 2);
+}
+
+#[test]
+fn test_wasm_malformed_module() {
+    let mut code = ockham::vm::wasm::WASM_MAGIC.to_vec();
+    code.extend_from_slice(&[0x01, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff]);
+    assert_rejected_deployment(code);
+}
+
+#[test]
+fn test_wasm_unknown_import() {
+    assert_rejected_deployment(wat::parse_str(UNKNOWN_IMPORT_WAT).unwrap());
+}
+
+#[test]
+fn test_wasm_trapping_call() {
+    let s = setup();
+    let contract = s.sender.create(0);
+    let code = wat::parse_str(TRAPPING_WAT).unwrap();
+    let receipts = s.execute(1, vec![s.tx(0, None, code)]);
+    assert_eq!(receipts[0].status, 1);
+    assert_eq!(
+        s.storage.get_storage(&contract, &U256::ZERO).unwrap(),
+        U256::from(7)
+    );
+
+    // The trap discards the call's write, but its gas is paid and the nonce bumped
+    let receipts = s.execute(2, vec![s.tx(1, Some(contract), vec![])]);
+    assert_eq!(receipts[0].status, 0);
+    assert!(receipts[0].gas_used > 0);
+    assert_eq!(
+        s.storage.get_storage(&contract, &U256::ZERO).unwrap(),
+        U256::from(7)
+    );
+    assert_eq!(s.storage.get_account(&s.sender).unwrap().unwrap().nonce, 2);
+}
+
+#[test]
+fn test_wasm_revert_charges_before_reading() {
+    // A 2 GiB revert payload runs out of gas instead of being read
+    let huge_revert = r#"
+(module
+  (import "env" "revert" (func $revert (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "deploy")
+    (call $revert (i32.const 0) (i32.const 0x7fffffff)))
+)
+"#;
+    assert_rejected_deployment(wat::parse_str(huge_revert).unwrap());
+
+    // Affordable reads past the end of memory trap
+    let out_of_bounds = r#"
+(module
+  (import "env" "return_data" (func $ret (param i32 i32)))
+  (memory (export "memory") 1)
+  (func (export "deploy")
+    (call $ret (i32.const 65500) (i32.const 100)))
+)
+"#;
+    let s = setup();
+    let code = wat::parse_str(out_of_bounds).unwrap();
+    let receipts = s.execute(1, vec![s.tx(0, None, code)]);
+    assert_eq!(receipts[0].status, 0);
+    assert!(receipts[0].gas_used < GAS_LIMIT);
+    assert!(
+        s.storage
+            .get_account(&s.sender.create(0))
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_wasm_bad_nonce_fails_in_receipt() {
+    let s = setup();
+    let code = wat::parse_str(COUNTER_WAT).unwrap();
+    let receipts = s.execute(
+        1,
+        vec![s.tx(5, None, code), s.tx(0, Some(Address::ZERO), vec![])],
+    );
+    assert_eq!(receipts[0].status, 0);
+    assert_eq!(receipts[0].gas_used, 0);
+    assert_eq!(receipts[1].status, 1);
+    assert!(
+        s.storage
+            .get_account(&s.sender.create(5))
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(s.storage.get_account(&s.sender).unwrap().unwrap().nonce, 1);
+}
+
+#[test]
+fn test_wasm_gas_includes_intrinsic_and_host_calls() {
+    let s = setup();
+    let code = wat::parse_str(COUNTER_WAT).unwrap();
+    let deploy = s.tx(0, None, code);
+    let intrinsic = deploy.intrinsic_gas();
+    let receipts = s.execute(1, vec![deploy]);
+    assert_eq!(receipts[0].status, 1);
+    assert!(receipts[0].gas_used > intrinsic);
+
+    // Host calls and instructions share the limit left after the intrinsic gas
+    let mut call = s.tx(1, Some(s.sender.create(0)), vec![]);
+    call.gas_limit = 21_000 + ockham::vm::wasm::GAS_STORAGE_READ;
+    call.signature = sign(&s.sk, &call.sighash().0);
+    let receipts = s.execute(2, vec![call]);
+    assert_eq!(receipts[0].status, 0);
+    assert_eq!(
+        receipts[0].gas_used,
+        21_000 + ockham::vm::wasm::GAS_STORAGE_READ
+    );
+    assert_eq!(
+        s.storage
+            .get_storage(&s.sender.create(0), &U256::ZERO)
+            .unwrap(),
+        U256::from(42)
+    );
+}
@@ -0,0 +1,148 @@
+use ockham::consensus::SimplexState;
+use ockham::crypto::{Hash, PrivateKey, PublicKey, generate_keypair_from_id, hash_data, sign};
+use ockham::state::StateManager;
+use ockham::storage::{AccountInfo, MemStorage, StateOverlay, Storage};
+use ockham::types::{Address, Block, QuorumCertificate, Transaction, U256};
+use std::sync::{Arc, Mutex};
+
+fn make_node(key: &(PublicKey, PrivateKey), storage: Arc<MemStorage>) -> SimplexState {
+    let tx_pool = Arc::new(ockham::tx_pool::TxPool::new(storage.clone()));
+    let state_manager = Arc::new(Mutex::new(StateManager::new(storage.clone(), None)));
+    let executor = ockham::vm::Executor::new(state_manager, ockham::types::DEFAULT_BLOCK_GAS_LIMIT);
+    SimplexState::new(
+        key.0.clone(),
+        key.1.clone(),
+        vec![key.0.clone()],
+        storage,
+        tx_pool,
+        executor,
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    )
+}
+
+fn address_of(pk: &PublicKey) -> Address {
+    Address::from_slice(&ockham::types::keccak256(pk.0.to_bytes())[12..])
+}
+
+/// A parent block at `root` and a child transferring from `sender`, executed on top of it.
+fn make_blocks(
+    key: &(PublicKey, PrivateKey),
+    sender: &(PublicKey, PrivateKey),
+    storage: Arc<MemStorage>,
+    root: Hash,
+) -> (Block, Block) {
+    let committee_hash = hash_data(&vec![key.0.clone()]);
+    let block = |view, parent, state_root, payload| {
+        Block::new(
+            key.0.clone(),
+            view,
+            parent,
+            QuorumCertificate::default(),
+            state_root,
+            Hash::default(),
+            payload,
+            U256::from(10_000_000u64),
+            0,
+            vec![],
+            committee_hash,
+        )
+    };
+    let parent = block(1, Hash::default(), root, vec![]);
+
+    let mut tx = Transaction {
+        chain_id: 1337,
+        nonce: 0,
+        max_priority_fee_per_gas: U256::from(1u64),
+        max_fee_per_gas: U256::from(100_000_000u64),
+        gas_limit: 21000,
+        to: Some(Address::from([0x42; 20])),
+        value: U256::from(1_000u64),
+        data: vec![].into(),
+        access_list: vec![],
+        public_key: sender.0.clone(),
+        signature: ockham::crypto::Signature::default(),
+    };
+    tx.signature = sign(&sender.1, &tx.sighash().0);
+    let mut child = block(2, hash_data(&parent), Hash::default(), vec![tx]);
+    child.height = parent.height + 1;
+
+    // Fill in the roots the way a proposer would
+    let overlay = Arc::new(StateOverlay::new(storage));
+    let executor = ockham::vm::Executor::new(
+        Arc::new(Mutex::new(StateManager::new(overlay, Some(root)))),
+        ockham::types::DEFAULT_BLOCK_GAS_LIMIT,
+    );
+    executor.execute_block(&mut child).unwrap();
+    child.sign(&key.1);
+    (parent, child)
+}
+
+#[test]
+fn test_stateless_validation_with_witness() {
+    let key = generate_keypair_from_id(0);
+    let sender = generate_keypair_from_id(7);
+
+    // Full node: the sender's account is committed to the state tree
+    let full_storage = Arc::new(MemStorage::new());
+    let mut full = make_node(&key, full_storage.clone());
+    let state = StateManager::new(full_storage.clone(), None);
+    let funded = AccountInfo {
+        balance: U256::from(10u64).pow(U256::from(18)),
+        code_hash: Hash(ockham::types::keccak256([]).into()),
+        ..Default::default()
+    };
+    state
+        .commit_account(address_of(&sender.0), funded.clone())
+        .unwrap();
+    let (parent, child) = make_blocks(&key, &sender, full_storage.clone(), state.root());
+    let child_hash = hash_data(&child);
+    full_storage.save_block(&parent).unwrap();
+
+    // Full validation records the witness
+    full.on_block_response(child.clone()).unwrap();
+    assert!(full_storage.get_block(&child_hash).unwrap().is_some());
+    let witness = full_storage.get_witness(&child_hash).unwrap().unwrap();
+    assert!(
+        witness
+            .accounts
+            .iter()
+            .any(|(a, info)| *a == address_of(&sender.0) && info.as_ref() == Some(&funded))
+    );
+    assert!(witness.verify(&parent.state_root));
+
+    // A node without the sender's state validates from the witness alone
+    let stateless_node = |witness| {
+        let storage = Arc::new(MemStorage::new());
+        let node = make_node(&key, storage.clone()).with_stateless_validation();
+        storage.save_block(&parent).unwrap();
+        if let Some(witness) = witness {
+            node.on_witness(child_hash, witness);
+        }
+        (node, storage)
+    };
+    let (mut node, storage) = stateless_node(Some(witness.clone()));
+    assert!(
+        storage
+            .get_account(&address_of(&sender.0))
+            .unwrap()
+            .is_none()
+    );
+    node.on_block_response(child.clone()).unwrap();
+    assert!(storage.get_block(&child_hash).unwrap().is_some());
+
+    // Without it, the block cannot be executed
+    let (mut node, storage) = stateless_node(None);
+    assert!(node.on_block_response(child.clone()).is_err());
+    assert!(storage.get_block(&child_hash).unwrap().is_none());
+
+    // A forged witness is rejected
+    let mut forged = witness;
+    for (address, info) in forged.accounts.iter_mut() {
+        if *address == address_of(&sender.0) {
+            info.as_mut().unwrap().balance = U256::from(1u64);
+        }
+    }
+    assert!(!forged.verify(&parent.state_root));
+    let (mut node, _) = stateless_node(Some(forged));
+    assert!(node.on_block_response(child).is_err());
+}